@@ -0,0 +1,34 @@
+//! Benchmarks of the bitset utilities.
+//!
+//! The models (e.g. tsptw and psp) iterate over the members of their bitsets
+//! in every transition and the rankings compare them lexicographically.
+//! These benchmarks guard against regressions of `BitSetIter` and `LexBitSet`
+//! on sets of various sizes and densities.
+
+mod support;
+
+use std::hint::black_box;
+
+use engineering::{BitSetIter, LexBitSet};
+use support::{bitset, Harness};
+
+fn main() {
+    let harness = Harness::from_args();
+
+    for size in [64, 1024, 65536] {
+        for density in [1, 10, 50, 90] {
+            let set = bitset(size, density, 3);
+            harness.bench(&format!("bitset_iter/{}/density{}%", size, density), || {
+                BitSetIter::new(black_box(&set)).sum::<usize>()
+            });
+        }
+
+        // the worst case: the sets only differ on their very last bit
+        let a = bitset(size, 50, 3);
+        let mut b = a.clone();
+        b.set(size - 1, !a[size - 1]);
+        harness.bench(&format!("lex_bitset/cmp/{}", size), || {
+            LexBitSet(black_box(&a)).cmp(&LexBitSet(black_box(&b)))
+        });
+    }
+}
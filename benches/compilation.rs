@@ -0,0 +1,65 @@
+//! Benchmarks of the compilation of the `Barrier` dds.
+//!
+//! These benchmarks guard against regressions of the layer construction
+//! (`branch_on`, and the lookup of the next layer which detects duplicate
+//! states) on models with a controlled duplicate rate; and of the bottom-up
+//! pass computing the local bounds and thresholds, which is only run by the
+//! relaxed compilations. Comparing the restricted and relaxed timings of the
+//! same shape gives an idea of the cost of that pass. The last benchmarks
+//! show what the arenas allocated ahead of time save on a first compilation.
+
+mod support;
+
+use engineering::{Barrier, CompilationType, CutsetType, DecisionDiagram};
+use support::{Harness, Layered};
+
+fn main() {
+    let harness = Harness::from_args();
+
+    // (name, nb variables, domain size, distinct states per layer, width)
+    let shapes = [
+        ("narrow_deep", 200, 4, 64, 16),
+        ("wide_shallow", 10, 8, 4096, 1000),
+        ("dup_low", 30, 4, 100_000, 250),
+        ("dup_high", 30, 4, 64, 250),
+    ];
+
+    for (name, nb_vars, domain, distinct, width) in shapes {
+        let model = Layered::new(nb_vars, domain, distinct);
+        for comp_type in [CompilationType::Restricted, CompilationType::Relaxed] {
+            for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+                let barriers = model.barriers();
+                let input = model.input(comp_type, width);
+                let mut dd = Barrier::new(barriers.clone(), cutset_type);
+                harness.bench(&format!("barrier/{:?}/{:?}/{}", comp_type, cutset_type, name), || {
+                    // the thresholds of a previous compilation would prune
+                    // the whole dd
+                    for depth in 0..barriers.nb_layers() {
+                        barriers.clear_layer(depth);
+                    }
+                    dd.compile(&input);
+                    dd.best_value()
+                });
+            }
+        }
+    }
+
+    // the first compilation of a dd, with and without arenas allocated ahead
+    // of time: this is what each worker pays when a resolution starts
+    let model = Layered::new(30, 4, 100_000);
+    let input = model.input(CompilationType::Relaxed, 250);
+    let barriers = model.barriers();
+    let mut dd = Barrier::new(barriers.clone(), CutsetType::Frontier);
+    dd.compile(&input);
+    let (nodes, edges) = dd.capacity();
+    for (name, capacity) in [("fresh", (0, 0)), ("preallocated", (nodes, edges))] {
+        harness.bench(&format!("barrier/first_compilation/{}", name), || {
+            for depth in 0..barriers.nb_layers() {
+                barriers.clear_layer(depth);
+            }
+            let mut dd = Barrier::with_capacity(barriers.clone(), CutsetType::Frontier, capacity.0, capacity.1);
+            dd.compile(&input);
+            dd.best_value()
+        });
+    }
+}
@@ -0,0 +1,44 @@
+//! Benchmarks of the thresholds shared by the workers of the barrier solver.
+//!
+//! Several threads compile relaxed dds of the same model at once: hence they
+//! all read and update the same thresholds (`BarrierStore::update_threshold`). These
+//! benchmarks guard against regressions of the locking scheme of the shared
+//! barrier; the time per iteration should remain (roughly) constant as the
+//! number of threads grows, as long as there are enough cores.
+
+mod support;
+
+use std::thread;
+
+use engineering::{Barrier, CompilationType, CutsetType, DecisionDiagram};
+use support::{Harness, Layered};
+
+/// The number of dds compiled by each thread per iteration
+const COMPILATIONS: usize = 4;
+
+fn main() {
+    let harness = Harness::from_args();
+    let model = Layered::new(30, 4, 64);
+    let input = model.input(CompilationType::Relaxed, 100);
+
+    for nb_threads in [1, 2, 4, 8] {
+        let barriers = model.barriers();
+        harness.bench(&format!("barrier/shared_thresholds/{}_threads", nb_threads), || {
+            // all the threads start from the same (empty) thresholds
+            for depth in 0..barriers.nb_layers() {
+                barriers.clear_layer(depth);
+            }
+            thread::scope(|s| {
+                for _ in 0..nb_threads {
+                    s.spawn(|| {
+                        let mut dd = Barrier::new(barriers.clone(), CutsetType::Frontier);
+                        for _ in 0..COMPILATIONS {
+                            dd.compile(&input);
+                        }
+                        dd.get_explored()
+                    });
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,68 @@
+//! Benchmarks of the `NoDupFrontier`.
+//!
+//! Every node of the branch-and-bound goes through one push and (unless it is
+//! merged with a duplicate) one pop of the frontier. These benchmarks guard
+//! against regressions of the heap maintenance and of the state lookup done
+//! on push, for frontiers of increasing size and increasing duplicate rates.
+
+mod support;
+
+use engineering::{Frontier, NoDupFrontier, SimpleFrontier};
+use support::{subproblems, Harness, LayeredRanking};
+
+fn main() {
+    let harness = Harness::from_args();
+
+    for n in [1_000, 10_000, 100_000] {
+        // (almost) no duplicates, 50% and 90% duplicates
+        for (dup, distinct) in [(0, n * 100), (50, n / 2), (90, n / 10)] {
+            let nodes = subproblems(n, distinct, 7);
+            harness.bench(&format!("no_dup/push_pop/{}/dup{}%", n, dup), || {
+                let mut fringe = NoDupFrontier::new(&LayeredRanking);
+                for node in nodes.iter() {
+                    fringe.push(node.clone());
+                }
+                let mut popped = 0;
+                while fringe.pop().is_some() {
+                    popped += 1;
+                }
+                popped
+            });
+        }
+
+        // the baseline without any duplicate detection
+        let nodes = subproblems(n, n * 100, 7);
+        harness.bench(&format!("simple/push_pop/{}", n), || {
+            let mut fringe = SimpleFrontier::new(&LayeredRanking);
+            for node in nodes.iter() {
+                fringe.push(node.clone());
+            }
+            let mut popped = 0;
+            while fringe.pop().is_some() {
+                popped += 1;
+            }
+            popped
+        });
+    }
+
+    // merging a duplicate into a large frontier: only the lookup and the
+    // sifting of the improved node should be paid for
+    for n in [1_000, 100_000] {
+        let mut fringe = NoDupFrontier::new(&LayeredRanking);
+        for node in subproblems(n, n * 100, 7) {
+            fringe.push(node);
+        }
+        let duplicates = subproblems(1_000, n * 100, 7);
+        let mut round = 0;
+        harness.bench(&format!("no_dup/merge_into/{}", n), || {
+            round += 1;
+            for node in duplicates.iter() {
+                let mut node = node.clone();
+                node.value += round;
+                node.ub += round;
+                fringe.push(node);
+            }
+            fringe.len()
+        });
+    }
+}
@@ -0,0 +1,68 @@
+//! Benchmarks of the transitions of the srflp example, on the 49 departments
+//! of the instance `sko49_1`.
+//!
+//! Each iteration creates one node: a department is placed after one of the
+//! states which are met along a dive of the problem, either with `transition`
+//! then `transition_cost` or with the fused `transition_with_cost` (which the
+//! dds call). The time per iteration is thus the time spent in the model for
+//! each node of a dd, which the rebuilt cut vector is meant to reduce.
+
+mod support;
+
+// the modules of the example (along with their unit tests, hence the
+// bench-tools feature)
+#[allow(dead_code, unused_imports)]
+#[path = "../examples/srflp/heuristics.rs"]
+mod heuristics;
+#[allow(dead_code, unused_imports)]
+#[path = "../examples/srflp/instance.rs"]
+mod instance;
+#[allow(dead_code, unused_imports)]
+#[path = "../examples/srflp/model.rs"]
+mod model;
+#[allow(dead_code, unused_imports)]
+#[path = "../examples/srflp/relax.rs"]
+mod relax;
+#[allow(dead_code, unused_imports)]
+#[path = "../examples/srflp/state.rs"]
+mod state;
+
+use std::{cell::Cell, fs::File};
+
+use engineering::{Decision, Problem, Variable};
+use instance::SrflpInstance;
+use model::Srflp;
+use state::State;
+use support::Harness;
+
+fn main() {
+    let harness = Harness::from_args();
+    let model = Srflp::new(SrflpInstance::from(File::open("resources/srflp/sko49_1").unwrap()));
+
+    // every decision of the states met along a dive, which places the first
+    // department of each domain
+    let mut nodes: Vec<(State, Decision)> = vec![];
+    let mut state = model.initial_state();
+    for depth in 0..model.nb_variables() {
+        let var = Variable(depth);
+        let mut domain = vec![];
+        model.for_each_in_domain(var, &state, |decision| domain.push(decision));
+        nodes.extend(domain.iter().map(|decision| (state.clone(), *decision)));
+        state = model.transition(&state, domain[0]);
+    }
+
+    let next = Cell::new(0);
+    let node = || {
+        let i = next.get();
+        next.set((i + 1) % nodes.len());
+        &nodes[i]
+    };
+    harness.bench("srflp/transition_then_cost/sko49_1", || {
+        let (state, decision) = node();
+        (model.transition(state, *decision), model.transition_cost(state, *decision))
+    });
+    harness.bench("srflp/transition_with_cost/sko49_1", || {
+        let (state, decision) = node();
+        model.transition_with_cost(state, *decision)
+    });
+}
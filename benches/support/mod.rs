@@ -0,0 +1,290 @@
+//! This module is shared by all the micro-benchmarks. It provides a (tiny)
+//! timing harness along with the synthetic models and generators which are
+//! used to exercise the hot paths of the solvers in isolation.
+//!
+//! # Note:
+//! Each benchmark binary only uses part of this module.
+#![allow(dead_code)]
+
+use std::{
+    cmp::Ordering,
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bitset_fixed::BitSet;
+use engineering::{AssignedVars, BarrierStore, Barriers, CompilationInput, CompilationType, Decision, MergePolicy, Problem, Relaxation, StateRanking, SubProblem, Variable};
+
+/// The time spent running a benchmark before it is measured
+const WARM_UP: Duration = Duration::from_millis(200);
+/// The number of samples whose median is reported
+const SAMPLES: usize = 15;
+/// The time each sample should (roughly) last
+const SAMPLE_TIME: Duration = Duration::from_millis(20);
+
+/// A group of benchmarks. Only the benchmarks whose name contains one of the
+/// filters given on the command line (if any) are executed, e.g. with
+/// `cargo bench --bench frontier -- no_dup/push_pop`.
+pub struct Harness {
+    filters: Vec<String>,
+}
+impl Harness {
+    pub fn from_args() -> Self {
+        // cargo passes `--bench` to the binaries it runs with `cargo bench`
+        let filters = std::env::args().skip(1).filter(|a| !a.starts_with("--")).collect();
+        Self { filters }
+    }
+
+    /// Measures the time needed to execute `routine` and prints the median
+    /// (and fastest) time per iteration among several samples.
+    pub fn bench<T, F: FnMut() -> T>(&self, name: &str, mut routine: F) {
+        if !self.filters.is_empty() && !self.filters.iter().any(|f| name.contains(f.as_str())) {
+            return;
+        }
+
+        // warm up and estimate the number of iterations to put in each sample
+        let start = Instant::now();
+        let mut iters = 0_u32;
+        while start.elapsed() < WARM_UP {
+            black_box(routine());
+            iters += 1;
+        }
+        let per_iter = start.elapsed() / iters;
+        let batch = (SAMPLE_TIME.as_nanos() / per_iter.as_nanos().max(1)).max(1) as u32;
+
+        let mut samples = (0..SAMPLES)
+            .map(|_| {
+                let start = Instant::now();
+                for _ in 0..batch {
+                    black_box(routine());
+                }
+                start.elapsed() / batch
+            })
+            .collect::<Vec<_>>();
+        samples.sort_unstable();
+
+        println!("{:<48} {:>10} /iter (fastest {}, {} x {} iterations)",
+            name, pretty(samples[SAMPLES / 2]), pretty(samples[0]), SAMPLES, batch);
+    }
+}
+
+fn pretty(d: Duration) -> String {
+    let ns = d.as_nanos();
+    if ns < 10_000 {
+        format!("{} ns", ns)
+    } else if ns < 10_000_000 {
+        format!("{:.2} µs", ns as f64 / 1e3)
+    } else {
+        format!("{:.2} ms", ns as f64 / 1e6)
+    }
+}
+
+/// A (deterministic) xorshift generator: the benchmarks must exercise the
+/// same inputs from one run to the next.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    /// Returns a number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// --- SYNTHETIC MODEL -------------------------------------------------------
+
+/// The state of the synthetic model: the depth of the layer and one out of
+/// `distinct` labels.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LayeredState {
+    pub depth: usize,
+    pub label: usize,
+}
+
+/// A synthetic model whose layers contain at most `distinct` states while
+/// each state has `domain` successors. Hence, the higher the ratio between
+/// `width * domain` and `distinct`, the more transitions reach a state which
+/// already exists in the next layer (the duplicate rate of the model).
+#[derive(Debug, Clone)]
+pub struct Layered {
+    pub nb_vars: usize,
+    pub domain: usize,
+    pub distinct: usize,
+    /// The cost of each (label, value) pair
+    costs: Vec<isize>,
+    max_cost: isize,
+}
+impl Layered {
+    pub fn new(nb_vars: usize, domain: usize, distinct: usize) -> Self {
+        let mut rng = Rng::new(42);
+        let costs: Vec<isize> = (0..distinct * domain).map(|_| rng.below(100) as isize).collect();
+        let max_cost = costs.iter().copied().max().unwrap_or(0);
+        Self { nb_vars, domain, distinct, costs, max_cost }
+    }
+    /// Returns the label reached when taking `value` from `label`: the labels
+    /// are scattered so that all of them eventually get reached.
+    fn successor(&self, label: usize, value: usize) -> usize {
+        label.wrapping_mul(31).wrapping_add(value.wrapping_mul(17)).wrapping_add(7) % self.distinct
+    }
+    /// Returns the root subproblem of the model
+    pub fn root(&self) -> SubProblem<LayeredState> {
+        SubProblem {
+            state: Arc::new(self.initial_state()),
+            value: self.initial_value(),
+            path: vec![],
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        }
+    }
+    /// Returns an empty set of thresholds for each layer of the model
+    pub fn barriers(&self) -> Barriers<LayeredState> {
+        Arc::new(BarrierStore::new(self.nb_vars + 1))
+    }
+    /// Returns the input needed to compile a dd of the given type and width
+    /// for the root of the model
+    pub fn input(&self, comp_type: CompilationType, max_width: usize) -> CompilationInput<'_, Layered, LayeredRelax, LayeredRanking> {
+        CompilationInput {
+            comp_type,
+            max_width,
+            problem: self,
+            relaxation: &LayeredRelax,
+            ranking: &LayeredRanking,
+            residual: self.root(),
+            assigned: AssignedVars::new(self.nb_vars),
+            best_lb: isize::MIN,
+            min_relax_depth_offset: 1,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            dominance: None,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: false,
+            timed: false,
+            self_check: false,
+            cancellation: None,
+            incumbent: None,
+        }
+    }
+}
+impl Problem for Layered {
+    type State = LayeredState;
+
+    fn nb_variables(&self) -> usize {
+        self.nb_vars
+    }
+
+    fn initial_state(&self) -> Self::State {
+        LayeredState { depth: 0, label: 0 }
+    }
+
+    fn initial_value(&self) -> isize {
+        0
+    }
+
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        next_layer
+            .next()
+            .map(|s| s.depth)
+            .filter(|depth| *depth < self.nb_vars)
+            .map(Variable)
+    }
+
+    fn for_each_in_domain<F>(&self, var: Variable, _state: &Self::State, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        for value in 0..self.domain {
+            f(Decision { var, value: value as isize });
+        }
+    }
+
+    fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        LayeredState { depth: state.depth + 1, label: self.successor(state.label, decision.value as usize) }
+    }
+
+    fn transition_cost(&self, state: &Self::State, decision: Decision) -> isize {
+        self.costs[state.label * self.domain + decision.value as usize]
+    }
+
+    fn estimate(&self, state: &Self::State) -> isize {
+        (self.nb_vars - state.depth) as isize * self.max_cost
+    }
+}
+
+/// Merging keeps the smallest label, which is as good as any other choice
+/// since it only matters that the relaxation is cheap
+#[derive(Debug, Clone, Copy)]
+pub struct LayeredRelax;
+impl Relaxation for LayeredRelax {
+    type State = LayeredState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        let mut depth = 0;
+        let mut label = usize::MAX;
+        for state in states {
+            depth = depth.max(state.depth);
+            label = label.min(state.label);
+        }
+        LayeredState { depth, label }
+    }
+
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        cost
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LayeredRanking;
+impl StateRanking for LayeredRanking {
+    type State = LayeredState;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        a.label.cmp(&b.label)
+    }
+}
+
+// --- GENERATORS ------------------------------------------------------------
+
+/// Generates `n` subproblems whose states are drawn among `distinct` ones
+/// (hence, about `n - distinct` of them are duplicates as soon as `n` is
+/// large enough).
+pub fn subproblems(n: usize, distinct: usize, seed: u64) -> Vec<SubProblem<LayeredState>> {
+    let mut rng = Rng::new(seed);
+    (0..n)
+        .map(|_| {
+            let label = rng.below(distinct);
+            let value = rng.below(1000) as isize;
+            SubProblem {
+                state: Arc::new(LayeredState { depth: 1, label }),
+                value,
+                path: vec![],
+                ub: value + rng.below(1000) as isize,
+                id: 0,
+                parent: None,
+                prefix: None,
+            }
+        })
+        .collect()
+}
+
+/// Generates a bitset of `size` bits where each bit is set with a probability
+/// of `density` percents.
+pub fn bitset(size: usize, density: usize, seed: u64) -> BitSet {
+    let mut rng = Rng::new(seed);
+    let mut set = BitSet::new(size);
+    for i in 0..size {
+        set.set(i, rng.below(100) < density);
+    }
+    set
+}
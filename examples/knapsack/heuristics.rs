@@ -0,0 +1,29 @@
+use std::cmp::Ordering;
+
+use engineering::api::{StateRanking, WidthHeuristic};
+
+use crate::model::KnapsackState;
+
+/// The states having the largest remaining capacity are the most promising
+#[derive(Debug, Copy, Clone)]
+pub struct KnapsackRanking;
+impl StateRanking for KnapsackRanking {
+    type State = KnapsackState;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        a.capacity.cmp(&b.capacity)
+    }
+}
+
+/// Gives the dds a width of `factor` times the number of items which remain
+/// to be decided
+#[derive(Debug, Copy, Clone)]
+pub struct KnapsackWidth {
+    pub nb_vars: usize,
+    pub factor: usize,
+}
+impl WidthHeuristic<KnapsackState> for KnapsackWidth {
+    fn max_width(&self, state: &KnapsackState) -> usize {
+        (self.nb_vars - state.depth).max(1) * self.factor
+    }
+}
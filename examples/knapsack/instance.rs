@@ -0,0 +1,63 @@
+//! This module parses the knapsack instances. The first line of a file gives
+//! the number of items and the capacity of the knapsack; each of the next
+//! lines gives the profit and the weight of an item. The lines starting with
+//! a '#' are comments.
+
+use std::{fs::File, io::{BufRead, BufReader, Lines, Read}};
+
+/// This structure represents a 0/1 knapsack instance.
+#[derive(Debug, Clone)]
+pub struct KnapsackInstance {
+    /// The weight of each item
+    pub weights: Vec<usize>,
+    /// The profit of each item
+    pub profits: Vec<isize>,
+    /// The capacity of the knapsack
+    pub capacity: usize,
+}
+
+impl From<File> for KnapsackInstance {
+    fn from(file: File) -> Self {
+        Self::from(BufReader::new(file))
+    }
+}
+impl<S: Read> From<BufReader<S>> for KnapsackInstance {
+    fn from(buf: BufReader<S>) -> Self {
+        Self::from(buf.lines())
+    }
+}
+impl<B: BufRead> From<Lines<B>> for KnapsackInstance {
+    fn from(lines: Lines<B>) -> Self {
+        let mut nb_items = 0;
+        let mut instance = KnapsackInstance { weights: vec![], profits: vec![], capacity: 0 };
+
+        let lines = lines.map(|line| line.unwrap());
+        let mut lines = lines.filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+        if let Some(header) = lines.next() {
+            let mut fields = header.split_whitespace().map(|s| s.parse::<usize>().unwrap());
+            nb_items = fields.next().unwrap();
+            instance.capacity = fields.next().unwrap();
+        }
+        for line in lines.take(nb_items) {
+            let mut fields = line.split_whitespace();
+            instance.profits.push(fields.next().unwrap().parse().unwrap());
+            instance.weights.push(fields.next().unwrap().parse().unwrap());
+        }
+        instance
+    }
+}
+
+#[cfg(test)]
+mod test_instance {
+    use std::io::BufReader;
+
+    use super::KnapsackInstance;
+
+    #[test]
+    fn the_items_are_read_after_the_header() {
+        let instance = KnapsackInstance::from(BufReader::new("# a comment\n2 7\n10 5\n\n4 3\n".as_bytes()));
+        assert_eq!(7, instance.capacity);
+        assert_eq!(vec![10, 4], instance.profits);
+        assert_eq!(vec![5, 3], instance.weights);
+    }
+}
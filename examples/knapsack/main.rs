@@ -0,0 +1,45 @@
+//! A minimal example: the 0/1 knapsack. Its model is the smallest one a
+//! solver needs (a `Problem`, a `Relaxation`, a `StateRanking` and a
+//! `WidthHeuristic`), hence it is the place to start from when writing a new
+//! one.
+
+use std::{fs::File, path::Path, time::Duration};
+
+use engineering::api::Problem;
+use engineering::{xputils::{resolution_header, solve, solve_timeout, Args}, bench::{bench_file, instance_name}};
+use heuristics::{KnapsackRanking, KnapsackWidth};
+use instance::KnapsackInstance;
+use model::Knapsack;
+use relax::KnapsackRelax;
+use structopt::StructOpt;
+
+mod heuristics;
+mod instance;
+mod model;
+mod relax;
+
+fn main() {
+    let args = Args::from_args();
+
+    match args {
+        Args::Solve { file, width, timeout, threads, solver, cutset, .. } => {
+            let path = Path::new(&file);
+            let name = path
+                .file_stem()
+                .map(|s| s.to_str().unwrap_or("-- unknown --"))
+                .unwrap_or("-- unknown --");
+            let name: &'static str = Box::leak(Box::new(name.to_string()));
+
+            let model = Knapsack::new(KnapsackInstance::from(File::open(path).unwrap()));
+            let width = KnapsackWidth { nb_vars: model.nb_variables(), factor: width.unwrap_or(1) };
+            let timeout = Duration::from_secs(timeout as u64);
+            solve_timeout(name, timeout, &width, &model, &KnapsackRelax, &KnapsackRanking, threads, solver, cutset);
+        }
+        Args::Bench { experiment, output } => bench_file(&experiment, &output, |file, config, timeout| {
+            let model = Knapsack::new(KnapsackInstance::from(File::open(file).unwrap()));
+            let width = KnapsackWidth { nb_vars: model.nb_variables(), factor: config.width };
+            solve(&instance_name(file), timeout, &width, &model, &KnapsackRelax, &KnapsackRanking, config.threads, config.solver, config.cutset, false)
+        }),
+        Args::PrintHeader => resolution_header(),
+    }
+}
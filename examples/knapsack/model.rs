@@ -0,0 +1,117 @@
+//! This module defines the dynamic programming model of the 0/1 knapsack:
+//! the items are considered one after the other, and the decision on the
+//! variable of an item tells whether it goes into the knapsack (1) or not
+//! (0).
+
+use engineering::api::{Decision, Problem, Variable};
+
+use crate::instance::KnapsackInstance;
+
+/// The state of the knapsack: its remaining capacity, after the decisions on
+/// the first `depth` items. The depth is part of the state so that the
+/// subproblems of different layers are never mistaken for one another (e.g.
+/// by the frontiers which drop the duplicate states).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KnapsackState {
+    pub depth: usize,
+    pub capacity: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Knapsack {
+    pub instance: KnapsackInstance,
+}
+impl Knapsack {
+    pub fn new(instance: KnapsackInstance) -> Self {
+        Knapsack { instance }
+    }
+}
+impl Problem for Knapsack {
+    type State = KnapsackState;
+
+    fn nb_variables(&self) -> usize {
+        self.instance.profits.len()
+    }
+
+    fn initial_state(&self) -> Self::State {
+        KnapsackState { depth: 0, capacity: self.instance.capacity }
+    }
+
+    fn initial_value(&self) -> isize {
+        0
+    }
+
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        next_layer
+            .next()
+            .map(|state| state.depth)
+            .filter(|depth| *depth < self.nb_variables())
+            .map(Variable)
+    }
+
+    fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        f(Decision { var, value: 0 });
+        if self.instance.weights[var.id()] <= state.capacity {
+            f(Decision { var, value: 1 });
+        }
+    }
+
+    fn domain_size_hint(&self, _var: Variable, _state: &Self::State) -> Option<usize> {
+        Some(2)
+    }
+
+    fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        let taken = decision.value as usize;
+        KnapsackState {
+            depth: state.depth + 1,
+            capacity: state.capacity - taken * self.instance.weights[decision.var.id()],
+        }
+    }
+
+    fn transition_cost(&self, _state: &Self::State, decision: Decision) -> isize {
+        decision.value * self.instance.profits[decision.var.id()]
+    }
+
+    /// The remaining items cannot bring more than the sum of their profits
+    fn estimate(&self, state: &Self::State) -> isize {
+        self.instance.profits[state.depth..].iter().map(|p| (*p).max(0)).sum()
+    }
+}
+
+#[cfg(test)]
+mod test_model {
+    use engineering::api::{BarrierParallelSolver, CutsetType, Problem, Solver};
+
+    use crate::{heuristics::{KnapsackRanking, KnapsackWidth}, instance::KnapsackInstance, model::Knapsack, relax::KnapsackRelax};
+
+    /// The best items are 1 and 3 (worth 90), which fill 7 units of the 10
+    fn instance() -> KnapsackInstance {
+        KnapsackInstance {
+            weights: vec![5, 4, 6, 3, 7],
+            profits: vec![10, 40, 30, 50, 35],
+            capacity: 10,
+        }
+    }
+
+    #[test]
+    fn the_solvers_find_the_optimal_value() {
+        let model = Knapsack::new(instance());
+        for factor in [1, 10] {
+            let width = KnapsackWidth { nb_vars: model.nb_variables(), factor };
+            for cutset in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+                let mut solver = BarrierParallelSolver::custom(&model, &KnapsackRelax, &KnapsackRanking, &width, cutset, 2);
+                solver.maximize();
+                assert_eq!(Some(90), solver.best_value());
+
+                let solution = solver.best_solution().unwrap();
+                assert_eq!(Ok(90), model.validate_solution(&solution));
+                let mut taken = solution.iter().filter(|d| d.value == 1).map(|d| d.var.id()).collect::<Vec<_>>();
+                taken.sort_unstable();
+                assert_eq!(vec![1, 3], taken);
+            }
+        }
+    }
+}
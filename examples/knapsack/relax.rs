@@ -0,0 +1,33 @@
+//! This module defines the relaxation of the knapsack model.
+
+use engineering::api::{Decision, Relaxation};
+
+use crate::model::KnapsackState;
+
+/// Merges the states by keeping the largest remaining capacity: the merged
+/// state admits all the completions of the states it merges.
+#[derive(Debug, Clone, Copy)]
+pub struct KnapsackRelax;
+impl Relaxation for KnapsackRelax {
+    type State = KnapsackState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        let mut merged = KnapsackState { depth: 0, capacity: 0 };
+        for state in states {
+            merged.depth = merged.depth.max(state.depth);
+            merged.capacity = merged.capacity.max(state.capacity);
+        }
+        merged
+    }
+
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        cost
+    }
+
+    fn check_merge(&self, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        match originals.iter().find(|state| state.capacity > merged.capacity) {
+            Some(state) => Err(format!("capacity of {:?} is smaller than that of {:?}", merged, state)),
+            None => Ok(()),
+        }
+    }
+}
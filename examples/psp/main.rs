@@ -1,12 +1,18 @@
-use std::{fs::File, path::Path, time::Duration};
+use std::{fs::File, io::BufReader, path::Path, process::exit, time::Duration};
 
-use engineering::{xputils::{solve_timeout, Args, SolverType, resolution_header}, Problem, CutsetType};
+use engineering::api::{FringePolicy, Problem, CutsetType};
+use engineering::{
+    xputils::{solve, solve_custom, write_decisions_file, write_solution_file, Args, OrderType, SolverType, resolution_header}, bench::{bench_file, instance_name}, WarmStartBudget, MergePolicy,
+};
 use psp::PspWidth;
+use schedule::ScheduleCsv;
 use structopt::StructOpt;
 
-use crate::psp::{Psp, PspRelax, PspRanking};
+use crate::{mst_cache::MstCache, psp::{Psp, PspRelax, PspRanking}};
 
+mod mst_cache;
 mod psp;
+mod schedule;
 mod utils;
 
 fn main() {
@@ -20,11 +26,24 @@ fn main() {
             threads,
             solver,
             cutset,
-        } => run_resolution_xp(file, width, timeout, threads, solver, cutset),
+            solution_out,
+            output,
+            verbose,
+            order,
+            fringe_policy,
+            max_nodes,
+            warm_start,
+            merge,
+            trace,
+            self_check,
+            ..
+        } => run_resolution_xp(file, width, timeout, threads, solver, cutset, solution_out, output, verbose, order, fringe_policy, max_nodes, warm_start, merge, trace, self_check),
+        Args::Bench { experiment, output } => run_benchmark_xp(experiment, output),
         Args::PrintHeader => resolution_header(),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_resolution_xp(
     file: String,
     width: Option<usize>,
@@ -32,6 +51,16 @@ fn run_resolution_xp(
     threads: Option<usize>,
     solver: SolverType,
     cutset: CutsetType,
+    solution_out: Option<String>,
+    output: Option<String>,
+    verbose: bool,
+    order: OrderType,
+    fringe_policy: FringePolicy,
+    max_nodes: Option<usize>,
+    warm_start: Option<WarmStartBudget>,
+    merge: MergePolicy,
+    trace: Option<String>,
+    self_check: bool,
 ) {
     let afile = Box::new(file);
     let afile = Box::leak(afile);
@@ -49,6 +78,56 @@ fn run_resolution_xp(
     let name: &'static str = Box::leak(name);
     let timeout = Duration::from_secs(timeout as u64);
 
-    let width = PspWidth::new(model.nb_variables(), width.unwrap_or(1));
-    solve_timeout::<Psp, PspRelax, PspRanking, PspWidth>(name,timeout, &width, &model, &relax, &ranking, threads,solver, cutset);
+    let width = PspWidth::new(model.nb_variables(), model.width_factor(width));
+    let report = solve_custom(name, timeout, &width, &model, &relax, &ranking, threads, solver, cutset, verbose, self_check, None, None, order, fringe_policy, max_nodes, warm_start, merge, trace.as_deref());
+    println!("{}", report);
+    if let Some(error) = report.verification_error.as_ref() {
+        eprintln!("{}", error);
+    }
+    for violation in &report.violations {
+        eprintln!("warning: {}", violation);
+    }
+    if let Some(count) = report.optimal_solutions {
+        println!("optimal solutions: {}", count);
+    }
+    if let Some(split) = report.time_split().filter(|_| verbose) {
+        println!("{}", split);
+    }
+    if let Some(peak) = report.peak_attribution.filter(|_| verbose) {
+        println!("{}", peak);
+    }
+    if let Some(summary) = report.barrier_summary().filter(|_| verbose) {
+        println!("{}", summary);
+    }
+
+    if let Some(path) = solution_out {
+        if let Err(error) = write_solution_file(&ScheduleCsv(&model), &report, &path) {
+            eprintln!("cannot write the solution to {}: {}", path, error);
+            exit(1);
+        }
+    }
+    if let Some(path) = output {
+        write_decisions_file(&report, &path).unwrap();
+    }
+}
+
+fn run_benchmark_xp(experiment: String, output: String) {
+    // the instances of a suite often share their changeover matrix
+    let mut cache = mst_cache();
+    bench_file(&experiment, &output, |file, config, timeout| {
+        let model = Psp::with_mst_cache(BufReader::new(File::open(file).unwrap()), &mut cache).unwrap();
+        let width = PspWidth::new(model.nb_variables(), config.width);
+        solve(&instance_name(file), timeout, &width, &model, &PspRelax, &PspRanking, config.threads, config.solver, config.cutset, false)
+    });
+}
+
+/// The cache of the MST tables used by the benchmarks. With the
+/// `psp-mst-cache` feature, the tables are persisted to the directory given
+/// by the `PSP_MST_CACHE` environment variable (if any).
+fn mst_cache() -> MstCache {
+    #[cfg(feature = "psp-mst-cache")]
+    if let Some(dir) = std::env::var_os("PSP_MST_CACHE") {
+        return MstCache::persistent(dir).unwrap();
+    }
+    MstCache::new()
 }
@@ -0,0 +1,187 @@
+//! This module implements a cache of the MST tables of the PSP. Computing the
+//! table of an instance takes 2^n MSTs (one per subset of its items), but the
+//! instances of a benchmark suite often share their changeover matrix (the
+//! families only differ by their demands). The tables are hence stored by the
+//! hash of the matrix they were computed from, so that the batch runner only
+//! computes each of them once.
+//!
+//! With the `psp-mst-cache` feature, the tables can also be persisted to a
+//! cache directory, so that they outlive the process.
+
+use std::{
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+#[cfg(feature = "psp-mst-cache")]
+use std::{fs, path::PathBuf};
+
+use rustc_hash::{FxHashMap, FxHasher};
+
+use crate::utils::Matrix;
+
+/// The MST table computed from some changeover matrix. The matrix is kept
+/// along with its table, so that two matrices having the same hash never
+/// share their table.
+#[derive(Debug)]
+struct Entry {
+    changeover: Matrix<usize>,
+    mst: Arc<Vec<usize>>,
+}
+
+/// A store of MST tables, keyed by the content hash of their changeover matrix
+#[derive(Debug, Default)]
+pub struct MstCache {
+    entries: FxHashMap<u64, Entry>,
+    /// The directory where the tables are persisted (if any)
+    #[cfg(feature = "psp-mst-cache")]
+    dir: Option<PathBuf>,
+    /// The number of tables which had to be computed
+    computed: usize,
+}
+
+impl MstCache {
+    /// Creates an empty, in-memory cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates a cache which also persists its tables to the given directory
+    /// (it is created if necessary)
+    #[cfg(feature = "psp-mst-cache")]
+    pub fn persistent(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir: Some(dir), ..Self::default() })
+    }
+    /// Returns the number of tables which had to be computed (rather than
+    /// being found in the cache)
+    #[allow(dead_code)]
+    pub fn computed(&self) -> usize {
+        self.computed
+    }
+
+    /// Returns the table of the given changeover matrix, which is computed
+    /// with `compute` when it is neither in memory nor on disk
+    pub fn get_or_compute<F>(&mut self, changeover: &Matrix<usize>, compute: F) -> Arc<Vec<usize>>
+    where
+        F: FnOnce() -> Vec<usize>,
+    {
+        let key = Self::key(changeover);
+        if let Some(entry) = self.entries.get(&key).filter(|e| e.changeover == *changeover) {
+            return entry.mst.clone();
+        }
+
+        let mst = match self.load(key, changeover) {
+            Some(mst) => mst,
+            None => {
+                self.computed += 1;
+                let mst = compute();
+                self.store(key, changeover, &mst);
+                mst
+            }
+        };
+        let mst = Arc::new(mst);
+        self.entries.insert(key, Entry { changeover: changeover.clone(), mst: mst.clone() });
+        mst
+    }
+
+    /// The content hash of a changeover matrix
+    fn key(changeover: &Matrix<usize>) -> u64 {
+        let mut hasher = FxHasher::default();
+        changeover.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[cfg(not(feature = "psp-mst-cache"))]
+    fn load(&self, _key: u64, _changeover: &Matrix<usize>) -> Option<Vec<usize>> {
+        None
+    }
+    #[cfg(not(feature = "psp-mst-cache"))]
+    fn store(&self, _key: u64, _changeover: &Matrix<usize>, _mst: &[usize]) {}
+
+    #[cfg(feature = "psp-mst-cache")]
+    fn path(&self, key: u64) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{:016x}.mst", key)))
+    }
+    /// Reads a persisted table. The file starts with the matrix the table was
+    /// computed from: a file whose matrix differs is stale, and it is ignored.
+    #[cfg(feature = "psp-mst-cache")]
+    fn load(&self, key: u64, changeover: &Matrix<usize>) -> Option<Vec<usize>> {
+        let text = fs::read_to_string(self.path(key)?).ok()?;
+        let mut lines = text.lines();
+        let matrix = lines.next()?.split_whitespace().map(str::parse).collect::<Result<Vec<usize>, _>>().ok()?;
+        if matrix.len() != 2 + changeover.data.len() || matrix[..2] != [changeover.n, changeover.m] || matrix[2..] != changeover.data {
+            return None;
+        }
+        let mst = lines.next()?.split_whitespace().map(str::parse).collect::<Result<Vec<usize>, _>>().ok()?;
+        (mst.len() == 1 << changeover.n).then_some(mst)
+    }
+    /// Persists a table (when the cache has a directory). A table which cannot
+    /// be written is simply not persisted.
+    #[cfg(feature = "psp-mst-cache")]
+    fn store(&self, key: u64, changeover: &Matrix<usize>, mst: &[usize]) {
+        let Some(path) = self.path(key) else { return };
+        let join = |values: &mut dyn Iterator<Item = &usize>| values.map(usize::to_string).collect::<Vec<_>>().join(" ");
+        let dims = [changeover.n, changeover.m];
+        let text = format!("{}\n{}\n", join(&mut dims.iter().chain(changeover.data.iter())), join(&mut mst.iter()));
+        let _ = fs::write(path, text);
+    }
+}
+
+#[cfg(test)]
+mod test_mst_cache {
+    use std::{cell::Cell, io::BufReader};
+
+    use crate::psp::Psp;
+
+    use super::MstCache;
+
+    #[test]
+    fn instances_sharing_their_matrix_share_their_table() {
+        let mut cache = MstCache::new();
+        let parse = |text: &str, cache: &mut MstCache| Psp::with_mst_cache(BufReader::new(text.as_bytes()), cache).unwrap();
+        // both instances have the same changeover matrix, but not the same demands
+        let first = parse("3\n2\n2\n\n0 5\n3 0\n\n1 2\n\n0 1 0\n0 0 1\n\n17\n", &mut cache);
+        let second = parse("3\n2\n2\n\n0 5\n3 0\n\n1 2\n\n0 0 1\n0 1 0\n\n5\n", &mut cache);
+        assert_eq!(1, cache.computed());
+        assert_eq!(first.mst, second.mst);
+        assert_eq!(*Psp::try_from(BufReader::new("3\n2\n2\n\n0 5\n3 0\n\n1 2\n\n0 1 0\n0 0 1\n".as_bytes())).unwrap().mst, *first.mst);
+
+        // another changeover matrix gets a table of its own
+        parse("3\n2\n2\n\n0 4\n3 0\n\n1 2\n\n0 1 0\n0 0 1\n\n17\n", &mut cache);
+        assert_eq!(2, cache.computed());
+    }
+
+    #[test]
+    fn a_table_is_only_computed_on_a_miss() {
+        let mut cache = MstCache::new();
+        let calls = Cell::new(0);
+        let compute = || {
+            calls.set(calls.get() + 1);
+            vec![0, 1, 2, 3]
+        };
+        let mut changeover = crate::utils::Matrix::new_default(2, 2, 0);
+        changeover[(0, 1)] = 1;
+        assert_eq!(vec![0, 1, 2, 3], *cache.get_or_compute(&changeover, compute));
+        assert_eq!(vec![0, 1, 2, 3], *cache.get_or_compute(&changeover, compute));
+        assert_eq!(1, calls.get());
+        changeover[(1, 0)] = 1;
+        cache.get_or_compute(&changeover, compute);
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    #[cfg(feature = "psp-mst-cache")]
+    fn a_persisted_table_outlives_the_cache() {
+        let dir = std::env::temp_dir().join(format!("psp-mst-cache-{}", std::process::id()));
+        let mut changeover = crate::utils::Matrix::new_default(2, 2, 0);
+        changeover[(0, 1)] = 7;
+
+        let mut cache = MstCache::persistent(&dir).unwrap();
+        cache.get_or_compute(&changeover, || vec![4, 3, 2, 1]);
+        let mut cache = MstCache::persistent(&dir).unwrap();
+        assert_eq!(vec![4, 3, 2, 1], *cache.get_or_compute(&changeover, || unreachable!()));
+        assert_eq!(0, cache.computed());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,18 +1,20 @@
 use std::{
     cell::RefCell,
-    fmt::Debug,
+    cmp::Reverse,
+    fmt::{Debug, Display},
     fs::File,
-    io::{BufRead, BufReader, Lines, Read},
+    io::{self, BufRead, BufReader, Lines, Read, Write},
+    num::ParseIntError,
+    str::FromStr,
+    sync::Arc,
 };
 
-use engineering::{
-    Decision, NbUnassigned, Problem, Relaxation, StateRanking, Variable, WidthHeuristic,
-};
+use engineering::api::{Decision, NbUnassigned, Problem, Relaxation, StateRanking, Variable, WidthHeuristic};
 
 use smallbitset::Set32;
 use thread_local::ThreadLocal;
 
-use crate::utils::Matrix;
+use crate::{mst_cache::MstCache, utils::Matrix};
 
 static IDLE: isize = -1;
 static BOT: i32 = -1;
@@ -61,6 +63,21 @@ impl Relaxation for PspRelax {
     ) -> isize {
         cost
     }
+
+    fn check_merge(&self, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        if merged.k != BOT {
+            return Err(format!("{:?} should not be producing any item", merged));
+        }
+        for state in originals {
+            if merged.time < state.time {
+                return Err(format!("time of {:?} is smaller than that of {:?}", merged, state));
+            }
+            if merged.u.iter().zip(state.u.iter()).any(|(m, s)| m > s) {
+                return Err(format!("deadlines of {:?} are later than those of {:?}", merged, state));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -97,17 +114,33 @@ impl WidthHeuristic<State> for PspWidth {
 
 #[derive(Debug)]
 pub struct Psp {
+    #[allow(dead_code)]
     pub optimum: Option<usize>,
     pub nb_periods: usize,
     pub nb_items: usize,
+    #[allow(dead_code)]
     pub nb_orders: usize,
     pub changeover_cost: Matrix<usize>,
     pub stocking_cost: Vec<usize>,
+    /// The demand of each item (row) in each period (column), as read from
+    /// the instance file
+    #[allow(dead_code)]
+    pub demands: Matrix<usize>,
     // le précédent/suivant est -1 lorsqu'il n'ya plus de deadline
     pub prev_demand: Matrix<i32>,
     pub rem_demand: Matrix<isize>,
 
-    pub mst: Vec<usize>,
+    /// The MST table: the cost of the MST of each subset of the items
+    pub mst: Arc<Vec<usize>>,
+
+    /// The width factor recommended for this instance (if any), as read from
+    /// the optional metadata section at the end of the instance file
+    pub suggested_width: Option<usize>,
+    /// The cost of each order served from the inventory left over by the
+    /// previous horizon (if any), as read from the optional metadata section:
+    /// when it is set, the plan may leave orders unproduced, and each of them
+    /// is charged this cost at the end of the horizon
+    pub leftover_cost: Option<usize>,
 
     buffer_state: ThreadLocal<RefCell<Vec<i32>>>,
     buffer_time: ThreadLocal<RefCell<Vec<usize>>>,
@@ -145,21 +178,24 @@ impl Problem for Psp {
         }
     }
 
+    fn domain_size_hint(&self, var: Variable, state: &Self::State) -> Option<usize> {
+        // the items which may still be produced at this time, and idling
+        let time = var.0 as i32;
+        Some(state.u.iter().filter(|u| **u >= time).count() + 1)
+    }
+
     fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, mut f: F)
     where
         F: FnMut(Decision),
     {
         let time = var.0;
         let dom = (0..self.nb_items as isize).filter(move |i| state.u[*i as usize] >= time as i32);
-        let rem_demand = (0..self.nb_items as usize).map(|i| {
-            if state.u[i] < 0 {
-                0
-            } else {
-                self.rem_demand[(i, state.u[i] as usize)] as usize
-            }
-        }).sum::<usize>();
-        
-        if rem_demand > time + 1 {
+        let rem_demand = self.pending_orders(state);
+        // the orders which do not fit in the remaining periods may only be
+        // served from the leftover inventory
+        let leftover = self.leftover_cost.is_some();
+
+        if rem_demand > time + 1 && !leftover {
             return;
         }
 
@@ -167,7 +203,7 @@ impl Problem for Psp {
             f(Decision { var, value: val })
         }
 
-        if rem_demand < time + 1 {
+        if rem_demand < time + 1 || leftover {
             f(Decision { var, value: IDLE })
         }
     }
@@ -198,6 +234,17 @@ impl Problem for Psp {
         }
     }
 
+    fn terminal_value(&self, state: &Self::State) -> isize {
+        // the plan is built backwards: the orders still pending in the final
+        // state are those which are served from the leftover inventory. A
+        // merged state keeps the earliest deadlines, hence the fewest pending
+        // orders: its penalty bounds those of the states it merges
+        match self.leftover_cost {
+            Some(cost) => -((cost * self.pending_orders(state)) as isize),
+            None => 0,
+        }
+    }
+
     fn estimate(&self, state: &Self::State) -> isize {
         if state.time == 0 {
             0
@@ -237,7 +284,58 @@ impl Problem for Psp {
     }
 }
 
+/// What happens during one period of a production plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeriodTrace {
+    pub period: usize,
+    /// The item produced during the period (if any)
+    pub item: Option<usize>,
+    /// The cost of stocking the produced item until it is delivered
+    pub stocking: usize,
+    /// The cost of the changeover from the previously produced item
+    pub changeover: usize,
+}
+
 impl Psp {
+    /// Replays the given solution with the transitions of the model and
+    /// returns what happens during each period, in chronological order.
+    pub fn trace(&self, solution: &[Decision]) -> Vec<PeriodTrace> {
+        let mut trace = (0..self.nb_periods)
+            .map(|period| PeriodTrace { period, item: None, stocking: 0, changeover: 0 })
+            .collect::<Vec<_>>();
+
+        // the model plans the last period first
+        let mut decisions = solution.to_vec();
+        decisions.sort_unstable_by_key(|d| Reverse(d.var.id()));
+
+        let mut state = self.initial_state();
+        let mut next_production: Option<usize> = None;
+        for decision in decisions {
+            let period = decision.var.id();
+            if decision.value != IDLE {
+                let item = decision.value as usize;
+                trace[period].item = Some(item);
+                trace[period].stocking = self.stocking_cost[item] * (state.u[item] as usize - period);
+                // state.k is the item produced in the next_production period
+                if let Some(next) = next_production {
+                    trace[next].changeover = self.changeover_cost[(item, state.k as usize)];
+                }
+                next_production = Some(period);
+            }
+            state = self.transition(&state, decision);
+        }
+        trace
+    }
+
+    /// Returns the number of orders which are still to be produced in the
+    /// given state
+    fn pending_orders(&self, state: &State) -> usize {
+        (0..self.nb_items)
+            .filter(|i| state.u[*i] >= 0)
+            .map(|i| self.rem_demand[(i, state.u[i] as usize)] as usize)
+            .sum()
+    }
+
     /*** ESTIMATION ON THE STOCKING COSTS ***************************************/
     fn compute_ideal_stocking(
         periods: usize,
@@ -259,9 +357,10 @@ impl Psp {
                 }
             }
 
-            *storage_cost = (deadline - time) * cost;
+            *storage_cost = 0;
             if item != IDLE {
                 let item = item as usize;
+                *storage_cost = (deadline - time) * cost;
                 state[item] = prev_dem[(item, deadline)];
             }
         }
@@ -350,6 +449,21 @@ pub enum PspError {
     Missing(&'static str),
     #[error("expected int {0}")]
     ParseInt(#[from] std::num::ParseIntError),
+    #[error("expected {expected} {what} on a row, found {found}")]
+    RowLength { what: &'static str, expected: usize, found: usize },
+}
+impl Psp {
+    /// Returns the width factor to use: the one given on the command line if
+    /// any, otherwise the one suggested by the instance file (defaults to 1).
+    pub fn width_factor(&self, width: Option<usize>) -> usize {
+        width.or(self.suggested_width).unwrap_or(1)
+    }
+    /// Parses an instance, looking its MST table up in the given cache (the
+    /// table is only computed when no instance with the same changeover
+    /// matrix was seen before)
+    pub fn with_mst_cache<B: BufRead>(buf: B, cache: &mut MstCache) -> Result<Psp, PspError> {
+        Psp::parse(buf.lines(), Some(cache))
+    }
 }
 impl TryFrom<File> for Psp {
     type Error = PspError;
@@ -368,98 +482,79 @@ impl<S: Read> TryFrom<BufReader<S>> for Psp {
 impl<B: BufRead> TryFrom<Lines<B>> for Psp {
     type Error = PspError;
 
-    fn try_from(mut lines: Lines<B>) -> Result<Psp, PspError> {
-        let nb_periods = lines
-            .next()
-            .ok_or(PspError::Missing("nb periods"))??
-            .parse::<usize>()?;
-        let nb_items = lines
-            .next()
-            .ok_or(PspError::Missing("nb items"))??
-            .parse::<usize>()?;
-        let nb_orders = lines
-            .next()
-            .ok_or(PspError::Missing("nb orders"))??
-            .parse::<usize>()?;
-
-        let _blank = lines.next();
-        let mut changeover_cost = Matrix::new_default(nb_items, nb_items, 0);
-
-        let mut i = 0;
-        for line in &mut lines {
-            let line = line?;
-            let line = line.trim();
-            if line.is_empty() {
-                break;
-            }
-
-            let costs = line.split_whitespace();
-            for (other, cost) in costs.enumerate() {
-                changeover_cost[(i, other)] = cost.parse::<usize>()?;
-            }
-
-            i += 1;
-        }
-
-        let stocking_texts = lines.next().ok_or(PspError::Missing("stocking costs"))??;
-        let mut stocking_cost = vec![0; nb_items];
-        let stock_iter = stocking_cost
-            .iter_mut()
-            .zip(stocking_texts.split_whitespace());
+    fn try_from(lines: Lines<B>) -> Result<Psp, PspError> {
+        Psp::parse(lines, None)
+    }
+}
+impl Psp {
+    /// Parses an instance. The sections of the file may be separated by any
+    /// number of blank lines: the number of rows of each section is known
+    /// from the header.
+    fn parse<B: BufRead>(mut lines: Lines<B>, cache: Option<&mut MstCache>) -> Result<Psp, PspError> {
+        let nb_periods = next_line(&mut lines, "nb periods")?.parse::<usize>()?;
+        let nb_items = next_line(&mut lines, "nb items")?.parse::<usize>()?;
+        let nb_orders = next_line(&mut lines, "nb orders")?.parse::<usize>()?;
 
-        for (cost, text) in stock_iter {
-            *cost = text.parse::<usize>()?;
+        let mut changeover_cost = Matrix::new_default(nb_items, nb_items, 0);
+        for i in 0..nb_items {
+            let costs = parse_row(&next_line(&mut lines, "changeover costs")?, nb_items, "changeover costs")?;
+            changeover_cost.row_mut(i).zip(costs).for_each(|(cost, parsed)| *cost = parsed);
         }
 
-        let _blank = lines.next();
+        let stocking_cost = parse_row(&next_line(&mut lines, "stocking costs")?, nb_items, "stocking costs")?;
 
+        let mut demands = Matrix::new(nb_items, nb_periods);
         let mut prev_demand = Matrix::new(nb_items, nb_periods + 1);
         let mut rem_demand: Matrix<isize> = Matrix::new(nb_items, nb_periods);
-        i = 0;
-        for line in &mut lines {
-            let line = line?;
-            let line = line.trim();
-
-            if line.is_empty() {
-                break;
-            }
-
-            let demands_for_item = line.split_whitespace();
+        for i in 0..nb_items {
+            let demands_for_item = parse_row(&next_line(&mut lines, "demands")?, nb_periods, "demands")?;
 
             // on construit la relation prev_demand[i]
             let mut last_period = BOT;
-            for (period, demand_text) in demands_for_item.enumerate() {
+            for (period, demand) in demands_for_item.into_iter().enumerate() {
+                demands[(i, period)] = demand;
                 prev_demand[(i, period)] = last_period;
 
                 if period > 0 {
                     rem_demand[(i, period)] = rem_demand[(i, period - 1)];
                 }
 
-                let demand = demand_text.parse::<usize>()?;
                 if demand > 0 {
                     last_period = period as i32;
                     rem_demand[(i, period)] += 1;
                 }
+            }
+            prev_demand[(i, nb_periods)] = last_period;
+        }
 
-                if period == nb_periods - 1 {
-                    prev_demand[(i, 1 + period)] = last_period;
+        // The known optimum (if any) comes after the demands and may be
+        // followed by metadata lines such as '# width: 5' or '# leftover: 10'.
+        let mut optimum = None;
+        let mut suggested_width = None;
+        let mut leftover_cost = None;
+        for line in &mut lines {
+            let line = line?;
+            let line = line.trim();
+
+            if let Some(meta) = line.strip_prefix('#') {
+                if let Some((key, value)) = meta.split_once(':') {
+                    match key.trim() {
+                        "width" => suggested_width = Some(value.trim().parse::<usize>()?),
+                        "leftover" => leftover_cost = Some(value.trim().parse::<usize>()?),
+                        _ => {}
+                    }
                 }
+            } else if !line.is_empty() && optimum.is_none() {
+                optimum = Some(line.parse::<usize>()?);
             }
-
-            i += 1;
         }
 
-        // This means there mus be TWO blank lines between the end of demands
-        // and the known optimum.
-        let _skip = lines.next();
-        let optimum = if let Some(line) = lines.next() {
-            Some(line?.trim().parse::<usize>()?)
-        } else {
-            None
+        let compute = || Psp::precompute_all_mst(nb_items, &changeover_cost);
+        let mst = match cache {
+            Some(cache) => cache.get_or_compute(&changeover_cost, compute),
+            None => Arc::new(compute()),
         };
 
-        let mst = Psp::precompute_all_mst(nb_items, &changeover_cost);
-
         Ok(Psp {
             optimum,
             nb_periods,
@@ -467,13 +562,174 @@ impl<B: BufRead> TryFrom<Lines<B>> for Psp {
             nb_orders,
             changeover_cost,
             stocking_cost,
+            demands,
             prev_demand,
             rem_demand,
 
             mst,
+            suggested_width,
+            leftover_cost,
 
             buffer_state: ThreadLocal::new(), //RefCell::new(vec![0; nb_items]),
             buffer_time: ThreadLocal::new(),  //RefCell::new(vec![0; nb_periods]),
         })
     }
+
+    /// Writes the instance in the format of the instance files, which the
+    /// parser reads back as the same instance: the sections are separated by
+    /// a single blank line, and the known optimum (if any) is followed by the
+    /// metadata of the instance.
+    #[allow(dead_code)]
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "{}\n{}\n{}\n", self.nb_periods, self.nb_items, self.nb_orders)?;
+        for i in 0..self.nb_items {
+            write_row(&mut w, self.changeover_cost.row(i))?;
+        }
+        writeln!(w)?;
+        write_row(&mut w, self.stocking_cost.iter())?;
+        writeln!(w)?;
+        for i in 0..self.nb_items {
+            write_row(&mut w, self.demands.row(i))?;
+        }
+        if let Some(optimum) = self.optimum {
+            writeln!(w, "\n{}", optimum)?;
+        }
+        if let Some(width) = self.suggested_width {
+            writeln!(w, "# width: {}", width)?;
+        }
+        if let Some(cost) = self.leftover_cost {
+            writeln!(w, "# leftover: {}", cost)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the next line which is not blank (trimmed)
+fn next_line<B: BufRead>(lines: &mut Lines<B>, what: &'static str) -> Result<String, PspError> {
+    for line in lines {
+        let line = line?;
+        if !line.trim().is_empty() {
+            return Ok(line.trim().to_owned());
+        }
+    }
+    Err(PspError::Missing(what))
+}
+
+/// Parses a row of the given length
+fn parse_row<T: FromStr<Err = ParseIntError>>(line: &str, len: usize, what: &'static str) -> Result<Vec<T>, PspError> {
+    let row = line.split_whitespace().map(str::parse).collect::<Result<Vec<T>, _>>()?;
+    if row.len() != len {
+        return Err(PspError::RowLength { what, expected: len, found: row.len() });
+    }
+    Ok(row)
+}
+
+#[allow(dead_code)]
+fn write_row<'a, T: Display + 'a>(w: &mut impl Write, row: impl Iterator<Item = &'a T>) -> io::Result<()> {
+    let row = row.map(|x| x.to_string()).collect::<Vec<_>>();
+    writeln!(w, "{}", row.join(" "))
+}
+
+#[cfg(test)]
+mod test_psp {
+    use std::{fs::File, io::BufReader};
+
+    use engineering::api::{BarrierParallelSolver, CutsetType, Fixed, Solver};
+
+    use super::{Psp, PspError, PspRanking, PspRelax};
+
+    const INSTANCE: &str = "3\n2\n2\n\n0 5\n3 0\n\n1 2\n\n0 1 0\n0 0 1\n\n\n17\n";
+
+    fn parse(text: &str) -> Psp {
+        Psp::try_from(BufReader::new(text.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn instance_without_metadata_has_no_suggested_width() {
+        let psp = parse(INSTANCE);
+        assert_eq!(Some(17), psp.optimum);
+        assert_eq!(None, psp.suggested_width);
+        assert_eq!(1, psp.width_factor(None));
+        assert_eq!(3, psp.width_factor(Some(3)));
+    }
+
+    #[test]
+    fn instance_with_metadata_suggests_a_width() {
+        let psp = parse(&format!("{}# width: 5\n", INSTANCE));
+        assert_eq!(Some(17), psp.optimum);
+        assert_eq!(Some(5), psp.suggested_width);
+        assert_eq!(5, psp.width_factor(None));
+        assert_eq!(3, psp.width_factor(Some(3)));
+    }
+
+    #[test]
+    fn any_number_of_blank_lines_may_separate_the_sections() {
+        let psp = parse(INSTANCE);
+        for text in ["3\n2\n2\n0 5\n3 0\n1 2\n0 1 0\n0 0 1\n17\n", "\n3\n2\n2\n\n\n\n0 5\n3 0\n\n\n1 2\n\n\n\n0 1 0\n  \n0 0 1\n17\n"] {
+            let spaced = parse(text);
+            assert_eq!(psp.changeover_cost, spaced.changeover_cost);
+            assert_eq!(psp.stocking_cost, spaced.stocking_cost);
+            assert_eq!(psp.prev_demand, spaced.prev_demand);
+            assert_eq!(psp.rem_demand, spaced.rem_demand);
+            assert_eq!(Some(17), spaced.optimum);
+        }
+    }
+
+    #[test]
+    fn a_row_of_the_wrong_length_is_an_error() {
+        let text = "3\n2\n2\n\n0 5\n3 0\n\n1 2\n\n0 1 0 1\n0 0 1\n";
+        let error = Psp::try_from(BufReader::new(text.as_bytes())).unwrap_err();
+        assert!(matches!(error, PspError::RowLength { what: "demands", expected: 3, found: 4 }));
+        let error = Psp::try_from(BufReader::new("3\n2\n2\n\n0 5\n3 0\n\n1 2\n\n0 1 0\n".as_bytes())).unwrap_err();
+        assert!(matches!(error, PspError::Missing("demands")));
+    }
+
+    /// Writes the instance and parses it back
+    fn round_trip(psp: &Psp) -> Psp {
+        let mut text = vec![];
+        psp.write(&mut text).unwrap();
+        Psp::try_from(BufReader::new(text.as_slice())).unwrap()
+    }
+
+    fn assert_same_instance(expected: &Psp, actual: &Psp) {
+        assert_eq!((expected.nb_periods, expected.nb_items, expected.nb_orders), (actual.nb_periods, actual.nb_items, actual.nb_orders));
+        assert_eq!(expected.changeover_cost, actual.changeover_cost);
+        assert_eq!(expected.stocking_cost, actual.stocking_cost);
+        assert_eq!(expected.demands, actual.demands);
+        assert_eq!(expected.prev_demand, actual.prev_demand);
+        assert_eq!(expected.rem_demand, actual.rem_demand);
+        assert_eq!(expected.mst, actual.mst);
+        assert_eq!((expected.optimum, expected.suggested_width, expected.leftover_cost), (actual.optimum, actual.suggested_width, actual.leftover_cost));
+    }
+
+    #[test]
+    fn a_written_instance_is_parsed_back_as_is() {
+        let psp = Psp::try_from(File::open("resources/psp/psp_100_10_100_100_0").unwrap()).unwrap();
+        assert_same_instance(&psp, &round_trip(&psp));
+
+        let psp = parse(&format!("{}# width: 5\n# leftover: 4\n", INSTANCE));
+        assert_same_instance(&psp, &round_trip(&psp));
+        let mut text = vec![];
+        psp.write(&mut text).unwrap();
+        assert_eq!(format!("{}# width: 5\n# leftover: 4\n", INSTANCE.replace("\n\n\n", "\n\n")), String::from_utf8(text).unwrap());
+    }
+
+    #[test]
+    fn the_pruning_counters_of_a_single_threaded_resolution_are_deterministic() {
+        let psp = Psp::try_from(File::open("resources/psp/psp_50_5_100_100_0").unwrap()).unwrap();
+        let counters = || {
+            let mut solver = BarrierParallelSolver::custom(&psp, &PspRelax, &PspRanking, &Fixed(1), CutsetType::LastExactLayer, 1);
+            solver.maximize();
+            assert_eq!(Some(-269340), solver.best_value());
+            (solver.get_pruned_by_barrier(), solver.get_pruned_by_rub(), solver.get_skipped_by_barrier())
+        };
+        assert_eq!((2517, 194, 22), counters());
+        assert_eq!((2517, 194, 22), counters());
+    }
+
+    #[test]
+    fn unknown_metadata_is_ignored() {
+        let psp = parse(&format!("{}# family: 100_10\n", INSTANCE));
+        assert_eq!(None, psp.suggested_width);
+    }
 }
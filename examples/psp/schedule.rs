@@ -0,0 +1,95 @@
+//! This module writes the production plans found by the solver as csv files.
+
+use std::io::{self, Write};
+
+use engineering::api::Decision;
+use engineering::xputils::SolutionFormatter;
+
+use crate::psp::Psp;
+
+/// Writes one row per period giving the produced item (numbered from 0, in
+/// the order of the instance file) or IDLE, along with the costs incurred
+/// in that period.
+#[derive(Debug, Clone, Copy)]
+pub struct ScheduleCsv<'a>(pub &'a Psp);
+
+impl SolutionFormatter for ScheduleCsv<'_> {
+    fn write_solution(&self, solution: &[Decision], out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "period,item,stocking_cost,changeover_cost")?;
+        for period in self.0.trace(solution) {
+            let item = period.item.map_or_else(|| "IDLE".to_owned(), |i| i.to_string());
+            writeln!(out, "{},{},{},{}", period.period, item, period.stocking, period.changeover)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_schedule {
+    use std::{io::BufReader, time::Duration};
+
+    use engineering::api::{CutsetType, Problem};
+    use engineering::xputils::{solve, SolutionFormatter, SolverType};
+
+    use crate::psp::{Psp, PspRanking, PspRelax, PspWidth};
+
+    use super::ScheduleCsv;
+
+    #[test]
+    fn the_optimal_schedule_is_written_with_its_costs() {
+        // item 0 is due in period 1 and item 1 in period 2; switching from
+        // item 0 to item 1 costs 5
+        let text = "3\n2\n2\n\n0 5\n3 0\n\n1 2\n\n0 1 0\n0 0 1\n\n5\n";
+        let model = Psp::try_from(BufReader::new(text.as_bytes())).unwrap();
+        let width = PspWidth::new(model.nb_variables(), 1);
+        let report = solve("tiny", Duration::from_secs(10), &width, &model, &PspRelax, &PspRanking,
+            Some(1), SolverType::Parallel, CutsetType::LastExactLayer, false);
+        assert_eq!(Some(-5), report.best_value);
+
+        let solution = report.best_solution.unwrap();
+        assert_eq!(Ok(-5), model.validate_solution(&solution));
+        let total = model.trace(&solution).iter().map(|p| p.stocking + p.changeover).sum::<usize>();
+        assert_eq!(5, total);
+        assert_eq!(
+            "period,item,stocking_cost,changeover_cost\n0,IDLE,0,0\n1,0,0,0\n2,1,0,5\n",
+            ScheduleCsv(&model).format_solution(&solution)
+        );
+    }
+
+    #[test]
+    fn the_orders_served_from_the_leftover_inventory_shift_the_optimum_by_their_cost() {
+        // item 0 is due in periods 0 and 1 and item 1 in period 2: the best
+        // plan produces them in that order and costs a changeover of 5
+        let text = "3\n2\n3\n\n0 5\n3 0\n\n1 2\n\n1 1 0\n0 0 1\n\n5\n";
+        let model = Psp::try_from(BufReader::new(text.as_bytes())).unwrap();
+        let width = PspWidth::new(model.nb_variables(), 1);
+        let report = solve("tiny", Duration::from_secs(10), &width, &model, &PspRelax, &PspRanking,
+            Some(1), SolverType::Barrier, CutsetType::LastExactLayer, false);
+        assert_eq!(Some(-5), report.best_value);
+        let expected = ScheduleCsv(&model).format_solution(&report.best_solution.unwrap());
+
+        // one more order of item 1 in period 0 does not fit in the horizon:
+        // it is served from the leftover inventory, and the plan is the same
+        let text = "3\n2\n4\n\n0 5\n3 0\n\n1 2\n\n1 1 0\n1 0 1\n\n11\n# leftover: 6\n";
+        let model = Psp::try_from(BufReader::new(text.as_bytes())).unwrap();
+        assert_eq!(Some(6), model.leftover_cost);
+        let width = PspWidth::new(model.nb_variables(), 1);
+        let report = solve("tiny", Duration::from_secs(10), &width, &model, &PspRelax, &PspRanking,
+            Some(1), SolverType::Barrier, CutsetType::LastExactLayer, false);
+        assert_eq!(Some(-11), report.best_value);
+
+        let solution = report.best_solution.unwrap();
+        assert_eq!(Ok(-11), model.validate_solution(&solution));
+        assert_eq!(expected, ScheduleCsv(&model).format_solution(&solution));
+    }
+
+    #[test]
+    fn without_the_leftover_inventory_the_orders_which_do_not_fit_are_infeasible() {
+        let text = "3\n2\n4\n\n0 5\n3 0\n\n1 2\n\n1 1 0\n1 0 1\n";
+        let model = Psp::try_from(BufReader::new(text.as_bytes())).unwrap();
+        let width = PspWidth::new(model.nb_variables(), 1);
+        let report = solve("tiny", Duration::from_secs(10), &width, &model, &PspRelax, &PspRanking,
+            Some(1), SolverType::Barrier, CutsetType::LastExactLayer, false);
+        assert_eq!(None, report.best_value);
+    }
+}
@@ -37,14 +37,12 @@ impl<T: Default + Clone> Matrix<T> {
     #[allow(dead_code)]
     pub fn row(&self, i: usize) -> impl Iterator<Item = &T> {
         let start = self.pos((i, 0));
-        let end = self.pos((i, self.m));
-        self.data[start..end].iter()
+        self.data[start..start + self.m].iter()
     }
     #[allow(dead_code)]
     pub fn row_mut(&mut self, i: usize) -> impl Iterator<Item = &mut T> {
         let start = self.pos((i, 0));
-        let end = self.pos((i, self.m));
-        self.data[start..end].iter_mut()
+        self.data[start..start + self.m].iter_mut()
     }
     pub fn col(&self, i: usize) -> impl Iterator<Item = &T> {
         (0..self.n).map(move |r| &self.data[self.pos((r, i))])
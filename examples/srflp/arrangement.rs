@@ -0,0 +1,77 @@
+//! This module writes the arrangements found by the solver.
+
+use std::io::{self, Write};
+
+use engineering::api::Decision;
+use engineering::xputils::SolutionFormatter;
+
+/// Writes the departments from left to right on a single line. The
+/// departments are numbered from 0, in the order of the instance file.
+#[derive(Debug, Clone, Copy)]
+pub struct Arrangement;
+
+impl SolutionFormatter for Arrangement {
+    fn write_solution(&self, solution: &[Decision], out: &mut dyn Write) -> io::Result<()> {
+        // the i-th decision tells which department is placed in i-th position
+        let departments = solution.iter().map(|d| d.value.to_string()).collect::<Vec<_>>();
+        writeln!(out, "{}", departments.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod test_arrangement {
+    use std::{collections::HashSet, io::BufReader, time::Duration};
+
+    use engineering::api::{CutsetType, Decision, Problem, Variable};
+    use engineering::{xputils::{solve, SolutionFormatter, SolverType}, ExactSolver};
+
+    use crate::{heuristics::{SrflpRanking, SrflpWidth}, instance::SrflpInstance, model::Srflp, relax::SrflpRelax};
+
+    use super::Arrangement;
+
+    #[test]
+    fn the_arrangement_lists_the_departments_from_left_to_right() {
+        let solution = [2, 0, 1].iter().enumerate()
+            .map(|(i, v)| Decision { var: Variable(i), value: *v })
+            .collect::<Vec<_>>();
+        assert_eq!("2 0 1\n", Arrangement.format_solution(&solution));
+    }
+
+    #[test]
+    fn the_optimal_arrangement_is_written() {
+        // 0 and 2 must be neighbours, and so must be 2 and 1. Then, no
+        // department stands between two departments having some flow (the
+        // model leaves out the half lengths, which are the same for all)
+        let text = "3\n1 1 1\n0 0 5\n0 0 1\n5 1 0\n";
+        let model = Srflp::new(SrflpInstance::from(BufReader::new(text.as_bytes())));
+        let relax = SrflpRelax::new(&model);
+        let width = SrflpWidth::new(model.nb_variables(), 1);
+        let report = solve("tiny", Duration::from_secs(10), &width, &model, &relax, &SrflpRanking,
+            Some(1), SolverType::Parallel, CutsetType::LastExactLayer, false);
+        assert_eq!(Some(0), report.best_value);
+
+        // an arrangement is just as good as its mirror
+        let written = Arrangement.format_solution(report.best_solution.as_ref().unwrap());
+        assert!(written == "0 2 1\n" || written == "1 2 0\n", "unexpected arrangement {:?}", written);
+    }
+
+    #[test]
+    fn the_exact_solver_enumerates_every_optimal_arrangement() {
+        // only 0 and 1 exchange some flow: they must be neighbours, which they
+        // are in 3 positions, in 2 orders, with 2 orders of the others
+        let text = "4\n1 1 1 1\n0 1 0 0\n1 0 0 0\n0 0 0 0\n0 0 0 0\n";
+        let model = Srflp::new(SrflpInstance::from(BufReader::new(text.as_bytes())));
+        let relax = SrflpRelax::new(&model);
+        let mut solver = ExactSolver::new(&model, &relax, &SrflpRanking);
+        solver.compute();
+        assert_eq!(Some(0), solver.best_value());
+        let arrangements = solver.best_solutions().iter().map(|sol| Arrangement.format_solution(sol)).collect::<HashSet<_>>();
+        assert_eq!(12, arrangements.len());
+        assert!(arrangements.contains("2 0 1 3\n") && !arrangements.contains("0 2 1 3\n"));
+
+        let width = SrflpWidth::new(model.nb_variables(), 1);
+        let report = solve("tiny", Duration::from_secs(10), &width, &model, &relax, &SrflpRanking,
+            Some(1), SolverType::Exact, CutsetType::LastExactLayer, false);
+        assert_eq!((Some(0), Some(12)), (report.best_value, report.optimal_solutions));
+    }
+}
@@ -1,4 +1,4 @@
-use engineering::{StateRanking, WidthHeuristic};
+use engineering::api::{StateRanking, WidthHeuristic};
 
 use crate::state::State;
 
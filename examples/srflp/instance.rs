@@ -34,6 +34,17 @@ pub struct SrflpInstance {
     pub lengths       : Vec<isize>,
     /// This is the flow matrix between any two departments
     pub flows         : Matrix<isize>,
+    /// The width factor recommended for this instance (if any). It is read
+    /// from the optional metadata lines (e.g. '# width: 5') of the file
+    pub suggested_width: Option<usize>,
+}
+
+impl SrflpInstance {
+    /// Returns the width factor to use: the one given on the command line if
+    /// any, otherwise the one suggested by the instance file (defaults to 1).
+    pub fn width_factor(&self, width: Option<usize>) -> usize {
+        width.or(self.suggested_width).unwrap_or(1)
+    }
 }
 
 impl From<File> for SrflpInstance {
@@ -51,7 +62,8 @@ impl <B: BufRead> From<Lines<B>> for SrflpInstance {
         let mut lc = 0;
         let mut nb_departments = 0;
         let mut lengths = vec![];
-        let mut flows = Matrix::new_default(nb_departments as usize, nb_departments as usize, 0);
+        let mut flows = Matrix::new_default(nb_departments, nb_departments, 0);
+        let mut suggested_width = None;
 
         for line in lines {
             let line = line.unwrap();
@@ -61,11 +73,21 @@ impl <B: BufRead> From<Lines<B>> for SrflpInstance {
             if line.is_empty() {
                 continue;
             }
+
+            // metadata lines
+            if let Some(meta) = line.strip_prefix('#') {
+                if let Some((key, value)) = meta.split_once(':') {
+                    if key.trim() == "width" {
+                        suggested_width = Some(value.trim().parse::<usize>().unwrap());
+                    }
+                }
+                continue;
+            }
             
            // First line is the number of nodes
             if lc == 0 { 
-                nb_departments  = line.split(&[' ',',','\t']).filter(|s| !s.is_empty()).next().unwrap().to_string().parse::<usize>().unwrap();
-                flows = Matrix::new_default(nb_departments as usize, nb_departments as usize, 0);
+                nb_departments  = line.split(&[' ',',','\t']).find(|s| !s.is_empty()).unwrap().to_string().parse::<usize>().unwrap();
+                flows = Matrix::new_default(nb_departments, nb_departments, 0);
             } 
             // Second line contains the lengths
             else if lc == 1 {
@@ -76,7 +98,7 @@ impl <B: BufRead> From<Lines<B>> for SrflpInstance {
             }
             // The next 'nb_nodes' lines represent the distances matrix
             else if (2..=(nb_departments+1)).contains(&lc) {
-                let i = (lc - 2) as usize;
+                let i = lc - 2;
                 for (j, flow) in line.split(&[' ',',','\t']).filter(|s| !s.is_empty()).enumerate() {
                     let flow = flow.to_string().parse::<isize>().unwrap();
                     flows[(i, j)] = flow;
@@ -87,8 +109,8 @@ impl <B: BufRead> From<Lines<B>> for SrflpInstance {
         }
 
         // handle asymmetrical flows
-        for i in 0..(nb_departments as usize) {
-            for j in (i+1)..(nb_departments as usize) {
+        for i in 0..nb_departments {
+            for j in (i+1)..nb_departments {
                 if flows[(i, j)] != flows[(j, i)] {
                     flows[(i, j)] += flows[(j, i)];
                     flows[(j, i)] = flows[(i, j)];
@@ -96,6 +118,38 @@ impl <B: BufRead> From<Lines<B>> for SrflpInstance {
             }
         }
 
-        SrflpInstance{nb_departments, lengths, flows}
+        SrflpInstance{nb_departments, lengths, flows, suggested_width}
+    }
+}
+
+#[cfg(test)]
+mod test_instance {
+    use std::io::BufReader;
+
+    use super::SrflpInstance;
+
+    const INSTANCE: &str = "3\n1,2,3\n0,1,2\n1,0,3\n2,3,0\n";
+
+    fn parse(text: &str) -> SrflpInstance {
+        SrflpInstance::from(BufReader::new(text.as_bytes()))
+    }
+
+    #[test]
+    fn instance_without_metadata_has_no_suggested_width() {
+        let instance = parse(INSTANCE);
+        assert_eq!(vec![1, 2, 3], instance.lengths);
+        assert_eq!(None, instance.suggested_width);
+        assert_eq!(1, instance.width_factor(None));
+        assert_eq!(3, instance.width_factor(Some(3)));
+    }
+
+    #[test]
+    fn instance_with_metadata_suggests_a_width() {
+        let instance = parse(&format!("{}# width: 5\n", INSTANCE));
+        assert_eq!(vec![1, 2, 3], instance.lengths);
+        assert_eq!(3, instance.flows[(2, 1)]);
+        assert_eq!(Some(5), instance.suggested_width);
+        assert_eq!(5, instance.width_factor(None));
+        assert_eq!(3, instance.width_factor(Some(3)));
     }
 }
@@ -1,14 +1,20 @@
-use std::{fs::File, path::Path, time::Duration};
+use std::{fs::File, path::Path, process::exit, time::Duration};
 
+use engineering::api::{FringePolicy, CutsetType, Problem, Relaxation};
 use engineering::{
-    xputils::{solve_timeout, Args, SolverType, resolution_header}, Problem, CutsetType,
+    xputils::{solve, solve_custom, write_decisions_file, write_solution_file, Args, OrderType, SolveReport, SolverType, resolution_header}, bench::{bench_file, instance_name}, WarmStartBudget, MergePolicy, LayeredRelaxation,
 };
+#[cfg(feature = "checkpoint")]
+use engineering::xputils::solve_with_barrier_files;
+use arrangement::Arrangement;
 use heuristics::{SrflpRanking, SrflpWidth};
 use instance::SrflpInstance;
 use model::Srflp;
-use relax::SrflpRelax;
+use relax::{SrflpCoarseRelax, SrflpRelax};
+use state::State;
 use structopt::StructOpt;
 
+mod arrangement;
 mod heuristics;
 mod instance;
 mod model;
@@ -26,11 +32,27 @@ fn main() {
             threads,
             solver,
             cutset,
-        } => run_resolution_xp(file, width, timeout, threads, solver, cutset),
+            solution_out,
+            output,
+            verbose,
+            coarse_depth,
+            order,
+            fringe_policy,
+            max_nodes,
+            warm_start,
+            merge,
+            barrier_in,
+            barrier_out,
+            trace,
+            self_check,
+            ..
+        } => run_resolution_xp(file, width, timeout, threads, solver, cutset, solution_out, output, verbose, coarse_depth, order, fringe_policy, max_nodes, warm_start, merge, (barrier_in, barrier_out), trace, self_check),
+        Args::Bench { experiment, output } => run_benchmark_xp(experiment, output),
         Args::PrintHeader => resolution_header()
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_resolution_xp(
     file: String,
     width: Option<usize>,
@@ -38,6 +60,18 @@ fn run_resolution_xp(
     threads: Option<usize>,
     solver: SolverType,
     cutset: CutsetType,
+    solution_out: Option<String>,
+    output: Option<String>,
+    verbose: bool,
+    coarse_depth: Option<usize>,
+    order: OrderType,
+    fringe_policy: FringePolicy,
+    max_nodes: Option<usize>,
+    warm_start: Option<WarmStartBudget>,
+    merge: MergePolicy,
+    barrier_files: (Option<String>, Option<String>),
+    trace: Option<String>,
+    self_check: bool,
 ) {
     let afile = Box::new(file);
     let afile = Box::leak(afile);
@@ -47,17 +81,139 @@ fn run_resolution_xp(
         .map(|s| s.to_str().unwrap_or("-- unknown --"))
         .unwrap_or("-- unknown --");
     let file = File::open(path).unwrap();
-    let instance = SrflpInstance::try_from(file).unwrap();
+    let instance = SrflpInstance::from(file);
+    let width_factor = instance.width_factor(width);
     let model = Srflp::new(instance);
     let relax = SrflpRelax::new(&model);
-    let ranking = SrflpRanking;
-    let width = SrflpWidth::new(model.nb_variables(), width.unwrap_or(1));
+    let width = SrflpWidth::new(model.nb_variables(), width_factor);
 
     let name = Box::new(name);
     let name: &'static str = Box::leak(name);
     let timeout = Duration::from_secs(timeout as u64);
     
-    let _ub = solve_timeout::<Srflp, SrflpRelax, SrflpRanking, SrflpWidth>(name, timeout, &width, &model, &relax, &ranking, threads, solver, cutset) as f64;
+    let report = match coarse_depth {
+        Some(depth) => {
+            let relax = LayeredRelaxation::new(SrflpCoarseRelax::new(&model), relax, depth);
+            solve_srflp(name, timeout, &width, &model, &relax, threads, solver, cutset, verbose, self_check, order, fringe_policy, max_nodes, warm_start, merge, &barrier_files, trace.as_deref())
+        }
+        None => solve_srflp(name, timeout, &width, &model, &relax, threads, solver, cutset, verbose, self_check, order, fringe_policy, max_nodes, warm_start, merge, &barrier_files, trace.as_deref()),
+    };
+    println!("{}", report);
+    if let Some(error) = report.verification_error.as_ref() {
+        eprintln!("{}", error);
+    }
+    for violation in &report.violations {
+        eprintln!("warning: {}", violation);
+    }
+    if let Some(count) = report.optimal_solutions {
+        println!("optimal solutions: {}", count);
+    }
+    if let Some(split) = report.time_split().filter(|_| verbose) {
+        println!("{}", split);
+    }
+    if let Some(peak) = report.peak_attribution.filter(|_| verbose) {
+        println!("{}", peak);
+    }
+    if let Some(summary) = report.barrier_summary().filter(|_| verbose) {
+        println!("{}", summary);
+    }
+
+    // println!("solution with root value: {}", model.root_value() + report.lb);
+
+    if let Some(path) = solution_out {
+        if let Err(error) = write_solution_file(&Arrangement, &report, &path) {
+            eprintln!("cannot write the solution to {}: {}", path, error);
+            exit(1);
+        }
+    }
+    if let Some(path) = output {
+        write_decisions_file(&report, &path).unwrap();
+    }
+}
+
+/// Solves the problem like `solve_custom`, unless the thresholds of the
+/// barrier are read from (or written to) the given files: the barrier solver
+/// then persists them (which needs the checkpoint feature)
+#[allow(clippy::too_many_arguments)]
+fn solve_srflp<R: Relaxation<State = State> + Send + Sync>(
+    name: &str,
+    timeout: Duration,
+    width: &SrflpWidth,
+    model: &Srflp,
+    relax: &R,
+    threads: Option<usize>,
+    solver: SolverType,
+    cutset: CutsetType,
+    verbose: bool,
+    self_check: bool,
+    order: OrderType,
+    fringe_policy: FringePolicy,
+    max_nodes: Option<usize>,
+    warm_start: Option<WarmStartBudget>,
+    merge: MergePolicy,
+    barrier_files: &(Option<String>, Option<String>),
+    trace: Option<&str>,
+) -> SolveReport {
+    let (barrier_in, barrier_out) = barrier_files;
+    if barrier_in.is_none() && barrier_out.is_none() {
+        return solve_custom(name, timeout, width, model, relax, &SrflpRanking, threads, solver, cutset, verbose, self_check, None, None, order, fringe_policy, max_nodes, warm_start, merge, trace);
+    }
+    if solver != SolverType::Barrier {
+        eprintln!("the barrier files are only supported by the barrier solver");
+        exit(1);
+    }
+    #[cfg(feature = "checkpoint")]
+    {
+        solve_with_barrier_files(name, timeout, width, model, relax, &SrflpRanking, threads, cutset, verbose, self_check, None, None, order, fringe_policy, max_nodes, warm_start, merge,
+            barrier_in.as_deref(), barrier_out.as_deref(), trace).unwrap()
+    }
+    #[cfg(not(feature = "checkpoint"))]
+    {
+        eprintln!("the barrier files need the checkpoint feature");
+        exit(1);
+    }
+}
+
+fn run_benchmark_xp(experiment: String, output: String) {
+    bench_file(&experiment, &output, |file, config, timeout| {
+        let instance = SrflpInstance::from(File::open(file).unwrap());
+        let model = Srflp::new(instance);
+        let relax = SrflpRelax::new(&model);
+        let width = SrflpWidth::new(model.nb_variables(), config.width);
+        solve(&instance_name(file), timeout, &width, &model, &relax, &SrflpRanking, config.threads, config.solver, config.cutset, false)
+    });
+}
+
+#[cfg(all(test, feature = "checkpoint"))]
+mod test_checkpoint {
+    use std::fs::File;
 
-    // println!("solution with root value: {}", model.root_value() - ub);
-}
\ No newline at end of file
+    use engineering::api::{BarrierParallelSolver, CutsetType, InterruptibleSolver, NodeCutoff, Problem, ResolutionStatus, Solver};
+
+    use crate::{heuristics::{SrflpRanking, SrflpWidth}, instance::SrflpInstance, model::Srflp, relax::SrflpRelax};
+
+    #[test]
+    fn a_checkpointed_resolution_resumes_to_the_same_optimum() {
+        let instance = SrflpInstance::from(File::open("resources/srflp/Cl12").unwrap());
+        let width_factor = instance.width_factor(None);
+        let model = Srflp::new(instance);
+        let relax = SrflpRelax::new(&model);
+        let width = SrflpWidth::new(model.nb_variables(), width_factor);
+
+        let mut direct = BarrierParallelSolver::new(&model, &relax, &SrflpRanking, &width, CutsetType::LastExactLayer);
+        direct.minimize();
+
+        let mut solver = BarrierParallelSolver::new(&model, &relax, &SrflpRanking, &width, CutsetType::LastExactLayer).with_suspension(true);
+        assert_eq!(ResolutionStatus::Interrupted, solver.minimize_with_cutoff(NodeCutoff(20)));
+        let path = std::env::temp_dir().join(format!("srflp-checkpoint-{}.bin", std::process::id()));
+        solver.checkpoint(&path).unwrap();
+        drop(solver);
+
+        let resumed = BarrierParallelSolver::resume_from(&path, &model, &relax, &SrflpRanking, &width, CutsetType::LastExactLayer);
+        std::fs::remove_file(&path).unwrap();
+        let mut resumed = resumed.unwrap();
+        resumed.minimize();
+        assert_eq!(Some(11400), direct.best_value());
+        assert_eq!(direct.best_value(), resumed.best_value());
+    }
+}
@@ -23,7 +23,8 @@
 use std::{ops::Not, cmp::Reverse, vec};
 
 use bitset_fixed::BitSet;
-use engineering::{BitSetIter, Problem, Decision, Variable};
+use engineering::api::{AssignedVars, Problem, Decision, Objective, Variable};
+use engineering::BitSetIter;
 use ordered_float::OrderedFloat;
 
 use crate::{instance::SrflpInstance, state::State};
@@ -44,18 +45,92 @@ impl Srflp {
         let mut sorted_flows = vec![];
         for i in 0..inst.nb_departments {
             for j in (i+1)..inst.nb_departments {
-                sorted_flows.push((inst.flows[(i as usize, j as usize)], i as usize, j as usize));
+                sorted_flows.push((inst.flows[(i, j)], i, j));
             }
         }
         sorted_flows.sort_unstable();
 
         let state = State {
-            must_place: BitSet::new(inst.nb_departments as usize).not(),
+            must_place: BitSet::new(inst.nb_departments).not(),
             maybe_place: None,
-            cut: vec![0; inst.nb_departments as usize],
+            cut: vec![0; inst.nb_departments],
             depth : 0
         };
-        Self { instance: inst, sorted_lengths: sorted_lengths, sorted_flows: sorted_flows, initial: state }
+        Self { instance: inst, sorted_lengths, sorted_flows, initial: state }
+    }
+
+    /// Returns the flows between the department `d` and each department
+    fn flows_from(&self, d: usize) -> &[isize] {
+        let n = self.instance.flows.m;
+        &self.instance.flows.data[d * n..(d + 1) * n]
+    }
+
+    /// Places the department `d` after the given state. The cut vector is
+    /// rebuilt in a single pass, 64 departments at a time: the flows from `d`
+    /// are added under the mask of the departments which are left to place,
+    /// so that the cuts of those already placed (which are dead) are kept
+    /// as they are. `visit` is shown each chunk of the former cut, along with
+    /// the words of the departments which must (resp. maybe) be placed next.
+    fn place<F>(&self, state: &State, d: usize, mut visit: F) -> State
+    where
+        F: FnMut(&[isize], u64, u64),
+    {
+        let mut must_place = state.must_place.clone();
+        must_place.set(d, false);
+        let mut maybe_place = state.maybe_place.clone();
+        if let Some(maybe) = maybe_place.as_mut() {
+            maybe.set(d, false);
+        }
+
+        let must = must_place.buffer();
+        let maybe = maybe_place.as_ref().map(|maybe| maybe.buffer());
+        let mut cut = Vec::with_capacity(state.cut.len());
+        for (w, (cuts, flows)) in state.cut.chunks(64).zip(self.flows_from(d).chunks(64)).enumerate() {
+            let (must, maybe) = (must[w], maybe.map_or(0, |maybe| maybe[w]));
+            let alive = must | maybe;
+            cut.extend(cuts.iter().zip(flows).enumerate().map(|(j, (cut, flow))| cut + (flow & -(((alive >> j) & 1) as isize))));
+            visit(cuts, must, maybe);
+        }
+        cut[d] = 0;
+
+        State { must_place, maybe_place, cut, depth: state.depth + 1 }
+    }
+
+    /// Returns the cost of placing `d` after the given state, knowing what
+    /// the departments left to place once it is contribute to it
+    fn placement_cost(&self, state: &State, d: usize, mut tally: Tally) -> isize {
+        let complete_arrangement = self.instance.nb_departments - (state.depth + 1) - tally.nb_must;
+        let mut cut = tally.must_cut;
+        if complete_arrangement > 0 {
+            tally.maybe_cuts.sort_unstable();
+            cut += tally.maybe_cuts.iter().take(complete_arrangement).sum::<isize>();
+        }
+        cut * self.instance.lengths[d]
+    }
+}
+
+/// What the departments left to place contribute to the cost of a placement:
+/// the cuts of those which must be placed all count, while only the smallest
+/// cuts of those which maybe must be placed do
+#[derive(Default)]
+struct Tally {
+    must_cut: isize,
+    nb_must: usize,
+    maybe_cuts: Vec<isize>,
+}
+impl Tally {
+    /// Accounts for a chunk of (at most 64) cuts, given the words which tell
+    /// whether their departments must (resp. maybe) be placed
+    fn add(&mut self, cuts: &[isize], mut must: u64, mut maybe: u64) {
+        while must != 0 {
+            self.must_cut += cuts[must.trailing_zeros() as usize];
+            self.nb_must += 1;
+            must &= must - 1;
+        }
+        while maybe != 0 {
+            self.maybe_cuts.push(cuts[maybe.trailing_zeros() as usize]);
+            maybe &= maybe - 1;
+        }
     }
 }
 
@@ -63,22 +138,31 @@ impl Problem for Srflp {
     type State = State;
 
     fn nb_variables(&self) -> usize {
-        self.instance.nb_departments as usize
+        self.instance.nb_departments
     }
 
     fn initial_state(&self) -> State {
         self.initial.clone()
     }
 
+    fn objective(&self) -> Objective {
+        Objective::Minimize
+    }
+
     fn initial_value(&self) -> isize {
         0
     }
 
+    fn domain_size_hint(&self, _var: Variable, state: &Self::State) -> Option<usize> {
+        let maybe = state.maybe_place.as_ref().map_or(0, |maybe| maybe.count_ones());
+        Some((state.must_place.count_ones() + maybe) as usize)
+    }
+
     fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, mut f: F)
     where
         F: FnMut(Decision),
     {
-        let mut complete_arrangement = self.nb_variables() - state.depth as usize;
+        let mut complete_arrangement = self.nb_variables() - state.depth;
 
         for i in BitSetIter::new(&state.must_place) {
             complete_arrangement -= 1;
@@ -95,74 +179,34 @@ impl Problem for Srflp {
     }
 
     fn transition(&self, state: &State, d: Decision) -> State {
-        let d = d.value as usize;
-
-        // if it is a true move
-        let mut remaining = state.must_place.clone();
-        remaining.set(d, false);
-        // if it is a possible move
-        let mut maybes = state.maybe_place.clone();
-        if let Some(maybe) = maybes.as_mut() {
-            maybe.set(d, false);
-        }
-
-        let mut cut = state.cut.clone();
-        cut[d] = 0;
-
-        for i in BitSetIter::new(&remaining) {
-            cut[i] += self.instance.flows[(d, i)];
-        }
-
-        if let Some(maybe) = maybes.as_ref() {
-            for i in BitSetIter::new(&maybe) {
-                cut[i] += self.instance.flows[(d, i)];
-            }
-        }
-
-        State {
-            must_place: remaining,
-            maybe_place: maybes,
-            cut: cut,
-            depth: state.depth + 1
-        }
+        self.place(state, d.value as usize, |_, _, _| ())
     }
 
     fn transition_cost(&self, state: &State, d: Decision) -> isize {
         let d = d.value as usize;
+        let must = state.must_place.buffer();
+        let maybe = state.maybe_place.as_ref().map(|maybe| maybe.buffer());
 
-        let mut cut = 0;
-        let mut complete_arrangement = (self.instance.nb_departments - (state.depth + 1)) as usize;
-
-        for i in BitSetIter::new(&state.must_place) {
-            if i != d {
-                cut += state.cut[i];
-                complete_arrangement -= 1;
-            }
-        }
-
-        if complete_arrangement > 0 {
-            if let Some(maybe) = state.maybe_place.as_ref() {
-                let mut temp = vec![];
-                for i in BitSetIter::new(&maybe) {
-                    if i != d {
-                        temp.push(state.cut[i]);
-                    }
-                }
-                temp.sort_unstable();
-                cut += temp.iter().take(complete_arrangement).sum::<isize>();
-            }
+        let mut tally = Tally::default();
+        for (w, cuts) in state.cut.chunks(64).enumerate() {
+            let except_d = if w == d / 64 { !(1 << (d % 64)) } else { !0 };
+            tally.add(cuts, must[w] & except_d, maybe.map_or(0, |maybe| maybe[w]) & except_d);
         }
+        self.placement_cost(state, d, tally)
+    }
 
-        // Srflp is a minimization problem but the solver works with a 
-        // maximization perspective. So we have to negate the cost.
-        - cut * self.instance.lengths[d]
+    fn transition_with_cost(&self, state: &State, d: Decision) -> (State, isize) {
+        let d = d.value as usize;
+        let mut tally = Tally::default();
+        let next = self.place(state, d, |cuts, must, maybe| tally.add(cuts, must, maybe));
+        (next, self.placement_cost(state, d, tally))
     }
 
     fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>)
         -> Option<Variable> {
         let state = next_layer.next();
         if let Some(s) = state {
-            let depth = s.depth as usize;
+            let depth = s.depth;
             if depth == self.nb_variables() {
                 None
             } else {
@@ -173,9 +217,17 @@ impl Problem for Srflp {
         }
     }
 
+    /// The departments are placed from left to right: the next position to
+    /// fill is the leftmost one that has not been assigned yet.
+    fn next_variable_with_context(&self, assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &Self::State>)
+        -> Option<Variable> {
+        next_layer.next()?;
+        (0..self.nb_variables()).map(Variable).find(|v| !assigned.contains(*v))
+    }
+
     fn estimate(&self, state: &State) -> isize {
-        let complete_arrangement = self.nb_variables() - state.depth as usize;
-        let n_flows = complete_arrangement * (complete_arrangement - 1) / 2;
+        let complete_arrangement = self.nb_variables() - state.depth;
+        let n_flows = complete_arrangement * complete_arrangement.saturating_sub(1) / 2;
         let n_must_place = state.must_place.count_ones() as usize;
         let n_from_maybe_place = complete_arrangement - n_must_place;
 
@@ -201,15 +253,13 @@ impl Problem for Srflp {
         }
 
         let mut n_flows_from_must_to_maybe_place = n_must_place * n_from_maybe_place;
-        let mut n_flows_in_maybe_place = n_from_maybe_place * (n_from_maybe_place - 1) / 2;
+        let mut n_flows_in_maybe_place = n_from_maybe_place * n_from_maybe_place.saturating_sub(1) / 2;
         for (f,i,j) in self.sorted_flows.iter() {
             if state.must_place[*i] && state.must_place[*j] {
                 flows.push(*f);
             } else if let Some(maybe) = state.maybe_place.as_ref() {
-                if state.must_place[*i] && maybe[*j] && n_flows_from_must_to_maybe_place > 0 {
-                    flows.push(*f);
-                    n_flows_from_must_to_maybe_place -= 1;
-                } else if maybe[*i] && state.must_place[*j] && n_flows_from_must_to_maybe_place > 0 {
+                if ((state.must_place[*i] && maybe[*j]) || (maybe[*i] && state.must_place[*j]))
+                    && n_flows_from_must_to_maybe_place > 0 {
                     flows.push(*f);
                     n_flows_from_must_to_maybe_place -= 1;
                 } else if maybe[*i] && maybe[*j] && n_flows_in_maybe_place > 0 {
@@ -255,16 +305,16 @@ impl Problem for Srflp {
         let mut edge_bound = 0;
         let mut idx = 0;
         cumul_length = 0;
-        for i in 0..(complete_arrangement-1) {
+        for (i, length) in lengths.iter().enumerate().take(complete_arrangement.saturating_sub(1)) {
             for _ in 0..(complete_arrangement-(i+1)) {
                 edge_bound += cumul_length * flows[n_flows - 1 - idx];
                 idx += 1;
             }
 
-            cumul_length += lengths[i];
+            cumul_length += length;
         }
 
-        - (cut_bound + edge_bound)
+        cut_bound + edge_bound
     }
 }
 
@@ -274,11 +324,167 @@ impl Srflp {
 
         for i in 0..self.instance.nb_departments {
             for j in (i+1)..self.instance.nb_departments {
-                value += 0.5 * ((self.instance.lengths[i as usize] + self.instance.lengths[j as usize])
-                             * self.instance.flows[(i as usize, j as usize)]) as f64;
+                value += 0.5 * ((self.instance.lengths[i] + self.instance.lengths[j])
+                             * self.instance.flows[(i, j)]) as f64;
             }
         }
 
         value
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test_model {
+    use std::{collections::hash_map::DefaultHasher, fs::File, hash::{Hash, Hasher}, io::BufReader, sync::{Arc, Mutex}};
+
+    use engineering::api::{BarrierParallelSolver, CutsetType, Decision, InterruptibleSolver, Problem, Relaxation, ResolutionStatus, Solver, Variable};
+    use engineering::{BitSetIter, MinimizeProblem};
+
+    use crate::{heuristics::{SrflpRanking, SrflpWidth}, instance::SrflpInstance, relax::SrflpRelax, state::State};
+
+    use super::Srflp;
+
+    #[test]
+    fn the_maximized_model_costs_as_much_as_the_minimized_one() {
+        let text = "6\n3 1 4 1 5 2\n0 2 0 5 1 3\n2 0 4 1 0 2\n0 4 0 3 2 0\n5 1 3 0 4 1\n1 0 2 4 0 6\n3 2 0 1 6 0\n";
+        let model = Srflp::new(SrflpInstance::from(BufReader::new(text.as_bytes())));
+        let width = SrflpWidth::new(model.nb_variables(), 1);
+
+        let relax = SrflpRelax::new(&model);
+        let mut solver = BarrierParallelSolver::custom(&model, &relax, &SrflpRanking, &width, CutsetType::LastExactLayer, 2);
+        solver.minimize();
+        let minimized = solver.best_value();
+
+        // the relaxation reaches the srflp through the wrapper
+        let wrapped = MinimizeProblem::new(model.clone());
+        let relax = SrflpRelax::new(&wrapped);
+        let mut solver = BarrierParallelSolver::custom(&wrapped, &relax, &SrflpRanking, &width, CutsetType::LastExactLayer, 2);
+        solver.maximize();
+        assert!(minimized.is_some());
+        assert_eq!(minimized, solver.best_value().map(MinimizeProblem::<Srflp>::actual_value));
+        let arrangement = solver.best_solution().unwrap();
+        assert_eq!(minimized.map(Ok), Some(model.validate_solution(&arrangement)));
+    }
+
+    /// The transition as it was before the cut vector was rebuilt in a single
+    /// pass: the property test checks that both agree
+    fn reference_transition(model: &Srflp, state: &State, d: usize) -> State {
+        let mut remaining = state.must_place.clone();
+        remaining.set(d, false);
+        let mut maybes = state.maybe_place.clone();
+        if let Some(maybe) = maybes.as_mut() {
+            maybe.set(d, false);
+        }
+        let mut cut = state.cut.clone();
+        cut[d] = 0;
+        for i in BitSetIter::new(&remaining) {
+            cut[i] += model.instance.flows[(d, i)];
+        }
+        if let Some(maybe) = maybes.as_ref() {
+            for i in BitSetIter::new(maybe) {
+                cut[i] += model.instance.flows[(d, i)];
+            }
+        }
+        State { must_place: remaining, maybe_place: maybes, cut, depth: state.depth + 1 }
+    }
+
+    /// The transition cost as it was before it shared its loop with the transition
+    fn reference_transition_cost(model: &Srflp, state: &State, d: usize) -> isize {
+        let mut cut = 0;
+        let mut complete_arrangement = model.instance.nb_departments - (state.depth + 1);
+        for i in BitSetIter::new(&state.must_place) {
+            if i != d {
+                cut += state.cut[i];
+                complete_arrangement -= 1;
+            }
+        }
+        if complete_arrangement > 0 {
+            if let Some(maybe) = state.maybe_place.as_ref() {
+                let mut temp = BitSetIter::new(maybe).filter(|i| *i != d).map(|i| state.cut[i]).collect::<Vec<_>>();
+                temp.sort_unstable();
+                cut += temp.iter().take(complete_arrangement).sum::<isize>();
+            }
+        }
+        cut * model.instance.lengths[d]
+    }
+
+    /// A tiny xorshift generator, so that the test is reproducible
+    fn rng(seed: &mut u64, bound: usize) -> usize {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        (*seed % bound as u64) as usize
+    }
+
+    fn hash(state: &State) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        state.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn the_single_pass_transition_matches_the_former_one() {
+        // more than 64 departments, so that the cuts span several words
+        let n = 70;
+        let lengths = (0..n).map(|i| (1 + i % 5).to_string()).collect::<Vec<_>>().join(" ");
+        let flows = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 0 } else { (i * j) % 7 }.to_string()).collect::<Vec<_>>().join(" "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let text = format!("{}\n{}\n{}\n", n, lengths, flows);
+        let wide = Srflp::new(SrflpInstance::from(BufReader::new(text.as_bytes())));
+        let sko49 = Srflp::new(SrflpInstance::from(File::open("resources/srflp/sko49_1").unwrap()));
+
+        let mut seed = 0x5eed;
+        for model in [&wide, &sko49] {
+            let relax = SrflpRelax::new(model);
+            for _ in 0..10 {
+                // two arrangements are built side by side, and merged from time
+                // to time so that some departments maybe must be placed
+                let mut states = [model.initial_state(), model.initial_state()];
+                while states[0].depth < model.nb_variables() {
+                    if rng(&mut seed, 4) == 0 {
+                        let merged = relax.merge(&mut states.iter());
+                        states = [merged.clone(), merged];
+                    }
+                    for state in states.iter_mut() {
+                        let mut domain = vec![];
+                        model.for_each_in_domain(Variable(state.depth), state, |d| domain.push(d));
+                        let decision = domain[rng(&mut seed, domain.len())];
+                        let d = decision.value as usize;
+
+                        let expected = reference_transition(model, state, d);
+                        let cost = reference_transition_cost(model, state, d);
+                        let next = model.transition(state, decision);
+                        assert_eq!((&expected, hash(&expected)), (&next, hash(&next)));
+                        assert_eq!(cost, model.transition_cost(state, decision));
+                        assert_eq!((expected, cost), model.transition_with_cost(state, decision));
+                        *state = next;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_progress_reports_ever_better_arrangements() {
+        let model = Srflp::new(SrflpInstance::from(File::open("resources/srflp/Cl12").unwrap()));
+        let relax = SrflpRelax::new(&model);
+        let width = SrflpWidth::new(model.nb_variables(), 1);
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let collected = events.clone();
+        let mut solver = BarrierParallelSolver::custom(&model, &relax, &SrflpRanking, &width, CutsetType::LastExactLayer, 4);
+        let status = solver.minimize_with_progress(|| false, move |cost, arrangement: &[Decision]| {
+            collected.lock().unwrap().push((cost, arrangement.to_vec()));
+        });
+        assert_eq!(ResolutionStatus::Proved, status);
+
+        let events = events.lock().unwrap();
+        assert!(events.len() > 1);
+        assert!(events.windows(2).all(|pair| pair[0].0 > pair[1].0));
+        assert_eq!(solver.best_value(), events.last().map(|(cost, _)| *cost));
+        for (cost, arrangement) in events.iter() {
+            assert_eq!(Ok(*cost), model.validate_solution(arrangement));
+        }
+    }
+}
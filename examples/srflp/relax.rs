@@ -23,7 +23,8 @@
 use std::{ops::Not};
 
 use bitset_fixed::BitSet;
-use engineering::{Relaxation, Decision, BitSetIter};
+use engineering::api::{Relaxation, Decision};
+use engineering::BitSetIter;
 
 use crate::{model::Srflp, state::State};
 
@@ -105,13 +106,13 @@ impl Relaxation for SrflpRelax<'_> {
     type State = State;
 
     fn merge(&self, states: &mut dyn Iterator<Item = &State>) -> State {
-        let mut helper = RelaxHelper::new(self.pb.instance.nb_departments as usize);
+        let mut helper = RelaxHelper::new(self.pb.instance.nb_departments);
 
         for state in states {
             helper.track_depth(state.depth);
             helper.track_must_visit(&state.must_place);
             helper.track_maybe_visit(&state.maybe_place);
-            helper.track_cut(&state);
+            helper.track_cut(state);
         }
 
         State {
@@ -133,4 +134,139 @@ impl Relaxation for SrflpRelax<'_> {
     {
         cost
     }
+
+    fn check_merge(&self, merged: &State, originals: &[&State]) -> Result<(), String> {
+        let merged_all = all_place(merged);
+        for state in originals {
+            if !is_subset(&merged.must_place, &state.must_place) {
+                return Err(format!("must_place of {:?} is not included in that of {:?}", merged, state));
+            }
+            let state_all = all_place(state);
+            if !is_subset(&state_all, &merged_all) {
+                return Err(format!("{:?} forgets some departments {:?} may still place", merged, state));
+            }
+            if BitSetIter::new(&state_all).any(|i| merged.cut[i] > state.cut[i]) {
+                return Err(format!("cut of {:?} is greater than that of {:?}", merged, state));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A coarser relaxation which merges the states just like `SrflpRelax` but
+/// forgets their cuts. It is cheaper, and near the root (where few departments
+/// are placed and the cuts are small) it barely weakens the bound: it is meant
+/// to merge the shallow layers when used in a `LayeredRelaxation`.
+#[derive(Clone)]
+pub struct SrflpCoarseRelax<'a> {
+    pb : &'a Srflp,
+}
+impl <'a> SrflpCoarseRelax<'a> {
+    pub fn new(pb: &'a Srflp) -> Self {
+        Self{pb}
+    }
+}
+
+impl Relaxation for SrflpCoarseRelax<'_> {
+    type State = State;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &State>) -> State {
+        let n = self.pb.instance.nb_departments;
+        let mut helper = RelaxHelper::new(n);
+
+        for state in states {
+            helper.track_depth(state.depth);
+            helper.track_must_visit(&state.must_place);
+            helper.track_maybe_visit(&state.maybe_place);
+        }
+
+        State {
+            depth      : helper.get_depth(),
+            must_place : helper.get_must_place(),
+            maybe_place: helper.get_maybe_place(),
+            cut        : vec![0; n],
+        }
+    }
+
+    fn relax(
+        &self,
+        _: &Self::State,
+        _: &Self::State,
+        _: &Self::State,
+        _: Decision,
+        cost: isize,
+    ) -> isize
+    {
+        cost
+    }
+
+    fn check_merge(&self, merged: &State, originals: &[&State]) -> Result<(), String> {
+        // the cuts are all null, hence smaller than those of any state
+        SrflpRelax::new(self.pb).check_merge(merged, originals)
+    }
+}
+
+/// The set of departments which must or might still be placed in the given state
+fn all_place(state: &State) -> BitSet {
+    match state.maybe_place.as_ref() {
+        Some(maybe) => &state.must_place | maybe,
+        None => state.must_place.clone(),
+    }
+}
+
+fn is_subset(a: &BitSet, b: &BitSet) -> bool {
+    a.buffer().iter().zip(b.buffer().iter()).all(|(x, y)| x & !y == 0)
+}
+
+#[cfg(test)]
+mod test_relax {
+    use std::{io::BufReader, time::Duration};
+
+    use engineering::api::{CutsetType, Decision, Problem, Relaxation, Variable};
+    use engineering::{xputils::{solve, SolverType}, LayeredRelaxation};
+
+    use crate::{heuristics::{SrflpRanking, SrflpWidth}, instance::SrflpInstance, model::Srflp};
+
+    use super::{SrflpCoarseRelax, SrflpRelax};
+
+    fn model() -> Srflp {
+        let text = "6\n3 1 4 1 5 2\n0 2 0 5 1 3\n2 0 4 1 0 2\n0 4 0 3 2 0\n5 1 3 0 4 1\n1 0 2 4 0 6\n3 2 0 1 6 0\n";
+        Srflp::new(SrflpInstance::from(BufReader::new(text.as_bytes())))
+    }
+
+    #[test]
+    fn the_shallow_layers_forget_the_cuts_and_the_deep_ones_keep_them() {
+        let model = model();
+        let relax = LayeredRelaxation::new(SrflpCoarseRelax::new(&model), SrflpRelax::new(&model), 2);
+        let root = model.initial_state();
+        let states = (0..3)
+            .map(|i| model.transition(&root, Decision { var: Variable(0), value: i }))
+            .collect::<Vec<_>>();
+
+        let shallow = relax.merge_at_depth(1, &mut states.iter());
+        assert!(shallow.cut.iter().all(|c| *c == 0));
+        assert!(relax.check_merge_at_depth(1, &shallow, &states.iter().collect::<Vec<_>>()).is_ok());
+
+        let deep = relax.merge_at_depth(2, &mut states.iter());
+        assert_eq!(SrflpRelax::new(&model).merge(&mut states.iter()), deep);
+        assert!(deep.cut.iter().any(|c| *c > 0));
+    }
+
+    #[test]
+    fn the_layered_relaxation_finds_the_same_optimum() {
+        let model = model();
+        let width = SrflpWidth::new(model.nb_variables(), 1);
+        let timeout = Duration::from_secs(10);
+        let fine = SrflpRelax::new(&model);
+        let expected = solve("tiny", timeout, &width, &model, &fine, &SrflpRanking,
+            Some(1), SolverType::Parallel, CutsetType::LastExactLayer, false);
+        for depth in 0..=model.nb_variables() {
+            let relax = LayeredRelaxation::new(SrflpCoarseRelax::new(&model), SrflpRelax::new(&model), depth);
+            for solver in [SolverType::Parallel, SolverType::Barrier] {
+                let report = solve("tiny", timeout, &width, &model, &relax, &SrflpRanking,
+                    Some(1), solver, CutsetType::LastExactLayer, false);
+                assert_eq!(expected.best_value, report.best_value, "depth {} with {}", depth, solver);
+            }
+        }
+    }
 }
@@ -25,6 +25,7 @@ use std::hash::Hash;
 use bitset_fixed::BitSet;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(from = "StateRecord", into = "StateRecord"))]
 pub struct State {
     /// These are the departments that need to be placed
     pub must_place : BitSet,
@@ -35,3 +36,46 @@ pub struct State {
     /// This is the 'depth' in the arrangement, the number of departments that have already been placed
     pub depth: usize
 }
+
+/// The serialized form of a state: a `BitSet` is written as its size and the
+/// words of its buffer
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StateRecord {
+    must_place : (usize, Vec<u64>),
+    maybe_place: Option<(usize, Vec<u64>)>,
+    cut: Vec<isize>,
+    depth: usize,
+}
+#[cfg(feature = "serde")]
+fn bitset_record(set: &BitSet) -> (usize, Vec<u64>) {
+    (set.size(), set.buffer().to_vec())
+}
+#[cfg(feature = "serde")]
+fn bitset_from_record((size, words): (usize, Vec<u64>)) -> BitSet {
+    let mut set = BitSet::new(size);
+    set.buffer_mut().copy_from_slice(&words);
+    set
+}
+#[cfg(feature = "serde")]
+impl From<State> for StateRecord {
+    fn from(state: State) -> Self {
+        StateRecord {
+            must_place : bitset_record(&state.must_place),
+            maybe_place: state.maybe_place.as_ref().map(bitset_record),
+            cut: state.cut,
+            depth: state.depth,
+        }
+    }
+}
+#[cfg(feature = "serde")]
+impl From<StateRecord> for State {
+    fn from(record: StateRecord) -> Self {
+        State {
+            must_place : bitset_from_record(record.must_place),
+            maybe_place: record.maybe_place.map(bitset_from_record),
+            cut: record.cut,
+            depth: record.depth,
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use engineering::{StateRanking, WidthHeuristic};
+use bitset_fixed::BitSet;
+use engineering::api::{StateRanking, WidthHeuristic};
+use engineering::Dominance;
 
-use crate::state::State;
+use crate::state::{ElapsedTime, Position, State};
 
 #[derive(Debug, Copy, Clone)]
 pub struct TsptwRanking;
@@ -13,6 +15,29 @@ impl StateRanking for TsptwRanking {
     }
 }
 
+/// The salesman who stands at the same position, with the same nodes left to
+/// visit, is better off when he got there sooner and for a lower cost: since
+/// he may wait, arriving earlier never closes a time window.
+#[derive(Debug, Copy, Clone)]
+pub struct TsptwDominance;
+
+impl Dominance for TsptwDominance {
+    type State = State;
+    type Key = (Position, BitSet, Option<BitSet>);
+
+    fn dominance_key(&self, state: &State) -> Self::Key {
+        (state.position.clone(), state.must_visit.clone(), state.maybe_visit.clone())
+    }
+    fn is_dominated(&self, a_value: isize, a: &State, b_value: isize, b: &State) -> bool {
+        match (a.elapsed, b.elapsed) {
+            (ElapsedTime::FixedAmount { duration: a_time }, ElapsedTime::FixedAmount { duration: b_time }) => {
+                a_time >= b_time && a_value <= b_value
+            }
+            _ => false,
+        }
+    }
+}
+
 pub struct TsptwWidth {
     nb_vars: usize,
     factor: usize,
@@ -27,3 +52,57 @@ impl WidthHeuristic<State> for TsptwWidth {
         self.nb_vars * (state.depth as usize + 1) * self.factor
     }
 }
+
+#[cfg(test)]
+mod test_heuristics {
+    use std::fs::File;
+
+    use engineering::api::{AdaptiveWidth, BarrierParallelSolver, CutsetType, Fixed, InterruptibleSolver, NodeCutoff, Problem, Solver, WidthHeuristic};
+
+    use crate::{instance::TsptwInstance, model::Tsptw, relax::TsptwRelax, state::State};
+
+    use super::{TsptwDominance, TsptwRanking, TsptwWidth};
+
+    /// Returns the number of dd nodes expanded by the first 100 nodes of the
+    /// branch-and-bound
+    fn explored_dd<W: WidthHeuristic<State> + Send + Sync>(model: &Tsptw, width: &W) -> usize {
+        let relax = TsptwRelax::new(model);
+        let mut solver = BarrierParallelSolver::custom(model, &relax, &TsptwRanking, width, CutsetType::LastExactLayer, 1);
+        solver.minimize_with_cutoff(NodeCutoff(100));
+        solver.get_explored_dd()
+    }
+
+    #[test]
+    fn an_adaptive_width_explores_more_than_a_fixed_small_width() {
+        let model = Tsptw::new(TsptwInstance::from(File::open("resources/tsptw/AFG/rbg016a.tw").unwrap()));
+        // the restricted dds of width 1 keep running into dead ends
+        let adaptive = AdaptiveWidth::new(Fixed(1));
+        assert!(explored_dd(&model, &adaptive) > explored_dd(&model, &Fixed(1)));
+        assert!(adaptive.factor() > 1);
+    }
+
+    /// Returns the optimal value along with the number of dd nodes expanded
+    /// and the number of nodes dropped by the dominance rule
+    fn solve(model: &Tsptw, dominance: bool) -> (Option<isize>, usize, usize) {
+        let relax = TsptwRelax::new(model);
+        let width = TsptwWidth::new(model.nb_variables(), 1);
+        let mut solver = BarrierParallelSolver::custom(model, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 1);
+        if dominance {
+            solver = solver.with_dominance(&TsptwDominance);
+        }
+        solver.minimize();
+        (solver.best_value(), solver.get_explored_dd(), solver.compilation_stats().dominated_nodes)
+    }
+
+    #[test]
+    fn the_dominance_rule_shrinks_the_dds_but_keeps_the_optimum() {
+        let model = Tsptw::new(TsptwInstance::from(File::open("resources/tsptw/AFG/rbg010a.tw").unwrap()));
+        let (plain, plain_explored, none) = solve(&model, false);
+        let (dominated, dominated_explored, dropped) = solve(&model, true);
+
+        assert_eq!(plain, dominated);
+        assert_eq!(0, none);
+        assert!(dropped > 0);
+        assert!(dominated_explored < plain_explored);
+    }
+}
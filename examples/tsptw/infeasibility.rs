@@ -0,0 +1,149 @@
+//! This module implements a cheap analysis of the time windows of a TSP+TW
+//! instance. It checks a few necessary conditions for the existence of a
+//! feasible tour; so that when the solver finds no solution, one can tell
+//! whether the instance is truly infeasible (or if it was mis-parsed).
+
+use std::fmt::Display;
+
+use crate::model::Tsptw;
+
+/// This is the evidence that an instance admits no feasible tour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfeasibilityCertificate {
+    /// The time window of `node` closes before the earliest time at which it
+    /// can be reached from the depot
+    UnreachableNode { node: usize, earliest_arrival: usize, latest: usize },
+    /// Both `i` and `j` can be reached in time, but visiting `j` after `i`
+    /// misses the window of `j` and visiting `i` after `j` misses that of `i`
+    IncompatiblePair { i: usize, j: usize },
+}
+
+impl Display for InfeasibilityCertificate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InfeasibilityCertificate::UnreachableNode { node, earliest_arrival, latest } =>
+                write!(f, "node {} cannot be reached before {} but its time window closes at {}",
+                    node, time(*earliest_arrival), time(*latest)),
+            InfeasibilityCertificate::IncompatiblePair { i, j } =>
+                write!(f, "nodes {} and {} cannot both be visited: either order misses a time window", i, j),
+        }
+    }
+}
+/// Converts a duration back to the unit used in the instance files
+fn time(t: usize) -> f32 {
+    t as f32 / 10000.0
+}
+
+/// Checks cheap necessary conditions for the feasibility of the given instance
+/// and returns a certificate whenever one of them is violated. A `None` answer
+/// does not mean that the instance is feasible.
+pub fn analyze_infeasibility(tsptw: &Tsptw) -> Option<InfeasibilityCertificate> {
+    let inst = &tsptw.instance;
+    let n = inst.nb_nodes as usize;
+    let arrival = earliest_arrivals(tsptw);
+
+    for (node, &earliest_arrival) in arrival.iter().enumerate().skip(1) {
+        let latest = inst.timewindows[node].latest;
+        if earliest_arrival > latest {
+            return Some(InfeasibilityCertificate::UnreachableNode { node, earliest_arrival, latest });
+        }
+    }
+
+    let fits_after = |i: usize, j: usize| {
+        let twj = inst.timewindows[j];
+        (arrival[i] + inst.distances[(i, j)]).max(twj.earliest) <= twj.latest
+    };
+    for i in 1..n {
+        for j in i + 1..n {
+            if !fits_after(i, j) && !fits_after(j, i) {
+                return Some(InfeasibilityCertificate::IncompatiblePair { i, j });
+            }
+        }
+    }
+    None
+}
+
+/// Computes the earliest time at which each node can be reached when leaving
+/// the depot at time zero and only traversing nodes whose time window is
+/// still open (waiting for the windows to open whenever necessary).
+fn earliest_arrivals(tsptw: &Tsptw) -> Vec<usize> {
+    let inst = &tsptw.instance;
+    let n = inst.nb_nodes as usize;
+
+    let mut arrival = vec![usize::MAX; n];
+    let mut done = vec![false; n];
+    arrival[0] = 0;
+
+    // dijkstra: the arrival time is a non decreasing function of the departure
+    while let Some(i) = (0..n).filter(|i| !done[*i]).min_by_key(|i| arrival[*i]) {
+        done[i] = true;
+        let departure = arrival[i];
+        // a node whose window is already closed cannot be used as a relay
+        if departure > inst.timewindows[i].latest {
+            continue;
+        }
+        // the depot is only left at the beginning of the tour
+        for j in 1..n {
+            if !done[j] {
+                let t = (departure + inst.distances[(i, j)]).max(inst.timewindows[j].earliest);
+                arrival[j] = arrival[j].min(t);
+            }
+        }
+    }
+    arrival
+}
+
+#[cfg(test)]
+mod test_infeasibility {
+    use std::io::BufReader;
+
+    use crate::{instance::TsptwInstance, model::Tsptw};
+
+    use super::{analyze_infeasibility, InfeasibilityCertificate};
+
+    fn model(text: &str) -> Tsptw {
+        Tsptw::new(TsptwInstance::from(BufReader::new(text.as_bytes())))
+    }
+
+    #[test]
+    fn a_feasible_instance_has_no_certificate() {
+        let tsptw = model("3\n0 1 1\n1 0 1\n1 1 0\n0 10\n0 10\n0 10\n");
+        assert_eq!(None, analyze_infeasibility(&tsptw));
+    }
+
+    #[test]
+    fn a_node_whose_window_closes_too_early_is_unreachable() {
+        let tsptw = model("3\n0 1 5\n1 0 5\n5 5 0\n0 20\n0 10\n0 3\n");
+        let certificate = analyze_infeasibility(&tsptw);
+        assert_eq!(
+            Some(InfeasibilityCertificate::UnreachableNode { node: 2, earliest_arrival: 50000, latest: 30000 }),
+            certificate
+        );
+        assert_eq!(
+            "node 2 cannot be reached before 5 but its time window closes at 3",
+            certificate.unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn the_earliest_arrival_accounts_for_relays_and_waiting_times() {
+        // node 3 is far from the depot, but can be reached through node 1 once
+        // its window opens (at time 4)
+        let tsptw = model("4\n0 1 9 9\n1 0 9 2\n9 9 0 9\n9 2 9 0\n0 50\n4 10\n0 50\n0 5\n");
+        assert_eq!(
+            Some(InfeasibilityCertificate::UnreachableNode { node: 3, earliest_arrival: 60000, latest: 50000 }),
+            analyze_infeasibility(&tsptw)
+        );
+    }
+
+    #[test]
+    fn two_nodes_with_the_same_tight_window_are_incompatible() {
+        let tsptw = model("3\n0 1 1\n1 0 2\n1 2 0\n0 20\n1 2\n1 2\n");
+        let certificate = analyze_infeasibility(&tsptw);
+        assert_eq!(Some(InfeasibilityCertificate::IncompatiblePair { i: 1, j: 2 }), certificate);
+        assert_eq!(
+            "nodes 1 and 2 cannot both be visited: either order misses a time window",
+            certificate.unwrap().to_string()
+        );
+    }
+}
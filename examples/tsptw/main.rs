@@ -1,19 +1,26 @@
-use std::{fs::File, path::Path, time::Duration};
+use std::{fs::File, path::Path, process::exit, time::Duration};
 
+use engineering::api::{FringePolicy, Problem, CutsetType};
 use engineering::{
-    xputils::{solve_timeout, Args, SolverType, resolution_header}, Problem, CutsetType,
+    xputils::{solve, solve_custom, write_decisions_file, write_solution_file, Args, OrderType, SolverType, resolution_header}, bench::{bench_file, instance_name}, WarmStartBudget, MergePolicy,
 };
-use heuristics::{TsptwRanking, TsptwWidth};
+use heuristics::{TsptwDominance, TsptwRanking, TsptwWidth};
+use infeasibility::analyze_infeasibility;
 use instance::TsptwInstance;
 use model::Tsptw;
 use relax::TsptwRelax;
+use secondary::EarliestVisit;
 use structopt::StructOpt;
+use tour::TsplibTour;
 
 mod heuristics;
+mod infeasibility;
 mod instance;
 mod model;
 mod relax;
+mod secondary;
 mod state;
+mod tour;
 
 fn main() {
     let args = Args::from_args();
@@ -26,11 +33,26 @@ fn main() {
             threads,
             solver,
             cutset,
-        } => run_resolution_xp(file, width, timeout, threads, solver, cutset),
+            solution_out,
+            output,
+            verbose,
+            earliest_visit,
+            dominance,
+            order,
+            fringe_policy,
+            max_nodes,
+            warm_start,
+            merge,
+            trace,
+            self_check,
+            ..
+        } => run_resolution_xp(file, width, timeout, threads, solver, cutset, solution_out, output, verbose, earliest_visit, dominance, order, fringe_policy, max_nodes, warm_start, merge, trace, self_check),
+        Args::Bench { experiment, output } => run_benchmark_xp(experiment, output),
         Args::PrintHeader => resolution_header(),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_resolution_xp(
     file: String,
     width: Option<usize>,
@@ -38,6 +60,18 @@ fn run_resolution_xp(
     threads: Option<usize>,
     solver: SolverType,
     cutset: CutsetType,
+    solution_out: Option<String>,
+    output: Option<String>,
+    verbose: bool,
+    earliest_visit: Option<usize>,
+    dominance: bool,
+    order: OrderType,
+    fringe_policy: FringePolicy,
+    max_nodes: Option<usize>,
+    warm_start: Option<WarmStartBudget>,
+    merge: MergePolicy,
+    trace: Option<String>,
+    self_check: bool,
 ) {
     let afile = Box::new(file);
     let afile = Box::leak(afile);
@@ -47,7 +81,7 @@ fn run_resolution_xp(
         .map(|s| s.to_str().unwrap_or("-- unknown --"))
         .unwrap_or("-- unknown --");
     let file = File::open(path).unwrap();
-    let instance = TsptwInstance::try_from(file).unwrap();
+    let instance = TsptwInstance::from(file);
     let model = Tsptw::new(instance);
     let relax = TsptwRelax::new(&model);
     let ranking = TsptwRanking;
@@ -57,5 +91,55 @@ fn run_resolution_xp(
     let name: &'static str = Box::leak(name);
     let timeout = Duration::from_secs(timeout as u64);
     
-    solve_timeout::<Tsptw, TsptwRelax, TsptwRanking, TsptwWidth>(name, timeout, &width, &model, &relax, &ranking, threads, solver, cutset);
-}
\ No newline at end of file
+    let secondary = earliest_visit.map(|node| EarliestVisit::new(&model, node));
+    let report = solve_custom(name, timeout, &width, &model, &relax, &ranking, threads, solver, cutset, verbose, self_check,
+        secondary.as_ref().map(|s| s as _), dominance.then_some(&TsptwDominance as _), order, fringe_policy, max_nodes, warm_start, merge, trace.as_deref());
+    println!("{}", report);
+    if let Some(error) = report.verification_error.as_ref() {
+        eprintln!("{}", error);
+    }
+    for violation in &report.violations {
+        eprintln!("warning: {}", violation);
+    }
+    if let Some(count) = report.optimal_solutions {
+        println!("optimal solutions: {}", count);
+    }
+    if let Some(split) = report.time_split().filter(|_| verbose) {
+        println!("{}", split);
+    }
+    if let Some(peak) = report.peak_attribution.filter(|_| verbose) {
+        println!("{}", peak);
+    }
+    if let Some(summary) = report.barrier_summary().filter(|_| verbose) {
+        println!("{}", summary);
+    }
+
+    if let Some(path) = solution_out {
+        let tour = TsplibTour { name, nb_nodes: model.instance.nb_nodes as usize };
+        if let Err(error) = write_solution_file(&tour, &report, &path) {
+            eprintln!("cannot write the solution to {}: {}", path, error);
+            exit(1);
+        }
+    }
+    if let Some(path) = output {
+        write_decisions_file(&report, &path).unwrap();
+    }
+
+    // when no tour was found, tell whether the time windows make it impossible
+    if report.best_value.is_none() {
+        match analyze_infeasibility(&model) {
+            Some(certificate) => println!("infeasible: {}", certificate),
+            None => println!("no infeasibility certificate found"),
+        }
+    }
+}
+
+fn run_benchmark_xp(experiment: String, output: String) {
+    bench_file(&experiment, &output, |file, config, timeout| {
+        let instance = TsptwInstance::from(File::open(file).unwrap());
+        let model = Tsptw::new(instance);
+        let relax = TsptwRelax::new(&model);
+        let width = TsptwWidth::new(model.nb_variables(), config.width);
+        solve(&instance_name(file), timeout, &width, &model, &relax, &TsptwRanking, config.threads, config.solver, config.cutset, false)
+    });
+}
@@ -23,20 +23,35 @@
 use std::ops::Not;
 
 use bitset_fixed::BitSet;
-use engineering::{BitSetIter, Problem, Decision, Variable};
+use engineering::api::{Problem, Decision, Objective, Variable};
+use engineering::BitSetIter;
 
 use crate::{instance::TsptwInstance, state::{ElapsedTime, Position, State}};
 
 
 /// This is the structure encapsulating the Tsptw problem.
+///
+/// By default, the return to the depot is the terminal value of the states
+/// which have visited every node (see `Problem::terminal_value`): there is
+/// one variable per node to visit. The model can also state it as an explicit
+/// last decision, which always goes to the depot (see `with_explicit_return`).
 #[derive(Debug, Clone)]
 pub struct Tsptw {
     pub instance: TsptwInstance,
     pub initial : State,
     cheapest_edge: Vec<usize>,
+    explicit_return: bool,
 }
 impl Tsptw {
     pub fn new(inst: TsptwInstance) -> Self {
+        Self::formulated(inst, false)
+    }
+    /// Creates a model whose last variable is the return to the depot
+    #[cfg(test)]
+    pub fn with_explicit_return(inst: TsptwInstance) -> Self {
+        Self::formulated(inst, true)
+    }
+    fn formulated(inst: TsptwInstance, explicit_return: bool) -> Self {
         let cheapest_edge = Self::compute_cheapest_edges(&inst);
         let mut state = State {
             position  : Position::Node(0),
@@ -46,14 +61,14 @@ impl Tsptw {
             depth : 0
         };
         state.must_visit.set(0, false);
-        Self { instance: inst, initial: state, cheapest_edge }
+        Self { instance: inst, initial: state, cheapest_edge, explicit_return }
     }
 
     fn compute_cheapest_edges(inst: &TsptwInstance) -> Vec<usize> {
         let mut cheapest = vec![];
         let n = inst.nb_nodes as usize;
         for i in 0..n {
-            let mut min_i = usize::max_value();
+            let mut min_i = usize::MAX;
             for j in 0..n {
                 if i == j {
                     continue;
@@ -65,6 +80,12 @@ impl Tsptw {
         cheapest
     }
 
+    /// Tells whether the next decision after the given state is the last one
+    /// of the tour (an instance made of the depot alone has none at all)
+    fn is_last_move(&self, state: &State) -> bool {
+        state.depth as usize + 1 == self.nb_variables()
+    }
+
     pub fn _total_openness(&self, state: &State) -> isize {
         let now = state.depth as usize;
         let mut tot = 0;
@@ -85,17 +106,31 @@ impl Problem for Tsptw {
     type State = State;
 
     fn nb_variables(&self) -> usize {
-        self.instance.nb_nodes as usize
+        let nb_nodes = self.instance.nb_nodes as usize;
+        if self.explicit_return { nb_nodes } else { nb_nodes - 1 }
     }
 
     fn initial_state(&self) -> State {
         self.initial.clone()
     }
 
+    fn objective(&self) -> Objective {
+        Objective::Minimize
+    }
+
     fn initial_value(&self) -> isize {
         0
     }
 
+    fn domain_size_hint(&self, _var: Variable, state: &Self::State) -> Option<usize> {
+        if self.explicit_return && self.is_last_move(state) {
+            Some(1)
+        } else {
+            let maybe = state.maybe_visit.as_ref().map_or(0, |maybe| maybe.count_ones());
+            Some((state.must_visit.count_ones() + maybe) as usize)
+        }
+    }
+
     fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, mut f: F)
     where
         F: FnMut(Decision),
@@ -103,9 +138,19 @@ impl Problem for Tsptw {
         // When we are at the end of the tour, the only possible destination is
         // to go back to the depot. Any state that violates this constraint is
         // de facto infeasible.
-        if state.depth as usize == self.nb_variables() - 1 {
-            if self.can_move_to(state, 0) {
-                f(Decision { var, value: 0 })
+        if self.is_last_move(state) {
+            if self.explicit_return {
+                if self.can_move_to(state, 0) {
+                    f(Decision { var, value: 0 })
+                }
+                return;
+            }
+            // Without the explicit return, the last node is one from which the
+            // depot can still be reached in time.
+            for i in self.remaining(state) {
+                if self.can_move_to(state, i) && self.can_move_to(&self.arrive_at(state, i), 0) {
+                    f(Decision { var, value: i as isize })
+                }
             }
             return;
         }
@@ -130,48 +175,19 @@ impl Problem for Tsptw {
     }
 
     fn transition(&self, state: &State, d: Decision) -> State {
-        // if it is a true move
-        let mut remaining = state.must_visit.clone();
-        remaining.set(d.value as usize, false);
-        // if it is a possible move
-        let mut maybes = state.maybe_visit.clone();
-        if let Some(maybe) = maybes.as_mut() {
-            maybe.set(d.value as usize, false);
-        }
-
-        let time = self.arrival_time(state, d.value as usize);
-
-        State {
-            position : Position::Node(d.value as u16),
-            elapsed  : time,
-            must_visit: remaining,
-            maybe_visit: maybes,
-            depth: state.depth + 1
-        }
+        self.arrive_at(state, d.value as usize)
     }
 
     fn transition_cost(&self, state: &State, d: Decision) -> isize {
-        // Tsptw is a minimization problem but the solver works with a 
-        // maximization perspective. So we have to negate the min if we want to
-        // yield a lower bound.
-        let twj = self.instance.timewindows[d.value as usize];
-        let travel_time = self.min_distance_to(state, d.value as usize);
-        let waiting_time = match state.elapsed {
-            ElapsedTime::FixedAmount{duration} => 
-                if (duration + travel_time) < twj.earliest {
-                    twj.earliest - (duration + travel_time)
-                } else {
-                    0
-                },
-            ElapsedTime::FuzzyAmount{earliest, ..} => 
-                if (earliest + travel_time) < twj.earliest {
-                    twj.earliest - (earliest + travel_time)
-                } else {
-                    0
-                }
-        };
+        self.move_cost(state, d.value as usize)
+    }
 
-        -( (travel_time + waiting_time) as isize)
+    fn terminal_value(&self, state: &State) -> isize {
+        if self.explicit_return {
+            0
+        } else {
+            self.move_cost(state, 0)
+        }
     }
 
     fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>)
@@ -190,10 +206,10 @@ impl Problem for Tsptw {
     }
 
     fn estimate(&self, state: &State) -> isize {
-        let mut complete_tour = self.nb_variables() - state.depth as usize;
+        let mut complete_tour = self.instance.nb_nodes as usize - state.depth as usize;
  
         let mut mandatory     = 0;
-        let mut back_to_depot = usize::max_value();
+        let mut back_to_depot = usize::MAX;
         
         let mut temp = vec![];
  
@@ -205,7 +221,7 @@ impl Problem for Tsptw {
             let latest   = self.instance.timewindows[i].latest;
             let earliest = state.elapsed.add_duration(self.cheapest_edge[i]).earliest();
             if earliest > latest {
-                return isize::min_value();
+                return isize::MAX;
             }
         }
  
@@ -224,7 +240,7 @@ impl Problem for Tsptw {
             }
 
             if temp.len() - violations < complete_tour {
-                return isize::min_value();
+                return isize::MAX;
             }
 
             temp.sort_unstable();
@@ -245,19 +261,58 @@ impl Problem for Tsptw {
         }
  
         // When it is impossible to get back to the depot in time, the current
-        // state is infeasible. So we can give it an infinitely negative ub.
+        // state is infeasible. So we can give it an infinite lower bound.
         let total_distance  = mandatory + back_to_depot;
         let earliest_arrival= state.elapsed.add_duration(total_distance).earliest();
         let latest_deadline = self.instance.timewindows[0].latest;
         if earliest_arrival > latest_deadline {
-            isize::min_value()
+            isize::MAX
         } else {
-             -(total_distance as isize)
+             total_distance as isize
         }
     }
 }
 
 impl Tsptw {
+    /// Returns the state reached by moving to node j
+    fn arrive_at(&self, state: &State, j: usize) -> State {
+        // if it is a true move
+        let mut remaining = state.must_visit.clone();
+        remaining.set(j, false);
+        // if it is a possible move
+        let mut maybes = state.maybe_visit.clone();
+        if let Some(maybe) = maybes.as_mut() {
+            maybe.set(j, false);
+        }
+
+        let time = self.arrival_time(state, j);
+
+        State {
+            position : Position::Node(j as u16),
+            elapsed  : time,
+            must_visit: remaining,
+            maybe_visit: maybes,
+            depth: state.depth + 1
+        }
+    }
+    /// Returns the travel and waiting time it takes to move to node j
+    fn move_cost(&self, state: &State, j: usize) -> isize {
+        let twj = self.instance.timewindows[j];
+        let travel_time = self.min_distance_to(state, j);
+        let waiting_time = match state.elapsed {
+            ElapsedTime::FixedAmount{duration} => 
+                twj.earliest.saturating_sub(duration + travel_time),
+            ElapsedTime::FuzzyAmount{earliest, ..} => 
+                twj.earliest.saturating_sub(earliest + travel_time)
+        };
+
+        (travel_time + waiting_time) as isize
+    }
+    /// Returns the nodes which must or might still be visited
+    fn remaining<'a>(&self, state: &'a State) -> impl Iterator<Item = usize> + 'a {
+        let maybe = state.maybe_visit.iter().flat_map(BitSetIter::new);
+        BitSetIter::new(&state.must_visit).chain(maybe)
+    }
     pub fn can_move_to(&self, state: &State, j: usize) -> bool {
         let twj         = self.instance.timewindows[j];
         let min_arrival = state.elapsed.add_duration(self.min_distance_to(state, j));
@@ -309,7 +364,7 @@ impl Tsptw {
             Position::Node(i) => self.instance.distances[(*i as usize, j)],
             Position::Virtual(candidates) => 
                 BitSetIter::new(candidates)
-                    .map(|i| self.instance.distances[(i as usize, j as usize)])
+                    .map(|i| self.instance.distances[(i, j)])
                     .min()
                     .unwrap()
         }
@@ -319,9 +374,158 @@ impl Tsptw {
             Position::Node(i) => self.instance.distances[(*i as usize, j)],
             Position::Virtual(candidates) => 
                 BitSetIter::new(candidates)
-                    .map(|i| self.instance.distances[(i as usize, j as usize)])
+                    .map(|i| self.instance.distances[(i, j)])
                     .max()
                     .unwrap()
         }
     }
 }
+
+#[cfg(test)]
+mod test_model {
+    use std::{fs::File, io::BufReader};
+
+    use engineering::api::{BarrierParallelSolver, CutsetType, Decision, InterruptibleSolver, NodeCutoff, Problem, ResolutionStatus, Solver, Variable};
+    use engineering::MinimizeProblem;
+
+    use crate::{heuristics::{TsptwRanking, TsptwWidth}, instance::TsptwInstance, relax::TsptwRelax};
+
+    use super::Tsptw;
+
+    /// Returns the optimal value, the value of the optimal tour once replayed
+    /// and the number of layers that the dds have collapsed
+    fn solve(model: &Tsptw, max_forced_layers: usize) -> (Option<isize>, isize, usize) {
+        let relax = TsptwRelax::new(model);
+        let width = TsptwWidth::new(model.nb_variables(), 1);
+        let mut solver = BarrierParallelSolver::custom(model, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 2)
+            .with_forced_layers(max_forced_layers);
+        solver.minimize();
+        let mut tour = solver.best_solution().unwrap();
+        tour.sort_unstable_by_key(|d| d.var.id());
+        (solver.best_value(), model.validate_solution(&tour).unwrap(), solver.compilation_stats().collapsed_layers)
+    }
+
+    #[test]
+    fn collapsing_the_forced_endgames_keeps_the_optimal_tour() {
+        // the last move of every tour goes to the only node which remains to
+        // be visited
+        let model = Tsptw::new(TsptwInstance::from(File::open("resources/tsptw/AFG/rbg010a.tw").unwrap()));
+        let (layered, layered_replay, none) = solve(&model, 0);
+        let (collapsed, collapsed_replay, collapsed_layers) = solve(&model, model.nb_variables());
+
+        assert_eq!(layered, collapsed);
+        assert_eq!(layered_replay, collapsed_replay);
+        assert_eq!(0, none);
+        assert!(collapsed_layers > 0);
+    }
+
+    #[test]
+    fn the_return_to_the_depot_as_a_terminal_value_keeps_the_optimal_tour() {
+        for name in ["rbg010a", "rbg016a"] {
+            let file = format!("resources/tsptw/AFG/{}.tw", name);
+            let terminal = Tsptw::new(TsptwInstance::from(File::open(&file).unwrap()));
+            let explicit = Tsptw::with_explicit_return(TsptwInstance::from(File::open(&file).unwrap()));
+            assert_eq!(explicit.nb_variables() - 1, terminal.nb_variables());
+
+            let (optimum, replayed, _) = solve(&terminal, 0);
+            assert_eq!(optimum, Some(replayed));
+            assert_eq!(solve(&explicit, 0).0, optimum);
+        }
+    }
+
+    #[test]
+    fn the_maximized_model_costs_as_much_as_the_minimized_one() {
+        let model = Tsptw::new(TsptwInstance::from(File::open("resources/tsptw/AFG/rbg010a.tw").unwrap()));
+        let (minimized, _, _) = solve(&model, 0);
+
+        // the relaxation reaches the tsptw through the wrapper
+        let wrapped = MinimizeProblem::new(model);
+        let relax = TsptwRelax::new(&wrapped);
+        let width = TsptwWidth::new(wrapped.nb_variables(), 1);
+        let mut solver = BarrierParallelSolver::custom(&wrapped, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 2);
+        solver.maximize();
+        assert_eq!(minimized, solver.best_value().map(MinimizeProblem::<Tsptw>::actual_value));
+    }
+
+    #[test]
+    fn a_tour_of_the_depot_alone_is_empty() {
+        let model = Tsptw::new(TsptwInstance::from(BufReader::new("1\n0\n0 10\n".as_bytes())));
+        assert_eq!(0, model.nb_variables());
+        let relax = TsptwRelax::new(&model);
+        let width = TsptwWidth::new(model.nb_variables(), 1);
+        let mut solver = BarrierParallelSolver::custom(&model, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 2);
+        assert_eq!(ResolutionStatus::Proved, solver.minimize_with_interrupt(|| false));
+        assert_eq!((Some(0), Some(vec![])), (solver.best_value(), solver.best_solution()));
+
+        // with the explicit return, the only decision goes back to the depot
+        let model = Tsptw::with_explicit_return(TsptwInstance::from(BufReader::new("1\n0\n0 10\n".as_bytes())));
+        let mut domain = vec![];
+        model.for_each_in_domain(Variable(0), &model.initial_state(), |d| domain.push(d.value));
+        assert_eq!(vec![0], domain);
+        assert_eq!(Some(1), model.domain_size_hint(Variable(0), &model.initial_state()));
+    }
+
+    #[test]
+    fn a_root_whose_windows_cannot_be_met_is_proved_infeasible() {
+        // node 2 is at least 5 away from everywhere, but its window closes at 3
+        let model = Tsptw::new(TsptwInstance::from(BufReader::new("3\n0 1 5\n1 0 5\n5 5 0\n0 20\n0 10\n0 3\n".as_bytes())));
+        assert_eq!(isize::MAX, model.estimate(&model.initial_state()));
+
+        let relax = TsptwRelax::new(&model);
+        let width = TsptwWidth::new(model.nb_variables(), 1);
+        let mut solver = BarrierParallelSolver::custom(&model, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 2);
+        assert_eq!(ResolutionStatus::Infeasible, solver.minimize_with_interrupt(|| false));
+        assert_eq!((None, 0, 0), (solver.best_value(), solver.get_explored(), solver.get_explored_dd()));
+        assert_eq!((isize::MAX, isize::MAX), (solver.best_lower_bound(), solver.best_upper_bound()));
+
+        // the maximization of the wrapped model is just as infeasible
+        let wrapped = MinimizeProblem::new(model);
+        let relax = TsptwRelax::new(&wrapped);
+        let mut solver = BarrierParallelSolver::custom(&wrapped, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 2);
+        assert_eq!(ResolutionStatus::Infeasible, solver.maximize_with_interrupt(|| false));
+        assert_eq!((None, isize::MIN, isize::MIN), (solver.best_value(), solver.best_lower_bound(), solver.best_upper_bound()));
+    }
+
+    /// Returns the tour which always goes to the closest node it may visit
+    /// next, along with its value (none when it runs into a dead end)
+    fn greedy_tour(model: &Tsptw) -> Option<(isize, Vec<Decision>)> {
+        let mut state = model.initial_state();
+        let mut tour = vec![];
+        while let Some(var) = model.next_variable(&mut std::iter::once(&state)) {
+            let mut closest: Option<(Decision, isize)> = None;
+            model.for_each_in_domain(var, &state, |decision| {
+                let cost = model.transition_cost(&state, decision);
+                if closest.is_none_or(|(_, c)| cost < c) {
+                    closest = Some((decision, cost));
+                }
+            });
+            let (decision, _) = closest?;
+            state = model.transition(&state, decision);
+            tour.push(decision);
+        }
+        Some((model.validate_solution(&tour).ok()?, tour))
+    }
+
+    #[test]
+    fn a_greedy_initial_tour_saves_some_exploration() {
+        let model = Tsptw::new(TsptwInstance::from(File::open("resources/tsptw/AFG/rbg016a.tw").unwrap()));
+        let relax = TsptwRelax::new(&model);
+        let width = TsptwWidth::new(model.nb_variables(), 1);
+        let seeded = || {
+            let (greedy, tour) = greedy_tour(&model).unwrap();
+            BarrierParallelSolver::custom(&model, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 1).with_initial_lb(greedy, tour)
+        };
+
+        let mut cold = BarrierParallelSolver::custom(&model, &relax, &TsptwRanking, &width, CutsetType::LastExactLayer, 1);
+        cold.minimize();
+        let mut warm = seeded();
+        warm.minimize();
+        assert_eq!(cold.best_value(), warm.best_value());
+        assert!(warm.get_explored_dd() < cold.get_explored_dd());
+
+        // the initial tour is the best one until the solver finds a better one
+        let mut interrupted = seeded();
+        assert_eq!(ResolutionStatus::Interrupted, interrupted.minimize_with_cutoff(NodeCutoff(0)));
+        assert_eq!(greedy_tour(&model).map(|(value, _)| value), interrupted.best_value());
+    }
+}
@@ -23,7 +23,7 @@
 use std::{ops::Not};
 
 use bitset_fixed::BitSet;
-use engineering::{Relaxation, Decision, Problem};
+use engineering::api::{Relaxation, Decision};
 
 use crate::{model::Tsptw, state::{ElapsedTime, Position, State}};
 
@@ -51,8 +51,8 @@ impl RelaxHelper {
         Self {
             depth    : 0_u16,
             position : BitSet::new(n),
-            earliest : usize::max_value(),
-            latest   : usize::min_value(),
+            earliest : usize::MAX,
+            latest   : usize::MIN,
             all_must : BitSet::new(n),
             all_agree: BitSet::new(n).not(),
             all_maybe: BitSet::new(n),
@@ -123,7 +123,7 @@ impl Relaxation for TsptwRelax<'_> {
     type State = State;
 
     fn merge(&self, states: &mut dyn Iterator<Item = &State>) -> State {
-        let mut helper = RelaxHelper::new(self.pb.nb_variables());
+        let mut helper = RelaxHelper::new(self.pb.instance.nb_nodes as usize);
 
         for state in states {
             helper.track_depth(state.depth);
@@ -145,4 +145,40 @@ impl Relaxation for TsptwRelax<'_> {
     fn relax(&self, _: &State, _: &State, _: &State, _: Decision, cost: isize) -> isize {
         cost
     }
+
+    fn check_merge(&self, merged: &State, originals: &[&State]) -> Result<(), String> {
+        let merged_all = all_visit(merged);
+        for state in originals {
+            if !is_subset(&merged.must_visit, &state.must_visit) {
+                return Err(format!("must_visit of {:?} is not included in that of {:?}", merged, state));
+            }
+            if !is_subset(&all_visit(state), &merged_all) {
+                return Err(format!("{:?} forgets some nodes {:?} may still visit", merged, state));
+            }
+            if merged.elapsed.earliest() > state.elapsed.earliest() || merged.elapsed._latest() < state.elapsed._latest() {
+                return Err(format!("elapsed time of {:?} does not cover that of {:?}", merged, state));
+            }
+            let covered = match (&merged.position, &state.position) {
+                (Position::Virtual(m), Position::Node(x)) => m[*x as usize],
+                (Position::Virtual(m), Position::Virtual(xs)) => is_subset(xs, m),
+                (m, s) => m == s,
+            };
+            if !covered {
+                return Err(format!("position of {:?} does not cover that of {:?}", merged, state));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The set of nodes which must or might still be visited from the given state
+fn all_visit(state: &State) -> BitSet {
+    match state.maybe_visit.as_ref() {
+        Some(maybe) => &state.must_visit | maybe,
+        None => state.must_visit.clone(),
+    }
+}
+
+fn is_subset(a: &BitSet, b: &BitSet) -> bool {
+    a.buffer().iter().zip(b.buffer().iter()).all(|(x, y)| x & !y == 0)
 }
@@ -0,0 +1,73 @@
+//! This module contains a secondary objective for the TSP+TW: among the tours
+//! of minimum duration, it prefers those which reach a designated customer
+//! the earliest.
+
+use engineering::api::{Decision, Problem};
+use engineering::SecondaryObjective;
+
+use crate::model::Tsptw;
+
+/// Scores a tour with the opposite of the time at which it reaches `node`
+/// (including the time spent waiting for its window to open). The arrival
+/// time is found by replaying the tour through the transitions of the model.
+#[derive(Debug, Clone, Copy)]
+pub struct EarliestVisit<'a> {
+    tsptw: &'a Tsptw,
+    node: usize,
+}
+impl<'a> EarliestVisit<'a> {
+    pub fn new(tsptw: &'a Tsptw, node: usize) -> Self {
+        Self { tsptw, node }
+    }
+}
+
+impl SecondaryObjective for EarliestVisit<'_> {
+    fn score(&self, path: &[Decision]) -> isize {
+        let mut tour = path.to_vec();
+        tour.sort_unstable_by_key(|d| d.var.id());
+
+        let mut state = self.tsptw.initial_state();
+        for visit in tour {
+            state = self.tsptw.transition(&state, visit);
+            if visit.value as usize == self.node {
+                return -(state.elapsed.earliest() as isize);
+            }
+        }
+        // the node is not part of the tour
+        isize::MIN
+    }
+}
+
+#[cfg(test)]
+mod test_secondary {
+    use std::{io::BufReader, time::Duration};
+
+    use engineering::api::{CutsetType, Problem};
+    use engineering::xputils::{solve_with_secondary, SolverType};
+
+    use crate::{heuristics::{TsptwRanking, TsptwWidth}, instance::TsptwInstance, model::Tsptw, relax::TsptwRelax};
+
+    use super::EarliestVisit;
+
+    #[test]
+    fn the_optimal_tour_reaching_the_node_first_is_preferred() {
+        // the nodes lie on a square: going around it either way takes 4 (the
+        // return to the depot is implicit)
+        let text = "4\n0 1 2 1\n1 0 1 2\n2 1 0 1\n1 2 1 0\n0 100\n0 100\n0 100\n0 100\n";
+        let model = Tsptw::new(TsptwInstance::from(BufReader::new(text.as_bytes())));
+        let relax = TsptwRelax::new(&model);
+        let width = TsptwWidth::new(model.nb_variables(), 1);
+        for (node, expected) in [(1, vec![1, 2, 3]), (3, vec![3, 2, 1])] {
+            let secondary = EarliestVisit::new(&model, node);
+            for solver in [SolverType::Parallel, SolverType::Barrier] {
+                let report = solve_with_secondary("square", Duration::from_secs(10), &width, &model, &relax, &TsptwRanking,
+                    Some(2), solver, CutsetType::LastExactLayer, false, Some(&secondary));
+                assert_eq!(Some(40000), report.best_value);
+                let solution = report.best_solution.unwrap();
+                assert_eq!(Ok(40000), model.validate_solution(&solution));
+                let tour = solution.iter().map(|d| d.value).collect::<Vec<_>>();
+                assert_eq!(expected, tour);
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! This module writes the tours found by the solver as TSPLIB `.tour` files.
+
+use std::io::{self, Write};
+
+use engineering::api::Decision;
+use engineering::xputils::SolutionFormatter;
+
+/// Writes a tour in the TSPLIB format. As required by that format, the nodes
+/// are numbered from 1 (which is the depot). The tour starts at the depot and
+/// the return to the depot is implicit.
+#[derive(Debug, Clone, Copy)]
+pub struct TsplibTour<'a> {
+    pub name: &'a str,
+    pub nb_nodes: usize,
+}
+
+impl SolutionFormatter for TsplibTour<'_> {
+    fn write_solution(&self, solution: &[Decision], out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "NAME : {}.tour", self.name)?;
+        writeln!(out, "TYPE : TOUR")?;
+        writeln!(out, "DIMENSION : {}", self.nb_nodes)?;
+        writeln!(out, "TOUR_SECTION")?;
+        writeln!(out, "1")?;
+        // the i-th decision tells which node is visited in i-th position
+        for visit in solution.iter().filter(|d| d.value != 0) {
+            writeln!(out, "{}", visit.value + 1)?;
+        }
+        writeln!(out, "-1")?;
+        writeln!(out, "EOF")
+    }
+}
+
+#[cfg(test)]
+mod test_tour {
+    use std::{io::BufReader, time::Duration};
+
+    use engineering::api::{CutsetType, Problem};
+    use engineering::xputils::{solve, SolutionFormatter, SolverType};
+
+    use crate::{heuristics::{TsptwRanking, TsptwWidth}, instance::TsptwInstance, model::Tsptw, relax::TsptwRelax};
+
+    use super::TsplibTour;
+
+    #[test]
+    fn the_optimal_tour_is_written_in_the_tsplib_format() {
+        // the time windows only leave room for the tour 0 -> 2 -> 3 -> 1 -> 0
+        let text = "4\n0 1 1 1\n1 0 1 1\n1 1 0 1\n1 1 1 0\n0 100\n3 3\n1 1\n2 2\n";
+        let model = Tsptw::new(TsptwInstance::from(BufReader::new(text.as_bytes())));
+        let relax = TsptwRelax::new(&model);
+        let width = TsptwWidth::new(model.nb_variables(), 1);
+        let report = solve("tiny", Duration::from_secs(10), &width, &model, &relax, &TsptwRanking,
+            Some(1), SolverType::Parallel, CutsetType::LastExactLayer, false);
+        assert_eq!(Some(40000), report.best_value);
+        assert_eq!(Ok(40000), model.validate_solution(report.best_solution.as_ref().unwrap()));
+
+        let tour = TsplibTour { name: "tiny", nb_nodes: 4 };
+        assert_eq!(
+            "NAME : tiny.tour\nTYPE : TOUR\nDIMENSION : 4\nTOUR_SECTION\n1\n3\n4\n2\n-1\nEOF\n",
+            tour.format_solution(report.best_solution.as_ref().unwrap())
+        );
+    }
+}
@@ -0,0 +1,29 @@
+//! This module is the stable public api of the crate: the traits which model
+//! a problem, the core types they work with, the two solvers, the frontiers
+//! and the provided heuristics. Everything else that the crate exposes may
+//! change from one release to the next.
+//!
+//! ```
+//! use engineering::api::*;
+//! ```
+
+// the traits
+pub use crate::{DecisionDiagram, Frontier, FrontierOrder, InterruptibleSolver, Problem, Relaxation, Solver, StateRanking, WidthHeuristic};
+
+// the core types
+pub use crate::{
+    AssignedVars, CompilationType, CutsetType, Decision, NodeCutoff, Objective, PathSegment, PushOutcome, ResolutionStatus, SubProblem,
+    TimeCutoff, ValidationError, Variable,
+};
+
+// the solvers
+pub use crate::{BarrierParallelSolver, ParallelSolver};
+
+// the frontiers
+pub use crate::{
+    AgedFrontier, BoundedFrontier, DfsFrontier, FringePolicy, LayeredFrontier, MaxDepth, MaxUB, MaxValue, NoDupFrontier, SimpleFrontier,
+    StratifiedFrontier,
+};
+
+// the heuristics
+pub use crate::{AdaptiveWidth, ClampedWidth, DepthDecreasing, Fixed, LexRanking, MaxWidth, MinWidth, NbUnassigned, RevRanking};
@@ -0,0 +1,358 @@
+//! This module provides a small harness to compare several configurations of
+//! the solvers on a set of instances. The experiment is described in a file
+//! using a (tiny) subset of TOML:
+//!
+//! ```text
+//! instances   = ["resources/tsptw/AFG/rbg010a.tw", "resources/tsptw/AFG/rbg016a.tw"]
+//! timeout     = 60  # seconds, per run
+//! repetitions = 1
+//!
+//! [[config]]
+//! name    = "B&B+C"
+//! solver  = "barrier"   # defaults to parallel
+//! cutset  = "frontier"  # defaults to lel
+//! width   = 10          # defaults to 1
+//! threads = 1           # defaults to the number of cpus
+//! ```
+//!
+//! The full cross product (instance x config x repetition) is executed
+//! sequentially and one csv row is written per run.
+//!
+//! # Note:
+//! A panic in one run is recorded as an error row and does not stop the
+//! benchmark. This only works when the binary is compiled with `panic =
+//! "unwind"` (the `release` profile of this crate aborts on panic).
+//! Also, the peak memory is measured for the whole process: hence it is only
+//! meaningful for the first run of the benchmark and then grows monotonically.
+
+use std::{
+    any::Any,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    xputils::{SolveReport, SolverType},
+    CutsetType, ResolutionStatus,
+};
+
+/// One configuration of the solver which is to be benchmarked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchConfig {
+    pub name: String,
+    pub solver: SolverType,
+    pub cutset: CutsetType,
+    /// The multiplying factor of the width heuristic
+    pub width: usize,
+    pub threads: Option<usize>,
+}
+impl BenchConfig {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            solver: SolverType::Parallel,
+            cutset: CutsetType::LastExactLayer,
+            width: 1,
+            threads: None,
+        }
+    }
+}
+
+/// The description of a complete benchmark
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Experiment {
+    pub instances: Vec<String>,
+    pub configs: Vec<BenchConfig>,
+    pub timeout: Duration,
+    pub repetitions: usize,
+}
+impl FromStr for Experiment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut instances = None;
+        let mut timeout = Duration::from_secs(60);
+        let mut repetitions = 1;
+        let mut configs: Vec<BenchConfig> = vec![];
+
+        for (lc, line) in s.lines().enumerate() {
+            let err = |msg: &str| format!("line {}: {}", lc + 1, msg);
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "[[config]]" {
+                configs.push(BenchConfig::new(format!("config-{}", configs.len())));
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| err("expected 'key = value'"))?;
+            let key = key.trim();
+            let value = value.trim();
+
+            if let Some(config) = configs.last_mut() {
+                match key {
+                    "name" => config.name = parse_string(value).ok_or_else(|| err("expected a string"))?,
+                    "solver" => config.solver = parse_string(value).ok_or_else(|| err("expected a string"))?.parse().map_err(err)?,
+                    "cutset" => config.cutset = parse_string(value).ok_or_else(|| err("expected a string"))?.parse().map_err(err)?,
+                    "width" => config.width = value.parse().map_err(|_| err("expected an integer"))?,
+                    "threads" => config.threads = Some(value.parse().map_err(|_| err("expected an integer"))?),
+                    _ => return Err(err(&format!("unknown config key '{}'", key))),
+                }
+            } else {
+                match key {
+                    "instances" => instances = Some(parse_string_array(value).ok_or_else(|| err("expected an array of strings"))?),
+                    "timeout" => timeout = Duration::from_secs(value.parse().map_err(|_| err("expected an integer"))?),
+                    "repetitions" => repetitions = value.parse().map_err(|_| err("expected an integer"))?,
+                    _ => return Err(err(&format!("unknown key '{}'", key))),
+                }
+            }
+        }
+
+        let instances = instances.ok_or_else(|| "missing 'instances'".to_owned())?;
+        if configs.is_empty() {
+            return Err("at least one [[config]] is required".to_owned());
+        }
+        Ok(Experiment { instances, configs, timeout, repetitions })
+    }
+}
+
+/// The outcome of one run of the benchmark
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchRow {
+    pub instance: String,
+    pub config: String,
+    pub repetition: usize,
+    /// The report of the run, or the panic message when the run failed
+    pub outcome: Result<SolveReport, String>,
+}
+
+/// Executes the full cross product of the experiment sequentially and writes
+/// one csv row per run in `out`. The closure `run` is responsible for loading
+/// the instance and solving it with the given configuration and timeout.
+pub fn run_benchmark<F, Out>(experiment: &Experiment, out: &mut Out, mut run: F) -> io::Result<Vec<BenchRow>>
+where
+    F: FnMut(&str, &BenchConfig, Duration) -> SolveReport,
+    Out: Write,
+{
+    writeln!(out, "instance,config,repetition,{},error", SolveReport::CSV_HEADER)?;
+
+    let mut rows = vec![];
+    for instance in experiment.instances.iter() {
+        for config in experiment.configs.iter() {
+            for repetition in 0..experiment.repetitions {
+                let outcome = panic::catch_unwind(AssertUnwindSafe(|| run(instance, config, experiment.timeout)))
+                    .map_err(panic_message);
+
+                match &outcome {
+                    Ok(report) => writeln!(out, "{},{},{},{},",
+                        csv_field(instance), csv_field(&config.name), repetition, report.to_csv())?,
+                    Err(msg) => writeln!(out, "{},{},{},{},{},{},error,,,,,,,,,{}",
+                        csv_field(instance), csv_field(&config.name), repetition,
+                        csv_field(&instance_name(instance)), config.solver, config.cutset, csv_field(msg))?,
+                }
+                out.flush()?;
+
+                rows.push(BenchRow {
+                    instance: instance.clone(),
+                    config: config.name.clone(),
+                    repetition,
+                    outcome,
+                });
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// Prints a summary of the benchmark: for each config, the number of proved
+/// runs, the mean time on the proved runs and the mean gap on the others.
+pub fn print_summary(experiment: &Experiment, rows: &[BenchRow]) {
+    println!(
+        "{:>30} | {:>8} | {:>8} | {:>8} | {:>15} | {:>15}",
+        "CONFIG", "RUNS", "PROVED", "ERRORS", "MEAN TIME (P)", "MEAN GAP (U)"
+    );
+    for config in experiment.configs.iter() {
+        let runs = rows.iter().filter(|r| r.config == config.name);
+        let reports = runs.clone().filter_map(|r| r.outcome.as_ref().ok());
+        let proved = reports.clone().filter(|r| r.status == ResolutionStatus::Proved).collect::<Vec<_>>();
+        let unproved = reports.filter(|r| r.status != ResolutionStatus::Proved).collect::<Vec<_>>();
+
+        let mean_time = mean(proved.iter().map(|r| r.duration.as_secs_f32()));
+        let mean_gap = mean(unproved.iter().map(|r| r.gap));
+
+        println!(
+            "{:>30} | {:>8} | {:>8} | {:>8} | {:>15} | {:>15}",
+            config.name,
+            runs.clone().count(),
+            proved.len(),
+            runs.filter(|r| r.outcome.is_err()).count(),
+            mean_time.map(|t| format!("{:.2}", t)).unwrap_or_else(|| "-".to_owned()),
+            mean_gap.map(|g| format!("{:.4}", g)).unwrap_or_else(|| "-".to_owned()),
+        );
+    }
+}
+
+/// Reads the experiment description from `experiment`, runs it while writing
+/// the results to `output` and finally prints the summary table.
+pub fn bench_file<F>(experiment: &str, output: &str, run: F)
+where
+    F: FnMut(&str, &BenchConfig, Duration) -> SolveReport,
+{
+    let text = fs::read_to_string(experiment).unwrap();
+    let experiment = text.parse::<Experiment>().unwrap();
+    let mut out = BufWriter::new(File::create(output).unwrap());
+
+    let rows = run_benchmark(&experiment, &mut out, run).unwrap();
+    print_summary(&experiment, &rows);
+}
+
+/// Returns the name of the instance designated by the given path
+pub fn instance_name(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("-- unknown --")
+        .to_owned()
+}
+
+fn mean<I: Iterator<Item = f32>>(values: I) -> Option<f32> {
+    let (sum, count) = values.fold((0.0, 0), |(s, c), v| (s + v, c + 1));
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f32)
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_string(value: &str) -> Option<String> {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .filter(|v| !v.contains('"'))
+        .map(|v| v.to_owned())
+}
+
+fn parse_string_array(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?.trim();
+    if inner.is_empty() {
+        return Some(vec![]);
+    }
+    inner
+        .trim_end_matches(',')
+        .split(',')
+        .map(|v| parse_string(v.trim()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_bench {
+    use std::time::Duration;
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax};
+    use crate::xputils::{solve, SolverType};
+    use crate::{CutsetType, Fixed};
+
+    use super::{instance_name, run_benchmark, Experiment};
+
+    const EXPERIMENT: &str = r#"
+        # two tiny instances
+        instances   = ["tiny/small.kp", "tiny/large.kp"]
+        timeout     = 5
+        repetitions = 1
+
+        [[config]]
+        name   = "barrier, frontier"
+        solver = "barrier"
+        cutset = "frontier"
+        width  = 2
+        threads = 1
+
+        [[config]]
+        name   = "parallel"
+        threads = 2
+    "#;
+
+    #[test]
+    fn experiment_is_parsed() {
+        let xp = EXPERIMENT.parse::<Experiment>().unwrap();
+        assert_eq!(vec!["tiny/small.kp", "tiny/large.kp"], xp.instances);
+        assert_eq!(Duration::from_secs(5), xp.timeout);
+        assert_eq!(2, xp.configs.len());
+        assert_eq!(SolverType::Barrier, xp.configs[0].solver);
+        assert_eq!(CutsetType::Frontier, xp.configs[0].cutset);
+        assert_eq!(SolverType::Parallel, xp.configs[1].solver);
+        assert_eq!(CutsetType::LastExactLayer, xp.configs[1].cutset);
+        assert_eq!(1, xp.configs[1].width);
+    }
+
+    #[test]
+    fn errors_are_reported_with_their_line() {
+        let err = "instances = [\"a\"]\n[[config]]\nsolver = \"magic\"".parse::<Experiment>().unwrap_err();
+        assert!(err.starts_with("line 3"));
+        assert!("timeout = 5".parse::<Experiment>().is_err());
+    }
+
+    #[test]
+    fn one_row_is_written_per_run_and_panics_are_isolated() {
+        let mut xp = EXPERIMENT.parse::<Experiment>().unwrap();
+        xp.instances.push("tiny/broken.kp".to_owned());
+        xp.repetitions = 2;
+
+        let mut out = vec![];
+        let rows = run_benchmark(&xp, &mut out, |file, config, timeout| {
+            let mut problem = Knapsack::toy();
+            match instance_name(file).as_str() {
+                "large" => problem.capacity *= 2,
+                "broken" => panic!("cannot read {}", file),
+                _ => {}
+            }
+            let width = Fixed(config.width);
+            solve(&instance_name(file), timeout, &width, &problem, &KnapsackRelax, &KnapsackRanking,
+                config.threads, config.solver, config.cutset, false)
+        }).unwrap();
+
+        let csv = String::from_utf8(out).unwrap();
+        assert_eq!(1 + 3 * 2 * 2, csv.lines().count());
+        assert_eq!(4, rows.iter().filter(|r| r.outcome.is_err()).count());
+        assert!(rows.iter()
+            .filter(|r| r.instance == "tiny/small.kp")
+            .all(|r| r.outcome.as_ref().unwrap().best_value == Some(13)));
+        assert!(csv.contains("\"barrier, frontier\""));
+        assert!(csv.contains("cannot read tiny/broken.kp"));
+    }
+}
@@ -0,0 +1,180 @@
+//! This module estimates the size of the exact dd of a problem without
+//! compiling it. The estimate tells whether compiling the exact dd (see
+//! `ExactSolver`) is viable, or whether the problem had better be solved by
+//! branch-and-bound right away.
+//!
+//! The estimate relies on random dives from the root: each dive takes a
+//! random decision for each variable, and records the (hash of the) state it
+//! reaches at each depth. The number of distinct states of a layer is then
+//! estimated from the collisions of the dives, after the birthday paradox:
+//! the more states were met by a single dive compared to those met by two of
+//! them, the more states the dives have not met at all (this is the Chao1
+//! estimator, which accounts for the dives not reaching the states of a layer
+//! uniformly). It falls short of the actual size of the dd when most states
+//! are seldom reached, but stays within a small factor of it.
+
+use std::hash::{Hash, Hasher};
+
+use rustc_hash::{FxHashMap, FxHasher};
+
+use crate::{mix, AssignedVars, Decision, Problem};
+
+/// The estimated size of the exact dd of a problem (see `estimate_dd_size`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DdSizeEstimate {
+    /// The estimated number of distinct states of each layer, starting with
+    /// the root
+    pub layers: Vec<f64>,
+    /// The estimated number of nodes of the dd (the sum of its layers)
+    pub nodes: f64,
+    /// A pessimistic estimate of the number of nodes of the dd, which assumes
+    /// that the dives saw two standard deviations more collisions than they
+    /// would on average. It is infinite when some layer has too few
+    /// collisions to tell.
+    pub upper: f64,
+    /// The number of distinct states that the dives have met, which is a
+    /// lower bound on the size of the dd
+    pub sampled: usize,
+}
+impl DdSizeEstimate {
+    /// Returns true when the exact dd most likely has no more than `budget`
+    /// nodes, which is the case when even the pessimistic estimate fits
+    pub fn fits(&self, budget: usize) -> bool {
+        self.upper <= budget as f64
+    }
+}
+
+/// Estimates the number of nodes of the exact dd of the problem from the
+/// given number of random dives. The dives are drawn from the `seed`, so
+/// that the same seed always yields the same estimate.
+///
+/// # Note
+/// The dives select their next variable from their own state only, whereas
+/// the compilation of a dd selects it from the whole next layer. The
+/// estimate is thus only meaningful for the problems whose variable ordering
+/// does not depend on the layer.
+pub fn estimate_dd_size<P>(problem: &P, samples: usize, seed: u64) -> DdSizeEstimate
+where
+    P: Problem,
+    P::State: Hash,
+{
+    let mut layers: Vec<FxHashMap<u64, usize>> = vec![];
+    let mut rng = mix(seed);
+    let mut decisions = vec![];
+    for _ in 0..samples {
+        let mut assigned = AssignedVars::new(problem.nb_variables());
+        let mut state = problem.initial_state();
+        let mut depth = 0;
+        loop {
+            if layers.len() <= depth {
+                layers.push(FxHashMap::default());
+            }
+            *layers[depth].entry(state_hash(&state)).or_insert(0) += 1;
+
+            let Some(var) = problem.next_variable_with_context(&assigned, &mut std::iter::once(&state)) else {
+                break;
+            };
+            decisions.clear();
+            problem.for_each_in_domain(var, &state, |decision: Decision| decisions.push(decision));
+            if decisions.is_empty() {
+                break;
+            }
+            rng = mix(rng);
+            let decision = decisions[(rng % decisions.len() as u64) as usize];
+            assigned.insert(var);
+            state = problem.transition(&state, decision);
+            depth += 1;
+        }
+    }
+
+    let mut estimate = DdSizeEstimate { layers: vec![], nodes: 0.0, upper: 0.0, sampled: 0 };
+    for layer in layers {
+        let (size, upper) = layer_size(&layer);
+        estimate.layers.push(size);
+        estimate.nodes += size;
+        estimate.upper += upper;
+        estimate.sampled += layer.len();
+    }
+    estimate
+}
+
+/// Returns the estimated number of distinct states of a layer, along with
+/// its pessimistic estimate, from the number of dives which met each state
+fn layer_size(layer: &FxHashMap<u64, usize>) -> (f64, f64) {
+    let distinct = layer.len() as f64;
+    let once = layer.values().filter(|&&dives| dives == 1).count() as f64;
+    let twice = layer.values().filter(|&&dives| dives == 2).count() as f64;
+    if once <= 1.0 {
+        // all the states (but maybe one) were met several times: the dives
+        // have most likely met all the states of the layer
+        return (distinct, distinct);
+    }
+    let unseen = |twice: f64| once * (once - 1.0) / (2.0 * (twice + 1.0));
+    // the number of states met twice is about a Poisson variable
+    let few_twice = (twice - 2.0 * twice.sqrt()).max(0.0);
+    (distinct + unseen(twice), distinct + unseen(few_twice))
+}
+
+fn state_hash<T: Hash>(state: &T) -> u64 {
+    let mut hasher = FxHasher::default();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test_diagnostics {
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax};
+    use crate::{estimate_dd_size, All, CompilationInputBuilder, CutsetType, DecisionDiagram};
+
+    fn exact_size(problem: &Knapsack) -> usize {
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        let input = CompilationInputBuilder::exact(problem, &KnapsackRelax, &KnapsackRanking)
+            .root()
+            .max_width(usize::MAX)
+            .build()
+            .unwrap();
+        mdd.compile(&input);
+        mdd.node_count()
+    }
+
+    /// A knapsack whose exact dd has many nodes per layer
+    fn wide_knapsack(nb_items: usize) -> Knapsack {
+        Knapsack {
+            capacity: 5 * nb_items + 3,
+            profit: (0..nb_items).map(|i| 10 + (i * 7) % 5).collect(),
+            weight: (0..nb_items).map(|i| 9 + (i * 3) % 4).collect(),
+        }
+    }
+
+    #[test]
+    fn the_estimate_is_within_a_small_factor_of_the_exact_dd() {
+        for nb_items in [10, 24, 40] {
+            let problem = wide_knapsack(nb_items);
+            let exact = exact_size(&problem) as f64;
+            for seed in 0..5 {
+                let estimate = estimate_dd_size(&problem, 200, seed);
+                assert_eq!(estimate, estimate_dd_size(&problem, 200, seed));
+                assert_eq!(nb_items + 1, estimate.layers.len());
+                assert!(estimate.sampled as f64 <= exact);
+                assert!(estimate.sampled as f64 <= estimate.nodes && estimate.nodes <= estimate.upper);
+                assert!(exact / 3.0 <= estimate.nodes && estimate.nodes <= 3.0 * exact, "{} vs {}", estimate.nodes, exact);
+            }
+        }
+    }
+
+    #[test]
+    fn the_dives_meet_all_the_states_of_a_small_dd() {
+        let problem = Knapsack::toy();
+        let estimate = estimate_dd_size(&problem, 200, 0);
+        assert_eq!(exact_size(&problem), estimate.sampled);
+        assert_eq!((41.0, 41.0), (estimate.nodes, estimate.upper));
+        assert!(estimate.fits(41) && !estimate.fits(40));
+    }
+
+    #[test]
+    fn a_wide_dd_does_not_fit_a_small_budget() {
+        let estimate = estimate_dd_size(&wide_knapsack(40), 200, 0);
+        assert!(!estimate.fits(1000));
+        assert!(estimate.fits(20_000));
+    }
+}
@@ -0,0 +1,358 @@
+//! This module lets the solvers work with models hidden behind trait objects,
+//! e.g. when the models are loaded from several crates through a common
+//! interface. `Problem` cannot be made into an object because its
+//! `for_each_in_domain` method is generic: `DynProblem` is its object safe
+//! mirror. Every `Problem` is a `DynProblem`, and a boxed `DynProblem` is a
+//! `Problem` again, which is what the solvers and the dds expect. The other
+//! traits of a model are object safe already: they only need to be boxed.
+//!
+//! # Performance
+//! The dds and the solvers are monomorphized for the boxed types just like
+//! they are for any other model. Going through this layer only costs one
+//! virtual call per call to the model (plus one per decision of a domain,
+//! which is handed over through a `&mut dyn FnMut`).
+//!
+//! # Example
+//! ```
+//! # use engineering::*;
+//! fn solve<S>(problem: BoxedProblem<S>, relaxation: BoxedRelaxation<S>, ranking: BoxedRanking<S>) -> Option<isize>
+//! where
+//!     S: Eq + std::hash::Hash + Clone + Send + Sync,
+//! {
+//!     let width = Fixed(100);
+//!     let mut solver = BarrierParallelSolver::new(&problem, &relaxation, &ranking, &width, CutsetType::Frontier);
+//!     solver.maximize();
+//!     solver.best_value()
+//! }
+//! ```
+
+use crate::{
+    AssignedVars, CompilationInput, Decision, FrontierOrder, Objective, Problem, Relaxation, StateRanking, SubProblem, ValidationError,
+    Variable, WidthHeuristic,
+};
+
+/// The object safe mirror of `Problem`. The methods of both traits only
+/// differ by their `dyn_` prefix: this is what keeps the calls to the methods
+/// of a model unambiguous when both traits are in scope.
+pub trait DynProblem: Send + Sync {
+    type State;
+
+    fn dyn_nb_variables(&self) -> usize;
+    fn dyn_initial_state(&self) -> Self::State;
+    fn dyn_initial_value(&self) -> isize;
+    fn dyn_next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable>;
+    fn dyn_next_variable_with_context(&self, assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &Self::State>)
+        -> Option<Variable>;
+    fn dyn_for_each_in_domain(&self, var: Variable, state: &Self::State, f: &mut dyn FnMut(Decision));
+    fn dyn_domain_size_hint(&self, var: Variable, state: &Self::State) -> Option<usize>;
+    fn dyn_transition(&self, state: &Self::State, decision: Decision) -> Self::State;
+    fn dyn_transition_cost(&self, state: &Self::State, decision: Decision) -> isize;
+    fn dyn_transition_with_cost(&self, state: &Self::State, decision: Decision) -> (Self::State, isize);
+    fn dyn_terminal_value(&self, state: &Self::State) -> isize;
+    fn dyn_impacted_by(&self, var: Variable, state: &Self::State) -> bool;
+    fn dyn_objective(&self) -> Objective;
+    fn dyn_estimate(&self, state: &Self::State) -> isize;
+    fn dyn_estimate_from_path(&self, state: &Self::State, path: &[Decision]) -> isize;
+    fn dyn_estimates_from_path(&self) -> bool;
+    fn dyn_validate_solution(&self, solution: &[Decision]) -> Result<isize, ValidationError<Self::State>>;
+}
+
+impl<P: Problem + Send + Sync> DynProblem for P {
+    type State = P::State;
+
+    fn dyn_nb_variables(&self) -> usize {
+        self.nb_variables()
+    }
+    fn dyn_initial_state(&self) -> Self::State {
+        self.initial_state()
+    }
+    fn dyn_initial_value(&self) -> isize {
+        self.initial_value()
+    }
+    fn dyn_next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        self.next_variable(next_layer)
+    }
+    fn dyn_next_variable_with_context(&self, assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &Self::State>)
+        -> Option<Variable> {
+        self.next_variable_with_context(assigned, next_layer)
+    }
+    fn dyn_for_each_in_domain(&self, var: Variable, state: &Self::State, f: &mut dyn FnMut(Decision)) {
+        self.for_each_in_domain(var, state, f)
+    }
+    fn dyn_domain_size_hint(&self, var: Variable, state: &Self::State) -> Option<usize> {
+        self.domain_size_hint(var, state)
+    }
+    fn dyn_transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        self.transition(state, decision)
+    }
+    fn dyn_transition_cost(&self, state: &Self::State, decision: Decision) -> isize {
+        self.transition_cost(state, decision)
+    }
+    fn dyn_transition_with_cost(&self, state: &Self::State, decision: Decision) -> (Self::State, isize) {
+        self.transition_with_cost(state, decision)
+    }
+    fn dyn_terminal_value(&self, state: &Self::State) -> isize {
+        self.terminal_value(state)
+    }
+    fn dyn_impacted_by(&self, var: Variable, state: &Self::State) -> bool {
+        self.impacted_by(var, state)
+    }
+    fn dyn_objective(&self) -> Objective {
+        self.objective()
+    }
+    fn dyn_estimate(&self, state: &Self::State) -> isize {
+        self.estimate(state)
+    }
+    fn dyn_estimate_from_path(&self, state: &Self::State, path: &[Decision]) -> isize {
+        self.estimate_from_path(state, path)
+    }
+    fn dyn_estimates_from_path(&self) -> bool {
+        self.estimates_from_path()
+    }
+    fn dyn_validate_solution(&self, solution: &[Decision]) -> Result<isize, ValidationError<Self::State>> {
+        self.validate_solution(solution)
+    }
+}
+
+/// A problem behind a trait object
+pub type BoxedProblem<S> = Box<dyn DynProblem<State = S>>;
+/// A relaxation behind a trait object
+pub type BoxedRelaxation<S> = Box<dyn Relaxation<State = S> + Send + Sync>;
+/// A state ranking behind a trait object
+pub type BoxedRanking<S> = Box<dyn StateRanking<State = S> + Send + Sync>;
+/// The input of the compilation of a dd for a model behind trait objects
+pub type DynCompilationInput<'a, S> = CompilationInput<'a, BoxedProblem<S>, BoxedRelaxation<S>, BoxedRanking<S>>;
+
+impl<S> Problem for BoxedProblem<S> {
+    type State = S;
+
+    fn nb_variables(&self) -> usize {
+        self.as_ref().dyn_nb_variables()
+    }
+    fn initial_state(&self) -> S {
+        self.as_ref().dyn_initial_state()
+    }
+    fn initial_value(&self) -> isize {
+        self.as_ref().dyn_initial_value()
+    }
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &S>) -> Option<Variable> {
+        self.as_ref().dyn_next_variable(next_layer)
+    }
+    fn next_variable_with_context(&self, assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &S>) -> Option<Variable> {
+        self.as_ref().dyn_next_variable_with_context(assigned, next_layer)
+    }
+    fn for_each_in_domain<F>(&self, var: Variable, state: &S, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        self.as_ref().dyn_for_each_in_domain(var, state, &mut f)
+    }
+    fn domain_size_hint(&self, var: Variable, state: &S) -> Option<usize> {
+        self.as_ref().dyn_domain_size_hint(var, state)
+    }
+    fn transition(&self, state: &S, decision: Decision) -> S {
+        self.as_ref().dyn_transition(state, decision)
+    }
+    fn transition_cost(&self, state: &S, decision: Decision) -> isize {
+        self.as_ref().dyn_transition_cost(state, decision)
+    }
+    fn transition_with_cost(&self, state: &S, decision: Decision) -> (S, isize) {
+        self.as_ref().dyn_transition_with_cost(state, decision)
+    }
+    fn terminal_value(&self, state: &S) -> isize {
+        self.as_ref().dyn_terminal_value(state)
+    }
+    fn impacted_by(&self, var: Variable, state: &S) -> bool {
+        self.as_ref().dyn_impacted_by(var, state)
+    }
+    fn objective(&self) -> Objective {
+        self.as_ref().dyn_objective()
+    }
+    fn estimate(&self, state: &S) -> isize {
+        self.as_ref().dyn_estimate(state)
+    }
+    fn estimate_from_path(&self, state: &S, path: &[Decision]) -> isize {
+        self.as_ref().dyn_estimate_from_path(state, path)
+    }
+    fn estimates_from_path(&self) -> bool {
+        self.as_ref().dyn_estimates_from_path()
+    }
+    fn validate_solution(&self, solution: &[Decision]) -> Result<isize, ValidationError<S>> {
+        self.as_ref().dyn_validate_solution(solution)
+    }
+}
+
+impl<R: Relaxation + ?Sized> Relaxation for Box<R> {
+    type State = R::State;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        self.as_ref().merge(states)
+    }
+    fn relax(&self, source: &Self::State, dest: &Self::State, new: &Self::State, decision: Decision, cost: isize) -> isize {
+        self.as_ref().relax(source, dest, new, decision, cost)
+    }
+    fn check_merge(&self, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        self.as_ref().check_merge(merged, originals)
+    }
+    fn merge_at_depth(&self, depth: usize, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        self.as_ref().merge_at_depth(depth, states)
+    }
+    fn relax_at_depth(&self, depth: usize, source: &Self::State, dest: &Self::State, new: &Self::State, decision: Decision, cost: isize)
+        -> isize {
+        self.as_ref().relax_at_depth(depth, source, dest, new, decision, cost)
+    }
+    fn check_merge_at_depth(&self, depth: usize, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        self.as_ref().check_merge_at_depth(depth, merged, originals)
+    }
+}
+
+impl<O: StateRanking + ?Sized> StateRanking for Box<O> {
+    type State = O::State;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> std::cmp::Ordering {
+        self.as_ref().compare(a, b)
+    }
+}
+
+impl<S, W: WidthHeuristic<S> + ?Sized> WidthHeuristic<S> for Box<W> {
+    fn max_width(&self, state: &S) -> usize {
+        self.as_ref().max_width(state)
+    }
+    fn max_width_of(&self, sub: &SubProblem<S>) -> usize {
+        self.as_ref().max_width_of(sub)
+    }
+}
+
+impl<T, C: FrontierOrder<T> + ?Sized> FrontierOrder<T> for Box<C> {
+    fn compare(&self, l: &SubProblem<T>, r: &SubProblem<T>) -> std::cmp::Ordering {
+        self.as_ref().compare(l, r)
+    }
+    fn is_ub_ordered(&self) -> bool {
+        self.as_ref().is_ub_ordered()
+    }
+}
+
+#[cfg(test)]
+mod test_dynamic {
+    use std::sync::Arc;
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState, Tweaked};
+    use crate::{
+        All, AssignedVars, BarrierParallelSolver, CompilationInput, CompilationType, CutsetType, DecisionDiagram, Fixed,
+        InterruptibleSolver, MergePolicy, NoDupFrontier, Objective, ParallelSolver, Problem, Solver, SubProblem, Variable,
+    };
+
+    use super::{BoxedProblem, BoxedRanking, BoxedRelaxation, DynCompilationInput};
+
+    fn boxed() -> (BoxedProblem<KnapsackState>, BoxedRelaxation<KnapsackState>, BoxedRanking<KnapsackState>) {
+        (Box::new(Knapsack::toy()), Box::new(KnapsackRelax), Box::new(KnapsackRanking))
+    }
+
+    #[test]
+    fn a_dd_compiles_the_same_through_the_dyn_layer() {
+        let generic = Knapsack::toy();
+        let (problem, relaxation, ranking) = boxed();
+        let input: DynCompilationInput<'_, KnapsackState> = CompilationInput {
+            comp_type: CompilationType::Relaxed,
+            max_width: 2,
+            problem: &problem,
+            relaxation: &relaxation,
+            ranking: &ranking,
+            residual: SubProblem { state: Arc::new(problem.initial_state()), value: 0, path: vec![], ub: isize::MAX, id: 0, parent: None, prefix: None },
+            assigned: AssignedVars::new(problem.nb_variables()),
+            best_lb: isize::MIN,
+            min_relax_depth_offset: 1,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            dominance: None,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: true,
+            timed: false,
+            self_check: false,
+            cancellation: None,
+            incumbent: None,
+        };
+        let mut dynamic = All::new(CutsetType::Frontier);
+        dynamic.compile_dyn(&input);
+
+        let mut expected = All::new(CutsetType::Frontier);
+        expected.compile(&CompilationInput {
+            comp_type: input.comp_type,
+            max_width: input.max_width,
+            problem: &generic,
+            relaxation: &KnapsackRelax,
+            ranking: &KnapsackRanking,
+            residual: input.residual.clone(),
+            assigned: input.assigned.clone(),
+            best_lb: input.best_lb,
+            min_relax_depth_offset: input.min_relax_depth_offset,
+            restrict_first_layers: input.restrict_first_layers,
+            max_forced_layers: input.max_forced_layers,
+            dominance: input.dominance,
+            reuse_prefix: input.reuse_prefix,
+            merge_policy: input.merge_policy,
+            check_merge: input.check_merge,
+            timed: input.timed,
+            self_check: input.self_check,
+            cancellation: input.cancellation,
+            incumbent: input.incumbent,
+        });
+
+        assert_eq!(expected.nb_nodes(), dynamic.nb_nodes());
+        assert_eq!(expected.nb_edges(), dynamic.nb_edges());
+        assert_eq!(expected.best_value(), dynamic.best_value());
+        assert_eq!(expected.best_solution(), dynamic.best_solution());
+    }
+
+    #[test]
+    fn the_dyn_layer_solves_like_the_generic_path() {
+        let generic = Knapsack::toy();
+        let (problem, relaxation, ranking) = boxed();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+            let mut expected = ParallelSolver::custom(&generic, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, &mut fringe, 1);
+            expected.maximize();
+            let mut fringe = NoDupFrontier::new(&ranking);
+            let mut solver = ParallelSolver::custom(&problem, &relaxation, &ranking, &Fixed(2), cutset_type, &mut fringe, 1);
+            solver.maximize();
+
+            assert_eq!(Some(13), solver.best_value());
+            assert_eq!(expected.best_solution(), solver.best_solution());
+            assert_eq!(expected.get_explored(), solver.get_explored());
+            assert_eq!(expected.get_compilation_statistics(), solver.get_compilation_statistics());
+
+            let mut expected = BarrierParallelSolver::custom(&generic, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 1);
+            expected.maximize();
+            let mut solver = BarrierParallelSolver::custom(&problem, &relaxation, &ranking, &Fixed(2), cutset_type, 1);
+            solver.maximize();
+
+            assert_eq!(Some(13), solver.best_value());
+            assert_eq!(expected.best_solution(), solver.best_solution());
+            assert_eq!(expected.best_upper_bound(), solver.best_upper_bound());
+            assert_eq!(expected.get_explored(), solver.get_explored());
+        }
+    }
+
+    #[test]
+    fn a_declared_minimization_keeps_its_sense_through_the_dyn_layer() {
+        let problem: BoxedProblem<KnapsackState> = Box::new(Tweaked::spare_capacity(Knapsack::toy(), 3));
+        let relaxation: BoxedRelaxation<KnapsackState> = Box::new(KnapsackRelax);
+        let ranking: BoxedRanking<KnapsackState> = Box::new(KnapsackRanking);
+        assert_eq!(Objective::Minimize, problem.objective());
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &relaxation, &ranking, &Fixed(2), CutsetType::Frontier, 2);
+        solver.minimize();
+        assert_eq!(Some(12), solver.best_value());
+        assert_eq!(Ok(12), problem.validate_solution(&solver.best_solution().unwrap()));
+    }
+
+    #[test]
+    fn the_domain_size_hints_go_through_the_dyn_layer() {
+        let generic = Tweaked::hinted(Knapsack::toy());
+        let problem: BoxedProblem<KnapsackState> = Box::new(generic.clone());
+        let state = problem.initial_state();
+        assert!(problem.domain_size_hint(Variable(0), &state).is_some());
+        assert_eq!(generic.domain_size_hint(Variable(0), &state), problem.domain_size_hint(Variable(0), &state));
+    }
+}
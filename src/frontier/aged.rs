@@ -0,0 +1,175 @@
+use compare::Compare;
+use std::cmp::Ordering;
+
+use crate::{Frontier, PushOutcome, StateRanking, SubProblem};
+use binary_heap_plus::BinaryHeap;
+
+/// A node of the frontier, along with the number of pops that had occurred
+/// when it was pushed.
+struct Aged<T> {
+    node: SubProblem<T>,
+    stamp: usize,
+}
+
+/// This ordering favors the nodes having the greatest upper bound; except that
+/// each node gains a bonus of `weight` for each pop that occurred since it was
+/// pushed. This prevents nodes having a slightly lower ub (typically the deep
+/// ones) from sitting on the frontier forever.
+///
+/// # Note
+/// All the nodes age at the same pace. Hence, comparing `ub + weight * (pops - stamp)`
+/// is the same as comparing `ub - weight * stamp`, which does not change over time.
+#[derive(Debug, Clone, Copy)]
+struct AgedMaxUB<'a, O: StateRanking> {
+    ranking: &'a O,
+    weight: usize,
+}
+impl<O: StateRanking> AgedMaxUB<'_, O> {
+    fn priority(&self, x: &Aged<O::State>) -> isize {
+        let malus = self.weight.saturating_mul(x.stamp).min(isize::MAX as usize) as isize;
+        x.node.ub.saturating_sub(malus)
+    }
+}
+impl<O: StateRanking> Compare<Aged<O::State>> for AgedMaxUB<'_, O> {
+    fn compare(&self, l: &Aged<O::State>, r: &Aged<O::State>) -> Ordering {
+        self.priority(l).cmp(&self.priority(r))
+            .then_with(|| self.ranking.compare(&l.node.state, &r.node.state))
+    }
+}
+
+/// A frontier which orders the nodes by upper bound while ageing the nodes
+/// that remain on it (see `AgedMaxUB`). With a weight of zero, it behaves
+/// exactly as the `SimpleFrontier`.
+pub struct AgedFrontier<'a, O: StateRanking> {
+    heap: BinaryHeap<Aged<O::State>, AgedMaxUB<'a, O>>,
+    /// The bonus gained by a node for each pop occurring while it waits
+    weight: usize,
+    /// The number of nodes popped so far
+    pops: usize,
+}
+impl<'a, O: StateRanking> AgedFrontier<'a, O> {
+    pub fn new(ranking: &'a O, weight: usize) -> Self {
+        Self {
+            heap: BinaryHeap::from_vec_cmp(vec![], AgedMaxUB { ranking, weight }),
+            weight,
+            pops: 0,
+        }
+    }
+}
+impl<O: StateRanking> Frontier for AgedFrontier<'_, O> {
+    type State = O::State;
+
+    fn push(&mut self, node: SubProblem<O::State>) -> PushOutcome<O::State> {
+        self.heap.push(Aged { node, stamp: self.pops });
+        PushOutcome::Inserted
+    }
+
+    fn pop(&mut self) -> Option<SubProblem<O::State>> {
+        let aged = self.heap.pop()?;
+        self.pops += 1;
+        Some(aged.node)
+    }
+
+    fn peek(&self) -> Option<&SubProblem<O::State>> {
+        self.heap.peek().map(|aged| &aged.node)
+    }
+
+    fn clear(&mut self) {
+        self.heap.clear()
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// The bonus is only meant to be used for the ordering: the nodes are no
+    /// longer popped by decreasing ub as soon as they can age.
+    fn is_ub_ordered(&self) -> bool {
+        self.weight == 0
+    }
+
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<O::State>) -> bool) {
+        let kept = self.heap.drain().filter(|aged| f(&aged.node)).collect::<Vec<_>>();
+        self.heap.extend(kept);
+    }
+
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<O::State>)) {
+        let step = self.heap.len().div_ceil(count.max(1)).max(1);
+        self.heap.iter().step_by(step).for_each(|aged| f(&aged.node));
+    }
+}
+
+#[cfg(test)]
+mod test_aged {
+    use std::sync::Arc;
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState};
+    use crate::{AgedFrontier, CutsetType, Decision, Fixed, Frontier, ParallelSolver, SimpleFrontier, Solver, SubProblem, Variable};
+
+    fn node(depth: usize, ub: isize) -> SubProblem<KnapsackState> {
+        SubProblem {
+            state: Arc::new(KnapsackState { depth, capacity: 0 }),
+            value: 0,
+            path: vec![Decision { var: Variable(0), value: 0 }; depth],
+            ub,
+            id: 0,
+            parent: None,
+            prefix: None,
+        }
+    }
+
+    /// Pops the frontier until the deep node comes out while the shallow
+    /// nodes keep spawning children with a (slightly) better ub. Returns the
+    /// number of pops which were needed (if it ever came out).
+    fn pops_until_deep_node(frontier: &mut dyn Frontier<State = KnapsackState>) -> Option<usize> {
+        frontier.push(node(10, 95));
+        frontier.push(node(1, 100));
+        for pops in 1..=100 {
+            let nn = frontier.pop().unwrap();
+            if nn.path.len() == 10 {
+                return Some(pops);
+            }
+            frontier.push(node(1, 100));
+        }
+        None
+    }
+
+    #[test]
+    fn a_zero_weight_behaves_as_max_ub() {
+        let ranking = KnapsackRanking;
+        let mut aged = AgedFrontier::new(&ranking, 0);
+        let mut simple = SimpleFrontier::new(&ranking);
+        for (depth, ub) in [(1, 4), (2, 8), (3, 8), (4, 1), (5, 6)] {
+            aged.push(node(depth, ub));
+            simple.push(node(depth, ub));
+        }
+        assert!(aged.is_ub_ordered());
+        while let Some(expected) = simple.pop() {
+            let actual = aged.pop().unwrap();
+            assert_eq!(expected.ub, actual.ub);
+            assert_eq!(expected.state, actual.state);
+        }
+        assert!(aged.is_empty());
+    }
+
+    #[test]
+    fn a_deep_node_with_a_lower_ub_eventually_gets_popped() {
+        let ranking = KnapsackRanking;
+        assert_eq!(None, pops_until_deep_node(&mut SimpleFrontier::new(&ranking)));
+        assert_eq!(None, pops_until_deep_node(&mut AgedFrontier::new(&ranking, 0)));
+        assert_eq!(Some(4), pops_until_deep_node(&mut AgedFrontier::new(&ranking, 2)));
+
+        assert!(!AgedFrontier::new(&ranking, 1).is_ub_ordered());
+    }
+
+    #[test]
+    fn ageing_does_not_break_the_bound_based_termination() {
+        let problem = Knapsack::toy();
+        for weight in [0, 1, 1000] {
+            let mut fringe = AgedFrontier::new(&KnapsackRanking, weight);
+            let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 2);
+            solver.maximize();
+            assert_eq!(Some(13), solver.best_value());
+        }
+    }
+}
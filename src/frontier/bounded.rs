@@ -0,0 +1,164 @@
+use crate::{Frontier, PushOutcome, StateRanking, SubProblem};
+use binary_heap_plus::BinaryHeap;
+
+use super::{ByOrder, FrontierOrder, MaxUB};
+
+/// A frontier which never holds more than `capacity` nodes. It pops its nodes
+/// by decreasing upper bound, just like the `SimpleFrontier`; but once it is
+/// full, each push evicts the node having the lowest upper bound (which may be
+/// the one being pushed, see `PushOutcome::Evicted`).
+///
+/// # Note
+/// This is a heuristic frontier: the subproblems of the evicted nodes are
+/// never explored, so that a resolution which evicted some node may miss the
+/// optimum, and its best upper bound no longer accounts for that node. It
+/// only proves the optimality of its best solution when nothing was evicted.
+/// Besides, a push onto a full frontier takes a time linear in its capacity.
+pub struct BoundedFrontier<'a, O: StateRanking> {
+    heap: BinaryHeap<SubProblem<O::State>, ByOrder<MaxUB<'a, O>>>,
+    ranking: &'a O,
+    capacity: usize,
+    /// The number of nodes that were evicted since the frontier was created
+    evicted: usize,
+}
+impl<'a, O: StateRanking> BoundedFrontier<'a, O> {
+    /// Creates a frontier holding at most `capacity` nodes
+    ///
+    /// # Panics
+    /// When the capacity is zero
+    pub fn new(ranking: &'a O, capacity: usize) -> Self {
+        assert!(capacity > 0, "a bounded frontier holds at least one node");
+        Self {
+            heap: BinaryHeap::from_vec_cmp(Vec::with_capacity(capacity), ByOrder(MaxUB(ranking))),
+            ranking,
+            capacity,
+            evicted: 0,
+        }
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Returns the number of nodes that the frontier evicted so far: the
+    /// resolution is only complete when this is zero
+    pub fn evicted(&self) -> usize {
+        self.evicted
+    }
+}
+impl<O: StateRanking> Frontier for BoundedFrontier<'_, O> {
+    type State = O::State;
+
+    fn push(&mut self, node: SubProblem<O::State>) -> PushOutcome<O::State> {
+        if self.heap.len() < self.capacity {
+            self.heap.push(node);
+            return PushOutcome::Inserted;
+        }
+        self.evicted += 1;
+        let order = MaxUB(self.ranking);
+        let (worst, worst_node) = self
+            .heap
+            .iter()
+            .enumerate()
+            .min_by(|(_, l), (_, r)| order.compare(l, r))
+            .expect("a full frontier holds some node");
+        if order.compare(worst_node, &node).is_ge() {
+            return PushOutcome::Evicted(node);
+        }
+        let empty = BinaryHeap::from_vec_cmp(vec![], ByOrder(MaxUB(self.ranking)));
+        let mut nodes = std::mem::replace(&mut self.heap, empty).into_vec();
+        let dropped = nodes.swap_remove(worst);
+        nodes.push(node);
+        self.heap = BinaryHeap::from_vec_cmp(nodes, ByOrder(order));
+        PushOutcome::Evicted(dropped)
+    }
+
+    fn pop(&mut self) -> Option<SubProblem<O::State>> {
+        self.heap.pop()
+    }
+
+    fn peek(&self) -> Option<&SubProblem<O::State>> {
+        self.heap.peek()
+    }
+
+    fn clear(&mut self) {
+        self.heap.clear()
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<O::State>) -> bool) {
+        let kept = self.heap.drain().filter(|node| f(node)).collect::<Vec<_>>();
+        self.heap.extend(kept);
+    }
+
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<O::State>)) {
+        let step = self.heap.len().div_ceil(count.max(1)).max(1);
+        self.heap.iter().step_by(step).for_each(f);
+    }
+}
+
+#[cfg(test)]
+mod test_bounded {
+    use std::sync::Arc;
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState};
+    use crate::{
+        BoundedFrontier, CutsetType, Fixed, Frontier, InterruptibleSolver, NodeCutoff, ParallelSolver, PushOutcome, ResolutionStatus,
+        Solver, SubProblem,
+    };
+
+    fn node(capacity: usize, ub: isize) -> SubProblem<KnapsackState> {
+        SubProblem { state: Arc::new(KnapsackState { depth: 1, capacity }), value: 0, path: vec![], ub, id: 0, parent: None, prefix: None }
+    }
+
+    #[test]
+    fn a_full_frontier_evicts_the_lowest_upper_bound() {
+        let mut fringe = BoundedFrontier::new(&KnapsackRanking, 3);
+        for (capacity, ub) in [(0, 9), (1, 20), (2, 5)] {
+            assert!(matches!(fringe.push(node(capacity, ub)), PushOutcome::Inserted));
+        }
+        // the pushed node is worse than all the others
+        assert!(matches!(fringe.push(node(3, 4)), PushOutcome::Evicted(n) if n.ub == 4));
+        // the pushed node is better than the worst one
+        assert!(matches!(fringe.push(node(4, 12)), PushOutcome::Evicted(n) if n.ub == 5));
+        assert_eq!((3, 2), (fringe.len(), fringe.evicted()));
+        assert_eq!(Some(20), fringe.peek().map(|n| n.ub));
+        for expected in [20, 12, 9] {
+            assert_eq!(Some(expected), fringe.pop().map(|n| n.ub));
+        }
+        assert!(fringe.is_empty() && fringe.is_ub_ordered());
+    }
+
+    #[test]
+    fn a_large_enough_frontier_proves_the_optimum() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let problem = Knapsack::toy();
+        let mut fringe = BoundedFrontier::new(&KnapsackRanking, 100);
+        assert_send_sync(&fringe);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 2);
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_cutoff(NodeCutoff(usize::MAX)));
+        assert_eq!(Some(13), solver.best_value());
+        assert_eq!(0, fringe.evicted());
+    }
+
+    #[test]
+    fn a_frontier_which_evicted_nodes_proves_nothing() {
+        // the single slot of the frontier cannot keep the branch of the optimum (8)
+        let problem = Knapsack::funnel();
+        let mut fringe = BoundedFrontier::new(&KnapsackRanking, 1);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 1);
+        assert_eq!(ResolutionStatus::Bounded, solver.maximize_with_cutoff(NodeCutoff(usize::MAX)));
+        assert_eq!(Some(7), solver.best_value());
+        // the evicted nodes still bound the objective
+        assert!(solver.best_upper_bound() >= 8);
+        drop(solver);
+        assert!(fringe.evicted() > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a bounded frontier holds at least one node")]
+    fn a_frontier_cannot_be_bounded_to_nothing() {
+        BoundedFrontier::new(&KnapsackRanking, 0);
+    }
+}
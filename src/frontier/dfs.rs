@@ -0,0 +1,76 @@
+use std::{cmp::Ordering, hash::Hash};
+
+use crate::{Frontier, NoDupFrontier, PushOutcome, StateRanking, SubProblem};
+
+use super::FrontierOrder;
+
+/// This order favors the deepest nodes (those having the longest path from
+/// the root), then those having the greatest upper bound. Among the nodes
+/// having the same depth and upper bound, the state ranking breaks the ties.
+/// Just like `MaxValue`, it tends to reach complete solutions early, but it
+/// cannot stop the resolution until each node of the frontier was found not
+/// to improve the best solution.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxDepth<'a, O: StateRanking>(pub &'a O);
+impl<O: StateRanking> FrontierOrder<O::State> for MaxDepth<'_, O> {
+    fn compare(&self, l: &SubProblem<O::State>, r: &SubProblem<O::State>) -> Ordering {
+        l.depth().cmp(&r.depth())
+            .then_with(|| l.ub.cmp(&r.ub))
+            .then_with(|| self.0.compare(&l.state, &r.state))
+    }
+    fn is_ub_ordered(&self) -> bool {
+        false
+    }
+}
+
+/// A frontier which explores the deepest subproblems first (see `MaxDepth`),
+/// so as to find feasible solutions quickly. It never holds two nodes having
+/// the same state: it is a `NoDupFrontier` which pops its nodes in the
+/// `MaxDepth` order.
+pub struct DfsFrontier<'a, O>(NoDupFrontier<'a, O, MaxDepth<'a, O>>)
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone;
+
+impl<'a, O> DfsFrontier<'a, O>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+{
+    pub fn new(ranking: &'a O) -> Self {
+        Self(NoDupFrontier::with_order(MaxDepth(ranking)))
+    }
+}
+
+impl<O> Frontier for DfsFrontier<'_, O>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+{
+    type State = O::State;
+
+    fn push(&mut self, node: SubProblem<O::State>) -> PushOutcome<O::State> {
+        self.0.push(node)
+    }
+    fn pop(&mut self) -> Option<SubProblem<O::State>> {
+        self.0.pop()
+    }
+    fn peek(&self) -> Option<&SubProblem<O::State>> {
+        self.0.peek()
+    }
+    fn clear(&mut self) {
+        self.0.clear()
+    }
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    fn is_ub_ordered(&self) -> bool {
+        self.0.is_ub_ordered()
+    }
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<O::State>) -> bool) {
+        self.0.retain(f)
+    }
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<O::State>)) {
+        self.0.sample(count, f)
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{Frontier, PushOutcome, StateRanking, SubProblem};
+use binary_heap_plus::BinaryHeap;
+
+use super::{ByOrder, MaxUB};
+
+type Layer<'a, O> = BinaryHeap<SubProblem<<O as StateRanking>::State>, ByOrder<MaxUB<'a, O>>>;
+
+/// A frontier which explores the subproblems layer by layer: all the nodes
+/// at some depth are popped before any deeper node (and, within a layer, the
+/// node having the greatest upper bound is popped first). As the nodes of a
+/// layer are all done before the next one is started, the solvers need not
+/// keep the barrier entries of the shallower layers around.
+///
+/// Since the nodes are not popped by decreasing upper bound across layers,
+/// this frontier is not ub ordered: its nodes are pruned one at a time.
+pub struct LayeredFrontier<'a, O: StateRanking> {
+    ranking: &'a O,
+    /// The nodes of each depth
+    layers: Vec<Layer<'a, O>>,
+    /// No layer shallower than this one holds a node
+    shallowest: usize,
+    len: usize,
+}
+impl<'a, O: StateRanking> LayeredFrontier<'a, O> {
+    pub fn new(ranking: &'a O) -> Self {
+        Self { ranking, layers: vec![], shallowest: 0, len: 0 }
+    }
+}
+impl<O: StateRanking> Frontier for LayeredFrontier<'_, O> {
+    type State = O::State;
+
+    fn push(&mut self, node: SubProblem<O::State>) -> PushOutcome<O::State> {
+        let depth = node.depth();
+        while self.layers.len() <= depth {
+            self.layers.push(BinaryHeap::from_vec_cmp(vec![], ByOrder(MaxUB(self.ranking))));
+        }
+        self.layers[depth].push(node);
+        self.shallowest = self.shallowest.min(depth);
+        self.len += 1;
+        PushOutcome::Inserted
+    }
+
+    fn pop(&mut self) -> Option<SubProblem<O::State>> {
+        while self.shallowest < self.layers.len() {
+            if let Some(node) = self.layers[self.shallowest].pop() {
+                self.len -= 1;
+                return Some(node);
+            }
+            self.shallowest += 1;
+        }
+        None
+    }
+
+    fn clear(&mut self) {
+        self.layers.iter_mut().for_each(BinaryHeap::clear);
+        self.shallowest = 0;
+        self.len = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_ub_ordered(&self) -> bool {
+        false
+    }
+
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<O::State>) -> bool) {
+        for layer in self.layers.iter_mut() {
+            let kept = layer.drain().filter(|node| f(node)).collect::<Vec<_>>();
+            layer.extend(kept);
+        }
+        self.len = self.layers.iter().map(BinaryHeap::len).sum();
+    }
+
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<O::State>)) {
+        let step = self.len.div_ceil(count.max(1)).max(1);
+        self.layers.iter().flat_map(BinaryHeap::iter).step_by(step).for_each(f);
+    }
+}
@@ -1,20 +1,313 @@
-//! This module provides the implementation of usual frontiers.
+//! This module provides the implementation of usual frontiers, along with the
+//! orders in which they can pop their nodes.
 use compare::Compare;
-use std::cmp::Ordering;
+use std::{cmp::Ordering, sync::Arc};
 
 use crate::{StateRanking, SubProblem};
 
+/// The order in which a frontier pops its nodes: the greatest node is the
+/// first one to be popped. Raising the upper bound of a node must never make
+/// it smaller, which lets the `NoDupFrontier` merge the bound of a duplicate
+/// into the node it keeps without comparing them.
+pub trait FrontierOrder<T> {
+    fn compare(&self, l: &SubProblem<T>, r: &SubProblem<T>) -> Ordering;
+    /// Returns true iff the greatest node of this order is always one having
+    /// the greatest upper bound (see `Frontier::is_ub_ordered`)
+    fn is_ub_ordered(&self) -> bool;
+}
+
+/// This order favors the nodes having the greatest upper bound. Among the
+/// nodes having the same upper bound, the state ranking breaks the ties.
 #[derive(Debug, Clone, Copy)]
-struct MaxUB<'a, O: StateRanking>(pub &'a O);
-impl<O: StateRanking> Compare<SubProblem<O::State>> for MaxUB<'_, O> {
+pub struct MaxUB<'a, O: StateRanking>(pub &'a O);
+impl<O: StateRanking> FrontierOrder<O::State> for MaxUB<'_, O> {
     fn compare(&self, l: &SubProblem<O::State>, r: &SubProblem<O::State>) -> Ordering {
         l.ub.cmp(&r.ub)
             .then_with(|| self.0.compare(&l.state, &r.state))
     }
+    fn is_ub_ordered(&self) -> bool {
+        true
+    }
+}
+
+/// This order favors the nodes having the greatest value (the longest path
+/// from the root), then those having the greatest upper bound. As the value
+/// grows with the depth, this is much like a depth first search: it tends to
+/// improve the best solution earlier, but it cannot stop the resolution until
+/// each node of the frontier was found not to improve the best solution.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxValue<'a, O: StateRanking>(pub &'a O);
+impl<O: StateRanking> FrontierOrder<O::State> for MaxValue<'_, O> {
+    fn compare(&self, l: &SubProblem<O::State>, r: &SubProblem<O::State>) -> Ordering {
+        l.value.cmp(&r.value)
+            .then_with(|| l.ub.cmp(&r.ub))
+            .then_with(|| self.0.compare(&l.state, &r.state))
+    }
+    fn is_ub_ordered(&self) -> bool {
+        false
+    }
+}
+
+/// An order shared among several frontiers (e.g. the layers of a
+/// `StratifiedFrontier`) is the order it wraps
+impl<T, C: FrontierOrder<T> + ?Sized> FrontierOrder<T> for Arc<C> {
+    fn compare(&self, l: &SubProblem<T>, r: &SubProblem<T>) -> Ordering {
+        self.as_ref().compare(l, r)
+    }
+    fn is_ub_ordered(&self) -> bool {
+        self.as_ref().is_ub_ordered()
+    }
 }
 
+/// Lets a binary heap be ordered by a frontier order
+#[derive(Debug, Clone, Copy)]
+struct ByOrder<C>(C);
+impl<T, C: FrontierOrder<T>> Compare<SubProblem<T>> for ByOrder<C> {
+    fn compare(&self, l: &SubProblem<T>, r: &SubProblem<T>) -> Ordering {
+        self.0.compare(l, r)
+    }
+}
+
+/// The number of nodes which are sampled by default to estimate the fraction
+/// of the fringe that could be shed
+pub const DEFAULT_SHED_SAMPLES: usize = 32;
+
+/// Tells when the solvers shed the nodes of their fringe which can no longer
+/// improve the best solution, rather than dropping them once they are popped.
+/// Each time the best solution improves, the fringe is shed when the best
+/// value has grown by more than `margin` since the last shed, or when more
+/// than `fraction` of the nodes sampled from the fringe cannot improve it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shedding {
+    pub margin: isize,
+    pub fraction: f64,
+    /// The number of nodes sampled to estimate the fraction of the fringe
+    /// which could be shed (`DEFAULT_SHED_SAMPLES` by default)
+    pub samples: usize,
+}
+impl Shedding {
+    pub fn new(margin: isize, fraction: f64) -> Self {
+        Self { margin, fraction, samples: DEFAULT_SHED_SAMPLES }
+    }
+    /// Sets the number of nodes sampled to estimate the fraction of the
+    /// fringe which could be shed
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+}
+
+pub mod aged;
+pub mod bounded;
+pub mod dfs;
+pub mod layered;
 pub mod no_dup;
 pub mod simple;
+pub mod stratified;
 
+pub use aged::*;
+pub use bounded::*;
+pub use dfs::*;
+pub use layered::*;
 pub use no_dup::*;
 pub use simple::*;
+pub use stratified::*;
+
+#[cfg(test)]
+mod test_frontier {
+    use std::sync::Arc;
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState};
+    use crate::{
+        BarrierParallelSolver, CutsetType, Decision, DfsFrontier, Fixed, FringePolicy, Frontier, InterruptibleSolver, LayeredFrontier, MaxValue,
+        NoDupFrontier, ParallelSolver, ResolutionStatus, SimpleFrontier, Solver, StratifiedFrontier, SubProblem, Variable,
+    };
+
+    fn node(capacity: usize, value: isize, ub: isize) -> SubProblem<KnapsackState> {
+        SubProblem { state: Arc::new(KnapsackState { depth: 1, capacity }), value, path: vec![], ub, id: 0, parent: None, prefix: None }
+    }
+
+    #[test]
+    fn max_value_pops_the_best_value_first() {
+        let ranking = KnapsackRanking;
+        let mut simple: SimpleFrontier<KnapsackRanking, _> = SimpleFrontier::with_order(MaxValue(&ranking));
+        let mut no_dup: NoDupFrontier<KnapsackRanking, _> = NoDupFrontier::with_order(MaxValue(&ranking));
+        for (capacity, value, ub) in [(0, 1, 9), (1, 5, 6), (2, 3, 20), (3, 5, 7), (4, 0, 30)] {
+            simple.push(node(capacity, value, ub));
+            no_dup.push(node(capacity, value, ub));
+        }
+        assert!(!simple.is_ub_ordered());
+        assert!(!no_dup.is_ub_ordered());
+        for expected in [(5, 7), (5, 6), (3, 20), (1, 9), (0, 30)] {
+            let popped = simple.pop().unwrap();
+            assert_eq!(expected, (popped.value, popped.ub));
+            let popped = no_dup.pop().unwrap();
+            assert_eq!(expected, (popped.value, popped.ub));
+        }
+        assert!(simple.is_empty() && no_dup.is_empty());
+    }
+
+    #[test]
+    fn peeking_does_not_remove_the_next_node() {
+        let ranking = KnapsackRanking;
+        let mut simple = SimpleFrontier::new(&ranking);
+        let mut no_dup = NoDupFrontier::new(&ranking);
+        assert!(simple.peek().is_none() && no_dup.peek().is_none());
+        for (capacity, value, ub) in [(0, 1, 9), (1, 5, 6), (2, 3, 20), (0, 4, 2)] {
+            simple.push(node(capacity, value, ub));
+            no_dup.push(node(capacity, value, ub));
+        }
+        assert_eq!(Some(20), simple.peek().map(|n| n.ub));
+        assert_eq!(Some(20), no_dup.peek().map(|n| n.ub));
+        assert_eq!((4, 3), (simple.len(), no_dup.len()));
+        simple.pop();
+        no_dup.pop();
+        assert_eq!(Some((0, 9)), simple.peek().map(|n| (n.state.capacity, n.ub)));
+        // the duplicate of capacity 0 kept the best value and the best bound
+        assert_eq!(Some((0, 4, 9)), no_dup.peek().map(|n| (n.state.capacity, n.value, n.ub)));
+    }
+
+    #[test]
+    #[should_panic(expected = "peek not supported by this frontier")]
+    fn a_frontier_cannot_be_peeked_at_by_default() {
+        let ranking = KnapsackRanking;
+        let mut layered = LayeredFrontier::new(&ranking);
+        layered.push(node(0, 1, 9));
+        layered.peek();
+    }
+
+    #[test]
+    fn a_duplicate_with_a_better_value_moves_up_the_max_value_order() {
+        let ranking = KnapsackRanking;
+        let mut no_dup: NoDupFrontier<KnapsackRanking, _> = NoDupFrontier::with_order(MaxValue(&ranking));
+        no_dup.push(node(0, 1, 9));
+        no_dup.push(node(1, 3, 5));
+        no_dup.push(node(0, 4, 2));
+        let popped = no_dup.pop().unwrap();
+        assert_eq!((0, 4, 9), (popped.state.capacity, popped.value, popped.ub));
+    }
+
+    #[test]
+    fn the_dfs_frontier_pops_the_deepest_node_first() {
+        let ranking = KnapsackRanking;
+        let mut dfs = DfsFrontier::new(&ranking);
+        let deep = |capacity: usize, depth: usize, ub: isize| {
+            let mut node = node(capacity, 0, ub);
+            node.path = vec![Decision { var: Variable(0), value: 0 }; depth];
+            node
+        };
+        for (capacity, depth, ub) in [(0, 1, 9), (1, 3, 2), (2, 2, 20), (3, 3, 7), (0, 2, 4)] {
+            dfs.push(deep(capacity, depth, ub));
+        }
+        // the two nodes of capacity 0 share their state: the merged node keeps
+        // the path of the first one (its value is not worse) and the best bound
+        assert_eq!(4, dfs.len());
+        assert!(!dfs.is_ub_ordered());
+        for expected in [(3, 7), (3, 2), (2, 20), (1, 9)] {
+            let popped = dfs.pop().unwrap();
+            assert_eq!(expected, (popped.depth(), popped.ub));
+        }
+        assert!(dfs.is_empty());
+    }
+
+    #[test]
+    fn the_layered_frontier_pops_the_shallowest_layer_first() {
+        let ranking = KnapsackRanking;
+        let mut layered = LayeredFrontier::new(&ranking);
+        let deep = |capacity: usize, depth: usize, ub: isize| {
+            let mut node = node(capacity, 0, ub);
+            node.path = vec![Decision { var: Variable(0), value: 0 }; depth];
+            node
+        };
+        for (capacity, depth, ub) in [(0, 2, 9), (1, 3, 30), (2, 2, 20), (3, 1, 7)] {
+            layered.push(deep(capacity, depth, ub));
+        }
+        assert_eq!(4, layered.len());
+        assert!(!layered.is_ub_ordered());
+        assert_eq!((1, 7), layered.pop().map(|n| (n.depth(), n.ub)).unwrap());
+        assert_eq!((2, 20), layered.pop().map(|n| (n.depth(), n.ub)).unwrap());
+        // a shallower node goes before the deeper ones
+        layered.push(deep(4, 1, 5));
+        for expected in [(1, 5), (2, 9), (3, 30)] {
+            assert_eq!(expected, layered.pop().map(|n| (n.depth(), n.ub)).unwrap());
+        }
+        assert!(layered.is_empty() && layered.pop().is_none());
+
+        layered.push(deep(0, 2, 9));
+        layered.push(deep(1, 3, 30));
+        layered.retain(&mut |n| n.ub > 10);
+        assert_eq!(1, layered.len());
+        layered.clear();
+        assert!(layered.is_empty());
+    }
+
+    #[test]
+    fn the_optimum_is_proved_whatever_the_order() {
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            for nb_threads in [1, 2] {
+                let mut fringe: NoDupFrontier<KnapsackRanking, _> = NoDupFrontier::with_order(MaxValue(&KnapsackRanking));
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, &mut fringe, nb_threads);
+                assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+                assert_eq!(Some(13), solver.best_value());
+                assert_eq!(13, solver.best_upper_bound());
+
+                let mut fringe = DfsFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, &mut fringe, nb_threads);
+                assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+                assert_eq!(Some(13), solver.best_value());
+
+                let mut fringe = LayeredFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, &mut fringe, nb_threads);
+                assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+                assert_eq!(Some(13), solver.best_value());
+
+                let mut fringe = StratifiedFrontier::new(&KnapsackRanking, FringePolicy::DeepestLayer);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, &mut fringe, nb_threads);
+                assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+                assert_eq!(Some(13), solver.best_value());
+                assert_eq!(13, solver.best_upper_bound());
+
+                let mut solver = BarrierParallelSolver::ordered(
+                    &problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, nb_threads, MaxValue(&KnapsackRanking),
+                );
+                solver.maximize();
+                assert_eq!(Some(13), solver.best_value());
+                assert_eq!(13, solver.best_upper_bound());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn a_snapshot_of_the_frontier_restores_its_nodes() {
+        use crate::PathSegment;
+
+        let ranking = KnapsackRanking;
+        let mut frontier = NoDupFrontier::new(&ranking);
+        let decision = |var, value| Decision { var: Variable(var), value };
+        for (capacity, value, ub) in [(0, 1, 9), (1, 5, 6), (2, 3, 20)] {
+            let mut node = node(capacity, value, ub);
+            node.path = vec![decision(1, capacity as isize)];
+            node.prefix = Some(Arc::new(PathSegment::new(None, vec![decision(0, 1)])));
+            frontier.push(node);
+        }
+        let mut snapshot = vec![];
+        frontier.serialize_to(&mut serde_json::Serializer::new(&mut snapshot)).unwrap();
+
+        let mut restored = NoDupFrontier::new(&ranking);
+        restored.push(node(1, 7, 8));
+        restored.deserialize_from(&mut serde_json::Deserializer::from_slice(&snapshot)).unwrap();
+        assert_eq!(3, restored.len());
+        for expected in [(2, 3, 20), (0, 1, 9), (1, 7, 8)] {
+            let popped = restored.pop().unwrap();
+            assert_eq!(expected, (popped.state.capacity, popped.value, popped.ub));
+            // the node which was already on the frontier keeps its (empty) path
+            if popped.value != 7 {
+                assert_eq!(2, popped.depth());
+                let path = popped.prefix.unwrap().to_vec().into_iter().chain(popped.path).collect::<Vec<_>>();
+                assert_eq!(vec![decision(0, 1), decision(1, expected.0 as isize)], path);
+            }
+        }
+    }
+}
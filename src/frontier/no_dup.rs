@@ -1,15 +1,15 @@
-use compare::Compare;
 use rustc_hash::FxHashMap;
 use std::cmp::Ordering;
+use std::marker::PhantomData;
 use std::cmp::Ordering::{Greater, Less};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::{hash::Hash, sync::Arc};
 
-use crate::{Frontier, StateRanking, SubProblem};
+use crate::{Frontier, PushOutcome, StateRanking, SubProblem};
 
 use self::Action::{BubbleDown, BubbleUp, DoNothing};
 
-use super::MaxUB;
+use super::{FrontierOrder, MaxUB};
 
 /// This is a type-safe identifier for some node in the queue.
 /// Basically, this NodeId equates to the position of the identified
@@ -28,14 +28,15 @@ enum Action {
 /// This is an updatable binary heap backed by a vector which ensures that
 /// items remain ordered in the priority queue while guaranteeing that a
 /// given state will only ever be present *ONCE* in the priority queue (the
-/// node with the longest path to state is the only kept copy).
-pub struct NoDupFrontier<'a, O>
+/// node with the longest path to state is the only kept copy). By default,
+/// the nodes are popped in the `MaxUB` order.
+pub struct NoDupFrontier<'a, O, C = MaxUB<'a, O>>
 where
     O: StateRanking,
     O::State: Eq + Hash + Clone,
 {
     /// This is the comparator used to order the nodes in the binary heap
-    cmp: MaxUB<'a, O>,
+    cmp: C,
     /// A mapping that associates some state to a node identifier.
     states: FxHashMap<Arc<O::State>, NodeId>,
     /// The actual payload (nodes) ordered in the list
@@ -46,12 +47,16 @@ where
     heap: Vec<NodeId>,
     /// The positions in the `nodes` vector that can be recycled.
     recycle_bin: Vec<NodeId>,
+    /// The number of nodes of the heap at each depth (see `len_by_depth`)
+    depth_histogram: Vec<usize>,
+    _ranking: PhantomData<&'a O>,
 }
 
-impl<'a, O> Frontier for NoDupFrontier<'a, O>
+impl<O, C> Frontier for NoDupFrontier<'_, O, C>
 where
     O: StateRanking,
     O::State: Eq + Hash + Clone,
+    C: FrontierOrder<O::State>,
 {
     type State = O::State;
 
@@ -61,42 +66,44 @@ where
     /// # Note:
     /// In the event where the heap already contains a copy `x` of a node having
     /// the same state as the `node` being pushed. The priority of the node
-    /// left in the heap might be affected. If `node` node is "better" (greater
-    /// UB and or longer longest path), the priority of the node will be
-    /// increased. As always, in the event where the newly pushed node has a
-    /// longer longest path than the pre-existing node, that one will be kept.
-    fn push(&mut self, mut node: SubProblem<O::State>) {
+    /// left in the heap might be affected: it is given the greatest UB of both
+    /// nodes, and in the event where the newly pushed node has a longer
+    /// longest path than the pre-existing node, that one will be kept. The
+    /// node which is not kept is handed back to the caller (with its own UB).
+    fn push(&mut self, mut node: SubProblem<O::State>) -> PushOutcome<O::State> {
         let state = Arc::clone(&node.state);
 
-        let action = match self.states.entry(state) {
+        let (action, outcome) = match self.states.entry(state) {
             Occupied(e) => {
                 let id = *e.get();
-
-                // info about the pre-existing node
-                let old_lp = self.nodes[id.0].value;
-                let old_ub = self.nodes[id.0].ub;
-                // info about the new node
-                let new_lp = node.value;
-                let new_ub = node.ub;
-                // make sure that ub is the max of the known ubs
-                node.ub = new_ub.max(old_ub);
-
-                let action = if self.cmp.compare(&node, &self.nodes[id.0]) == Greater {
-                    BubbleUp(id)
+                let old = &self.nodes[id.0];
+                // the merged node has the payload of the node having the best
+                // value, and the best ub of both
+                let ub = node.ub.max(old.ub);
+
+                if node.value > old.value {
+                    node.ub = ub;
+                    // the payload changes (e.g. the same state may be reached
+                    // at another depth): the priority may go either way
+                    let action = match self.cmp.compare(&node, old) {
+                        Greater => BubbleUp(id),
+                        Less => BubbleDown(id),
+                        Ordering::Equal => DoNothing,
+                    };
+                    Self::count_at_depth(&mut self.depth_histogram, node.depth(), true);
+                    let dropped = std::mem::replace(&mut self.nodes[id.0], node);
+                    Self::count_at_depth(&mut self.depth_histogram, dropped.depth(), false);
+                    (action, PushOutcome::Merged(dropped))
                 } else {
-                    DoNothing
-                };
-
-                if new_lp > old_lp {
-                    self.nodes[id.0] = node;
-                }
-                if new_ub > old_ub {
-                    self.nodes[id.0].ub = new_ub;
+                    // only the ub of the existing node may grow, which never
+                    // lowers its priority (see `FrontierOrder`)
+                    let action = if ub > old.ub { BubbleUp(id) } else { DoNothing };
+                    self.nodes[id.0].ub = ub;
+                    (action, PushOutcome::Merged(node))
                 }
-
-                action
             }
             Vacant(e) => {
+                Self::count_at_depth(&mut self.depth_histogram, node.depth(), true);
                 let id = if self.recycle_bin.is_empty() {
                     let id = NodeId(self.nodes.len());
                     self.nodes.push(node);
@@ -111,12 +118,13 @@ where
                 self.heap.push(id);
                 self.pos[id.0] = self.heap.len() - 1;
                 e.insert(id);
-                BubbleUp(id)
+                (BubbleUp(id), PushOutcome::Inserted)
             }
         };
 
         // restore the invariants
         self.process_action(action);
+        outcome
     }
 
     /// Pops the best node out of the heap. Here, the best is defined as the
@@ -139,10 +147,17 @@ where
 
         let node = self.nodes[id.0].clone();
         self.states.remove(&node.state);
+        Self::count_at_depth(&mut self.depth_histogram, node.depth(), false);
 
         Some(node)
     }
 
+    /// Returns the best node of the heap (the one `pop` would yield) without
+    /// touching the heap.
+    fn peek(&self) -> Option<&SubProblem<Self::State>> {
+        self.heap.first().map(|id| &self.nodes[id.0])
+    }
+
     /// Clears the content of the heap to reset it to a state equivalent to
     /// a fresh instantiation of the heap.
     fn clear(&mut self) {
@@ -151,6 +166,7 @@ where
         self.pos.clear();
         self.heap.clear();
         self.recycle_bin.clear();
+        self.depth_histogram.clear();
     }
 
     /// Returns the 'length' of the heap. That is, the number of items that
@@ -158,6 +174,47 @@ where
     fn len(&self) -> usize {
         self.heap.len()
     }
+
+    fn is_ub_ordered(&self) -> bool {
+        self.cmp.is_ub_ordered()
+    }
+
+    /// Only keeps the nodes for which `f` returns true. The nodes which are
+    /// kept are moved to a fresh storage (so that the memory of the others
+    /// is released) and the heap is rebuilt bottom up, in O(n).
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<O::State>) -> bool) {
+        let mut slots = std::mem::take(&mut self.nodes).into_iter().map(Some).collect::<Vec<_>>();
+        let mut remap = vec![None; slots.len()];
+        for id in self.heap.iter() {
+            let node = slots[id.0].take().unwrap();
+            if f(&node) {
+                remap[id.0] = Some(NodeId(self.nodes.len()));
+                self.nodes.push(node);
+            }
+        }
+        self.states.retain(|_, id| match remap[id.0] {
+            Some(kept) => {
+                *id = kept;
+                true
+            }
+            None => false,
+        });
+        self.heap = (0..self.nodes.len()).map(NodeId).collect();
+        self.pos = (0..self.nodes.len()).collect();
+        self.recycle_bin.clear();
+        self.depth_histogram.clear();
+        for node in self.nodes.iter() {
+            Self::count_at_depth(&mut self.depth_histogram, node.depth(), true);
+        }
+        for pos in (0..self.heap.len() / 2).rev() {
+            self.bubble_down(self.heap[pos]);
+        }
+    }
+
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<O::State>)) {
+        let step = self.heap.len().div_ceil(count.max(1)).max(1);
+        self.heap.iter().step_by(step).for_each(|id| f(&self.nodes[id.0]));
+    }
 }
 
 impl<'a, O> NoDupFrontier<'a, O>
@@ -165,16 +222,35 @@ where
     O: StateRanking,
     O::State: Eq + Hash + Clone,
 {
-    /// Creates a new instance of the no dup heap which uses cmp as
-    /// comparison criterion.
+    /// Creates a new instance of the no dup heap which pops the nodes in the
+    /// `MaxUB` order.
     pub fn new(ranking: &'a O) -> Self {
+        Self::with_order(MaxUB(ranking))
+    }
+}
+
+impl<O, C> NoDupFrontier<'_, O, C>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+    C: FrontierOrder<O::State>,
+{
+    /// Creates a new instance of the no dup heap which uses the given order
+    /// as comparison criterion.
+    ///
+    /// # Note
+    /// The ranking cannot be inferred from the order: it must be named, e.g.
+    /// `NoDupFrontier::<MyRanking, _>::with_order(MaxValue(&ranking))`.
+    pub fn with_order(order: C) -> Self {
         Self {
-            cmp: MaxUB(ranking),
+            cmp: order,
             states: Default::default(),
             nodes: vec![],
             pos: vec![],
             heap: vec![],
             recycle_bin: vec![],
+            depth_histogram: vec![],
+            _ranking: PhantomData,
         }
     }
 
@@ -183,6 +259,27 @@ where
         self.heap.is_empty()
     }
 
+    /// Returns the number of nodes of the heap at each depth (the index of
+    /// the slice is the depth of the nodes, see `SubProblem::depth`). The
+    /// counts are kept up to date as the nodes come and go, so this costs
+    /// nothing; the slice may end with depths which no longer hold any node.
+    pub fn len_by_depth(&self) -> &[usize] {
+        &self.depth_histogram
+    }
+
+    /// Internal helper method to count a node in (or out of) the histogram of
+    /// the depths
+    fn count_at_depth(histogram: &mut Vec<usize>, depth: usize, added: bool) {
+        if added {
+            if depth >= histogram.len() {
+                histogram.resize(depth + 1, 0);
+            }
+            histogram[depth] += 1;
+        } else {
+            histogram[depth] -= 1;
+        }
+    }
+
     /// Internal helper method to bubble a node up or down, depending of the
     /// specified action.
     fn process_action(&mut self, action: Action) {
@@ -301,3 +398,195 @@ where
     }
     */
 }
+
+#[cfg(feature = "serde")]
+impl<O, C> NoDupFrontier<'_, O, C>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+    C: FrontierOrder<O::State>,
+{
+    /// Writes a snapshot of the nodes of the frontier (as a sequence of
+    /// subproblems, in no particular order)
+    pub fn serialize_to<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        O::State: serde::Serialize,
+    {
+        serializer.collect_seq(self.heap.iter().map(|id| &self.nodes[id.0]))
+    }
+    /// Pushes the nodes of a snapshot written by `serialize_to` onto the
+    /// frontier. The nodes are merged with those of the frontier which have
+    /// the same state, as if they were pushed one by one.
+    pub fn deserialize_from<'de, D>(&mut self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        O::State: serde::Deserialize<'de>,
+    {
+        let nodes: Vec<SubProblem<O::State>> = serde::Deserialize::deserialize(deserializer)?;
+        for node in nodes {
+            self.push(node);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_no_dup {
+    use std::sync::Arc;
+
+    use std::cmp::Ordering;
+
+    use rustc_hash::FxHashMap;
+
+    use crate::test_utils::{KnapsackRanking, KnapsackState};
+    use crate::{mix, Decision, Frontier, FrontierOrder, MaxDepth, MaxUB, MaxValue, NoDupFrontier, SubProblem, Variable};
+
+    fn node(capacity: usize, ub: isize) -> SubProblem<KnapsackState> {
+        SubProblem { state: Arc::new(KnapsackState { depth: 1, capacity }), value: 0, path: vec![], ub, id: capacity, parent: None, prefix: None }
+    }
+    fn node_at(capacity: usize, depth: usize, value: isize) -> SubProblem<KnapsackState> {
+        let path = (0..depth).map(|i| Decision { var: Variable(i), value: 0 }).collect();
+        SubProblem { value, path, ..node(capacity, 100) }
+    }
+
+    #[test]
+    fn len_by_depth_counts_the_pushed_and_popped_nodes() {
+        let ranking = KnapsackRanking;
+        let mut frontier = NoDupFrontier::new(&ranking);
+        assert!(frontier.len_by_depth().is_empty());
+
+        frontier.push(node_at(1, 0, 0));
+        frontier.push(node_at(2, 2, 0));
+        frontier.push(node_at(3, 2, 0));
+        frontier.push(node_at(4, 3, 0));
+        assert_eq!(&[1, 0, 2, 1], frontier.len_by_depth());
+
+        while frontier.pop().is_some() {}
+        assert_eq!(&[0, 0, 0, 0], frontier.len_by_depth());
+        assert_eq!(0, frontier.len_by_depth().iter().sum::<usize>());
+    }
+    #[test]
+    fn len_by_depth_follows_the_node_which_is_kept_upon_merge() {
+        let ranking = KnapsackRanking;
+        let mut frontier = NoDupFrontier::new(&ranking);
+        frontier.push(node_at(1, 1, 5));
+        // worse value: the node at depth 1 is kept
+        frontier.push(node_at(1, 3, 2));
+        assert_eq!(&[0, 1], frontier.len_by_depth());
+        // better value: the node moves to depth 2
+        frontier.push(node_at(1, 2, 8));
+        assert_eq!(&[0, 0, 1], frontier.len_by_depth());
+        assert_eq!(1, frontier.len());
+    }
+    #[test]
+    fn len_by_depth_is_recomputed_upon_retain_and_clear() {
+        let ranking = KnapsackRanking;
+        let mut frontier = NoDupFrontier::new(&ranking);
+        for capacity in 0..10 {
+            frontier.push(node_at(capacity, capacity % 3, 0));
+        }
+        assert_eq!(&[4, 3, 3], frontier.len_by_depth());
+        frontier.retain(&mut |node| node.depth() != 1);
+        assert_eq!(&[4, 0, 3], frontier.len_by_depth());
+        frontier.clear();
+        assert!(frontier.len_by_depth().is_empty());
+    }
+
+    #[test]
+    fn retain_preserves_the_heap_and_the_state_map() {
+        let ranking = KnapsackRanking;
+        let mut frontier = NoDupFrontier::new(&ranking);
+        for capacity in 0..100 {
+            frontier.push(node(capacity, ((capacity * 37) % 101) as isize));
+        }
+        // leave some ids in the recycle bin
+        for _ in 0..10 {
+            frontier.pop();
+        }
+        frontier.push(node(1000, 50));
+
+        frontier.retain(&mut |node| node.ub % 3 != 0);
+        let kept = frontier.nodes.iter().map(|n| n.state.capacity).collect::<Vec<_>>();
+        assert!(kept.iter().all(|c| (c * 37) % 101 % 3 != 0 || *c == 1000));
+        assert_eq!(kept.len(), frontier.len());
+        assert_eq!(kept.len(), frontier.states.len());
+        for (state, id) in frontier.states.iter() {
+            assert_eq!(state, &frontier.nodes[id.0].state);
+        }
+        for (pos, id) in frontier.heap.iter().enumerate() {
+            assert_eq!(pos, frontier.pos[id.0]);
+            if pos > 0 {
+                assert_ne!(std::cmp::Ordering::Less, frontier.compare_at_pos(frontier.parent(pos), pos));
+            }
+        }
+
+        let mut last = isize::MAX;
+        while let Some(node) = frontier.pop() {
+            assert!(node.ub <= last && node.ub % 3 != 0);
+            last = node.ub;
+        }
+        // a state which was removed can be pushed again
+        frontier.push(node(3, 9));
+        assert_eq!(1, frontier.len());
+    }
+
+    /// Pushes and pops random nodes, many of which share their state, and
+    /// checks that each popped node is the greatest of the frontier (in the
+    /// given order) once its duplicates are merged: it has the best value and
+    /// the best ub of its duplicates
+    fn check_random_pushes_and_pops<C: FrontierOrder<KnapsackState>>(order: C, seed: u64) {
+        let mut frontier: NoDupFrontier<KnapsackRanking, _> = NoDupFrontier::with_order(order);
+        let mut expected: FxHashMap<usize, SubProblem<KnapsackState>> = FxHashMap::default();
+        let mut rng = seed;
+        let mut draw = |bound: u64| {
+            rng = mix(rng);
+            (rng % bound) as usize
+        };
+        for _ in 0..50 {
+            for _ in 0..draw(20) {
+                let pushed = SubProblem { ub: draw(30) as isize, ..node_at(draw(8), draw(5), draw(10) as isize) };
+                let merged = match expected.remove(&pushed.state.capacity) {
+                    Some(old) if old.value >= pushed.value => SubProblem { ub: old.ub.max(pushed.ub), ..old },
+                    Some(old) => SubProblem { ub: old.ub.max(pushed.ub), ..pushed.clone() },
+                    None => pushed.clone(),
+                };
+                expected.insert(merged.state.capacity, merged);
+                frontier.push(pushed);
+            }
+            assert_eq!(expected.len(), frontier.len());
+            for _ in 0..draw(10) {
+                let Some(popped) = frontier.pop() else { break };
+                let merged = expected.remove(&popped.state.capacity).unwrap();
+                assert_eq!((merged.value, merged.ub, merged.depth()), (popped.value, popped.ub, popped.depth()));
+                for other in expected.values() {
+                    assert_ne!(Ordering::Less, frontier.cmp.compare(&popped, other));
+                }
+            }
+        }
+        let mut last: Option<SubProblem<KnapsackState>> = None;
+        while let Some(popped) = frontier.pop() {
+            if let Some(last) = last.as_ref() {
+                assert_ne!(Ordering::Greater, frontier.cmp.compare(&popped, last));
+            }
+            last = Some(popped);
+        }
+    }
+
+    #[test]
+    fn the_merged_duplicates_are_popped_by_decreasing_ub() {
+        for seed in 0..20 {
+            check_random_pushes_and_pops(MaxUB(&KnapsackRanking), seed);
+        }
+    }
+
+    #[test]
+    fn the_merged_duplicates_are_popped_in_any_order() {
+        // a duplicate having a better value may be shallower, which lowers
+        // the priority of the merged node in the `MaxDepth` order
+        for seed in 0..20 {
+            check_random_pushes_and_pops(MaxValue(&KnapsackRanking), seed);
+            check_random_pushes_and_pops(MaxDepth(&KnapsackRanking), seed);
+        }
+    }
+}
@@ -1,29 +1,50 @@
-use crate::{Frontier, StateRanking, SubProblem};
+use std::marker::PhantomData;
+
+use crate::{Frontier, PushOutcome, StateRanking, SubProblem};
 use binary_heap_plus::BinaryHeap;
 
-use super::MaxUB;
+use super::{ByOrder, FrontierOrder, MaxUB};
 
-pub struct SimpleFrontier<'a, O: StateRanking> {
-    heap: BinaryHeap<SubProblem<O::State>, MaxUB<'a, O>>,
+pub struct SimpleFrontier<'a, O: StateRanking, C: FrontierOrder<O::State> = MaxUB<'a, O>> {
+    heap: BinaryHeap<SubProblem<O::State>, ByOrder<C>>,
+    ub_ordered: bool,
+    _ranking: PhantomData<&'a O>,
 }
 impl<'a, O: StateRanking> SimpleFrontier<'a, O> {
     pub fn new(ranking: &'a O) -> Self {
+        Self::with_order(MaxUB(ranking))
+    }
+}
+impl<O: StateRanking, C: FrontierOrder<O::State>> SimpleFrontier<'_, O, C> {
+    /// Creates a frontier which pops its nodes in the given order
+    ///
+    /// # Note
+    /// The ranking cannot be inferred from the order: it must be named, e.g.
+    /// `SimpleFrontier::<MyRanking, _>::with_order(MaxValue(&ranking))`.
+    pub fn with_order(order: C) -> Self {
         Self {
-            heap: BinaryHeap::from_vec_cmp(vec![], MaxUB(ranking)),
+            ub_ordered: order.is_ub_ordered(),
+            heap: BinaryHeap::from_vec_cmp(vec![], ByOrder(order)),
+            _ranking: PhantomData,
         }
     }
 }
-impl<O: StateRanking> Frontier for SimpleFrontier<'_, O> {
+impl<O: StateRanking, C: FrontierOrder<O::State>> Frontier for SimpleFrontier<'_, O, C> {
     type State = O::State;
 
-    fn push(&mut self, node: SubProblem<O::State>) {
-        self.heap.push(node)
+    fn push(&mut self, node: SubProblem<O::State>) -> PushOutcome<O::State> {
+        self.heap.push(node);
+        PushOutcome::Inserted
     }
 
     fn pop(&mut self) -> Option<SubProblem<O::State>> {
         self.heap.pop()
     }
 
+    fn peek(&self) -> Option<&SubProblem<O::State>> {
+        self.heap.peek()
+    }
+
     fn clear(&mut self) {
         self.heap.clear()
     }
@@ -31,4 +52,18 @@ impl<O: StateRanking> Frontier for SimpleFrontier<'_, O> {
     fn len(&self) -> usize {
         self.heap.len()
     }
+
+    fn is_ub_ordered(&self) -> bool {
+        self.ub_ordered
+    }
+
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<O::State>) -> bool) {
+        let kept = self.heap.drain().filter(|node| f(node)).collect::<Vec<_>>();
+        self.heap.extend(kept);
+    }
+
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<O::State>)) {
+        let step = self.heap.len().div_ceil(count.max(1)).max(1);
+        self.heap.iter().step_by(step).for_each(f);
+    }
 }
@@ -0,0 +1,269 @@
+use std::{fmt::Display, hash::Hash, str::FromStr, sync::Arc};
+
+use crate::{Frontier, NoDupFrontier, PushOutcome, StateRanking, SubProblem};
+
+use super::{FrontierOrder, MaxUB};
+
+/// How the fringe of a solver picks the next node to explore (see
+/// `StratifiedFrontier`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FringePolicy {
+    /// The nodes are popped in the order of the fringe, which is by
+    /// decreasing upper bound unless told otherwise. This is what closes the
+    /// gap the fastest, hence the policy to use when proving optimality
+    /// matters.
+    #[default]
+    BestBound,
+    /// The nodes of the deepest layer are popped first (in the order of the
+    /// fringe within their layer). As the deep nodes have the smallest
+    /// subproblems, the fringe stays much smaller than with `BestBound`, but
+    /// the best bound only improves once the shallow layers are done.
+    DeepestLayer,
+}
+impl FromStr for FringePolicy {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bestbound" => Ok(Self::BestBound),
+            "deepest" => Ok(Self::DeepestLayer),
+            _ => Err("The only supported fringe policies are 'bestbound' and 'deepest'"),
+        }
+    }
+}
+impl Display for FringePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BestBound => write!(f, "bestbound"),
+            Self::DeepestLayer => write!(f, "deepest"),
+        }
+    }
+}
+
+/// A frontier which keeps one `NoDupFrontier` per depth and pops its nodes
+/// according to its `FringePolicy`. With `DeepestLayer`, the node it pops is
+/// the first one of the deepest layer which is not empty: when that node can
+/// no longer improve the best solution, neither can the rest of its layer,
+/// and the solvers prune them one at a time until they reach a layer whose
+/// best node still can. With `BestBound`, all the nodes share a single layer:
+/// this is a `NoDupFrontier`.
+///
+/// # Note
+/// Two nodes having the same state are only merged when they are in the same
+/// layer: with `DeepestLayer`, a state reached at several depths is held once
+/// per depth.
+pub struct StratifiedFrontier<'a, O, C = MaxUB<'a, O>>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+{
+    /// The order of the nodes within each layer, which all layers share
+    order: Arc<C>,
+    policy: FringePolicy,
+    /// The nodes of each depth (or all of them in the first layer, when the
+    /// policy is `BestBound`)
+    layers: Vec<NoDupFrontier<'a, O, Arc<C>>>,
+    /// No layer deeper than this one holds a node
+    deepest: usize,
+    len: usize,
+}
+impl<'a, O> StratifiedFrontier<'a, O>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+{
+    /// Creates an empty frontier whose layers pop their nodes in the `MaxUB`
+    /// order
+    pub fn new(ranking: &'a O, policy: FringePolicy) -> Self {
+        Self::with_order(MaxUB(ranking), policy)
+    }
+}
+impl<O, C> StratifiedFrontier<'_, O, C>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+    C: FrontierOrder<O::State>,
+{
+    /// Creates an empty frontier whose layers pop their nodes in the given
+    /// order
+    pub fn with_order(order: C, policy: FringePolicy) -> Self {
+        Self { order: Arc::new(order), policy, layers: vec![], deepest: 0, len: 0 }
+    }
+    pub fn policy(&self) -> FringePolicy {
+        self.policy
+    }
+    /// Changes the policy of the frontier, whose nodes are spread over the
+    /// layers of the new policy
+    pub fn set_policy(&mut self, policy: FringePolicy) {
+        let mut nodes = Vec::with_capacity(self.len);
+        while let Some(node) = self.pop() {
+            nodes.push(node);
+        }
+        self.policy = policy;
+        for node in nodes {
+            self.push(node);
+        }
+    }
+    /// The layer in which a node of the given depth goes
+    fn layer(&self, depth: usize) -> usize {
+        match self.policy {
+            FringePolicy::BestBound => 0,
+            FringePolicy::DeepestLayer => depth,
+        }
+    }
+}
+impl<O, C> Frontier for StratifiedFrontier<'_, O, C>
+where
+    O: StateRanking,
+    O::State: Eq + Hash + Clone,
+    C: FrontierOrder<O::State>,
+{
+    type State = O::State;
+
+    fn push(&mut self, node: SubProblem<O::State>) -> PushOutcome<O::State> {
+        let layer = self.layer(node.depth());
+        while self.layers.len() <= layer {
+            self.layers.push(NoDupFrontier::with_order(self.order.clone()));
+        }
+        let outcome = self.layers[layer].push(node);
+        if let PushOutcome::Inserted = outcome {
+            self.len += 1;
+        }
+        self.deepest = self.deepest.max(layer);
+        outcome
+    }
+
+    fn pop(&mut self) -> Option<SubProblem<O::State>> {
+        loop {
+            if let Some(node) = self.layers.get_mut(self.deepest)?.pop() {
+                self.len -= 1;
+                return Some(node);
+            }
+            if self.deepest == 0 {
+                return None;
+            }
+            self.deepest -= 1;
+        }
+    }
+
+    fn peek(&self) -> Option<&SubProblem<O::State>> {
+        self.layers.iter().take(self.deepest + 1).rev().find_map(|layer| layer.peek())
+    }
+
+    fn clear(&mut self) {
+        self.layers.iter_mut().for_each(|layer| layer.clear());
+        self.deepest = 0;
+        self.len = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_ub_ordered(&self) -> bool {
+        self.policy == FringePolicy::BestBound && self.order.is_ub_ordered()
+    }
+
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<O::State>) -> bool) {
+        self.layers.iter_mut().for_each(|layer| layer.retain(f));
+        self.len = self.layers.iter().map(|layer| layer.len()).sum();
+    }
+
+    /// Samples each layer in proportion to the nodes it holds: all the nodes
+    /// are sampled when `count` is the length of the frontier
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<O::State>)) {
+        let count = count.min(self.len);
+        for layer in self.layers.iter().filter(|layer| !layer.is_empty()) {
+            layer.sample((count * layer.len()).div_ceil(self.len), f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_stratified {
+    use std::sync::Arc;
+
+    use crate::test_utils::{KnapsackRanking, KnapsackState};
+    use crate::{Decision, FringePolicy, Frontier, MaxValue, PushOutcome, StratifiedFrontier, SubProblem, Variable};
+
+    fn node(capacity: usize, depth: usize, value: isize, ub: isize) -> SubProblem<KnapsackState> {
+        SubProblem {
+            state: Arc::new(KnapsackState { depth, capacity }),
+            value,
+            path: vec![Decision { var: Variable(0), value: 0 }; depth],
+            ub,
+            id: 0,
+            parent: None,
+            prefix: None,
+        }
+    }
+
+    #[test]
+    fn the_deepest_layer_goes_first() {
+        let mut fringe = StratifiedFrontier::new(&KnapsackRanking, FringePolicy::DeepestLayer);
+        for (capacity, depth, ub) in [(0, 1, 30), (1, 3, 7), (2, 2, 20), (3, 3, 9), (4, 1, 10)] {
+            fringe.push(node(capacity, depth, 0, ub));
+        }
+        assert!(!fringe.is_ub_ordered());
+        assert_eq!(5, fringe.len());
+        assert_eq!(Some(9), fringe.peek().map(|n| n.ub));
+        for expected in [(3, 9), (3, 7), (2, 20)] {
+            assert_eq!(Some(expected), fringe.pop().map(|n| (n.depth(), n.ub)));
+        }
+        // a deeper node goes before the shallower ones
+        fringe.push(node(5, 4, 0, 1));
+        for expected in [(4, 1), (1, 30), (1, 10)] {
+            assert_eq!(Some(expected), fringe.pop().map(|n| (n.depth(), n.ub)));
+        }
+        assert!(fringe.is_empty() && fringe.pop().is_none() && fringe.peek().is_none());
+    }
+
+    #[test]
+    fn the_best_bound_policy_is_a_no_dup_frontier() {
+        let mut fringe = StratifiedFrontier::new(&KnapsackRanking, FringePolicy::BestBound);
+        for (capacity, depth, ub) in [(0, 1, 30), (1, 3, 7), (2, 2, 20), (3, 3, 9)] {
+            fringe.push(node(capacity, depth, 0, ub));
+        }
+        assert!(fringe.is_ub_ordered());
+        assert!(matches!(fringe.push(node(1, 3, 2, 8)), PushOutcome::Merged(_)));
+        assert_eq!(4, fringe.len());
+        for expected in [30, 20, 9, 8] {
+            assert_eq!(Some(expected), fringe.pop().map(|n| n.ub));
+        }
+
+        let mut fringe: StratifiedFrontier<KnapsackRanking, _> = StratifiedFrontier::with_order(MaxValue(&KnapsackRanking), FringePolicy::BestBound);
+        fringe.push(node(0, 1, 5, 30));
+        assert!(!fringe.is_ub_ordered());
+    }
+
+    #[test]
+    fn the_nodes_follow_a_change_of_policy() {
+        let mut fringe = StratifiedFrontier::new(&KnapsackRanking, FringePolicy::BestBound);
+        for (capacity, depth, ub) in [(0, 1, 30), (1, 3, 7), (2, 2, 20)] {
+            fringe.push(node(capacity, depth, 0, ub));
+        }
+        fringe.set_policy(FringePolicy::DeepestLayer);
+        assert_eq!((FringePolicy::DeepestLayer, 3), (fringe.policy(), fringe.len()));
+        assert_eq!(Some(3), fringe.pop().map(|n| n.depth()));
+    }
+
+    #[test]
+    fn the_whole_frontier_is_sampled_and_retained() {
+        let mut fringe = StratifiedFrontier::new(&KnapsackRanking, FringePolicy::DeepestLayer);
+        for (capacity, depth, ub) in [(0, 1, 30), (1, 3, 7), (2, 2, 20), (3, 3, 9), (4, 1, 10)] {
+            fringe.push(node(capacity, depth, 0, ub));
+        }
+        let mut best_ub = isize::MIN;
+        fringe.sample(fringe.len(), &mut |n| best_ub = best_ub.max(n.ub));
+        assert_eq!(30, best_ub);
+        let mut sampled = 0;
+        fringe.sample(2, &mut |_| sampled += 1);
+        assert!((1..=3).contains(&sampled));
+
+        fringe.retain(&mut |n| n.ub > 9);
+        assert_eq!(3, fringe.len());
+        assert_eq!(Some((2, 20)), fringe.pop().map(|n| (n.depth(), n.ub)));
+        fringe.clear();
+        assert!(fringe.is_empty() && fringe.pop().is_none());
+    }
+}
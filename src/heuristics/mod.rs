@@ -1,4 +1,6 @@
-use crate::WidthHeuristic;
+use std::{cmp::Ordering, sync::atomic::{AtomicUsize, Ordering as AtomicOrdering}};
+
+use crate::{StateRanking, SubProblem, WidthHeuristic};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Fixed(pub usize);
@@ -13,3 +15,268 @@ pub struct NbUnassigned {
     pub nb_vars: usize,
 }
 // Implement WidthHeuristic in the various example models
+
+/// Gives the dds of the subproblems a width which shrinks by `factor` with
+/// each level of depth in the b&b tree, down to 1: the dds near the root are
+/// the widest. Without a subproblem (at the root), the width is `base`.
+#[derive(Debug, Clone, Copy)]
+pub struct DepthDecreasing {
+    pub base: usize,
+    pub factor: usize,
+}
+impl<T> WidthHeuristic<T> for DepthDecreasing {
+    fn max_width(&self, _state: &T) -> usize {
+        self.base
+    }
+    fn max_width_of(&self, sub: &SubProblem<T>) -> usize {
+        self.base.saturating_sub(self.factor.saturating_mul(sub.depth())).max(1)
+    }
+}
+
+/// Gives the smallest of the widths of `A` and `B`
+#[derive(Debug, Clone, Copy)]
+pub struct MinWidth<A, B>(pub A, pub B);
+impl<T, A, B> WidthHeuristic<T> for MinWidth<A, B>
+where
+    A: WidthHeuristic<T>,
+    B: WidthHeuristic<T>,
+{
+    fn max_width(&self, state: &T) -> usize {
+        self.0.max_width(state).min(self.1.max_width(state))
+    }
+    fn max_width_of(&self, sub: &SubProblem<T>) -> usize {
+        self.0.max_width_of(sub).min(self.1.max_width_of(sub))
+    }
+    fn on_restriction(&self, ran_dry: bool) {
+        self.0.on_restriction(ran_dry);
+        self.1.on_restriction(ran_dry);
+    }
+}
+
+/// Gives the largest of the widths of `A` and `B`
+#[derive(Debug, Clone, Copy)]
+pub struct MaxWidth<A, B>(pub A, pub B);
+impl<T, A, B> WidthHeuristic<T> for MaxWidth<A, B>
+where
+    A: WidthHeuristic<T>,
+    B: WidthHeuristic<T>,
+{
+    fn max_width(&self, state: &T) -> usize {
+        self.0.max_width(state).max(self.1.max_width(state))
+    }
+    fn max_width_of(&self, sub: &SubProblem<T>) -> usize {
+        self.0.max_width_of(sub).max(self.1.max_width_of(sub))
+    }
+    fn on_restriction(&self, ran_dry: bool) {
+        self.0.on_restriction(ran_dry);
+        self.1.on_restriction(ran_dry);
+    }
+}
+
+/// Keeps the width of `inner` within `min..=max` (which must not be empty)
+#[derive(Debug, Clone, Copy)]
+pub struct ClampedWidth<W> {
+    pub inner: W,
+    pub min: usize,
+    pub max: usize,
+}
+impl<T, W: WidthHeuristic<T>> WidthHeuristic<T> for ClampedWidth<W> {
+    fn max_width(&self, state: &T) -> usize {
+        self.inner.max_width(state).clamp(self.min, self.max)
+    }
+    fn max_width_of(&self, sub: &SubProblem<T>) -> usize {
+        self.inner.max_width_of(sub).clamp(self.min, self.max)
+    }
+    fn on_restriction(&self, ran_dry: bool) {
+        self.inner.on_restriction(ran_dry);
+    }
+}
+
+/// Multiplies the width of `inner` by a factor which doubles each time more
+/// than `threshold` restricted dds in a row ran out of nodes (see
+/// `DecisionDiagram::ran_dry`): a width too small for the problem grows until
+/// the restricted dds reach the end of the problem again. The factor is
+/// shared by all the threads of a solver, and it never shrinks.
+#[derive(Debug)]
+pub struct AdaptiveWidth<W> {
+    inner: W,
+    threshold: usize,
+    /// The number of restricted dds in a row which ran out of nodes
+    dry: AtomicUsize,
+    factor: AtomicUsize,
+}
+impl<W> AdaptiveWidth<W> {
+    /// The number of restricted dds in a row which may run out of nodes
+    /// before the width is doubled
+    pub const DEFAULT_THRESHOLD: usize = 3;
+
+    pub fn new(inner: W) -> Self {
+        Self::with_threshold(inner, Self::DEFAULT_THRESHOLD)
+    }
+    pub fn with_threshold(inner: W, threshold: usize) -> Self {
+        Self { inner, threshold, dry: AtomicUsize::new(0), factor: AtomicUsize::new(1) }
+    }
+    /// Returns the factor by which the width of `inner` is multiplied
+    pub fn factor(&self) -> usize {
+        self.factor.load(AtomicOrdering::Relaxed)
+    }
+}
+impl<T, W: WidthHeuristic<T>> WidthHeuristic<T> for AdaptiveWidth<W> {
+    fn max_width(&self, state: &T) -> usize {
+        self.inner.max_width(state).saturating_mul(self.factor())
+    }
+    fn max_width_of(&self, sub: &SubProblem<T>) -> usize {
+        self.inner.max_width_of(sub).saturating_mul(self.factor())
+    }
+    fn on_restriction(&self, ran_dry: bool) {
+        self.inner.on_restriction(ran_dry);
+        if !ran_dry {
+            self.dry.store(0, AtomicOrdering::Relaxed);
+            return;
+        }
+        // only the thread which exceeds the threshold doubles the factor
+        let threshold = self.threshold;
+        let exceeded = self.dry.fetch_update(AtomicOrdering::Relaxed, AtomicOrdering::Relaxed, |dry| {
+            Some(if dry >= threshold { 0 } else { dry + 1 })
+        });
+        if exceeded.is_ok_and(|dry| dry >= threshold) {
+            self.factor.fetch_update(AtomicOrdering::Relaxed, AtomicOrdering::Relaxed, |factor| Some(factor.saturating_mul(2))).ok();
+        }
+    }
+}
+
+/// Ranks the states with `A`, and only uses `B` to break the ties of `A`
+#[derive(Debug, Clone, Copy)]
+pub struct LexRanking<A, B>(pub A, pub B);
+impl<A, B> StateRanking for LexRanking<A, B>
+where
+    A: StateRanking,
+    B: StateRanking<State = A::State>,
+{
+    type State = A::State;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        self.0.compare(a, b).then_with(|| self.1.compare(a, b))
+    }
+}
+
+/// Ranks the states in the reverse order of `A`: the worst states of `A`
+/// become the most likely to be kept
+#[derive(Debug, Clone, Copy)]
+pub struct RevRanking<A>(pub A);
+impl<A: StateRanking> StateRanking for RevRanking<A> {
+    type State = A::State;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        self.0.compare(b, a)
+    }
+}
+
+#[cfg(test)]
+mod test_heuristics {
+    use std::cmp::Ordering;
+
+    use std::sync::Arc;
+
+    use crate::test_utils::{KnapsackRanking, KnapsackState};
+    use crate::{Decision, Fixed, StateRanking, SubProblem, Variable, WidthHeuristic};
+
+    use super::{AdaptiveWidth, ClampedWidth, DepthDecreasing, LexRanking, MaxWidth, MinWidth, RevRanking};
+
+    /// Favors the deepest states
+    #[derive(Debug, Clone, Copy)]
+    struct ByDepth;
+    impl StateRanking for ByDepth {
+        type State = KnapsackState;
+
+        fn compare(&self, a: &KnapsackState, b: &KnapsackState) -> Ordering {
+            a.depth.cmp(&b.depth)
+        }
+    }
+
+    #[test]
+    fn the_second_ranking_only_breaks_the_ties_of_the_first() {
+        let state = |depth, capacity| KnapsackState { depth, capacity };
+        let mut states = vec![state(1, 5), state(3, 2), state(2, 5), state(0, 2), state(4, 9)];
+
+        let ranking = LexRanking(RevRanking(KnapsackRanking), ByDepth);
+        let copy = ranking;
+        states.sort_unstable_by(|a, b| copy.compare(b, a));
+        // the smallest capacities first, then the deepest states
+        assert_eq!(vec![state(3, 2), state(0, 2), state(2, 5), state(1, 5), state(4, 9)], states);
+
+        assert_eq!(Ordering::Equal, ranking.compare(&state(1, 5), &state(1, 5)));
+        assert_eq!(Ordering::Greater, LexRanking(KnapsackRanking, ByDepth).compare(&state(0, 9), &state(4, 2)));
+    }
+
+    /// Gives a width of twice the remaining capacity
+    #[derive(Debug, Clone, Copy)]
+    struct ByCapacity;
+    impl WidthHeuristic<KnapsackState> for ByCapacity {
+        fn max_width(&self, state: &KnapsackState) -> usize {
+            2 * state.capacity
+        }
+    }
+
+    #[test]
+    fn the_width_combinators_take_the_min_the_max_or_clamp_the_width() {
+        let state = |capacity| KnapsackState { depth: 0, capacity };
+        let min = MinWidth(ByCapacity, Fixed(10));
+        let max = MaxWidth(ByCapacity, Fixed(10));
+        let copy = (min, max);
+        assert_eq!((8, 10), (min.max_width(&state(4)), max.max_width(&state(4))));
+        assert_eq!((10, 10), (copy.0.max_width(&state(5)), copy.1.max_width(&state(5))));
+        assert_eq!((10, 12), (min.max_width(&state(6)), max.max_width(&state(6))));
+
+        let clamped = ClampedWidth { inner: ByCapacity, min: 4, max: 10 };
+        let widths = [0, 1, 2, 3, 5, 6].map(|capacity| clamped.max_width(&state(capacity)));
+        assert_eq!([4, 4, 4, 6, 10, 10], widths);
+        // an empty dd is never wanted, even when the inner width is zero
+        assert_eq!(1, ClampedWidth { inner: Fixed(0), min: 1, max: 1 }.max_width(&state(3)));
+    }
+
+    #[test]
+    fn the_adaptive_width_doubles_once_too_many_restrictions_ran_dry_in_a_row() {
+        let state = KnapsackState { depth: 0, capacity: 3 };
+        let adaptive = AdaptiveWidth::with_threshold(ByCapacity, 2);
+        for ran_dry in [true, true, false, true, true] {
+            adaptive.on_restriction(ran_dry);
+        }
+        assert_eq!((1, 6), (adaptive.factor(), adaptive.max_width(&state)));
+
+        adaptive.on_restriction(true);
+        assert_eq!((2, 12), (adaptive.factor(), adaptive.max_width(&state)));
+        // the count starts over once the width is doubled
+        for _ in 0..3 {
+            adaptive.on_restriction(true);
+        }
+        assert_eq!(4, adaptive.factor());
+        assert_eq!(1, AdaptiveWidth::new(Fixed(1)).max_width(&state));
+    }
+
+    fn sub(depth: usize) -> SubProblem<KnapsackState> {
+        SubProblem {
+            state: Arc::new(KnapsackState { depth, capacity: 3 }),
+            value: 0,
+            path: vec![Decision { var: Variable(0), value: 0 }; depth],
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        }
+    }
+
+    #[test]
+    fn the_depth_decreasing_width_shrinks_down_to_one() {
+        let heuristic = DepthDecreasing { base: 10, factor: 4 };
+        assert_eq!([10, 6, 2, 1, 1], [0, 1, 2, 3, 100].map(|depth| heuristic.max_width_of(&sub(depth))));
+        assert_eq!(10, heuristic.max_width(&KnapsackState { depth: 3, capacity: 3 }));
+
+        // the combinators hand the subproblem over to their heuristics
+        assert_eq!(6, MaxWidth(Fixed(2), heuristic).max_width_of(&sub(1)));
+        assert_eq!(5, ClampedWidth { inner: heuristic, min: 1, max: 5 }.max_width_of(&sub(0)));
+        assert_eq!(6, AdaptiveWidth::new(heuristic).max_width_of(&sub(1)));
+        // the heuristics which only look at the state are asked for it
+        assert_eq!(6, MinWidth(ByCapacity, heuristic).max_width_of(&sub(0)));
+    }
+}
@@ -1,18 +1,55 @@
 pub mod prelude;
 
+pub mod api;
 pub mod frontier;
 pub mod heuristics;
-pub mod mdd;
+pub mod objective;
 pub mod solver;
+
+// the internals of the crate: they remain reachable for the code which relies
+// on them, but they are not part of its api (see `api`) and may change from
+// one release to the next
+#[doc(hidden)]
+pub mod diagnostics;
+#[doc(hidden)]
+pub mod dynamic;
+#[doc(hidden)]
+pub mod mdd;
+#[doc(hidden)]
+pub mod relaxation;
+#[doc(hidden)]
 pub mod utils;
+#[doc(hidden)]
+pub mod validate;
 
 pub use frontier::*;
 pub use heuristics::*;
-pub use mdd::*;
+pub use objective::*;
 pub use prelude::*;
 pub use solver::*;
 
+// the internals used to be re-exported at the root of the crate: this is
+// deprecated, the code which needs them imports them from their modules and
+// everything else from `api`
+#[doc(hidden)]
+pub use diagnostics::*;
+#[doc(hidden)]
+pub use dynamic::*;
+#[doc(hidden)]
+pub use mdd::*;
+#[doc(hidden)]
+pub use relaxation::*;
+#[doc(hidden)]
+pub use validate::*;
+
+#[doc(hidden)]
 pub use utils::*;
 
 // ony useful for the xp about examples
+#[cfg(feature = "bench-tools")]
 pub mod xputils;
+#[cfg(feature = "bench-tools")]
+pub mod bench;
+
+#[cfg(test)]
+mod test_utils;
@@ -0,0 +1,79 @@
+//! This module implements the arenas in which the dds allocate their nodes
+//! and their edges. An arena is a slab which is allocated once, with a
+//! capacity that can be configured, and it is cleared (but never released)
+//! between two compilations. As each worker of a solver reuses the same dd for
+//! all the subproblems it explores, the memory of its nodes and edges is only
+//! allocated while the first (largest) dds are compiled.
+
+use std::ops::{Deref, DerefMut};
+
+/// A slab of items which are identified by their position
+#[derive(Debug, Clone)]
+pub(crate) struct Arena<T> {
+    items: Vec<T>,
+}
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self { items: vec![] }
+    }
+}
+impl<T> Arena<T> {
+    /// Creates an arena with room for the given number of items
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { items: Vec::with_capacity(capacity) }
+    }
+    /// Adds an item to the arena and returns its position
+    pub fn alloc(&mut self, item: T) -> usize {
+        self.items.push(item);
+        self.items.len() - 1
+    }
+    /// Makes room for (at least) the given number of additional items
+    pub fn reserve(&mut self, additional: usize) {
+        self.items.reserve(additional);
+    }
+    /// Drops the items allocated after the first `len` ones
+    pub fn truncate(&mut self, len: usize) {
+        self.items.truncate(len);
+    }
+    /// Drops all the items of the arena, but keeps its memory
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+    /// The number of items the arena can hold without reallocating its memory
+    pub fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+}
+impl<T> Deref for Arena<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+impl<T> DerefMut for Arena<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+}
+
+#[cfg(test)]
+mod test_arena {
+    use super::Arena;
+
+    #[test]
+    fn clearing_an_arena_keeps_its_memory() {
+        let mut arena = Arena::with_capacity(4);
+        assert_eq!(4, arena.capacity());
+        for i in 0..10 {
+            assert_eq!(i, arena.alloc(i * 2));
+        }
+        assert_eq!(10, arena.len());
+        assert_eq!(8, arena[4]);
+        let capacity = arena.capacity();
+        arena.clear();
+        assert!(arena.is_empty());
+        assert_eq!(capacity, arena.capacity());
+        assert_eq!(0, arena.alloc(7));
+    }
+}
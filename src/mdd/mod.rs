@@ -1,3 +1,4 @@
+mod arena;
 mod node_flags;
 
 mod with_all;
@@ -59,6 +59,10 @@ impl NodeFlags {
     pub const F_DELETED: u8 = 16;
     /// The position of the barrier flag.
     pub const F_BARRIER: u8 = 32;
+    /// The position of the candidate flag.
+    pub const F_CANDIDATE: u8 = 64;
+    /// The position of the exact path flag.
+    pub const F_EXACT_PATH: u8 = 128;
 
     /// Creates a new set of flags, either initialized with exact on or with
     /// relaxed on.
@@ -112,6 +116,18 @@ impl NodeFlags {
     pub fn is_pruned_by_barrier(self) -> bool {
         self.test(NodeFlags::F_BARRIER)
     }
+    /// Returns true iff the candidate flag is turned on: the node belongs to
+    /// one of the cutsets an adaptive dd chooses from
+    #[inline]
+    pub fn is_candidate(self) -> bool {
+        self.test(NodeFlags::F_CANDIDATE)
+    }
+    /// Returns true iff the exact path flag is turned on: following the best
+    /// edges up from the node, an exact node is met before any relaxed one
+    #[inline]
+    pub fn is_exact_path(self) -> bool {
+        self.test(NodeFlags::F_EXACT_PATH)
+    }
     /// Sets the exact flag to the given value
     #[inline]
     pub fn set_exact(&mut self, exact: bool) {
@@ -142,6 +158,16 @@ impl NodeFlags {
     pub fn set_pruned_by_barrier(&mut self, barrier: bool) {
         self.set(NodeFlags::F_BARRIER, barrier)
     }
+    /// Sets the candidate flag to the given value
+    #[inline]
+    pub fn set_candidate(&mut self, candidate: bool) {
+        self.set(NodeFlags::F_CANDIDATE, candidate)
+    }
+    /// Sets the exact path flag to the given value
+    #[inline]
+    pub fn set_exact_path(&mut self, exact_path: bool) {
+        self.set(NodeFlags::F_EXACT_PATH, exact_path)
+    }
     /// Checks whether all the flags encoded in the given mask are turned on.
     /// Otherwise, it returns false
     #[inline]
@@ -262,6 +288,18 @@ mod test_node_flags {
         assert_eq!(true, tested.is_marked());
     }
     #[test]
+    fn is_candidate_iff_marked_so() {
+        let mut tested = NodeFlags::new_exact();
+        assert_eq!(false, tested.is_candidate());
+
+        tested.set_candidate(true);
+        assert_eq!(true, tested.is_candidate());
+        assert_eq!(false, tested.is_cutset());
+
+        tested.set_candidate(false);
+        assert_eq!(false, tested.is_candidate());
+    }
+    #[test]
     fn test_yields_the_value_of_the_flag() {
         let mut tested = NodeFlags::new_exact();
         assert_eq!(true, tested.test(NodeFlags::F_EXACT));
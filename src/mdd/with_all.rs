@@ -1,10 +1,10 @@
-use std::{collections::hash_map::Entry, hash::Hash, sync::Arc};
+use std::{collections::hash_map::Entry, hash::Hash, sync::Arc, time::{Duration, Instant}};
 
 use rustc_hash::FxHashMap;
 
 use crate::{
-    prelude::{CompilationInput, CompilationType, Decision, Problem, Relaxation, StateRanking},
-    DecisionDiagram, SubProblem, CutsetType,
+    prelude::{AssignedVars, CompilationInput, CompilationStats, CompilationType, Decision, Problem, Relaxation, StateRanking, Variable},
+    validate::check_estimate, DecisionDiagram, PathSegment, SubProblem, CutsetType, Violation,
 };
 
 use super::node_flags::NodeFlags;
@@ -40,28 +40,123 @@ struct Edge {
     next: Option<EdgeId>,
 }
 
+/// Where a compilation stands: the layer being developed, the depth of that
+/// layer and of the one above it (relative to the root of the dd), and the
+/// variables assigned on the paths leading to it
+#[derive(Debug, Clone)]
+struct Cursor {
+    curr_l: Vec<NodeId>,
+    depth: usize,
+    above: usize,
+    /// Whether some node of the last expanded layer was branched on
+    branched: bool,
+    assigned: AssignedVars,
+}
+
+/// The layers that a restricted compilation developed before its width first
+/// bound, which are those of the relaxed dd of the same subproblem (see
+/// `DecisionDiagram::resume_relaxed`). The nodes and edges of these layers are
+/// the first ones of the arenas: the restricted dd only appends to them.
+#[derive(Debug, Clone)]
+struct Prefix {
+    nb_nodes: usize,
+    nb_edges: usize,
+    /// The variable of the layer whose width bound, along with the cursor of
+    /// the compilation when it was reached
+    var: Variable,
+    cursor: Cursor,
+    prev_l: Vec<NodeId>,
+    /// The estimates of the nodes of that layer, before the restricted dd
+    /// expanded them
+    rubs: Vec<isize>,
+    best_lb: isize,
+    stats: CompilationStats,
+    explored: usize,
+    avoided_allocations: usize,
+    reservations: usize,
+    reallocations: usize,
+    deleted: usize,
+    growth: Option<f64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct All<T>
 where
     T: Eq + PartialEq + Hash + Clone,
 {
     root_pa: Vec<Decision>,
+    /// The prefix of the residual subproblem, which the nodes of the cutset
+    /// share: only the decisions of `root_pa` made after it go in their paths
+    root_prefix: Option<Arc<PathSegment>>,
+    /// The best path of the node being estimated, from the root of the
+    /// problem (see `Problem::estimates_from_path`)
+    path_buffer: Vec<Decision>,
     //
     nodes: Vec<Node<T>>,
     edges: Vec<Edge>,
+    /// The forced decisions that the arcs (by id) take after their own
+    /// decision, when the layers below them were collapsed (see
+    /// `CompilationInput::max_forced_layers`)
+    forced: FxHashMap<usize, Vec<Decision>>,
     //
     prev_l: Vec<NodeId>,
     next_l: FxHashMap<Arc<T>, NodeId>,
     cutset: Vec<NodeId>,
+    /// The frontier cutset, while an adaptive dd weighs it against the last
+    /// exact layer (which it then gathers in `cutset`)
+    frontier: Vec<NodeId>,
+    /// The exact nodes that a layer too shallow to be merged could not hold
+    /// (see `CompilationInput::restrict_first_layers`), and the greatest of
+    /// their upper bounds
+    dropped: Vec<NodeId>,
+    dropped_ub: Option<isize>,
+    /// The terminal values of the nodes of the last layer which have one
+    /// (see `Problem::terminal_value`)
+    terminal: FxHashMap<NodeId, isize>,
     //
     best_n: Option<NodeId>,
+    /// The best lower bound the dd prunes with: that of the input, unless the
+    /// incumbent improved on it during the compilation (see
+    /// `CompilationInput::incumbent`)
+    best_lb: isize,
     // ebpo
     exact: bool,
     approximate: bool,
+    /// Whether the last compilation was restricted and all the nodes it kept
+    /// in some layer were dead ends
+    ran_dry: bool,
+    /// What the last compilation restricted, merged and pruned
+    stats: CompilationStats,
     //
     cutset_type: CutsetType,
     //
     explored: usize,
+    /// The number of transitions that reached a state which already existed
+    /// in the next layer (hence, for which no arc has been allocated)
+    avoided_allocations: usize,
+    /// The arc of the last state which turned out to be in the next layer
+    /// already: it holds the next state which is looked up in that layer
+    spare: Option<Arc<T>>,
+    /// The number of layers whose memory was reserved before branching
+    reservations: usize,
+    /// The number of times the next layer, the nodes or the edges outgrew
+    /// their memory while a layer was expanded
+    reallocations: usize,
+    /// The number of nodes that the restricted and relaxed layers dropped
+    /// (see `DecisionDiagram::node_count`)
+    deleted: usize,
+    /// The number of nodes of the last layer per node of the one before it
+    growth: Option<f64>,
+    /// The transitions of the layer being expanded, when a timed compilation
+    /// computes them ahead of time
+    transitions: Vec<(NodeId, Decision, T, isize)>,
+    model_time: Duration,
+    framework_time: Duration,
+    /// The violations found by a self checked compilation
+    violations: Vec<Violation<T>>,
+    /// The layers the last compilation kept for the relaxed dd of its
+    /// subproblem (see `CompilationInput::reuse_prefix`)
+    prefix: Option<Prefix>,
 }
 impl<T> DecisionDiagram for All<T>
 where
@@ -75,7 +170,31 @@ where
         R: Relaxation<State = P::State>,
         O: StateRanking<State = P::State>,
     {
-        self._compile(input)
+        if input.timed {
+            let start = Instant::now();
+            self._compile(input);
+            self.framework_time = start.elapsed().saturating_sub(self.model_time);
+        } else {
+            self._compile(input)
+        }
+        self.stats.total_arcs = self.edges.len();
+    }
+
+    fn resume_relaxed<P, R, O>(&mut self, input: &CompilationInput<P, R, O>) -> bool
+    where
+        P: Problem<State = Self::State>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let start = Instant::now();
+        if !self._resume_relaxed(input) {
+            return false;
+        }
+        if input.timed {
+            self.framework_time = start.elapsed().saturating_sub(self.model_time);
+        }
+        self.stats.total_arcs = self.edges.len();
+        true
     }
 
     fn is_exact(&self) -> bool {
@@ -83,19 +202,98 @@ where
     }
 
     fn best_value(&self) -> Option<isize> {
-        self._best_value()
+        self._best_value().max(self.dropped_ub)
     }
 
     fn best_solution(&self) -> Option<Vec<Decision>> {
         self._best_solution()
     }
 
+    fn best_exact_value(&self) -> Option<isize> {
+        self._best_value().filter(|_| self.has_exact_best_path(self.best_n))
+    }
+
+    fn best_exact_solution(&self) -> Option<Vec<Decision>> {
+        self.has_exact_best_path(self.best_n).then(|| self._best_solution()).flatten()
+    }
+
+    fn best_solutions(&self, limit: usize) -> Vec<Vec<Decision>> {
+        self._best_solutions(limit)
+    }
+
     fn drain_cutset<F>(&mut self, func: F)
     where
         F: FnMut(SubProblem<T>),
     {
         self._drain_cutset(func)
     }
+
+    fn nb_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn nb_active_nodes(&self) -> usize {
+        self.nodes.iter().filter(|n| !n.flags.is_deleted()).count()
+    }
+
+    fn nb_avoided_allocations(&self) -> usize {
+        self.avoided_allocations
+    }
+
+    fn nb_reservations(&self) -> usize {
+        self.reservations
+    }
+
+    fn nb_reallocations(&self) -> usize {
+        self.reallocations
+    }
+
+    fn ran_dry(&self) -> bool {
+        self.ran_dry
+    }
+
+    fn stats(&self) -> &CompilationStats {
+        &self.stats
+    }
+
+    fn model_time(&self) -> Duration {
+        self.model_time
+    }
+
+    fn framework_time(&self) -> Duration {
+        self.framework_time
+    }
+
+    fn drain_violations<F>(&mut self, func: F)
+    where
+        F: FnMut(Violation<T>),
+    {
+        self.violations.drain(..).for_each(func)
+    }
+
+    fn nb_edges(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|n| !n.flags.is_deleted())
+            .map(|n| {
+                let mut count = 0;
+                let mut edge_id = n.inbound;
+                while let Some(eid) = edge_id {
+                    count += 1;
+                    edge_id = self.edges[eid.0].next;
+                }
+                count
+            })
+            .sum()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len() - self.deleted
+    }
+
+    fn arc_count(&self) -> usize {
+        self.edges.len()
+    }
 }
 impl<T> All<T>
 where
@@ -104,43 +302,103 @@ where
     pub fn new(cutset_type: CutsetType) -> Self {
         Self {
             root_pa: vec![],
+            root_prefix: None,
+            path_buffer: vec![],
             nodes: vec![],
             edges: vec![],
+            forced: Default::default(),
             prev_l: Default::default(),
             next_l: Default::default(),
             cutset: vec![],
+            frontier: vec![],
+            dropped: vec![],
+            dropped_ub: None,
+            terminal: Default::default(),
             best_n: None,
+            best_lb: isize::MIN,
             exact: true,
             approximate: false,
-            cutset_type: cutset_type,
+            ran_dry: false,
+            stats: CompilationStats::default(),
+            cutset_type,
             explored: 0,
+            avoided_allocations: 0,
+            spare: None,
+            reservations: 0,
+            reallocations: 0,
+            deleted: 0,
+            growth: None,
+            transitions: vec![],
+            model_time: Duration::ZERO,
+            framework_time: Duration::ZERO,
+            violations: vec![],
+            prefix: None,
         }
     }
     fn clear(&mut self) {
         self.root_pa.clear();
+        self.root_prefix = None;
         self.nodes.clear();
         self.edges.clear();
+        self.forced.clear();
+        self.prefix = None;
+        self.stats = CompilationStats::default();
+        self.explored = 0;
+        self.avoided_allocations = 0;
+        self.reservations = 0;
+        self.reallocations = 0;
+        self.deleted = 0;
+        self.growth = None;
+        self.clear_outcome();
+    }
+    /// Forgets what the layers below the current one have computed
+    fn clear_outcome(&mut self) {
         self.next_l.clear();
         self.cutset.clear();
+        self.frontier.clear();
+        self.dropped.clear();
+        self.dropped_ub = None;
+        self.terminal.clear();
         self.best_n = None;
         self.exact = true;
         self.approximate = false;
-        self.explored = 0;
+        self.ran_dry = false;
+        self.model_time = Duration::ZERO;
+        self.framework_time = Duration::ZERO;
+        self.violations.clear();
     }
 
     fn _is_exact(&self, comp_type: CompilationType) -> bool {
-        !self.approximate
-            || (comp_type == CompilationType::Relaxed && self.has_exact_best_path(self.best_n))
+        self.dropped.is_empty() && (!self.approximate
+            || (comp_type == CompilationType::Relaxed && self.has_exact_best_path(self.best_n)))
     }
 
     fn has_exact_best_path(&self, node: Option<NodeId>) -> bool {
+        node.is_none_or(|node_id| self.nodes[node_id.0].flags.is_exact_path())
+    }
+
+    /// Sets the exact path flag of a node from its own flags and from those
+    /// of its best parent. This is called whenever the best edge or the
+    /// exactness of a node changes: its parents are final by then, since the
+    /// flags of a layer never change once the next one is being built.
+    fn update_exact_path(&mut self, node_id: NodeId) {
+        let node = &self.nodes[node_id.0];
+        let exact_path = node.flags.is_exact()
+            || (!node.flags.is_relaxed() && node.best.is_none_or(|e| self.nodes[self.edges[e.0].from.0].flags.is_exact_path()));
+        self.nodes[node_id.0].flags.set_exact_path(exact_path);
+    }
+
+    /// Walks the best edges up from a node to tell whether its best path is
+    /// exact (this is what the exact path flags keep track of)
+    #[cfg(test)]
+    fn walk_exact_best_path(&self, node: Option<NodeId>) -> bool {
         if let Some(node_id) = node {
             let n = &self.nodes[node_id.0];
             if n.flags.is_exact() {
                 true
             } else {
                 !n.flags.is_relaxed()
-                    && self.has_exact_best_path(n.best.map(|e| self.edges[e.0].from))
+                    && self.walk_exact_best_path(n.best.map(|e| self.edges[e.0].from))
             }
         } else {
             true
@@ -148,15 +406,80 @@ where
     }
 
     fn _best_value(&self) -> Option<isize> {
-        self.best_n.map(|id| self.nodes[id.0].value)
+        self.best_n.map(|id| self.final_value(id))
+    }
+
+    /// Records the terminal values of the nodes of the last layer (see
+    /// `Problem::terminal_value`)
+    fn record_terminal_values<P: Problem<State = T>>(&mut self, problem: &P) {
+        for id in self.next_l.values() {
+            let terminal = problem.terminal_value(self.nodes[id.0].state.as_ref());
+            if terminal != 0 {
+                self.terminal.insert(*id, terminal);
+            }
+        }
+    }
+
+    /// Returns the value of a node of the last layer, its terminal value
+    /// included
+    fn final_value(&self, id: NodeId) -> isize {
+        self.nodes[id.0].value.saturating_add(self.terminal.get(&id).copied().unwrap_or(0))
     }
 
     fn _best_solution(&self) -> Option<Vec<Decision>> {
         self.best_n.map(|id| self._best_path(id))
     }
 
+    fn _best_solutions(&self, limit: usize) -> Vec<Vec<Decision>> {
+        let mut solutions = vec![];
+        // the ties of a bound are not solutions
+        if let Some(best) = self.best_n.filter(|_| self.has_exact_best_path(self.best_n)) {
+            let value = self.final_value(best);
+            let mut dead = vec![false; self.nodes.len()];
+            let terminals = std::iter::once(best)
+                .chain(self.next_l.values().copied().filter(|id| *id != best && self.final_value(*id) == value));
+            for id in terminals {
+                self._tied_paths(id, &mut vec![], &mut dead, limit, &mut solutions);
+            }
+        }
+        solutions
+    }
+
+    /// Appends the exact paths from the root to the given node which are worth
+    /// its value (the decisions below the node are in `suffix`). The best edge
+    /// of each node is followed first, and the nodes known to have no such
+    /// path are marked as `dead`. It returns true when some path was found.
+    fn _tied_paths(&self, id: NodeId, suffix: &mut Vec<Decision>, dead: &mut [bool], limit: usize, solutions: &mut Vec<Vec<Decision>>) -> bool {
+        let node = &self.nodes[id.0];
+        if solutions.len() >= limit || dead[id.0] || node.flags.is_relaxed() {
+            return false;
+        }
+        if node.inbound.is_none() {
+            let mut sol = self.root_pa.clone();
+            sol.extend_from_slice(suffix);
+            solutions.push(sol);
+            return true;
+        }
+
+        let mut found = false;
+        let best = node.best.map(|e| e.0);
+        let others = std::iter::successors(node.inbound, |e| self.edges[e.0].next).filter(|e| Some(e.0) != best);
+        for eid in node.best.into_iter().chain(others) {
+            let edge = self.edges[eid.0];
+            if self.nodes[edge.from.0].value.saturating_add(edge.cost) == node.value {
+                let len = suffix.len();
+                suffix.push(edge.decision);
+                suffix.extend(self.forced.get(&eid.0).into_iter().flatten());
+                found |= self._tied_paths(edge.from, suffix, dead, limit, solutions);
+                suffix.truncate(len);
+            }
+        }
+        dead[id.0] = !found && solutions.len() < limit;
+        found
+    }
+
     fn _best_path(&self, id: NodeId) -> Vec<Decision> {
-        Self::_best_path_partial_borrow(id, &self.root_pa, &self.nodes, &self.edges)
+        Self::_best_path_partial_borrow(id, &self.root_pa, &self.nodes, &self.edges, &self.forced)
     }
 
     fn _best_path_partial_borrow(
@@ -164,42 +487,72 @@ where
         root_pa: &[Decision],
         nodes: &[Node<T>],
         edges: &[Edge],
+        forced: &FxHashMap<usize, Vec<Decision>>,
     ) -> Vec<Decision> {
         let mut sol = root_pa.to_owned();
         let mut edge_id = nodes[id.0].best;
         while let Some(eid) = edge_id {
             let edge = edges[eid.0];
             sol.push(edge.decision);
+            sol.extend(forced.get(&eid.0).into_iter().flatten());
             edge_id = nodes[edge.from.0].best;
         }
         sol
     }
 
+    /// Returns the rough upper bound of the given node. When the problem
+    /// estimates from the paths, the best path of the node is first
+    /// materialized (in order) in `path_buffer`.
+    fn rough_upper_bound<P: Problem<State = T>>(&mut self, problem: &P, id: NodeId) -> isize {
+        let state = self.nodes[id.0].state.as_ref();
+        if !problem.estimates_from_path() {
+            return problem.estimate(state);
+        }
+        let path = &mut self.path_buffer;
+        path.clear();
+        path.extend_from_slice(&self.root_pa);
+        let mut edge_id = self.nodes[id.0].best;
+        while let Some(eid) = edge_id {
+            let edge = self.edges[eid.0];
+            path.extend(self.forced.get(&eid.0).into_iter().flatten().rev());
+            path.push(edge.decision);
+            edge_id = self.nodes[edge.from.0].best;
+        }
+        path[self.root_pa.len()..].reverse();
+        problem.estimate_from_path(state, path)
+    }
+
     fn _drain_cutset<F>(&mut self, mut func: F)
     where
         F: FnMut(SubProblem<T>),
     {
+        let shared = self.root_prefix.as_ref().map_or(0, |prefix| prefix.len());
         if let Some(best_value) = self.best_value() {
-            for node_id in self.cutset.drain(..) {
+            let cutset = self.cutset.drain(..).filter(|id| self.nodes[id.0].flags.is_marked()).map(|id| {
+                let node = &self.nodes[id.0];
+                let rub = node.value.saturating_add(node.rub);
+                let locb = node.value.saturating_add(node.value_bot);
+                (id, rub.min(locb).min(best_value))
+            });
+            // the dropped nodes were never expanded: only their estimate bounds them
+            let dropped = self.dropped.drain(..).map(|id| (id, self.nodes[id.0].value.saturating_add(self.nodes[id.0].rub)));
+            for (node_id, ub) in cutset.chain(dropped) {
                 let node = &self.nodes[node_id.0];
-
-                if node.flags.is_marked() {
-                    let rub = node.value.saturating_add(node.rub);
-                    let locb = node.value.saturating_add(node.value_bot);
-                    let ub = rub.min(locb).min(best_value);
-
-                    func(SubProblem {
-                        state: node.state.clone(),
-                        value: node.value,
-                        path: Self::_best_path_partial_borrow(
-                            node_id,
-                            &self.root_pa,
-                            &self.nodes,
-                            &self.edges,
-                        ),
-                        ub,
-                    })
-                }
+                func(SubProblem {
+                    state: node.state.clone(),
+                    value: node.value,
+                    path: Self::_best_path_partial_borrow(
+                        node_id,
+                        &self.root_pa[shared..],
+                        &self.nodes,
+                        &self.edges,
+                        &self.forced,
+                    ),
+                    ub,
+                    id: 0,
+                    parent: None,
+                    prefix: self.root_prefix.clone(),
+                })
             }
         }
     }
@@ -208,6 +561,19 @@ where
         self.explored
     }
 
+    /// Estimates the number of bytes held by this dd: its buffers keep their
+    /// capacity from one compilation to the next, and its nodes keep their
+    /// states until the next compilation
+    pub fn footprint(&self) -> usize {
+        use std::mem::size_of;
+        self.nodes.capacity() * size_of::<Node<T>>()
+            + self.nodes.len() * size_of::<T>()
+            + self.edges.capacity() * size_of::<Edge>()
+            + (self.prev_l.capacity() + self.cutset.capacity() + self.frontier.capacity()) * size_of::<NodeId>()
+            + self.next_l.capacity() * size_of::<(Arc<T>, NodeId)>()
+            + self.transitions.capacity() * size_of::<(NodeId, Decision, T, isize)>()
+    }
+
     fn _compile<P, R, O>(&mut self, input: &CompilationInput<P, R, O>)
     where
         P: Problem<State = T>,
@@ -215,13 +581,11 @@ where
         O: StateRanking<State = P::State>,
     {
         self.clear();
-
-        let mut depth = 0;
-        let mut curr_l = vec![];
+        self.best_lb = input.best_lb;
 
         let root_s = input.residual.state.clone();
         let root_v = input.residual.value;
-        let root_n = Node {
+        let mut root_n = Node {
             state: root_s.clone(),
             value: root_v,
             best: None,
@@ -230,73 +594,257 @@ where
             rub: input.residual.ub - root_v,
             flags: NodeFlags::new_exact(),
         };
-        input
-            .residual
-            .path
-            .iter()
-            .copied()
-            .for_each(|x| self.root_pa.push(x));
+        root_n.flags.set_exact_path(true);
+        if let Some(prefix) = &input.residual.prefix {
+            prefix.extend_into(&mut self.root_pa);
+        }
+        self.root_pa.extend_from_slice(&input.residual.path);
+        self.root_prefix = input.residual.prefix.clone();
 
         self.nodes.push(root_n);
         self.next_l.insert(root_s, NodeId(0));
 
-        while let Some(var) = input.problem.next_variable(&mut self.next_l.keys().map(|s| s.as_ref())) {
-            self.prev_l.clear();
-            for node_id in curr_l.drain(..) {
-                self.prev_l.push(node_id);
-            }
-            for (_, node_id) in self.next_l.drain() {
-                curr_l.push(node_id);
-            }
+        let cursor = Cursor { curr_l: vec![], depth: 0, above: 0, branched: false, assigned: input.assigned.clone() };
+        self.compile_layers(input, cursor, None);
+    }
 
-            if curr_l.is_empty() {
-                return;
-            }
+    /// Resumes the relaxed compilation of the subproblem of the last
+    /// (restricted) compilation from the layers it kept (see
+    /// `DecisionDiagram::resume_relaxed`)
+    fn _resume_relaxed<P, R, O>(&mut self, input: &CompilationInput<P, R, O>) -> bool
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let Some(prefix) = self.prefix.take() else {
+            return false;
+        };
+        if input.comp_type != CompilationType::Relaxed || input.self_check || input.best_lb != prefix.best_lb {
+            return false;
+        }
+
+        self.nodes.truncate(prefix.nb_nodes);
+        self.edges.truncate(prefix.nb_edges);
+        self.forced.retain(|edge_id, _| *edge_id < prefix.nb_edges);
+        for (node_id, rub) in prefix.cursor.curr_l.iter().zip(prefix.rubs) {
+            self.nodes[node_id.0].rub = rub;
+        }
+        self.prev_l.clear();
+        self.prev_l.extend(prefix.prev_l);
+        self.clear_outcome();
+        self.best_lb = input.best_lb;
+        self.stats = CompilationStats { reused_nodes: prefix.explored, ..prefix.stats };
+        self.explored = 0;
+        self.avoided_allocations = prefix.avoided_allocations;
+        self.reservations = prefix.reservations;
+        self.reallocations = prefix.reallocations;
+        self.deleted = prefix.deleted;
+        self.growth = prefix.growth;
+
+        self.compile_layers(input, prefix.cursor, Some(prefix.var));
+        true
+    }
+
+    /// Develops the layers below the cursor, then picks the best node of the
+    /// last one (and the cutset of a relaxed dd). When a variable is given,
+    /// the layer of the cursor is that of this variable, whose nodes are
+    /// ready to be restricted or merged.
+    fn compile_layers<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, mut cursor: Cursor, mut resumed: Option<Variable>)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        loop {
+            let var = match resumed.take() {
+                Some(var) => var,
+                None => {
+                    let Some(var) = input.problem.next_variable_with_context(&cursor.assigned, &mut self.next_l.keys().map(|s| s.as_ref())) else {
+                        break;
+                    };
+                    cursor.assigned.insert(var);
+                    self.prev_l.clear();
+                    for node_id in cursor.curr_l.drain(..) {
+                        self.prev_l.push(node_id);
+                    }
+                    for (_, node_id) in self.next_l.drain() {
+                        cursor.curr_l.push(node_id);
+                    }
+
+                    if cursor.curr_l.is_empty() {
+                        // nothing is left below the nodes which were dropped
+                        self.exact = self.dropped.is_empty();
+                        self.ran_dry = self.is_dry(input.comp_type, cursor.branched);
+                        return;
+                    }
+                    if input.is_cancelled() {
+                        // stop at the layer boundary, without pretending to be exact
+                        self.exact = false;
+                        return;
+                    }
+                    let best_lb = input.current_best_lb();
+                    if best_lb > self.best_lb {
+                        // the incumbent improved since the compilation began
+                        self.best_lb = best_lb;
+                        self.stats.bound_refreshes += 1;
+                    }
+                    if input.reuse_prefix && input.comp_type == CompilationType::Restricted
+                        && cursor.curr_l.len() > input.max_width && self.prefix.is_none()
+                    {
+                        self.keep_prefix(input, var, &cursor);
+                    }
+                    var
+                }
+            };
+            let curr_l = &mut cursor.curr_l;
 
             match input.comp_type {
                 CompilationType::Exact => { /* do nothing: you want to explore the complete DD */ }
                 CompilationType::Restricted => {
                     if curr_l.len() > input.max_width {
-                        self.restrict(input, &mut curr_l)
+                        self.restrict(input, curr_l)
                     }
                 }
                 CompilationType::Relaxed => {
-                    if curr_l.len() > input.max_width && depth > 1 {
-                        self.relax(input, &mut curr_l)
+                    if curr_l.len() > input.max_width {
+                        if cursor.depth > input.min_relax_depth_offset {
+                            self.relax(input, self.root_pa.len() + cursor.depth, self.root_pa.len() + cursor.above, curr_l)
+                        } else if input.restrict_first_layers {
+                            self.drop_excess(input, curr_l)
+                        }
                     }
                 }
             }
 
+            self.reserve_next_layer(input.problem, var, curr_l);
+            let capacities = self.layer_capacities();
+            if input.timed {
+                self.branch_timed(input, var, curr_l);
+            }
+            cursor.branched = false;
             for node_id in curr_l.iter() {
                 let state = self.nodes[node_id.0].state.clone();
-                let rub = input.problem.estimate(state.as_ref());
-                self.nodes[node_id.0].rub = rub;
+                let rub = if input.timed {
+                    self.nodes[node_id.0].rub
+                } else {
+                    let rub = self.rough_upper_bound(input.problem, *node_id);
+                    self.nodes[node_id.0].rub = rub;
+                    rub
+                };
                 let ub = rub.saturating_add(self.nodes[node_id.0].value);
-                if ub > input.best_lb {
-                    input.problem.for_each_in_domain(var, state.as_ref(), |decision| {
-                        self.branch_on(*node_id, decision, input.problem)
-                    });
+                if ub > self.best_lb {
+                    if !input.timed {
+                        input.problem.for_each_in_domain(var, state.as_ref(), |decision| {
+                            self.branch_on(*node_id, decision, input.problem)
+                        });
+                    }
 
                     self.explored += 1;
+                    cursor.branched = true;
+                } else {
+                    self.stats.rub_prunes += 1;
                 }
             }
+            self.record_growth(capacities, curr_l.len());
+
+            if input.self_check {
+                self.self_check_estimate(input, var, &cursor.assigned, curr_l);
+            }
 
-            depth += 1;
+            cursor.above = cursor.depth;
+            cursor.depth += 1;
+            if input.max_forced_layers > 0 {
+                self.collapse_forced_layers(input, &mut cursor.assigned, &mut cursor.depth);
+            }
         }
 
         //
+        self.record_terminal_values(input.problem);
         self.best_n = self
             .next_l
             .values()
             .copied()
-            .max_by_key(|id| self.nodes[id.0].value);
+            .max_by_key(|id| self.final_value(*id));
         self.exact = self._is_exact(input.comp_type);
+        self.ran_dry = self.best_n.is_none() && self.is_dry(input.comp_type, cursor.branched);
         //
         if matches!(input.comp_type, CompilationType::Relaxed) {
+            if !self.approximate && !self.dropped.is_empty() && self.cutset_type != CutsetType::Frontier {
+                // nothing was merged: the terminal layer is the last exact one,
+                // and it is drained along with the nodes which were dropped
+                self.cutset.extend(self.next_l.values().copied());
+            }
             self.compute_local_bounds();
         }
     }
 
+    /// Keeps the layers developed so far (before the layer of the given
+    /// variable gets restricted) for the relaxed dd of the same subproblem
+    fn keep_prefix<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, var: Variable, cursor: &Cursor)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        self.prefix = Some(Prefix {
+            nb_nodes: self.nodes.len(),
+            nb_edges: self.edges.len(),
+            var,
+            cursor: cursor.clone(),
+            prev_l: self.prev_l.clone(),
+            rubs: cursor.curr_l.iter().map(|id| self.nodes[id.0].rub).collect(),
+            best_lb: input.best_lb,
+            stats: self.stats,
+            explored: self.explored,
+            avoided_allocations: self.avoided_allocations,
+            reservations: self.reservations,
+            reallocations: self.reallocations,
+            deleted: self.deleted,
+            growth: self.growth,
+        });
+    }
+
+    /// Tells whether a compilation which ended with an empty layer ran out of
+    /// nodes because of its restriction (see `DecisionDiagram::ran_dry`): the
+    /// nodes of the layer above were branched on, but none of them had any
+    /// successor.
+    fn is_dry(&self, comp_type: CompilationType, branched: bool) -> bool {
+        branched && self.approximate && comp_type == CompilationType::Restricted
+    }
+
+    /// Reserves the memory of the next layer before the current one is
+    /// expanded. The hints of the problem bound the number of transitions;
+    /// without them, the next layer is expected to grow like the last one.
+    fn reserve_next_layer<P: Problem<State = T>>(&mut self, problem: &P, var: Variable, curr_l: &[NodeId]) {
+        let hinted = curr_l
+            .iter()
+            .map(|id| problem.domain_size_hint(var, self.nodes[id.0].state.as_ref()))
+            .sum::<Option<usize>>();
+        let expected = hinted.or_else(|| self.growth.map(|growth| (growth * curr_l.len() as f64).ceil() as usize));
+        if let Some(additional) = expected {
+            let capacities = self.layer_capacities();
+            self.next_l.reserve(additional);
+            self.nodes.reserve(additional);
+            self.edges.reserve(additional);
+            if self.layer_capacities() != capacities {
+                self.reservations += 1;
+            }
+        }
+    }
+
+    /// Counts the containers which outgrew the memory they had before the
+    /// current layer was expanded, and remembers how much the dd grew
+    fn record_growth(&mut self, capacities: (usize, usize, usize), width: usize) {
+        let (next_l, nodes, edges) = self.layer_capacities();
+        self.reallocations += usize::from(next_l > capacities.0) + usize::from(nodes > capacities.1) + usize::from(edges > capacities.2);
+        self.growth = Some(self.next_l.len() as f64 / width.max(1) as f64);
+    }
+
+    fn layer_capacities(&self) -> (usize, usize, usize) {
+        (self.next_l.capacity(), self.nodes.capacity(), self.edges.capacity())
+    }
+
     fn branch_on<P: Problem<State = T>>(
         &mut self,
         from_id: NodeId,
@@ -304,9 +852,167 @@ where
         problem: &P,
     ) {
         let state = self.nodes[from_id.0].state.as_ref();
-        let next_state = Arc::new(problem.transition(state, decision));
-        let cost = problem.transition_cost(state, decision);
+        let (next_state, cost) = problem.transition_with_cost(state, decision);
+        self.link(from_id, decision, next_state, cost)
+    }
+
+    /// Expands the nodes of the current layer like an untimed compilation,
+    /// except that the estimates and the transitions are all computed before
+    /// the next layer gets built: this is how the time spent in the model is
+    /// measured without timing each call.
+    fn branch_timed<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, var: Variable, curr_l: &[NodeId])
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let start = Instant::now();
+        for node_id in curr_l.iter() {
+            let rub = self.rough_upper_bound(input.problem, *node_id);
+            self.nodes[node_id.0].rub = rub;
+        }
+        let mut transitions = std::mem::take(&mut self.transitions);
+        for node_id in curr_l.iter() {
+            let node = &self.nodes[node_id.0];
+            if node.rub.saturating_add(node.value) > self.best_lb {
+                let state = node.state.as_ref();
+                input.problem.for_each_in_domain(var, state, |decision| {
+                    let (next_state, cost) = input.problem.transition_with_cost(state, decision);
+                    transitions.push((*node_id, decision, next_state, cost));
+                });
+            }
+        }
+        self.model_time += start.elapsed();
+
+        for (from_id, decision, next_state, cost) in transitions.drain(..) {
+            self.link(from_id, decision, next_state, cost);
+        }
+        self.transitions = transitions;
+    }
+
+    /// Collapses the layers below the one which was just built, for as long as
+    /// none of its nodes has more than one decision on the next variable (and
+    /// at most `CompilationInput::max_forced_layers` times). Each node then
+    /// takes its forced decision in place: the arcs which reach it carry the
+    /// decision and its cost.
+    fn collapse_forced_layers<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, assigned: &mut AssignedVars, depth: &mut usize)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let mut transitions = std::mem::take(&mut self.transitions);
+        for _ in 0..input.max_forced_layers {
+            if self.next_l.is_empty() {
+                break;
+            }
+            let Some(var) = input.problem.next_variable_with_context(assigned, &mut self.next_l.keys().map(|s| s.as_ref())) else {
+                break;
+            };
+
+            // the dead ends have no decision to take: they are simply dropped
+            let mut dead_ends = vec![];
+            let start = Instant::now();
+            let forced = self.next_l.iter().all(|(state, node_id)| {
+                let mut decisions = 0;
+                input.problem.for_each_in_domain(var, state.as_ref(), |decision| {
+                    decisions += 1;
+                    if decisions == 1 {
+                        let (next_state, cost) = input.problem.transition_with_cost(state.as_ref(), decision);
+                        transitions.push((*node_id, decision, next_state, cost));
+                    }
+                });
+                if decisions == 0 {
+                    dead_ends.push(*node_id);
+                }
+                decisions <= 1
+            });
+            if input.timed {
+                self.model_time += start.elapsed();
+            }
+            if !forced || transitions.is_empty() {
+                transitions.clear();
+                break;
+            }
+
+            assigned.insert(var);
+            *depth += 1;
+            self.stats.collapsed_layers += 1;
+            self.next_l.clear();
+            self.deleted += dead_ends.len();
+            for node_id in dead_ends {
+                self.nodes[node_id.0].flags.set_deleted(true);
+            }
+            for (node_id, decision, next_state, cost) in transitions.drain(..) {
+                self.force(node_id, decision, next_state, cost);
+            }
+        }
+        self.transitions = transitions;
+    }
+
+    /// Wraps the given state in an arc. The arc of a state which was already
+    /// in the next layer is recycled: only the states which are new in that
+    /// layer need an allocation.
+    fn wrap(&mut self, state: T) -> Arc<T> {
+        match self.spare.take() {
+            Some(mut spare) => {
+                *Arc::get_mut(&mut spare).expect("the spare arc is not shared") = state;
+                spare
+            }
+            None => Arc::new(state),
+        }
+    }
+
+    /// Makes the given node of the next layer take its forced decision: every
+    /// arc which reaches it goes on with that decision, and the node takes the
+    /// state it leads to (unless some other node of the layer already holds
+    /// it, in which case the arcs are handed over to that node)
+    fn force(&mut self, node_id: NodeId, decision: Decision, next_state: T, cost: isize) {
+        let mut last = None;
+        let mut edge_id = self.nodes[node_id.0].inbound;
+        while let Some(eid) = edge_id {
+            let edge = &mut self.edges[eid.0];
+            edge.cost = edge.cost.saturating_add(cost);
+            self.forced.entry(eid.0).or_default().push(decision);
+            last = Some(eid);
+            edge_id = edge.next;
+        }
+        let node = &mut self.nodes[node_id.0];
+        node.value = node.value.saturating_add(cost);
 
+        let next_state = self.wrap(next_state);
+        match self.next_l.entry(next_state.clone()) {
+            Entry::Vacant(e) => {
+                e.insert(node_id);
+                self.nodes[node_id.0].state = next_state;
+            }
+            Entry::Occupied(e) => {
+                let kept_id = *e.get();
+                self.spare = Some(next_state);
+                self.avoided_allocations += 1;
+                let dropped = self.nodes[node_id.0].clone();
+                self.nodes[node_id.0].flags.set_deleted(true);
+                self.deleted += 1;
+                if let Some(last) = last {
+                    self.edges[last.0].next = self.nodes[kept_id.0].inbound;
+                }
+
+                let kept = &mut self.nodes[kept_id.0];
+                kept.inbound = dropped.inbound.or(kept.inbound);
+                // flags hygiene
+                let exact = kept.flags.is_exact() & dropped.flags.is_exact();
+                kept.flags.set_exact(exact);
+                if dropped.value > kept.value {
+                    kept.value = dropped.value;
+                    kept.best = dropped.best;
+                }
+                self.update_exact_path(kept_id);
+            }
+        }
+    }
+
+    fn link(&mut self, from_id: NodeId, decision: Decision, next_state: T, cost: isize) {
+        let next_state = self.wrap(next_state);
         match self.next_l.entry(next_state.clone()) {
             Entry::Vacant(e) => {
                 let node_id = NodeId(self.nodes.len());
@@ -322,7 +1028,7 @@ where
                 });
                 self.nodes.push(Node {
                     //my_id  : node_id,
-                    state: next_state,
+                    state: next_state.clone(),
                     value: self.nodes[from_id.0].value.saturating_add(cost),
                     best: Some(edge_id),
                     inbound: Some(edge_id),
@@ -330,6 +1036,7 @@ where
                     value_bot: isize::MIN,
                     //
                     rub: isize::MAX,
+                    // the child is exact (and on an exact path) iff its parent is
                     flags: self.nodes[from_id.0].flags,
                 });
 
@@ -337,6 +1044,8 @@ where
             }
             Entry::Occupied(e) => {
                 let node_id = *e.get();
+                self.spare = Some(next_state);
+                self.avoided_allocations += 1;
                 let exact = self.nodes[from_id.0].flags.is_exact();
                 let value = self.nodes[from_id.0].value.saturating_add(cost);
                 let node = &mut self.nodes[node_id.0];
@@ -360,6 +1069,23 @@ where
                     node.value = value;
                     node.best = Some(edge_id);
                 }
+                self.update_exact_path(node_id);
+            }
+        }
+    }
+
+    /// Checks the estimate of the first exact node of the current layer
+    /// against the value of a greedy completion of its state
+    fn self_check_estimate<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, var: Variable, assigned: &AssignedVars, curr_l: &[NodeId])
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        if let Some(node_id) = curr_l.iter().find(|id| self.nodes[id.0].flags.is_exact()) {
+            let node = &self.nodes[node_id.0];
+            if let Some(violation) = check_estimate(input.problem, node.state.as_ref(), var, assigned, node.rub) {
+                self.violations.push(violation);
             }
         }
     }
@@ -381,19 +1107,58 @@ where
                 .then_with(|| input.ranking.compare(self.nodes[a.0].state.as_ref(), self.nodes[b.0].state.as_ref()))
                 .reverse()
         }); // reverse because greater means more likely to be kept
+        self.stats.restricted_nodes += curr_l.len() - input.max_width;
+        self.deleted += curr_l.len() - input.max_width;
         curr_l.truncate(input.max_width);
     }
 
-    fn relax<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, curr_l: &mut Vec<NodeId>)
+    /// Restricts a layer which is too shallow to be merged. The exact nodes it
+    /// drops are kept aside to be drained with the cutset, bounded by their
+    /// estimate.
+    fn drop_excess<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, curr_l: &mut Vec<NodeId>)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        curr_l.sort_unstable_by(|a, b| {
+            self.nodes[a.0]
+                .value
+                .cmp(&self.nodes[b.0].value)
+                .then_with(|| input.ranking.compare(self.nodes[a.0].state.as_ref(), self.nodes[b.0].state.as_ref()))
+                .reverse()
+        }); // reverse because greater means more likely to be kept
+        self.stats.restricted_nodes += curr_l.len() - input.max_width;
+        self.deleted += curr_l.len() - input.max_width;
+        for node_id in curr_l.drain(input.max_width..) {
+            let rub = self.rough_upper_bound(input.problem, node_id);
+            let node = &mut self.nodes[node_id.0];
+            node.rub = rub;
+            node.flags.set_deleted(true);
+            let ub = node.value.saturating_add(node.rub);
+            if ub > self.best_lb {
+                self.dropped.push(node_id);
+                self.dropped_ub = self.dropped_ub.max(Some(ub));
+            }
+        }
+    }
+
+    fn relax<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, depth: usize, lel_depth: usize, curr_l: &mut Vec<NodeId>)
     where
         P: Problem<State = T>,
         R: Relaxation<State = P::State>,
         O: StateRanking<State = P::State>,
     {
-        if self.cutset_type == CutsetType::LastExactLayer && !self.approximate {
+        if !self.approximate {
+            self.stats.lel_depth = Some(lel_depth);
+        }
+        if self.cutset_type != CutsetType::Frontier && !self.approximate {
             for id in self.prev_l.iter() {
                 self.cutset.push(*id);
-                self.nodes[id.0].flags.set_cutset(true);
+                // an adaptive dd may still go for the frontier instead
+                if self.cutset_type == CutsetType::LastExactLayer {
+                    self.nodes[id.0].flags.set_cutset(true);
+                }
             }
         }
 
@@ -407,10 +1172,86 @@ where
         }); // reverse because greater means more likely to be kept
 
         //--
-        let (keep, merge) = curr_l.split_at_mut(input.max_width - 1);
-        let merged = Arc::new(input.relaxation.merge(&mut merge.iter().map(|node_id| self.nodes[node_id.0].state.as_ref())));
+        let nb_chunks = input.merge_policy.nb_chunks(input.max_width);
+        let (keep, merge) = curr_l.split_at_mut(input.max_width - nb_chunks);
+        if nb_chunks > 1 {
+            // each chunk gathers states which are close to each other
+            merge.sort_unstable_by(|a, b| input.ranking.compare(self.nodes[a.0].state.as_ref(), self.nodes[b.0].state.as_ref()).reverse());
+        }
+
+        let (size, extra) = (merge.len() / nb_chunks, merge.len() % nb_chunks);
+        let mut merged_ids = Vec::with_capacity(nb_chunks);
+        self.stats.relaxed_merges += nb_chunks;
+        let mut recycled = false;
+        let mut end = 0;
+        for chunk in 0..nb_chunks {
+            let group = &merge[end..end + size + usize::from(chunk < extra)];
+            end += group.len();
 
-        let recycled = keep.iter().find(|node_id| self.nodes[node_id.0].state.eq(&merged)).map(|node_id| *node_id);
+            let merged = self.merge_states(input, depth, group);
+            let recycled_id = keep.iter().chain(merged_ids.iter()).find(|node_id| self.nodes[node_id.0].state.eq(&merged)).copied();
+            let merged_id = self.redirect_to_merged(input, depth, group, merged, recycled_id);
+            match recycled_id {
+                Some(_) => recycled = true,
+                None => merged_ids.push(merged_id),
+            }
+        }
+
+        if recycled && nb_chunks == 1 {
+            // the most valuable of the merged nodes takes the free spot
+            curr_l.truncate(input.max_width);
+            let saved_id = curr_l[input.max_width - 1];
+            self.nodes[saved_id.0].flags.set_deleted(false);
+            self.deleted -= 1;
+        } else {
+            // a chunk whose merged state is an existing node leaves its spot
+            // empty: those states were merged with their neighbours already
+            curr_l.truncate(input.max_width - nb_chunks);
+            curr_l.extend(merged_ids);
+        }
+    }
+
+    /// Merges the states of the given nodes, and validates the merge when
+    /// asked to
+    fn merge_states<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, depth: usize, group: &[NodeId]) -> Arc<T>
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let start = input.timed.then(Instant::now);
+        let merged = Arc::new(input.relaxation.merge_at_depth(depth, &mut group.iter().map(|node_id| self.nodes[node_id.0].state.as_ref())));
+        if let Some(start) = start {
+            self.model_time += start.elapsed();
+        }
+
+        if input.check_merge {
+            let originals = group.iter().map(|node_id| self.nodes[node_id.0].state.as_ref()).collect::<Vec<_>>();
+            if let Err(msg) = input.relaxation.check_merge_at_depth(depth, merged.as_ref(), &originals) {
+                panic!("inconsistent merge of {} states: {}", originals.len(), msg);
+            }
+        }
+        merged
+    }
+
+    /// Deletes the nodes of the group and redirects their inbound edges to
+    /// the node of the merged state: the recycled one when that state is
+    /// already in the layer, a new relaxed node otherwise. Returns the id of
+    /// the merged node.
+    fn redirect_to_merged<P, R, O>(
+        &mut self,
+        input: &CompilationInput<P, R, O>,
+        depth: usize,
+        group: &[NodeId],
+        merged: Arc<T>,
+        recycled: Option<NodeId>,
+    ) -> NodeId
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let most_valuable = group.iter().copied().max_by_key(|node_id| self.nodes[node_id.0].value).expect("a merged group is not empty");
 
         let merged_id = recycled.unwrap_or_else(|| {
             let node_id = NodeId(self.nodes.len());
@@ -431,15 +1272,25 @@ where
 
         self.nodes[merged_id.0].flags.set_relaxed(true);
 
-        for drop_id in merge {
+        self.deleted += group.len();
+        for drop_id in group {
+            self.nodes[drop_id.0].flags.set_deleted(true);
+
             let mut edge_id = self.nodes[drop_id.0].inbound;
             while let Some(eid) = edge_id {
                 let edge = self.edges[eid.0];
                 let src = self.nodes[edge.from.0].state.as_ref();
+                // a collapsed arc enters its node with the last of its forced decisions
+                let chain = self.forced.get(&eid.0).cloned();
+                let last = chain.as_ref().and_then(|c| c.last()).copied().unwrap_or(edge.decision);
 
+                let start = input.timed.then(Instant::now);
                 let rcost = input
                     .relaxation
-                    .relax(src, self.nodes[drop_id.0].state.as_ref(), merged.as_ref(), edge.decision, edge.cost);
+                    .relax_at_depth(depth, src, self.nodes[drop_id.0].state.as_ref(), merged.as_ref(), last, edge.cost);
+                if let Some(start) = start {
+                    self.model_time += start.elapsed();
+                }
 
                 let new_eid = EdgeId(self.edges.len());
                 let new_edge = Edge {
@@ -451,6 +1302,9 @@ where
                     next: self.nodes[merged_id.0].inbound,
                 };
                 self.edges.push(new_edge);
+                if let Some(chain) = chain {
+                    self.forced.insert(new_eid.0, chain);
+                }
                 self.nodes[merged_id.0].inbound = Some(new_eid);
 
                 let new_value = self.nodes[edge.from.0].value.saturating_add(rcost);
@@ -462,13 +1316,21 @@ where
                 edge_id = edge.next;
             }
         }
+        self.update_exact_path(merged_id);
 
-        if recycled.is_some() {
-            curr_l.truncate(input.max_width);
-        } else {
-            curr_l.truncate(input.max_width - 1);
-            curr_l.push(merged_id);
+        if input.self_check {
+            let merged = &self.nodes[merged_id.0];
+            let original = &self.nodes[most_valuable.0];
+            if merged.value < original.value {
+                self.violations.push(Violation::LostValue {
+                    merged: merged.state.as_ref().clone(),
+                    merged_value: merged.value,
+                    original: original.state.as_ref().clone(),
+                    original_value: original.value,
+                });
+            }
         }
+        merged_id
     }
 
     fn compute_local_bounds(&mut self) {
@@ -479,9 +1341,17 @@ where
 
             // all the nodes from the last layer have a lp_from_bot of 0
             for id in self.next_l.values().copied() {
-                self.nodes[id.0].value_bot = 0;
+                self.nodes[id.0].value_bot = self.terminal.get(&id).copied().unwrap_or(0);
                 self.nodes[id.0].flags.set_marked(true);
                 visit.push(id);
+
+                // exact terminal nodes belong to the frontier too: otherwise
+                // the solutions they encode would be lost
+                if self.cutset_type != CutsetType::LastExactLayer && self.nodes[id.0].flags.is_exact() {
+                    self.nodes[id.0].flags.set_cutset(true);
+                    let frontier = if self.cutset_type == CutsetType::Adaptive { &mut self.frontier } else { &mut self.cutset };
+                    frontier.push(id);
+                }
             }
 
             while !visit.is_empty() {
@@ -506,18 +1376,448 @@ where
                             }
                         }
 
-                        if self.cutset_type == CutsetType::Frontier && self.nodes[node_id.0].flags.is_marked(){
-                            if !self.nodes[node_id.0].flags.is_exact() && self.nodes[edge.from.0].flags.is_exact() &&
-                                !self.nodes[edge.from.0].flags.is_cutset() {
-                                self.nodes[edge.from.0].flags.set_cutset(true);
-                                self.cutset.push(edge.from);
-                            }
+                        if self.cutset_type != CutsetType::LastExactLayer && self.nodes[node_id.0].flags.is_marked() &&
+                            !self.nodes[node_id.0].flags.is_exact() && self.nodes[edge.from.0].flags.is_exact() &&
+                            !self.nodes[edge.from.0].flags.is_cutset() {
+                            self.nodes[edge.from.0].flags.set_cutset(true);
+                            let frontier = if self.cutset_type == CutsetType::Adaptive { &mut self.frontier } else { &mut self.cutset };
+                            frontier.push(edge.from);
                         }
 
                         inbound = edge.next;
                     }
                 }
             }
+
+            if self.cutset_type == CutsetType::Adaptive {
+                // only the marked nodes of the last exact layer are drained
+                let lel = self.cutset.iter().filter(|id| self.nodes[id.0].flags.is_marked()).count();
+                if self.frontier.len() < lel {
+                    std::mem::swap(&mut self.cutset, &mut self.frontier);
+                }
+                self.frontier.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_all {
+    use std::{sync::{atomic::Ordering, Arc}, time::Duration};
+
+    use crate::test_utils::{drained, Chained, ChainedRelax, Knapsack, KnapsackMergeInto, KnapsackRanking, KnapsackRelax, KnapsackSlowRelax, KnapsackState, KnapsackStingyRelax, Tweaked};
+    use crate::{AssignedVars, CompilationInput, CompilationInputBuilder, CompilationType, CutsetType, Decision, DecisionDiagram, MergePolicy, Problem, SubProblem, Variable};
+
+    use super::{All, NodeId};
+
+    fn input(problem: &Knapsack, comp_type: CompilationType, max_width: usize)
+        -> CompilationInput<'_, Knapsack, KnapsackRelax, KnapsackRanking>
+    {
+        CompilationInput {
+            comp_type,
+            max_width,
+            problem,
+            relaxation: &KnapsackRelax,
+            ranking: &KnapsackRanking,
+            residual: SubProblem {
+                state: Arc::new(problem.initial_state()),
+                value: problem.initial_value(),
+                path: vec![],
+                ub: isize::MAX,
+                id: 0,
+                parent: None,
+                prefix: None,
+            },
+            assigned: AssignedVars::new(problem.nb_variables()),
+            best_lb: isize::MIN,
+            min_relax_depth_offset: 1,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            dominance: None,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: true,
+            timed: false,
+            self_check: false,
+            cancellation: None,
+            incumbent: None,
+        }
+    }
+
+    #[test]
+    fn a_relaxed_best_path_has_no_exact_value() {
+        let problem = Knapsack::toy();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Relaxed, 1));
+        assert!(!mdd.is_exact());
+        assert!(mdd.best_value().unwrap() > 13);
+        assert!(mdd.best_solution().is_some());
+        assert_eq!(None, mdd.best_exact_value());
+        assert_eq!(None, mdd.best_exact_solution());
+
+        // the paths of a restricted dd are solutions, even though it is not exact
+        mdd.compile(&input(&problem, CompilationType::Restricted, 1));
+        assert_eq!(mdd.best_value(), mdd.best_exact_value());
+        assert_eq!(mdd.best_solution(), mdd.best_exact_solution());
+        mdd.compile(&input(&problem, CompilationType::Exact, usize::MAX));
+        assert_eq!(Some(13), mdd.best_exact_value());
+    }
+
+    #[test]
+    fn exact_dd_counts_every_node_and_edge() {
+        let problem = Knapsack::toy();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Exact, usize::MAX));
+        assert_eq!(41, mdd.nb_nodes());
+        assert_eq!(41, mdd.nb_active_nodes());
+        assert_eq!(54, mdd.nb_edges());
+    }
+
+    #[test]
+    fn no_arc_is_allocated_for_the_states_that_already_exist() {
+        // the toy knapsack is full of diamonds: e.g. taking either item 0 or
+        // item 3 (both weigh 4) leads to the same state
+        let problem = Knapsack::toy();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Exact, usize::MAX));
+        // every edge which did not create a node reached an existing state
+        assert_eq!(mdd.nb_edges() - (mdd.nb_nodes() - 1), mdd.nb_avoided_allocations());
+        assert_eq!(14, mdd.nb_avoided_allocations());
+        assert_eq!(Some(13), mdd.best_value());
+    }
+
+    #[test]
+    fn relaxed_dd_does_not_count_deleted_nodes_nor_redirected_edges() {
+        let problem = Knapsack::toy();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Relaxed, 1));
+        assert_eq!(19, mdd.nb_nodes());
+        assert_eq!(9, mdd.nb_active_nodes());
+        assert_eq!(14, mdd.nb_edges());
+    }
+
+    #[test]
+    fn restricted_dd_only_counts_the_nodes_it_created() {
+        let problem = Knapsack::toy();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Restricted, 1));
+        assert_eq!(mdd.nb_nodes(), mdd.nb_active_nodes());
+        assert!(mdd.nb_nodes() < 41);
+    }
+
+    #[test]
+    fn the_node_and_arc_counts_are_those_of_the_compiled_dd() {
+        let problem = Knapsack::toy();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Exact, usize::MAX));
+        assert_eq!((mdd.nb_active_nodes(), mdd.nb_edges()), (mdd.node_count(), mdd.arc_count()));
+
+        mdd.compile(&input(&problem, CompilationType::Restricted, 1));
+        assert!(mdd.stats().restricted_nodes > 0);
+        assert_eq!(mdd.nb_nodes() - mdd.stats().restricted_nodes, mdd.node_count());
+        assert_eq!(mdd.stats().total_arcs, mdd.arc_count());
+
+        mdd.compile(&input(&problem, CompilationType::Relaxed, 1));
+        assert_eq!(mdd.nb_active_nodes(), mdd.node_count());
+        assert!(mdd.arc_count() > mdd.nb_edges());
+    }
+
+    #[test]
+    fn the_relaxed_dd_which_resumes_from_the_restricted_one_is_the_same() {
+        let problem = Knapsack { capacity: 15, profit: vec![5, 3, 4, 6, 2, 1, 7, 3, 5, 2], weight: vec![4, 3, 5, 4, 2, 3, 6, 2, 5, 1] };
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            for (width, best_lb) in [(1, isize::MIN), (2, isize::MIN), (3, 15), (8, 18)] {
+                let restricted = CompilationInput { best_lb, ..input(&problem, CompilationType::Restricted, width) };
+                let mut two_pass = All::new(cutset_type);
+                two_pass.compile(&restricted);
+                let expected_restricted = (two_pass.best_value(), two_pass.best_solution());
+                two_pass.compile(&restricted.for_relaxed());
+                let explored = two_pass.get_explored();
+                let counts = (two_pass.node_count(), two_pass.arc_count());
+                let mut expected = (two_pass.best_value(), two_pass.best_solution(), two_pass.is_exact(), vec![]);
+                two_pass.drain_cutset(|n| expected.3.push((n.state.depth, n.state.capacity, n.value, n.ub)));
+
+                let restricted = CompilationInput { reuse_prefix: true, ..restricted };
+                let mut fused = All::new(cutset_type);
+                fused.compile(&restricted);
+                assert_eq!(expected_restricted, (fused.best_value(), fused.best_solution()));
+                assert!(fused.resume_relaxed(&restricted.for_relaxed()));
+                assert_eq!(explored, fused.get_explored() + fused.stats().reused_nodes);
+                assert_eq!(counts, (fused.node_count(), fused.arc_count()));
+                let mut actual = (fused.best_value(), fused.best_solution(), fused.is_exact(), vec![]);
+                fused.drain_cutset(|n| actual.3.push((n.state.depth, n.state.capacity, n.value, n.ub)));
+                expected.3.sort_unstable();
+                actual.3.sort_unstable();
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn the_adaptive_cutset_is_the_smaller_one() {
+        let toy = Knapsack::toy();
+        let lel = drained(&mut All::new(CutsetType::LastExactLayer), &toy, 2, isize::MIN);
+        assert!(lel.len() < drained(&mut All::new(CutsetType::Frontier), &toy, 2, isize::MIN).len());
+        assert_eq!(lel, drained(&mut All::new(CutsetType::Adaptive), &toy, 2, isize::MIN));
+
+        // once the nodes which cannot beat 7 are pruned, the frontier of this
+        // knapsack is smaller than its last exact layer
+        let funnel = Knapsack::funnel();
+        let frontier = drained(&mut All::new(CutsetType::Frontier), &funnel, 4, 7);
+        assert!(frontier.len() < drained(&mut All::new(CutsetType::LastExactLayer), &funnel, 4, 7).len());
+        assert_eq!(frontier, drained(&mut All::new(CutsetType::Adaptive), &funnel, 4, 7));
+    }
+
+    #[test]
+    fn a_slow_estimate_is_attributed_to_the_model() {
+        let delay = Duration::from_millis(2);
+        let (problem, calls) = Tweaked::slow_estimate(Knapsack::toy(), delay);
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&CompilationInput { timed: true, ..problem.input(CompilationType::Relaxed, vec![]) });
+        let calls = calls.load(Ordering::Relaxed) as u32;
+        assert!(calls > 0);
+        assert!(mdd.model_time() >= delay * calls);
+        assert!(mdd.model_time() > mdd.framework_time());
+        let timed = (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value());
+
+        // nothing is measured unless the compilation is timed, which does not
+        // change the dd
+        mdd.compile(&problem.input(CompilationType::Relaxed, vec![]));
+        assert_eq!(timed, (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value()));
+        assert_eq!(Duration::ZERO, mdd.model_time());
+        assert_eq!(Duration::ZERO, mdd.framework_time());
+    }
+
+    #[test]
+    fn a_slow_relax_is_attributed_to_the_model() {
+        let problem = Knapsack::toy();
+        let relax = KnapsackSlowRelax { delay: Duration::from_millis(2), ..Default::default() };
+        let input = CompilationInputBuilder::relaxed(&problem, &relax, &KnapsackRanking).root().max_width(2).timed(true).build().unwrap();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input);
+        let calls = relax.calls.load(Ordering::Relaxed) as u32;
+        assert!(calls > 0);
+        assert!(mdd.model_time() >= relax.delay * calls);
+        assert!(mdd.model_time() > mdd.framework_time());
+    }
+
+    #[test]
+    fn the_model_sees_the_variables_assigned_above_each_layer() {
+        let (problem, observed) = Tweaked::context_probe(Knapsack::toy());
+        let path = vec![Decision { var: Variable(0), value: 1 }, Decision { var: Variable(1), value: 0 }];
+        for comp_type in [CompilationType::Exact, CompilationType::Restricted, CompilationType::Relaxed] {
+            observed.lock().unwrap().clear();
+            let mut mdd = All::new(CutsetType::LastExactLayer);
+            mdd.compile(&problem.input(comp_type, path.clone()));
+
+            // the residual path, and then one more variable per compiled layer
+            let expected = (2..=6).map(|depth| (0..depth).collect::<Vec<_>>()).collect::<Vec<_>>();
+            assert_eq!(expected, *observed.lock().unwrap());
+        }
+    }
+
+    #[test]
+    fn the_estimates_see_the_best_path_of_each_node() {
+        let path = vec![Decision { var: Variable(0), value: 1 }, Decision { var: Variable(1), value: 0 }];
+        for comp_type in [CompilationType::Exact, CompilationType::Restricted] {
+            let (problem, observed) = Tweaked::path_probe(Knapsack::toy(), true);
+            let mut mdd = All::new(CutsetType::Frontier);
+            mdd.compile(&problem.input(comp_type, path.clone()));
+
+            // the nodes of an exact or restricted dd are all reached by their best path
+            let observed = observed.lock().unwrap();
+            assert!(!observed.is_empty());
+            for (state, best_path) in observed.iter() {
+                assert!(best_path.starts_with(&path));
+                assert_eq!(*state, problem.replay(best_path));
+            }
+        }
+    }
+
+    #[test]
+    fn the_paths_are_only_materialized_when_the_problem_estimates_from_them() {
+        let ((plain, plain_observed), (probed, probed_observed)) = (Tweaked::path_probe(Knapsack::toy(), false), Tweaked::path_probe(Knapsack::toy(), true));
+        for comp_type in [CompilationType::Exact, CompilationType::Restricted, CompilationType::Relaxed] {
+            let mut mdd = All::new(CutsetType::Frontier);
+            mdd.compile(&plain.input(comp_type, vec![]));
+            let expected = (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value());
+            mdd.compile(&probed.input(comp_type, vec![]));
+            assert_eq!(expected, (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value()));
+        }
+        assert!(plain_observed.lock().unwrap().is_empty());
+        assert!(!probed_observed.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_relaxed_dd_prunes_with_the_incumbent_found_while_it_compiles() {
+        // the toy knapsack is worth 13: an incumbent of 12 keeps the bound valid
+        let (problem, incumbent) = Tweaked::improving(Knapsack::toy(), 3, 12);
+        let compile = |incumbent| {
+            let mut mdd = All::new(CutsetType::LastExactLayer);
+            let builder = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(3);
+            mdd.compile(&match incumbent {
+                Some(incumbent) => builder.incumbent(incumbent).build().unwrap(),
+                None => builder.build().unwrap(),
+            });
+            (mdd.best_value(), *mdd.stats())
+        };
+        let (stale, stale_stats) = compile(None);
+        let (fresh, fresh_stats) = compile(Some(&incumbent));
+
+        assert_eq!(12, incumbent.load(Ordering::Relaxed));
+        assert_eq!((0, 1), (stale_stats.bound_refreshes, fresh_stats.bound_refreshes));
+        assert!(fresh_stats.rub_prunes > stale_stats.rub_prunes);
+        assert!(stale.unwrap() >= 13);
+        assert!(fresh.unwrap_or(isize::MIN).max(12) >= 13);
+    }
+
+    /// A knapsack where any two of the four items make an optimal solution
+    fn ties() -> Knapsack {
+        Knapsack { capacity: 2, profit: vec![1, 1, 1, 1], weight: vec![1, 1, 1, 1] }
+    }
+    fn taken(solution: &[Decision]) -> Vec<usize> {
+        let mut taken = solution.iter().filter(|d| d.value == 1).map(|d| d.var.id()).collect::<Vec<_>>();
+        taken.sort_unstable();
+        taken
+    }
+
+    #[test]
+    fn every_tie_of_the_best_solution_is_enumerated() {
+        let problem = ties();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Exact, usize::MAX));
+        assert_eq!(Some(2), mdd.best_value());
+
+        let solutions = mdd.best_solutions(100);
+        assert_eq!(mdd.best_solution().as_ref(), solutions.first());
+        let mut tied = solutions.iter().map(|sol| taken(sol)).collect::<Vec<_>>();
+        tied.sort_unstable();
+        assert_eq!(vec![vec![0, 1], vec![0, 2], vec![0, 3], vec![1, 2], vec![1, 3], vec![2, 3]], tied);
+        assert!(solutions.iter().all(|sol| sol.len() == 4));
+
+        assert_eq!(solutions[..4], mdd.best_solutions(4)[..]);
+    }
+
+    #[test]
+    fn no_tie_goes_through_a_relaxed_node() {
+        let problem = ties();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, CompilationType::Relaxed, 2));
+        let best = mdd.best_value().unwrap();
+        // a bound has no ties to enumerate
+        assert_eq!(mdd.best_exact_value().is_none(), mdd.best_solutions(100).is_empty());
+        for sol in mdd.best_solutions(100) {
+            let taken = taken(&sol);
+            assert!(taken.len() <= problem.capacity);
+            assert_eq!(best, taken.len() as isize);
+        }
+    }
+
+    /// Checks the exact path flag of each node against a walk of its best path
+    fn assert_exact_paths(mdd: &All<KnapsackState>) {
+        for (id, node) in mdd.nodes.iter().enumerate().filter(|(_, node)| !node.flags.is_deleted()) {
+            assert_eq!(mdd.walk_exact_best_path(Some(NodeId(id))), node.flags.is_exact_path(), "node #{} at depth {}", id, node.state.depth);
+        }
+        assert_eq!(mdd.walk_exact_best_path(mdd.best_n), mdd.has_exact_best_path(mdd.best_n));
+    }
+
+    #[test]
+    fn the_exact_path_flags_agree_with_the_best_paths() {
+        let larger = Knapsack {
+            capacity: 30,
+            profit: (0..14).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..14).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        for problem in [Knapsack::toy(), larger] {
+            for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+                for width in 1..=6 {
+                    for offset in 0..=2 {
+                        let mut mdd = All::new(cutset_type);
+                        let restricted = CompilationInputBuilder::restricted(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(width).build().unwrap();
+                        mdd.compile(&restricted);
+                        assert_exact_paths(&mdd);
+                        let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(width).min_relax_depth_offset(offset);
+                        mdd.compile(&relaxed.build().unwrap());
+                        assert_exact_paths(&mdd);
+                        let stingy = CompilationInputBuilder::relaxed(&problem, &KnapsackStingyRelax, &KnapsackRanking).root().max_width(width).min_relax_depth_offset(offset);
+                        mdd.compile(&stingy.build().unwrap());
+                        assert_exact_paths(&mdd);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_recycled_merged_node_is_not_on_an_exact_path() {
+        // with a width of 2, the second layer keeps the node of capacity 3 and
+        // merges the three others into it
+        let problem = Knapsack::toy();
+        let cutset_type = CutsetType::LastExactLayer;
+        let mut mdd = All::new(cutset_type);
+        let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackMergeInto(3), &KnapsackRanking).root().max_width(2).check_merge(false).build().unwrap();
+        mdd.compile(&relaxed);
+        let recycled = mdd.nodes.iter().position(|n| n.state.capacity == 3 && n.flags.is_relaxed() && !n.flags.is_deleted());
+        assert!(recycled.is_some());
+        assert!(!mdd.nodes[recycled.unwrap()].flags.is_exact_path());
+        assert_exact_paths(&mdd);
+        assert_eq!(None, mdd.best_exact_value());
+    }
+
+    #[test]
+    fn a_chunked_merge_gives_a_valid_bound() {
+        let problem = Knapsack {
+            capacity: 30,
+            profit: (0..14).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..14).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut exact = All::new(CutsetType::LastExactLayer);
+        exact.compile(&CompilationInputBuilder::exact(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(usize::MAX).build().unwrap());
+        let optimum = exact.best_value().unwrap();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            for width in 1..=6 {
+                for chunks in 2..=4 {
+                    let mut mdd = All::new(cutset_type);
+                    let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(width).merge_policy(MergePolicy::Chunked(chunks));
+                    mdd.compile(&relaxed.self_check(true).build().unwrap());
+                    assert!(mdd.best_value().unwrap() >= optimum);
+                    mdd.drain_violations(|v| panic!("unexpected violation: {}", v));
+                    assert_exact_paths(&mdd);
+                }
+            }
         }
     }
+
+    #[test]
+    fn the_chunks_merged_into_the_same_state_share_their_node() {
+        // no exact node has that capacity: the first chunk creates the node
+        // which the second one recycles
+        let problem = Knapsack::toy();
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackMergeInto(11), &KnapsackRanking).root().max_width(3).merge_policy(MergePolicy::Chunked(2));
+        mdd.compile(&relaxed.check_merge(false).build().unwrap());
+        let live = mdd.nodes.iter().filter(|n| !n.flags.is_deleted()).map(|n| n.state.as_ref()).collect::<Vec<_>>();
+        for (i, state) in live.iter().enumerate() {
+            assert!(!live[i + 1..].contains(state));
+        }
+        assert_exact_paths(&mdd);
+    }
+
+    #[test]
+    fn collapsing_the_forced_layers_keeps_the_optimum_and_its_path() {
+        let problem = Chained::toy();
+        let builder = || CompilationInputBuilder::exact(&problem, &ChainedRelax, &ChainedRelax).root().max_width(usize::MAX);
+
+        let mut layered = All::new(CutsetType::LastExactLayer);
+        layered.compile(&builder().build().unwrap());
+        let mut collapsed = All::new(CutsetType::LastExactLayer);
+        collapsed.compile(&builder().max_forced_layers(usize::MAX).build().unwrap());
+
+        assert_eq!(Some(10), collapsed.best_value());
+        assert_eq!((0, 3), (layered.stats().collapsed_layers, collapsed.stats().collapsed_layers));
+        let mut solution = collapsed.best_solution().unwrap();
+        solution.sort_unstable_by_key(|d| d.var.id());
+        assert_eq!(vec![0, 0, 1, 1, 0, 0], solution.iter().map(|d| d.value).collect::<Vec<_>>());
+        assert!(collapsed.nb_nodes() < layered.nb_nodes());
+    }
 }
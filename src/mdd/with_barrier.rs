@@ -1,14 +1,22 @@
-use std::{collections::hash_map::Entry, hash::Hash, sync::Arc};
+use std::{
+    collections::hash_map::Entry,
+    hash::{Hash, Hasher},
+    ops::Range,
+    sync::{atomic::{AtomicUsize, Ordering}, Arc},
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+    collections::VecDeque,
+};
 
-use parking_lot::RwLock;
-use rustc_hash::FxHashMap;
+use parking_lot::{Mutex, RwLock};
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 
 use crate::{
-    prelude::{CompilationInput, CompilationType, Decision, Problem, Relaxation, StateRanking},
-    DecisionDiagram, SubProblem, CutsetType,
+    prelude::{AssignedVars, CompilationInput, CompilationStats, CompilationType, Decision, DominanceChecker, Problem, Relaxation, StateRanking, Variable},
+    validate::check_estimate, DecisionDiagram, PathSegment, SubProblem, CutsetType, Violation,
 };
 
-use super::node_flags::NodeFlags;
+use super::{arena::Arena, node_flags::NodeFlags};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 struct NodeId(usize);
@@ -26,14 +34,25 @@ struct Node<T> {
     //
     depth: usize,
     //
-    value_bot: isize,
-    theta: isize,
-    //
     rub: isize,
     //
     flags: NodeFlags,
 }
 
+/// The local bound and the threshold of a node. Only the relaxed compilations
+/// compute them: they are kept apart from the nodes, so that the restricted
+/// (and exact) dds do not pay for their memory.
+#[derive(Debug, Clone, Copy)]
+struct LocalBounds {
+    value_bot: isize,
+    theta: isize,
+}
+impl Default for LocalBounds {
+    fn default() -> Self {
+        Self { value_bot: isize::MIN, theta: isize::MAX }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct Edge {
     //_my_id   : EdgeId,
@@ -43,37 +62,436 @@ struct Edge {
     cost: isize,
     next: Option<EdgeId>,
 }
-#[derive(Debug)]
+/// Where a compilation stands: the layer being developed, its depth, and the
+/// variables assigned on the paths leading to it
+#[derive(Debug, Clone)]
+struct Cursor {
+    curr_l: Vec<NodeId>,
+    depth: usize,
+    /// Whether some node of the last expanded layer was branched on
+    branched: bool,
+    assigned: AssignedVars,
+}
+
+/// The layers that a restricted compilation developed before its width first
+/// bound, which are those of the relaxed dd of the same subproblem (see
+/// `DecisionDiagram::resume_relaxed`). The nodes and edges of these layers are
+/// the first ones of the arenas: the restricted dd only appends to them.
+#[derive(Debug, Clone)]
+struct Prefix {
+    nb_nodes: usize,
+    nb_edges: usize,
+    /// The variable of the layer whose width bound, along with the cursor of
+    /// the compilation when it was reached
+    var: Variable,
+    cursor: Cursor,
+    prev_l: Vec<NodeId>,
+    /// The estimates of the nodes of that layer, before the restricted dd
+    /// expanded them
+    rubs: Vec<isize>,
+    best_lb: isize,
+    stats: CompilationStats,
+    explored: usize,
+    avoided_allocations: usize,
+    reservations: usize,
+    reallocations: usize,
+    deleted: usize,
+    growth: Option<f64>,
+}
+
+/// The threshold of a state in the barrier, and whether the subproblem of that
+/// state was explored.
+///
+/// Internal: this is not part of the stable api (see `api`) and may change in
+/// any release.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BarrierInfo {
     pub theta: isize,
     pub explored: bool,
 }
 
+/// The number of shards of each layer of a `BarrierStore` (by default)
+pub const DEFAULT_BARRIER_SHARDS: usize = 16;
+
+/// The thresholds of the states whose hash falls into some shard of a layer
+type Shard<T> = RwLock<FxHashMap<Arc<T>, BarrierInfo>>;
+
+/// What the barrier did at some depth (see `BarrierStore::layer_stats`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BarrierLayerStats {
+    /// The lookups which pruned a node (in a dd, or when it was popped)
+    pub hits: usize,
+    /// The lookups which let a node through
+    pub misses: usize,
+    /// The thresholds which were stored for a new state
+    pub inserts: usize,
+    /// The thresholds which were raised (or marked as explored)
+    pub overwrites: usize,
+}
+
+/// The number of events kept in the log of a watched state (by default)
+pub const DEFAULT_WATCH_CAPACITY: usize = 10_000;
+
+/// What the barrier did with the watched state (see `BarrierStore::watch`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarrierAction {
+    /// Its first threshold was stored
+    Insert(BarrierInfo),
+    /// Its threshold was raised (or marked as explored)
+    Overwrite { from: BarrierInfo, to: BarrierInfo },
+    /// A node having that state and value was pruned by its threshold
+    Prune { value: isize, threshold: BarrierInfo },
+    /// A node having that state and value was let through
+    Explore { value: isize, threshold: Option<BarrierInfo> },
+}
+
+/// An interaction of some thread with the threshold of the watched state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarrierEvent {
+    pub action: BarrierAction,
+    pub thread: ThreadId,
+    pub depth: usize,
+    /// The best lower bound known to the thread at that time
+    pub best_lb: isize,
+}
+
+/// The state whose interactions with the barrier are logged, along with the
+/// latest of them
+#[derive(Debug)]
+struct Watch<T> {
+    state: T,
+    capacity: usize,
+    log: Mutex<VecDeque<BarrierEvent>>,
+}
+
+/// The counters behind the `BarrierLayerStats` of a layer
+#[derive(Debug, Default)]
+struct LayerCounters {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    inserts: AtomicUsize,
+    overwrites: AtomicUsize,
+}
+
+/// The thresholds shared among all threads, for each layer of the problem.
+/// Each layer is split into shards keyed by the hash of the states, so that
+/// the threads which work at the same depth seldom wait for the same lock.
+///
+/// The barrier can be restricted to a range of depths (see `with_depths`):
+/// outside of it, no threshold is ever stored, hence no node is pruned.
+#[derive(Debug)]
+pub struct BarrierStore<T> {
+    layers: Vec<Vec<Shard<T>>>,
+    counters: Vec<LayerCounters>,
+    /// The depths at which the thresholds are read and written
+    depths: Range<usize>,
+    /// The state whose interactions are logged, if any
+    watch: Option<Watch<T>>,
+}
+/// The thresholds shared among all threads (see `BarrierStore`).
+pub type Barriers<T> = Arc<BarrierStore<T>>;
+
+impl<T: Eq + Hash> BarrierStore<T> {
+    /// Creates empty thresholds for the given number of layers
+    pub fn new(nb_layers: usize) -> Self {
+        Self::with_shards(nb_layers, DEFAULT_BARRIER_SHARDS)
+    }
+    /// Creates empty thresholds for the given number of layers, each of which
+    /// is split into the given number of shards
+    pub fn with_shards(nb_layers: usize, nb_shards: usize) -> Self {
+        let layer = || (0..nb_shards.max(1)).map(|_| RwLock::new(FxHashMap::default())).collect();
+        Self {
+            layers: (0..nb_layers).map(|_| layer()).collect(),
+            counters: (0..nb_layers).map(|_| LayerCounters::default()).collect(),
+            depths: 0..nb_layers,
+            watch: None,
+        }
+    }
+    /// Restricts the barrier to the given depths
+    ///
+    /// # Panics
+    /// When the range is empty or goes beyond the last layer
+    pub fn with_depths(mut self, depths: Range<usize>) -> Self {
+        assert!(
+            depths.start < depths.end && depths.end <= self.layers.len(),
+            "the barrier depths {:?} must be a non empty range within 0..{}",
+            depths,
+            self.layers.len()
+        );
+        self.depths = depths;
+        self
+    }
+    pub fn nb_layers(&self) -> usize {
+        self.layers.len()
+    }
+    /// Returns the depths at which the thresholds are read and written
+    pub fn depths(&self) -> Range<usize> {
+        self.depths.clone()
+    }
+    /// Returns the threshold of the given state, if any
+    pub fn get_threshold(&self, depth: usize, state: &T) -> Option<BarrierInfo> {
+        if !self.depths.contains(&depth) {
+            return None;
+        }
+        self.shard(depth, state).read().get(state).copied()
+    }
+    /// Counts a lookup of the threshold of some state at the given depth,
+    /// which pruned the node having that state (a hit) or not (a miss)
+    pub(crate) fn record_lookup(&self, depth: usize, hit: bool) {
+        if self.depths.contains(&depth) {
+            let counters = &self.counters[depth];
+            let counter = if hit { &counters.hits } else { &counters.misses };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    /// Returns what the barrier did at each depth
+    pub fn layer_stats(&self) -> Vec<BarrierLayerStats> {
+        self.counters
+            .iter()
+            .map(|c| BarrierLayerStats {
+                hits: c.hits.load(Ordering::Relaxed),
+                misses: c.misses.load(Ordering::Relaxed),
+                inserts: c.inserts.load(Ordering::Relaxed),
+                overwrites: c.overwrites.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+    /// Raises the threshold of the given state to `theta` (or marks it as
+    /// explored when `theta` equals its threshold). The comparison is made
+    /// under the write lock of the shard, hence a concurrent update can never
+    /// replace a higher threshold. It returns true iff the threshold changed.
+    /// Outside of the depths of the barrier, nothing is stored but the update
+    /// is deemed to have succeeded.
+    pub fn update_threshold(&self, depth: usize, state: Arc<T>, theta: isize, explored: bool) -> bool {
+        self.raise_threshold(depth, state, theta, explored).is_some()
+    }
+    /// Updates the threshold of the given state like `update_threshold`, and
+    /// logs the change when that state is watched
+    pub(crate) fn update_watched_threshold(&self, depth: usize, state: Arc<T>, theta: isize, explored: bool, best_lb: isize) -> bool {
+        if !self.is_watched(&state) {
+            return self.update_threshold(depth, state, theta, explored);
+        }
+        let to = BarrierInfo { theta, explored };
+        match self.raise_threshold(depth, state, theta, explored) {
+            Some(Some(from)) => self.log(depth, BarrierAction::Overwrite { from, to }, best_lb),
+            Some(None) if self.depths.contains(&depth) => self.log(depth, BarrierAction::Insert(to), best_lb),
+            Some(None) => {}
+            None => return false,
+        }
+        true
+    }
+    /// Raises the threshold like `update_threshold`. It returns the threshold
+    /// it replaced (none when there was none), or none at all when the
+    /// threshold did not change.
+    fn raise_threshold(&self, depth: usize, state: Arc<T>, theta: isize, explored: bool) -> Option<Option<BarrierInfo>> {
+        if !self.depths.contains(&depth) {
+            return Some(None);
+        }
+        let mut shard = self.shard(depth, &state).write();
+        let counters = &self.counters[depth];
+        match shard.entry(state) {
+            Entry::Occupied(mut e) => {
+                let info = e.get_mut();
+                let update = theta > info.theta || (theta == info.theta && !info.explored && explored);
+                if !update {
+                    return None;
+                }
+                let from = *info;
+                *info = BarrierInfo { theta, explored };
+                counters.overwrites.fetch_add(1, Ordering::Relaxed);
+                Some(Some(from))
+            }
+            Entry::Vacant(e) => {
+                e.insert(BarrierInfo { theta, explored });
+                counters.inserts.fetch_add(1, Ordering::Relaxed);
+                Some(None)
+            }
+        }
+    }
+    /// Logs every interaction of the threads with the threshold of the given
+    /// state: the insertion and the raises of its threshold, and the nodes
+    /// having that state which it prunes (or lets through). Only the latest
+    /// `capacity` events are kept (see `watched_state_log`).
+    pub fn watch(&mut self, state: T, capacity: usize) {
+        self.watch = Some(Watch { state, capacity, log: Mutex::new(VecDeque::new()) });
+    }
+    /// Returns the latest interactions with the threshold of the watched
+    /// state, from the oldest to the newest (nothing when no state is watched)
+    pub fn watched_state_log(&self) -> Vec<BarrierEvent> {
+        self.watch.as_ref().map_or_else(Vec::new, |watch| watch.log.lock().iter().cloned().collect())
+    }
+    /// Returns true iff the given state is the watched one
+    pub(crate) fn is_watched(&self, state: &T) -> bool {
+        self.watch.as_ref().is_some_and(|watch| watch.state == *state)
+    }
+    /// Logs that a node having the given state and value was pruned (or let
+    /// through) by the given threshold, when that state is watched
+    pub(crate) fn log_lookup(&self, depth: usize, state: &T, value: isize, threshold: Option<BarrierInfo>, pruned: bool, best_lb: isize) {
+        if !self.is_watched(state) || !self.depths.contains(&depth) {
+            return;
+        }
+        let action = match threshold.filter(|_| pruned) {
+            Some(threshold) => BarrierAction::Prune { value, threshold },
+            None => BarrierAction::Explore { value, threshold },
+        };
+        self.log(depth, action, best_lb);
+    }
+    fn log(&self, depth: usize, action: BarrierAction, best_lb: isize) {
+        if let Some(watch) = self.watch.as_ref() {
+            let mut log = watch.log.lock();
+            if log.len() == watch.capacity {
+                log.pop_front();
+            }
+            log.push_back(BarrierEvent { action, thread: thread::current().id(), depth, best_lb });
+        }
+    }
+    /// Forgets about the thresholds of the given layer
+    pub fn clear_layer(&self, depth: usize) {
+        self.layers[depth].iter().for_each(|shard| shard.write().clear());
+    }
+    /// Returns true iff the given layer has no threshold
+    pub fn is_layer_empty(&self, depth: usize) -> bool {
+        self.layers[depth].iter().all(|shard| shard.read().is_empty())
+    }
+    /// Estimates the number of bytes held by the thresholds. It only reads
+    /// the length of each shard.
+    pub fn footprint(&self) -> usize {
+        let entries = self.layers.iter().flatten().map(|shard| shard.read().len()).sum::<usize>();
+        entries * (std::mem::size_of::<(Arc<T>, BarrierInfo)>() + std::mem::size_of::<T>())
+    }
+    /// Forgets that the given state was explored with the given value, when
+    /// its threshold was set by that exploration (see `update_threshold`)
+    pub(crate) fn reopen(&self, depth: usize, state: &T, value: isize) {
+        if !self.depths.contains(&depth) {
+            return;
+        }
+        if let Some(info) = self.shard(depth, state).write().get_mut(state).filter(|info| info.theta == value) {
+            info.explored = false;
+        }
+    }
+    /// Returns the thresholds of each layer. The read locks of all the shards
+    /// are taken before any of them is read: the thresholds are those of a
+    /// single instant, even when the barrier is being updated.
+    #[cfg(any(test, feature = "checkpoint"))]
+    pub(crate) fn thresholds(&self) -> Vec<Vec<(Arc<T>, BarrierInfo)>> {
+        let layers = self.layers.iter().map(|layer| layer.iter().map(|shard| shard.read()).collect::<Vec<_>>()).collect::<Vec<_>>();
+        layers
+            .iter()
+            .map(|shards| shards.iter().flat_map(|shard| shard.iter().map(|(state, info)| (state.clone(), *info))).collect())
+            .collect()
+    }
+    /// Stores the thresholds returned by `thresholds` (which are not counted
+    /// as inserts by the layer stats)
+    #[cfg(feature = "checkpoint")]
+    pub(crate) fn restore_thresholds(&self, layers: Vec<Vec<(Arc<T>, BarrierInfo)>>) {
+        for (depth, layer) in layers.into_iter().enumerate() {
+            for (state, info) in layer {
+                self.shard(depth, &state).write().insert(state, info);
+            }
+        }
+    }
+    fn shard(&self, depth: usize, state: &T) -> &Shard<T> {
+        let layer = &self.layers[depth];
+        let mut hasher = FxHasher::default();
+        state.hash(&mut hasher);
+        &layer[(hasher.finish() % layer.len() as u64) as usize]
+    }
+}
+
+/// The arena of the nodes of a dd
+type NodeArena<T> = Arena<Node<T>>;
+/// The arena of the edges of a dd
+type EdgeArena = Arena<Edge>;
+
 #[derive(Debug, Clone)]
 pub struct Barrier<T>
 where
     T: Eq + PartialEq + Hash + Clone,
 {
     root_pa: Vec<Decision>,
+    /// The prefix of the residual subproblem, which the nodes of the cutset
+    /// share: only the decisions of `root_pa` made after it go in their paths
+    root_prefix: Option<Arc<PathSegment>>,
+    /// The best path of the node being estimated, from the root of the
+    /// problem (see `Problem::estimates_from_path`)
+    path_buffer: Vec<Decision>,
     //
-    barriers: Arc<Vec<RwLock<FxHashMap<Arc<T>, BarrierInfo>>>>,
+    barriers: Barriers<T>,
     //
-    nodes: Vec<Node<T>>,
-    edges: Vec<Edge>,
+    nodes: NodeArena<T>,
+    edges: EdgeArena,
+    /// The forced decisions that the arcs (by id) take after their own
+    /// decision, when the layers below them were collapsed (see
+    /// `CompilationInput::max_forced_layers`)
+    forced: FxHashMap<usize, Vec<Decision>>,
+    /// The local bounds of the nodes (by id), which stay empty unless the dd
+    /// is relaxed
+    bounds: Vec<LocalBounds>,
     //
     prev_l: Vec<NodeId>,
     next_l: FxHashMap<Arc<T>, NodeId>,
     cutset: Vec<NodeId>,
+    /// The frontier cutset, while an adaptive dd weighs it against the last
+    /// exact layer (which it then gathers in `cutset`)
+    frontier: Vec<NodeId>,
     lel_depth: Option<usize>,
+    /// The exact nodes that a layer too shallow to be merged could not hold
+    /// (see `CompilationInput::restrict_first_layers`), and the greatest of
+    /// their upper bounds
+    dropped: Vec<NodeId>,
+    dropped_ub: Option<isize>,
+    /// The terminal values of the nodes of the last layer which have one
+    /// (see `Problem::terminal_value`)
+    terminal: FxHashMap<NodeId, isize>,
     //
     best_n: Option<NodeId>,
+    /// The best lower bound the dd prunes with: that of the input, unless the
+    /// incumbent improved on it during the compilation (see
+    /// `CompilationInput::incumbent`)
+    best_lb: isize,
     // ebpo
     exact: bool,
     approximate: bool,
+    /// Whether the last compilation was restricted and all the nodes it kept
+    /// in some layer were dead ends
+    ran_dry: bool,
+    /// What the last compilation restricted, merged and pruned
+    stats: CompilationStats,
     //
     cutset_type: CutsetType,
     //
     explored: usize,
+    /// The number of transitions that reached a state which already existed
+    /// in the next layer (hence, for which no arc has been allocated)
+    avoided_allocations: usize,
+    /// The arc of the last state which turned out to be in the next layer
+    /// already: it holds the next state which is looked up in that layer
+    spare: Option<Arc<T>>,
+    /// The number of layers whose memory was reserved before branching
+    reservations: usize,
+    /// The number of times the next layer, the nodes or the edges outgrew
+    /// their memory while a layer was expanded
+    reallocations: usize,
+    /// The number of nodes that the restricted and relaxed layers dropped
+    /// (see `DecisionDiagram::node_count`)
+    deleted: usize,
+    /// The number of nodes of the last layer per node of the one before it
+    growth: Option<f64>,
+    /// The transitions of the layer being expanded, when a timed compilation
+    /// computes them ahead of time
+    transitions: Vec<(NodeId, Decision, T, isize)>,
+    model_time: Duration,
+    framework_time: Duration,
+    /// The violations found by a self checked compilation
+    violations: Vec<Violation<T>>,
+    /// The layers the last compilation kept for the relaxed dd of its
+    /// subproblem (see `CompilationInput::reuse_prefix`), along with the
+    /// thresholds that dd gives to their nodes: these only reach the barrier
+    /// once it resumes from them
+    prefix: Option<Prefix>,
+    deferred: Vec<(usize, Arc<T>, isize)>,
 }
 impl<T> DecisionDiagram for Barrier<T>
 where
@@ -87,7 +505,31 @@ where
         R: Relaxation<State = P::State>,
         O: StateRanking<State = P::State>,
     {
-        self._compile(input)
+        if input.timed {
+            let start = Instant::now();
+            self._compile(input);
+            self.framework_time = start.elapsed().saturating_sub(self.model_time);
+        } else {
+            self._compile(input)
+        }
+        self.stats.total_arcs = self.edges.len();
+    }
+
+    fn resume_relaxed<P, R, O>(&mut self, input: &CompilationInput<P, R, O>) -> bool
+    where
+        P: Problem<State = Self::State>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let start = Instant::now();
+        if !self._resume_relaxed(input) {
+            return false;
+        }
+        if input.timed {
+            self.framework_time = start.elapsed().saturating_sub(self.model_time);
+        }
+        self.stats.total_arcs = self.edges.len();
+        true
     }
 
     fn is_exact(&self) -> bool {
@@ -95,67 +537,223 @@ where
     }
 
     fn best_value(&self) -> Option<isize> {
-        self._best_value()
+        self._best_value().max(self.dropped_ub)
     }
 
     fn best_solution(&self) -> Option<Vec<Decision>> {
         self._best_solution()
     }
 
+    fn best_exact_value(&self) -> Option<isize> {
+        self._best_value().filter(|_| self.has_exact_best_path(self.best_n))
+    }
+
+    fn best_exact_solution(&self) -> Option<Vec<Decision>> {
+        self.has_exact_best_path(self.best_n).then(|| self._best_solution()).flatten()
+    }
+
+    fn best_solutions(&self, limit: usize) -> Vec<Vec<Decision>> {
+        self._best_solutions(limit)
+    }
+
     fn drain_cutset<F>(&mut self, func: F)
     where
         F: FnMut(SubProblem<T>),
     {
+        assert!(!self.exact, "an exact dd has no cutset to drain");
         self._drain_cutset(func)
     }
+
+    fn nb_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn nb_active_nodes(&self) -> usize {
+        self.nodes.iter().filter(|n| !n.flags.is_deleted()).count()
+    }
+
+    fn nb_avoided_allocations(&self) -> usize {
+        self.avoided_allocations
+    }
+
+    fn nb_reservations(&self) -> usize {
+        self.reservations
+    }
+
+    fn nb_reallocations(&self) -> usize {
+        self.reallocations
+    }
+
+    fn ran_dry(&self) -> bool {
+        self.ran_dry
+    }
+
+    fn stats(&self) -> &CompilationStats {
+        &self.stats
+    }
+
+    fn model_time(&self) -> Duration {
+        self.model_time
+    }
+
+    fn framework_time(&self) -> Duration {
+        self.framework_time
+    }
+
+    fn drain_violations<F>(&mut self, func: F)
+    where
+        F: FnMut(Violation<T>),
+    {
+        self.violations.drain(..).for_each(func)
+    }
+
+    fn nb_edges(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|n| !n.flags.is_deleted())
+            .map(|n| {
+                let mut count = 0;
+                let mut edge_id = n.inbound;
+                while let Some(eid) = edge_id {
+                    count += 1;
+                    edge_id = self.edges[eid.0].next;
+                }
+                count
+            })
+            .sum()
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len() - self.deleted
+    }
+
+    fn arc_count(&self) -> usize {
+        self.edges.len()
+    }
 }
 impl<T> Barrier<T>
 where
     T: Eq + PartialEq + Hash + Clone,
 {
-    pub fn new(barriers: Arc<Vec<RwLock<FxHashMap<Arc<T>, BarrierInfo>>>>, cutset_type: CutsetType) -> Self {
+    pub fn new(barriers: Barriers<T>, cutset_type: CutsetType) -> Self {
+        Self::with_capacity(barriers, cutset_type, 0, 0)
+    }
+    /// Creates a dd whose arenas have room for the given number of nodes and
+    /// edges from the start. The arenas are never released, hence a dd which
+    /// is reused for many compilations only allocates its memory once (as long
+    /// as it does not outgrow these capacities).
+    pub fn with_capacity(barriers: Barriers<T>, cutset_type: CutsetType, nodes: usize, edges: usize) -> Self {
         Self {
             root_pa: vec![],
-            barriers: barriers,
-            nodes: vec![],
-            edges: vec![],
+            root_prefix: None,
+            path_buffer: vec![],
+            barriers,
+            nodes: NodeArena::with_capacity(nodes),
+            edges: EdgeArena::with_capacity(edges),
+            forced: Default::default(),
+            bounds: vec![],
             prev_l: Default::default(),
             next_l: Default::default(),
             cutset: vec![],
+            frontier: vec![],
             lel_depth: None,
+            dropped: vec![],
+            dropped_ub: None,
+            terminal: Default::default(),
             best_n: None,
+            best_lb: isize::MIN,
             exact: true,
             approximate: false,
-            cutset_type: cutset_type,
+            ran_dry: false,
+            stats: CompilationStats::default(),
+            cutset_type,
             explored: 0,
+            avoided_allocations: 0,
+            spare: None,
+            reservations: 0,
+            reallocations: 0,
+            deleted: 0,
+            growth: None,
+            transitions: vec![],
+            model_time: Duration::ZERO,
+            framework_time: Duration::ZERO,
+            violations: vec![],
+            prefix: None,
+            deferred: vec![],
         }
     }
+    /// Returns the number of nodes and edges this dd can hold without
+    /// allocating more memory
+    pub fn capacity(&self) -> (usize, usize) {
+        (self.nodes.capacity(), self.edges.capacity())
+    }
     fn clear(&mut self) {
         self.root_pa.clear();
+        self.root_prefix = None;
         self.nodes.clear();
         self.edges.clear();
+        self.forced.clear();
+        self.bounds.clear();
+        self.prefix = None;
+        self.deferred.clear();
+        self.stats = CompilationStats::default();
+        self.explored = 0;
+        self.avoided_allocations = 0;
+        self.reservations = 0;
+        self.reallocations = 0;
+        self.deleted = 0;
+        self.growth = None;
+        self.clear_outcome();
+    }
+    /// Forgets what the layers below the current one have computed
+    fn clear_outcome(&mut self) {
         self.next_l.clear();
         self.cutset.clear();
+        self.frontier.clear();
         self.lel_depth = None;
+        self.dropped.clear();
+        self.dropped_ub = None;
+        self.terminal.clear();
         self.best_n = None;
         self.exact = true;
         self.approximate = false;
-        self.explored = 0;
+        self.ran_dry = false;
+        self.model_time = Duration::ZERO;
+        self.framework_time = Duration::ZERO;
+        self.violations.clear();
     }
 
     fn _is_exact(&self, comp_type: CompilationType) -> bool {
-        !self.approximate
-            || (comp_type == CompilationType::Relaxed && self.has_exact_best_path(self.best_n))
+        self.dropped.is_empty() && (!self.approximate
+            || (comp_type == CompilationType::Relaxed && self.has_exact_best_path(self.best_n)))
     }
 
     fn has_exact_best_path(&self, node: Option<NodeId>) -> bool {
+        node.is_none_or(|node_id| self.nodes[node_id.0].flags.is_exact_path())
+    }
+
+    /// Sets the exact path flag of a node from its own flags and from those
+    /// of its best parent. This is called whenever the best edge or the
+    /// exactness of a node changes: its parents are final by then, since the
+    /// flags of a layer never change once the next one is being built.
+    fn update_exact_path(&mut self, node_id: NodeId) {
+        let node = &self.nodes[node_id.0];
+        let exact_path = node.flags.is_exact()
+            || (!node.flags.is_relaxed() && node.best.is_none_or(|e| self.nodes[self.edges[e.0].from.0].flags.is_exact_path()));
+        self.nodes[node_id.0].flags.set_exact_path(exact_path);
+    }
+
+    /// Walks the best edges up from a node to tell whether its best path is
+    /// exact (this is what the exact path flags keep track of)
+    #[cfg(test)]
+    fn walk_exact_best_path(&self, node: Option<NodeId>) -> bool {
         if let Some(node_id) = node {
             let n = &self.nodes[node_id.0];
             if n.flags.is_exact() {
                 true
             } else {
                 !n.flags.is_relaxed()
-                    && self.has_exact_best_path(n.best.map(|e| self.edges[e.0].from))
+                    && self.walk_exact_best_path(n.best.map(|e| self.edges[e.0].from))
             }
         } else {
             true
@@ -163,15 +761,80 @@ where
     }
 
     fn _best_value(&self) -> Option<isize> {
-        self.best_n.map(|id| self.nodes[id.0].value)
+        self.best_n.map(|id| self.final_value(id))
+    }
+
+    /// Records the terminal values of the nodes of the last layer (see
+    /// `Problem::terminal_value`)
+    fn record_terminal_values<P: Problem<State = T>>(&mut self, problem: &P) {
+        for id in self.next_l.values() {
+            let terminal = problem.terminal_value(self.nodes[id.0].state.as_ref());
+            if terminal != 0 {
+                self.terminal.insert(*id, terminal);
+            }
+        }
+    }
+
+    /// Returns the value of a node of the last layer, its terminal value
+    /// included
+    fn final_value(&self, id: NodeId) -> isize {
+        self.nodes[id.0].value.saturating_add(self.terminal.get(&id).copied().unwrap_or(0))
     }
 
     fn _best_solution(&self) -> Option<Vec<Decision>> {
         self.best_n.map(|id| self._best_path(id))
     }
 
+    fn _best_solutions(&self, limit: usize) -> Vec<Vec<Decision>> {
+        let mut solutions = vec![];
+        // the ties of a bound are not solutions
+        if let Some(best) = self.best_n.filter(|_| self.has_exact_best_path(self.best_n)) {
+            let value = self.final_value(best);
+            let mut dead = vec![false; self.nodes.len()];
+            let terminals = std::iter::once(best)
+                .chain(self.next_l.values().copied().filter(|id| *id != best && self.final_value(*id) == value));
+            for id in terminals {
+                self._tied_paths(id, &mut vec![], &mut dead, limit, &mut solutions);
+            }
+        }
+        solutions
+    }
+
+    /// Appends the exact paths from the root to the given node which are worth
+    /// its value (the decisions below the node are in `suffix`). The best edge
+    /// of each node is followed first, and the nodes known to have no such
+    /// path are marked as `dead`. It returns true when some path was found.
+    fn _tied_paths(&self, id: NodeId, suffix: &mut Vec<Decision>, dead: &mut [bool], limit: usize, solutions: &mut Vec<Vec<Decision>>) -> bool {
+        let node = &self.nodes[id.0];
+        if solutions.len() >= limit || dead[id.0] || node.flags.is_relaxed() {
+            return false;
+        }
+        if node.inbound.is_none() {
+            let mut sol = self.root_pa.clone();
+            sol.extend_from_slice(suffix);
+            solutions.push(sol);
+            return true;
+        }
+
+        let mut found = false;
+        let best = node.best.map(|e| e.0);
+        let others = std::iter::successors(node.inbound, |e| self.edges[e.0].next).filter(|e| Some(e.0) != best);
+        for eid in node.best.into_iter().chain(others) {
+            let edge = self.edges[eid.0];
+            if self.nodes[edge.from.0].value.saturating_add(edge.cost) == node.value {
+                let len = suffix.len();
+                suffix.push(edge.decision);
+                suffix.extend(self.forced.get(&eid.0).into_iter().flatten());
+                found |= self._tied_paths(edge.from, suffix, dead, limit, solutions);
+                suffix.truncate(len);
+            }
+        }
+        dead[id.0] = !found && solutions.len() < limit;
+        found
+    }
+
     fn _best_path(&self, id: NodeId) -> Vec<Decision> {
-        Self::_best_path_partial_borrow(id, &self.root_pa, &self.nodes, &self.edges)
+        Self::_best_path_partial_borrow(id, &self.root_pa, &self.nodes, &self.edges, &self.forced)
     }
 
     fn _best_path_partial_borrow(
@@ -179,43 +842,95 @@ where
         root_pa: &[Decision],
         nodes: &[Node<T>],
         edges: &[Edge],
+        forced: &FxHashMap<usize, Vec<Decision>>,
     ) -> Vec<Decision> {
         let mut sol = root_pa.to_owned();
         let mut edge_id = nodes[id.0].best;
         while let Some(eid) = edge_id {
             let edge = edges[eid.0];
             sol.push(edge.decision);
+            sol.extend(forced.get(&eid.0).into_iter().flatten());
             edge_id = nodes[edge.from.0].best;
         }
         sol
     }
 
+    /// Returns the rough upper bound of the given node. When the problem
+    /// estimates from the paths, the best path of the node is first
+    /// materialized (in order) in `path_buffer`.
+    fn rough_upper_bound<P: Problem<State = T>>(&mut self, problem: &P, id: NodeId) -> isize {
+        let state = self.nodes[id.0].state.as_ref();
+        if !problem.estimates_from_path() {
+            return problem.estimate(state);
+        }
+        let path = &mut self.path_buffer;
+        path.clear();
+        path.extend_from_slice(&self.root_pa);
+        let mut edge_id = self.nodes[id.0].best;
+        while let Some(eid) = edge_id {
+            let edge = self.edges[eid.0];
+            path.extend(self.forced.get(&eid.0).into_iter().flatten().rev());
+            path.push(edge.decision);
+            edge_id = self.nodes[edge.from.0].best;
+        }
+        path[self.root_pa.len()..].reverse();
+        problem.estimate_from_path(state, path)
+    }
+
     fn _drain_cutset<F>(&mut self, mut func: F)
     where
         F: FnMut(SubProblem<T>),
     {
+        let shared = self.root_prefix.as_ref().map_or(0, |prefix| prefix.len());
         if let Some(best_value) = self.best_value() {
-            for node_id in self.cutset.drain(..) {
+            // a frontier cutset may hold the same state at several depths:
+            // only the node of greatest value is drained (with the greatest
+            // of their upper bounds), so that no path is built for the others
+            let mut drained: Vec<(NodeId, isize)> = vec![];
+            let mut by_state: FxHashMap<&T, usize> = FxHashMap::default();
+            let cutset = self.cutset.drain(..).filter(|id| self.nodes[id.0].flags.is_marked()).map(|id| {
+                let node = &self.nodes[id.0];
+                let rub = node.value.saturating_add(node.rub);
+                let locb = node.value.saturating_add(self.bounds[id.0].value_bot);
+                (id, rub.min(locb).min(best_value))
+            });
+            // the dropped nodes were never expanded: only their estimate bounds them
+            let dropped = self.dropped.drain(..).map(|id| (id, self.nodes[id.0].value.saturating_add(self.nodes[id.0].rub)));
+            for (node_id, ub) in cutset.chain(dropped) {
                 let node = &self.nodes[node_id.0];
-
-                if node.flags.is_marked() {
-                    let rub = node.value.saturating_add(node.rub);
-                    let locb = node.value.saturating_add(node.value_bot);
-                    let ub = rub.min(locb).min(best_value);
-
-                    func(SubProblem {
-                        state: node.state.clone(),
-                        value: node.value,
-                        path: Self::_best_path_partial_borrow(
-                            node_id,
-                            &self.root_pa,
-                            &self.nodes,
-                            &self.edges,
-                        ),
-                        ub,
-                    })
+                match by_state.entry(node.state.as_ref()) {
+                    Entry::Occupied(e) => {
+                        let (kept, kept_ub) = &mut drained[*e.get()];
+                        *kept_ub = ub.max(*kept_ub);
+                        if node.value > self.nodes[kept.0].value {
+                            *kept = node_id;
+                        }
+                    }
+                    Entry::Vacant(e) => {
+                        e.insert(drained.len());
+                        drained.push((node_id, ub));
+                    }
                 }
             }
+
+            for (node_id, ub) in drained {
+                let node = &self.nodes[node_id.0];
+                func(SubProblem {
+                    state: node.state.clone(),
+                    value: node.value,
+                    path: Self::_best_path_partial_borrow(
+                        node_id,
+                        &self.root_pa[shared..],
+                        &self.nodes,
+                        &self.edges,
+                        &self.forced,
+                    ),
+                    ub,
+                    id: 0,
+                    parent: None,
+                    prefix: self.root_prefix.clone(),
+                })
+            }
         }
     }
 
@@ -223,6 +938,19 @@ where
         self.explored
     }
 
+    /// Estimates the number of bytes held by this dd: its buffers keep their
+    /// capacity from one compilation to the next, and its nodes keep their
+    /// states until the next compilation
+    pub fn footprint(&self) -> usize {
+        use std::mem::size_of;
+        self.nodes.capacity() * size_of::<Node<T>>()
+            + self.nodes.len() * size_of::<T>()
+            + self.edges.capacity() * size_of::<Edge>()
+            + (self.prev_l.capacity() + self.cutset.capacity() + self.frontier.capacity()) * size_of::<NodeId>()
+            + self.next_l.capacity() * size_of::<(Arc<T>, NodeId)>()
+            + self.transitions.capacity() * size_of::<(NodeId, Decision, T, isize)>()
+    }
+
     fn _compile<P, R, O>(&mut self, input: &CompilationInput<P, R, O>)
     where
         P: Problem<State = T>,
@@ -230,164 +958,570 @@ where
         O: StateRanking<State = P::State>,
     {
         self.clear();
+        self.best_lb = input.best_lb;
 
-        let mut curr_l = vec![];
-
-        input
-            .residual
-            .path
-            .iter()
-            .copied()
-            .for_each(|x| self.root_pa.push(x));
+        if let Some(prefix) = &input.residual.prefix {
+            prefix.extend_into(&mut self.root_pa);
+        }
+        self.root_pa.extend_from_slice(&input.residual.path);
+        self.root_prefix = input.residual.prefix.clone();
 
         let root_depth = self.root_pa.len();
 
         let root_s = input.residual.state.clone();
         let root_v = input.residual.value;
-        let root_n = Node {
+        let mut root_n = Node {
             state: root_s.clone(),
             value: root_v,
             best: None,
             inbound: None,
             depth: root_depth,
-            value_bot: isize::MIN,
-            theta: isize::MAX,
             rub: input.residual.ub - root_v,
             flags: NodeFlags::new_exact(),
         };
+        root_n.flags.set_exact_path(true);
 
-        self.nodes.push(root_n);
+        self.nodes.alloc(root_n);
         self.next_l.insert(root_s, NodeId(0));
 
-        let mut depth = root_depth;
+        let cursor = Cursor { curr_l: vec![], depth: root_depth, branched: false, assigned: input.assigned.clone() };
+        self.compile_layers(input, cursor, None);
+    }
 
-        while let Some(var) = input.problem.next_variable(&mut self.next_l.keys().map(|s| s.as_ref())) {
-            self.prev_l.clear();
-            for node_id in curr_l.drain(..) {
-                self.prev_l.push(node_id);
-            }
-            for (_, node_id) in self.next_l.drain() {
-                curr_l.push(node_id);
-            }
+    /// Resumes the relaxed compilation of the subproblem of the last
+    /// (restricted) compilation from the layers it kept (see
+    /// `DecisionDiagram::resume_relaxed`)
+    fn _resume_relaxed<P, R, O>(&mut self, input: &CompilationInput<P, R, O>) -> bool
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let Some(prefix) = self.prefix.take() else {
+            return false;
+        };
+        if input.comp_type != CompilationType::Relaxed || input.self_check || input.best_lb != prefix.best_lb {
+            return false;
+        }
 
-            if curr_l.is_empty() {
-                return;
-            }
+        self.nodes.truncate(prefix.nb_nodes);
+        self.edges.truncate(prefix.nb_edges);
+        self.bounds.truncate(prefix.nb_nodes);
+        self.forced.retain(|edge_id, _| *edge_id < prefix.nb_edges);
+        for (node_id, rub) in prefix.cursor.curr_l.iter().zip(prefix.rubs) {
+            self.nodes[node_id.0].rub = rub;
+        }
+        self.prev_l.clear();
+        self.prev_l.extend(prefix.prev_l);
+        self.clear_outcome();
+        self.best_lb = input.best_lb;
+        self.stats = CompilationStats { reused_nodes: prefix.explored, ..prefix.stats };
+        self.explored = 0;
+        self.avoided_allocations = prefix.avoided_allocations;
+        self.reservations = prefix.reservations;
+        self.reallocations = prefix.reallocations;
+        self.deleted = prefix.deleted;
+        self.growth = prefix.growth;
+        for (depth, state, theta) in std::mem::take(&mut self.deferred) {
+            self.try_update_barrier(depth, state, theta, false, self.best_lb);
+        }
 
-            if depth > root_depth && !self.barriers[depth].read().is_empty() {
-                // try to prune nodes before expanding them
-                curr_l.retain_mut(|node_id| {
-                    if self.nodes[node_id.0].flags.is_relaxed() {
-                        true
-                    } else {
-                        let state = &self.nodes[node_id.0].state;
-                        let theta = self.barriers[depth].read().get(state).map_or(isize::MIN, |bi| bi.theta);
-
-                        if self.nodes[node_id.0].value > theta {
-                            true
-                        } else {
-                            self.nodes[node_id.0].theta = theta; // set theta for later propagation
-                            self.nodes[node_id.0].flags.set_pruned_by_barrier(true);
-                            false
-                        }
+        self.compile_layers(input, prefix.cursor, Some(prefix.var));
+        true
+    }
+
+    /// Develops the layers below the cursor, then picks the best node of the
+    /// last one (and the cutset of a relaxed dd). When a variable is given,
+    /// the layer of the cursor is that of this variable, whose nodes have
+    /// been pruned and are ready to be restricted or merged.
+    fn compile_layers<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, mut cursor: Cursor, mut resumed: Option<Variable>)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let root_depth = self.root_pa.len();
+        loop {
+            let var = match resumed.take() {
+                Some(var) => var,
+                None => {
+                    let Some(var) = input.problem.next_variable_with_context(&cursor.assigned, &mut self.next_l.keys().map(|s| s.as_ref())) else {
+                        break;
+                    };
+                    cursor.assigned.insert(var);
+                    self.prev_l.clear();
+                    for node_id in cursor.curr_l.drain(..) {
+                        self.prev_l.push(node_id);
                     }
-                });
-            }
+                    for (_, node_id) in self.next_l.drain() {
+                        cursor.curr_l.push(node_id);
+                    }
+
+                    if cursor.curr_l.is_empty() {
+                        // nothing is left below the nodes which were dropped
+                        self.exact = self.dropped.is_empty();
+                        self.ran_dry = self.is_dry(input.comp_type, cursor.branched);
+                        return;
+                    }
+                    if input.is_cancelled() {
+                        // stop at the layer boundary, without pretending to be exact
+                        self.exact = false;
+                        return;
+                    }
+                    let best_lb = input.current_best_lb();
+                    if best_lb > self.best_lb {
+                        // the incumbent improved since the compilation began
+                        self.best_lb = best_lb;
+                        self.stats.bound_refreshes += 1;
+                    }
+
+                    self.prune(input, cursor.depth, &mut cursor.curr_l);
+                    if input.reuse_prefix && input.comp_type == CompilationType::Restricted
+                        && cursor.curr_l.len() > input.max_width && self.prefix.is_none()
+                    {
+                        self.keep_prefix(input, var, &cursor);
+                    }
+                    var
+                }
+            };
+            let depth = cursor.depth;
+            let curr_l = &mut cursor.curr_l;
 
             match input.comp_type {
                 CompilationType::Exact => { /* do nothing: you want to explore the complete DD */ }
                 CompilationType::Restricted => {
                     if curr_l.len() > input.max_width {
-                        self.restrict(input, &mut curr_l)
+                        self.restrict(input, curr_l)
                     }
                 }
                 CompilationType::Relaxed => {
-                    if curr_l.len() > input.max_width && depth > root_depth + 1 {
-                        self.relax(input, &mut curr_l)
+                    if curr_l.len() > input.max_width {
+                        if depth > root_depth + input.min_relax_depth_offset {
+                            self.relax(input, depth, curr_l)
+                        } else if input.restrict_first_layers {
+                            self.drop_excess(input, curr_l)
+                        }
                     }
                 }
             }
 
+            self.reserve_next_layer(input.problem, var, curr_l);
+            let capacities = self.layer_capacities();
+            if input.timed {
+                self.branch_timed(input, var, curr_l);
+            }
+            let thresholds = self.computes_thresholds(input);
+            cursor.branched = false;
             for node_id in curr_l.iter() {
                 let state = self.nodes[node_id.0].state.clone();
-                let rub = input.problem.estimate(state.as_ref());
-                self.nodes[node_id.0].rub = rub;
+                let rub = if input.timed {
+                    self.nodes[node_id.0].rub
+                } else {
+                    let rub = self.rough_upper_bound(input.problem, *node_id);
+                    self.nodes[node_id.0].rub = rub;
+                    rub
+                };
                 let ub = rub.saturating_add(self.nodes[node_id.0].value);
 
-                if ub > input.best_lb {
-                    input.problem.for_each_in_domain(var, state.as_ref(), |decision| {
-                        self.branch_on(*node_id, decision, input.problem)
-                    });
+                if ub > self.best_lb {
+                    if !input.timed {
+                        input.problem.for_each_in_domain(var, state.as_ref(), |decision| {
+                            self.branch_on(*node_id, decision, input.problem)
+                        });
+                    }
 
                     self.explored += 1;
+                    cursor.branched = true;
 
-                    if matches!(input.comp_type, CompilationType::Relaxed) && self.nodes[node_id.0].flags.is_exact() {
+                    if thresholds && self.nodes[node_id.0].flags.is_exact() {
                         // if we made it to here, we have improved the threshold
                         // try to update threshold for other threads already
-                        self.try_update_barrier(depth, state, self.nodes[node_id.0].value, false);
+                        self.update_exact_threshold(input, depth, state, self.nodes[node_id.0].value);
                     }
                 } else {
-                    self.nodes[node_id.0].theta = input.best_lb.saturating_sub(rub); // set theta for later propagation
-
-                    if matches!(input.comp_type, CompilationType::Relaxed) && self.nodes[node_id.0].flags.is_exact() {
-                        // try to update threshold for other threads already
-                        self.try_update_barrier(depth, state, self.nodes[node_id.0].theta, false);
+                    self.stats.rub_prunes += 1;
+                    if thresholds {
+                        let theta = self.best_lb.saturating_sub(rub);
+                        self.bounds_mut(*node_id).theta = theta; // set theta for later propagation
+
+                        if self.nodes[node_id.0].flags.is_exact() {
+                            // try to update threshold for other threads already
+                            self.update_exact_threshold(input, depth, state, theta);
+                        }
                     }
                 }
             }
+            self.record_growth(capacities, curr_l.len());
+
+            if input.self_check {
+                self.self_check_estimate(input, var, &cursor.assigned, curr_l);
+            }
 
-            depth += 1;
+            cursor.depth += 1;
+            if input.max_forced_layers > 0 {
+                self.collapse_forced_layers(input, &mut cursor.assigned, &mut cursor.depth);
+            }
         }
 
         //
+        self.record_terminal_values(input.problem);
         self.best_n = self
             .next_l
             .values()
             .copied()
-            .max_by_key(|id| self.nodes[id.0].value);
+            .max_by_key(|id| self.final_value(*id));
         self.exact = self._is_exact(input.comp_type);
+        self.ran_dry = self.best_n.is_none() && self.is_dry(input.comp_type, cursor.branched);
         //
         if matches!(input.comp_type, CompilationType::Relaxed) {
-            self.compute_local_bounds_and_theta(input.best_lb);
+            if !self.approximate && !self.dropped.is_empty() && self.cutset_type != CutsetType::Frontier {
+                // nothing was merged: the terminal layer is the last exact one,
+                // and it is drained along with the nodes which were dropped
+                self.cutset.extend(self.next_l.values().copied());
+            }
+            self.compute_local_bounds_and_theta(self.best_lb);
         }
     }
 
-    fn branch_on<P: Problem<State = T>>(
-        &mut self,
-        from_id: NodeId,
-        decision: Decision,
-        problem: &P,
-    ) {
-        let state = self.nodes[from_id.0].state.as_ref();
-        let next_state = Arc::new(problem.transition(state, decision));
-        let cost = problem.transition_cost(state, decision);
-
-        match self.next_l.entry(next_state.clone()) {
-            Entry::Vacant(e) => {
-                let node_id = NodeId(self.nodes.len());
-                let edge_id = EdgeId(self.edges.len());
+    /// Drops the nodes of the current layer which the barrier or the
+    /// dominance rule prune, before the layer is expanded
+    fn prune<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, depth: usize, curr_l: &mut Vec<NodeId>)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let thresholds = self.computes_thresholds(input);
+        if depth > self.root_pa.len() && !self.barriers.is_layer_empty(depth) {
+            // try to prune nodes before expanding them
+            curr_l.retain_mut(|node_id| {
+                if self.nodes[node_id.0].flags.is_relaxed() {
+                    true
+                } else {
+                    let state = &self.nodes[node_id.0].state;
+                    let threshold = self.barriers.get_threshold(depth, state);
+                    let theta = threshold.map_or(isize::MIN, |bi| bi.theta);
+
+                    let kept = self.nodes[node_id.0].value > theta;
+                    self.barriers.record_lookup(depth, !kept);
+                    self.barriers.log_lookup(depth, state, self.nodes[node_id.0].value, threshold, !kept, self.best_lb);
+                    if kept {
+                        true
+                    } else {
+                        if thresholds {
+                            self.bounds_mut(*node_id).theta = theta; // set theta for later propagation
+                        }
+                        self.nodes[node_id.0].flags.set_pruned_by_barrier(true);
+                        self.stats.barrier_prunes += 1;
+                        false
+                    }
+                }
+            });
+        }
+        if let Some(dominance) = input.dominance {
+            self.prune_dominated(thresholds, dominance, curr_l);
+        }
+    }
 
-                self.edges.push(Edge {
-                    //my_id: edge_id,
-                    from: from_id,
-                    //to   : node_id,
-                    decision,
+    /// Tells whether the compilation gives its nodes the thresholds of a
+    /// relaxed dd: a relaxed compilation does, and so does a restricted one
+    /// in the layers it may keep for the relaxed dd of its subproblem (see
+    /// `CompilationInput::reuse_prefix`)
+    fn computes_thresholds<P, R, O>(&self, input: &CompilationInput<P, R, O>) -> bool
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        match input.comp_type {
+            CompilationType::Relaxed => true,
+            CompilationType::Restricted => input.reuse_prefix && !self.approximate,
+            CompilationType::Exact => false,
+        }
+    }
+
+    /// Hands the threshold of an exact node to the barrier right away in a
+    /// relaxed dd, and once it resumes from the layers of a restricted dd
+    /// otherwise (see `computes_thresholds`)
+    fn update_exact_threshold<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, depth: usize, state: Arc<T>, theta: isize)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        if input.comp_type == CompilationType::Relaxed {
+            self.try_update_barrier(depth, state, theta, false, self.best_lb);
+        } else {
+            self.deferred.push((depth, state, theta));
+        }
+    }
+
+    /// Keeps the layers developed so far (before the layer of the given
+    /// variable gets restricted) for the relaxed dd of the same subproblem
+    fn keep_prefix<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, var: Variable, cursor: &Cursor)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        self.prefix = Some(Prefix {
+            nb_nodes: self.nodes.len(),
+            nb_edges: self.edges.len(),
+            var,
+            cursor: cursor.clone(),
+            prev_l: self.prev_l.clone(),
+            rubs: cursor.curr_l.iter().map(|id| self.nodes[id.0].rub).collect(),
+            best_lb: input.best_lb,
+            stats: self.stats,
+            explored: self.explored,
+            avoided_allocations: self.avoided_allocations,
+            reservations: self.reservations,
+            reallocations: self.reallocations,
+            deleted: self.deleted,
+            growth: self.growth,
+        });
+    }
+
+    /// Tells whether a compilation which ended with an empty layer ran out of
+    /// nodes because of its restriction (see `DecisionDiagram::ran_dry`): the
+    /// nodes of the layer above were branched on, but none of them had any
+    /// successor.
+    fn is_dry(&self, comp_type: CompilationType, branched: bool) -> bool {
+        branched && self.approximate && comp_type == CompilationType::Restricted
+    }
+
+    /// Reserves the memory of the next layer before the current one is
+    /// expanded. The hints of the problem bound the number of transitions;
+    /// without them, the next layer is expected to grow like the last one.
+    fn reserve_next_layer<P: Problem<State = T>>(&mut self, problem: &P, var: Variable, curr_l: &[NodeId]) {
+        let hinted = curr_l
+            .iter()
+            .map(|id| problem.domain_size_hint(var, self.nodes[id.0].state.as_ref()))
+            .sum::<Option<usize>>();
+        let expected = hinted.or_else(|| self.growth.map(|growth| (growth * curr_l.len() as f64).ceil() as usize));
+        if let Some(additional) = expected {
+            let capacities = self.layer_capacities();
+            self.next_l.reserve(additional);
+            self.nodes.reserve(additional);
+            self.edges.reserve(additional);
+            if self.layer_capacities() != capacities {
+                self.reservations += 1;
+            }
+        }
+    }
+
+    /// Counts the containers which outgrew the memory they had before the
+    /// current layer was expanded, and remembers how much the dd grew
+    fn record_growth(&mut self, capacities: (usize, usize, usize), width: usize) {
+        let (next_l, nodes, edges) = self.layer_capacities();
+        self.reallocations += usize::from(next_l > capacities.0) + usize::from(nodes > capacities.1) + usize::from(edges > capacities.2);
+        self.growth = Some(self.next_l.len() as f64 / width.max(1) as f64);
+    }
+
+    fn layer_capacities(&self) -> (usize, usize, usize) {
+        (self.next_l.capacity(), self.nodes.capacity(), self.edges.capacity())
+    }
+
+    /// Returns the local bounds of a node of a relaxed dd, which are only
+    /// allocated once they are needed
+    fn bounds_mut(&mut self, node_id: NodeId) -> &mut LocalBounds {
+        if self.bounds.len() <= node_id.0 {
+            self.bounds.resize(self.nodes.len(), LocalBounds::default());
+        }
+        &mut self.bounds[node_id.0]
+    }
+
+    fn branch_on<P: Problem<State = T>>(
+        &mut self,
+        from_id: NodeId,
+        decision: Decision,
+        problem: &P,
+    ) {
+        let state = self.nodes[from_id.0].state.as_ref();
+        let (next_state, cost) = problem.transition_with_cost(state, decision);
+        self.link(from_id, decision, next_state, cost)
+    }
+
+    /// Expands the nodes of the current layer like an untimed compilation,
+    /// except that the estimates and the transitions are all computed before
+    /// the next layer gets built: this is how the time spent in the model is
+    /// measured without timing each call.
+    fn branch_timed<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, var: Variable, curr_l: &[NodeId])
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let start = Instant::now();
+        for node_id in curr_l.iter() {
+            let rub = self.rough_upper_bound(input.problem, *node_id);
+            self.nodes[node_id.0].rub = rub;
+        }
+        let mut transitions = std::mem::take(&mut self.transitions);
+        for node_id in curr_l.iter() {
+            let node = &self.nodes[node_id.0];
+            if node.rub.saturating_add(node.value) > self.best_lb {
+                let state = node.state.as_ref();
+                input.problem.for_each_in_domain(var, state, |decision| {
+                    let (next_state, cost) = input.problem.transition_with_cost(state, decision);
+                    transitions.push((*node_id, decision, next_state, cost));
+                });
+            }
+        }
+        self.model_time += start.elapsed();
+
+        for (from_id, decision, next_state, cost) in transitions.drain(..) {
+            self.link(from_id, decision, next_state, cost);
+        }
+        self.transitions = transitions;
+    }
+
+    /// Collapses the layers below the one which was just built, for as long as
+    /// none of its nodes has more than one decision on the next variable (and
+    /// at most `CompilationInput::max_forced_layers` times). Each node then
+    /// takes its forced decision in place: the arcs which reach it carry the
+    /// decision and its cost, and it moves one level down. The intermediate
+    /// states are never stored, so they get no threshold in the barrier.
+    fn collapse_forced_layers<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, assigned: &mut AssignedVars, depth: &mut usize)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let mut transitions = std::mem::take(&mut self.transitions);
+        for _ in 0..input.max_forced_layers {
+            if self.next_l.is_empty() {
+                break;
+            }
+            let Some(var) = input.problem.next_variable_with_context(assigned, &mut self.next_l.keys().map(|s| s.as_ref())) else {
+                break;
+            };
+
+            // the dead ends have no decision to take: they are simply dropped
+            let mut dead_ends = vec![];
+            let start = Instant::now();
+            let forced = self.next_l.iter().all(|(state, node_id)| {
+                let mut decisions = 0;
+                input.problem.for_each_in_domain(var, state.as_ref(), |decision| {
+                    decisions += 1;
+                    if decisions == 1 {
+                        let (next_state, cost) = input.problem.transition_with_cost(state.as_ref(), decision);
+                        transitions.push((*node_id, decision, next_state, cost));
+                    }
+                });
+                if decisions == 0 {
+                    dead_ends.push(*node_id);
+                }
+                decisions <= 1
+            });
+            if input.timed {
+                self.model_time += start.elapsed();
+            }
+            if !forced || transitions.is_empty() {
+                transitions.clear();
+                break;
+            }
+
+            assigned.insert(var);
+            *depth += 1;
+            self.stats.collapsed_layers += 1;
+            self.next_l.clear();
+            self.deleted += dead_ends.len();
+            for node_id in dead_ends {
+                self.nodes[node_id.0].flags.set_deleted(true);
+            }
+            for (node_id, decision, next_state, cost) in transitions.drain(..) {
+                self.force(node_id, decision, next_state, cost);
+            }
+        }
+        self.transitions = transitions;
+    }
+
+    /// Wraps the given state in an arc. The arc of a state which was already
+    /// in the next layer is recycled: only the states which are new in that
+    /// layer need an allocation.
+    fn wrap(&mut self, state: T) -> Arc<T> {
+        match self.spare.take() {
+            Some(mut spare) => {
+                *Arc::get_mut(&mut spare).expect("the spare arc is not shared") = state;
+                spare
+            }
+            None => Arc::new(state),
+        }
+    }
+
+    /// Makes the given node of the next layer take its forced decision: every
+    /// arc which reaches it goes on with that decision, and the node takes the
+    /// state it leads to (unless some other node of the layer already holds
+    /// it, in which case the arcs are handed over to that node)
+    fn force(&mut self, node_id: NodeId, decision: Decision, next_state: T, cost: isize) {
+        let mut last = None;
+        let mut edge_id = self.nodes[node_id.0].inbound;
+        while let Some(eid) = edge_id {
+            let edge = &mut self.edges[eid.0];
+            edge.cost = edge.cost.saturating_add(cost);
+            self.forced.entry(eid.0).or_default().push(decision);
+            last = Some(eid);
+            edge_id = edge.next;
+        }
+        let node = &mut self.nodes[node_id.0];
+        node.value = node.value.saturating_add(cost);
+        node.depth += 1;
+
+        let next_state = self.wrap(next_state);
+        match self.next_l.entry(next_state.clone()) {
+            Entry::Vacant(e) => {
+                e.insert(node_id);
+                self.nodes[node_id.0].state = next_state;
+            }
+            Entry::Occupied(e) => {
+                let kept_id = *e.get();
+                self.spare = Some(next_state);
+                self.avoided_allocations += 1;
+                let dropped = self.nodes[node_id.0].clone();
+                self.nodes[node_id.0].flags.set_deleted(true);
+                self.deleted += 1;
+                if let Some(last) = last {
+                    self.edges[last.0].next = self.nodes[kept_id.0].inbound;
+                }
+
+                let kept = &mut self.nodes[kept_id.0];
+                kept.inbound = dropped.inbound.or(kept.inbound);
+                if dropped.value > kept.value || (dropped.value == kept.value && dropped.flags.is_exact()) {
+                    kept.value = dropped.value;
+                    kept.best = dropped.best;
+                    kept.flags = dropped.flags;
+                }
+            }
+        }
+    }
+
+    fn link(&mut self, from_id: NodeId, decision: Decision, next_state: T, cost: isize) {
+        let next_state = self.wrap(next_state);
+        match self.next_l.entry(next_state.clone()) {
+            Entry::Vacant(e) => {
+                let node_id = NodeId(self.nodes.len());
+                let edge_id = EdgeId(self.edges.len());
+
+                self.edges.alloc(Edge {
+                    //my_id: edge_id,
+                    from: from_id,
+                    //to   : node_id,
+                    decision,
                     cost,
                     next: None,
                 });
-                self.nodes.push(Node {
+                self.nodes.alloc(Node {
                     //my_id  : node_id,
-                    state: next_state,
+                    state: next_state.clone(),
                     value: self.nodes[from_id.0].value.saturating_add(cost),
                     best: Some(edge_id),
                     inbound: Some(edge_id),
                     //
                     depth: self.nodes[from_id.0].depth + 1,
                     //
-                    value_bot: isize::MIN,
-                    theta: isize::MAX,
-                    //
                     rub: isize::MAX,
+                    // the child is exact (and on an exact path) iff its parent is
                     flags: self.nodes[from_id.0].flags,
                 });
 
@@ -395,12 +1529,14 @@ where
             }
             Entry::Occupied(e) => {
                 let node_id = *e.get();
+                self.spare = Some(next_state);
+                self.avoided_allocations += 1;
                 let flags = self.nodes[from_id.0].flags;
                 let value = self.nodes[from_id.0].value.saturating_add(cost);
                 let node = &mut self.nodes[node_id.0];
 
                 let edge_id = EdgeId(self.edges.len());
-                self.edges.push(Edge {
+                self.edges.alloc(Edge {
                     //my_id: edge_id,
                     from: from_id,
                     //to   : node_id,
@@ -413,12 +1549,55 @@ where
                 if value > node.value || (value == node.value && flags.is_exact()) {
                     node.value = value;
                     node.best = Some(edge_id);
+                    // this also takes the exact path flag of the new best parent
                     node.flags = flags;
                 }
             }
         }
     }
 
+    /// Checks the estimate of the first exact node of the current layer
+    /// against the value of a greedy completion of its state
+    fn self_check_estimate<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, var: Variable, assigned: &AssignedVars, curr_l: &[NodeId])
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        if let Some(node_id) = curr_l.iter().find(|id| self.nodes[id.0].flags.is_exact()) {
+            let node = &self.nodes[node_id.0];
+            if let Some(violation) = check_estimate(input.problem, node.state.as_ref(), var, assigned, node.rub) {
+                self.violations.push(violation);
+            }
+        }
+    }
+
+    /// Drops the exact nodes of the current layer which are dominated by
+    /// another exact node of that layer. They are not deleted: like the nodes
+    /// pruned by the barrier, they keep their arcs so that the threshold of a
+    /// relaxed dd reaches their parents. Their value is a safe threshold since
+    /// the node which dominates them is covered by the dd.
+    fn prune_dominated(&mut self, thresholds: bool, dominance: &dyn DominanceChecker<T>, curr_l: &mut Vec<NodeId>) {
+        let exact = curr_l.iter().copied().filter(|id| self.nodes[id.0].flags.is_exact()).collect::<Vec<_>>();
+        if exact.len() < 2 {
+            return;
+        }
+        let layer = exact.iter().map(|id| (self.nodes[id.0].value, self.nodes[id.0].state.as_ref())).collect::<Vec<_>>();
+        let dominated = dominance.dominated(&layer);
+        let dominated = exact.into_iter().zip(dominated).filter_map(|(id, d)| d.then_some(id)).collect::<FxHashSet<_>>();
+        if dominated.is_empty() {
+            return;
+        }
+
+        curr_l.retain(|id| !dominated.contains(id));
+        for id in dominated {
+            if thresholds {
+                self.bounds_mut(id).theta = self.nodes[id.0].value;
+            }
+            self.stats.dominated_nodes += 1;
+        }
+    }
+
     fn restrict<P, R, O>(
         &mut self,
         input: &CompilationInput<P, R, O>,
@@ -436,19 +1615,77 @@ where
                 .then_with(|| input.ranking.compare(self.nodes[a.0].state.as_ref(), self.nodes[b.0].state.as_ref()))
                 .reverse()
         }); // reverse because greater means more likely to be kept
+        self.stats.restricted_nodes += curr_l.len() - input.max_width;
+        self.deleted += curr_l.len() - input.max_width;
         curr_l.truncate(input.max_width);
     }
 
-    fn relax<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, curr_l: &mut Vec<NodeId>)
+    /// Restricts a layer which is too shallow to be merged. The exact nodes it
+    /// drops are kept aside to be drained with the cutset: they are bounded by
+    /// their estimate, and their thresholds are those of cutset nodes (which
+    /// do not claim that their subtrees have been explored).
+    fn drop_excess<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, curr_l: &mut Vec<NodeId>)
     where
         P: Problem<State = T>,
         R: Relaxation<State = P::State>,
         O: StateRanking<State = P::State>,
     {
-        if self.cutset_type == CutsetType::LastExactLayer && !self.approximate {
+        curr_l.sort_unstable_by(|a, b| {
+            self.nodes[a.0]
+                .value
+                .cmp(&self.nodes[b.0].value)
+                .then_with(|| input.ranking.compare(self.nodes[a.0].state.as_ref(), self.nodes[b.0].state.as_ref()))
+                .reverse()
+        }); // reverse because greater means more likely to be kept
+        self.stats.restricted_nodes += curr_l.len() - input.max_width;
+        self.deleted += curr_l.len() - input.max_width;
+        for node_id in curr_l.drain(input.max_width..) {
+            let rub = self.rough_upper_bound(input.problem, node_id);
+            let node = &mut self.nodes[node_id.0];
+            node.rub = rub;
+            node.flags.set_deleted(true);
+            let (value, rub) = (node.value, node.rub);
+            let ub = value.saturating_add(rub);
+            let theta = if ub > self.best_lb {
+                self.dropped.push(node_id);
+                self.dropped_ub = self.dropped_ub.max(Some(ub));
+                self.bounds_mut(node_id).theta.min(value)
+            } else {
+                self.best_lb.saturating_sub(rub)
+            };
+            self.bounds_mut(node_id).theta = theta;
+            let node = &self.nodes[node_id.0];
+            let (depth, state) = (node.depth, node.state.clone());
+            self.try_update_barrier(depth, state, theta, false, self.best_lb);
+
+            // the parents will not get this threshold from the (deleted) node
+            let mut inbound = self.nodes[node_id.0].inbound;
+            while let Some(edge_id) = inbound {
+                let edge = self.edges[edge_id.0];
+                let parent = self.bounds_mut(edge.from);
+                parent.theta = parent.theta.min(theta.saturating_sub(edge.cost));
+                inbound = edge.next;
+            }
+        }
+    }
+
+    fn relax<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, depth: usize, curr_l: &mut Vec<NodeId>)
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        if !self.approximate {
+            // the layer above may be more than one level up when forced layers were collapsed
+            self.stats.lel_depth = self.prev_l.first().map(|id| self.nodes[id.0].depth);
+        }
+        if self.cutset_type != CutsetType::Frontier && !self.approximate {
             for id in self.prev_l.iter() {
                 self.cutset.push(*id);
-                self.nodes[id.0].flags.set_cutset(true);
+                // an adaptive dd may still go for the frontier instead
+                if self.cutset_type == CutsetType::LastExactLayer {
+                    self.nodes[id.0].flags.set_cutset(true);
+                }
                 self.lel_depth = Some(self.nodes[id.0].depth);
             }
         }
@@ -463,24 +1700,97 @@ where
         }); // reverse because greater means more likely to be kept
 
         //--
-        let (keep, merge) = curr_l.split_at_mut(input.max_width - 1);
-        let merged = Arc::new(input.relaxation.merge(&mut merge.iter().map(|node_id| self.nodes[node_id.0].state.as_ref())));
+        let nb_chunks = input.merge_policy.nb_chunks(input.max_width);
+        let (keep, merge) = curr_l.split_at_mut(input.max_width - nb_chunks);
+        if nb_chunks > 1 {
+            // each chunk gathers states which are close to each other
+            merge.sort_unstable_by(|a, b| input.ranking.compare(self.nodes[a.0].state.as_ref(), self.nodes[b.0].state.as_ref()).reverse());
+        }
 
-        let recycled = keep.iter().find(|node_id| self.nodes[node_id.0].state.eq(&merged)).map(|node_id| *node_id);
+        let (size, extra) = (merge.len() / nb_chunks, merge.len() % nb_chunks);
+        let mut merged_ids = Vec::with_capacity(nb_chunks);
+        self.stats.relaxed_merges += nb_chunks;
+        let mut recycled = false;
+        let mut end = 0;
+        for chunk in 0..nb_chunks {
+            let group = &merge[end..end + size + usize::from(chunk < extra)];
+            end += group.len();
+
+            let merged = self.merge_states(input, depth, group);
+            let recycled_id = keep.iter().chain(merged_ids.iter()).find(|node_id| self.nodes[node_id.0].state.eq(&merged)).copied();
+            let merged_id = self.redirect_to_merged(input, depth, group, merged, recycled_id);
+            match recycled_id {
+                Some(_) => recycled = true,
+                None => merged_ids.push(merged_id),
+            }
+        }
+
+        if recycled && nb_chunks == 1 {
+            // the most valuable of the merged nodes takes the free spot
+            curr_l.truncate(input.max_width);
+            let saved_id = curr_l[input.max_width - 1];
+            self.nodes[saved_id.0].flags.set_deleted(false);
+            self.deleted -= 1;
+        } else {
+            // a chunk whose merged state is an existing node leaves its spot
+            // empty: those states were merged with their neighbours already
+            curr_l.truncate(input.max_width - nb_chunks);
+            curr_l.extend(merged_ids);
+        }
+    }
+
+    /// Merges the states of the given nodes, and validates the merge when
+    /// asked to
+    fn merge_states<P, R, O>(&mut self, input: &CompilationInput<P, R, O>, depth: usize, group: &[NodeId]) -> Arc<T>
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let start = input.timed.then(Instant::now);
+        let merged = Arc::new(input.relaxation.merge_at_depth(depth, &mut group.iter().map(|node_id| self.nodes[node_id.0].state.as_ref())));
+        if let Some(start) = start {
+            self.model_time += start.elapsed();
+        }
+
+        if input.check_merge {
+            let originals = group.iter().map(|node_id| self.nodes[node_id.0].state.as_ref()).collect::<Vec<_>>();
+            if let Err(msg) = input.relaxation.check_merge_at_depth(depth, merged.as_ref(), &originals) {
+                panic!("inconsistent merge of {} states: {}", originals.len(), msg);
+            }
+        }
+        merged
+    }
+
+    /// Deletes the nodes of the group and redirects their inbound edges to
+    /// the node of the merged state: the recycled one when that state is
+    /// already in the layer, a new relaxed node otherwise. Returns the id of
+    /// the merged node.
+    fn redirect_to_merged<P, R, O>(
+        &mut self,
+        input: &CompilationInput<P, R, O>,
+        depth: usize,
+        group: &[NodeId],
+        merged: Arc<T>,
+        recycled: Option<NodeId>,
+    ) -> NodeId
+    where
+        P: Problem<State = T>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        let most_valuable = group.iter().copied().max_by_key(|node_id| self.nodes[node_id.0].value).expect("a merged group is not empty");
 
         let merged_id = recycled.unwrap_or_else(|| {
             let node_id = NodeId(self.nodes.len());
-            self.nodes.push(Node {
+            self.nodes.alloc(Node {
                 //my_id  : node_id,
                 state: merged.clone(),
                 value: isize::MIN,
                 best: None,    // yet
                 inbound: None, // yet
                 //
-                depth: self.nodes[merge[0].0].depth,
-                //
-                value_bot: isize::MIN,
-                theta: isize::MAX,
+                depth: self.nodes[group[0].0].depth,
                 //
                 rub: isize::MAX,
                 flags: NodeFlags::new_relaxed(),
@@ -490,17 +1800,25 @@ where
 
         self.nodes[merged_id.0].flags.set_relaxed(true);
 
-        for drop_id in merge {
+        self.deleted += group.len();
+        for drop_id in group {
             self.nodes[drop_id.0].flags.set_deleted(true);
 
             let mut edge_id = self.nodes[drop_id.0].inbound;
             while let Some(eid) = edge_id {
                 let edge = self.edges[eid.0];
                 let src = self.nodes[edge.from.0].state.as_ref();
+                // a collapsed arc enters its node with the last of its forced decisions
+                let chain = self.forced.get(&eid.0).cloned();
+                let last = chain.as_ref().and_then(|c| c.last()).copied().unwrap_or(edge.decision);
 
+                let start = input.timed.then(Instant::now);
                 let rcost = input
                     .relaxation
-                    .relax(src, self.nodes[drop_id.0].state.as_ref(), merged.as_ref(), edge.decision, edge.cost);
+                    .relax_at_depth(depth, src, self.nodes[drop_id.0].state.as_ref(), merged.as_ref(), last, edge.cost);
+                if let Some(start) = start {
+                    self.model_time += start.elapsed();
+                }
 
                 let new_eid = EdgeId(self.edges.len());
                 let new_edge = Edge {
@@ -511,7 +1829,10 @@ where
                     cost: rcost,
                     next: self.nodes[merged_id.0].inbound,
                 };
-                self.edges.push(new_edge);
+                self.edges.alloc(new_edge);
+                if let Some(chain) = chain {
+                    self.forced.insert(new_eid.0, chain);
+                }
                 self.nodes[merged_id.0].inbound = Some(new_eid);
 
                 let new_value = self.nodes[edge.from.0].value.saturating_add(rcost);
@@ -523,30 +1844,44 @@ where
                 edge_id = edge.next;
             }
         }
-
-        if recycled.is_some() {
-            curr_l.truncate(input.max_width);
-            let saved_id = curr_l[input.max_width - 1];
-            self.nodes[saved_id.0].flags.set_deleted(false);
-        } else {
-            curr_l.truncate(input.max_width - 1);
-            curr_l.push(merged_id);
+        self.update_exact_path(merged_id);
+
+        if input.self_check {
+            let merged = &self.nodes[merged_id.0];
+            let original = &self.nodes[most_valuable.0];
+            if merged.value < original.value {
+                self.violations.push(Violation::LostValue {
+                    merged: merged.state.as_ref().clone(),
+                    merged_value: merged.value,
+                    original: original.state.as_ref().clone(),
+                    original_value: original.value,
+                });
+            }
         }
+        merged_id
     }
 
     fn compute_local_bounds_and_theta(&mut self, best_lb: isize) {
+        self.bounds.resize(self.nodes.len(), LocalBounds::default());
         for node_id in self.next_l.values() {
             // init for local bounds
-            self.nodes[node_id.0].value_bot = 0;
+            self.bounds[node_id.0].value_bot = self.terminal.get(node_id).copied().unwrap_or(0);
             self.nodes[node_id.0].flags.set_marked(true);
 
             if self.cutset_type == CutsetType::LastExactLayer && !self.approximate {
                 self.nodes[node_id.0].flags.set_cutset(true);
             } else if self.cutset_type == CutsetType::Frontier && self.nodes[node_id.0].flags.is_exact() {
+                // exact terminal nodes belong to the frontier too: otherwise
+                // the solutions they encode would be lost
                 self.nodes[node_id.0].flags.set_cutset(true);
+                self.cutset.push(*node_id);
             }
         }
 
+        if self.cutset_type == CutsetType::Adaptive {
+            self.choose_cutset();
+        }
+
         // propagate values upwards and update barrier
         for node_id in (0..self.nodes.len()).rev() {
             let node_id = NodeId(node_id);
@@ -557,12 +1892,12 @@ where
 
             if self.nodes[node_id.0].flags.is_cutset() {
                 // set theta for frontier nodes
-                let locb = self.nodes[node_id.0].value.saturating_add(self.nodes[node_id.0].value_bot);
+                let locb = self.nodes[node_id.0].value.saturating_add(self.bounds[node_id.0].value_bot);
                 if locb < best_lb {
-                    let pruning_theta = best_lb.saturating_sub(self.nodes[node_id.0].value_bot);
-                    self.nodes[node_id.0].theta = self.nodes[node_id.0].theta.min(pruning_theta);
+                    let pruning_theta = best_lb.saturating_sub(self.bounds[node_id.0].value_bot);
+                    self.bounds[node_id.0].theta = self.bounds[node_id.0].theta.min(pruning_theta);
                 } else {
-                    self.nodes[node_id.0].theta = self.nodes[node_id.0].theta.min(self.nodes[node_id.0].value);
+                    self.bounds[node_id.0].theta = self.bounds[node_id.0].theta.min(self.nodes[node_id.0].value);
                 }
             }
 
@@ -573,8 +1908,11 @@ where
                 self.try_update_barrier(
                     self.nodes[node_id.0].depth, 
                     self.nodes[node_id.0].state.clone(), 
-                    self.nodes[node_id.0].theta, 
-                    !self.nodes[node_id.0].flags.is_cutset() // do not mark nodes of the frontier cutset as explored
+                    self.bounds[node_id.0].theta, 
+                    // do not mark the cutset nodes as explored, nor those of the
+                    // cutset an adaptive dd turned down
+                    !self.nodes[node_id.0].flags.is_cutset() && !self.nodes[node_id.0].flags.is_candidate(),
+                    best_lb,
                 );
             }
 
@@ -584,9 +1922,9 @@ where
 
                 // propagate for local bounds
                 if self.nodes[node_id.0].flags.is_marked() {
-                    let lp_from_bot_using_edge = self.nodes[node_id.0].value_bot.saturating_add(edge.cost);
+                    let lp_from_bot_using_edge = self.bounds[node_id.0].value_bot.saturating_add(edge.cost);
 
-                    self.nodes[edge.from.0].value_bot = self.nodes[edge.from.0]
+                    self.bounds[edge.from.0].value_bot = self.bounds[edge.from.0]
                         .value_bot
                         .max(lp_from_bot_using_edge);
                     
@@ -594,15 +1932,14 @@ where
                 }
 
                 // propagate for theta
-                let theta_using_edge = self.nodes[node_id.0].theta.saturating_sub(edge.cost);
-                self.nodes[edge.from.0].theta = self.nodes[edge.from.0].theta.min(theta_using_edge);
-
-                if self.cutset_type == CutsetType::Frontier && self.nodes[node_id.0].flags.is_marked(){
-                    if !self.nodes[node_id.0].flags.is_exact() && self.nodes[edge.from.0].flags.is_exact() &&
-                        !self.nodes[edge.from.0].flags.is_cutset() {
-                        self.nodes[edge.from.0].flags.set_cutset(true);
-                        self.cutset.push(edge.from);
-                    }
+                let theta_using_edge = self.bounds[node_id.0].theta.saturating_sub(edge.cost);
+                self.bounds[edge.from.0].theta = self.bounds[edge.from.0].theta.min(theta_using_edge);
+
+                if self.cutset_type == CutsetType::Frontier && self.nodes[node_id.0].flags.is_marked() &&
+                    !self.nodes[node_id.0].flags.is_exact() && self.nodes[edge.from.0].flags.is_exact() &&
+                    !self.nodes[edge.from.0].flags.is_cutset() {
+                    self.nodes[edge.from.0].flags.set_cutset(true);
+                    self.cutset.push(edge.from);
                 }
 
                 inbound = edge.next;
@@ -610,23 +1947,1088 @@ where
         }
     }
 
-    fn try_update_barrier(&mut self, depth: usize, state: Arc<T>, theta: isize, explored: bool)
+    /// Flags the nodes of the smaller of the last exact layer and the frontier
+    /// cutsets as cutset nodes, before the thresholds get computed. Both are
+    /// flagged as candidates so that none of their thresholds claims to be
+    /// explored: which one is picked only depends on the shape of the dd.
+    fn choose_cutset(&mut self) {
+        if !self.approximate {
+            // like the last exact layer, keep the terminal nodes open
+            for node_id in self.next_l.values() {
+                self.nodes[node_id.0].flags.set_cutset(true);
+            }
+            return;
+        }
+
+        // the frontier is made of the exact nodes having a marked inexact
+        // child, and of the exact terminal nodes
+        for node_id in self.next_l.values() {
+            if self.nodes[node_id.0].flags.is_exact() {
+                self.nodes[node_id.0].flags.set_candidate(true);
+                self.frontier.push(*node_id);
+            }
+        }
+        for node_id in (0..self.nodes.len()).rev() {
+            let node = &self.nodes[node_id];
+            if node.flags.is_deleted() || !node.flags.is_marked() {
+                continue;
+            }
+
+            let child_is_exact = node.flags.is_exact();
+            let mut inbound = node.inbound;
+            while let Some(edge_id) = inbound {
+                let edge = self.edges[edge_id.0];
+                let parent = &mut self.nodes[edge.from.0].flags;
+                parent.set_marked(true);
+                if !child_is_exact && parent.is_exact() && !parent.is_candidate() {
+                    parent.set_candidate(true);
+                    self.frontier.push(edge.from);
+                }
+                inbound = edge.next;
+            }
+        }
+
+        // only the marked nodes of the last exact layer would be drained
+        let lel = self.cutset.iter().filter(|id| self.nodes[id.0].flags.is_marked()).count();
+        if self.frontier.len() < lel {
+            std::mem::swap(&mut self.cutset, &mut self.frontier);
+            // the exact nodes below the last exact layer are covered by the frontier
+            self.lel_depth = None;
+        }
+        for node_id in self.frontier.drain(..) {
+            self.nodes[node_id.0].flags.set_candidate(true);
+        }
+        for node_id in self.cutset.iter() {
+            self.nodes[node_id.0].flags.set_cutset(true);
+            self.nodes[node_id.0].flags.set_candidate(true);
+        }
+    }
+
+    fn try_update_barrier(&mut self, depth: usize, state: Arc<T>, theta: isize, explored: bool, best_lb: isize)
     {
-        // do not store thresholds below last exact layer, otherwise it blocks transitions below the cutset nodes
-        if self.cutset_type == CutsetType::LastExactLayer && self.lel_depth.is_some() && depth > self.lel_depth.unwrap() {
+        // do not store thresholds below last exact layer, otherwise it blocks transitions below the cutset nodes.
+        // Until it has chosen its cutset, an adaptive dd plays it safe
+        if self.cutset_type != CutsetType::Frontier && self.lel_depth.is_some() && depth > self.lel_depth.unwrap() {
             return;
         }
 
-        let update = self.barriers[depth].read().get(&state).map_or(true, |info| {
-            if theta > info.theta || (theta == info.theta && !info.explored && explored) {
-                true
-            } else {
-                false
+        self.barriers.update_watched_threshold(depth, state, theta, explored, best_lb);
+    }
+}
+
+#[cfg(feature = "dot")]
+impl<T> Barrier<T>
+where
+    T: Eq + PartialEq + Hash + Clone,
+{
+    /// Writes the last compiled dd in the Graphviz dot format. Each node is
+    /// labeled with its `(depth, value, flags)` and each edge with its
+    /// `(variable, value, cost)`. The cutset nodes are blue, the other exact
+    /// nodes green and the relaxed ones red. The nodes deleted by a merge are
+    /// left out.
+    pub fn to_dot(&self, f: &mut dyn std::io::Write) -> std::io::Result<()> {
+        writeln!(f, "digraph mdd {{")?;
+        writeln!(f, "  node [shape=box, style=filled];")?;
+        let active = || self.nodes.iter().enumerate().filter(|(_, n)| !n.flags.is_deleted());
+        for (id, node) in active() {
+            writeln!(f, "  n{} [label=\"({}, {}, {})\", fillcolor={}];", id, node.depth, node.value, flag_names(node.flags), dot_color(node.flags))?;
+        }
+        for (id, node) in active() {
+            let mut edge_id = node.inbound;
+            while let Some(eid) = edge_id {
+                let edge = self.edges[eid.0];
+                writeln!(f, "  n{} -> n{} [label=\"({}, {}, {})\"];", edge.from.0, id, edge.decision.var.id(), edge.decision.value, edge.cost)?;
+                edge_id = edge.next;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+/// The names of the flags which are set, separated by pipes
+#[cfg(feature = "dot")]
+fn flag_names(flags: NodeFlags) -> String {
+    let names = [
+        (flags.is_exact(), "exact"),
+        (flags.is_relaxed(), "relaxed"),
+        (flags.is_cutset(), "cutset"),
+        (flags.is_marked(), "marked"),
+        (flags.is_pruned_by_barrier(), "barrier"),
+    ];
+    names.iter().filter(|(set, _)| *set).map(|(_, name)| *name).collect::<Vec<_>>().join("|")
+}
+#[cfg(feature = "dot")]
+fn dot_color(flags: NodeFlags) -> &'static str {
+    if flags.is_cutset() {
+        "lightblue"
+    } else if flags.is_relaxed() {
+        "salmon"
+    } else if flags.is_exact() {
+        "palegreen"
+    } else {
+        "white"
+    }
+}
+
+#[cfg(test)]
+mod test_barrier {
+    use std::{sync::{atomic::Ordering, Arc}, time::Duration};
+
+    use crate::test_utils::{drained, ChainState, Chained, ChainedRelax, Diamond, DiamondRelax, Knapsack, KnapsackBrokenRelax, KnapsackMergeInto, KnapsackRanking, KnapsackRelax, KnapsackSlowRelax, KnapsackState, KnapsackStingyRelax, Tweaked};
+    use crate::{AssignedVars, CancellationToken, CompilationInput, CompilationInputBuilder, CompilationType, CutsetType, Decision, DecisionDiagram, MergePolicy, Negated, PathSegment, Problem, Relaxation, SubProblem, Variable, Violation};
+
+    use super::{Barrier, BarrierAction, BarrierInfo, BarrierLayerStats, BarrierStore, Barriers, LocalBounds, Node, NodeId};
+
+    fn barriers(problem: &Knapsack) -> Barriers<KnapsackState> {
+        Arc::new(BarrierStore::new(problem.nb_variables() + 1))
+    }
+
+    #[test]
+    fn the_layer_stats_count_the_lookups_and_the_updates() {
+        let state = |capacity| Arc::new(KnapsackState { depth: 1, capacity });
+        let store = BarrierStore::<KnapsackState>::new(3).with_depths(1..2);
+        assert!(store.update_threshold(1, state(4), 3, false));
+        assert!(store.update_threshold(1, state(4), 5, false));
+        assert!(!store.update_threshold(1, state(4), 2, true));
+        assert!(store.update_threshold(1, state(2), 1, false));
+        store.record_lookup(1, true);
+        store.record_lookup(1, false);
+        store.record_lookup(1, false);
+
+        // outside of the depths of the barrier, nothing happens
+        assert!(store.update_threshold(2, state(4), 7, false));
+        assert!(store.get_threshold(2, &state(4)).is_none());
+        assert!(store.is_layer_empty(2));
+        store.record_lookup(0, true);
+
+        let stats = store.layer_stats();
+        assert_eq!(BarrierLayerStats { hits: 1, misses: 2, inserts: 2, overwrites: 1 }, stats[1]);
+        assert_eq!(BarrierLayerStats::default(), stats[0]);
+        assert_eq!(BarrierLayerStats::default(), stats[2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn the_depths_of_the_barrier_must_be_within_its_layers() {
+        let _ = BarrierStore::<KnapsackState>::new(3).with_depths(1..4);
+    }
+
+    #[test]
+    fn the_log_of_the_watched_state_keeps_its_latest_events() {
+        let mut store = BarrierStore::<KnapsackState>::new(3);
+        let watched = KnapsackState { depth: 1, capacity: 5 };
+        store.watch(watched.clone(), 2);
+        store.update_watched_threshold(1, Arc::new(KnapsackState { depth: 1, capacity: 4 }), 3, false, 0);
+        store.update_watched_threshold(1, Arc::new(watched.clone()), 3, false, 0);
+        store.log_lookup(1, &watched, 2, store.get_threshold(1, &watched), true, 1);
+        // a threshold which is not raised is not logged
+        assert!(!store.update_watched_threshold(1, Arc::new(watched.clone()), 2, false, 1));
+        store.update_watched_threshold(1, Arc::new(watched), 4, true, 2);
+
+        let from = BarrierInfo { theta: 3, explored: false };
+        let actions = store.watched_state_log().iter().map(|e| (e.action, e.best_lb)).collect::<Vec<_>>();
+        assert_eq!(vec![
+            (BarrierAction::Prune { value: 2, threshold: from }, 1),
+            (BarrierAction::Overwrite { from, to: BarrierInfo { theta: 4, explored: true } }, 2),
+        ], actions);
+    }
+
+    #[test]
+    fn concurrent_updates_keep_the_highest_threshold() {
+        const THREADS: usize = 16;
+        const UPDATES: usize = 1000;
+        // a pseudo-random theta, so that the updates come in any order
+        let theta = |thread: usize, i: usize| ((i * 7919 + thread * 104_729) % 10_007) as isize;
+
+        let store = BarrierStore::<KnapsackState>::with_shards(2, 4);
+        let states = (0..8).map(|capacity| Arc::new(KnapsackState { depth: 1, capacity })).collect::<Vec<_>>();
+        std::thread::scope(|s| {
+            for thread in 0..THREADS {
+                let (store, states) = (&store, &states);
+                s.spawn(move || {
+                    for i in 0..UPDATES {
+                        store.update_threshold(1, states[i % states.len()].clone(), theta(thread, i), i % 3 == 0);
+                    }
+                });
+            }
+        });
+        let mut expected = vec![isize::MIN; states.len()];
+        for thread in 0..THREADS {
+            for i in 0..UPDATES {
+                expected[i % states.len()] = expected[i % states.len()].max(theta(thread, i));
+            }
+        }
+        for (state, expected) in states.iter().zip(expected) {
+            assert_eq!(Some(expected), store.get_threshold(1, state).map(|info| info.theta));
+        }
+        assert!(store.is_layer_empty(0) && !store.is_layer_empty(1));
+        store.clear_layer(1);
+        assert!(store.is_layer_empty(1));
+    }
+
+    fn input<'a, R>(problem: &'a Knapsack, relaxation: &'a R, comp_type: CompilationType, max_width: usize, offset: usize)
+        -> CompilationInput<'a, Knapsack, R, KnapsackRanking>
+    where
+        R: Relaxation<State = KnapsackState>,
+    {
+        CompilationInput {
+            comp_type,
+            max_width,
+            problem,
+            relaxation,
+            ranking: &KnapsackRanking,
+            residual: SubProblem {
+                state: Arc::new(problem.initial_state()),
+                value: problem.initial_value(),
+                path: vec![],
+                ub: isize::MAX,
+                id: 0,
+                parent: None,
+                prefix: None,
+            },
+            assigned: AssignedVars::new(problem.nb_variables()),
+            best_lb: isize::MIN,
+            min_relax_depth_offset: offset,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            dominance: None,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: true,
+            timed: false,
+            self_check: false,
+            cancellation: None,
+            incumbent: None,
+        }
+    }
+
+    /// What a relaxed dd tells the solver: its best value and solution, its
+    /// exactness, its cutset (with their upper bounds) and the thresholds it
+    /// left in the barrier
+    type Outcome = (Option<isize>, Option<Vec<Decision>>, bool, Vec<(usize, usize, isize, isize)>, Vec<(usize, usize, isize, bool)>);
+
+    fn relaxed_outcome(mdd: &mut Barrier<KnapsackState>) -> Outcome {
+        let (best_value, best_solution, exact) = (mdd.best_value(), mdd.best_solution(), mdd.is_exact());
+        let mut cutset = vec![];
+        mdd.drain_cutset(|n| cutset.push((n.state.depth, n.state.capacity, n.value, n.ub)));
+        cutset.sort_unstable();
+        let mut thresholds = mdd.barriers.thresholds().into_iter().enumerate()
+            .flat_map(|(depth, layer)| layer.into_iter().map(move |(s, info)| (depth, s.capacity, info.theta, info.explored)))
+            .collect::<Vec<_>>();
+        thresholds.sort_unstable();
+        (best_value, best_solution, exact, cutset, thresholds)
+    }
+
+    #[test]
+    fn the_relaxed_dd_which_resumes_from_the_restricted_one_is_the_same() {
+        let problem = Knapsack { capacity: 15, profit: vec![5, 3, 4, 6, 2, 1, 7, 3, 5, 2], weight: vec![4, 3, 5, 4, 2, 3, 6, 2, 5, 1] };
+        let mut resumed_once = false;
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            for (width, offset, best_lb) in [(1, 0, isize::MIN), (2, 1, isize::MIN), (3, 1, 15), (3, 3, isize::MIN), (8, 2, 18)] {
+                let restricted = CompilationInput { best_lb, ..input(&problem, &KnapsackRelax, CompilationType::Restricted, width, offset) };
+                let mut two_pass = Barrier::new(barriers(&problem), cutset_type);
+                two_pass.compile(&restricted);
+                let expected_restricted = (two_pass.best_value(), two_pass.best_solution(), two_pass.is_exact());
+                two_pass.compile(&restricted.for_relaxed());
+                let explored = two_pass.get_explored();
+                let counts = (two_pass.node_count(), two_pass.arc_count());
+                let expected = relaxed_outcome(&mut two_pass);
+
+                let restricted = CompilationInput { reuse_prefix: true, ..restricted };
+                let mut fused = Barrier::new(barriers(&problem), cutset_type);
+                fused.compile(&restricted);
+                assert_eq!(expected_restricted, (fused.best_value(), fused.best_solution(), fused.is_exact()));
+                let resumed = fused.resume_relaxed(&restricted.for_relaxed());
+                assert_eq!(!expected_restricted.2, resumed);
+                if resumed {
+                    resumed_once = true;
+                    assert!(fused.stats().reused_nodes > 0);
+                    assert_eq!(explored, fused.get_explored() + fused.stats().reused_nodes);
+                assert_eq!(counts, (fused.node_count(), fused.arc_count()));
+                    assert_eq!(expected, relaxed_outcome(&mut fused));
+                }
+                // the layers are only kept until the next compilation
+                assert!(!fused.resume_relaxed(&restricted.for_relaxed()));
             }
+        }
+        assert!(resumed_once);
+    }
+
+    #[test]
+    fn a_relaxed_dd_does_not_resume_once_the_lower_bound_has_changed() {
+        let problem = Knapsack::toy();
+        let restricted = CompilationInput { reuse_prefix: true, ..input(&problem, &KnapsackRelax, CompilationType::Restricted, 1, 1) };
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&restricted);
+        assert!(!mdd.resume_relaxed(&CompilationInput { best_lb: 10, ..restricted.for_relaxed() }));
+
+        // nothing is kept unless asked to
+        mdd.compile(&CompilationInput { reuse_prefix: false, ..restricted.for_restricted() });
+        assert!(!mdd.resume_relaxed(&restricted.for_relaxed()));
+    }
+
+    #[test]
+    fn the_cutset_holds_each_state_once() {
+        let problem = Diamond(Knapsack {
+            capacity: 12,
+            profit: (0..10).map(|i| 1 + (i * 7) % 11).collect(),
+            weight: (0..10).map(|i| 1 + (i * 5) % 4).collect(),
         });
+        let mut duplicated = 0;
+        for width in 2..6 {
+            let input = CompilationInputBuilder::relaxed(&problem, &DiamondRelax, &DiamondRelax).max_width(width).root().build().unwrap();
+            let mut mdd = Barrier::new(Arc::new(BarrierStore::new(problem.nb_variables() + 1)), CutsetType::Frontier);
+            mdd.compile(&input);
+            // the nodes of the same state at several depths
+            duplicated += mdd.cutset.iter().filter(|a| mdd.cutset.iter().any(|b| a != &b && mdd.nodes[a.0].state == mdd.nodes[b.0].state)).count();
+
+            let mut drained = vec![];
+            mdd.drain_cutset(|node| drained.push(node));
+            let mut states = drained.iter().map(|node| *node.state).collect::<Vec<_>>();
+            states.sort_unstable();
+            states.dedup();
+            assert_eq!(states.len(), drained.len());
+        }
+        assert!(duplicated > 0);
+    }
 
-        if update {
-            self.barriers[depth].write().insert(state, BarrierInfo { theta, explored });
+    fn compile_relaxed(cutset_type: CutsetType, offset: usize) -> (Barrier<KnapsackState>, Vec<SubProblem<KnapsackState>>) {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), cutset_type);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Relaxed, 1, offset));
+        let mut cutset = vec![];
+        mdd.drain_cutset(|n| cutset.push(n));
+        (mdd, cutset)
+    }
+
+    #[test]
+    fn exact_dd_counts_every_node_and_edge() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+        assert_eq!(41, mdd.nb_nodes());
+        assert_eq!(41, mdd.nb_active_nodes());
+        assert_eq!(54, mdd.nb_edges());
+    }
+
+    #[test]
+    fn an_exact_compilation_gives_the_optimum() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        let exact = mdd.compile_exact(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1)).unwrap();
+        assert_eq!(Some(13), exact.optimal_value());
+        let mut taken = exact.optimal_solution().unwrap().iter().filter(|d| d.value == 1).map(|d| d.var.id()).collect::<Vec<_>>();
+        taken.sort_unstable();
+        assert_eq!(vec![0, 3, 4], taken);
+        assert_eq!(exact.optimal_solutions(10).first(), exact.optimal_solution().as_ref());
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = CompilationInput { cancellation: Some(&token), ..input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1) };
+        assert!(mdd.compile_exact(&cancelled).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "compile_exact needs an exact compilation")]
+    fn compile_exact_rejects_a_relaxed_compilation() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile_exact(&input(&problem, &KnapsackRelax, CompilationType::Relaxed, 1, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "an exact dd has no cutset to drain")]
+    fn an_exact_dd_has_no_cutset_to_drain() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+        mdd.drain_cutset(|_| {});
+    }
+
+    #[test]
+    fn a_relaxed_best_path_has_no_exact_value() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Relaxed, 1, 1));
+        assert!(!mdd.is_exact());
+        assert!(mdd.best_value().unwrap() > 13);
+        assert!(mdd.best_solution().is_some());
+        assert_eq!(None, mdd.best_exact_value());
+        assert_eq!(None, mdd.best_exact_solution());
+
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Restricted, 1, 1));
+        assert_eq!(mdd.best_value(), mdd.best_exact_value());
+        assert_eq!(mdd.best_solution(), mdd.best_exact_solution());
+    }
+
+    #[test]
+    fn a_dd_with_enough_capacity_never_reallocates() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::with_capacity(barriers(&problem), CutsetType::LastExactLayer, 64, 64);
+        for _ in 0..3 {
+            mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+            assert_eq!(41, mdd.nb_nodes());
+            assert_eq!((64, 64), mdd.capacity());
+        }
+        // the arenas grow as needed, and they keep their memory once cleared
+        let mut mdd = Barrier::with_capacity(barriers(&problem), CutsetType::LastExactLayer, 1, 1);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+        let capacity = mdd.capacity();
+        assert!(capacity.0 >= 41 && capacity.1 >= 54);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+        assert_eq!(capacity, mdd.capacity());
+        assert_eq!(Some(13), mdd.best_value());
+    }
+
+    #[test]
+    fn the_hints_of_the_problem_reserve_each_layer_before_branching() {
+        let problem = Tweaked::hinted(Knapsack::toy());
+        let hinted = CompilationInputBuilder::exact(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(usize::MAX).build().unwrap();
+        let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::LastExactLayer);
+        mdd.compile(&hinted);
+        // a hint bounds the transitions, hence nothing outgrows its memory
+        assert!(mdd.nb_reservations() > 0);
+        assert_eq!(0, mdd.nb_reallocations());
+        assert_eq!(Some(13), mdd.best_value());
+
+        // without hints, the first layers are built before the growth is known
+        let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem.knapsack, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+        assert!(mdd.nb_reallocations() > 0);
+        assert_eq!(Some(13), mdd.best_value());
+    }
+
+    #[test]
+    fn the_hints_of_a_minimization_reserve_each_layer_before_branching() {
+        let problem = Tweaked::hinted(Knapsack::toy());
+        let minimization = Negated(&problem);
+        let relaxation = Negated(&KnapsackRelax);
+        let hinted = CompilationInputBuilder::exact(&minimization, &relaxation, &KnapsackRanking).root().max_width(usize::MAX).build().unwrap();
+        let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::LastExactLayer);
+        mdd.compile(&hinted);
+        assert!(mdd.nb_reservations() > 0);
+        assert_eq!(0, mdd.nb_reallocations());
+        assert_eq!(Some(0), mdd.best_value());
+    }
+
+    #[test]
+    fn the_layers_which_cannot_be_merged_can_be_restricted_instead() {
+        let problem = Knapsack::toy();
+        // a residual subproblem two layers below the root, whose next two
+        // layers may not be merged
+        let path = vec![Decision { var: Variable(0), value: 0 }, Decision { var: Variable(1), value: 0 }];
+        let residual = SubProblem { state: Arc::new(KnapsackState { depth: 2, capacity: 10 }), value: 0, path, ub: isize::MAX, id: 0, parent: None, prefix: None };
+        let deep = CompilationInput {
+            assigned: AssignedVars::from_path(problem.nb_variables(), &residual.path),
+            residual,
+            ..input(&problem, &KnapsackRelax, CompilationType::Relaxed, 1, 2)
+        };
+        let widths = |mdd: &Barrier<KnapsackState>| {
+            let mut widths = vec![0; problem.nb_variables() + 1];
+            mdd.nodes.iter().filter(|n| !n.flags.is_deleted()).for_each(|n| widths[n.depth] += 1);
+            widths
+        };
+
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&deep);
+        assert_eq!(vec![0, 0, 1, 2, 4, 1, 2], widths(&mdd));
+
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            let mut mdd = Barrier::new(barriers(&problem), cutset_type);
+            mdd.compile(&CompilationInput { restrict_first_layers: true, ..deep.for_relaxed() });
+            assert_eq!(vec![0, 0, 1, 1, 1, 1, 1], widths(&mdd));
+            assert!(!mdd.is_exact());
+            // the best of the remaining items (2 and 3) are worth 10: some
+            // drained node must still be able to reach them
+            assert!(mdd.best_value().unwrap() >= 10);
+            let mut cutset = vec![];
+            mdd.drain_cutset(|n| cutset.push(n));
+            assert!(cutset.iter().any(|n| n.ub >= 10));
+            // the node dropped right below the residual is drained too
+            assert!(cutset.iter().any(|n| n.state.depth == 3));
+            assert!(cutset.iter().all(|n| n.ub >= n.value));
         }
     }
+
+    #[test]
+    fn no_arc_is_allocated_for_the_states_that_already_exist() {
+        // the toy knapsack is full of diamonds: e.g. taking either item 0 or
+        // item 3 (both weigh 4) leads to the same state
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+        // every edge which did not create a node reached an existing state
+        assert_eq!(mdd.nb_edges() - (mdd.nb_nodes() - 1), mdd.nb_avoided_allocations());
+        assert_eq!(14, mdd.nb_avoided_allocations());
+        assert_eq!(Some(13), mdd.best_value());
+    }
+
+    #[test]
+    fn relaxed_dd_does_not_count_deleted_nodes_nor_redirected_edges() {
+        let (mdd, _) = compile_relaxed(CutsetType::LastExactLayer, 1);
+        assert_eq!(19, mdd.nb_nodes());
+        assert_eq!(9, mdd.nb_active_nodes());
+        assert_eq!(14, mdd.nb_edges());
+    }
+
+    #[test]
+    fn the_node_and_arc_counts_are_those_of_the_compiled_dd() {
+        let problem = Knapsack::toy();
+        let compile = |comp_type, max_width| {
+            let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+            mdd.compile(&input(&problem, &KnapsackRelax, comp_type, max_width, 1));
+            mdd
+        };
+        let mdd = compile(CompilationType::Exact, usize::MAX);
+        assert_eq!((mdd.nb_active_nodes(), mdd.nb_edges()), (mdd.node_count(), mdd.arc_count()));
+
+        let mdd = compile(CompilationType::Restricted, 1);
+        assert!(mdd.stats().restricted_nodes > 0);
+        assert_eq!(mdd.nb_nodes() - mdd.stats().restricted_nodes, mdd.node_count());
+        assert_eq!(mdd.stats().total_arcs, mdd.arc_count());
+
+        let mdd = compile(CompilationType::Relaxed, 1);
+        assert_eq!(mdd.nb_active_nodes(), mdd.node_count());
+        assert!(mdd.arc_count() > mdd.nb_edges());
+    }
+
+    #[test]
+    fn the_counts_of_a_restricted_or_relaxed_dd_differ_from_its_active_nodes_and_edges() {
+        let problem = Knapsack::toy();
+        let compile = |comp_type| {
+            let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+            mdd.compile(&input(&problem, &KnapsackRelax, comp_type, 1, 1));
+            mdd
+        };
+        // the 3 nodes truncated by the restricted layers are still active
+        let mdd = compile(CompilationType::Restricted);
+        assert_eq!(3, mdd.stats().restricted_nodes);
+        assert_eq!((10, 7), (mdd.nb_active_nodes(), mdd.node_count()));
+        assert_eq!((9, 9), (mdd.nb_edges(), mdd.arc_count()));
+
+        // the 10 arcs which reach the merged nodes were copied to the merged ones
+        let mdd = compile(CompilationType::Relaxed);
+        assert_eq!((9, 9), (mdd.nb_active_nodes(), mdd.node_count()));
+        assert_eq!((14, 24), (mdd.nb_edges(), mdd.arc_count()));
+    }
+
+    #[test]
+    fn relaxed_bound_is_valid_whatever_the_offset() {
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            for offset in 0..=2 {
+                let (mdd, _) = compile_relaxed(cutset_type, offset);
+                assert!(mdd.best_value().unwrap() >= 13);
+            }
+        }
+    }
+
+    #[test]
+    fn a_greater_offset_keeps_more_nodes() {
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            let explored = (0..=2)
+                .map(|offset| compile_relaxed(cutset_type, offset).0.get_explored())
+                .collect::<Vec<_>>();
+            assert!(explored[0] < explored[1]);
+            assert!(explored[1] < explored[2]);
+        }
+    }
+
+    #[test]
+    fn with_offset_zero_the_root_is_the_only_cutset_node() {
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            let (_, cutset) = compile_relaxed(cutset_type, 0);
+            assert_eq!(1, cutset.len());
+            assert!(cutset[0].path.is_empty());
+        }
+    }
+
+    #[test]
+    fn the_cutset_covers_the_optimum_when_offset_is_large_enough() {
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            for offset in 1..=2 {
+                let (_, cutset) = compile_relaxed(cutset_type, offset);
+                assert!(!cutset.is_empty());
+                assert!(cutset.iter().all(|n| !n.path.is_empty()));
+                assert!(cutset.iter().map(|n| n.ub).max().unwrap() >= 13);
+            }
+        }
+    }
+
+    #[test]
+    fn the_cutset_nodes_share_the_prefix_of_the_residual() {
+        let problem = Knapsack::toy();
+        let path = vec![Decision { var: Variable(0), value: 1 }, Decision { var: Variable(1), value: 0 }];
+        let residual = SubProblem {
+            state: Arc::new(KnapsackState { depth: 2, capacity: 6 }),
+            value: 5,
+            path: path.clone(),
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        };
+        let prefix = Arc::new(PathSegment::new(Some(Arc::new(PathSegment::new(None, path[..1].to_vec()))), path[1..].to_vec()));
+        let shared = SubProblem { path: vec![], prefix: Some(prefix.clone()), ..residual.clone() };
+        let drain = |residual: SubProblem<KnapsackState>| {
+            let input = CompilationInput {
+                assigned: AssignedVars::from_subproblem(problem.nb_variables(), &residual),
+                residual,
+                ..input(&problem, &KnapsackRelax, CompilationType::Relaxed, 1, 1)
+            };
+            let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+            mdd.compile(&input);
+            let mut cutset = vec![];
+            mdd.drain_cutset(|node| cutset.push(node));
+            (mdd.best_value(), cutset)
+        };
+
+        let (value, complete) = drain(residual);
+        let (shared_value, suffixes) = drain(shared);
+        assert_eq!(value, shared_value);
+        assert!(!complete.is_empty());
+        assert_eq!(complete.len(), suffixes.len());
+        for (complete, suffix) in complete.iter().zip(suffixes.iter()) {
+            assert!(complete.prefix.is_none());
+            assert!(Arc::ptr_eq(&prefix, suffix.prefix.as_ref().unwrap()));
+            assert_eq!(complete.depth(), suffix.depth());
+            let mut path = prefix.to_vec();
+            path.extend_from_slice(&suffix.path);
+            assert_eq!(complete.path, path);
+        }
+    }
+
+    #[test]
+    fn the_adaptive_cutset_is_the_smaller_one() {
+        let cutset = |problem, cutset_type, max_width, best_lb| {
+            drained(&mut Barrier::new(barriers(problem), cutset_type), problem, max_width, best_lb)
+        };
+        let toy = Knapsack::toy();
+        let lel = cutset(&toy, CutsetType::LastExactLayer, 2, isize::MIN);
+        let frontier = cutset(&toy, CutsetType::Frontier, 2, isize::MIN);
+        assert!(lel.len() < frontier.len());
+        assert_eq!(lel, cutset(&toy, CutsetType::Adaptive, 2, isize::MIN));
+
+        let funnel = Knapsack::funnel();
+        let lel = cutset(&funnel, CutsetType::LastExactLayer, 4, 7);
+        let frontier = cutset(&funnel, CutsetType::Frontier, 4, 7);
+        assert!(frontier.len() < lel.len());
+        assert_eq!(frontier, cutset(&funnel, CutsetType::Adaptive, 4, 7));
+    }
+
+    #[test]
+    fn the_adaptive_cutset_covers_the_optimum() {
+        for (problem, max_width, best_lb, optimum) in [(Knapsack::toy(), 2, isize::MIN, 13), (Knapsack::funnel(), 4, 7, 8)] {
+            let mut mdd = Barrier::new(barriers(&problem), CutsetType::Adaptive);
+            let cutset = drained(&mut mdd, &problem, max_width, best_lb);
+            assert!(mdd.best_value().unwrap() >= optimum);
+            assert!(cutset.iter().map(|n| n.3).max().unwrap() >= optimum);
+        }
+    }
+
+    #[test]
+    fn the_thresholds_of_the_cutset_turned_down_do_not_claim_explored() {
+        let problem = Knapsack::funnel();
+        let lel = drained(&mut Barrier::new(barriers(&problem), CutsetType::LastExactLayer), &problem, 4, 7);
+        let frontier = drained(&mut Barrier::new(barriers(&problem), CutsetType::Frontier), &problem, 4, 7);
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::Adaptive);
+        let chosen = drained(&mut mdd, &problem, 4, 7);
+        assert_eq!(frontier, chosen);
+
+        let mut checked = 0;
+        for (depth, capacity, _, _) in lel {
+            let state = KnapsackState { depth, capacity };
+            if let Some(info) = mdd.barriers.get_threshold(depth, &state) {
+                assert!(!info.explored, "{:?} claims to be explored", state);
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "inconsistent merge")]
+    fn a_broken_relaxation_is_caught_by_the_merge_check() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::Frontier);
+        mdd.compile(&input(&problem, &KnapsackBrokenRelax, CompilationType::Relaxed, 1, 1));
+    }
+
+    #[test]
+    fn a_broken_relaxation_goes_unnoticed_without_the_merge_check() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::Frontier);
+        let input = input(&problem, &KnapsackBrokenRelax, CompilationType::Relaxed, 1, 1);
+        mdd.compile(&CompilationInput { check_merge: false, ..input });
+        // the relaxed bound is not an upper bound on the optimum (13) anymore
+        assert!(mdd.best_value().unwrap() < 13);
+    }
+
+    #[test]
+    fn a_relaxation_losing_value_is_caught_by_the_self_check() {
+        let problem = Knapsack::toy();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::Frontier);
+        let stingy = input(&problem, &KnapsackStingyRelax, CompilationType::Relaxed, 2, 1);
+        mdd.compile(&CompilationInput { self_check: true, ..stingy });
+        let mut violations = vec![];
+        mdd.drain_violations(|v| violations.push(v));
+        assert!(!violations.is_empty());
+        for violation in violations {
+            match violation {
+                Violation::LostValue { merged_value, original_value, .. } => assert!(merged_value < original_value),
+                _ => panic!("unexpected violation: {}", violation),
+            }
+        }
+
+        // a valid model goes through the self check
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::Frontier);
+        let valid = input(&problem, &KnapsackRelax, CompilationType::Relaxed, 2, 1);
+        mdd.compile(&CompilationInput { self_check: true, ..valid });
+        mdd.drain_violations(|v| panic!("unexpected violation: {}", v));
+    }
+
+    #[test]
+    fn a_slow_estimate_is_attributed_to_the_model() {
+        let delay = Duration::from_millis(2);
+        let (problem, calls) = Tweaked::slow_estimate(Knapsack::toy(), delay);
+        let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::LastExactLayer);
+        mdd.compile(&CompilationInput { timed: true, ..problem.input(CompilationType::Relaxed, vec![]) });
+        let calls = calls.load(Ordering::Relaxed) as u32;
+        assert!(calls > 0);
+        assert!(mdd.model_time() >= delay * calls);
+        assert!(mdd.model_time() > mdd.framework_time());
+        let timed = (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value());
+
+        // nothing is measured unless the compilation is timed, which does not
+        // change the dd (given the same thresholds)
+        let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::LastExactLayer);
+        mdd.compile(&problem.input(CompilationType::Relaxed, vec![]));
+        assert_eq!(timed, (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value()));
+        assert_eq!(Duration::ZERO, mdd.model_time());
+        assert_eq!(Duration::ZERO, mdd.framework_time());
+    }
+
+    #[test]
+    fn a_slow_relax_is_attributed_to_the_model() {
+        let problem = Knapsack::toy();
+        let relax = KnapsackSlowRelax { delay: Duration::from_millis(2), ..Default::default() };
+        let input = CompilationInputBuilder::relaxed(&problem, &relax, &KnapsackRanking).root().max_width(2).timed(true).build().unwrap();
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&input);
+        let calls = relax.calls.load(Ordering::Relaxed) as u32;
+        assert!(calls > 0);
+        assert!(mdd.model_time() >= relax.delay * calls);
+        assert!(mdd.model_time() > mdd.framework_time());
+    }
+
+    #[test]
+    fn the_model_sees_the_variables_assigned_above_each_layer() {
+        let (problem, observed) = Tweaked::context_probe(Knapsack::toy());
+        let path = vec![Decision { var: Variable(0), value: 1 }, Decision { var: Variable(1), value: 0 }];
+        for comp_type in [CompilationType::Exact, CompilationType::Restricted, CompilationType::Relaxed] {
+            observed.lock().unwrap().clear();
+            let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::Frontier);
+            mdd.compile(&problem.input(comp_type, path.clone()));
+
+            // the residual path, and then one more variable per compiled layer
+            let expected = (2..=6).map(|depth| (0..depth).collect::<Vec<_>>()).collect::<Vec<_>>();
+            assert_eq!(expected, *observed.lock().unwrap());
+        }
+    }
+
+    #[test]
+    fn the_estimates_see_the_best_path_of_each_node() {
+        let path = vec![Decision { var: Variable(0), value: 1 }, Decision { var: Variable(1), value: 0 }];
+        for comp_type in [CompilationType::Exact, CompilationType::Restricted] {
+            let (problem, observed) = Tweaked::path_probe(Knapsack::toy(), true);
+            let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::Frontier);
+            mdd.compile(&problem.input(comp_type, path.clone()));
+
+            // the nodes of an exact or restricted dd are all reached by their best path
+            let observed = observed.lock().unwrap();
+            assert!(!observed.is_empty());
+            for (state, best_path) in observed.iter() {
+                assert!(best_path.starts_with(&path));
+                assert_eq!(*state, problem.replay(best_path));
+            }
+        }
+    }
+
+    #[test]
+    fn the_paths_are_only_materialized_when_the_problem_estimates_from_them() {
+        let ((plain, plain_observed), (probed, probed_observed)) = (Tweaked::path_probe(Knapsack::toy(), false), Tweaked::path_probe(Knapsack::toy(), true));
+        for comp_type in [CompilationType::Exact, CompilationType::Restricted, CompilationType::Relaxed] {
+            let mut mdd = Barrier::new(barriers(&plain.knapsack), CutsetType::Frontier);
+            mdd.compile(&plain.input(comp_type, vec![]));
+            let expected = (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value());
+            // a fresh barrier, so that both compilations see the same thresholds
+            let mut mdd = Barrier::new(barriers(&probed.knapsack), CutsetType::Frontier);
+            mdd.compile(&probed.input(comp_type, vec![]));
+            assert_eq!(expected, (mdd.nb_nodes(), mdd.nb_edges(), mdd.best_value()));
+        }
+        assert!(plain_observed.lock().unwrap().is_empty());
+        assert!(!probed_observed.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_relaxed_dd_prunes_with_the_incumbent_found_while_it_compiles() {
+        // the toy knapsack is worth 13: an incumbent of 12 keeps the bound valid
+        let (problem, incumbent) = Tweaked::improving(Knapsack::toy(), 3, 12);
+        let compile = |incumbent| {
+            let mut mdd = Barrier::new(barriers(&problem.knapsack), CutsetType::LastExactLayer);
+            let builder = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(3);
+            mdd.compile(&match incumbent {
+                Some(incumbent) => builder.incumbent(incumbent).build().unwrap(),
+                None => builder.build().unwrap(),
+            });
+            (mdd.best_value(), *mdd.stats())
+        };
+        let (stale, stale_stats) = compile(None);
+        let (fresh, fresh_stats) = compile(Some(&incumbent));
+
+        assert_eq!(12, incumbent.load(Ordering::Relaxed));
+        assert_eq!((0, 1), (stale_stats.bound_refreshes, fresh_stats.bound_refreshes));
+        assert!(fresh_stats.rub_prunes > stale_stats.rub_prunes);
+        assert!(stale.unwrap() >= 13);
+        assert!(fresh.unwrap_or(isize::MIN).max(12) >= 13);
+    }
+
+    #[cfg(feature = "dot")]
+    #[test]
+    fn the_dot_output_shows_every_node_and_edge() {
+        let problem = Knapsack { capacity: 2, profit: vec![1, 2, 3], weight: vec![1, 1, 1] };
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&input(&problem, &KnapsackRelax, CompilationType::Exact, usize::MAX, 1));
+        let mut out = vec![];
+        mdd.to_dot(&mut out).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+
+        let node = regex::Regex::new(r#"^  n\d+ \[label="\((\d+), (-?\d+), ([a-z|]*)\)", fillcolor=(\w+)\];$"#).unwrap();
+        let edge = regex::Regex::new(r#"^  n\d+ -> n\d+ \[label="\((\d+), ([01]), (-?\d+)\)"\];$"#).unwrap();
+        let lines = dot.lines().collect::<Vec<_>>();
+        assert_eq!(Some(&"digraph mdd {"), lines.first());
+        assert_eq!(Some(&"}"), lines.last());
+
+        let nodes = lines.iter().filter_map(|l| node.captures(l)).collect::<Vec<_>>();
+        let edges = lines.iter().filter_map(|l| edge.captures(l)).collect::<Vec<_>>();
+        assert_eq!(mdd.nb_active_nodes(), nodes.len());
+        assert_eq!(mdd.nb_edges(), edges.len());
+        // the dd is exact, and the best of its terminal nodes is worth 5
+        assert!(nodes.iter().all(|c| c[3].starts_with("exact") && &c[4] == "palegreen"));
+        assert!(nodes.iter().any(|c| &c[1] == "3" && &c[2] == "5"));
+        assert!(edges.iter().all(|c| c[1].parse::<usize>().unwrap() < 3));
+        assert_eq!(lines.len(), 3 + nodes.len() + edges.len());
+    }
+
+    #[test]
+    fn the_restricted_dds_do_not_pay_for_the_local_bounds() {
+        let problem = Knapsack::toy();
+        let footprint = |mdd: &Barrier<KnapsackState>| {
+            mdd.nodes.len() * std::mem::size_of::<Node<KnapsackState>>() + mdd.bounds.len() * std::mem::size_of::<LocalBounds>()
+        };
+
+        // wide enough to be exact, both dds hold the same nodes
+        let mut restricted = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        restricted.compile(&input(&problem, &KnapsackRelax, CompilationType::Restricted, usize::MAX, 1));
+        let mut relaxed = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        relaxed.compile(&input(&problem, &KnapsackRelax, CompilationType::Relaxed, usize::MAX, 1));
+        assert_eq!(restricted.nodes.len(), relaxed.nodes.len());
+        assert!(restricted.bounds.is_empty());
+        assert_eq!(relaxed.nodes.len(), relaxed.bounds.len());
+        assert!(footprint(&restricted) < footprint(&relaxed));
+        assert_eq!((true, Some(13)), (restricted.is_exact(), restricted.best_value()));
+        assert_eq!((true, Some(13)), (relaxed.is_exact(), relaxed.best_value()));
+        assert_eq!(restricted.best_solution(), relaxed.best_solution());
+
+        // an actual restriction does not need them either
+        restricted.compile(&input(&problem, &KnapsackRelax, CompilationType::Restricted, 2, 1));
+        assert!(!restricted.is_exact());
+        assert!(restricted.bounds.is_empty());
+        assert!(restricted.best_value() <= Some(13));
+    }
+
+    /// Checks the exact path flag of each node against a walk of its best path
+    fn assert_exact_paths(mdd: &Barrier<KnapsackState>) {
+        for (id, node) in mdd.nodes.iter().enumerate().filter(|(_, node)| !node.flags.is_deleted()) {
+            assert_eq!(mdd.walk_exact_best_path(Some(NodeId(id))), node.flags.is_exact_path(), "node #{} at depth {}", id, node.state.depth);
+        }
+        assert_eq!(mdd.walk_exact_best_path(mdd.best_n), mdd.has_exact_best_path(mdd.best_n));
+    }
+
+    #[test]
+    fn the_exact_path_flags_agree_with_the_best_paths() {
+        let larger = Knapsack {
+            capacity: 30,
+            profit: (0..14).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..14).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        for problem in [Knapsack::toy(), larger] {
+            for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+                for width in 1..=6 {
+                    for offset in 0..=2 {
+                        let mut mdd = Barrier::new(barriers(&problem), cutset_type);
+                        let restricted = CompilationInputBuilder::restricted(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(width).build().unwrap();
+                        mdd.compile(&restricted);
+                        assert_exact_paths(&mdd);
+                        let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(width).min_relax_depth_offset(offset);
+                        mdd.compile(&relaxed.build().unwrap());
+                        assert_exact_paths(&mdd);
+                        let stingy = CompilationInputBuilder::relaxed(&problem, &KnapsackStingyRelax, &KnapsackRanking).root().max_width(width).min_relax_depth_offset(offset);
+                        mdd.compile(&stingy.build().unwrap());
+                        assert_exact_paths(&mdd);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_recycled_merged_node_is_not_on_an_exact_path() {
+        // with a width of 2, the second layer keeps the node of capacity 3 and
+        // merges the three others into it
+        let problem = Knapsack::toy();
+        let cutset_type = CutsetType::LastExactLayer;
+        let mut mdd = Barrier::new(barriers(&problem), cutset_type);
+        let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackMergeInto(3), &KnapsackRanking).root().max_width(2).check_merge(false).build().unwrap();
+        mdd.compile(&relaxed);
+        let recycled = mdd.nodes.iter().position(|n| n.state.capacity == 3 && n.flags.is_relaxed() && !n.flags.is_deleted());
+        assert!(recycled.is_some());
+        assert!(!mdd.nodes[recycled.unwrap()].flags.is_exact_path());
+        assert_exact_paths(&mdd);
+        assert_eq!(None, mdd.best_exact_value());
+    }
+
+    #[test]
+    fn a_chunked_merge_gives_a_valid_bound() {
+        let larger = Knapsack {
+            capacity: 30,
+            profit: (0..14).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..14).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        for problem in [Knapsack::toy(), larger] {
+            let mut exact = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+            exact.compile(&CompilationInputBuilder::exact(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(usize::MAX).build().unwrap());
+            let optimum = exact.best_value().unwrap();
+            for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+                for width in 1..=6 {
+                    for chunks in 2..=4 {
+                        let mut mdd = Barrier::new(barriers(&problem), cutset_type);
+                        let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(width).merge_policy(MergePolicy::Chunked(chunks));
+                        mdd.compile(&relaxed.self_check(true).build().unwrap());
+                        assert!(mdd.best_value().unwrap() >= optimum);
+                        mdd.drain_violations(|v| panic!("unexpected violation: {}", v));
+                        assert_exact_paths(&mdd);
+                        // neither the first layer below the root nor the terminal one are merged
+                        for depth in 2..problem.nb_variables() {
+                            assert!(mdd.nodes.iter().filter(|n| n.depth == depth && !n.flags.is_deleted()).count() <= width);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_chunked_merge_is_at_least_as_tight_as_a_single_merged_node() {
+        let problem = Knapsack::toy();
+        let bound = |width: usize, policy: MergePolicy| {
+            let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+            mdd.compile(&CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(width).merge_policy(policy).build().unwrap());
+            mdd.best_value().unwrap()
+        };
+        for width in 3..=6 {
+            for chunks in 2..=4 {
+                assert!(bound(width, MergePolicy::Chunked(chunks)) <= bound(width, MergePolicy::SingleNode));
+            }
+        }
+        // the chunks keep apart capacities which the single merged node mixes
+        assert!(bound(3, MergePolicy::Chunked(2)) < bound(3, MergePolicy::SingleNode));
+    }
+
+    #[test]
+    fn the_stats_count_what_each_compilation_restricted_merged_and_pruned() {
+        let problem = Knapsack::toy();
+        let relaxed = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(3).merge_policy(MergePolicy::Chunked(2)).build().unwrap();
+        let restricted = CompilationInputBuilder::restricted(&problem, &KnapsackRelax, &KnapsackRanking).root().max_width(3).build().unwrap();
+
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&restricted);
+        assert!(mdd.stats().restricted_nodes > 0);
+        assert_eq!((0, 0, None), (mdd.stats().relaxed_merges, mdd.stats().barrier_prunes, mdd.stats().lel_depth));
+
+        let mut mdd = Barrier::new(barriers(&problem), CutsetType::LastExactLayer);
+        mdd.compile(&relaxed);
+        // each merged layer is given one node per chunk
+        assert_eq!((8, 0, Some(1)), (mdd.stats().relaxed_merges, mdd.stats().barrier_prunes, mdd.stats().lel_depth));
+        assert!(mdd.stats().total_arcs > mdd.nb_edges());
+
+        // the thresholds of the relaxed dd prune the next compilation
+        mdd.compile(&restricted);
+        assert_eq!(2, mdd.stats().barrier_prunes);
+        assert_eq!((0, None, 2), (mdd.stats().relaxed_merges, mdd.stats().lel_depth, mdd.stats().total_arcs));
+    }
+
+    fn compile_chained(problem: &Chained, comp_type: CompilationType, max_width: usize, max_forced_layers: usize) -> Barrier<ChainState> {
+        let input = CompilationInputBuilder::new()
+            .comp_type(comp_type)
+            .problem(problem)
+            .relaxation(&ChainedRelax)
+            .ranking(&ChainedRelax)
+            .root()
+            .max_width(max_width)
+            .max_forced_layers(max_forced_layers)
+            .build()
+            .unwrap();
+        let mut mdd = Barrier::new(Arc::new(BarrierStore::new(problem.nb_variables() + 1)), CutsetType::LastExactLayer);
+        mdd.compile(&input);
+        mdd
+    }
+
+    fn sorted(mut solution: Vec<Decision>) -> Vec<Decision> {
+        solution.sort_unstable_by_key(|d| d.var.id());
+        solution
+    }
+
+    #[test]
+    fn collapsing_the_forced_layers_keeps_the_exact_optimum() {
+        let problem = Chained::toy();
+        let layered = compile_chained(&problem, CompilationType::Exact, usize::MAX, 0);
+        let collapsed = compile_chained(&problem, CompilationType::Exact, usize::MAX, usize::MAX);
+
+        assert_eq!(Some(10), collapsed.best_value());
+        assert_eq!(layered.best_value(), collapsed.best_value());
+        assert_eq!(layered.best_solution().map(sorted), collapsed.best_solution().map(sorted));
+        assert_eq!(problem.nb_variables(), collapsed.best_solution().unwrap().len());
+        assert_eq!((0, 3), (layered.stats().collapsed_layers, collapsed.stats().collapsed_layers));
+        assert!(collapsed.nb_nodes() < layered.nb_nodes());
+        // the paths of the tied solutions go through the collapsed arcs as well
+        assert_eq!(
+            layered.best_solutions(usize::MAX).into_iter().map(sorted).collect::<Vec<_>>(),
+            collapsed.best_solutions(usize::MAX).into_iter().map(sorted).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn the_collapsed_layers_are_bounded_by_the_input() {
+        let problem = Chained { knapsack: Knapsack::toy(), length: 3 };
+        assert_eq!(2, compile_chained(&problem, CompilationType::Exact, usize::MAX, 1).stats().collapsed_layers);
+        assert_eq!(4, compile_chained(&problem, CompilationType::Exact, usize::MAX, 2).stats().collapsed_layers);
+
+        let exact = compile_chained(&problem, CompilationType::Exact, usize::MAX, 0);
+        assert_eq!(exact.best_value(), compile_chained(&problem, CompilationType::Exact, usize::MAX, 1).best_value());
+    }
+
+    #[test]
+    fn the_cutset_of_a_collapsed_dd_sits_at_the_depth_of_its_nodes() {
+        let problem = Chained::toy();
+        let mut mdd = compile_chained(&problem, CompilationType::Relaxed, 2, usize::MAX);
+        assert!(mdd.best_value().unwrap() >= 10);
+        // the first merged layer is four levels down the root, and the layer
+        // above it two levels down
+        assert_eq!(Some(2), mdd.stats().lel_depth);
+
+        let mut cutset = vec![];
+        mdd.drain_cutset(|n| cutset.push(n));
+        assert!(!cutset.is_empty());
+        assert!(cutset.iter().all(|n| n.depth() == 2 && n.state.depth == 2));
+    }
 }
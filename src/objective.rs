@@ -0,0 +1,278 @@
+//! This module lets the solvers minimize an objective even though the dds only
+//! ever maximize. A minimization is carried out as the maximization of the
+//! negated objective: `Negated` is the adapter through which the solvers see
+//! a problem (or a relaxation) written for a minimization. `MinimizeProblem`
+//! does the same for the code which only maximizes.
+//!
+//! # Solving a minimization
+//! A model which minimizes states its costs as they are, and should say so
+//! through `Problem::objective`. It is then solved with `Solver::minimize` (or
+//! one of its interruptible counterparts), which reports costs: this is what
+//! the tsptw and srflp examples do. The solvers wrap the problem in `Negated`
+//! on their own, there is no need to do it by hand. `MinimizeProblem` is only
+//! meant for the code which cannot minimize, like a dd compiled by hand: it
+//! reports the values of the maximization, which `MinimizeProblem::actual_value`
+//! turns back into costs.
+//!
+//! It also defines the secondary objectives, through which a solver picks one
+//! solution among those that are worth the same (primary) value.
+
+use std::ops::Deref;
+
+use crate::{AssignedVars, Decision, Problem, Relaxation, ValidationError, Variable};
+
+/// Whether a resolution maximizes or minimizes the objective of the problem
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Objective {
+    #[default]
+    Maximize,
+    Minimize,
+}
+
+/// The number of best paths of a dd which are scored by default, when a
+/// solver breaks the ties with a secondary objective
+pub const DEFAULT_TIE_LIMIT: usize = 64;
+
+/// A secondary objective, which breaks the ties among the solutions that are
+/// worth the same value (the objective of the problem comes first, much like
+/// in a lexicographic order).
+///
+/// # Note
+/// The solvers only compare the solutions they come across: the ties are
+/// enumerated among the best paths of each dd they compile (up to the tie
+/// limit of the solver), but a path which is dropped because another one
+/// reached the same state first in some other subproblem (on the fringe, by a
+/// barrier or by a push filter) is never scored. Ties which only show up in
+/// the merged nodes of a relaxed dd are not solutions, hence not scored.
+pub trait SecondaryObjective {
+    /// The score of a complete solution: the higher, the better. The
+    /// decisions of the path are not sorted by variable.
+    fn score(&self, path: &[Decision]) -> isize;
+}
+
+/// Negates a value, mapping each infinite bound to the other one
+pub(crate) fn negate(value: isize) -> isize {
+    match value {
+        isize::MIN => isize::MAX,
+        isize::MAX => isize::MIN,
+        value => -value,
+    }
+}
+
+/// Checks that a resolution in the sense of `objective` suits the problem. A
+/// problem which declares a minimization (see `Problem::objective`) states its
+/// estimates as lower bounds on its costs, which cannot bound a maximization.
+/// Any problem can be minimized though, as the estimate `isize::MAX` of the
+/// others is kept as is (it means that there is no estimate).
+pub(crate) fn check_objective<P: Problem + ?Sized>(problem: &P, objective: Objective) {
+    assert!(
+        objective == Objective::Minimize || problem.objective() == Objective::Maximize,
+        "a problem which declares a minimization cannot be maximized"
+    );
+}
+
+/// The maximization view of a problem or a relaxation written for a
+/// minimization. The initial value, the transition costs, the estimates and
+/// the costs of the relaxed arcs are negated; everything else is forwarded
+/// as is.
+///
+/// # Note
+/// The estimate of a minimization must be a lower bound on the cost of the
+/// remaining decisions: `isize::MIN` means that there is no estimate at all,
+/// and `isize::MAX` that the state is infeasible. The problems which do not
+/// declare that they minimize (see `Problem::objective`) may still return the
+/// default estimate of a maximization, `isize::MAX`, which then keeps meaning
+/// that there is no estimate.
+#[derive(Debug)]
+pub struct Negated<'a, T>(pub &'a T);
+
+impl<P: Problem> Problem for Negated<'_, P> {
+    type State = P::State;
+
+    fn nb_variables(&self) -> usize {
+        self.0.nb_variables()
+    }
+    fn initial_state(&self) -> Self::State {
+        self.0.initial_state()
+    }
+    fn initial_value(&self) -> isize {
+        negate(self.0.initial_value())
+    }
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        self.0.next_variable(next_layer)
+    }
+    fn next_variable_with_context(&self, assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &Self::State>)
+        -> Option<Variable> {
+        self.0.next_variable_with_context(assigned, next_layer)
+    }
+    fn domain_size_hint(&self, var: Variable, state: &Self::State) -> Option<usize> {
+        self.0.domain_size_hint(var, state)
+    }
+    fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, f: F)
+    where
+        F: FnMut(Decision),
+    {
+        self.0.for_each_in_domain(var, state, f)
+    }
+    fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        self.0.transition(state, decision)
+    }
+    fn transition_cost(&self, state: &Self::State, decision: Decision) -> isize {
+        negate(self.0.transition_cost(state, decision))
+    }
+    fn transition_with_cost(&self, state: &Self::State, decision: Decision) -> (Self::State, isize) {
+        let (next, cost) = self.0.transition_with_cost(state, decision);
+        (next, negate(cost))
+    }
+    fn terminal_value(&self, state: &Self::State) -> isize {
+        negate(self.0.terminal_value(state))
+    }
+    fn impacted_by(&self, var: Variable, state: &Self::State) -> bool {
+        self.0.impacted_by(var, state)
+    }
+    fn objective(&self) -> Objective {
+        Objective::Maximize
+    }
+    fn estimate(&self, state: &Self::State) -> isize {
+        match self.0.estimate(state) {
+            isize::MAX if self.0.objective() == Objective::Maximize => isize::MAX,
+            estimate => negate(estimate),
+        }
+    }
+    fn estimate_from_path(&self, state: &Self::State, path: &[Decision]) -> isize {
+        match self.0.estimate_from_path(state, path) {
+            isize::MAX if self.0.objective() == Objective::Maximize => isize::MAX,
+            estimate => negate(estimate),
+        }
+    }
+    fn estimates_from_path(&self) -> bool {
+        self.0.estimates_from_path()
+    }
+    fn validate_solution(&self, solution: &[Decision]) -> Result<isize, ValidationError<Self::State>> {
+        self.0.validate_solution(solution).map(negate)
+    }
+}
+
+impl<R: Relaxation> Relaxation for Negated<'_, R> {
+    type State = R::State;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        self.0.merge(states)
+    }
+    fn relax(&self, source: &Self::State, dest: &Self::State, new: &Self::State, decision: Decision, cost: isize) -> isize {
+        negate(self.0.relax(source, dest, new, decision, negate(cost)))
+    }
+    fn check_merge(&self, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        self.0.check_merge(merged, originals)
+    }
+    fn merge_at_depth(&self, depth: usize, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        self.0.merge_at_depth(depth, states)
+    }
+    fn relax_at_depth(&self, depth: usize, source: &Self::State, dest: &Self::State, new: &Self::State, decision: Decision, cost: isize)
+        -> isize {
+        negate(self.0.relax_at_depth(depth, source, dest, new, decision, negate(cost)))
+    }
+    fn check_merge_at_depth(&self, depth: usize, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        self.0.check_merge_at_depth(depth, merged, originals)
+    }
+}
+
+/// A minimization problem which owns its model and is meant to be maximized:
+/// this is `Negated`, for the code which only knows how to maximize (a dd
+/// compiled by hand, or `Solver::maximize`). The values it reports are those
+/// of the maximization, which `MinimizeProblem::actual_value` turns back into
+/// costs. The inner problem is reachable through `Deref`.
+///
+/// This is an adapter, not the way to solve a minimization: a solver is given
+/// the model itself and minimizes it (see the module documentation).
+///
+/// # Note
+/// The wrapped problem must not be minimized by the solver on top of this
+/// (its objective is `Objective::Maximize`): the costs would be negated twice.
+/// The relaxation sees the negated costs of the arcs it relaxes.
+#[derive(Debug, Clone)]
+pub struct MinimizeProblem<P>(P);
+
+impl<P: Problem> MinimizeProblem<P> {
+    pub fn new(problem: P) -> Self {
+        Self(problem)
+    }
+    /// Returns the wrapped problem
+    pub fn into_inner(self) -> P {
+        self.0
+    }
+    /// The cost of the minimization which is worth the given value of the
+    /// maximization (as reported by the solver)
+    pub fn actual_value(solver_value: isize) -> isize {
+        negate(solver_value)
+    }
+}
+
+impl<P> Deref for MinimizeProblem<P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P: Problem> Problem for MinimizeProblem<P> {
+    type State = P::State;
+
+    fn nb_variables(&self) -> usize {
+        self.0.nb_variables()
+    }
+    fn initial_state(&self) -> Self::State {
+        self.0.initial_state()
+    }
+    fn initial_value(&self) -> isize {
+        Negated(&self.0).initial_value()
+    }
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        self.0.next_variable(next_layer)
+    }
+    fn next_variable_with_context(&self, assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &Self::State>)
+        -> Option<Variable> {
+        self.0.next_variable_with_context(assigned, next_layer)
+    }
+    fn domain_size_hint(&self, var: Variable, state: &Self::State) -> Option<usize> {
+        self.0.domain_size_hint(var, state)
+    }
+    fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, f: F)
+    where
+        F: FnMut(Decision),
+    {
+        self.0.for_each_in_domain(var, state, f)
+    }
+    fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        self.0.transition(state, decision)
+    }
+    fn transition_cost(&self, state: &Self::State, decision: Decision) -> isize {
+        Negated(&self.0).transition_cost(state, decision)
+    }
+    fn transition_with_cost(&self, state: &Self::State, decision: Decision) -> (Self::State, isize) {
+        Negated(&self.0).transition_with_cost(state, decision)
+    }
+    fn terminal_value(&self, state: &Self::State) -> isize {
+        Negated(&self.0).terminal_value(state)
+    }
+    fn impacted_by(&self, var: Variable, state: &Self::State) -> bool {
+        self.0.impacted_by(var, state)
+    }
+    fn objective(&self) -> Objective {
+        Objective::Maximize
+    }
+    fn estimate(&self, state: &Self::State) -> isize {
+        Negated(&self.0).estimate(state)
+    }
+    fn estimate_from_path(&self, state: &Self::State, path: &[Decision]) -> isize {
+        Negated(&self.0).estimate_from_path(state, path)
+    }
+    fn estimates_from_path(&self) -> bool {
+        self.0.estimates_from_path()
+    }
+    fn validate_solution(&self, solution: &[Decision]) -> Result<isize, ValidationError<Self::State>> {
+        Negated(&self.0).validate_solution(solution)
+    }
+}
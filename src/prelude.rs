@@ -1,6 +1,19 @@
-use std::{cmp::Ordering, fmt::Display, str::FromStr, sync::Arc};
+use std::{
+    cmp::{Ordering, Reverse},
+    fmt::Display,
+    hash::Hash,
+    str::FromStr,
+    sync::{atomic::{AtomicIsize, Ordering as AtomicOrdering}, Arc},
+    time::Duration,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use bitset_fixed::BitSet;
+use rustc_hash::FxHashMap;
+
+use crate::{replay_solution, BitSetIter, CancellationToken, DynCompilationInput, Objective, ValidationError, Violation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Variable(pub usize);
 
 impl Variable {
@@ -9,12 +22,56 @@ impl Variable {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Decision {
     pub var: Variable,
     pub value: isize,
 }
 
+/// The set of variables which have already been assigned on the paths leading
+/// to some layer of a dd
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignedVars(BitSet);
+
+impl AssignedVars {
+    /// Creates an empty set for a problem having the given number of variables
+    pub fn new(nb_variables: usize) -> Self {
+        Self(BitSet::new(nb_variables))
+    }
+    /// Creates the set of the variables assigned by the given path
+    pub fn from_path(nb_variables: usize, path: &[Decision]) -> Self {
+        let mut assigned = Self::new(nb_variables);
+        path.iter().for_each(|d| assigned.insert(d.var));
+        assigned
+    }
+    /// Creates the set of the variables assigned by the path of the given
+    /// subproblem, including those of its prefix
+    pub fn from_subproblem<T>(nb_variables: usize, sub: &SubProblem<T>) -> Self {
+        let mut assigned = Self::from_path(nb_variables, &sub.path);
+        for segment in sub.prefix.iter().flat_map(|prefix| prefix.segments()) {
+            segment.decisions.iter().for_each(|d| assigned.insert(d.var));
+        }
+        assigned
+    }
+    pub fn insert(&mut self, var: Variable) {
+        self.0.set(var.id(), true)
+    }
+    pub fn contains(&self, var: Variable) -> bool {
+        self.0[var.id()]
+    }
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Iterates over the assigned variables by increasing id
+    pub fn iter(&self) -> impl Iterator<Item = Variable> + '_ {
+        BitSetIter::new(&self.0).map(Variable)
+    }
+}
+
 pub trait Problem {
     type State;
 
@@ -24,21 +81,96 @@ pub trait Problem {
 
     fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>)
         -> Option<Variable>;
+    /// This is what the dds call to know on which variable they must branch
+    /// next. On top of the next layer, it gets the variables which are already
+    /// assigned on the paths leading to that layer (so that the models need not
+    /// encode them in their states). By default, that context is ignored.
+    fn next_variable_with_context(&self, _assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &Self::State>)
+        -> Option<Variable> {
+        self.next_variable(next_layer)
+    }
 
     fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, f: F)
     where
         F: FnMut(Decision);
 
+    /// Returns an upper bound on the number of decisions `for_each_in_domain`
+    /// yields for the given variable and state, when it is cheap to compute.
+    /// The dds sum these hints over a layer to reserve the memory of the next
+    /// one before branching; without a hint, they guess it from the growth of
+    /// the previous layer.
+    fn domain_size_hint(&self, _var: Variable, _state: &Self::State) -> Option<usize> {
+        None
+    }
+
     fn transition(&self, state: &Self::State, decision: Decision) -> Self::State;
     fn transition_cost(&self, state: &Self::State, decision: Decision) -> isize;
+    /// Returns both the `transition` and the `transition_cost` of the given
+    /// decision, which is how the dds branch. A model whose state and cost
+    /// come out of the same loop may compute them in a single pass.
+    fn transition_with_cost(&self, state: &Self::State, decision: Decision) -> (Self::State, isize) {
+        (self.transition(state, decision), self.transition_cost(state, decision))
+    }
 
     // only useful in order to introduce long arcs (pooled mdd)
     fn impacted_by(&self, _var: Variable, _state: &Self::State) -> bool {
         true
     }
-    // rub
+    /// Tells whether the values of the problem are to be maximized (the
+    /// default) or minimized. A minimization states its costs as they are:
+    /// `Solver::minimize` negates them for the dds (see the `objective` module
+    /// for the other ways to minimize). The examples use this to pick the
+    /// resolution they run (see `xputils::solve`).
+    fn objective(&self) -> Objective {
+        Objective::Maximize
+    }
+    /// Returns what a complete solution which ends in the given state adds to
+    /// its value, on top of the costs of its decisions (0 by default). This
+    /// is where a model states its end-of-horizon corrections, which only
+    /// depend on the final state: the dds add it to the nodes of their last
+    /// layer, and `replay_solution` to the value of the solutions it replays.
+    /// A relaxed dd adds it to its merged nodes too: the terminal value of a
+    /// merged state must therefore bound those of the states it merges (be at
+    /// least as good as each of them), lest the relaxed bound be invalid.
+    fn terminal_value(&self, _state: &Self::State) -> isize {
+        0
+    }
+    /// Bounds the value of the remaining decisions from the given state: an
+    /// upper bound on what they can add to a maximization, or a lower bound
+    /// on what they cost to a minimization, the terminal value of the state
+    /// they end in included (see `terminal_value`). An infeasible state is
+    /// bounded by `isize::MIN` (resp. `isize::MAX`). The default tells
+    /// nothing: it is `isize::MAX` (resp. `isize::MIN`).
     fn estimate(&self, _state: &Self::State) -> isize {
-        isize::MAX
+        match self.objective() {
+            Objective::Maximize => isize::MAX,
+            Objective::Minimize => isize::MIN,
+        }
+    }
+    /// Estimates the state like `estimate`, knowing the decisions of the best
+    /// path which leads to it from the root of the problem (in the order in
+    /// which they were taken). This lets a model tighten its estimate with
+    /// what its states do not keep track of. The estimate must bound the
+    /// state whatever the path though, since a relaxed state is reached by
+    /// other paths than its best one. By default, this is `estimate`.
+    fn estimate_from_path(&self, state: &Self::State, _path: &[Decision]) -> isize {
+        self.estimate(state)
+    }
+    /// Tells whether the dds must call `estimate_from_path` rather than
+    /// `estimate`. As they have to materialize the best path of each node to
+    /// do so, this is only worth it when `estimate_from_path` makes use of the
+    /// path. By default, it does not.
+    fn estimates_from_path(&self) -> bool {
+        false
+    }
+
+    /// Checks that the given solution (as returned by `Solver::best_solution`)
+    /// is feasible and returns its value. By default, the decisions are
+    /// replayed from the initial state through the transitions of the problem
+    /// (see `replay_solution`); the error tells at which step the replay
+    /// failed, on which decision and in which state.
+    fn validate_solution(&self, solution: &[Decision]) -> Result<isize, ValidationError<Self::State>> {
+        replay_solution(self, solution)
     }
 }
 
@@ -55,6 +187,42 @@ pub trait Relaxation {
         decision: Decision,
         cost: isize,
     ) -> isize;
+
+    /// Verifies that `merged` is a valid relaxation of all the `originals`
+    /// states it was obtained from. This is only called by the dds when the
+    /// merge check is enabled (see `CompilationInput::check_merge`), and it is
+    /// meant to catch buggy merge operators which would otherwise silently
+    /// yield invalid bounds. The error message should describe the offending
+    /// states.
+    fn check_merge(&self, _merged: &Self::State, _originals: &[&Self::State]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Merges the states of the layer at the given depth (the number of
+    /// variables assigned to reach these states). This is the method the dds
+    /// call, and it lets a relaxation adapt its merge to the depth of the
+    /// layer (see `LayeredRelaxation`). By default, it ignores the depth.
+    fn merge_at_depth(&self, _depth: usize, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        self.merge(states)
+    }
+    /// Relaxes the cost of an arc towards a state merged at the given depth.
+    /// By default, it ignores the depth.
+    fn relax_at_depth(
+        &self,
+        _depth: usize,
+        source: &Self::State,
+        dest: &Self::State,
+        new: &Self::State,
+        decision: Decision,
+        cost: isize,
+    ) -> isize {
+        self.relax(source, dest, new, decision, cost)
+    }
+    /// Verifies a merge performed at the given depth. By default, it ignores
+    /// the depth.
+    fn check_merge_at_depth(&self, _depth: usize, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        self.check_merge(merged, originals)
+    }
 }
 
 pub trait StateRanking {
@@ -64,13 +232,88 @@ pub trait StateRanking {
     fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering;
 }
 
+/// A dominance rule among the states of a layer: the states which share the
+/// same key can be compared with each other, and those which are dominated
+/// are dropped before the layer is expanded (see `CompilationInput::dominance`).
+/// The values are those of the maximization compiled by the dds (negated for
+/// a minimization).
+pub trait Dominance {
+    type State;
+    type Key: Eq + Hash;
+
+    /// Returns the key of the states which `state` can be compared with
+    fn dominance_key(&self, state: &Self::State) -> Self::Key;
+    /// Returns true iff the state `a` reached with `a_value` is dominated by
+    /// the state `b` reached with `b_value` (both have the same key): no
+    /// completion of `a` may be worth more than the best completion of `b`.
+    /// A dominated state must remain so when it is reached with a lower value.
+    fn is_dominated(&self, a_value: isize, a: &Self::State, b_value: isize, b: &Self::State) -> bool;
+}
+
+/// The object safe view of a `Dominance` rule, which hides the type of its
+/// keys: this is what the dds are given.
+pub trait DominanceChecker<T> {
+    /// Tells which of the given `(value, state)` pairs are dominated by
+    /// another one of them. Among the states which dominate each other, the
+    /// one with the greatest value (the first one in case of ties) is kept.
+    fn dominated(&self, layer: &[(isize, &T)]) -> Vec<bool>;
+}
+impl<D: Dominance> DominanceChecker<D::State> for D {
+    fn dominated(&self, layer: &[(isize, &D::State)]) -> Vec<bool> {
+        let mut groups = FxHashMap::<D::Key, Vec<usize>>::default();
+        for (i, (_, state)) in layer.iter().enumerate() {
+            groups.entry(self.dominance_key(state)).or_default().push(i);
+        }
+
+        let mut dominated = vec![false; layer.len()];
+        let mut kept: Vec<usize> = vec![];
+        for mut group in groups.into_values() {
+            // the best values come first: they are the most likely to dominate
+            group.sort_by_key(|i| Reverse(layer[*i].0));
+            kept.clear();
+            for i in group {
+                let (a_value, a) = layer[i];
+                if kept.iter().any(|k| self.is_dominated(a_value, a, layer[*k].0, layer[*k].1)) {
+                    dominated[i] = true;
+                } else {
+                    kept.push(i);
+                }
+            }
+        }
+        dominated
+    }
+}
+
 pub trait WidthHeuristic<State> {
     // Estimates a good max width for the given state
     fn max_width(&self, state: &State) -> usize;
+    /// Estimates a good max width for the dds of the given subproblem, whose
+    /// depth in the b&b tree (`SubProblem::depth`), value and upper bound are
+    /// known as well. Its values are those of the maximization run by the
+    /// solver (negated for a minimization). By default, only the state is
+    /// looked at (see `max_width`).
+    fn max_width_of(&self, sub: &SubProblem<State>) -> usize {
+        self.max_width(sub.state.as_ref())
+    }
+    /// Tells the heuristic whether the last restricted dd of a node (whose
+    /// width it gave) ran out of nodes (see `DecisionDiagram::ran_dry`). By
+    /// default, this is ignored.
+    fn on_restriction(&self, _ran_dry: bool) {}
 }
 
 pub trait Solver {
+    /// Maximizes the objective of the problem.
+    ///
+    /// # Panics
+    /// When the problem declares a minimization (see `Problem::objective`),
+    /// since its estimates cannot bound a maximization.
     fn maximize(&mut self);
+    /// Minimizes the objective of the problem, without the problem having to
+    /// negate its costs: the initial value, the transition costs, the
+    /// estimates and the costs of the relaxed arcs are negated by the solver
+    /// (see `Negated`). The best value and bounds are then reported in terms
+    /// of the minimization.
+    fn minimize(&mut self);
     fn best_value(&self) -> Option<isize>;
     fn best_solution(&self) -> Option<Vec<Decision>>;
 }
@@ -79,20 +322,145 @@ pub trait Solver {
 pub enum ResolutionStatus {
     Proved,
     Interrupted,
+    /// The resolution completed, but its best solution is not worth the value
+    /// found by the solver when it is replayed (see `VerificationError`)
+    VerificationFailed,
+    /// Only a dual bound of the problem was computed (see `RelaxedBoundSolver`),
+    /// or the resolution completed after a bounded fringe dropped some nodes
+    /// (see `BoundedFrontier`): the best upper bound covers these nodes, but
+    /// the best solution is not proved optimal.
+    Bounded,
+    /// The estimate of the root proves that the problem has no solution: the
+    /// resolution stopped before exploring anything. There is no best value,
+    /// and both bounds are `isize::MIN` (resp. `isize::MAX` for a minimization).
+    Infeasible,
 }
 impl Display for ResolutionStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ResolutionStatus::Proved => write!(f, "Proved"),
             ResolutionStatus::Interrupted => write!(f, "Timeout"),
+            ResolutionStatus::VerificationFailed => write!(f, "Unverified"),
+            ResolutionStatus::Bounded => write!(f, "Bound"),
+            ResolutionStatus::Infeasible => write!(f, "Infeasible"),
         }
     }
 }
 
+/// What a cutoff knows about the progress of a resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CutoffStats {
+    /// The time elapsed since the resolution started
+    pub elapsed: Duration,
+    /// The number of nodes of the branch-and-bound which were explored
+    pub explored: usize,
+    /// The number of dd nodes which were expanded
+    pub explored_dd: usize,
+}
+
+/// A condition which stops a resolution before the optimum is proved. The
+/// solvers check it each time a worker asks for a workload; a resolution
+/// which is cut off returns `ResolutionStatus::Interrupted`.
+pub trait Cutoff {
+    fn must_stop(&self, stats: &CutoffStats) -> bool;
+}
+/// Stops the resolution once it has run for the given time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeCutoff(pub Duration);
+impl Cutoff for TimeCutoff {
+    fn must_stop(&self, stats: &CutoffStats) -> bool {
+        stats.elapsed >= self.0
+    }
+}
+/// Stops the resolution once it has explored the given number of nodes of
+/// the branch-and-bound. The nodes which are being explored at that time are
+/// completed though: a parallel resolution may explore a few more of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeCutoff(pub usize);
+impl Cutoff for NodeCutoff {
+    fn must_stop(&self, stats: &CutoffStats) -> bool {
+        stats.explored >= self.0
+    }
+}
+/// Stops the resolution as soon as either cutoff does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EitherCutoff<A, B>(pub A, pub B);
+impl<A: Cutoff, B: Cutoff> Cutoff for EitherCutoff<A, B> {
+    fn must_stop(&self, stats: &CutoffStats) -> bool {
+        self.0.must_stop(stats) || self.1.must_stop(stats)
+    }
+}
+/// Stops the resolution as soon as the interrupt condition is satisfied
+#[derive(Debug, Clone, Copy)]
+pub struct InterruptCutoff<I>(pub I);
+impl<I: Fn() -> bool> Cutoff for InterruptCutoff<I> {
+    fn must_stop(&self, _: &CutoffStats) -> bool {
+        (self.0)()
+    }
+}
+
 pub trait InterruptibleSolver: Solver {
     fn maximize_with_interrupt<I>(&mut self, interrupt: I) -> ResolutionStatus
     where
-        I: Fn() -> bool + Send + Sync + 'static;
+        I: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.maximize_with_cutoff(InterruptCutoff(interrupt))
+    }
+    /// The counterpart of `Solver::minimize` which stops as soon as the
+    /// `interrupt` condition is satisfied
+    fn minimize_with_interrupt<I>(&mut self, interrupt: I) -> ResolutionStatus
+    where
+        I: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.minimize_with_cutoff(InterruptCutoff(interrupt))
+    }
+    /// The counterpart of `Solver::maximize` which stops as soon as the
+    /// `cutoff` says so
+    fn maximize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static;
+    /// The counterpart of `Solver::minimize` which stops as soon as the
+    /// `cutoff` says so
+    fn minimize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static;
+    /// The counterpart of `maximize_with_interrupt` which calls
+    /// `on_improvement` with the value and the decisions of each better
+    /// solution, as soon as it is found
+    fn maximize_with_progress<I, F>(&mut self, interrupt: I, on_improvement: F) -> ResolutionStatus
+    where
+        I: Fn() -> bool + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.maximize_with_cutoff_and_progress(InterruptCutoff(interrupt), on_improvement)
+    }
+    /// The counterpart of `minimize_with_interrupt` which calls
+    /// `on_improvement` with the cost and the decisions of each better
+    /// solution, as soon as it is found
+    fn minimize_with_progress<I, F>(&mut self, interrupt: I, on_improvement: F) -> ResolutionStatus
+    where
+        I: Fn() -> bool + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.minimize_with_cutoff_and_progress(InterruptCutoff(interrupt), on_improvement)
+    }
+    /// The counterpart of `maximize_with_cutoff` which calls `on_improvement`
+    /// with the value and the decisions of each better solution, as soon as it
+    /// is found. The calls are made one at a time, by increasing value, and
+    /// outside of the critical section of the solver (the callback may call
+    /// the solver back): a solution which was superseded before it could be
+    /// reported is skipped.
+    fn maximize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static;
+    /// The counterpart of `minimize_with_cutoff` which reports its progress
+    /// like `maximize_with_cutoff_and_progress`: `on_improvement` is given the
+    /// cost of each better solution, hence by decreasing value.
+    fn minimize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static;
     //
     fn best_value_so_far(&self) -> Option<isize>;
     fn best_solution_so_far(&self) -> Option<Vec<Decision>>;
@@ -101,17 +469,46 @@ pub trait InterruptibleSolver: Solver {
     fn best_lower_bound(&self) -> isize;
 }
 
+/// The callback through which a solver reports each better solution (see
+/// `InterruptibleSolver::maximize_with_cutoff_and_progress`)
+pub(crate) type OnImprovement = Box<dyn Fn(isize, &[Decision]) + Send + Sync>;
+
+/// What a frontier did with a node that was pushed onto it
+#[derive(Debug, Clone)]
+pub enum PushOutcome<T> {
+    /// The node was added to the frontier
+    Inserted,
+    /// The frontier already held a node with the same state. Only one of them
+    /// remains on the frontier: this is the one which was dropped.
+    Merged(SubProblem<T>),
+    /// The frontier was full: this is the node it dropped to make room (which
+    /// may be the one that was pushed, see `BoundedFrontier`)
+    Evicted(SubProblem<T>),
+}
+
 pub trait Frontier {
     type State;
 
-    /// This is how you push a node onto the frontier.
-    fn push(&mut self, node: SubProblem<Self::State>);
+    /// This is how you push a node onto the frontier. It tells whether the
+    /// frontier holds one more node or if the node was merged with an
+    /// existing one (or evicted some node).
+    fn push(&mut self, node: SubProblem<Self::State>) -> PushOutcome<Self::State>;
     /// This method yields the most promising node from the frontier.
     /// # Note:
     /// The solvers rely on the assumption that a frontier will pop nodes in
     /// descending upper bound order. Hence, it is a requirement for any fringe
-    /// implementation to enforce that requirement.
+    /// implementation to enforce that requirement (unless it tells otherwise
+    /// with `is_ub_ordered`).
     fn pop(&mut self) -> Option<SubProblem<Self::State>>;
+    /// Returns the node which would be popped next, without removing it from
+    /// the frontier (e.g. to check whether its upper bound is already close
+    /// enough to the best solution).
+    ///
+    /// # Panics
+    /// By default, a frontier cannot be peeked at.
+    fn peek(&self) -> Option<&SubProblem<Self::State>> {
+        panic!("peek not supported by this frontier")
+    }
     /// This method clears the frontier: it removes all nodes from the queue.
     fn clear(&mut self);
     /// Yields the length of the queue.
@@ -120,6 +517,66 @@ pub trait Frontier {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+    /// Returns true iff the frontier pops its nodes in descending upper bound
+    /// order. This is what lets the solvers discard the whole frontier as soon
+    /// as they pop a node which cannot improve the best known solution.
+    /// Otherwise, the nodes are pruned one at a time.
+    fn is_ub_ordered(&self) -> bool {
+        true
+    }
+    /// Only keeps the nodes for which `f` returns true. This lets the solvers
+    /// shed the nodes which can no longer improve the best solution without
+    /// waiting for them to be popped. By default, the nodes are popped and
+    /// the ones which are kept are pushed back.
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<Self::State>) -> bool) {
+        let mut kept = vec![];
+        while let Some(node) = self.pop() {
+            if f(&node) {
+                kept.push(node);
+            }
+        }
+        for node in kept {
+            self.push(node);
+        }
+    }
+    /// Calls `f` on (at most) `count` nodes spread over the frontier, which
+    /// lets the solvers estimate the fraction of the frontier that could be
+    /// shed. By default, no node is sampled.
+    fn sample(&self, _count: usize, _f: &mut dyn FnMut(&SubProblem<Self::State>)) {}
+}
+
+/// A frontier borrowed from the caller can be used wherever a frontier is
+/// expected.
+impl<F: Frontier + ?Sized> Frontier for &mut F {
+    type State = F::State;
+
+    fn push(&mut self, node: SubProblem<Self::State>) -> PushOutcome<Self::State> {
+        (**self).push(node)
+    }
+    fn pop(&mut self) -> Option<SubProblem<Self::State>> {
+        (**self).pop()
+    }
+    fn peek(&self) -> Option<&SubProblem<Self::State>> {
+        (**self).peek()
+    }
+    fn clear(&mut self) {
+        (**self).clear()
+    }
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
+    }
+    fn is_ub_ordered(&self) -> bool {
+        (**self).is_ub_ordered()
+    }
+    fn retain(&mut self, f: &mut dyn FnMut(&SubProblem<Self::State>) -> bool) {
+        (**self).retain(f)
+    }
+    fn sample(&self, count: usize, f: &mut dyn FnMut(&SubProblem<Self::State>)) {
+        (**self).sample(count, f)
+    }
 }
 
 /* -------------------------------------------------------------------------- */
@@ -136,6 +593,9 @@ pub enum CompilationType {
 pub enum CutsetType {
     LastExactLayer,
     Frontier,
+    /// Picks, for each compilation, the smaller of the last exact layer and
+    /// the frontier cutsets. The last exact layer wins the ties.
+    Adaptive,
 }
 impl FromStr for CutsetType {
     type Err = &'static str;
@@ -144,7 +604,8 @@ impl FromStr for CutsetType {
         match s {
             "lel" => Ok(Self::LastExactLayer),
             "frontier" => Ok(Self::Frontier),
-            _ => Err("The only supported frontier types are 'parallel' and 'barrier'"),
+            "adaptive" => Ok(Self::Adaptive),
+            _ => Err("The only supported cutset types are 'lel', 'frontier' and 'adaptive'"),
         }
     }
 }
@@ -153,18 +614,175 @@ impl Display for CutsetType {
         match self {
             Self::LastExactLayer => write!(f, "lel"),
             Self::Frontier => write!(f, "frontier"),
+            Self::Adaptive => write!(f, "adaptive"),
+        }
+    }
+}
+impl CutsetType {
+    /// Returns the smallest `min_relax_depth_offset` for which a relaxed dd
+    /// compiled with this cutset type is guaranteed to make progress.
+    ///
+    /// # Note:
+    /// All cutset types need the children of the root to remain exact. When
+    /// the first layer below the root gets merged, the last exact layer is the
+    /// root layer itself. Likewise, the root is then the only exact node having
+    /// an inexact child, hence the sole member of the frontier cutset. Either
+    /// way, the solver would re-enqueue the very subproblem it just processed.
+    pub fn min_relax_depth_offset(self) -> usize {
+        match self {
+            Self::LastExactLayer => 1,
+            Self::Frontier => 1,
+            Self::Adaptive => 1,
         }
     }
 }
 
+/// How a relaxed dd merges the nodes of a layer which is wider than the
+/// maximum width
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// All the nodes but the `max_width - 1` most valuable ones are merged
+    /// into a single relaxed node.
+    #[default]
+    SingleNode,
+    /// All the nodes but the `max_width - k` most valuable ones are sorted
+    /// along the `StateRanking` and split into `k` contiguous chunks of
+    /// (nearly) equal sizes, each of which is merged into its own relaxed
+    /// node. As each merged state only gathers states which the ranking deems
+    /// close to each other, the bound is often tighter than the one of a
+    /// single merged node, although fewer exact nodes are kept. `k` is capped
+    /// by the maximum width.
+    Chunked(usize),
+}
+impl MergePolicy {
+    /// The number of relaxed nodes in which the overflow of a layer is merged
+    /// when the maximum width is `max_width`
+    pub(crate) fn nb_chunks(self, max_width: usize) -> usize {
+        match self {
+            Self::SingleNode => 1,
+            Self::Chunked(chunks) => chunks.clamp(1, max_width),
+        }
+    }
+}
+impl FromStr for MergePolicy {
+    type Err = &'static str;
+
+    /// Parses `single`, `chunked` (two chunks) or `chunked:<k>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = "A merge policy is either 'single', 'chunked' or 'chunked:<k>' (e.g. 'chunked:4')";
+        match s {
+            "single" => Ok(Self::SingleNode),
+            "chunked" => Ok(Self::Chunked(2)),
+            _ => match s.strip_prefix("chunked:") {
+                Some(chunks) => chunks.parse().ok().filter(|&k| k > 0).map(Self::Chunked).ok_or(invalid),
+                None => Err(invalid),
+            },
+        }
+    }
+}
+impl Display for MergePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SingleNode => write!(f, "single"),
+            Self::Chunked(chunks) => write!(f, "chunked:{}", chunks),
+        }
+    }
+}
+
+/// A subproblem of the branch-and-bound.
+///
+/// # Note
+/// With the `serde` feature, the state is serialized as the value it points
+/// to, and the prefix as the complete path it holds: a deserialized
+/// subproblem does not share its prefix with any other one.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubProblem<T> {
     pub state: Arc<T>,
     pub value: isize,
     pub path: Vec<Decision>,
     pub ub: isize,
+    /// The identifier of this subproblem in the branch-and-bound tree. It is
+    /// given by the solver when the node is pushed onto the fringe (the root is 0)
+    pub id: usize,
+    /// The identifier of the subproblem whose cutset contained this one
+    pub parent: Option<usize>,
+    /// When set, the decisions of `path` are made after those of the prefix.
+    /// This lets the nodes waiting on the fringe share the path leading to
+    /// their parent.
+    pub prefix: Option<Arc<PathSegment>>,
+}
+impl<T> SubProblem<T> {
+    /// Returns the number of decisions leading to this subproblem
+    pub fn depth(&self) -> usize {
+        self.prefix.as_ref().map_or(0, |p| p.len()) + self.path.len()
+    }
 }
 
+/// A piece of some path which is shared by several subproblems: the
+/// decisions of the segment are made after those of its parent segment.
+#[derive(Debug)]
+pub struct PathSegment {
+    parent: Option<Arc<PathSegment>>,
+    decisions: Vec<Decision>,
+    /// The total number of decisions (including those of the parent segment)
+    len: usize,
+}
+#[cfg(feature = "serde")]
+impl serde::Serialize for PathSegment {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.to_vec())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PathSegment {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(|decisions| Self::new(None, decisions))
+    }
+}
+impl PathSegment {
+    pub fn new(parent: Option<Arc<PathSegment>>, decisions: Vec<Decision>) -> Self {
+        let len = parent.as_ref().map_or(0, |p| p.len) + decisions.len();
+        Self { parent, decisions, len }
+    }
+    /// Returns the number of decisions of the complete path
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Returns the complete path ending with this segment
+    pub fn to_vec(&self) -> Vec<Decision> {
+        let mut path = Vec::with_capacity(self.len);
+        self.extend_into(&mut path);
+        path
+    }
+    /// Appends the complete path ending with this segment to the given path.
+    /// This allocates nothing when the path has enough spare capacity.
+    pub fn extend_into(&self, path: &mut Vec<Decision>) {
+        let start = path.len();
+        path.resize(start + self.len, Decision { var: Variable(0), value: 0 });
+        // the segments are walked from the last one to the first
+        let mut end = start + self.len;
+        for segment in self.segments() {
+            let begin = end - segment.decisions.len();
+            path[begin..end].copy_from_slice(&segment.decisions);
+            end = begin;
+        }
+    }
+    /// Returns this segment, followed by its parent segments
+    fn segments(&self) -> impl Iterator<Item = &PathSegment> {
+        std::iter::successors(Some(self), |segment| segment.parent.as_deref())
+    }
+}
+
+/// All that a dd needs to know to compile a residual subproblem.
+///
+/// The fields of this struct are still public, but they are meant to become
+/// private: new fields are added as the dds gain features, which breaks the
+/// code building its instances by hand. The `CompilationInputBuilder` is the
+/// stable way to create an input.
 pub struct CompilationInput<'a, P, R, O>
 where
     P: Problem,
@@ -176,8 +794,355 @@ where
     pub problem: &'a P,
     pub relaxation: &'a R,
     pub ranking: &'a O,
+    /// The subproblem to compile. When it has a prefix, the nodes of the
+    /// cutset share that prefix: their paths only hold the decisions made
+    /// after it.
     pub residual: SubProblem<P::State>,
+    /// The variables assigned by the path of the residual subproblem
+    pub assigned: AssignedVars,
     pub best_lb: isize,
+    /// A relaxed dd only merges the layers which are strictly deeper than
+    /// `root depth + min_relax_depth_offset` (1 by default: the first layer
+    /// below the root is never merged).
+    pub min_relax_depth_offset: usize,
+    /// When set, a relaxed dd restricts the layers which are too shallow to be
+    /// merged (see `min_relax_depth_offset`) rather than letting them outgrow
+    /// `max_width`. The exact nodes it drops are drained with the cutset, with
+    /// the upper bound given by their estimate, so that the bound of the dd
+    /// remains valid.
+    pub restrict_first_layers: bool,
+    /// The number of layers a dd may collapse below each layer it expands,
+    /// as long as every node of the next layer has a single decision (0, the
+    /// default, collapses nothing). The forced decisions are then carried by
+    /// the arcs which reach the nodes, along with their costs: the states
+    /// they go through get no node of their own, hence no threshold in the
+    /// barrier either.
+    pub max_forced_layers: usize,
+    /// When set, the exact nodes of each layer which are dominated by another
+    /// exact node of that layer are dropped before it is expanded (see
+    /// `Dominance`). Only the barrier dds apply this rule.
+    pub dominance: Option<&'a (dyn DominanceChecker<P::State> + Sync)>,
+    /// When set, a restricted dd keeps the layers it develops before its
+    /// width first binds: these are the same in the relaxed dd of the
+    /// subproblem, which may then resume from them rather than develop them
+    /// again (see `DecisionDiagram::resume_relaxed`).
+    pub reuse_prefix: bool,
+    /// How a relaxed dd merges the nodes of the layers which exceed
+    /// `max_width` (see `MergePolicy`)
+    pub merge_policy: MergePolicy,
+    /// When set, the dd validates every merge with `Relaxation::check_merge`
+    /// and panics as soon as an inconsistent merged state is produced.
+    pub check_merge: bool,
+    /// When set, the dd measures how much of the compilation time is spent in
+    /// the problem and the relaxation (see `DecisionDiagram::model_time`).
+    pub timed: bool,
+    /// When set, the dd checks that its merges never lose value and that the
+    /// estimates of its exact nodes are not beaten by greedy completions (see
+    /// `DecisionDiagram::drain_violations`).
+    pub self_check: bool,
+    /// When set, the dd stops its compilation at the first layer it reaches
+    /// once the token is cancelled: what it holds is then meaningless, and
+    /// it is up to the caller to check the token before using it.
+    pub cancellation: Option<&'a CancellationToken>,
+    /// When set, the dd re-reads the best lower bound from this mirror of the
+    /// incumbent at every layer, and prunes with it as soon as it exceeds
+    /// `best_lb` (see `CompilationStats::bound_refreshes`). Since a solution
+    /// of that value is known, this only prunes what cannot be improved.
+    pub incumbent: Option<&'a AtomicIsize>,
+}
+impl<P, R, O> CompilationInput<'_, P, R, O>
+where
+    P: Problem,
+    P::State: Clone,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+{
+    /// Returns true iff the compilation must stop (see `cancellation`)
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_some_and(CancellationToken::is_cancelled)
+    }
+    /// Returns the best lower bound which is currently known: that of the
+    /// incumbent when it is greater than `best_lb` (see `incumbent`)
+    pub fn current_best_lb(&self) -> isize {
+        self.incumbent.map_or(self.best_lb, |lb| lb.load(AtomicOrdering::Relaxed).max(self.best_lb))
+    }
+    /// A copy of this input, for the compilation of a relaxed dd
+    pub fn for_relaxed(&self) -> Self {
+        self.with_comp_type(CompilationType::Relaxed)
+    }
+    /// A copy of this input, for the compilation of a restricted dd
+    pub fn for_restricted(&self) -> Self {
+        self.with_comp_type(CompilationType::Restricted)
+    }
+    fn with_comp_type(&self, comp_type: CompilationType) -> Self {
+        CompilationInput {
+            comp_type,
+            max_width: self.max_width,
+            problem: self.problem,
+            relaxation: self.relaxation,
+            ranking: self.ranking,
+            residual: self.residual.clone(),
+            assigned: self.assigned.clone(),
+            best_lb: self.best_lb,
+            min_relax_depth_offset: self.min_relax_depth_offset,
+            restrict_first_layers: self.restrict_first_layers,
+            max_forced_layers: self.max_forced_layers,
+            dominance: self.dominance,
+            reuse_prefix: self.reuse_prefix,
+            merge_policy: self.merge_policy,
+            check_merge: self.check_merge,
+            timed: self.timed,
+            self_check: self.self_check,
+            cancellation: self.cancellation,
+            incumbent: self.incumbent,
+        }
+    }
+}
+
+/// Why a `CompilationInputBuilder` could not build its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilationInputError {
+    /// The given field was never set
+    Missing(&'static str),
+    /// The maximum width must be at least 1
+    ZeroWidth,
+}
+impl Display for CompilationInputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(field) => write!(f, "the {} of the compilation input is missing", field),
+            Self::ZeroWidth => write!(f, "the maximum width of a dd must be at least 1"),
+        }
+    }
+}
+impl std::error::Error for CompilationInputError {}
+
+/// Builds a `CompilationInput` out of the fields which matter to the caller.
+/// The compilation type, the maximum width, the problem, the relaxation, the
+/// ranking and the residual subproblem are required. The other fields get the
+/// same defaults as in the solvers: the variables assigned by the residual
+/// path, no lower bound, a relax depth offset of 1, no self check nor timing,
+/// and merge checks in debug builds only.
+///
+/// The `exact`, `relaxed` and `restricted` constructors set the compilation
+/// type and the model at once, and `root` makes the input compile the whole
+/// problem:
+///
+/// `CompilationInputBuilder::relaxed(&problem, &relaxation, &ranking).max_width(100).root().build()`
+pub struct CompilationInputBuilder<'a, P, R, O>
+where
+    P: Problem,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+{
+    comp_type: Option<CompilationType>,
+    max_width: Option<usize>,
+    problem: Option<&'a P>,
+    relaxation: Option<&'a R>,
+    ranking: Option<&'a O>,
+    residual: Option<SubProblem<P::State>>,
+    /// When set (and no residual is given), the residual is the root of the problem
+    root: bool,
+    assigned: Option<AssignedVars>,
+    best_lb: isize,
+    min_relax_depth_offset: usize,
+    restrict_first_layers: bool,
+    max_forced_layers: usize,
+    dominance: Option<&'a (dyn DominanceChecker<P::State> + Sync)>,
+    reuse_prefix: bool,
+    merge_policy: MergePolicy,
+    check_merge: bool,
+    timed: bool,
+    self_check: bool,
+    cancellation: Option<&'a CancellationToken>,
+    incumbent: Option<&'a AtomicIsize>,
+}
+impl<P, R, O> Default for CompilationInputBuilder<'_, P, R, O>
+where
+    P: Problem,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+{
+    fn default() -> Self {
+        Self {
+            comp_type: None,
+            max_width: None,
+            problem: None,
+            relaxation: None,
+            ranking: None,
+            residual: None,
+            root: false,
+            assigned: None,
+            best_lb: isize::MIN,
+            min_relax_depth_offset: 1,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            dominance: None,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: cfg!(debug_assertions),
+            timed: false,
+            self_check: false,
+            cancellation: None,
+            incumbent: None,
+        }
+    }
+}
+impl<'a, P, R, O> CompilationInputBuilder<'a, P, R, O>
+where
+    P: Problem,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// A builder for the exact compilation of the given model
+    pub fn exact(problem: &'a P, relaxation: &'a R, ranking: &'a O) -> Self {
+        Self::with_model(CompilationType::Exact, problem, relaxation, ranking)
+    }
+    /// A builder for the relaxed compilation of the given model
+    pub fn relaxed(problem: &'a P, relaxation: &'a R, ranking: &'a O) -> Self {
+        Self::with_model(CompilationType::Relaxed, problem, relaxation, ranking)
+    }
+    /// A builder for the restricted compilation of the given model
+    pub fn restricted(problem: &'a P, relaxation: &'a R, ranking: &'a O) -> Self {
+        Self::with_model(CompilationType::Restricted, problem, relaxation, ranking)
+    }
+    fn with_model(comp_type: CompilationType, problem: &'a P, relaxation: &'a R, ranking: &'a O) -> Self {
+        Self::new().comp_type(comp_type).problem(problem).relaxation(relaxation).ranking(ranking)
+    }
+    pub fn comp_type(mut self, comp_type: CompilationType) -> Self {
+        self.comp_type = Some(comp_type);
+        self
+    }
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+    pub fn problem(mut self, problem: &'a P) -> Self {
+        self.problem = Some(problem);
+        self
+    }
+    pub fn relaxation(mut self, relaxation: &'a R) -> Self {
+        self.relaxation = Some(relaxation);
+        self
+    }
+    pub fn ranking(mut self, ranking: &'a O) -> Self {
+        self.ranking = Some(ranking);
+        self
+    }
+    pub fn residual(mut self, residual: SubProblem<P::State>) -> Self {
+        self.residual = Some(residual);
+        self
+    }
+    /// Makes the input compile the whole problem: the residual subproblem is
+    /// the root (its initial state and value), unless `residual` is also set
+    pub fn root(mut self) -> Self {
+        self.root = true;
+        self
+    }
+    /// Sets the variables assigned above the residual subproblem, when they
+    /// are not (only) those of its path
+    pub fn assigned(mut self, assigned: AssignedVars) -> Self {
+        self.assigned = Some(assigned);
+        self
+    }
+    pub fn best_lb(mut self, best_lb: isize) -> Self {
+        self.best_lb = best_lb;
+        self
+    }
+    pub fn min_relax_depth_offset(mut self, offset: usize) -> Self {
+        self.min_relax_depth_offset = offset;
+        self
+    }
+    pub fn restrict_first_layers(mut self, restrict: bool) -> Self {
+        self.restrict_first_layers = restrict;
+        self
+    }
+    pub fn max_forced_layers(mut self, max: usize) -> Self {
+        self.max_forced_layers = max;
+        self
+    }
+    pub fn dominance(mut self, dominance: Option<&'a (dyn DominanceChecker<P::State> + Sync)>) -> Self {
+        self.dominance = dominance;
+        self
+    }
+    pub fn reuse_prefix(mut self, reuse: bool) -> Self {
+        self.reuse_prefix = reuse;
+        self
+    }
+    pub fn merge_policy(mut self, policy: MergePolicy) -> Self {
+        self.merge_policy = policy;
+        self
+    }
+    pub fn check_merge(mut self, check: bool) -> Self {
+        self.check_merge = check;
+        self
+    }
+    pub fn timed(mut self, timed: bool) -> Self {
+        self.timed = timed;
+        self
+    }
+    pub fn self_check(mut self, check: bool) -> Self {
+        self.self_check = check;
+        self
+    }
+    pub fn cancellation(mut self, token: &'a CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+    pub fn incumbent(mut self, best_lb: &'a AtomicIsize) -> Self {
+        self.incumbent = Some(best_lb);
+        self
+    }
+
+    pub fn build(self) -> Result<CompilationInput<'a, P, R, O>, CompilationInputError> {
+        let comp_type = self.comp_type.ok_or(CompilationInputError::Missing("compilation type"))?;
+        let max_width = self.max_width.ok_or(CompilationInputError::Missing("maximum width"))?;
+        if max_width == 0 {
+            return Err(CompilationInputError::ZeroWidth);
+        }
+        let problem = self.problem.ok_or(CompilationInputError::Missing("problem"))?;
+        let relaxation = self.relaxation.ok_or(CompilationInputError::Missing("relaxation"))?;
+        let ranking = self.ranking.ok_or(CompilationInputError::Missing("ranking"))?;
+        let residual = match self.residual {
+            Some(residual) => residual,
+            None if self.root => SubProblem {
+                state: Arc::new(problem.initial_state()),
+                value: problem.initial_value(),
+                path: vec![],
+                ub: isize::MAX,
+                id: 0,
+                parent: None,
+                prefix: None,
+            },
+            None => return Err(CompilationInputError::Missing("residual subproblem")),
+        };
+        let assigned = self.assigned.unwrap_or_else(|| AssignedVars::from_subproblem(problem.nb_variables(), &residual));
+
+        Ok(CompilationInput {
+            comp_type,
+            max_width,
+            problem,
+            relaxation,
+            ranking,
+            residual,
+            assigned,
+            best_lb: self.best_lb,
+            min_relax_depth_offset: self.min_relax_depth_offset,
+            restrict_first_layers: self.restrict_first_layers,
+            max_forced_layers: self.max_forced_layers,
+            dominance: self.dominance,
+            reuse_prefix: self.reuse_prefix,
+            merge_policy: self.merge_policy,
+            check_merge: self.check_merge,
+            timed: self.timed,
+            self_check: self.self_check,
+            cancellation: self.cancellation,
+            incumbent: self.incumbent,
+        })
+    }
 }
 
 pub trait DecisionDiagram {
@@ -188,10 +1153,60 @@ pub trait DecisionDiagram {
         P: Problem<State = Self::State>,
         R: Relaxation<State = P::State>,
         O: StateRanking<State = P::State>;
+    /// Compiles a dd for a model behind trait objects (see the `dynamic`
+    /// module). This is `compile`, save for the virtual calls to the model.
+    fn compile_dyn(&mut self, input: &DynCompilationInput<Self::State>) {
+        self.compile(input)
+    }
+    /// Compiles the exact dd of the given input, whose compilation type must
+    /// be `CompilationType::Exact`. The result gives the optimum of the
+    /// subproblem with no need to check `is_exact`. It is none when the
+    /// compilation was cancelled before it completed.
+    fn compile_exact<P, R, O>(&mut self, input: &CompilationInput<P, R, O>) -> Option<ExactCompilationResult<'_, Self>>
+    where
+        P: Problem<State = Self::State>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        assert_eq!(CompilationType::Exact, input.comp_type, "compile_exact needs an exact compilation");
+        self.compile(input);
+        ExactCompilationResult::new(self)
+    }
+    /// Compiles the relaxed dd of the subproblem whose restricted dd was the
+    /// last compilation, starting from the layers these two dds share: those
+    /// the restricted dd developed before its width first bound (when it was
+    /// asked to keep them, see `CompilationInput::reuse_prefix`). The input
+    /// must be that of the restricted dd, save for its compilation type, its
+    /// self check and its lower bound. It returns false (and compiles nothing)
+    /// when there are no such layers, or when the lower bound has changed
+    /// since they were developed: the dd must then be compiled from scratch.
+    /// The dds which keep no layers never resume.
+    fn resume_relaxed<P, R, O>(&mut self, _input: &CompilationInput<P, R, O>) -> bool
+    where
+        P: Problem<State = Self::State>,
+        R: Relaxation<State = P::State>,
+        O: StateRanking<State = P::State>,
+    {
+        false
+    }
 
     fn is_exact(&self) -> bool;
     fn best_value(&self) -> Option<isize>;
     fn best_solution(&self) -> Option<Vec<Decision>>;
+    /// Returns the best value of the last compilation, unless the best path
+    /// goes through a relaxed node (in which case the value is only a bound).
+    /// Unlike `best_value`, this is the value of an actual solution whatever
+    /// the type of the compilation: this is what the solvers store as their
+    /// best lower bound.
+    fn best_exact_value(&self) -> Option<isize>;
+    /// Returns the best solution of the last compilation, unless its path goes
+    /// through a relaxed node (see `best_exact_value`)
+    fn best_exact_solution(&self) -> Option<Vec<Decision>>;
+    /// Returns (at most `limit` of) the solutions of the last compilation which
+    /// are worth its best value, starting with `best_solution`. Only the paths
+    /// which do not go through a relaxed node are enumerated, and none is when
+    /// the best value is only a bound (see `best_exact_value`).
+    fn best_solutions(&self, limit: usize) -> Vec<Vec<Decision>>;
 
     /// FIXME
     /// This can only be called if the dd was compiled in relaxed mode.
@@ -199,6 +1214,214 @@ pub trait DecisionDiagram {
     fn drain_cutset<F>(&mut self, func: F)
     where
         F: FnMut(SubProblem<Self::State>);
+
+    /// Returns the number of nodes that were created during the last
+    /// compilation. This includes the nodes that have been deleted because they
+    /// were merged, as well as the merged nodes themselves.
+    fn nb_nodes(&self) -> usize;
+    /// Returns the number of nodes of the last compiled dd that have not been
+    /// deleted by a merge operation. The nodes that a restricted layer
+    /// truncates are not deleted: they are counted here (but not by
+    /// `node_count`).
+    fn nb_active_nodes(&self) -> usize;
+    /// Returns the number of edges of the last compiled dd that reach an active
+    /// node (see `nb_active_nodes`). When nodes are merged, their inbound
+    /// edges are redirected towards the merged node: only the redirected copy
+    /// of these edges is counted (unlike by `arc_count`).
+    fn nb_edges(&self) -> usize;
+    /// Returns the number of nodes of the last compiled dd, whatever its
+    /// compilation type: the nodes it created, save for those that its
+    /// restricted and relaxed layers dropped. It only differs from
+    /// `nb_active_nodes` on a restricted dd, whose truncated nodes are left
+    /// out here. Unlike `nb_active_nodes`, this is counted as the dd is
+    /// compiled (it costs nothing to call).
+    fn node_count(&self) -> usize {
+        0
+    }
+    /// Returns the number of arcs that the last compilation created, whatever
+    /// its compilation type. Unlike `nb_edges`, this also counts the arcs
+    /// that reach a deleted node: the original arcs of the merged nodes (on
+    /// top of their redirected copies), those of the nodes which a relaxed
+    /// layer dropped (see `CompilationInput::restrict_first_layers`) and those
+    /// of the dead ends of the collapsed layers. Both count the arcs that
+    /// reach the nodes truncated by a restricted layer.
+    fn arc_count(&self) -> usize {
+        0
+    }
+    /// Returns the number of transitions of the last compilation that led to
+    /// a state which already existed in the next layer: no new node (nor any
+    /// allocation of the state) was needed for these.
+    fn nb_avoided_allocations(&self) -> usize;
+    /// Returns the number of layers of the last compilation for which the dd
+    /// reserved the memory of the next layer before branching
+    fn nb_reservations(&self) -> usize;
+    /// Returns the number of times the next layer, the nodes or the edges of
+    /// the last compilation outgrew their memory while a layer was expanded
+    fn nb_reallocations(&self) -> usize;
+    /// Returns true iff the last compilation was restricted and stopped at an
+    /// empty layer, after the restriction had dropped some nodes: the nodes it
+    /// kept in the layer above were all dead ends (rather than pruned), and a
+    /// wider dd may have gone further.
+    fn ran_dry(&self) -> bool;
+    /// Returns what the last compilation restricted, merged and pruned
+    fn stats(&self) -> &CompilationStats;
+    /// Returns the time the last compilation spent computing the estimates,
+    /// the domains, the transitions, the merged states and the relaxed costs
+    /// of the arcs redirected to them. It is only
+    /// measured when the compilation was timed (it is zero otherwise).
+    fn model_time(&self) -> Duration;
+    /// Returns the rest of the time the last (timed) compilation took: the
+    /// bookkeeping of the dd and the accesses to the shared data.
+    fn framework_time(&self) -> Duration;
+    /// Hands over the violations found by the last (self checked) compilation
+    fn drain_violations<F>(&mut self, func: F)
+    where
+        F: FnMut(Violation<Self::State>);
+}
+
+/// A dd whose last compilation is known to be exact (see `compile_exact`):
+/// its best value and solution are those of the subproblem it was compiled
+/// for. As it only borrows the dd, the dd cannot be compiled again (nor its
+/// cutset drained) while the result is alive.
+pub struct ExactCompilationResult<'a, D: ?Sized> {
+    dd: &'a D,
+}
+impl<'a, D: DecisionDiagram + ?Sized> ExactCompilationResult<'a, D> {
+    /// Returns the result of the last compilation of the given dd, or none
+    /// when that dd is not exact
+    pub fn new(dd: &'a D) -> Option<Self> {
+        dd.is_exact().then_some(Self { dd })
+    }
+    /// Returns the optimal value of the subproblem (none when it has no
+    /// solution)
+    pub fn optimal_value(&self) -> Option<isize> {
+        self.dd.best_value()
+    }
+    /// Returns an optimal solution of the subproblem (none when it has no
+    /// solution)
+    pub fn optimal_solution(&self) -> Option<Vec<Decision>> {
+        self.dd.best_solution()
+    }
+    /// Returns (at most `limit` of) the optimal solutions of the subproblem
+    pub fn optimal_solutions(&self, limit: usize) -> Vec<Vec<Decision>> {
+        self.dd.best_solutions(limit)
+    }
+    /// Returns the exact dd
+    pub fn dd(&self) -> &'a D {
+        self.dd
+    }
+}
+
+/// What a single compilation did to keep its dd within the maximum width.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompilationStats {
+    /// The nodes that the restricted layers dropped
+    pub restricted_nodes: usize,
+    /// The merged nodes that the relaxed layers were given (one per chunk)
+    pub relaxed_merges: usize,
+    /// The nodes that were pruned by the thresholds of the barrier
+    pub barrier_prunes: usize,
+    /// The nodes that were not branched on because their rough upper bound
+    /// could not improve the best lower bound
+    pub rub_prunes: usize,
+    /// The exact nodes that were dropped because another node of their layer
+    /// dominated them (see `CompilationInput::dominance`)
+    pub dominated_nodes: usize,
+    /// The depth of the last exact layer of a relaxed dd (none when no layer
+    /// was merged)
+    pub lel_depth: Option<usize>,
+    /// The arcs that have been allocated, redirected ones included
+    pub total_arcs: usize,
+    /// The layers of forced decisions that were collapsed into the arcs of
+    /// the layer above them (see `CompilationInput::max_forced_layers`)
+    pub collapsed_layers: usize,
+    /// The nodes that a relaxed dd took over from the restricted dd of the
+    /// same subproblem, rather than expanding them again (see
+    /// `DecisionDiagram::resume_relaxed`)
+    pub reused_nodes: usize,
+    /// The layers at which the dd found that the incumbent had improved on
+    /// the best lower bound it was pruning with (see
+    /// `CompilationInput::incumbent`)
+    pub bound_refreshes: usize,
+}
+impl CompilationStats {
+    /// Accounts for the compilation summarized in `other`: the counters are
+    /// summed up, and the deepest last exact layer is kept
+    pub fn add(&mut self, other: &Self) {
+        self.restricted_nodes += other.restricted_nodes;
+        self.relaxed_merges += other.relaxed_merges;
+        self.barrier_prunes += other.barrier_prunes;
+        self.rub_prunes += other.rub_prunes;
+        self.dominated_nodes += other.dominated_nodes;
+        self.lel_depth = self.lel_depth.max(other.lel_depth);
+        self.total_arcs += other.total_arcs;
+        self.collapsed_layers += other.collapsed_layers;
+        self.reused_nodes += other.reused_nodes;
+        self.bound_refreshes += other.bound_refreshes;
+    }
+}
+
+/// Statistics about the size of the dds that have been compiled by a solver.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompilationStatistics {
+    pub nb_compilations: usize,
+    pub max_nodes: usize,
+    pub total_nodes: usize,
+    pub max_active_nodes: usize,
+    pub total_active_nodes: usize,
+    pub max_edges: usize,
+    pub total_edges: usize,
+    pub total_avoided_allocations: usize,
+    /// The layers whose memory was reserved before they were built
+    pub total_reservations: usize,
+    /// The reallocations that happened while the layers were built
+    pub total_reallocations: usize,
+    /// The time spent in the model by the timed compilations
+    pub model_time: Duration,
+    /// The time spent in the solver by the timed compilations
+    pub framework_time: Duration,
+    /// What all the compilations restricted, merged and pruned
+    pub counters: CompilationStats,
+}
+impl CompilationStatistics {
+    /// Accounts for the last compilation of the given dd
+    pub fn record<D: DecisionDiagram>(&mut self, dd: &D) {
+        self.nb_compilations += 1;
+        self.max_nodes = self.max_nodes.max(dd.nb_nodes());
+        self.total_nodes += dd.nb_nodes();
+        self.max_active_nodes = self.max_active_nodes.max(dd.nb_active_nodes());
+        self.total_active_nodes += dd.nb_active_nodes();
+        self.max_edges = self.max_edges.max(dd.nb_edges());
+        self.total_edges += dd.nb_edges();
+        self.total_avoided_allocations += dd.nb_avoided_allocations();
+        self.total_reservations += dd.nb_reservations();
+        self.total_reallocations += dd.nb_reallocations();
+        self.model_time += dd.model_time();
+        self.framework_time += dd.framework_time();
+        self.counters.add(dd.stats());
+    }
+    /// Accounts for all the compilations summarized in `other`
+    pub fn merge(&mut self, other: &Self) {
+        self.nb_compilations += other.nb_compilations;
+        self.max_nodes = self.max_nodes.max(other.max_nodes);
+        self.total_nodes += other.total_nodes;
+        self.max_active_nodes = self.max_active_nodes.max(other.max_active_nodes);
+        self.total_active_nodes += other.total_active_nodes;
+        self.max_edges = self.max_edges.max(other.max_edges);
+        self.total_edges += other.total_edges;
+        self.total_avoided_allocations += other.total_avoided_allocations;
+        self.total_reservations += other.total_reservations;
+        self.total_reallocations += other.total_reallocations;
+        self.model_time += other.model_time;
+        self.framework_time += other.framework_time;
+        self.counters.add(&other.counters);
+    }
+    /// Returns the share of the time of the timed compilations that has been
+    /// spent in the model (none when no compilation was timed)
+    pub fn model_time_share(&self) -> Option<f64> {
+        let total = self.model_time + self.framework_time;
+        (!total.is_zero()).then(|| self.model_time.as_secs_f64() / total.as_secs_f64())
+    }
 }
 
 impl FromStr for CompilationType {
@@ -222,3 +1445,161 @@ impl Display for CompilationType {
         }
     }
 }
+
+#[cfg(test)]
+mod test_prelude {
+    use std::{sync::{atomic::{AtomicUsize, Ordering}, Arc}, time::Duration};
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState};
+    use crate::{
+        All, BarrierParallelSolver, CompilationType, CutsetType, Decision, DecisionDiagram, Fixed, InterruptibleSolver, SubProblem,
+        Variable,
+    };
+
+    use super::{
+        CompilationInput, CompilationInputBuilder, CompilationInputError, Cutoff, CutoffStats, EitherCutoff, NodeCutoff, Problem,
+        ResolutionStatus, TimeCutoff,
+    };
+
+    fn residual() -> SubProblem<KnapsackState> {
+        SubProblem {
+            state: Arc::new(KnapsackState { depth: 1, capacity: 10 }),
+            value: 0,
+            path: vec![Decision { var: Variable(0), value: 0 }],
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        }
+    }
+
+    #[test]
+    fn the_builder_fills_in_the_defaults() {
+        let problem = Knapsack::toy();
+        let input = CompilationInputBuilder::new()
+            .comp_type(CompilationType::Restricted)
+            .max_width(2)
+            .problem(&problem)
+            .relaxation(&KnapsackRelax)
+            .ranking(&KnapsackRanking)
+            .residual(residual())
+            .build()
+            .unwrap();
+        assert_eq!(isize::MIN, input.best_lb);
+        assert_eq!(1, input.min_relax_depth_offset);
+        assert!(!input.timed && !input.self_check);
+        assert_eq!(vec![Variable(0)], input.assigned.iter().collect::<Vec<_>>());
+
+        // the relaxed copy only differs by its compilation type
+        let relaxed = input.for_relaxed();
+        assert_eq!(CompilationType::Relaxed, relaxed.comp_type);
+        assert_eq!(CompilationType::Restricted, relaxed.for_restricted().comp_type);
+        let mut expected = All::new(CutsetType::Frontier);
+        expected.compile(&CompilationInput { comp_type: CompilationType::Relaxed, ..input });
+        let mut mdd = All::new(CutsetType::Frontier);
+        mdd.compile(&relaxed);
+        assert_eq!(expected.best_value(), mdd.best_value());
+        assert_eq!(expected.nb_nodes(), mdd.nb_nodes());
+    }
+
+    #[test]
+    fn a_root_compilation_only_needs_the_model_and_the_width() {
+        let problem = Knapsack::toy();
+        let input = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).max_width(2).root().build().unwrap();
+        assert_eq!(CompilationType::Relaxed, input.comp_type);
+        assert_eq!(0, input.residual.depth());
+        assert_eq!(problem.initial_value(), input.residual.value);
+        assert_eq!(isize::MAX, input.residual.ub);
+        assert_eq!(0, input.assigned.iter().count());
+        assert_eq!((isize::MIN, 1, cfg!(debug_assertions)), (input.best_lb, input.min_relax_depth_offset, input.check_merge));
+
+        // an explicit residual wins over the root
+        let input = CompilationInputBuilder::exact(&problem, &KnapsackRelax, &KnapsackRanking)
+            .max_width(usize::MAX)
+            .root()
+            .residual(residual())
+            .best_lb(7)
+            .build()
+            .unwrap();
+        assert_eq!((CompilationType::Exact, 1, 7), (input.comp_type, input.residual.depth(), input.best_lb));
+        let input = CompilationInputBuilder::restricted(&problem, &KnapsackRelax, &KnapsackRanking)
+            .max_width(3)
+            .root()
+            .check_merge(false)
+            .timed(true)
+            .build()
+            .unwrap();
+        assert_eq!(CompilationType::Restricted, input.comp_type);
+        assert!(input.timed && !input.check_merge && !input.self_check);
+    }
+
+    #[test]
+    fn an_incomplete_input_is_rejected() {
+        let problem = Knapsack::toy();
+        let builder = || {
+            CompilationInputBuilder::new()
+                .comp_type(CompilationType::Relaxed)
+                .problem(&problem)
+                .relaxation(&KnapsackRelax)
+                .ranking(&KnapsackRanking)
+        };
+        assert_eq!(Some(CompilationInputError::Missing("maximum width")), builder().residual(residual()).build().err());
+        assert_eq!(Some(CompilationInputError::Missing("residual subproblem")), builder().max_width(2).build().err());
+        let error = builder().max_width(0).residual(residual()).build().err().unwrap();
+        assert_eq!(CompilationInputError::ZeroWidth, error);
+        assert_eq!("the maximum width of a dd must be at least 1", error.to_string());
+    }
+
+    #[test]
+    fn a_node_cutoff_interrupts_the_resolution_like_an_interrupt() {
+        let stats = |explored| CutoffStats { elapsed: Duration::from_secs(1), explored, explored_dd: 0 };
+        let cutoff = EitherCutoff(TimeCutoff(Duration::from_secs(2)), NodeCutoff(3));
+        assert!(!cutoff.must_stop(&stats(2)));
+        assert!(cutoff.must_stop(&stats(3)));
+        assert!(EitherCutoff(TimeCutoff(Duration::from_secs(1)), NodeCutoff(3)).must_stop(&stats(0)));
+
+        let problem = Knapsack::toy();
+        for nodes in 1..=3 {
+            let mut cut = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, 1);
+            assert_eq!(ResolutionStatus::Interrupted, cut.maximize_with_cutoff(NodeCutoff(nodes)));
+            assert_eq!(nodes, cut.get_explored());
+
+            // with a single worker, the cutoff is checked once before each node
+            let calls = AtomicUsize::new(0);
+            let mut interrupted = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, 1);
+            assert_eq!(ResolutionStatus::Interrupted, interrupted.maximize_with_interrupt(move || calls.fetch_add(1, Ordering::Relaxed) >= nodes));
+            assert_eq!(interrupted.get_explored(), cut.get_explored());
+            assert_eq!(interrupted.best_upper_bound(), cut.best_upper_bound());
+            assert_eq!(interrupted.best_lower_bound(), cut.best_lower_bound());
+            assert!(cut.best_upper_bound() >= 13);
+        }
+    }
+
+    /// A knapsack which has less room left for a lower value is dominated
+    struct LessRoom;
+    impl super::Dominance for LessRoom {
+        type State = KnapsackState;
+        type Key = usize;
+
+        fn dominance_key(&self, state: &KnapsackState) -> usize {
+            state.depth
+        }
+        fn is_dominated(&self, a_value: isize, a: &KnapsackState, b_value: isize, b: &KnapsackState) -> bool {
+            a.capacity <= b.capacity && a_value <= b_value
+        }
+    }
+
+    #[test]
+    fn the_dominated_states_are_only_compared_within_their_key() {
+        let states = [
+            KnapsackState { depth: 1, capacity: 5 },
+            KnapsackState { depth: 1, capacity: 8 },
+            KnapsackState { depth: 2, capacity: 3 },
+            KnapsackState { depth: 1, capacity: 8 },
+            KnapsackState { depth: 1, capacity: 2 },
+        ];
+        let layer = [(4, &states[0]), (6, &states[1]), (0, &states[2]), (6, &states[3]), (9, &states[4])];
+        // the first of two equivalent states is kept, the last one is worth more
+        assert_eq!(vec![true, false, false, true, false], super::DominanceChecker::dominated(&LessRoom, &layer));
+    }
+}
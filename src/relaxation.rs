@@ -0,0 +1,209 @@
+//! This module provides relaxations built out of other relaxations. A
+//! `LayeredRelaxation` lets a model merge its shallow layers with a coarse
+//! (cheap) operator and its deeper layers with a finer one: the bound suffers
+//! little from a coarse merge near the root, but a lot near the leaves.
+
+use crate::{Decision, Relaxation};
+
+/// A relaxation which uses the `shallow` operator to merge the layers which
+/// are less than `depth` variables deep, and the `deep` one to merge the
+/// others. More than two operators can be combined by nesting layered
+/// relaxations, e.g. `LayeredRelaxation::new(a, LayeredRelaxation::new(b, c, 20), 10)`
+/// uses `a` up to depth 10, `b` up to depth 20 and `c` beyond.
+///
+/// Both operators must be valid relaxations on their own. When they are
+/// called without a depth, the merges are performed as if at the root.
+#[derive(Debug, Clone, Copy)]
+pub struct LayeredRelaxation<S, D> {
+    shallow: S,
+    deep: D,
+    depth: usize,
+}
+impl<S, D> LayeredRelaxation<S, D> {
+    pub fn new(shallow: S, deep: D, depth: usize) -> Self {
+        Self { shallow, deep, depth }
+    }
+    /// The depth from which the layers are merged with the deep operator
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl<S, D> Relaxation for LayeredRelaxation<S, D>
+where
+    S: Relaxation,
+    D: Relaxation<State = S::State>,
+{
+    type State = S::State;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        self.merge_at_depth(0, states)
+    }
+    fn relax(&self, source: &Self::State, dest: &Self::State, new: &Self::State, decision: Decision, cost: isize) -> isize {
+        self.relax_at_depth(0, source, dest, new, decision, cost)
+    }
+    fn check_merge(&self, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        self.check_merge_at_depth(0, merged, originals)
+    }
+
+    fn merge_at_depth(&self, depth: usize, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        if depth < self.depth {
+            self.shallow.merge_at_depth(depth, states)
+        } else {
+            self.deep.merge_at_depth(depth, states)
+        }
+    }
+    fn relax_at_depth(&self, depth: usize, source: &Self::State, dest: &Self::State, new: &Self::State, decision: Decision, cost: isize)
+        -> isize {
+        if depth < self.depth {
+            self.shallow.relax_at_depth(depth, source, dest, new, decision, cost)
+        } else {
+            self.deep.relax_at_depth(depth, source, dest, new, decision, cost)
+        }
+    }
+    fn check_merge_at_depth(&self, depth: usize, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        if depth < self.depth {
+            self.shallow.check_merge_at_depth(depth, merged, originals)
+        } else {
+            self.deep.check_merge_at_depth(depth, merged, originals)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_relaxation {
+    use std::sync::{Arc, Mutex};
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState};
+    use crate::{
+        All, AssignedVars, Barrier, BarrierParallelSolver, BarrierStore, CompilationInput, CompilationType, CutsetType, Decision,
+        DecisionDiagram, Fixed, MergePolicy, Problem, Relaxation, Solver, SubProblem, Variable,
+    };
+
+    use super::LayeredRelaxation;
+
+    /// A relaxation which merges everything into the same state and tells
+    /// which operator was called through the cost of the relaxed arcs
+    struct Tag(isize);
+    impl Relaxation for Tag {
+        type State = isize;
+
+        fn merge(&self, _: &mut dyn Iterator<Item = &isize>) -> isize {
+            self.0
+        }
+        fn relax(&self, _: &isize, _: &isize, _: &isize, _: Decision, cost: isize) -> isize {
+            cost + self.0
+        }
+        fn check_merge(&self, merged: &isize, _: &[&isize]) -> Result<(), String> {
+            (*merged == self.0).then_some(()).ok_or_else(|| format!("{} was not merged by {}", merged, self.0))
+        }
+    }
+
+    #[test]
+    fn each_layer_is_merged_by_the_operator_of_its_depth() {
+        let relaxation = LayeredRelaxation::new(Tag(1), LayeredRelaxation::new(Tag(2), Tag(3), 5), 2);
+        let decision = Decision { var: Variable(0), value: 0 };
+        let expected = [1, 1, 2, 2, 2, 3, 3];
+        for (depth, tag) in expected.iter().enumerate() {
+            assert_eq!(*tag, relaxation.merge_at_depth(depth, &mut [0, 0].iter()));
+            assert_eq!(10 + tag, relaxation.relax_at_depth(depth, &0, &0, &0, decision, 10));
+            assert!(relaxation.check_merge_at_depth(depth, tag, &[&0]).is_ok());
+            assert!(relaxation.check_merge_at_depth(depth, &0, &[&0]).is_err());
+        }
+        assert_eq!(1, relaxation.merge(&mut [0].iter()));
+    }
+
+    /// A valid relaxation which records the depths at which it has merged
+    /// states, along with the depth of these states
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(usize, usize)>>);
+    impl Relaxation for Recorder {
+        type State = KnapsackState;
+
+        fn merge(&self, _: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+            unreachable!("the dds must merge through merge_at_depth")
+        }
+        fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+            cost
+        }
+        fn merge_at_depth(&self, depth: usize, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+            let merged = KnapsackRelax.merge(states);
+            self.0.lock().unwrap().push((depth, merged.depth));
+            merged
+        }
+    }
+
+    #[test]
+    fn the_dds_merge_each_layer_at_the_depth_of_its_states() {
+        let problem = Knapsack::toy();
+        // the residual problem starts after the decision on the first item
+        let residual = SubProblem {
+            state: Arc::new(KnapsackState { depth: 1, capacity: 10 }),
+            value: 0,
+            path: vec![Decision { var: Variable(0), value: 0 }],
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        };
+        let mut assigned = AssignedVars::new(problem.nb_variables());
+        assigned.insert(Variable(0));
+        let relaxation = Recorder::default();
+        let input = CompilationInput {
+            comp_type: CompilationType::Relaxed,
+            max_width: 2,
+            problem: &problem,
+            relaxation: &relaxation,
+            ranking: &KnapsackRanking,
+            residual,
+            assigned,
+            best_lb: isize::MIN,
+            min_relax_depth_offset: 0,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            dominance: None,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: true,
+            timed: false,
+            self_check: false,
+            cancellation: None,
+            incumbent: None,
+        };
+
+        All::new(CutsetType::Frontier).compile(&input);
+        let merges = relaxation.0.lock().unwrap().drain(..).collect::<Vec<_>>();
+        assert!(!merges.is_empty());
+        assert!(merges.iter().all(|(depth, state_depth)| depth == state_depth), "{:?}", merges);
+
+        let barriers = Arc::new(BarrierStore::new(problem.nb_variables() + 1));
+        Barrier::new(barriers, CutsetType::Frontier).compile(&input);
+        let barrier_merges = relaxation.0.lock().unwrap().drain(..).collect::<Vec<_>>();
+        assert_eq!(merges, barrier_merges);
+    }
+
+    /// A valid but coarse relaxation: the merged states get the capacity of
+    /// an empty knapsack
+    struct Coarse(usize);
+    impl Relaxation for Coarse {
+        type State = KnapsackState;
+
+        fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+            KnapsackState { depth: states.map(|s| s.depth).max().unwrap_or(0), capacity: self.0 }
+        }
+        fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+            cost
+        }
+    }
+
+    #[test]
+    fn a_layered_relaxation_of_valid_operators_keeps_the_optimum() {
+        let problem = Knapsack::toy();
+        for depth in 0..=problem.nb_variables() {
+            let relaxation = LayeredRelaxation::new(Coarse(problem.capacity), KnapsackRelax, depth);
+            let mut solver = BarrierParallelSolver::custom(&problem, &relaxation, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1);
+            solver.maximize();
+            assert_eq!(Some(13), solver.best_value());
+        }
+    }
+}
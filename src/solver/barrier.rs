@@ -1,107 +1,273 @@
-use std::{sync::Arc, hash::Hash};
+use std::{sync::Arc, hash::Hash, ops::Range};
 
-use parking_lot::{Condvar, Mutex, RwLock};
 use rustc_hash::FxHashMap;
 
 use crate::{
-    CompilationInput, CompilationType, Decision, DecisionDiagram, Frontier, InterruptibleSolver,
-    Problem, Relaxation, ResolutionStatus, Solver, StateRanking, SubProblem, WidthHeuristic, NoDupFrontier, Barrier, BarrierInfo, CutsetType,
+    CancellationToken, CompilationStatistics, CompilationStats, Cutoff, InterruptCutoff, Decision, SearchTree, InterruptibleSolver, Problem, Relaxation, ResolutionStatus, Solver,
+    StateRanking, SubProblem, WidthHeuristic, StratifiedFrontier, FringePolicy, FrontierOrder, MaxUB, Barrier, BarrierStore, Barriers, CutsetType, OnViolation, SecondaryObjective, Shedding,
+    Restarts, Violation, VerificationError, WarmStartBudget, BarrierLayerStats, MergePolicy, BarrierEvent, SolverObserver, DominanceChecker, SolutionPool, DEFAULT_WATCH_CAPACITY, MemoryAttribution, SolverConfig,
 };
 
-/// The shared data that may only be manipulated within critical sections
-struct Critical<'a, O>
+use super::engine::{Admission, Engine, NodeProcessor};
+#[cfg(feature = "checkpoint")]
+use super::checkpoint::Checkpoint;
+#[cfg(feature = "checkpoint")]
+use std::{io::{self, Read, Write}, path::Path};
+
+/// What the barrier solver does when a worker pops a node whose state is
+/// being explored (at the same depth) by another worker. This happens when the
+/// node has a better value than the one which is being explored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Both workers compile the same state concurrently
+    #[default]
+    Allow,
+    /// The node goes back to the fringe and the worker waits until some other
+    /// worker is done
+    Wait,
+    /// The node goes back to the fringe and the worker explores some other node
+    Skip,
+}
+
+/// The options which only the barrier solvers have, on top of those of their
+/// `SolverConfig`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarrierConfig<T> {
+    /// How the fringe picks the next node to explore. With
+    /// `FringePolicy::DeepestLayer`, the workers explore the deepest nodes
+    /// first, which keeps the fringe much smaller on the instances whose
+    /// shallow nodes all have high bounds, at the price of a best bound which
+    /// improves later. The default `FringePolicy::BestBound` pops the nodes
+    /// in the order of the solver.
+    pub fringe_policy: FringePolicy,
+    /// What a worker does when it pops a node whose state is being explored
+    /// by another worker
+    pub duplicate_policy: DuplicatePolicy,
+    /// The number of nodes and edges the dd of each worker has room for. Each
+    /// worker reuses its dd for all the nodes it explores, hence this saves
+    /// the reallocations of its first compilations.
+    pub dd_capacity: (usize, usize),
+    /// If set, the depths of the layers where the thresholds are read and
+    /// written: no threshold is read nor written at the other depths (see
+    /// `barrier_layer_stats` to find out where the barrier prunes). The range
+    /// may neither be empty nor go beyond the number of variables. The whole
+    /// problem is covered by default.
+    pub barrier_depths: Option<Range<usize>>,
+    /// If set, every interaction of the workers with the threshold of this
+    /// state is logged: its insertion and raises, and the nodes having that
+    /// state which it prunes or lets through, along with the thread, the
+    /// depth and the best lower bound (see `watched_state_log`). This is meant
+    /// to debug the barrier: when no state is watched, it costs nothing.
+    pub watched_state: Option<T>,
+    /// Whether the barrier keeps the thresholds of the layers the fringe left
+    /// behind until the solver is dropped, rather than forgetting about them.
+    /// This is what `dump_barrier` needs to save the thresholds of a complete
+    /// resolution. This is disabled by default, as it costs memory.
+    pub barrier_retention: bool,
+}
+impl<T> Default for BarrierConfig<T> {
+    fn default() -> Self {
+        Self {
+            fringe_policy: FringePolicy::default(),
+            duplicate_policy: DuplicatePolicy::default(),
+            dd_capacity: (0, 0),
+            barrier_depths: None,
+            watched_state: None,
+            barrier_retention: false,
+        }
+    }
+}
+
+/// The workers of the `BarrierParallelSolver` compile dds that share the
+/// barriers: the information about the states that have already been explored
+/// at each layer of the problem.
+struct BarrierProcessor<T> {
+    barriers: Barriers<T>,
+    /// The options of the barrier (its fringe policy is that of the fringe)
+    config: BarrierConfig<T>,
+}
+impl<T> BarrierProcessor<T>
 where
-    O: StateRanking,
-    O::State: Eq + PartialEq + Hash + Clone,
+    T: Eq + Hash + Clone,
 {
-    /// This is the fringe: the set of nodes that must still be explored before
-    /// the problem can be considered 'solved'.
-    ///
-    /// # Note:
-    /// This fringe orders the nodes by upper bound (so the highest ub is going
-    /// to pop first). So, it is guaranteed that the upper bound of the first
-    /// node being popped is an upper bound on the value reachable by exploring
-    /// any of the nodes remaining on the fringe. As a consequence, the
-    /// exploration can be stopped as soon as a node with an ub <= current best
-    /// lower bound is popped.
-    fringe: NoDupFrontier<'a, O>,
-    /// This is the number of nodes that are currently being explored.
+    fn new(nb_variables: usize, duplicate_policy: DuplicatePolicy) -> Self {
+        let config = BarrierConfig { duplicate_policy, ..BarrierConfig::default() };
+        Self { barriers: Arc::new(BarrierStore::new(nb_variables + 1)), config }
+    }
+    /// Replaces the options of the barrier. The barrier is only rebuilt (and
+    /// its thresholds forgotten) when its depths change.
     ///
-    /// # Note
-    /// This information may seem innocuous/superfluous, whereas in fact it is
-    /// very important. Indeed, this is the piece of information that lets us
-    /// distinguish between a node-starvation and the completion of the problem
-    /// resolution. The bottom line is, this counter needs to be carefully
-    /// managed to guarantee the termination of all threads.
-    ongoing: usize,
-    /// This is a counter that tracks the number of nodes that have effectively
-    /// been explored. That is, the number of nodes that have been popped from
-    /// the fringe, and for which a restricted and relaxed mdd have been developed.
-    explored: usize,
-    explored_dd: usize,
+    /// # Panics
+    /// When the depths are empty or go beyond the number of variables
+    fn set_config(&mut self, config: BarrierConfig<T>) {
+        let mut watched = self.config.watched_state.as_ref();
+        if config.barrier_depths != self.config.barrier_depths {
+            let store = BarrierStore::new(self.barriers.nb_layers());
+            let store = match config.barrier_depths.clone() {
+                Some(depths) => store.with_depths(depths),
+                None => store,
+            };
+            self.barriers = Arc::new(store);
+            watched = None;
+        }
+        if let Some(state) = config.watched_state.clone().filter(|state| watched != Some(state)) {
+            Arc::get_mut(&mut self.barriers)
+                .expect("the barrier is only shared during a resolution")
+                .watch(state, DEFAULT_WATCH_CAPACITY);
+        }
+        self.config = config;
+    }
+}
+/// How many nodes of some layer of the problem are on the fringe, are being
+/// explored by a worker, and have been explored (see `layer_statistics`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayerStats {
+    pub open: usize,
+    pub ongoing: usize,
+    pub completed: usize,
+    /// The nodes popped from the fringe which were discarded because their
+    /// upper bound could not improve the best lower bound
+    pub discarded_by_bound: usize,
+    /// The nodes popped from the fringe which were dominated by the barrier
+    pub discarded_by_barrier: usize,
+}
+
+/// The data the barrier solver maintains within its critical sections
+struct BarrierBookkeeping<T> {
     /// This is a counter of the number of nodes in the fringe, for each level of the model
     open_by_layer: Vec<usize>,
     /// This is a counter of the number of nodes in ongoing expansion, for each level of the model
     ongoing_by_layer: Vec<usize>,
+    /// This is a counter of the number of nodes whose expansion is over, for each level of the model
+    completed_by_layer: Vec<usize>,
     /// This is the index of the lowest level above which there are no nodes in the fringe
     lowest_active_layer: usize,
-    /// This is the value of the best known lower bound.
-    best_lb: isize,
-    /// This is the value of the best known lower bound.
-    /// *WARNING* This one only gets set when the interrupt condition is satisfied
-    best_ub: isize,
-    /// If set, this keeps the info about the best solution so far.
-    best_sol: Option<Vec<Decision>>,
-    /// This vector is used to store the upper bound on the node which is
-    /// currently processed by each thread.
-    ///
-    /// # Note
-    /// When a thread is idle (or more generally when it is done with processing
-    /// it node), it should place the value i32::min_value() in its corresponding
-    /// cell.
-    upper_bounds: Vec<isize>,
-    interrupted: bool,
+    /// The depth and state of the node being explored by each busy worker
+    in_flight: FxHashMap<usize, (usize, Arc<T>)>,
+    /// The number of times a popped node was being explored by another worker
+    concurrent_duplicates: usize,
+}
+impl<T> BarrierBookkeeping<T> {
+    fn new(nb_variables: usize) -> Self {
+        Self {
+            open_by_layer: vec![0; nb_variables + 1],
+            ongoing_by_layer: vec![0; nb_variables + 1],
+            completed_by_layer: vec![0; nb_variables + 1],
+            lowest_active_layer: 0,
+            in_flight: FxHashMap::default(),
+            concurrent_duplicates: 0,
+        }
+    }
 }
-/// The state which is shared among the many running threads: it provides an
-/// access to the critical data (protected by a mutex) as well as a monitor
-/// (condvar) to park threads in case of node-starvation.
-struct Shared<'a, P, R, O, W>
+
+impl<T> NodeProcessor<T> for BarrierProcessor<T>
 where
-    P: Problem + Send + Sync + 'a,
-    P::State: Eq + PartialEq + Hash + Clone,
-    R: Relaxation<State = P::State> + Send + Sync + 'a,
-    O: StateRanking<State = P::State> + Send + Sync + 'a,
-    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    T: Eq + Hash + Clone,
 {
-    problem: &'a P,
-    relaxation: &'a R,
-    ranking: &'a O,
-    width_heu: &'a W,
-    cutset_type: CutsetType,
-
-    /// This is the shared state data which can only be accessed within critical
-    /// sections. Therefore, it is protected by a mutex which prevents concurrent
-    /// reads/writes.
-    critical: Mutex<Critical<'a, O>>,
-    barriers: Arc<Vec<RwLock<FxHashMap<Arc<P::State>, BarrierInfo>>>>,
-    /// This is the monitor on which nodes must wait when facing an empty fringe.
-    /// The corollary, it that whenever a node has completed the processing of
-    /// a subproblem, it must wakeup all parked threads waiting on this monitor.
-    monitor: Condvar,
-}
-/// The workload a thread can get from the shared state
-enum WorkLoad<T> {
-    /// There is no work left to be done: you can safely terminate
-    Complete,
-    /// The work must stop because of an external cutoff
-    Interruption,
-    /// There is nothing you can do right now. Check again when you wake up
-    Starvation,
-    /// The item to process
-    WorkItem { node: SubProblem<T> },
+    type Dd = Barrier<T>;
+    type Bookkeeping = BarrierBookkeeping<T>;
+
+    fn new_dd(&self, cutset_type: CutsetType) -> Barrier<T> {
+        let (nodes, edges) = self.config.dd_capacity;
+        Barrier::with_capacity(self.barriers.clone(), cutset_type, nodes, edges)
+    }
+    fn explored(dd: &Barrier<T>) -> usize {
+        dd.get_explored()
+    }
+    fn footprint(dd: &Barrier<T>) -> usize {
+        dd.footprint()
+    }
+    fn shared_footprint(&self) -> usize {
+        self.barriers.footprint()
+    }
+
+    fn on_push(&self, counters: &mut BarrierBookkeeping<T>, node: &SubProblem<T>) {
+        counters.open_by_layer[node.depth()] += 1;
+    }
+    fn on_merge(&self, counters: &mut BarrierBookkeeping<T>, dropped: &SubProblem<T>) {
+        // only one of the two nodes remains on the fringe
+        counters.open_by_layer[dropped.depth()] -= 1;
+    }
+    fn on_get_workload(&self, counters: &mut BarrierBookkeeping<T>) {
+        if self.config.barrier_retention {
+            return;
+        }
+        // Can we clean up the barrier?
+        let nb_variables = self.barriers.nb_layers() - 1;
+        while counters.lowest_active_layer < nb_variables &&
+                counters.open_by_layer[counters.lowest_active_layer] + counters.ongoing_by_layer[counters.lowest_active_layer] == 0 {
+            self.barriers.clear_layer(counters.lowest_active_layer);
+            counters.lowest_active_layer += 1;
+        }
+    }
+    fn on_fringe_pruned(&self, counters: &mut BarrierBookkeeping<T>) {
+        counters.open_by_layer.iter_mut().for_each(|o| *o = 0);
+    }
+    fn on_node_pruned(&self, counters: &mut BarrierBookkeeping<T>, nn: &SubProblem<T>) {
+        counters.open_by_layer[nn.depth()] -= 1;
+    }
+    fn admit(&self, counters: &mut BarrierBookkeeping<T>, nn: &SubProblem<T>, best_lb: isize) -> Admission {
+        let depth = nn.depth();
+
+        let threshold = self.barriers.get_threshold(depth, &nn.state);
+        let explore = threshold.is_none_or(|info| {
+            nn.value > info.theta || (nn.value == info.theta && !info.explored)
+        });
+        self.barriers.record_lookup(depth, !explore);
+        if !explore {
+            self.barriers.log_lookup(depth, &nn.state, nn.value, threshold, true, best_lb);
+            counters.open_by_layer[depth] -= 1;
+            return Admission::Reject;
+        }
+
+        // Is someone else already busy with that state ?
+        let in_flight = counters.in_flight.values().any(|(d, state)| *d == depth && *state == nn.state);
+        if in_flight {
+            counters.concurrent_duplicates += 1;
+            match self.config.duplicate_policy {
+                DuplicatePolicy::Allow => {},
+                DuplicatePolicy::Wait  => return Admission::Wait,
+                DuplicatePolicy::Skip  => return Admission::Defer,
+            }
+        }
+
+        // a dd may have raised the threshold in the meantime
+        self.barriers.log_lookup(depth, &nn.state, nn.value, threshold, false, best_lb);
+        if !self.barriers.update_watched_threshold(depth, nn.state.clone(), nn.value, true, best_lb) {
+            let threshold = self.barriers.get_threshold(depth, &nn.state);
+            self.barriers.log_lookup(depth, &nn.state, nn.value, threshold, true, best_lb);
+            counters.open_by_layer[depth] -= 1;
+            return Admission::Reject;
+        }
+        Admission::Explore
+    }
+    fn on_start(&self, counters: &mut BarrierBookkeeping<T>, thread_id: usize, nn: &SubProblem<T>) {
+        let depth = nn.depth();
+        counters.open_by_layer[depth] -= 1;
+        counters.ongoing_by_layer[depth] += 1;
+        counters.in_flight.insert(thread_id, (depth, nn.state.clone()));
+    }
+    fn on_finish(&self, counters: &mut BarrierBookkeeping<T>, thread_id: usize, depth: usize) {
+        counters.ongoing_by_layer[depth] -= 1;
+        counters.completed_by_layer[depth] += 1;
+        counters.in_flight.remove(&thread_id);
+    }
+    fn on_abort(&self, counters: &mut BarrierBookkeeping<T>, thread_id: usize) {
+        counters.in_flight.remove(&thread_id);
+    }
+    fn on_suspend(&self, counters: &mut BarrierBookkeeping<T>, node: &SubProblem<T>) {
+        // the node was not explored after all: it must get through the
+        // barrier when the resolution is resumed
+        counters.completed_by_layer[node.depth()] -= 1;
+        self.barriers.reopen(node.depth(), &node.state, node.value);
+    }
 }
 
-pub struct BarrierParallelSolver<'a, P, R, O, W>
+/// The engine of a `BarrierParallelSolver` whose fringe pops the nodes in the
+/// order `C` (within the layers of its `FringePolicy`)
+type BarrierEngine<'a, P, R, O, W, C> = Engine<'a, P, R, O, W, StratifiedFrontier<'a, O, C>, BarrierProcessor<<P as Problem>::State>>;
+
+pub struct BarrierParallelSolver<'a, P, R, O, W, C = MaxUB<'a, O>>
 where
     P: Problem + Send + Sync + 'a,
     P::State: Eq + PartialEq + Hash + Clone,
@@ -109,12 +275,8 @@ where
     O: StateRanking<State = P::State> + Send + Sync + 'a,
     W: WidthHeuristic<P::State> + Send + Sync + 'a,
 {
-    /// This is the shared state. Each thread is going to take a reference to it.
-    shared: Shared<'a, P, R, O, W>,
-    /// This is a configuration parameter that tunes the number of threads that
-    /// will be spawned to solve the problem. By default, this number amounts
-    /// to the number of hardware threads available on the machine.
-    nb_threads: usize,
+    /// This is the branch-and-bound which does the actual work.
+    engine: BarrierEngine<'a, P, R, O, W, C>,
 }
 
 // private interface.
@@ -154,363 +316,563 @@ where
         cutset_type: CutsetType,
         nb_threads: usize,
     ) -> Self {
-        let mut barriers = vec![];
-        for _ in 0..=problem.nb_variables() {
-            barriers.push(RwLock::new(Default::default()));
-        }
-        let barriers = Arc::new(barriers);
+        Self::ordered(problem, relaxation, ranking, width_heu, cutset_type, nb_threads, MaxUB(ranking))
+    }
+}
+
+impl<'a, P, R, O, W, C> BarrierParallelSolver<'a, P, R, O, W, C>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    C: FrontierOrder<P::State>,
+{
+    /// Creates a solver like `custom`, whose fringe pops the nodes in the
+    /// given order rather than by decreasing upper bound. The ranking still
+    /// orders the states of the dds.
+    pub fn ordered(
+        problem: &'a P,
+        relaxation: &'a R,
+        ranking: &'a O,
+        width_heu: &'a W,
+        cutset_type: CutsetType,
+        nb_threads: usize,
+        order: C,
+    ) -> Self {
         BarrierParallelSolver {
-            shared: Shared {
+            engine: Engine::new(
                 problem,
                 relaxation,
                 ranking,
                 width_heu,
-                cutset_type: cutset_type,
-                //
-                monitor: Condvar::new(),
-                critical: Mutex::new(Critical {
-                    best_sol: None,
-                    best_lb: isize::MIN,
-                    best_ub: isize::MAX,
-                    upper_bounds: vec![isize::MAX; nb_threads],
-                    fringe: NoDupFrontier::new(ranking),
-                    ongoing: 0,
-                    explored: 0,
-                    explored_dd: 0,
-                    open_by_layer: vec![0; problem.nb_variables()+1],
-                    ongoing_by_layer: vec![0; problem.nb_variables()+1],
-                    lowest_active_layer: 0,
-                    interrupted: false,
-                }),
-                barriers: barriers
-            },
-            nb_threads,
-        }
-    }
-    /// Sets the number of threads used by the solver
+                cutset_type,
+                StratifiedFrontier::with_order(order, FringePolicy::default()),
+                nb_threads,
+                BarrierProcessor::new(problem.nb_variables(), DuplicatePolicy::default()),
+                BarrierBookkeeping::new(problem.nb_variables()),
+            ),
+        }
+    }
+    /// Replaces all the options of the solver at once (see `SolverConfig`)
+    pub fn with_config(mut self, config: SolverConfig) -> Self {
+        self.engine.set_config(config);
+        self
+    }
+    /// Replaces all the options which only the barrier solvers have at once (see `BarrierConfig`)
+    pub fn with_barrier_config(mut self, config: BarrierConfig<P::State>) -> Self {
+        self.set_barrier_config(config);
+        self
+    }
+    fn set_barrier_config(&mut self, config: BarrierConfig<P::State>) {
+        self.engine.fringe_mut().set_policy(config.fringe_policy);
+        self.engine.processor_mut().set_config(config);
+    }
+    /// Changes the options which only the barrier solvers have
+    fn configure_barrier(&mut self, f: impl FnOnce(&mut BarrierConfig<P::State>)) {
+        let mut config = self.engine.processor().config.clone();
+        f(&mut config);
+        self.set_barrier_config(config);
+    }
+    /// Sets `SolverConfig::nb_threads`
     pub fn with_nb_threads(mut self, nb_threads: usize) -> Self {
-        self.nb_threads = nb_threads;
+        self.engine.configure(|config| config.nb_threads = nb_threads);
+        self
+    }
+    /// Sets `SolverConfig::min_relax_depth_offset` (panics when it is too small)
+    pub fn with_min_relax_depth_offset(mut self, offset: usize) -> Self {
+        self.engine.configure(|config| config.min_relax_depth_offset = offset);
+        self
+    }
+    /// Sets `SolverConfig::restrict_first_layers`
+    pub fn with_first_layers_restriction(mut self, restrict: bool) -> Self {
+        self.engine.configure(|config| config.restrict_first_layers = restrict);
+        self
+    }
+    /// Sets `SolverConfig::max_forced_layers`
+    pub fn with_forced_layers(mut self, max: usize) -> Self {
+        self.engine.configure(|config| config.max_forced_layers = max);
+        self
+    }
+    /// Sets the dominance rule the dds apply to the exact nodes of each layer (see `Dominance`)
+    pub fn with_dominance(mut self, dominance: &'a (dyn DominanceChecker<P::State> + Sync)) -> Self {
+        self.engine.set_dominance(dominance);
+        self
+    }
+    /// Sets `SolverConfig::reuse_prefix`
+    pub fn with_prefix_reuse(mut self, reuse: bool) -> Self {
+        self.engine.configure(|config| config.reuse_prefix = reuse);
+        self
+    }
+    /// Sets `SolverConfig::merge_policy`
+    pub fn with_merge_policy(mut self, policy: MergePolicy) -> Self {
+        self.engine.configure(|config| config.merge_policy = policy);
+        self
+    }
+    /// Sets `SolverConfig::check_merge`
+    pub fn with_merge_check(mut self, check: bool) -> Self {
+        self.engine.configure(|config| config.check_merge = check);
+        self
+    }
+    /// Sets `SolverConfig::model_timing`
+    pub fn with_model_timing(mut self, timed: bool) -> Self {
+        self.engine.configure(|config| config.model_timing = timed);
+        self
+    }
+    /// Sets `SolverConfig::self_check`
+    pub fn with_self_check(mut self, check: bool) -> Self {
+        self.engine.configure(|config| config.self_check = check);
+        self
+    }
+    /// Sets `SolverConfig::on_violation`
+    pub fn with_violation_policy(mut self, policy: OnViolation) -> Self {
+        self.engine.configure(|config| config.on_violation = policy);
+        self
+    }
+    /// Sets `BarrierConfig::duplicate_policy`
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.configure_barrier(|config| config.duplicate_policy = policy);
+        self
+    }
+    /// Sets `BarrierConfig::dd_capacity`
+    pub fn with_dd_capacity(mut self, nodes: usize, edges: usize) -> Self {
+        self.configure_barrier(|config| config.dd_capacity = (nodes, edges));
+        self
+    }
+    /// Sets `BarrierConfig::barrier_depths` (panics when they are out of range)
+    pub fn with_barrier_depths(mut self, depths: Range<usize>) -> Self {
+        self.configure_barrier(|config| config.barrier_depths = Some(depths));
+        self
+    }
+    /// Sets `BarrierConfig::watched_state`
+    pub fn with_watched_state(mut self, state: P::State) -> Self {
+        self.configure_barrier(|config| config.watched_state = Some(state));
+        self
+    }
+    /// Sets `SolverConfig::push_filter`
+    pub fn with_push_filter(mut self, capacity: usize) -> Self {
+        self.engine.configure(|config| config.push_filter = capacity);
+        self
+    }
+    /// Breaks the ties among the solutions of equal value with the given secondary objective
+    pub fn with_secondary_objective(mut self, secondary: &'a (dyn SecondaryObjective + Sync)) -> Self {
+        self.engine.set_secondary_objective(secondary);
+        self
+    }
+    /// Sets `SolverConfig::tie_limit`
+    pub fn with_tie_limit(mut self, limit: usize) -> Self {
+        self.engine.configure(|config| config.tie_limit = limit);
+        self
+    }
+    /// Keeps the best distinct solutions found during the resolution in the given pool
+    pub fn with_solution_pool(mut self, pool: SolutionPool) -> Self {
+        self.engine.set_solution_pool(pool);
+        self
+    }
+    /// Sets `SolverConfig::verification`
+    pub fn with_verification(mut self, verify: bool) -> Self {
+        self.engine.configure(|config| config.verification = verify);
+        self
+    }
+    /// Seeds the best solution of the solver with a solution worth `value`, known beforehand
+    pub fn with_initial_lb(mut self, value: isize, solution: Vec<Decision>) -> Self {
+        self.engine.set_initial_solution(value, solution);
+        self
+    }
+    /// Sets `BarrierConfig::fringe_policy`
+    pub fn with_fringe_policy(mut self, policy: FringePolicy) -> Self {
+        self.configure_barrier(|config| config.fringe_policy = policy);
+        self
+    }
+    /// Sets `SolverConfig::shedding`
+    pub fn with_shedding(mut self, shedding: Shedding) -> Self {
+        self.engine.configure(|config| config.shedding = Some(shedding));
+        self
+    }
+    /// Sets `SolverConfig::cancellation`
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.engine.configure(|config| config.cancellation = token);
+        self
+    }
+    /// Tells the given observer about each improvement of the bounds (see `SolverObserver`)
+    pub fn with_observer(mut self, observer: &'a dyn SolverObserver) -> Self {
+        self.engine.set_observer(observer);
+        self
+    }
+    /// Sets `SolverConfig::restarts`
+    pub fn with_restarts(mut self, restarts: Restarts) -> Self {
+        self.engine.configure(|config| config.restarts = Some(restarts));
+        self
+    }
+    /// Sets `SolverConfig::tree_recording`
+    pub fn with_tree_recording(mut self, record: bool) -> Self {
+        self.engine.configure(|config| config.tree_recording = record);
+        self
+    }
+    /// Sets `SolverConfig::memory_sampling`
+    pub fn with_memory_sampling(mut self, period: usize) -> Self {
+        self.engine.configure(|config| config.memory_sampling = period);
+        self
+    }
+    /// Sets `SolverConfig::suspension`
+    pub fn with_suspension(mut self, suspend: bool) -> Self {
+        self.engine.configure(|config| config.suspension = suspend);
+        self
+    }
+    /// Sets `BarrierConfig::barrier_retention`
+    pub fn with_barrier_retention(mut self, retain: bool) -> Self {
+        self.configure_barrier(|config| config.barrier_retention = retain);
         self
     }
 
-    /// This method initializes the problem resolution. Put more simply, this
-    /// method posts the root node of the mdd onto the fringe so that a thread
-    /// can pick it up and the processing can be bootstrapped.
-    fn initialize(&self) {
-        let root = self.root_node();
-        let mut critical = self.shared.critical.lock();
-        critical.fringe.push(root);
-        critical.open_by_layer[0] += 1;
+    /// Returns the pool of solutions (see `with_solution_pool`)
+    pub fn solution_pool(&self) -> SolutionPool {
+        self.engine.solution_pool()
+    }
+    /// Returns the solutions of the pool, the best ones first, along with
+    /// their values (in the sense of the objective of the last resolution)
+    pub fn best_solutions(&self) -> Vec<(isize, Vec<Decision>)> {
+        self.engine.pooled_solutions()
+    }
+    pub fn get_explored(&self) -> usize {
+        self.engine.get_explored()
     }
 
-    fn root_node(&self) -> SubProblem<P::State> {
-        let shared = &self.shared;
-        SubProblem {
-            state: Arc::new(shared.problem.initial_state()),
-            value: shared.problem.initial_value(),
-            path: vec![],
-            ub: isize::MAX,
-        }
+    pub fn get_explored_dd(&self) -> usize {
+        self.engine.get_explored_dd()
     }
 
-    /// This method processes the given `node`. To do so, it reads the current
-    /// best lower bound from the critical data. Then it expands a restricted
-    /// and possibly a relaxed mdd rooted in `node`. If that is necessary,
-    /// it stores cutset nodes onto the fringe for further parallel processing.
-    fn process_one_node(
-        mdd: &mut Barrier<P::State>,
-        shared: &Shared<P, R, O, W>,
-        node: SubProblem<P::State>,
-    ) -> usize 
-    {
-        let mut explored_dd = 0;
-
-        // 1. RESTRICTION
-        let node_ub = node.ub;
-        let best_lb = Self::best_lb(shared);
-
-        if node_ub <= best_lb {
-            return explored_dd;
-        }
-
-        let width = shared.width_heu.max_width(&node.state);
-        let mut compilation = CompilationInput {
-            comp_type: CompilationType::Restricted,
-            max_width: width,
-            problem: shared.problem,
-            relaxation: shared.relaxation,
-            ranking: shared.ranking,
-            residual: node,
-            //
-            best_lb,
-        };
+    /// Returns the max and total size of the dds compiled during the resolution
+    pub fn get_compilation_statistics(&self) -> CompilationStatistics {
+        self.engine.get_compilation_statistics()
+    }
+    /// Returns the nodes that the dds compiled during the resolution have
+    /// restricted, merged and pruned (see `CompilationStats`)
+    pub fn compilation_stats(&self) -> CompilationStats {
+        self.get_compilation_statistics().counters
+    }
 
-        mdd.compile(&compilation);
-        explored_dd += mdd.get_explored();
-        Self::maybe_update_best(mdd, shared);
-        if mdd.is_exact() {
-            return explored_dd;
-        }
+    /// Returns the number of cutset nodes drained from the relaxed dds
+    pub fn get_cutset_generated(&self) -> usize {
+        self.engine.get_cutset_generated()
+    }
 
-        // 2. RELAXATION
-        let best_lb = Self::best_lb(shared);
-        compilation.comp_type = CompilationType::Relaxed;
-        compilation.best_lb = best_lb;
-        mdd.compile(&compilation);
-        explored_dd += mdd.get_explored();
-        if mdd.is_exact() {
-            Self::maybe_update_best(mdd, shared);
-        } else {
-            Self::enqueue_cutset(mdd, shared, node_ub);
-        }
+    /// Returns the number of cutset nodes which were pushed onto the fringe:
+    /// the others were pruned by the best lower bound (or by the push filter)
+    pub fn get_cutset_enqueued(&self) -> usize {
+        self.engine.get_cutset_enqueued()
+    }
 
-        return explored_dd;
+    /// Returns the number of cutset nodes which the push filter skipped
+    pub fn get_suppressed_pushes(&self) -> usize {
+        self.engine.get_suppressed_pushes()
     }
 
-    fn best_lb(shared: &Shared<P, R, O, W>) -> isize {
-        shared.critical.lock().best_lb
+    /// Returns the number of nodes which were shed from the fringe
+    pub fn get_shed_nodes(&self) -> usize {
+        self.engine.get_shed_nodes()
+    }
+    /// Returns the largest number of nodes that the fringe held at once
+    pub fn get_peak_fringe(&self) -> usize {
+        self.engine.get_peak_fringe()
+    }
+    /// Returns the number of nodes which were popped from the fringe and
+    /// discarded because their upper bound could not improve the best lower
+    /// bound (the shed nodes are not counted, see `get_shed_nodes`)
+    pub fn get_discarded_by_bound(&self) -> usize {
+        self.engine.get_discarded_by_bound()
+    }
+    /// Returns the number of nodes which were popped from the fringe and
+    /// discarded because the barrier dominates them (see `layer_statistics`
+    /// for the breakdown by depth)
+    pub fn get_discarded_by_barrier(&self) -> usize {
+        self.engine.get_discarded_by_barrier()
+    }
+    /// Returns the number of nodes which the compiled dds pruned because
+    /// their value did not exceed the threshold of the barrier
+    pub fn get_pruned_by_barrier(&self) -> usize {
+        self.compilation_stats().barrier_prunes
+    }
+    /// Returns the number of nodes which the compiled dds did not branch on
+    /// because their rough upper bound could not improve the best lower bound
+    pub fn get_pruned_by_rub(&self) -> usize {
+        self.compilation_stats().rub_prunes
+    }
+    /// Returns the number of fringe nodes which were skipped because the
+    /// barrier dominates them: this is `get_discarded_by_barrier`, named after
+    /// the pruning counters of the compilations
+    pub fn get_skipped_by_barrier(&self) -> usize {
+        self.get_discarded_by_barrier()
     }
 
-    /// This private method updates the shared best known node and lower bound in
-    /// case the best value of the current `mdd` expansion improves the current
-    /// bounds.
-    fn maybe_update_best(mdd: &Barrier<P::State>, shared: &Shared<P, R, O, W>) {
-        let mut shared = shared.critical.lock();
-        let dd_best_value = mdd.best_value().unwrap_or(isize::MIN);
-        if dd_best_value > shared.best_lb {
-            shared.best_lb = dd_best_value;
-            shared.best_sol = mdd.best_solution();
-        }
+    /// Returns the number of dives from the root made by the random restarts
+    pub fn get_dives(&self) -> usize {
+        self.engine.get_dives()
     }
-    /// If necessary, thightens the bound of nodes in the cutset of `mdd` and
-    /// then add the relevant nodes to the shared fringe.
-    fn enqueue_cutset(mdd: &mut Barrier<P::State>, shared: &Shared<P, R, O, W>, ub: isize) {
-        let mut critical = shared.critical.lock();
-        let best_lb = critical.best_lb;
 
-        mdd.drain_cutset(|mut cutset_node| {
-            cutset_node.ub = ub.min(cutset_node.ub);
-            if cutset_node.ub > best_lb {
-                let depth = cutset_node.path.len();
-                critical.fringe.push(cutset_node);
-                critical.open_by_layer[depth] += 1;
-            }
-        });
+    /// Returns the number of dives which improved the best solution
+    pub fn get_improving_dives(&self) -> usize {
+        self.engine.get_improving_dives()
+    }
+    /// Returns the token of `SolverConfig::cancellation`
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.engine.cancellation_token()
     }
-    /// Acknowledges that a thread finished processing its node.
-    fn notify_node_finished(shared: &Shared<P, R, O, W>, thread_id: usize, depth: usize, explored_dd: usize) {
-        let mut critical = shared.critical.lock();
-        critical.ongoing -= 1;
-        critical.upper_bounds[thread_id] = isize::MAX;
-        critical.ongoing_by_layer[depth] -= 1;
-        critical.explored_dd += explored_dd;
 
-        shared.monitor.notify_all();
+    /// Returns the violations found by the self check of the model
+    pub fn violations(&self) -> Vec<Violation<P::State>> {
+        self.engine.violations()
     }
 
-    /// Consults the shared state to fetch a workload. Depending on the current
-    /// state, the workload can either be:
-    ///
-    ///   + Complete, when the problem is solved and all threads should stop
-    ///   + Starvation, when there is no subproblem available for processing
-    ///     at the time being (but some subproblem are still being processed
-    ///     and thus the problem cannot be considered solved).
-    ///   + WorkItem, when the thread successfully obtained a subproblem to
-    ///     process.
-    fn get_workload<I>(
-        shared: &Shared<P, R, O, W>,
-        thread_id: usize,
-        interrupt: I,
-    ) -> WorkLoad<P::State>
-    where
-        I: Fn() -> bool,
-    {
-        let mut critical = shared.critical.lock();
+    /// Returns the reason why the best solution failed its verification
+    /// during the last resolution (if it did)
+    pub fn verification_error(&self) -> Option<VerificationError> {
+        self.engine.verification_error()
+    }
 
-        // Can we clean up the barrier?
-        while critical.lowest_active_layer < shared.problem.nb_variables() &&
-                critical.open_by_layer[critical.lowest_active_layer] + critical.ongoing_by_layer[critical.lowest_active_layer] == 0 {
-            shared.barriers[critical.lowest_active_layer].write().clear();
-            critical.lowest_active_layer += 1;
-        }
-
-        // Are we done ?
-        if critical.ongoing == 0 && critical.fringe.is_empty() {
-            critical.best_ub = critical.best_lb;
-            return WorkLoad::Complete;
-        }
-
-        // Do we need to stop
-        if critical.interrupted {
-            return WorkLoad::Interruption;
-        } else if interrupt() {
-            critical.interrupted = true;
-
-            critical.best_ub = if critical.ongoing > 0 {
-                critical
-                    .upper_bounds
-                    .iter()
-                    .copied()
-                    .filter(|x| *x != isize::MAX)
-                    .max()
-                    .unwrap_or(isize::MAX)
-            } else {
-                let nn = critical.fringe.pop().unwrap();
-                nn.ub
-            };
+    /// Returns what the barrier did at each depth: how often it pruned a node
+    /// (or let it through), and how many thresholds it stored (or raised)
+    pub fn barrier_layer_stats(&self) -> Vec<BarrierLayerStats> {
+        self.engine.processor().barriers.layer_stats()
+    }
 
-            critical.fringe.clear();
-            return WorkLoad::Interruption;
-        }
+    /// Returns the latest interactions with the threshold of the watched
+    /// state (see `with_watched_state`), from the oldest to the newest: only
+    /// the last `DEFAULT_WATCH_CAPACITY` of them are kept
+    pub fn watched_state_log(&self) -> Vec<BarrierEvent> {
+        self.engine.processor().barriers.watched_state_log()
+    }
 
-        // Nothing to do yet ? => Wait for someone to post jobs
-        if critical.fringe.is_empty() {
-            shared.monitor.wait(&mut critical);
-            return WorkLoad::Starvation;
-        }
-        // Nothing relevant ? =>  Wait for someone to post jobs
-        let mut nn = critical.fringe.pop().unwrap();
-        loop {
-            if nn.ub <= critical.best_lb {
-                critical.fringe.clear();
-                critical.open_by_layer.iter_mut().for_each(|o| *o = 0);
-                return WorkLoad::Starvation;
-            }
+    /// Returns, for each depth, the number of nodes which are on the fringe,
+    /// which are being explored and which were explored, along with those
+    /// which were discarded when they were popped (over all the resolutions
+    /// of the solver). After a complete resolution, the open and ongoing
+    /// counts are all zero.
+    pub fn layer_statistics(&self) -> Vec<LayerStats> {
+        let (by_bound, by_barrier) = self.engine.discarded_by_depth();
+        self.engine.with_bookkeeping(|counters| {
+            counters
+                .open_by_layer
+                .iter()
+                .zip(counters.ongoing_by_layer.iter())
+                .zip(counters.completed_by_layer.iter())
+                .enumerate()
+                .map(|(depth, ((&open, &ongoing), &completed))| LayerStats {
+                    open,
+                    ongoing,
+                    completed,
+                    discarded_by_bound: by_bound.get(depth).copied().unwrap_or(0),
+                    discarded_by_barrier: by_barrier.get(depth).copied().unwrap_or(0),
+                })
+                .collect()
+        })
+    }
 
-            let depth = nn.path.len();
+    /// Returns the number of times a worker popped a node whose state was
+    /// being explored by another worker
+    pub fn get_concurrent_duplicates(&self) -> usize {
+        self.engine.with_bookkeeping(|counters| counters.concurrent_duplicates)
+    }
 
-            let explore = shared.barriers[depth].read().get(&nn.state).map_or(true, |info| {
-                if nn.value > info.theta || (nn.value == info.theta && !info.explored) {
-                    true
-                } else {
-                    critical.open_by_layer[depth] -= 1;
-                    false
-                }
-            });
+    /// Returns the branch-and-bound tree explored during the resolution (if
+    /// it was recorded)
+    pub fn search_tree(&self) -> Option<SearchTree> {
+        self.engine.search_tree()
+    }
 
-            if explore {
-                shared.barriers[depth].write().insert(nn.state.clone(), BarrierInfo {theta: nn.value, explored: true});
-                break;
-            }
+    /// Returns the memory sample at which the dds, the fringe and the barrier
+    /// held the most bytes altogether (if the memory was sampled)
+    pub fn peak_attribution(&self) -> Option<MemoryAttribution> {
+        self.engine.peak_attribution()
+    }
+}
 
-            if critical.fringe.is_empty() {
-                return WorkLoad::Starvation;
-            }
+impl<'a, P, R, O, W, C> BarrierParallelSolver<'a, P, R, O, W, C>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    C: FrontierOrder<P::State> + Send + Sync,
+{
+    /// Seeds the best solution of the solver with restricted dds of the whole
+    /// problem, whose width doubles until the budget is spent (see
+    /// `WarmStartBudget`). This is meant to be called right before the
+    /// resolution: the first nodes of the branch-and-bound are then pruned
+    /// with the best lower bound of the warm start, which is also kept when
+    /// the resolution is interrupted right away.
+    pub fn warm_start(&mut self, budget: WarmStartBudget) {
+        self.engine.warm_start(budget)
+    }
+    /// Makes the first resolution start from the given nodes rather than from the root
+    pub fn with_initial_fringe(mut self, nodes: Vec<SubProblem<P::State>>) -> Self {
+        self.engine.set_initial_fringe(nodes);
+        self
+    }
+    /// Returns the nodes which the last resolution did not explore, for
+    /// another solver to resume it (see `with_initial_fringe`). There are
+    /// none unless that resolution was suspended (see `with_suspension`).
+    pub fn into_open_subproblems(self) -> Vec<SubProblem<P::State>> {
+        self.engine.into_open_subproblems()
+    }
+}
 
-            nn = critical.fringe.pop().unwrap();
+#[cfg(feature = "checkpoint")]
+impl<'a, P, R, O, W> BarrierParallelSolver<'a, P, R, O, W>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync + serde::Serialize + serde::de::DeserializeOwned,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+{
+    /// Saves the resolution to the given file, for `resume_from` to pick it
+    /// up: the nodes of the fringe, the thresholds of the barriers and the
+    /// best solution. This is meant to be called between two resolutions:
+    /// the last one must either be complete or suspended (see
+    /// `with_suspension`), otherwise the nodes it discarded are lost and this
+    /// fails with an `InvalidInput` error.
+    pub fn checkpoint(&self, path: &Path) -> io::Result<()> {
+        let search = self.engine.search_state().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the interrupted resolution was not suspended: its fringe is lost")
+        })?;
+        let barriers = self.engine.processor().barriers.thresholds();
+        Checkpoint { search, barriers }.save(path)
+    }
+    /// Creates a solver like `new`, which resumes the resolution saved by
+    /// `checkpoint` in the given file: its next resolution starts from the
+    /// saved fringe, barriers and best solution, with the objective of the
+    /// saved one. Just like the one of `new`, the solver is configured with
+    /// the `with_*` methods (it suspends its interrupted resolutions, so that
+    /// it may be checkpointed again).
+    pub fn resume_from(path: &Path, problem: &'a P, relaxation: &'a R, ranking: &'a O, width_heu: &'a W, cutset_type: CutsetType) -> io::Result<Self> {
+        let Checkpoint { search, barriers } = Checkpoint::load(path)?;
+        let mut solver = Self::new(problem, relaxation, ranking, width_heu, cutset_type).with_suspension(true);
+        let store = &solver.engine.processor().barriers;
+        if barriers.len() != store.nb_layers() {
+            let msg = format!("the checkpoint has {} barrier layers where the problem has {}", barriers.len(), store.nb_layers());
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
         }
-
-        // Consume the current node and process it
-        critical.ongoing += 1;
-        critical.explored += 1;
-        critical.upper_bounds[thread_id] = nn.ub;
-
-        let depth = nn.path.len();
-        critical.open_by_layer[depth] -= 1;
-        critical.ongoing_by_layer[depth] += 1;
-
-        WorkLoad::WorkItem { node: nn }
+        store.restore_thresholds(barriers);
+        solver.engine.restore_search_state(search);
+        Ok(solver)
     }
+}
 
-    pub fn get_explored(&self) -> usize {
-        return self.shared.critical.lock().explored;
+#[cfg(feature = "checkpoint")]
+impl<'a, P, R, O, W, C> BarrierParallelSolver<'a, P, R, O, W, C>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync + serde::Serialize + serde::de::DeserializeOwned,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    C: FrontierOrder<P::State> + Send + Sync,
+{
+    /// Writes the thresholds of the barriers to the given writer, for
+    /// `load_barrier` to spare another solver their proof. The best solution
+    /// is written along with them, as the thresholds only hold against it.
+    /// The last resolution must be complete (the thresholds of an interrupted
+    /// one depend on its fringe: see `checkpoint`), otherwise this fails with
+    /// an `InvalidInput` error. Unless the solver retains its thresholds (see
+    /// `with_barrier_retention`), most of them are gone by then.
+    pub fn dump_barrier<Wr: Write>(&self, writer: Wr) -> io::Result<()> {
+        let search = self.engine.search_state().filter(|search| !search.suspended).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the thresholds of an interrupted resolution depend on its fringe")
+        })?;
+        let barriers = self.engine.processor().barriers.thresholds();
+        Checkpoint { search, barriers }.write_to(writer)
     }
-
-    pub fn get_explored_dd(&self) -> usize {
-        return self.shared.critical.lock().explored_dd;
+    /// Reads the thresholds written by `dump_barrier` from the given reader:
+    /// the next resolution starts from the root with these thresholds and the
+    /// best solution they were proved against, with the objective of the
+    /// resolution which proved them. This is meant to be called on a fresh
+    /// solver, before its first resolution.
+    pub fn load_barrier<Rd: Read>(&mut self, reader: Rd) -> io::Result<()> {
+        let Checkpoint { search, barriers } = Checkpoint::read_from(reader)?;
+        let store = &self.engine.processor().barriers;
+        if barriers.len() != store.nb_layers() {
+            let msg = format!("the barrier file has {} layers where the problem has {}", barriers.len(), store.nb_layers());
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        }
+        store.restore_thresholds(barriers);
+        self.engine.restore_incumbent(search);
+        Ok(())
     }
 }
 
-impl<'a, P, R, O, W> Solver for BarrierParallelSolver<'a, P, R, O, W>
+impl<'a, P, R, O, W, C> Solver for BarrierParallelSolver<'a, P, R, O, W, C>
 where
     P: Problem + Send + Sync + 'a,
     P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
     R: Relaxation<State = P::State> + Send + Sync + 'a,
     O: StateRanking<State = P::State> + Send + Sync + 'a,
     W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    C: FrontierOrder<P::State> + Send + Sync,
 {
     /// Applies the branch and bound algorithm proposed by Bergman et al. to
     /// solve the problem to optimality. To do so, it spawns `nb_threads` workers
     /// (long running threads); each of which will continually get a workload
     /// and process it until the problem is solved.
     fn maximize(&mut self) {
-        self.initialize();
-
-        std::thread::scope(|s| {
-            for i in 0..self.nb_threads {
-                let shared = &self.shared;
-                s.spawn(move || {
-                    let mut mdd = Barrier::<P::State>::new(shared.barriers.clone(), shared.cutset_type);
-                    loop {
-                        match Self::get_workload(shared, i, || false) {
-                            WorkLoad::Complete => break,
-                            WorkLoad::Interruption => break, // this one cannot occur
-                            WorkLoad::Starvation => continue,
-                            WorkLoad::WorkItem { node } => {
-                                let depth = node.path.len();
-                                let explored_dd = Self::process_one_node(&mut mdd, shared, node);
-                                Self::notify_node_finished(shared, i, depth, explored_dd);
-                            }
-                        }
-                    }
-                });
-            }
-        });
+        self.engine.maximize_with_cutoff(InterruptCutoff(|| false));
+    }
+
+    /// Solves the problem like `maximize`, but minimizes its objective.
+    fn minimize(&mut self) {
+        self.engine.minimize_with_cutoff(InterruptCutoff(|| false));
     }
 
     /// Returns the best solution that has been identified for this problem.
     fn best_solution(&self) -> Option<Vec<Decision>> {
-        self.shared.critical.lock().best_sol.clone()
+        self.engine.best_solution()
     }
     /// Returns the value of the best solution that has been identified for
     /// this problem.
     fn best_value(&self) -> Option<isize> {
-        let critical = self.shared.critical.lock();
-        critical.best_sol.as_ref().map(|_sol| critical.best_lb)
+        self.engine.best_value()
     }
 }
 
-impl<'a, P, R, O, W> InterruptibleSolver for BarrierParallelSolver<'a, P, R, O, W>
+impl<'a, P, R, O, W, C> InterruptibleSolver for BarrierParallelSolver<'a, P, R, O, W, C>
 where
     P: Problem + Send + Sync + 'a,
     P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
     R: Relaxation<State = P::State> + Send + Sync + 'a,
     O: StateRanking<State = P::State> + Send + Sync + 'a,
     W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    C: FrontierOrder<P::State> + Send + Sync,
 {
-    fn maximize_with_interrupt<I>(&mut self, interrupt: I) -> crate::ResolutionStatus
+    fn maximize_with_cutoff<K>(&mut self, cutoff: K) -> ResolutionStatus
     where
-        I: Fn() -> bool + Send + Sync + 'static,
+        K: Cutoff + Send + Sync + 'static,
     {
-        self.initialize();
-        let callback = &interrupt;
-        std::thread::scope(|s| {
-            for i in 0..self.nb_threads {
-                let shared = &self.shared;
-                s.spawn(move || {
-                    let mut mdd = Barrier::<P::State>::new(shared.barriers.clone(), shared.cutset_type);
-                    loop {
-                        match Self::get_workload(shared, i, callback) {
-                            WorkLoad::Complete => break,
-                            WorkLoad::Interruption => break, // this one cannot occur
-                            WorkLoad::Starvation => continue,
-                            WorkLoad::WorkItem { node } => {
-                                let depth = node.path.len();
-                                let explored_dd = Self::process_one_node(&mut mdd, shared, node);
-                                Self::notify_node_finished(shared, i, depth, explored_dd);
-                            }
-                        }
-                    }
-                });
-            }
-        });
+        self.engine.maximize_with_cutoff(cutoff)
+    }
 
-        let lock = self.shared.critical.lock();
-        if !lock.interrupted {
-            ResolutionStatus::Proved
-        } else {
-            ResolutionStatus::Interrupted
-        }
+    fn minimize_with_cutoff<K>(&mut self, cutoff: K) -> ResolutionStatus
+    where
+        K: Cutoff + Send + Sync + 'static,
+    {
+        self.engine.minimize_with_cutoff(cutoff)
+    }
+
+    fn maximize_with_cutoff_and_progress<K, G>(&mut self, cutoff: K, on_improvement: G) -> ResolutionStatus
+    where
+        K: Cutoff + Send + Sync + 'static,
+        G: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.engine.maximize_with_progress(cutoff, on_improvement)
+    }
+
+    fn minimize_with_cutoff_and_progress<K, G>(&mut self, cutoff: K, on_improvement: G) -> ResolutionStatus
+    where
+        K: Cutoff + Send + Sync + 'static,
+        G: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.engine.minimize_with_progress(cutoff, on_improvement)
     }
 
     fn best_value_so_far(&self) -> Option<isize> {
@@ -522,10 +884,1307 @@ where
     }
 
     fn best_lower_bound(&self) -> isize {
-        self.shared.critical.lock().best_lb
+        self.engine.best_lower_bound()
     }
 
     fn best_upper_bound(&self) -> isize {
-        self.shared.critical.lock().best_ub
+        self.engine.best_upper_bound()
+    }
+}
+
+#[cfg(test)]
+mod test_barrier_solver {
+    use std::{sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Arc}, thread, time::{Duration, Instant}};
+
+    use crate::solver::engine::{Admission, NodeProcessor};
+    use crate::test_utils::{
+        Chained, ChainedRelax, DepthProbe, Diamond, DiamondRelax, Knapsack, KnapsackBrokenRelax, KnapsackRanking, KnapsackRelax, KnapsackState, KnapsackStingyRelax, Tweaked,
+    };
+    use crate::{
+        All, Barrier, BarrierAction, BarrierLayerStats, BarrierParallelSolver, BarrierStore, CancellationToken, CompilationInputBuilder, CompilationStatistics, CompilationStats, CompilationType, CutsetType, Decision, DecisionDiagram, DuplicatePolicy, Fixed,
+        FringePolicy, Frontier, InterruptibleSolver, LayerStats, MaxValue, MergePolicy, MinimizeProblem, Negated, NoDupFrontier, OnViolation, ParallelSolver, Problem, PushOutcome, ResolutionStatus,
+        NodeCutoff, PooledBarrierParallelSolver, SecondaryObjective, Shedding, Solver, SubProblem, TraceCollector, Variable, Violation, WarmStartBudget,
+    };
+
+    use super::{BarrierBookkeeping, BarrierProcessor};
+
+    #[test]
+    fn the_optimum_is_found_whatever_the_valid_offset() {
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            for offset in 1..=3 {
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 2)
+                    .with_min_relax_depth_offset(offset);
+                solver.maximize();
+                assert_eq!(Some(13), solver.best_value());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn an_offset_of_zero_is_rejected() {
+        let problem = Knapsack::toy();
+        let _ = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1)
+            .with_min_relax_depth_offset(0);
+    }
+
+    #[test]
+    fn restricting_the_first_layers_keeps_the_optimum() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..20).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..20).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut expected = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), CutsetType::LastExactLayer, &mut fringe, 1);
+        expected.maximize();
+
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            for offset in [1, 3] {
+                let mut solver = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, 2)
+                    .with_min_relax_depth_offset(offset)
+                    .with_first_layers_restriction(true);
+                solver.maximize();
+                assert_eq!(expected.best_value(), solver.best_value());
+                assert_eq!(solver.best_lower_bound(), solver.best_upper_bound());
+
+                let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, &mut fringe, 2)
+                    .with_min_relax_depth_offset(offset)
+                    .with_first_layers_restriction(true);
+                solver.maximize();
+                assert_eq!(expected.best_value(), solver.best_value());
+                assert_eq!(solver.best_lower_bound(), solver.best_upper_bound());
+            }
+        }
+    }
+
+    #[test]
+    fn a_chunked_merge_policy_keeps_the_optimum() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..20).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..20).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut expected = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), CutsetType::LastExactLayer, &mut fringe, 1);
+        expected.maximize();
+
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            let mut solver = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), cutset_type, 2)
+                .with_merge_policy(MergePolicy::Chunked(2));
+            solver.maximize();
+            assert_eq!(expected.best_value(), solver.best_value());
+
+            let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+            let mut solver = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), cutset_type, &mut fringe, 2)
+                .with_merge_policy(MergePolicy::Chunked(3));
+            solver.maximize();
+            assert_eq!(expected.best_value(), solver.best_value());
+        }
+
+        assert_eq!(Ok(MergePolicy::SingleNode), "single".parse());
+        assert_eq!(Ok(MergePolicy::Chunked(2)), "chunked".parse());
+        assert_eq!(Ok(MergePolicy::Chunked(4)), "chunked:4".parse());
+        assert!("chunked:0".parse::<MergePolicy>().is_err());
+        assert_eq!("chunked:4", MergePolicy::Chunked(4).to_string());
+    }
+
+    #[test]
+    fn collapsing_the_forced_layers_keeps_the_optimum() {
+        let problem = Chained::toy();
+        for nb_threads in [1, 2] {
+            let mut layered = BarrierParallelSolver::custom(&problem, &ChainedRelax, &ChainedRelax, &Fixed(2), CutsetType::LastExactLayer, nb_threads);
+            layered.maximize();
+            let mut collapsed = BarrierParallelSolver::custom(&problem, &ChainedRelax, &ChainedRelax, &Fixed(2), CutsetType::LastExactLayer, nb_threads)
+                .with_forced_layers(usize::MAX);
+            collapsed.maximize();
+
+            assert_eq!(Some(10), collapsed.best_value());
+            assert_eq!(layered.best_value(), collapsed.best_value());
+            assert_eq!(problem.nb_variables(), collapsed.best_solution().unwrap().len());
+            assert_eq!(0, layered.compilation_stats().collapsed_layers);
+            assert!(collapsed.compilation_stats().collapsed_layers > 0);
+        }
+    }
+
+    #[test]
+    fn the_width_heuristic_is_given_the_depth_of_each_subproblem() {
+        let problem = Knapsack::toy();
+        let width = DepthProbe::default();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &width, CutsetType::LastExactLayer, 2);
+        solver.maximize();
+        assert_eq!(Some(13), solver.best_value());
+
+        let depths = width.depths.lock().unwrap();
+        assert_eq!(solver.get_explored(), depths.len());
+        assert!(depths.iter().all(|(depth, state_depth)| depth == state_depth));
+        assert!(depths.iter().any(|(depth, _)| *depth > 0));
+    }
+
+    #[test]
+    fn the_compilation_stats_add_up_the_counters_of_every_dd() {
+        let problem = Knapsack::toy();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        solver.maximize();
+        let stats = solver.compilation_stats();
+        assert!(stats.restricted_nodes > 0 && stats.relaxed_merges > 0);
+        assert!(stats.lel_depth.is_some());
+        assert_eq!(solver.get_compilation_statistics().counters, stats);
+        assert!(stats.total_arcs >= solver.get_compilation_statistics().total_edges);
+    }
+
+    #[test]
+    fn compilation_statistics_account_for_every_dd() {
+        let problem = Knapsack::toy();
+        // wide enough for the root restriction to be exact
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(100), CutsetType::Frontier, 1);
+        solver.maximize();
+        assert_eq!(CompilationStatistics {
+            nb_compilations: 1,
+            max_nodes: 41,
+            total_nodes: 41,
+            max_active_nodes: 41,
+            total_active_nodes: 41,
+            max_edges: 54,
+            total_edges: 54,
+            total_avoided_allocations: 14,
+            total_reservations: 4,
+            total_reallocations: 2,
+            model_time: Duration::ZERO,
+            framework_time: Duration::ZERO,
+            counters: CompilationStats { total_arcs: 54, ..Default::default() },
+        }, solver.get_compilation_statistics());
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2);
+        solver.maximize();
+        let stats = solver.get_compilation_statistics();
+        assert!(stats.nb_compilations > 1);
+        assert!(stats.max_nodes < stats.total_nodes);
+        assert!(stats.max_active_nodes <= stats.max_nodes);
+        assert!(stats.total_active_nodes <= stats.total_nodes);
+    }
+
+    #[test]
+    fn the_time_spent_in_a_slow_estimate_is_reported_as_model_time() {
+        let delay = Duration::from_millis(1);
+        let (problem, calls) = Tweaked::slow_estimate(Knapsack::toy(), delay);
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2)
+            .with_model_timing(true);
+        solver.maximize();
+        assert_eq!(Some(13), solver.best_value());
+
+        let stats = solver.get_compilation_statistics();
+        assert!(stats.model_time >= delay * calls.load(Ordering::Relaxed) as u32);
+        assert!(stats.model_time_share().unwrap() > 0.5);
+    }
+
+    #[test]
+    fn the_peak_memory_is_attributed_to_the_dds_the_fringe_and_the_barrier() {
+        let problem = Knapsack::toy();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2);
+        solver.maximize();
+        assert_eq!(None, solver.peak_attribution());
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2)
+            .with_memory_sampling(1);
+        solver.maximize();
+        assert_eq!(Some(13), solver.best_value());
+
+        let peak = solver.peak_attribution().unwrap();
+        let peaks = solver.engine.component_memory_peaks().unwrap();
+        assert!(peaks.dds > 0 && peaks.barrier > 0);
+        assert_eq!(peak.total(), peak.dds + peak.fringe + peak.barrier);
+        // no component may exceed its own peak, and the largest sum is at
+        // least as large as the largest of the component peaks
+        assert!(peak.dds <= peaks.dds && peak.fringe <= peaks.fringe && peak.barrier <= peaks.barrier);
+        assert!(peak.total() >= peaks.dds.max(peaks.fringe).max(peaks.barrier));
+        assert!(peak.total() <= peaks.total());
+    }
+
+    #[test]
+    fn the_shared_paths_are_complete_and_freed() {
+        let problem = Knapsack::toy();
+        // a narrow width for the solution to be found deep in the b&b tree
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 2);
+        solver.maximize();
+
+        let mut solution = solver.best_solution().unwrap();
+        solution.sort_unstable_by_key(|d| d.var.id());
+        assert_eq!((0..problem.nb_variables()).collect::<Vec<_>>(), solution.iter().map(|d| d.var.id()).collect::<Vec<_>>());
+        let weight = solution.iter().map(|d| d.value as usize * problem.weight[d.var.id()]).sum::<usize>();
+        let profit = solution.iter().map(|d| d.value as usize * problem.profit[d.var.id()]).sum::<usize>();
+        assert!(weight <= problem.capacity);
+        assert_eq!(13, profit);
+
+        assert!(solver.get_explored() > 1);
+        assert_eq!(0, solver.engine.live_path_segments());
+    }
+
+    fn node(state: KnapsackState, value: isize) -> SubProblem<KnapsackState> {
+        SubProblem {
+            path: vec![Decision { var: Variable(0), value: 0 }; state.depth],
+            state: Arc::new(state),
+            value,
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        }
+    }
+
+    /// Two workers pop distinct nodes having the same state: the first one
+    /// starts exploring it, the second one pops the better node meanwhile.
+    fn concurrent_duplicate(policy: DuplicatePolicy) -> (Admission, BarrierProcessor<KnapsackState>, BarrierBookkeeping<KnapsackState>) {
+        let processor = BarrierProcessor::new(3, policy);
+        let mut counters = BarrierBookkeeping::new(3);
+        let state = KnapsackState { depth: 1, capacity: 5 };
+        let first = node(state.clone(), 3);
+        let second = node(state, 5);
+        processor.on_push(&mut counters, &first);
+        processor.on_push(&mut counters, &second);
+
+        assert_eq!(Admission::Explore, processor.admit(&mut counters, &first, isize::MIN));
+        processor.on_start(&mut counters, 0, &first);
+        let admission = processor.admit(&mut counters, &second, isize::MIN);
+        assert_eq!(1, counters.concurrent_duplicates);
+        (admission, processor, counters)
+    }
+
+    #[test]
+    fn a_state_in_flight_is_only_compiled_once_unless_allowed() {
+        let (admission, _, _) = concurrent_duplicate(DuplicatePolicy::Allow);
+        assert_eq!(Admission::Explore, admission);
+
+        for (policy, expected) in [(DuplicatePolicy::Wait, Admission::Wait), (DuplicatePolicy::Skip, Admission::Defer)] {
+            let (admission, processor, mut counters) = concurrent_duplicate(policy);
+            assert_eq!(expected, admission);
+            // the better node can be explored once the first one is done
+            processor.on_finish(&mut counters, 0, 1);
+            let second = node(KnapsackState { depth: 1, capacity: 5 }, 5);
+            assert_eq!(Admission::Explore, processor.admit(&mut counters, &second, isize::MIN));
+        }
+    }
+
+    #[test]
+    fn the_registry_is_cleaned_up_when_a_worker_panics() {
+        let (_, processor, mut counters) = concurrent_duplicate(DuplicatePolicy::Wait);
+        processor.on_abort(&mut counters, 0);
+        assert!(counters.in_flight.is_empty());
+    }
+
+    /// The first worker claims a state, then the cutset of some other node
+    /// brings that state back with a better value (and an unrelated state
+    /// along with it) while the first worker is still exploring it. The
+    /// second worker then claims a node: the better duplicate is only handed
+    /// over when the policy allows it, or once the first worker is done.
+    #[test]
+    fn two_workers_racing_on_a_state_compile_it_once_unless_allowed() {
+        let problem = Knapsack::toy();
+        let raced = KnapsackState { depth: 1, capacity: 5 };
+        let other = KnapsackState { depth: 1, capacity: 7 };
+        for policy in [DuplicatePolicy::Allow, DuplicatePolicy::Wait, DuplicatePolicy::Skip] {
+            let solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 2)
+                .with_duplicate_policy(policy);
+            let engine = &solver.engine;
+            engine.post_node(node(raced.clone(), 3));
+            let first = engine.claim_node(0).unwrap();
+            engine.post_node(SubProblem { ub: 20, ..node(raced.clone(), 5) });
+            engine.post_node(SubProblem { ub: 10, ..node(other.clone(), 0) });
+
+            let released = AtomicBool::new(false);
+            let second = thread::scope(|scope| {
+                let second = scope.spawn(|| {
+                    let second = loop {
+                        if let Some(node) = engine.claim_node(1) {
+                            break node;
+                        }
+                    };
+                    (second, released.load(Ordering::SeqCst))
+                });
+                if policy == DuplicatePolicy::Wait {
+                    // the second worker met the duplicate in the critical
+                    // section which it leaves to wait for the first one
+                    while solver.get_concurrent_duplicates() == 0 {
+                        thread::yield_now();
+                    }
+                    released.store(true, Ordering::SeqCst);
+                    engine.release_node(0, &first);
+                }
+                second.join().unwrap()
+            });
+            assert_eq!(1, solver.get_concurrent_duplicates());
+
+            match policy {
+                DuplicatePolicy::Allow => {
+                    // both workers compile the same state
+                    assert_eq!((raced.clone(), 5, false), (second.0.state.as_ref().clone(), second.0.value, second.1));
+                }
+                DuplicatePolicy::Wait => {
+                    assert_eq!((raced.clone(), 5, true), (second.0.state.as_ref().clone(), second.0.value, second.1));
+                }
+                DuplicatePolicy::Skip => {
+                    // the second worker explores the other state meanwhile
+                    assert_eq!(other, *second.0.state);
+                    engine.release_node(0, &first);
+                    let third = engine.claim_node(0).unwrap();
+                    assert_eq!((raced.clone(), 5), (third.state.as_ref().clone(), third.value));
+                }
+            }
+        }
+    }
+
+    /// A skipped duplicate goes back to the fringe while a node with a lower
+    /// ub is explored: an interruption must still account for its ub
+    #[test]
+    fn the_bound_of_an_interrupted_resolution_covers_the_skipped_duplicates() {
+        let problem = Knapsack::toy();
+        let raced = KnapsackState { depth: 1, capacity: 5 };
+        let token = CancellationToken::new();
+        let solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 2)
+            .with_duplicate_policy(DuplicatePolicy::Skip)
+            .with_cancellation_token(token.clone());
+        let engine = &solver.engine;
+        engine.post_node(SubProblem { ub: 30, ..node(raced.clone(), 3) });
+        let first = engine.claim_node(0).unwrap();
+        engine.post_node(SubProblem { ub: 20, ..node(raced.clone(), 5) });
+        engine.post_node(SubProblem { ub: 10, ..node(KnapsackState { depth: 1, capacity: 7 }, 0) });
+        assert_eq!(10, engine.claim_node(1).unwrap().ub);
+        engine.release_node(0, &first);
+
+        token.cancel();
+        assert!(engine.claim_node(0).is_none());
+        assert!(solver.best_upper_bound() >= 20);
+    }
+
+    /// A worker pushes a child of its node after the node being explored by
+    /// the other worker was popped: the child's ub counts when interrupted,
+    /// whatever the fringe policy
+    #[test]
+    fn the_bound_of_an_interrupted_resolution_covers_the_nodes_pushed_meanwhile() {
+        let problem = Knapsack::toy();
+        for policy in [FringePolicy::BestBound, FringePolicy::DeepestLayer] {
+            let token = CancellationToken::new();
+            let solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 2)
+                .with_fringe_policy(policy)
+                .with_cancellation_token(token.clone());
+            let engine = &solver.engine;
+            engine.post_node(SubProblem { ub: 100, ..node(KnapsackState { depth: 1, capacity: 5 }, 0) });
+            engine.post_node(SubProblem { ub: 50, ..node(KnapsackState { depth: 1, capacity: 7 }, 0) });
+            let first = engine.claim_node(0).unwrap();
+            assert_eq!(50, engine.claim_node(1).unwrap().ub);
+            engine.post_node(SubProblem { ub: 90, ..node(KnapsackState { depth: 2, capacity: 5 }, 0) });
+            engine.release_node(0, &first);
+
+            token.cancel();
+            assert!(engine.claim_node(0).is_none());
+            assert_eq!(90, solver.best_upper_bound());
+        }
+    }
+
+    #[test]
+    fn the_dds_know_the_variables_assigned_by_the_residual_path() {
+        let (problem, observed) = Tweaked::context_probe(Knapsack::toy());
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 1);
+        solver.maximize();
+        assert_eq!(Some(13), solver.best_value());
+
+        // the variables are always assigned in order, whatever the subproblem
+        let observed = observed.lock().unwrap();
+        assert!(observed.iter().any(|assigned| !assigned.is_empty() && assigned.len() < 6));
+        assert!(observed.iter().all(|assigned| *assigned == (0..assigned.len()).collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn merged_duplicates_do_not_keep_their_layer_open() {
+        let processor = BarrierProcessor::new(3, DuplicatePolicy::Allow);
+        let mut counters = BarrierBookkeeping::new(3);
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+
+        // ten nodes per layer, but only two distinct states
+        for depth in 1..=3 {
+            for value in 0..10 {
+                let nn = node(KnapsackState { depth, capacity: value % 2 }, value as isize);
+                processor.on_push(&mut counters, &nn);
+                if let PushOutcome::Merged(dropped) = fringe.push(nn) {
+                    processor.on_merge(&mut counters, &dropped);
+                }
+            }
+        }
+        assert_eq!(vec![0, 2, 2, 2], counters.open_by_layer);
+
+        while let Some(nn) = fringe.pop() {
+            assert_eq!(Admission::Explore, processor.admit(&mut counters, &nn, isize::MIN));
+            processor.on_start(&mut counters, 0, &nn);
+            processor.on_finish(&mut counters, 0, nn.depth());
+        }
+        assert!(counters.open_by_layer.iter().all(|o| *o == 0));
+        assert!(counters.ongoing_by_layer.iter().all(|o| *o == 0));
+
+        // hence, the barriers of all the layers can be cleaned up
+        processor.on_get_workload(&mut counters);
+        assert_eq!(3, counters.lowest_active_layer);
+        assert!((0..3).all(|depth| processor.barriers.is_layer_empty(depth)));
+    }
+
+    #[test]
+    fn the_optimum_is_found_whatever_the_duplicate_policy() {
+        let problem = Knapsack::toy();
+        for policy in [DuplicatePolicy::Allow, DuplicatePolicy::Wait, DuplicatePolicy::Skip] {
+            let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 4)
+                .with_duplicate_policy(policy);
+            solver.maximize();
+            assert_eq!(Some(13), solver.best_value());
+        }
+    }
+
+    #[test]
+    fn the_dds_can_be_allocated_ahead_of_time() {
+        let problem = Knapsack::toy();
+        for (nodes, edges) in [(0, 0), (1, 1), (1024, 2048)] {
+            let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2)
+                .with_dd_capacity(nodes, edges);
+            solver.maximize();
+            assert_eq!(Some(13), solver.best_value());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn the_merge_check_aborts_the_resolution() {
+        let problem = Knapsack::toy();
+        // the other workers must not wait forever for the one that panicked
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackBrokenRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 4)
+            .with_merge_check(true);
+        solver.maximize();
+    }
+
+    #[test]
+    fn the_self_check_aborts_the_resolution_when_the_relaxation_loses_value() {
+        let problem = Knapsack::toy();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackStingyRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2)
+            .with_self_check(true);
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| false));
+        assert!(matches!(solver.violations()[0], Violation::LostValue { .. }));
+    }
+
+    #[test]
+    fn the_self_check_reports_the_completions_beating_the_estimate() {
+        let problem = Tweaked::short_estimate(Knapsack::toy());
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1)
+            .with_self_check(true)
+            .with_violation_policy(OnViolation::Warn);
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+
+        let violations = solver.violations();
+        assert!(!violations.is_empty());
+        for violation in violations {
+            match violation {
+                Violation::Underestimate { state, estimate, decisions, completion } => {
+                    let mut current = state;
+                    let mut value = 0;
+                    for d in decisions {
+                        value += problem.transition_cost(&current, d);
+                        current = problem.transition(&current, d);
+                    }
+                    assert_eq!(problem.nb_variables(), current.depth);
+                    assert_eq!(completion, value);
+                    assert!(completion > estimate);
+                }
+                _ => panic!("unexpected violation: {}", violation),
+            }
+        }
+    }
+
+    #[test]
+    fn the_self_check_warns_about_a_maximized_minimization() {
+        let problem = Tweaked::handling(Knapsack::toy(), false);
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2)
+            .with_self_check(true)
+            .with_violation_policy(OnViolation::Warn);
+        solver.maximize_with_interrupt(|| false);
+        assert!(matches!(solver.violations()[0], Violation::SignConvention { estimate: 6, completion: 27, .. }));
+
+        let problem = Tweaked::handling(Knapsack::toy(), true);
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2)
+            .with_self_check(true);
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+        assert_eq!(Some(-14), solver.best_value());
+        assert!(solver.violations().is_empty());
+    }
+
+    #[test]
+    fn a_valid_model_goes_through_the_self_check() {
+        let problem = Knapsack::toy();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2)
+            .with_self_check(true);
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+        assert_eq!(Some(13), solver.best_value());
+        assert!(solver.violations().is_empty());
+    }
+
+    #[test]
+    fn the_reported_upper_bound_never_exceeds_the_root_relaxed_bound() {
+        // the relaxed dd of the root, with the width used by the solvers below
+        let probe = Tweaked::new(Knapsack::toy());
+        let mut root = All::new(CutsetType::Frontier);
+        root.compile(&probe.input(CompilationType::Relaxed, vec![]));
+        let root_ub = root.best_value().unwrap();
+
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            // the root is processed before the second workload is handed out
+            for nb_workloads in 2..8 {
+                let interrupt = |calls: Arc<AtomicUsize>| move || calls.fetch_add(1, Ordering::Relaxed) >= nb_workloads;
+
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 1);
+                solver.maximize_with_interrupt(interrupt(Arc::new(AtomicUsize::new(0))));
+                assert!(solver.best_upper_bound() <= root_ub);
+
+                let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, &mut fringe, 1);
+                solver.maximize_with_interrupt(interrupt(Arc::new(AtomicUsize::new(0))));
+                assert!(solver.best_upper_bound() <= root_ub);
+            }
+        }
+    }
+
+    #[test]
+    fn the_push_filter_keeps_the_optimum() {
+        // a larger instance, where some cutset nodes share their state
+        let problem = Knapsack {
+            capacity: 15,
+            profit: vec![5, 3, 4, 6, 2, 1, 4, 3, 5, 2],
+            weight: vec![4, 3, 5, 4, 2, 3, 3, 1, 5, 2],
+        };
+        let mut suppressed = 0;
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+            let mut expected = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, &mut fringe, 1);
+            expected.maximize();
+            assert_eq!(0, expected.get_suppressed_pushes());
+
+            for capacity in [1, 4, 64] {
+                let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, &mut fringe, 1)
+                    .with_push_filter(capacity);
+                solver.maximize();
+                assert_eq!(expected.best_value(), solver.best_value());
+                suppressed += solver.get_suppressed_pushes();
+
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 2)
+                    .with_push_filter(capacity);
+                solver.maximize();
+                assert_eq!(expected.best_value(), solver.best_value());
+                suppressed += solver.get_suppressed_pushes();
+            }
+        }
+        assert!(suppressed > 0);
+    }
+
+    #[test]
+    fn shedding_the_fringe_keeps_the_optimum() {
+        // an instance where the fringe holds dominated nodes when the best solution improves
+        let problem = Knapsack {
+            capacity: 24,
+            profit: (0..12).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..12).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut shed = 0;
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+            let mut expected = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, &mut fringe, 1);
+            expected.maximize();
+
+            for shedding in [Shedding::new(0, 1.0), Shedding::new(isize::MAX, 0.0)] {
+                let mut fringe: NoDupFrontier<KnapsackRanking, _> = NoDupFrontier::with_order(MaxValue(&KnapsackRanking));
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, &mut fringe, 2)
+                    .with_shedding(shedding);
+                solver.maximize();
+                assert_eq!(expected.best_value(), solver.best_value());
+                assert_eq!(expected.best_value().unwrap(), solver.best_upper_bound());
+                shed += solver.get_shed_nodes();
+
+                // the layers of the shed nodes must not be kept open
+                let mut solver = BarrierParallelSolver::ordered(
+                    &problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, 2, MaxValue(&KnapsackRanking),
+                ).with_shedding(shedding);
+                solver.maximize();
+                assert_eq!(expected.best_value(), solver.best_value());
+                assert_eq!(expected.best_value().unwrap(), solver.best_upper_bound());
+                shed += solver.get_shed_nodes();
+            }
+        }
+        assert!(shed > 0);
+    }
+
+    /// A knapsack whose items are all worth about the same per unit of
+    /// weight: the shallow nodes all have high bounds
+    fn flat_knapsack(nb_items: usize) -> Knapsack {
+        Knapsack {
+            capacity: 5 * nb_items + 3,
+            profit: (0..nb_items).map(|i| 10 + (i * 7) % 5).collect(),
+            weight: (0..nb_items).map(|i| 9 + (i * 3) % 4).collect(),
+        }
+    }
+
+    #[test]
+    fn the_deepest_layer_policy_keeps_the_fringe_smaller() {
+        let problem = flat_knapsack(16);
+        let mut best_bound = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1);
+        assert_eq!(ResolutionStatus::Proved, best_bound.maximize_with_interrupt(|| false));
+        let mut deepest = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1)
+            .with_fringe_policy(FringePolicy::DeepestLayer);
+        assert_eq!(ResolutionStatus::Proved, deepest.maximize_with_interrupt(|| false));
+
+        assert_eq!(Some(105), best_bound.best_value());
+        assert_eq!((Some(105), 105), (deepest.best_value(), deepest.best_upper_bound()));
+        assert!(deepest.get_peak_fringe() * 4 < best_bound.get_peak_fringe());
+
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type, 2)
+                .with_fringe_policy(FringePolicy::DeepestLayer);
+            solver.maximize();
+            assert_eq!(Some(105), solver.best_value());
+        }
+    }
+
+    #[test]
+    fn an_interrupted_deepest_layer_fringe_bounds_all_its_layers() {
+        let problem = flat_knapsack(16);
+        for max_nodes in [5, 20, 80] {
+            let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1)
+                .with_fringe_policy(FringePolicy::DeepestLayer);
+            assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_cutoff(NodeCutoff(max_nodes)));
+            // the shallow layers hold the nodes having the best bounds
+            assert!(solver.best_upper_bound() > 105 && solver.best_upper_bound() < isize::MAX);
+            assert!(solver.best_value().is_none_or(|value| value <= 105));
+        }
+    }
+
+    #[test]
+    fn the_barrier_is_only_active_within_its_depths() {
+        let problem = Knapsack {
+            capacity: 24,
+            profit: (0..12).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..12).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        solver.maximize();
+        let optimum = solver.best_value();
+        let stats = solver.barrier_layer_stats();
+        assert_eq!(problem.nb_variables() + 1, stats.len());
+        assert!(stats.iter().any(|l| l.hits > 0) && stats.iter().any(|l| l.misses > 0));
+        assert!(stats.iter().any(|l| l.inserts > 0));
+        // the root is never looked up, as it is never pruned
+        assert_eq!(0, stats[0].hits);
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1)
+            .with_barrier_depths(3..5);
+        solver.maximize();
+        assert_eq!(optimum, solver.best_value());
+        let stats = solver.barrier_layer_stats();
+        for (depth, layer) in stats.iter().enumerate().filter(|(depth, _)| !(3..5).contains(depth)) {
+            assert_eq!(BarrierLayerStats::default(), *layer, "the barrier was active at depth {}", depth);
+        }
+        assert!(stats[3..5].iter().any(|l| l.inserts > 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn the_barrier_depths_cannot_go_beyond_the_variables() {
+        let problem = Knapsack::toy();
+        let _ = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1)
+            .with_barrier_depths(2..problem.nb_variables() + 2);
+    }
+
+    #[test]
+    fn the_warm_start_survives_an_immediate_interruption() {
+        let problem = Knapsack {
+            capacity: 24,
+            profit: (0..12).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..12).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut expected = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 1);
+        expected.maximize();
+        let optimum = expected.best_value().unwrap();
+
+        // the width grows until the restricted dd is exact
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 1);
+        solver.warm_start(WarmStartBudget::Nodes(usize::MAX));
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_cutoff(NodeCutoff(0)));
+        assert_eq!((Some(optimum), optimum), (solver.best_value(), solver.best_lower_bound()));
+        assert_eq!(0, solver.get_explored());
+
+        // a single compilation of width 1
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, 1);
+        solver.warm_start(WarmStartBudget::Nodes(1));
+        assert_eq!(1, solver.get_compilation_statistics().nb_compilations);
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_cutoff(NodeCutoff(0)));
+        let warm = solver.best_lower_bound();
+        assert!(warm > 0 && warm <= optimum);
+        assert_eq!(Some(warm), solver.best_value());
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, 1);
+        solver.warm_start(WarmStartBudget::Nodes(1));
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+        assert_eq!(Some(optimum), solver.best_value());
+
+        assert_eq!(Ok(WarmStartBudget::Nodes(100)), "100".parse());
+        assert_eq!(Ok(WarmStartBudget::Time(Duration::from_secs(5))), "5s".parse());
+        assert!("5m".parse::<WarmStartBudget>().is_err());
+    }
+
+    #[test]
+    fn the_layer_counters_return_to_zero_when_the_cutset_holds_duplicates() {
+        // with a frontier cutset, the same remaining capacity is reached by
+        // paths of different lengths
+        let problem = Diamond(Knapsack {
+            capacity: 12,
+            profit: (0..10).map(|i| 1 + (i * 7) % 11).collect(),
+            weight: (0..10).map(|i| 1 + (i * 5) % 4).collect(),
+        });
+        let mut fringe = NoDupFrontier::new(&DiamondRelax);
+        let mut expected = ParallelSolver::custom(&problem, &DiamondRelax, &DiamondRelax, &Fixed(2), CutsetType::LastExactLayer, &mut fringe, 1);
+        expected.maximize();
+
+        for nb_threads in [1, 2] {
+            let mut solver = BarrierParallelSolver::custom(&problem, &DiamondRelax, &DiamondRelax, &Fixed(2), CutsetType::Frontier, nb_threads);
+            assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+            assert_eq!(expected.best_value(), solver.best_value());
+            solver.engine.with_bookkeeping(|counters| {
+                assert!(counters.open_by_layer.iter().all(|open| *open == 0));
+                assert!(counters.ongoing_by_layer.iter().all(|ongoing| *ongoing == 0));
+            });
+        }
+    }
+
+    #[test]
+    fn a_suspended_resolution_resumes_where_it_stopped() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..20).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..20).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut expected = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, &mut fringe, 1);
+        expected.maximize();
+
+        let mut solver = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 2)
+            .with_suspension(true);
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_cutoff(NodeCutoff(5)));
+        let explored = solver.get_explored();
+        assert!(solver.best_upper_bound() >= expected.best_value().unwrap());
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+        assert_eq!(expected.best_value(), solver.best_value());
+        assert!(solver.get_explored() > explored);
+        assert_eq!(expected.best_value().unwrap(), solver.best_upper_bound());
+
+        // the root, whose compilation is cancelled, goes back to the fringe
+        // and through the barrier of the resumed resolution
+        let token = CancellationToken::new();
+        let problem = Tweaked::cancelling(knapsack.clone(), token.clone());
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1)
+            .with_suspension(true)
+            .with_cancellation_token(token);
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| false));
+        assert_eq!(None, solver.best_value());
+        let mut solver = solver.with_cancellation_token(CancellationToken::new());
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+        assert_eq!(expected.best_value(), solver.best_value());
+    }
+
+    #[test]
+    fn a_wider_resolution_resumes_from_the_fringe_of_an_interrupted_one() {
+        let knapsack = Knapsack {
+            capacity: 60,
+            profit: (0..30).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..30).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let (narrow, wide) = (Fixed(1), Fixed(16));
+        let mut cold = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &wide, CutsetType::LastExactLayer, 1);
+        assert_eq!(ResolutionStatus::Proved, cold.maximize_with_interrupt(|| false));
+
+        let mut first = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &narrow, CutsetType::LastExactLayer, 1)
+            .with_suspension(true);
+        assert_eq!(ResolutionStatus::Interrupted, first.maximize_with_cutoff(NodeCutoff(10)));
+        let (value, solution) = (first.best_value().unwrap(), first.best_solution().unwrap());
+        let nodes = first.into_open_subproblems();
+        assert!(!nodes.is_empty());
+
+        let nb_nodes = nodes.len();
+        let mut second = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &wide, CutsetType::LastExactLayer, 1)
+            .with_initial_lb(value, solution)
+            .with_initial_fringe(nodes);
+        assert_eq!(nb_nodes, second.layer_statistics().iter().map(|layer| layer.open).sum::<usize>());
+        assert_eq!(ResolutionStatus::Proved, second.maximize_with_interrupt(|| false));
+        assert_eq!(cold.best_value(), second.best_value());
+        assert!(second.get_explored() < cold.get_explored());
+
+        // the parallel solver resumes from its fringe just as well (without a
+        // barrier, the subproblems of its open nodes overlap though)
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut first = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &narrow, CutsetType::LastExactLayer, &mut fringe, 1)
+            .with_suspension(true);
+        assert_eq!(ResolutionStatus::Interrupted, first.maximize_with_cutoff(NodeCutoff(10)));
+        let (value, solution) = (first.best_value().unwrap(), first.best_solution().unwrap());
+        let nodes = first.into_open_subproblems();
+        assert!(!nodes.is_empty());
+
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut second = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &wide, CutsetType::LastExactLayer, &mut fringe, 1)
+            .with_initial_lb(value, solution)
+            .with_initial_fringe(nodes);
+        assert_eq!(ResolutionStatus::Proved, second.maximize_with_interrupt(|| false));
+        assert_eq!(cold.best_value(), second.best_value());
+    }
+
+    #[cfg(feature = "checkpoint")]
+    #[test]
+    fn a_reloaded_barrier_spares_the_proof_of_its_thresholds() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..20).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..20).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut first = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1)
+            .with_barrier_retention(true);
+        first.maximize();
+        let mut dump = vec![];
+        first.dump_barrier(&mut dump).unwrap();
+
+        let mut second = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        second.load_barrier(dump.as_slice()).unwrap();
+        assert_eq!(ResolutionStatus::Proved, second.maximize_with_interrupt(|| false));
+        assert_eq!(first.best_value(), second.best_value());
+        assert!(second.get_explored() < first.get_explored());
+
+        // the thresholds of an interrupted resolution are not worth saving
+        let mut interrupted = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1)
+            .with_barrier_retention(true)
+            .with_suspension(true);
+        assert_eq!(ResolutionStatus::Interrupted, interrupted.maximize_with_cutoff(NodeCutoff(5)));
+        assert_eq!(std::io::ErrorKind::InvalidInput, interrupted.dump_barrier(vec![]).unwrap_err().kind());
+
+        // the barrier of another problem is refused
+        let toy = Knapsack::toy();
+        let mut other = BarrierParallelSolver::custom(&toy, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        assert_eq!(std::io::ErrorKind::InvalidData, other.load_barrier(dump.as_slice()).unwrap_err().kind());
+    }
+
+    #[test]
+    fn the_log_of_the_watched_state_replays_its_threshold() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..20).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..20).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let watched = KnapsackState { depth: 4, capacity: 32 };
+        let mut solver = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1)
+            .with_watched_state(watched);
+        solver.maximize();
+        let log = solver.watched_state_log();
+
+        // with a single worker, each event sees the threshold left by the previous one
+        let mut threshold = None;
+        for event in log.iter() {
+            assert_eq!(4, event.depth);
+            assert_eq!(log[0].thread, event.thread);
+            match event.action {
+                BarrierAction::Insert(info) => {
+                    assert_eq!(None, threshold);
+                    threshold = Some(info);
+                }
+                BarrierAction::Overwrite { from, to } => {
+                    assert_eq!(Some(from), threshold);
+                    assert!(to.theta >= from.theta);
+                    threshold = Some(to);
+                }
+                BarrierAction::Prune { value, threshold: info } => {
+                    assert_eq!(Some(info), threshold);
+                    assert!(value <= info.theta);
+                }
+                BarrierAction::Explore { threshold: info, .. } => assert_eq!(info, threshold),
+            }
+        }
+        let insert = log.iter().position(|e| matches!(e.action, BarrierAction::Insert(_))).unwrap();
+        let prune = log.iter().position(|e| matches!(e.action, BarrierAction::Prune { .. })).unwrap();
+        assert!(insert < prune);
+        assert!(log.windows(2).all(|w| w[0].best_lb <= w[1].best_lb));
+
+        // nothing is logged unless a state is watched
+        let mut solver = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        solver.maximize();
+        assert!(solver.watched_state_log().is_empty());
+    }
+
+    #[test]
+    fn a_cancelled_resolution_stops_within_a_layer() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..30).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..30).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut expected = ParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), CutsetType::LastExactLayer, &mut fringe, 2);
+        expected.maximize();
+        let optimum = expected.best_value().unwrap();
+
+        // each dd takes about a tenth of a second: the resolutions are either
+        // cancelled during the compilation of the root or of its children
+        let (problem, _) = Tweaked::slow_estimate(knapsack, Duration::from_millis(1));
+        let cancel_after = |token: CancellationToken, delay: u64| {
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(delay));
+                token.cancel();
+            })
+        };
+        for (cutset_type, delay) in [(CutsetType::LastExactLayer, 30), (CutsetType::Frontier, 300)] {
+            let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+            let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(4), cutset_type, &mut fringe, 2);
+            let canceller = cancel_after(solver.cancellation_token(), delay);
+            let start = Instant::now();
+            assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| false));
+            assert!(start.elapsed() < Duration::from_secs(2));
+            assert!(solver.best_lower_bound() <= optimum && optimum <= solver.best_upper_bound());
+            canceller.join().unwrap();
+
+            let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(4), cutset_type, 2);
+            let canceller = cancel_after(solver.cancellation_token(), delay);
+            let start = Instant::now();
+            assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| false));
+            assert!(start.elapsed() < Duration::from_secs(2));
+            assert!(solver.best_lower_bound() <= optimum && optimum <= solver.best_upper_bound());
+            canceller.join().unwrap();
+        }
+
+        // a token shared by several solvers cancels them all
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut solver = BarrierParallelSolver::custom(&problem.knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), CutsetType::Frontier, 1)
+            .with_cancellation_token(token.clone());
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| false));
+        assert_eq!((0, isize::MAX), (solver.get_explored(), solver.best_upper_bound()));
+    }
+
+    #[test]
+    fn a_minimization_does_not_need_negated_costs() {
+        for estimated in [true, false] {
+            let problem = Tweaked::lost_profit(Knapsack::toy(), estimated);
+            for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+                let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, &mut fringe, 2);
+                solver.minimize();
+                assert_eq!(Some(8), solver.best_value());
+                let mut taken = solver.best_solution().unwrap().iter().filter(|d| d.value == 1).map(|d| d.var.id()).collect::<Vec<_>>();
+                taken.sort_unstable();
+                assert_eq!(vec![0, 3, 4], taken);
+
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 2);
+                assert_eq!(ResolutionStatus::Proved, solver.minimize_with_interrupt(|| false));
+                assert_eq!(Some(8), solver.best_value());
+                assert_eq!(8, solver.best_upper_bound());
+                assert!(solver.best_lower_bound() <= 8);
+
+                // the same solver can maximize again
+                solver.maximize();
+                assert_eq!(Some(21), solver.best_value());
+            }
+        }
+    }
+
+    #[test]
+    fn a_minimize_problem_is_maximized_into_the_costs_of_the_minimization() {
+        let problem = MinimizeProblem::new(Tweaked::lost_profit(Knapsack::toy(), true));
+        assert_eq!(10, problem.knapsack.capacity);
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 2);
+        solver.maximize();
+        assert_eq!(Some(-8), solver.best_value());
+        assert_eq!(Some(8), solver.best_value().map(MinimizeProblem::<Tweaked>::actual_value));
+        let solution = solver.best_solution().unwrap();
+        assert_eq!(Ok(8), problem.validate_solution(&solution).map(MinimizeProblem::<Tweaked>::actual_value));
+
+        // an infeasible state of a declared minimization stays infeasible
+        let problem = MinimizeProblem::new(Tweaked::spare_capacity(Knapsack::toy(), 3));
+        assert_eq!(isize::MIN, problem.estimate(&KnapsackState { depth: 1, capacity: 0 }));
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2);
+        solver.maximize();
+        assert_eq!(Some(12), solver.best_value().map(MinimizeProblem::<Tweaked>::actual_value));
+    }
+
+    #[test]
+    fn the_relaxed_dds_which_resume_from_the_restricted_ones_change_nothing_but_the_work() {
+        let problem = Knapsack { capacity: 15, profit: vec![5, 3, 4, 6, 2, 1, 7, 3, 5, 2], weight: vec![4, 3, 5, 4, 2, 3, 6, 2, 5, 1] };
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            let solve = |reuse| {
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 1)
+                    .with_prefix_reuse(reuse);
+                solver.maximize();
+                let reused = solver.get_compilation_statistics().counters.reused_nodes;
+                ((solver.best_value(), solver.best_solution(), solver.get_explored()), solver.get_explored_dd(), reused)
+            };
+            let (expected, explored_dd, reused) = solve(false);
+            assert_eq!(0, reused);
+            let (actual, fused_explored_dd, reused) = solve(true);
+            assert_eq!(expected, actual);
+            assert!(reused > 0);
+            assert_eq!(explored_dd, fused_explored_dd + reused);
+        }
+    }
+
+    #[test]
+    fn the_pool_gathers_the_optimal_solutions() {
+        // any two of the first three items are worth 4
+        let problem = Knapsack { capacity: 2, profit: vec![2, 2, 2, 3], weight: vec![1, 1, 1, 2] };
+        for width in [Fixed(1), Fixed(2), Fixed(100)] {
+            let mut solver = PooledBarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &width, CutsetType::Frontier, 2, 5);
+            assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+            assert_eq!(Some(4), solver.best_value());
+
+            let pool = solver.best_solutions();
+            assert_eq!(4, pool[0].0);
+            // the exact dd of the root goes through all of them, whereas the
+            // barrier may prune those that go through an explored state
+            if width.0 == 100 {
+                assert_eq!(3, pool.iter().filter(|(value, _)| *value == 4).count());
+            }
+            for (value, sol) in pool.iter() {
+                assert_eq!(Ok(*value), problem.validate_solution(sol));
+            }
+        }
+
+        // the optimal solutions all share an item
+        let differ = |a: &[Decision], b: &[Decision]| a.iter().zip(b).filter(|(x, y)| x != y).count() > 2;
+        let width = Fixed(100);
+        let mut solver = PooledBarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &width, CutsetType::Frontier, 2, 5)
+            .with_diversity(differ);
+        solver.maximize();
+        assert_eq!(1, solver.best_solutions().iter().filter(|(value, _)| *value == 4).count());
+    }
+
+    #[test]
+    fn the_terminal_value_shifts_the_optimum_by_as_much() {
+        for bonus in [-20, 7] {
+            let problem = Tweaked::bonus(Knapsack::toy(), bonus);
+            for cutset in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset, 2);
+                assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+                assert_eq!(Some(13 + bonus), solver.best_value());
+                assert_eq!(Ok(13 + bonus), problem.validate_solution(&solver.best_solution().unwrap()));
+            }
+
+            let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+            let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, &mut fringe, 2);
+            solver.maximize();
+            assert_eq!(Some(13 + bonus), solver.best_value());
+
+            // the relaxed bounds of the root account for the bonus too
+            let input = CompilationInputBuilder::relaxed(&problem, &KnapsackRelax, &KnapsackRanking).max_width(2).root().build().unwrap();
+            let mut all = All::new(CutsetType::LastExactLayer);
+            all.compile(&input);
+            let mut barrier = Barrier::new(Arc::new(BarrierStore::new(problem.nb_variables() + 1)), CutsetType::LastExactLayer);
+            barrier.compile(&input);
+            assert!(all.best_value().unwrap() >= 13 + bonus);
+            assert!(barrier.best_value().unwrap() >= 13 + bonus);
+        }
+    }
+
+    #[test]
+    fn the_observer_sees_the_bounds_converge_to_the_optimum() {
+        let problem = Knapsack::toy();
+        let trace = TraceCollector::new();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, 2)
+            .with_observer(&trace);
+        solver.maximize();
+
+        let (incumbents, bounds) = (trace.incumbents(), trace.bounds());
+        assert_eq!(Some(13), incumbents.last().map(|(_, value)| *value));
+        assert_eq!(Some(13), bounds.last().map(|(_, bound)| *bound));
+        assert!(incumbents.windows(2).all(|w| w[0].0 <= w[1].0 && w[0].1 < w[1].1));
+        assert!(bounds.windows(2).all(|w| w[0].0 <= w[1].0 && w[0].1 > w[1].1));
+        // the relaxed dd of the root is inexact, hence the bound improves
+        assert!(bounds.len() >= 2);
+
+        // when minimizing, the incumbents decrease while the bounds increase
+        let problem = Tweaked::lost_profit(Knapsack::toy(), true);
+        let trace = TraceCollector::new();
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 2)
+            .with_observer(&trace);
+        solver.minimize();
+
+        let (incumbents, bounds) = (trace.incumbents(), trace.bounds());
+        assert_eq!(Some(8), incumbents.last().map(|(_, value)| *value));
+        assert_eq!(Some(8), bounds.last().map(|(_, bound)| *bound));
+        assert!(incumbents.windows(2).all(|w| w[0].1 > w[1].1));
+        assert!(bounds.windows(2).all(|w| w[0].1 < w[1].1));
+    }
+
+    #[test]
+    fn the_discarded_nodes_are_told_apart_by_reason_and_depth() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..20).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..20).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut solver = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        solver.maximize();
+        let (by_bound, by_barrier) = (solver.get_discarded_by_bound(), solver.get_discarded_by_barrier());
+        assert!(by_bound > 0 && by_barrier > 0);
+        let stats = solver.layer_statistics();
+        assert_eq!(by_bound, stats.iter().map(|layer| layer.discarded_by_bound).sum::<usize>());
+        assert_eq!(by_barrier, stats.iter().map(|layer| layer.discarded_by_barrier).sum::<usize>());
+        // the root is never discarded
+        assert_eq!((0, 0), (stats[0].discarded_by_bound, stats[0].discarded_by_barrier));
+
+        // with the optimum as incumbent, the nodes which cannot beat it are
+        // never pushed: none of them is discarded by bound once popped
+        let mut seeded = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        seeded.warm_start(WarmStartBudget::Nodes(100_000));
+        assert_eq!(solver.best_value(), seeded.best_value());
+        seeded.maximize();
+        assert_eq!(0, seeded.get_discarded_by_bound());
+
+        // the barrier retained from the first resolution dominates the root
+        let mut solver = BarrierParallelSolver::custom(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1)
+            .with_barrier_retention(true);
+        solver.maximize();
+        let (explored, by_bound, by_barrier) = (solver.get_explored(), solver.get_discarded_by_bound(), solver.get_discarded_by_barrier());
+        solver.maximize();
+        assert_eq!(explored, solver.get_explored());
+        assert_eq!(by_bound, solver.get_discarded_by_bound());
+        assert_eq!(by_barrier + 1, solver.get_discarded_by_barrier());
+        assert_eq!(1, solver.layer_statistics()[0].discarded_by_barrier);
+    }
+
+    #[test]
+    fn the_layer_statistics_count_the_explored_nodes_of_each_depth() {
+        let problem = Knapsack::toy();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, 2);
+        assert!(solver.layer_statistics().iter().all(|layer| *layer == LayerStats::default()));
+        solver.maximize();
+
+        let stats = solver.layer_statistics();
+        assert_eq!(problem.nb_variables() + 1, stats.len());
+        assert!(stats.iter().all(|layer| layer.open == 0 && layer.ongoing == 0));
+        assert_eq!(1, stats[0].completed);
+        assert_eq!(solver.get_explored(), stats.iter().map(|layer| layer.completed).sum::<usize>());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be maximized")]
+    fn a_declared_minimization_cannot_be_maximized() {
+        // its estimates are lower bounds: they would prune the optimum away
+        let problem = Tweaked::spare_capacity(Knapsack::toy(), 3);
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2);
+        solver.maximize();
+    }
+
+    #[test]
+    fn a_declared_minimization_is_solved_in_its_own_sense() {
+        let problem = Tweaked::spare_capacity(Knapsack::toy(), 3);
+        // the infeasible states of a declared minimization are pruned, while
+        // the default estimate of the other problems still tells nothing
+        let infeasible = KnapsackState { depth: 1, capacity: 0 };
+        assert_eq!(isize::MIN, Negated(&problem).estimate(&infeasible));
+        assert_eq!(isize::MAX, Negated(&Tweaked::lost_profit(Knapsack::toy(), false)).estimate(&infeasible));
+
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, &mut fringe, 2);
+        // the warm start happens in the sense of the problem
+        solver.warm_start(WarmStartBudget::Nodes(1000));
+        assert_eq!(Some(12), solver.best_value());
+        solver.minimize();
+        assert_eq!(Some(12), solver.best_value());
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 2);
+        assert_eq!(ResolutionStatus::Proved, solver.minimize_with_interrupt(|| false));
+        assert_eq!((Some(12), 12, 12), (solver.best_value(), solver.best_lower_bound(), solver.best_upper_bound()));
+    }
+
+    #[test]
+    fn the_bounds_of_an_interrupted_minimization_surround_the_optimum() {
+        let problem = Tweaked::lost_profit(Knapsack::toy(), true);
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier, 1);
+        let workloads = Arc::new(AtomicUsize::new(0));
+        let counter = workloads.clone();
+        solver.minimize_with_interrupt(move || counter.fetch_add(1, Ordering::Relaxed) >= 3);
+        assert!(solver.best_lower_bound() <= 8);
+        assert!(solver.best_upper_bound() >= 8);
+    }
+
+    /// Prefers the solutions taking the items with the smallest ids
+    struct EarlyItems;
+    impl SecondaryObjective for EarlyItems {
+        fn score(&self, path: &[Decision]) -> isize {
+            -path.iter().filter(|d| d.value == 1).map(|d| d.var.id() as isize).sum::<isize>()
+        }
+    }
+
+    #[test]
+    fn the_ties_are_broken_by_the_secondary_objective() {
+        // any two of the six items make an optimal solution
+        let problem = Knapsack { capacity: 2, profit: vec![1; 6], weight: vec![1; 6] };
+        let taken = |solution: Option<Vec<Decision>>| {
+            let mut taken = solution.unwrap().iter().filter(|d| d.value == 1).map(|d| d.var.id()).collect::<Vec<_>>();
+            taken.sort_unstable();
+            taken
+        };
+        for width in [2, 100] {
+            let width = Fixed(width);
+            for nb_threads in [1, 4] {
+                let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &width, CutsetType::LastExactLayer, &mut fringe, nb_threads)
+                    .with_secondary_objective(&EarlyItems);
+                solver.maximize();
+                assert_eq!(Some(2), solver.best_value());
+                assert_eq!(vec![0, 1], taken(solver.best_solution()));
+
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &width, CutsetType::LastExactLayer, nb_threads)
+                    .with_secondary_objective(&EarlyItems);
+                solver.maximize();
+                assert_eq!(Some(2), solver.best_value());
+                assert_eq!(vec![0, 1], taken(solver.best_solution()));
+            }
+        }
+
+        // without enumerating the ties, only the first best path is scored
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(100), CutsetType::LastExactLayer, 1)
+            .with_secondary_objective(&EarlyItems)
+            .with_tie_limit(1);
+        solver.maximize();
+        let mut expected = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(100), CutsetType::LastExactLayer, 1);
+        expected.maximize();
+        assert_eq!(expected.best_solution(), solver.best_solution());
+        assert_ne!(vec![0, 1], taken(expected.best_solution()));
+    }
+
+    #[test]
+    fn every_enqueued_cutset_node_is_in_the_search_tree() {
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            for nb_threads in [1, 4] {
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, nb_threads)
+                    .with_tree_recording(true);
+                solver.maximize();
+                let tree = solver.search_tree().unwrap();
+                assert!(solver.get_cutset_generated() >= solver.get_cutset_enqueued());
+                assert!(solver.get_cutset_enqueued() > 0);
+                // every node but the root is an enqueued cutset node
+                assert_eq!(tree.nodes.len() - 1, solver.get_cutset_enqueued());
+
+                let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, &mut fringe, nb_threads)
+                    .with_tree_recording(true);
+                solver.maximize();
+                let tree = solver.search_tree().unwrap();
+                assert!(solver.get_cutset_generated() >= solver.get_cutset_enqueued());
+                assert_eq!(tree.nodes.len() - 1, solver.get_cutset_enqueued());
+            }
+        }
     }
 }
@@ -0,0 +1,51 @@
+//! This module implements the handle which lets another thread cancel a
+//! running resolution. Unlike an interrupt closure, which must be given to
+//! the solver when the resolution starts, the token can be cloned out of the
+//! solver beforehand and cancelled whenever some external event says so.
+//!
+//! # Example
+//! The token of a solver can be cancelled when the user hits Ctrl-C (here
+//! with the `ctrlc` crate, which this crate does not depend on): setting an
+//! atomic flag is all a signal handler is allowed to do.
+//! ```ignore
+//! fn main() {
+//!     let mut fringe = NoDupFrontier::new(&ranking);
+//!     let mut solver = ParallelSolver::custom(&problem, &relaxation, &ranking, &width, CutsetType::LastExactLayer, &mut fringe, 4);
+//!     let token = solver.cancellation_token();
+//!     ctrlc::set_handler(move || token.cancel()).expect("the handler can be set");
+//!
+//!     match solver.maximize_with_interrupt(|| false) {
+//!         ResolutionStatus::Interrupted => println!("cancelled within [{}, {}]", solver.best_lower_bound(), solver.best_upper_bound()),
+//!         _ => println!("optimum {:?}", solver.best_value()),
+//!     }
+//! }
+//! ```
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A cloneable handle on the cancellation flag of a solver. The solvers check
+/// it each time a worker asks for a workload and at each layer of the dds
+/// they compile: a cancelled resolution returns `ResolutionStatus::Interrupted`
+/// promptly, with the bounds it had proved so far.
+///
+/// Once cancelled, a token stays so: the later resolutions of the solvers
+/// which share it are interrupted right away.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Asks the resolutions which use this token to stop. This only sets an
+    /// atomic flag: it can be called from a signal handler.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    /// Returns true iff the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,85 @@
+//! This module implements the files in which a `BarrierParallelSolver` saves
+//! a suspended resolution (see `BarrierParallelSolver::checkpoint`): the
+//! nodes of its fringe, the thresholds of its barriers and the best solution
+//! it found, so that another solver (possibly in another process) resumes
+//! the resolution where it stopped.
+//!
+//! The same content, without any fringe, is what the barrier files hold (see
+//! `BarrierParallelSolver::dump_barrier`): the thresholds proved by a complete
+//! resolution along with the best solution they were proved against.
+//!
+//! The files are written with `bincode`: they are only meant to be read back
+//! by the same version of the crate, for the same problem.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{BarrierInfo, Decision, Objective, SubProblem};
+
+/// What the engine must know to resume a resolution
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SearchState<T> {
+    pub objective: Objective,
+    /// Whether the fringe holds the nodes of a suspended resolution (when it
+    /// does not, the resolution starts over from the root)
+    pub suspended: bool,
+    pub best_lb: isize,
+    pub best_sol: Option<Vec<Decision>>,
+    pub best_score: isize,
+    pub global_ub: isize,
+    pub explored: usize,
+    pub explored_dd: usize,
+    pub fringe: Vec<SubProblem<T>>,
+}
+
+/// The content of a checkpoint file
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Checkpoint<T> {
+    pub search: SearchState<T>,
+    /// The thresholds of each layer of the barriers
+    pub barriers: Vec<Vec<(Arc<T>, BarrierInfo)>>,
+}
+impl<T> Checkpoint<T> {
+    /// Writes the checkpoint to the given file
+    pub fn save(&self, path: &Path) -> io::Result<()>
+    where
+        T: Serialize,
+    {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+    /// Reads the checkpoint written by `save` to the given file
+    pub fn load(path: &Path) -> io::Result<Self>
+    where
+        T: DeserializeOwned,
+    {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+    /// Writes the checkpoint to the given writer
+    pub fn write_to<Wr: Write>(&self, mut out: Wr) -> io::Result<()>
+    where
+        T: Serialize,
+    {
+        bincode::serialize_into(&mut out, self).map_err(|error| into_io_error(*error))?;
+        out.flush()
+    }
+    /// Reads the checkpoint written by `write_to` from the given reader
+    pub fn read_from<Rd: Read>(input: Rd) -> io::Result<Self>
+    where
+        T: DeserializeOwned,
+    {
+        bincode::deserialize_from(input).map_err(|error| into_io_error(*error))
+    }
+}
+
+fn into_io_error(error: bincode::ErrorKind) -> io::Error {
+    match error {
+        bincode::ErrorKind::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
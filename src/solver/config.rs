@@ -0,0 +1,145 @@
+//! This module implements the options of the parallel branch-and-bound, which
+//! the `with_*` methods of the solvers set one at a time. They are kept in a
+//! single struct so that the owned solvers, which hand them to a borrowed
+//! solver at each resolution, have all the options the borrowed ones have.
+
+use crate::{CancellationToken, CutsetType, MergePolicy, OnViolation, Restarts, Shedding, DEFAULT_TIE_LIMIT};
+
+/// The options of the parallel solvers (`ParallelSolver`,
+/// `BarrierParallelSolver` and their owned counterparts). The components
+/// which the borrowed solvers borrow (the dominance rule, the secondary
+/// objective and the observer) are given to the solvers on their own, as are
+/// the seeds of their resolutions (the initial solution and fringe, and the
+/// solution pool).
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    /// The number of threads used by the solver
+    pub nb_threads: usize,
+    /// The number of layers below the root of each subproblem that are kept
+    /// exact when compiling a relaxed dd (1 by default). It may not be smaller
+    /// than the minimum required by the cutset type (see
+    /// `CutsetType::min_relax_depth_offset`).
+    pub min_relax_depth_offset: usize,
+    /// Whether the relaxed dds restrict the layers they cannot merge (see
+    /// `CompilationInput::restrict_first_layers`). This is disabled by
+    /// default; it bounds the width of the first layers below the deep
+    /// subproblems, whose domains are large.
+    pub restrict_first_layers: bool,
+    /// The number of layers whose nodes all have a single decision that the
+    /// dds may collapse below each layer they expand (see
+    /// `CompilationInput::max_forced_layers`). This is disabled (0) by
+    /// default; it spares the bookkeeping of the layers which do not branch,
+    /// like the last moves of a tour.
+    pub max_forced_layers: usize,
+    /// Whether the relaxed dd of each subproblem reuses the layers its
+    /// restricted dd developed before the width bound (see
+    /// `DecisionDiagram::resume_relaxed`). This is disabled by default; it
+    /// spares the expansion of these layers, which both dds share.
+    pub reuse_prefix: bool,
+    /// How the relaxed dds merge the nodes of the layers which exceed their
+    /// maximum width (a single merged node per layer by default)
+    pub merge_policy: MergePolicy,
+    /// Whether the dds validate each merge with `Relaxation::check_merge`.
+    /// This is enabled by default in debug builds only, because it slows down
+    /// the compilation of relaxed dds.
+    pub check_merge: bool,
+    /// Whether the compilations measure the time they spend in the model, as
+    /// opposed to the solver (see `get_compilation_statistics`). This is
+    /// disabled by default; it makes the compilations a bit slower.
+    pub model_timing: bool,
+    /// Whether the first relaxed compilations check the model: they verify
+    /// that the merges never lose value and that the estimates are not beaten
+    /// by greedy completions (see `violations`). Before that, the solver
+    /// looks for a minimization whose costs are maximized (see
+    /// `Violation::SignConvention`). This is disabled by default.
+    pub self_check: bool,
+    /// What is done when the self check finds a violation (the resolution is
+    /// aborted by default)
+    pub on_violation: OnViolation,
+    /// The number of slots of the filter each worker keeps of the cutset
+    /// nodes it recently enqueued: a worker skips the nodes which are
+    /// dominated (both in value and upper bound) by one of them with the same
+    /// state (see `get_suppressed_pushes`). This is disabled by default (0
+    /// slots).
+    pub push_filter: usize,
+    /// The maximum number of best paths of each dd which are scored by the
+    /// secondary objective (`DEFAULT_TIE_LIMIT` by default)
+    pub tie_limit: usize,
+    /// Whether a resolution which completes replays its best solution through
+    /// the transitions of the problem before reporting that the optimum is
+    /// proved: it reports `VerificationFailed` instead when the solution is
+    /// not worth the best value it has found. This is enabled by default.
+    pub verification: bool,
+    /// If set, some workers stop exploring the fringe once the trigger fires
+    /// and compile randomized restricted dds from the root instead, until the
+    /// best solution improves or the fringe shrinks (see `Restarts`). These
+    /// dives only ever improve the best solution; the optimum is still proved
+    /// by the branch-and-bound. This is disabled by default.
+    pub restarts: Option<Restarts>,
+    /// If set, the nodes of the fringe which can no longer improve the best
+    /// solution are shed when it improves by more than the margin of the
+    /// policy, or when the nodes sampled from the fringe show that a large
+    /// enough fraction of it could be shed (see `Shedding` and
+    /// `get_shed_nodes`). Otherwise, these nodes are only dropped once they
+    /// are popped. This is disabled by default.
+    pub shedding: Option<Shedding>,
+    /// Lets another thread cancel the resolutions (see `CancellationToken`).
+    /// The interrupt closures and cutoffs given to the resolutions still apply
+    /// as well. Each solver has its own token by default; giving several
+    /// solvers the same one makes it cancel them all at once.
+    pub cancellation: CancellationToken,
+    /// Whether an interrupted resolution keeps the nodes it did not explore:
+    /// rather than discarding them, it leaves them on the fringe (along with
+    /// the nodes whose compilation was cancelled) and the next resolution
+    /// resumes from them instead of the root. This is what `checkpoint`
+    /// saves. This is disabled by default.
+    pub suspension: bool,
+    /// Whether the branch-and-bound tree is recorded (see `search_tree`).
+    /// This is disabled by default.
+    pub tree_recording: bool,
+    /// The number of nodes popped from the fringe between two samples of the
+    /// memory held by the dds, the fringe and the barrier, if any (see
+    /// `peak_attribution`). This is disabled by default (a period of 0).
+    pub memory_sampling: usize,
+}
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            nb_threads: num_cpus::get(),
+            min_relax_depth_offset: 1,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: cfg!(debug_assertions),
+            model_timing: false,
+            self_check: false,
+            on_violation: OnViolation::default(),
+            push_filter: 0,
+            tie_limit: DEFAULT_TIE_LIMIT,
+            verification: true,
+            restarts: None,
+            shedding: None,
+            cancellation: CancellationToken::new(),
+            suspension: false,
+            tree_recording: false,
+            memory_sampling: 0,
+        }
+    }
+}
+impl SolverConfig {
+    /// Checks that the options suit the given cutset type
+    ///
+    /// # Panics
+    /// When `min_relax_depth_offset` is smaller than the minimum required by
+    /// the cutset type (see `CutsetType::min_relax_depth_offset`).
+    pub(crate) fn check(&self, cutset_type: CutsetType) {
+        let min = cutset_type.min_relax_depth_offset();
+        assert!(
+            self.min_relax_depth_offset >= min,
+            "min_relax_depth_offset must be at least {} with the {} cutset",
+            min,
+            cutset_type
+        );
+    }
+}
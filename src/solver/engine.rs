@@ -0,0 +1,1910 @@
+//! This module implements the parallel branch-and-bound machinery which is
+//! common to all the solvers: the shared state, the way workloads are handed
+//! out to the worker threads, and the processing of one subproblem. What
+//! differs from one solver to the other (the dd compiled by each worker and
+//! the extra bookkeeping it needs) is provided by a `NodeProcessor`.
+
+use std::{hash::Hash, sync::{atomic::{AtomicIsize, AtomicUsize, Ordering}, Arc}, time::Instant};
+#[cfg(test)]
+use std::sync::Weak;
+
+use parking_lot::{Condvar, Mutex};
+
+use super::{
+    config::SolverConfig, filter::PushFilter, memory::{MemoryAttribution, MemorySampler}, observer::SolverObserver, pool::SolutionPool,
+    restart::{dive_width, RestartState, Role, Shuffled}, tree::{Disposition, SearchTree, TreeRecorder},
+    warm_start::WarmStartBudget,
+};
+use crate::{
+    objective::{check_objective, negate}, utils::OnPanic, validate::{check_sign_convention, SELF_CHECKED_COMPILATIONS}, CancellationToken, CompilationInputBuilder, DominanceChecker, CompilationStatistics, CompilationType, CutsetType,
+    Decision, DecisionDiagram, Frontier, PathSegment, Problem, PushOutcome, Relaxation, ResolutionStatus, StateRanking,
+    SubProblem, WidthHeuristic, Negated, Objective, OnViolation, SecondaryObjective, Violation,
+    check_path, Cutoff, CutoffStats, OnImprovement, VerificationError,
+};
+#[cfg(feature = "checkpoint")]
+use super::checkpoint::SearchState;
+
+/// The hooks through which a solver customizes the generic engine.
+///
+/// # Note
+/// All the methods taking a `bookkeeping` argument are called from within a
+/// critical section (the bookkeeping lives along with the critical data).
+pub(crate) trait NodeProcessor<T> {
+    /// The type of dd that is compiled by each worker
+    type Dd: DecisionDiagram<State = T>;
+    /// The additional data which the engine protects along with its critical data
+    type Bookkeeping;
+
+    /// Creates the dd used by one worker thread
+    fn new_dd(&self, cutset_type: CutsetType) -> Self::Dd;
+    /// Returns the number of nodes expanded during the last compilation of `dd`
+    fn explored(dd: &Self::Dd) -> usize;
+    /// Estimates the number of bytes held by `dd`
+    fn footprint(dd: &Self::Dd) -> usize;
+    /// Estimates the number of bytes held by the data which the processor
+    /// shares among the workers. This is called from within a critical section
+    /// and must thus be cheap.
+    fn shared_footprint(&self) -> usize {
+        0
+    }
+
+    /// Called whenever a node is pushed onto the fringe
+    fn on_push(&self, _bookkeeping: &mut Self::Bookkeeping, _node: &SubProblem<T>) {}
+    /// Called when a node pushed onto the fringe was merged with another one
+    /// having the same state (or when the fringe was full): `dropped` is the
+    /// one which left the fringe
+    fn on_merge(&self, _bookkeeping: &mut Self::Bookkeeping, _dropped: &SubProblem<T>) {}
+    /// Called whenever a worker asks for a workload, before anything else
+    fn on_get_workload(&self, _bookkeeping: &mut Self::Bookkeeping) {}
+    /// Called when the fringe is emptied because none of its nodes can improve
+    /// the best known solution
+    fn on_fringe_pruned(&self, _bookkeeping: &mut Self::Bookkeeping) {}
+    /// Called when a node is dropped from the fringe because it cannot improve
+    /// the best known solution: either it was popped (only when the fringe is
+    /// not ub ordered), or the fringe was shed
+    fn on_node_pruned(&self, _bookkeeping: &mut Self::Bookkeeping, _node: &SubProblem<T>) {}
+    /// Tells what must be done with a node which has just been popped from the
+    /// fringe, when the best lower bound is `best_lb`
+    fn admit(&self, _bookkeeping: &mut Self::Bookkeeping, _node: &SubProblem<T>, _best_lb: isize) -> Admission {
+        Admission::Explore
+    }
+    /// Called when the given worker starts processing the given node
+    fn on_start(&self, _bookkeeping: &mut Self::Bookkeeping, _thread_id: usize, _node: &SubProblem<T>) {}
+    /// Called when the given worker is done processing a node that was at the given depth
+    fn on_finish(&self, _bookkeeping: &mut Self::Bookkeeping, _thread_id: usize, _depth: usize) {}
+    /// Called when the given worker panicked (possibly while processing a node)
+    fn on_abort(&self, _bookkeeping: &mut Self::Bookkeeping, _thread_id: usize) {}
+    /// Called when the exploration of the given node was cancelled, right
+    /// before it is pushed back onto the fringe of a suspended resolution
+    fn on_suspend(&self, _bookkeeping: &mut Self::Bookkeeping, _node: &SubProblem<T>) {}
+}
+
+/// What must be done with a node popped from the fringe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Admission {
+    /// The node must be explored
+    Explore,
+    /// The node must be dropped
+    Reject,
+    /// The node cannot be explored right now: it must go back to the fringe
+    /// while some other node is explored
+    Defer,
+    /// The node cannot be explored right now: it must go back to the fringe
+    /// and the worker must wait until some other worker is done
+    Wait,
+}
+
+/// The shared data that may only be manipulated within critical sections
+struct Critical<F, B> {
+    /// This is the fringe: the set of nodes that must still be explored before
+    /// the problem can be considered 'solved'.
+    ///
+    /// # Note:
+    /// This fringe orders the nodes by upper bound (so the highest ub is going
+    /// to pop first). So, it is guaranteed that the upper bound of the first
+    /// node being popped is an upper bound on the value reachable by exploring
+    /// any of the nodes remaining on the fringe. As a consequence, the
+    /// exploration can be stopped as soon as a node with an ub <= current best
+    /// lower bound is popped. When the fringe tells that it is not ub ordered
+    /// (e.g. with the `MaxValue` order), such a node is only pruned by itself,
+    /// and the whole fringe is needed to bound the remaining nodes.
+    fringe: F,
+    /// This is the number of nodes that are currently being explored.
+    ///
+    /// # Note
+    /// This information may seem innocuous/superfluous, whereas in fact it is
+    /// very important. Indeed, this is the piece of information that lets us
+    /// distinguish between a node-starvation and the completion of the problem
+    /// resolution. The bottom line is, this counter needs to be carefully
+    /// managed to guarantee the termination of all threads.
+    ongoing: usize,
+    /// This is a counter that tracks the number of nodes that have effectively
+    /// been explored. That is, the number of nodes that have been popped from
+    /// the fringe, and for which a restricted and relaxed mdd have been developed.
+    explored: usize,
+    explored_dd: usize,
+    /// The number of cutset nodes drained from the relaxed dds
+    cutset_generated: usize,
+    /// The number of these cutset nodes which were pushed onto the fringe
+    /// (the others could not beat the best lower bound, or were suppressed
+    /// by the push filter)
+    cutset_enqueued: usize,
+    /// This keeps track of the size of all the dds that have been compiled
+    compilations: CompilationStatistics,
+    /// This is the value of the best known lower bound.
+    best_lb: isize,
+    /// This is the value of the best known lower bound.
+    /// *WARNING* This one only gets set when the resolution is cut off
+    best_ub: isize,
+    /// This is the upper bound given by the relaxed dd of the root: no node
+    /// can be worth more than that.
+    global_ub: isize,
+    /// The best upper bound of the nodes a bounded fringe dropped to make
+    /// room (`isize::MIN` while none was): the optimum may lie below them,
+    /// so this bounds the objective until the end of the resolution.
+    evicted_ub: isize,
+    /// If set, this keeps the info about the best solution so far.
+    best_sol: Option<Vec<Decision>>,
+    /// The secondary score of the best solution so far (when the solver has
+    /// a secondary objective)
+    best_score: isize,
+    /// The best distinct solutions found so far (when the solver keeps a
+    /// pool of them)
+    pool: SolutionPool,
+    /// This vector is used to store the upper bound on the node which is
+    /// currently processed by each thread.
+    ///
+    /// # Note
+    /// When a thread is idle (or more generally when it is done with processing
+    /// it node), it should place the value i32::min_value() in its corresponding
+    /// cell.
+    upper_bounds: Vec<isize>,
+    interrupted: bool,
+    /// Whether the fringe holds the nodes of a suspended resolution, which
+    /// the next one must resume rather than start from the root
+    suspended: bool,
+    /// The identifier to give to the next subproblem pushed onto the fringe
+    next_id: usize,
+    /// If set, this records the branch-and-bound tree
+    tree: Option<TreeRecorder>,
+    /// The samples of the memory held by the dds, the fringe and the shared
+    /// data of the processor
+    memory: MemorySampler,
+    /// The state of the random restarts, along with the role of each thread
+    restarts: RestartState,
+    /// The best lower bound when the fringe was last shed
+    shed_lb: isize,
+    /// The number of nodes which were shed from the fringe
+    shed: usize,
+    /// The largest number of nodes the fringe ever held
+    peak_fringe: usize,
+    /// The number of nodes popped from the fringe which were discarded because
+    /// their upper bound could not improve the best lower bound, by depth
+    discarded_by_bound: Vec<usize>,
+    /// The number of nodes popped from the fringe which were rejected by the
+    /// processor (that is, dominated by the barrier), by depth
+    discarded_by_barrier: Vec<usize>,
+    /// All the path segments that were ever created (to check they get freed)
+    #[cfg(test)]
+    segments: Vec<Weak<PathSegment>>,
+    /// The solver specific data
+    bookkeeping: B,
+}
+/// The callback of a resolution which reports its progress, along with the
+/// value of the last solution it was given
+struct Progress {
+    on_improvement: OnImprovement,
+    reported: Mutex<isize>,
+}
+
+/// The state which is shared among the many running threads: it provides an
+/// access to the critical data (protected by a mutex) as well as a monitor
+/// (condvar) to park threads in case of node-starvation.
+struct Shared<'a, P, R, O, W, F, N>
+where
+    P: Problem,
+    N: NodeProcessor<P::State>,
+{
+    problem: &'a P,
+    relaxation: &'a R,
+    ranking: &'a O,
+    width_heu: &'a W,
+    cutset_type: CutsetType,
+    /// Whether the current (or last) resolution maximizes or minimizes the
+    /// objective. All the values known to the engine are those of the
+    /// maximization: they are only negated when they are reported.
+    objective: Objective,
+    /// The options of the resolutions
+    config: SolverConfig,
+    /// The dominance rule the dds apply to their layers, if any (see
+    /// `CompilationInput::dominance`)
+    dominance: Option<&'a (dyn DominanceChecker<P::State> + Sync)>,
+    /// The number of relaxed compilations which must still be self checked
+    self_checks_left: AtomicUsize,
+    /// The violations found by the self check
+    violations: Mutex<Vec<Violation<P::State>>>,
+    /// The number of cutset nodes which were not pushed because the filter
+    /// found them dominated
+    suppressed_pushes: AtomicUsize,
+    /// The bytes held by the dd of each worker, as of its last compilation
+    dd_footprints: Vec<AtomicUsize>,
+    /// The objective which breaks the ties among the solutions of equal value
+    secondary: Option<&'a (dyn SecondaryObjective + Sync)>,
+    /// The number of solutions the pool keeps (0 when there is no pool)
+    pool_capacity: usize,
+    /// The reason why the best solution of the last resolution failed its
+    /// verification (if it did)
+    verification_error: Option<VerificationError>,
+    /// The best lower bound, mirrored outside of the critical section so that
+    /// the relaxed dds may re-read it while they compile (see
+    /// `CompilationInput::incumbent`). It is written along with `best_lb`.
+    best_lb_mirror: AtomicIsize,
+    /// If set, this is told about each improvement of the bounds
+    observer: Option<&'a dyn SolverObserver>,
+    /// If set, this is given each better solution of the current resolution
+    /// (see `InterruptibleSolver::maximize_with_cutoff_and_progress`)
+    progress: Option<Progress>,
+    /// When the current (or last) resolution or warm start began
+    started: Instant,
+    /// The solver specific behavior
+    processor: N,
+
+    /// This is the shared state data which can only be accessed within critical
+    /// sections. Therefore, it is protected by a mutex which prevents concurrent
+    /// reads/writes.
+    critical: Mutex<Critical<F, N::Bookkeeping>>,
+    /// This is the monitor on which nodes must wait when facing an empty fringe.
+    /// The corollary, it that whenever a node has completed the processing of
+    /// a subproblem, it must wakeup all parked threads waiting on this monitor.
+    monitor: Condvar,
+}
+impl<P, R, O, W, F, N> Shared<'_, P, R, O, W, F, N>
+where
+    P: Problem,
+    N: NodeProcessor<P::State>,
+{
+    /// The value which a node must exceed not to be pruned. With a secondary
+    /// objective or a pool of solutions, the nodes which can only tie with
+    /// the best lower bound must be explored as well.
+    fn pruning_bound(&self, best_lb: isize) -> isize {
+        if self.secondary.is_some() || self.pool_capacity > 0 {
+            best_lb.saturating_sub(1)
+        } else {
+            best_lb
+        }
+    }
+    /// The mirror of the best lower bound that the relaxed dds re-read while
+    /// they compile, unless they must explore the ties of the incumbent (see
+    /// `pruning_bound`)
+    fn incumbent(&self) -> Option<&AtomicIsize> {
+        (self.secondary.is_none() && self.pool_capacity == 0).then_some(&self.best_lb_mirror)
+    }
+    /// Turns a value of the maximization into a value of the objective
+    fn reported(&self, value: isize) -> isize {
+        match self.objective {
+            Objective::Maximize => value,
+            Objective::Minimize => negate(value),
+        }
+    }
+    /// Tells the observer (if any) about the new best lower bound. This is
+    /// called from within the critical section.
+    fn notify_incumbent(&self, best_lb: isize) {
+        if let Some(observer) = self.observer {
+            observer.on_new_incumbent(self.reported(best_lb), self.started.elapsed());
+        }
+    }
+    /// Hands the best solution, worth `best_lb`, to the progress callback (if
+    /// any) unless it was given a better one already. This is called outside
+    /// of the critical section.
+    fn report_progress(&self, best_lb: isize, solution: &[Decision]) {
+        if let Some(progress) = self.progress.as_ref() {
+            let mut reported = progress.reported.lock();
+            if best_lb > *reported {
+                *reported = best_lb;
+                (progress.on_improvement)(self.reported(best_lb), solution);
+            }
+        }
+    }
+    /// Tells the observer (if any) about the upper bound of the resolution when
+    /// it is tighter than the one it had `before`. This is called from within
+    /// the critical section.
+    fn notify_bound(&self, before: isize, after: isize) {
+        if let Some(observer) = self.observer.filter(|_| after < before) {
+            observer.on_new_bound(self.reported(after), self.started.elapsed());
+        }
+    }
+}
+impl<F, B> Critical<F, B> {
+    /// The best upper bound known so far
+    fn upper_bound(&self) -> isize {
+        self.best_ub.min(self.global_ub)
+    }
+}
+/// Counts one more discarded node at the given depth
+fn count_discard(discarded: &mut Vec<usize>, depth: usize) {
+    if depth >= discarded.len() {
+        discarded.resize(depth + 1, 0);
+    }
+    discarded[depth] += 1;
+}
+/// The workload a thread can get from the shared state
+enum WorkLoad<T> {
+    /// There is no work left to be done: you can safely terminate
+    Complete,
+    /// The work must stop because of an external cutoff
+    Interruption,
+    /// There is nothing you can do right now. Check again when you wake up
+    Starvation,
+    /// The item to process. Its whole path is its prefix: the segment, which
+    /// its children share as well
+    WorkItem { node: SubProblem<T>, segment: Arc<PathSegment> },
+    /// Compile a restricted dd from the root, whose random choices are made
+    /// after the given seed
+    Dive { seed: u64 },
+}
+
+/// The generic parallel branch-and-bound on which the solvers are built
+pub(crate) struct Engine<'a, P, R, O, W, F, N>
+where
+    P: Problem,
+    N: NodeProcessor<P::State>,
+{
+    /// This is the shared state. Each thread is going to take a reference to it.
+    shared: Shared<'a, P, R, O, W, F, N>,
+}
+
+impl<'a, P, R, O, W, F, N> Engine<'a, P, R, O, W, F, N>
+where
+    P: Problem,
+    N: NodeProcessor<P::State>,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        problem: &'a P,
+        relaxation: &'a R,
+        ranking: &'a O,
+        width_heu: &'a W,
+        cutset_type: CutsetType,
+        fringe: F,
+        nb_threads: usize,
+        processor: N,
+        bookkeeping: N::Bookkeeping,
+    ) -> Self {
+        Engine {
+            shared: Shared {
+                problem,
+                relaxation,
+                ranking,
+                width_heu,
+                cutset_type,
+                objective: problem.objective(),
+                config: SolverConfig { nb_threads, ..SolverConfig::default() },
+                dominance: None,
+                self_checks_left: AtomicUsize::new(0),
+                violations: Mutex::new(vec![]),
+                suppressed_pushes: AtomicUsize::new(0),
+                dd_footprints: vec![],
+                secondary: None,
+                pool_capacity: 0,
+                verification_error: None,
+                best_lb_mirror: AtomicIsize::new(isize::MIN),
+                observer: None,
+                progress: None,
+                started: Instant::now(),
+                processor,
+                //
+                monitor: Condvar::new(),
+                critical: Mutex::new(Critical {
+                    best_sol: None,
+                    best_score: isize::MIN,
+                    pool: SolutionPool::default(),
+                    best_lb: isize::MIN,
+                    best_ub: isize::MAX,
+                    global_ub: isize::MAX,
+                    evicted_ub: isize::MIN,
+                    upper_bounds: vec![isize::MAX; nb_threads],
+                    fringe,
+                    ongoing: 0,
+                    explored: 0,
+                    explored_dd: 0,
+                    cutset_generated: 0,
+                    cutset_enqueued: 0,
+                    compilations: CompilationStatistics::default(),
+                    interrupted: false,
+                    suspended: false,
+                    next_id: 0,
+                    tree: None,
+                    memory: MemorySampler::new(0),
+                    restarts: RestartState::new(nb_threads),
+                    shed_lb: isize::MIN,
+                    shed: 0,
+                    peak_fringe: 0,
+                    discarded_by_bound: vec![],
+                    discarded_by_barrier: vec![],
+                    #[cfg(test)]
+                    segments: vec![],
+                    bookkeeping,
+                }),
+            },
+        }
+    }
+
+    /// Changes the options of the solver (see `set_config`)
+    pub fn configure(&mut self, f: impl FnOnce(&mut SolverConfig)) {
+        let mut config = self.shared.config.clone();
+        f(&mut config);
+        self.set_config(config);
+    }
+    /// Replaces the options of the solver, and brings the state which depends
+    /// on them up to date
+    ///
+    /// # Panics
+    /// When the options do not suit the cutset type (see `SolverConfig::check`)
+    pub fn set_config(&mut self, config: SolverConfig) {
+        config.check(self.shared.cutset_type);
+        let critical = self.shared.critical.get_mut();
+        if config.nb_threads != critical.upper_bounds.len() {
+            critical.upper_bounds = vec![isize::MAX; config.nb_threads];
+            critical.restarts = RestartState::new(config.nb_threads);
+        }
+        if config.tree_recording != critical.tree.is_some() {
+            critical.tree = config.tree_recording.then(TreeRecorder::default);
+        }
+        if config.memory_sampling != critical.memory.period() {
+            critical.memory = MemorySampler::new(config.memory_sampling);
+        }
+        self.shared.config = config;
+    }
+    /// Sets the dominance rule the dds apply to the exact nodes of each layer
+    /// before they expand it (see `Dominance`). There is none by default.
+    pub fn set_dominance(&mut self, dominance: &'a (dyn DominanceChecker<P::State> + Sync)) {
+        self.shared.dominance = Some(dominance);
+    }
+    /// Breaks the ties among the solutions of equal value with the given
+    /// secondary objective: among the solutions worth the best value, the
+    /// solver reports the one with the highest score it came across (see
+    /// `SecondaryObjective`). The nodes which can only tie with the best
+    /// solution are not pruned, hence the resolution explores more nodes.
+    pub fn set_secondary_objective(&mut self, secondary: &'a (dyn SecondaryObjective + Sync)) {
+        self.shared.secondary = Some(secondary);
+    }
+    /// Keeps the best distinct solutions found during the resolution in the
+    /// given pool (see `SolutionPool`). The nodes which can only tie with the
+    /// best solution are then explored as well, like with a secondary
+    /// objective.
+    pub fn set_solution_pool(&mut self, pool: SolutionPool) {
+        self.shared.pool_capacity = pool.capacity();
+        self.shared.critical.get_mut().pool = pool;
+    }
+    pub fn solution_pool(&self) -> SolutionPool {
+        self.shared.critical.lock().pool.clone()
+    }
+    /// Returns the solutions of the pool (the best ones first), along with
+    /// their values in terms of the objective of the last resolution
+    pub fn pooled_solutions(&self) -> Vec<(isize, Vec<Decision>)> {
+        let pool = self.shared.critical.lock().pool.solutions();
+        pool.into_iter().map(|(value, sol)| (self.reported(value), sol)).collect()
+    }
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.shared.config.cancellation.clone()
+    }
+    /// Tells the given observer about each improvement of the best solution
+    /// and of the best bound, along with the time elapsed since the start of
+    /// the resolution (see `SolverObserver` and `TraceCollector`).
+    pub fn set_observer(&mut self, observer: &'a dyn SolverObserver) {
+        self.shared.observer = Some(observer);
+    }
+    /// Makes the given solution, which is worth `value`, the best solution of
+    /// the solver. Like the one of `warm_start`, it seeds the best lower bound
+    /// of the next resolution, and its value is in terms of the objective of
+    /// that resolution (the objective of the problem when the solver never
+    /// ran). The solution is only replayed in the debug builds.
+    pub fn set_initial_solution(&mut self, value: isize, solution: Vec<Decision>) {
+        debug_assert!(self.shared.problem.validate_solution(&solution).is_ok(), "the initial solution is infeasible");
+        let best_lb = match self.shared.objective {
+            Objective::Maximize => value,
+            Objective::Minimize => negate(value),
+        };
+        let critical = self.shared.critical.get_mut();
+        critical.best_lb = best_lb;
+        critical.best_sol = Some(solution);
+        self.shared.best_lb_mirror.store(best_lb, Ordering::Relaxed);
+    }
+
+    pub fn best_solution(&self) -> Option<Vec<Decision>> {
+        self.shared.critical.lock().best_sol.clone()
+    }
+
+    pub fn best_value(&self) -> Option<isize> {
+        let critical = self.shared.critical.lock();
+        critical.best_sol.as_ref().map(|_sol| self.reported(critical.best_lb))
+    }
+
+    /// Returns the best lower bound in terms of the objective of the last
+    /// resolution (for a minimization, this is the best upper bound of the
+    /// negated problem)
+    pub fn best_lower_bound(&self) -> isize {
+        match self.shared.objective {
+            Objective::Maximize => self.shared.critical.lock().best_lb,
+            Objective::Minimize => negate(self.max_upper_bound()),
+        }
+    }
+
+    /// Returns the best upper bound in terms of the objective of the last
+    /// resolution
+    pub fn best_upper_bound(&self) -> isize {
+        match self.shared.objective {
+            Objective::Maximize => self.max_upper_bound(),
+            Objective::Minimize => negate(self.shared.critical.lock().best_lb),
+        }
+    }
+
+    fn max_upper_bound(&self) -> isize {
+        self.shared.critical.lock().upper_bound()
+    }
+    /// Turns a value of the maximization into a value of the objective
+    fn reported(&self, value: isize) -> isize {
+        self.shared.reported(value)
+    }
+
+    pub fn get_explored(&self) -> usize {
+        self.shared.critical.lock().explored
+    }
+
+    pub fn get_explored_dd(&self) -> usize {
+        self.shared.critical.lock().explored_dd
+    }
+
+    pub fn get_cutset_generated(&self) -> usize {
+        self.shared.critical.lock().cutset_generated
+    }
+    pub fn get_cutset_enqueued(&self) -> usize {
+        self.shared.critical.lock().cutset_enqueued
+    }
+    pub fn get_compilation_statistics(&self) -> CompilationStatistics {
+        self.shared.critical.lock().compilations
+    }
+
+    pub fn get_suppressed_pushes(&self) -> usize {
+        self.shared.suppressed_pushes.load(Ordering::Relaxed)
+    }
+
+    pub fn get_shed_nodes(&self) -> usize {
+        self.shared.critical.lock().shed
+    }
+    pub fn get_peak_fringe(&self) -> usize {
+        self.shared.critical.lock().peak_fringe
+    }
+
+    pub fn get_discarded_by_bound(&self) -> usize {
+        self.shared.critical.lock().discarded_by_bound.iter().sum()
+    }
+    pub fn get_discarded_by_barrier(&self) -> usize {
+        self.shared.critical.lock().discarded_by_barrier.iter().sum()
+    }
+    /// Returns the number of nodes which were discarded by bound and by the
+    /// barrier, by depth (these vectors stop at the deepest discard)
+    pub fn discarded_by_depth(&self) -> (Vec<usize>, Vec<usize>) {
+        let critical = self.shared.critical.lock();
+        (critical.discarded_by_bound.clone(), critical.discarded_by_barrier.clone())
+    }
+
+    pub fn get_dives(&self) -> usize {
+        self.shared.critical.lock().restarts.dives()
+    }
+    pub fn get_improving_dives(&self) -> usize {
+        self.shared.critical.lock().restarts.improving_dives()
+    }
+
+    pub fn processor_mut(&mut self) -> &mut N {
+        &mut self.shared.processor
+    }
+    pub fn fringe_mut(&mut self) -> &mut F {
+        &mut self.shared.critical.get_mut().fringe
+    }
+    pub fn processor(&self) -> &N {
+        &self.shared.processor
+    }
+    /// Gives a read access to the data of the solver (within a critical section)
+    pub fn with_bookkeeping<X>(&self, f: impl FnOnce(&N::Bookkeeping) -> X) -> X {
+        f(&self.shared.critical.lock().bookkeeping)
+    }
+
+    pub fn violations(&self) -> Vec<Violation<P::State>>
+    where
+        P::State: Clone,
+    {
+        self.shared.violations.lock().clone()
+    }
+
+    pub fn verification_error(&self) -> Option<VerificationError> {
+        self.shared.verification_error.clone()
+    }
+
+    pub fn search_tree(&self) -> Option<SearchTree> {
+        self.shared.critical.lock().tree.as_ref().map(|tree| tree.tree())
+    }
+
+    /// Returns the memory sample at which the dds, the fringe and the barrier
+    /// held the most bytes altogether (when the memory is sampled)
+    pub fn peak_attribution(&self) -> Option<MemoryAttribution> {
+        self.shared.critical.lock().memory.peak()
+    }
+    /// Returns the largest number of bytes sampled for each component (when
+    /// the memory is sampled)
+    #[cfg(test)]
+    pub fn component_memory_peaks(&self) -> Option<MemoryAttribution> {
+        self.shared.critical.lock().memory.component_peaks()
+    }
+
+    /// Returns the number of path segments which are still alive
+    #[cfg(test)]
+    pub fn live_path_segments(&self) -> usize {
+        self.shared.critical.lock().segments.iter().filter(|s| s.strong_count() > 0).count()
+    }
+}
+
+impl<'a, P, R, O, W, F, N> Engine<'a, P, R, O, W, F, N>
+where
+    P: Problem + Sync,
+    P::State: Eq + Hash + Send,
+    R: Relaxation<State = P::State> + Sync,
+    O: StateRanking<State = P::State> + Sync,
+    W: WidthHeuristic<P::State> + Sync,
+    F: Frontier<State = P::State> + Send,
+    N: NodeProcessor<P::State> + Sync,
+    N::Bookkeeping: Send,
+{
+
+    /// Solves the problem by spawning `nb_threads` workers (long running
+    /// threads); each of which will continually get a workload and process it
+    /// until the problem is solved or the `cutoff` stops the resolution.
+    pub fn maximize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Sync,
+    {
+        self.solve_with_cutoff(Objective::Maximize, cutoff)
+    }
+
+    /// Solves the problem like `maximize_with_cutoff`, and hands each better
+    /// solution to `on_improvement` as soon as it is found (see
+    /// `InterruptibleSolver::maximize_with_cutoff_and_progress`)
+    pub fn maximize_with_progress<C, G>(&mut self, cutoff: C, on_improvement: G) -> ResolutionStatus
+    where
+        C: Cutoff + Sync,
+        G: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.solve_with_progress(Objective::Maximize, cutoff, Box::new(on_improvement))
+    }
+
+    /// Solves the problem like `minimize_with_cutoff`, and hands the cost of
+    /// each better solution to `on_improvement` as soon as it is found (see
+    /// `InterruptibleSolver::minimize_with_cutoff_and_progress`)
+    pub fn minimize_with_progress<C, G>(&mut self, cutoff: C, on_improvement: G) -> ResolutionStatus
+    where
+        C: Cutoff + Sync,
+        G: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.solve_with_progress(Objective::Minimize, cutoff, Box::new(on_improvement))
+    }
+
+    /// Solves the problem like `maximize_with_cutoff`, but minimizes its
+    /// objective: the dds are compiled for the `Negated` problem and relaxation.
+    pub fn minimize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Sync,
+    {
+        self.solve_with_cutoff(Objective::Minimize, cutoff)
+    }
+
+    /// Solves the problem in the sense of the given `objective`, handing each
+    /// better solution to `on_improvement`
+    fn solve_with_progress<C>(&mut self, objective: Objective, cutoff: C, on_improvement: OnImprovement) -> ResolutionStatus
+    where
+        C: Cutoff + Sync,
+    {
+        self.shared.progress = Some(Progress { on_improvement, reported: Mutex::new(isize::MIN) });
+        let status = self.solve_with_cutoff(objective, cutoff);
+        self.shared.progress = None;
+        status
+    }
+
+    fn solve_with_cutoff<C>(&mut self, objective: Objective, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Sync,
+    {
+        check_objective(self.shared.problem, objective);
+        assert!(
+            !self.shared.critical.get_mut().suspended || self.shared.objective == objective,
+            "a suspended resolution must be resumed with the same objective"
+        );
+        self.shared.objective = objective;
+        self.shared.dd_footprints = (0..self.shared.config.nb_threads).map(|_| AtomicUsize::new(0)).collect();
+        self.shared.critical.get_mut().memory.restart();
+        if !self.initialize() {
+            return ResolutionStatus::Infeasible;
+        }
+        let cutoff = &cutoff;
+        self.shared.started = Instant::now();
+        let start = self.shared.started;
+        std::thread::scope(|s| {
+            for i in 0..self.shared.config.nb_threads {
+                let shared = &self.shared;
+                s.spawn(move || {
+                    let _stop_all = OnPanic(|| {
+                        let mut critical = shared.critical.lock();
+                        critical.interrupted = true;
+                        shared.processor.on_abort(&mut critical.bookkeeping, i);
+                        shared.monitor.notify_all();
+                    });
+                    let mut mdd = shared.processor.new_dd(shared.cutset_type);
+                    let mut filter = PushFilter::new(shared.config.push_filter);
+                    loop {
+                        match Self::get_workload(shared, i, cutoff, start) {
+                            WorkLoad::Complete => break,
+                            WorkLoad::Interruption => break,
+                            WorkLoad::Starvation => continue,
+                            WorkLoad::WorkItem { node, segment } => {
+                                let id = node.id;
+                                let depth = node.depth();
+                                // a cancelled compilation gives its node back (see `suspend`)
+                                let backup = shared.config.suspension.then(|| SubProblem {
+                                    state: node.state.clone(),
+                                    path: segment.to_vec(),
+                                    prefix: None,
+                                    ..node
+                                });
+                                let mut stats = CompilationStatistics::default();
+                                let (explored_dd, disposition) = match shared.objective {
+                                    Objective::Maximize => Self::process_one_node(
+                                        &mut mdd, &mut filter, shared, shared.problem, shared.relaxation, node, &mut stats,
+                                    ),
+                                    Objective::Minimize => Self::process_one_node(
+                                        &mut mdd, &mut filter, shared, &Negated(shared.problem), &Negated(shared.relaxation), node,
+                                        &mut stats,
+                                    ),
+                                };
+                                shared.dd_footprints[i].store(N::footprint(&mdd), Ordering::Relaxed);
+                                Self::notify_node_finished(shared, i, id, depth, disposition, explored_dd, &stats);
+                                if let Some(node) = backup.filter(|_| disposition == Disposition::Interrupted) {
+                                    Self::suspend(shared, node);
+                                }
+                            }
+                            WorkLoad::Dive { seed } => {
+                                let mut stats = CompilationStatistics::default();
+                                let (explored_dd, improved) = match shared.objective {
+                                    Objective::Maximize => Self::dive(&mut mdd, shared, shared.problem, shared.relaxation, seed, &mut stats),
+                                    Objective::Minimize => Self::dive(
+                                        &mut mdd, shared, &Negated(shared.problem), &Negated(shared.relaxation), seed, &mut stats,
+                                    ),
+                                };
+                                shared.dd_footprints[i].store(N::footprint(&mdd), Ordering::Relaxed);
+                                Self::notify_dive_finished(shared, improved, explored_dd, &stats);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let lock = self.shared.critical.lock();
+        if lock.interrupted {
+            ResolutionStatus::Interrupted
+        } else if let Some(error) = self.verify(lock.best_sol.as_deref(), lock.best_lb) {
+            drop(lock);
+            self.shared.verification_error = Some(error);
+            ResolutionStatus::VerificationFailed
+        } else if lock.evicted_ub != isize::MIN {
+            // the optimum may lie below a node the fringe dropped
+            ResolutionStatus::Bounded
+        } else {
+            ResolutionStatus::Proved
+        }
+    }
+
+    /// Compiles restricted dds of the whole problem, whose width doubles from
+    /// the one the width heuristic gives to the root, until the budget is
+    /// spent, one of them is exact or the solver is cancelled. The best of their solutions becomes the
+    /// best solution of the solver: it seeds the best lower bound of the next
+    /// resolution, which must have the same objective as the last one (this
+    /// is the objective of the problem when the solver never ran).
+    pub fn warm_start(&mut self, budget: WarmStartBudget) {
+        self.shared.started = Instant::now();
+        let shared = &self.shared;
+        let mut mdd = shared.processor.new_dd(shared.cutset_type);
+        let mut stats = CompilationStatistics::default();
+        let start = Instant::now();
+        let mut explored_dd = 0;
+        let mut width = shared.width_heu.max_width(&shared.problem.initial_state()).max(1);
+        while !budget.is_spent(explored_dd, start.elapsed()) && !shared.config.cancellation.is_cancelled() {
+            let exact = match shared.objective {
+                Objective::Maximize => Self::compile_root(&mut mdd, shared, shared.problem, shared.relaxation, width, &mut stats),
+                Objective::Minimize => {
+                    Self::compile_root(&mut mdd, shared, &Negated(shared.problem), &Negated(shared.relaxation), width, &mut stats)
+                }
+            };
+            explored_dd += N::explored(&mdd);
+            if exact {
+                break;
+            }
+            width = width.saturating_mul(2);
+        }
+        let mut critical = shared.critical.lock();
+        critical.explored_dd += explored_dd;
+        critical.compilations.merge(&stats);
+    }
+
+    /// Returns the nodes left on the fringe by the last resolution: they are
+    /// all that it did not explore when it was suspended (see `suspension`),
+    /// and there are none when it completed or when it was interrupted
+    /// without being suspended. Their values and bounds are in terms of the
+    /// maximization of that resolution.
+    pub fn into_open_subproblems(mut self) -> Vec<SubProblem<P::State>> {
+        let critical = self.shared.critical.get_mut();
+        let mut nodes = Vec::with_capacity(critical.fringe.len());
+        while let Some(node) = critical.fringe.pop() {
+            nodes.push(node);
+        }
+        nodes
+    }
+
+    /// Makes the next resolution start from the given nodes rather than from
+    /// the root, as if it resumed the suspended resolution which left them
+    /// (see `into_open_subproblems`), e.g. one which ran out of time with a
+    /// smaller width: that resolution must have the same objective. The best
+    /// solution of that resolution is not among these nodes, it is given by
+    /// `set_initial_solution`. Without any node, it starts from the root.
+    pub fn set_initial_fringe(&mut self, nodes: Vec<SubProblem<P::State>>) {
+        if nodes.is_empty() {
+            return;
+        }
+        let mut critical = self.shared.critical.lock();
+        for node in nodes {
+            Self::push(&self.shared, &mut critical, node, None);
+        }
+        critical.suspended = true;
+    }
+
+    /// Replays the best solution (when the verification is enabled) and
+    /// returns the reason why it is not worth the best lower bound, if any
+    fn verify(&self, best_sol: Option<&[Decision]>, best_lb: isize) -> Option<VerificationError> {
+        let path = best_sol.filter(|_| self.shared.config.verification)?;
+        let replayed = match self.shared.objective {
+            Objective::Maximize => check_path(self.shared.problem, path),
+            Objective::Minimize => check_path(&Negated(self.shared.problem), path),
+        };
+        match replayed {
+            Ok(value) if value == best_lb => None,
+            Ok(value) => Some(VerificationError::ValueMismatch { reported: self.reported(best_lb), replayed: self.reported(value) }),
+            Err(error) => Some(error),
+        }
+    }
+
+    /// This method initializes the problem resolution. Put more simply, this
+    /// method posts the root node of the mdd onto the fringe so that a thread
+    /// can pick it up and the processing can be bootstrapped. It returns false
+    /// when the estimate of the root proves the problem infeasible: nothing is
+    /// pushed then, and both bounds are set to `isize::MIN`.
+    fn initialize(&mut self) -> bool {
+        self.shared.verification_error = None;
+        self.shared.self_checks_left.store(SELF_CHECKED_COMPILATIONS, Ordering::Relaxed);
+        self.shared.suppressed_pushes.store(0, Ordering::Relaxed);
+        if self.shared.config.self_check {
+            self.check_sign_convention();
+        }
+        let critical = self.shared.critical.get_mut();
+        if critical.suspended {
+            // the nodes left on the fringe by the suspended resolution are
+            // all that remains to be explored
+            critical.suspended = false;
+            critical.interrupted = false;
+        } else {
+            let root = self.root_node();
+            let estimate = match self.shared.objective {
+                Objective::Maximize => self.shared.problem.estimate(&root.state),
+                Objective::Minimize => Negated(self.shared.problem).estimate(&root.state),
+            };
+            let mut critical = self.shared.critical.lock();
+            if estimate == isize::MIN {
+                critical.best_ub = isize::MIN;
+                critical.global_ub = isize::MIN;
+                return false;
+            }
+            Self::push(&self.shared, &mut critical, root, None);
+        }
+        true
+    }
+
+    /// Warns about a minimization which seems to be maximized. As this is only
+    /// a suspicion, the resolution goes on whatever the violation policy.
+    fn check_sign_convention(&mut self) {
+        let shared = &mut self.shared;
+        let suspicion = match shared.objective {
+            Objective::Maximize => check_sign_convention(shared.problem),
+            Objective::Minimize => check_sign_convention(&Negated(shared.problem)),
+        };
+        if let Some(violation) = suspicion {
+            shared.violations.get_mut().push(violation);
+        }
+    }
+
+    fn root_node(&self) -> SubProblem<P::State> {
+        let shared = &self.shared;
+        SubProblem {
+            state: Arc::new(shared.problem.initial_state()),
+            value: match shared.objective {
+                Objective::Maximize => shared.problem.initial_value(),
+                Objective::Minimize => Negated(shared.problem).initial_value(),
+            },
+            path: vec![],
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        }
+    }
+
+    /// Pushes the given node onto the fringe, after giving it a fresh identifier
+    fn push(shared: &Shared<P, R, O, W, F, N>, critical: &mut Critical<F, N::Bookkeeping>, mut node: SubProblem<P::State>, parent: Option<usize>) {
+        node.id = critical.next_id;
+        node.parent = parent;
+        critical.next_id += 1;
+        if let Some(tree) = critical.tree.as_mut() {
+            tree.push(node.id, parent, node.depth(), node.value, node.ub);
+        }
+        shared.processor.on_push(&mut critical.bookkeeping, &node);
+        Self::enqueue(shared, critical, node);
+    }
+    /// Puts the given node on the fringe and tells the processor when it was
+    /// merged with a node having the same state (or when the fringe dropped
+    /// some node to make room for it)
+    fn enqueue(shared: &Shared<P, R, O, W, F, N>, critical: &mut Critical<F, N::Bookkeeping>, node: SubProblem<P::State>) {
+        match critical.fringe.push(node) {
+            PushOutcome::Merged(dropped) => shared.processor.on_merge(&mut critical.bookkeeping, &dropped),
+            PushOutcome::Evicted(dropped) => {
+                critical.evicted_ub = critical.evicted_ub.max(dropped.ub);
+                shared.processor.on_merge(&mut critical.bookkeeping, &dropped);
+            }
+            PushOutcome::Inserted => {}
+        }
+        critical.peak_fringe = critical.peak_fringe.max(critical.fringe.len());
+    }
+    /// Records what happened to the given node (when the tree is recorded)
+    fn dispose(critical: &mut Critical<F, N::Bookkeeping>, node: &SubProblem<P::State>, disposition: Disposition) {
+        if let Some(tree) = critical.tree.as_mut() {
+            tree.dispose(node.id, disposition);
+        }
+    }
+    /// Returns the best upper bound of the nodes on the fringe (`isize::MIN`
+    /// when it is empty): the first node of an ordered fringe bounds all the
+    /// others
+    fn fringe_ub(fringe: &F) -> isize {
+        if fringe.is_ub_ordered() {
+            fringe.peek().map_or(isize::MIN, |nn| nn.ub)
+        } else {
+            let mut best_ub = isize::MIN;
+            fringe.sample(fringe.len(), &mut |nn| best_ub = best_ub.max(nn.ub));
+            best_ub
+        }
+    }
+    /// Empties the fringe and records the disposition of the discarded nodes
+    /// (when the tree is recorded)
+    fn discard_fringe(critical: &mut Critical<F, N::Bookkeeping>, disposition: Disposition) {
+        if let Some(tree) = critical.tree.as_mut() {
+            while let Some(nn) = critical.fringe.pop() {
+                tree.dispose(nn.id, disposition);
+            }
+        } else {
+            critical.fringe.clear();
+        }
+    }
+
+    /// This method processes the given `node`. To do so, it reads the current
+    /// best lower bound from the critical data. Then it expands a restricted
+    /// and possibly a relaxed mdd rooted in `node`. If that is necessary,
+    /// it stores cutset nodes onto the fringe for further parallel processing.
+    /// It returns the number of nodes expanded in the dds along with the
+    /// disposition of `node`. The dds are compiled for the given `problem`
+    /// and `relaxation`, which are either those of the solver or their
+    /// `Negated` views.
+    #[allow(clippy::too_many_arguments)]
+    fn process_one_node<PP, RR>(
+        mdd: &mut N::Dd,
+        filter: &mut PushFilter<P::State>,
+        shared: &Shared<P, R, O, W, F, N>,
+        problem: &PP,
+        relaxation: &RR,
+        node: SubProblem<P::State>,
+        stats: &mut CompilationStatistics,
+    ) -> (usize, Disposition)
+    where
+        PP: Problem<State = P::State>,
+        RR: Relaxation<State = P::State>,
+    {
+        let mut explored_dd = 0;
+
+        // 1. RESTRICTION
+        let node_id = node.id;
+        let node_ub = node.ub;
+        let best_lb = Self::best_lb(shared);
+
+        if node_ub <= shared.pruning_bound(best_lb) {
+            return (explored_dd, Disposition::Pruned);
+        }
+
+        let is_root = node.depth() == 0;
+        let width = shared.width_heu.max_width_of(&node);
+        let mut compilation = CompilationInputBuilder::restricted(problem, relaxation, shared.ranking)
+            .max_width(width.max(1))
+            .residual(node)
+            .best_lb(shared.pruning_bound(best_lb))
+            .min_relax_depth_offset(shared.config.min_relax_depth_offset)
+            .restrict_first_layers(shared.config.restrict_first_layers)
+            .max_forced_layers(shared.config.max_forced_layers)
+            .dominance(shared.dominance)
+            .reuse_prefix(shared.config.reuse_prefix)
+            .merge_policy(shared.config.merge_policy)
+            .check_merge(shared.config.check_merge)
+            .timed(shared.config.model_timing)
+            .cancellation(&shared.config.cancellation)
+            .build()
+            .expect("the input of a compilation is complete");
+
+        mdd.compile(&compilation);
+        explored_dd += N::explored(mdd);
+        stats.record(mdd);
+        if shared.config.cancellation.is_cancelled() {
+            return (explored_dd, Disposition::Interrupted);
+        }
+        shared.width_heu.on_restriction(mdd.ran_dry());
+        Self::maybe_update_best(mdd, shared);
+        if mdd.is_exact() {
+            if is_root {
+                Self::set_global_ub(shared, mdd.best_value().unwrap_or(best_lb));
+            }
+            return (explored_dd, Disposition::Proved);
+        }
+
+        // 2. RELAXATION
+        let best_lb = Self::best_lb(shared);
+        compilation.comp_type = CompilationType::Relaxed;
+        compilation.best_lb = shared.pruning_bound(best_lb);
+        compilation.incumbent = shared.incumbent();
+        compilation.self_check = Self::take_self_check(shared);
+        if !mdd.resume_relaxed(&compilation) {
+            mdd.compile(&compilation);
+        }
+        explored_dd += N::explored(mdd);
+        stats.record(mdd);
+        if shared.config.cancellation.is_cancelled() {
+            return (explored_dd, Disposition::Interrupted);
+        }
+        if compilation.self_check {
+            Self::collect_violations(mdd, shared);
+        }
+        if is_root {
+            // the relaxed dd only discards the nodes which cannot beat best_lb,
+            // which may have improved while it compiled
+            let best_lb = Self::best_lb(shared);
+            Self::set_global_ub(shared, mdd.best_value().unwrap_or(best_lb).max(best_lb));
+        }
+        if mdd.is_exact() {
+            Self::maybe_update_best(mdd, shared);
+            (explored_dd, Disposition::Proved)
+        } else {
+            Self::enqueue_cutset(mdd, filter, shared, node_id, node_ub);
+            (explored_dd, Disposition::Expanded)
+        }
+    }
+
+    /// This method compiles a restricted dd of the whole problem, whose width
+    /// and ranking are drawn at random after the given `seed`, and updates the
+    /// best known solution with it. It returns the number of nodes expanded
+    /// in the dd along with whether it improved the best solution.
+    fn dive<PP, RR>(
+        mdd: &mut N::Dd,
+        shared: &Shared<P, R, O, W, F, N>,
+        problem: &PP,
+        relaxation: &RR,
+        seed: u64,
+        stats: &mut CompilationStatistics,
+    ) -> (usize, bool)
+    where
+        PP: Problem<State = P::State>,
+        RR: Relaxation<State = P::State>,
+    {
+        let ranking = Shuffled::new(shared.ranking, seed);
+        let compilation = CompilationInputBuilder::restricted(problem, relaxation, &ranking)
+            .root()
+            .max_width(dive_width(shared.width_heu.max_width(&problem.initial_state()), seed).max(1))
+            .best_lb(shared.pruning_bound(Self::best_lb(shared)))
+            .min_relax_depth_offset(shared.config.min_relax_depth_offset)
+            .dominance(shared.dominance)
+            .check_merge(shared.config.check_merge)
+            .timed(shared.config.model_timing)
+            .cancellation(&shared.config.cancellation)
+            .build()
+            .expect("the input of a compilation is complete");
+
+        mdd.compile(&compilation);
+        stats.record(mdd);
+        let improved = !shared.config.cancellation.is_cancelled() && Self::maybe_update_best(mdd, shared);
+        (N::explored(mdd), improved)
+    }
+
+    /// This method compiles a restricted dd of the whole problem with the
+    /// given width (for the warm start), and updates the best known solution
+    /// with it. It returns true iff the dd is exact.
+    fn compile_root<PP, RR>(
+        mdd: &mut N::Dd,
+        shared: &Shared<P, R, O, W, F, N>,
+        problem: &PP,
+        relaxation: &RR,
+        width: usize,
+        stats: &mut CompilationStatistics,
+    ) -> bool
+    where
+        PP: Problem<State = P::State>,
+        RR: Relaxation<State = P::State>,
+    {
+        let compilation = CompilationInputBuilder::restricted(problem, relaxation, shared.ranking)
+            .root()
+            .max_width(width)
+            .best_lb(shared.pruning_bound(Self::best_lb(shared)))
+            .min_relax_depth_offset(shared.config.min_relax_depth_offset)
+            .dominance(shared.dominance)
+            .check_merge(shared.config.check_merge)
+            .timed(shared.config.model_timing)
+            .cancellation(&shared.config.cancellation)
+            .build()
+            .expect("the input of a compilation is complete");
+
+        mdd.compile(&compilation);
+        stats.record(mdd);
+        if shared.config.cancellation.is_cancelled() {
+            return false;
+        }
+        Self::maybe_update_best(mdd, shared);
+        mdd.is_exact()
+    }
+
+    fn best_lb(shared: &Shared<P, R, O, W, F, N>) -> isize {
+        shared.critical.lock().best_lb
+    }
+
+    /// Records the bound given by the dds of the root node
+    fn set_global_ub(shared: &Shared<P, R, O, W, F, N>, ub: isize) {
+        let mut critical = shared.critical.lock();
+        let before = critical.upper_bound();
+        critical.global_ub = critical.global_ub.min(ub);
+        shared.notify_bound(before, critical.upper_bound());
+    }
+
+    /// Tells whether the next relaxed compilation must be self checked
+    fn take_self_check(shared: &Shared<P, R, O, W, F, N>) -> bool {
+        shared.config.self_check
+            && shared.self_checks_left.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1)).is_ok()
+    }
+    /// Keeps the violations found during the last compilation of `mdd`. With
+    /// the abort policy, the first one interrupts the resolution.
+    fn collect_violations(mdd: &mut N::Dd, shared: &Shared<P, R, O, W, F, N>) {
+        let mut violations = shared.violations.lock();
+        let before = violations.len();
+        mdd.drain_violations(|violation| violations.push(violation));
+        let found = violations.len() > before;
+        drop(violations);
+
+        if found && shared.config.on_violation == OnViolation::Abort {
+            shared.critical.lock().interrupted = true;
+            shared.monitor.notify_all();
+        }
+    }
+
+    /// This private method updates the shared best known node and lower bound in
+    /// case the best value of the current `mdd` expansion improves the current
+    /// bounds. With a secondary objective, a solution worth the best lower
+    /// bound also replaces the best one when its score is higher: the best
+    /// paths of `mdd` are scored (outside of the critical section) and the
+    /// first one with the highest score is kept. It returns true when the
+    /// best solution was replaced.
+    fn maybe_update_best(mdd: &N::Dd, shared: &Shared<P, R, O, W, F, N>) -> bool {
+        let dd_best_value = mdd.best_exact_value().unwrap_or(isize::MIN);
+        if shared.pool_capacity > 0 && dd_best_value > isize::MIN {
+            Self::offer_to_pool(mdd, shared, dd_best_value);
+        }
+        let Some(secondary) = shared.secondary else {
+            let mut critical = shared.critical.lock();
+            if dd_best_value > critical.best_lb {
+                critical.best_lb = dd_best_value;
+                critical.best_sol = mdd.best_exact_solution();
+                shared.best_lb_mirror.store(dd_best_value, Ordering::Relaxed);
+                shared.notify_incumbent(dd_best_value);
+                Self::maybe_shed(shared, &mut critical);
+                drop(critical);
+                if let Some(solution) = mdd.best_exact_solution().filter(|_| shared.progress.is_some()) {
+                    shared.report_progress(dd_best_value, &solution);
+                }
+                return true;
+            }
+            return false;
+        };
+
+        if dd_best_value == isize::MIN || dd_best_value < Self::best_lb(shared) {
+            return false;
+        }
+        let mut best: Option<(isize, Vec<Decision>)> = None;
+        for sol in mdd.best_solutions(shared.config.tie_limit.max(1)) {
+            let score = secondary.score(&sol);
+            if best.as_ref().is_none_or(|(s, _)| score > *s) {
+                best = Some((score, sol));
+            }
+        }
+        let Some((score, sol)) = best else { return false };
+
+        let mut critical = shared.critical.lock();
+        if dd_best_value > critical.best_lb || (dd_best_value == critical.best_lb && score > critical.best_score) {
+            let improved = dd_best_value > critical.best_lb;
+            let progress = (improved && shared.progress.is_some()).then(|| sol.clone());
+            critical.best_lb = dd_best_value;
+            critical.best_sol = Some(sol);
+            shared.best_lb_mirror.store(dd_best_value, Ordering::Relaxed);
+            critical.best_score = score;
+            if improved {
+                shared.notify_incumbent(dd_best_value);
+                Self::maybe_shed(shared, &mut critical);
+            }
+            drop(critical);
+            if let Some(solution) = progress {
+                shared.report_progress(dd_best_value, &solution);
+            }
+            true
+        } else {
+            false
+        }
+    }
+    /// Offers the best paths of `mdd` (which are worth `dd_best_value`) to the
+    /// pool of solutions, unless the pool is full of better ones already
+    fn offer_to_pool(mdd: &N::Dd, shared: &Shared<P, R, O, W, F, N>, dd_best_value: isize) {
+        if shared.critical.lock().pool.threshold().is_some_and(|threshold| dd_best_value <= threshold) {
+            return;
+        }
+        let solutions = mdd.best_solutions(shared.pool_capacity);
+        let mut critical = shared.critical.lock();
+        for sol in solutions {
+            critical.pool.offer(dd_best_value, sol);
+        }
+    }
+    /// Drops the nodes of the fringe which cannot improve the best lower bound
+    /// when the shedding policy says so (see `Shedding`). This is called from
+    /// within the critical section, right after the best lower bound improved.
+    fn maybe_shed(shared: &Shared<P, R, O, W, F, N>, critical: &mut Critical<F, N::Bookkeeping>) {
+        let Some(shedding) = shared.config.shedding else { return };
+        let bound = shared.pruning_bound(critical.best_lb);
+        let must_shed = critical.best_lb.saturating_sub(critical.shed_lb) > shedding.margin || {
+            let (mut sampled, mut dominated) = (0, 0);
+            critical.fringe.sample(shedding.samples, &mut |node| {
+                sampled += 1;
+                if node.ub <= bound {
+                    dominated += 1;
+                }
+            });
+            sampled > 0 && dominated as f64 > shedding.fraction * sampled as f64
+        };
+        if !must_shed {
+            return;
+        }
+
+        critical.shed_lb = critical.best_lb;
+        let (tree, bookkeeping) = (&mut critical.tree, &mut critical.bookkeeping);
+        let mut shed = 0;
+        critical.fringe.retain(&mut |node| {
+            if node.ub > bound {
+                return true;
+            }
+            shed += 1;
+            if let Some(tree) = tree.as_mut() {
+                tree.dispose(node.id, Disposition::Pruned);
+            }
+            shared.processor.on_node_pruned(bookkeeping, node);
+            false
+        });
+        critical.shed += shed;
+    }
+    /// If necessary, thightens the bound of nodes in the cutset of `mdd` (none
+    /// of which may exceed the bound of its parent nor the global upper bound)
+    /// and then add the relevant nodes to the shared fringe. These nodes only
+    /// keep the decisions made below their parent, whose path is the prefix
+    /// they share (see `CompilationInput::residual`).
+    ///
+    /// # Note
+    /// The cutset is drained (and the paths of its nodes are materialized)
+    /// outside of the critical section, so the lock is only held while the
+    /// surviving nodes are pushed onto the fringe. This is also where the
+    /// cutset nodes dominated by a node recently enqueued by the same worker
+    /// are filtered out (when the solver has a push filter).
+    fn enqueue_cutset(
+        mdd: &mut N::Dd,
+        filter: &mut PushFilter<P::State>,
+        shared: &Shared<P, R, O, W, F, N>,
+        parent: usize,
+        ub: isize,
+    ) {
+        let (best_lb, ub) = {
+            let critical = shared.critical.lock();
+            (shared.pruning_bound(critical.best_lb), ub.min(critical.global_ub))
+        };
+        let mut cutset = vec![];
+        let mut generated = 0;
+        let mut suppressed = 0;
+        mdd.drain_cutset(|mut cutset_node| {
+            generated += 1;
+            cutset_node.ub = ub.min(cutset_node.ub);
+            if cutset_node.ub <= best_lb {
+                return;
+            }
+            if filter.admit(&cutset_node.state, cutset_node.value, cutset_node.ub) {
+                cutset.push(cutset_node);
+            } else {
+                suppressed += 1;
+            }
+        });
+        if suppressed > 0 {
+            shared.suppressed_pushes.fetch_add(suppressed, Ordering::Relaxed);
+        }
+        if generated == 0 {
+            return;
+        }
+
+        let mut critical = shared.critical.lock();
+        let critical = &mut *critical;
+        critical.cutset_generated += generated;
+        // the best lower bound may have improved in the meantime
+        let best_lb = shared.pruning_bound(critical.best_lb);
+        for cutset_node in cutset {
+            if cutset_node.ub > best_lb {
+                critical.cutset_enqueued += 1;
+                Self::push(shared, critical, cutset_node, Some(parent));
+            }
+        }
+    }
+    /// Acknowledges that a thread finished processing its node.
+    fn notify_node_finished(shared: &Shared<P, R, O, W, F, N>, thread_id: usize, id: usize, depth: usize, disposition: Disposition, explored_dd: usize, stats: &CompilationStatistics) {
+        let mut critical = shared.critical.lock();
+        if let Some(tree) = critical.tree.as_mut() {
+            tree.dispose(id, disposition);
+        }
+        if disposition == Disposition::Interrupted && !critical.interrupted {
+            // the node was cancelled before any worker noticed: its bound
+            // must be accounted for while it is still ongoing
+            Self::interrupt(shared, &mut critical);
+        }
+        critical.ongoing -= 1;
+        critical.upper_bounds[thread_id] = isize::MAX;
+        shared.processor.on_finish(&mut critical.bookkeeping, thread_id, depth);
+        critical.explored_dd += explored_dd;
+        critical.compilations.merge(stats);
+
+        shared.monitor.notify_all();
+    }
+    /// Acknowledges that a thread finished a dive from the root.
+    fn notify_dive_finished(shared: &Shared<P, R, O, W, F, N>, improved: bool, explored_dd: usize, stats: &CompilationStatistics) {
+        let mut critical = shared.critical.lock();
+        if improved {
+            critical.restarts.on_improving_dive();
+        }
+        critical.explored_dd += explored_dd;
+        critical.compilations.merge(stats);
+
+        shared.monitor.notify_all();
+    }
+
+    /// Records the estimated bytes held by the dds of the workers, the nodes of
+    /// the fringe and the shared data of the processor. It only reads counters
+    /// (and lengths), so that it can be done from within the critical section.
+    fn sample_memory(shared: &Shared<P, R, O, W, F, N>, critical: &mut Critical<F, N::Bookkeeping>) {
+        let dds = shared.dd_footprints.iter().map(|bytes| bytes.load(Ordering::Relaxed)).sum();
+        let fringe = critical.fringe.len() * (std::mem::size_of::<SubProblem<P::State>>() + std::mem::size_of::<P::State>());
+        let barrier = shared.processor.shared_footprint();
+        critical.memory.record(dds, fringe, barrier);
+    }
+
+    /// Puts back onto the fringe a node whose exploration was cancelled, for
+    /// the suspended resolution to explore it when it is resumed
+    fn suspend(shared: &Shared<P, R, O, W, F, N>, node: SubProblem<P::State>) {
+        let mut critical = shared.critical.lock();
+        shared.processor.on_suspend(&mut critical.bookkeeping, &node);
+        let parent = node.parent;
+        Self::push(shared, &mut critical, node, parent);
+    }
+
+    /// Stops the resolution: the best upper bound becomes the greatest bound
+    /// of the nodes being explored and of those left on the fringe, which is
+    /// then discarded (unless the resolution is suspended, see `suspension`).
+    fn interrupt(shared: &Shared<P, R, O, W, F, N>, critical: &mut Critical<F, N::Bookkeeping>) {
+        critical.interrupted = true;
+
+        let before = critical.upper_bound();
+        let ongoing_ub = if critical.ongoing > 0 {
+            critical
+                .upper_bounds
+                .iter()
+                .copied()
+                .filter(|x| *x != isize::MAX)
+                .max()
+                .unwrap_or(isize::MAX)
+        } else {
+            isize::MIN
+        };
+        // a node may have gone (back) to the fringe after some node with a
+        // smaller ub was handed out: the fringe always counts
+        critical.best_ub = ongoing_ub.max(Self::fringe_ub(&critical.fringe)).max(critical.evicted_ub);
+        if shared.config.suspension {
+            // the nodes stay on the fringe, for the next resolution to resume them
+            critical.suspended = true;
+            shared.notify_bound(before, critical.upper_bound());
+            return;
+        }
+        shared.notify_bound(before, critical.upper_bound());
+
+        Self::discard_fringe(critical, Disposition::Interrupted);
+    }
+
+    /// Consults the shared state to fetch a workload. Depending on the current
+    /// state, the workload can either be:
+    ///
+    ///   + Complete, when the problem is solved and all threads should stop
+    ///   + Interruption, when the cutoff stops the resolution (or when
+    ///     another worker panicked)
+    ///   + Starvation, when there is no subproblem available for processing
+    ///     at the time being (but some subproblem are still being processed
+    ///     and thus the problem cannot be considered solved).
+    ///   + WorkItem, when the thread successfully obtained a subproblem to
+    ///     process.
+    ///   + Dive, when the random restarts are triggered and the thread is one
+    ///     of the divers: it must compile a restricted dd from the root.
+    fn get_workload<C>(
+        shared: &Shared<P, R, O, W, F, N>,
+        thread_id: usize,
+        cutoff: &C,
+        start: Instant,
+    ) -> WorkLoad<P::State>
+    where
+        C: Cutoff,
+    {
+        let mut critical = shared.critical.lock();
+
+        shared.processor.on_get_workload(&mut critical.bookkeeping);
+
+        // Was the resolution stopped ? (an interrupted resolution is never
+        // complete, even when the nodes it discarded leave nothing to do)
+        if critical.interrupted {
+            return WorkLoad::Interruption;
+        }
+
+        // Are we done ?
+        if critical.ongoing == 0 && critical.fringe.is_empty() {
+            let before = critical.upper_bound();
+            critical.best_ub = critical.best_lb.max(critical.evicted_ub);
+            shared.notify_bound(before, critical.upper_bound());
+            return WorkLoad::Complete;
+        }
+
+        // Do we need to stop
+        if shared.config.cancellation.is_cancelled()
+            || cutoff.must_stop(&CutoffStats { elapsed: start.elapsed(), explored: critical.explored, explored_dd: critical.explored_dd })
+        {
+            Self::interrupt(shared, &mut critical);
+            return WorkLoad::Interruption;
+        }
+
+        // Should we dive from the root rather than explore the fringe ?
+        if let Some(restarts) = shared.config.restarts.as_ref() {
+            let (best_lb, explored, fringe) = (critical.best_lb, critical.explored, critical.fringe.len());
+            if critical.restarts.role(restarts, thread_id, best_lb, explored, fringe) == Role::Diver {
+                return WorkLoad::Dive { seed: critical.restarts.start_dive() };
+            }
+        }
+
+        // Nothing to do yet ? => Wait for someone to post jobs
+        if critical.fringe.is_empty() {
+            shared.monitor.wait(&mut critical);
+            return WorkLoad::Starvation;
+        }
+        // Nothing relevant ? =>  Wait for someone to post jobs
+        let mut deferred = vec![];
+        let mut nn = critical.fringe.pop().unwrap();
+        let chosen = loop {
+            if nn.ub <= shared.pruning_bound(critical.best_lb) {
+                Self::dispose(&mut critical, &nn, Disposition::Pruned);
+                count_discard(&mut critical.discarded_by_bound, nn.depth());
+                if critical.fringe.is_ub_ordered() {
+                    // none of the other nodes can do better
+                    let inner = &mut *critical;
+                    let (fringe, discarded) = (&inner.fringe, &mut inner.discarded_by_bound);
+                    fringe.sample(fringe.len(), &mut |nn| count_discard(discarded, nn.depth()));
+                    Self::discard_fringe(&mut critical, Disposition::Pruned);
+                    shared.processor.on_fringe_pruned(&mut critical.bookkeeping);
+                    break None;
+                }
+                shared.processor.on_node_pruned(&mut critical.bookkeeping, &nn);
+            } else {
+                let best_lb = critical.best_lb;
+                match shared.processor.admit(&mut critical.bookkeeping, &nn, best_lb) {
+                    Admission::Explore => break Some(nn),
+                    Admission::Reject  => {
+                        Self::dispose(&mut critical, &nn, Disposition::Pruned);
+                        count_discard(&mut critical.discarded_by_barrier, nn.depth());
+                    }
+                    Admission::Defer   => deferred.push(nn),
+                    Admission::Wait    => {
+                        deferred.push(nn);
+                        break None;
+                    }
+                }
+            }
+
+            match critical.fringe.pop() {
+                Some(next) => nn = next,
+                None => break None,
+            }
+        };
+
+        // The nodes which were put aside go back to the fringe as they are
+        let must_wait = !deferred.is_empty();
+        for node in deferred {
+            Self::enqueue(shared, &mut critical, node);
+        }
+        let mut nn = match chosen {
+            Some(nn) => nn,
+            None => {
+                // someone else is busy with the only nodes we could explore
+                if must_wait {
+                    shared.monitor.wait(&mut critical);
+                }
+                return WorkLoad::Starvation;
+            }
+        };
+
+        // Consume the current node and process it
+        critical.ongoing += 1;
+        critical.explored += 1;
+        critical.upper_bounds[thread_id] = nn.ub;
+        if critical.memory.is_due() {
+            Self::sample_memory(shared, &mut critical);
+        }
+        shared.processor.on_start(&mut critical.bookkeeping, thread_id, &nn);
+
+        // Hand the complete path over to the worker as a single segment: the
+        // dds only materialize it in their own (reused) buffer, and the
+        // children of the node share it as their prefix
+        let segment = Arc::new(PathSegment::new(nn.prefix.take(), std::mem::take(&mut nn.path)));
+        nn.prefix = Some(segment.clone());
+        #[cfg(test)]
+        critical.segments.push(Arc::downgrade(&segment));
+
+        WorkLoad::WorkItem { node: nn, segment }
+    }
+
+    /// Pushes the given node onto the fringe, as a worker does with its
+    /// cutset. Along with `claim_node` and `release_node`, this lets the tests
+    /// play the part of the workers in whatever interleaving they choose.
+    #[cfg(test)]
+    pub fn post_node(&self, node: SubProblem<P::State>) {
+        let mut critical = self.shared.critical.lock();
+        Self::push(&self.shared, &mut critical, node, None);
+        self.shared.monitor.notify_all();
+    }
+    /// Asks for the workload of the given worker, and returns the node it is
+    /// given to explore (if any)
+    #[cfg(test)]
+    pub fn claim_node(&self, thread_id: usize) -> Option<SubProblem<P::State>> {
+        match Self::get_workload(&self.shared, thread_id, &crate::InterruptCutoff(|| false), Instant::now()) {
+            WorkLoad::WorkItem { node, .. } => Some(node),
+            _ => None,
+        }
+    }
+    /// Tells that the given worker is done with the node it was given
+    #[cfg(test)]
+    pub fn release_node(&self, thread_id: usize, node: &SubProblem<P::State>) {
+        let stats = CompilationStatistics::default();
+        Self::notify_node_finished(&self.shared, thread_id, node.id, node.depth(), Disposition::Expanded, 0, &stats);
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl<P, R, O, W, F, N> Engine<'_, P, R, O, W, F, N>
+where
+    P: Problem + Sync,
+    P::State: Eq + Hash + Clone + Send,
+    R: Relaxation<State = P::State> + Sync,
+    O: StateRanking<State = P::State> + Sync,
+    W: WidthHeuristic<P::State> + Sync,
+    F: Frontier<State = P::State> + Send,
+    N: NodeProcessor<P::State> + Sync,
+    N::Bookkeeping: Send,
+{
+    /// Returns what another engine needs to resume the last resolution, or
+    /// None when that resolution was interrupted without being suspended:
+    /// the nodes it did not explore are lost.
+    pub fn search_state(&self) -> Option<SearchState<P::State>> {
+        let critical = self.shared.critical.lock();
+        if critical.interrupted && !critical.suspended {
+            return None;
+        }
+        let mut fringe = Vec::with_capacity(critical.fringe.len());
+        critical.fringe.sample(critical.fringe.len(), &mut |nn| fringe.push(nn.clone()));
+        Some(SearchState {
+            objective: self.shared.objective,
+            suspended: critical.suspended,
+            best_lb: critical.best_lb,
+            best_sol: critical.best_sol.clone(),
+            best_score: critical.best_score,
+            global_ub: critical.global_ub,
+            explored: critical.explored,
+            explored_dd: critical.explored_dd,
+            fringe,
+        })
+    }
+    /// Resumes the resolution described by the given state: the next one
+    /// starts from its fringe (when it was suspended) and its best solution.
+    pub fn restore_search_state(&mut self, state: SearchState<P::State>) {
+        self.shared.objective = state.objective;
+        let mut critical = self.shared.critical.lock();
+        critical.suspended = state.suspended;
+        critical.best_lb = state.best_lb;
+        critical.best_sol = state.best_sol;
+        self.shared.best_lb_mirror.store(state.best_lb, Ordering::Relaxed);
+        critical.best_score = state.best_score;
+        critical.global_ub = state.global_ub;
+        critical.explored = state.explored;
+        critical.explored_dd = state.explored_dd;
+        for node in state.fringe {
+            Self::push(&self.shared, &mut critical, node, None);
+        }
+    }
+    /// Seeds the next resolution with the best solution of the given state,
+    /// without its fringe nor its counters: that resolution starts from the
+    /// root, with the objective of the given state.
+    pub fn restore_incumbent(&mut self, state: SearchState<P::State>) {
+        self.shared.objective = state.objective;
+        let mut critical = self.shared.critical.lock();
+        critical.best_lb = state.best_lb;
+        critical.best_sol = state.best_sol;
+        self.shared.best_lb_mirror.store(state.best_lb, Ordering::Relaxed);
+        critical.best_score = state.best_score;
+        critical.global_ub = state.global_ub;
+    }
+}
+
+#[cfg(test)]
+mod test_engine {
+    use std::{sync::{atomic::{AtomicUsize, Ordering}, Arc}, time::{Duration, Instant}};
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState, Tweaked};
+    use crate::{
+        All, CompilationInput, CompilationStats, CutsetType, Decision, DecisionDiagram, Fixed, Frontier, NoDupFrontier, Problem, Relaxation,
+        InterruptCutoff, ResolutionStatus, StateRanking, SubProblem, VerificationError, Violation, CompilationType,
+        Restarts, RestartTrigger, Shedding, check_path,
+    };
+
+    use super::{Engine, NodeProcessor};
+
+    /// A dd which may claim that its best solution is worth more than it is,
+    /// and which may hold the restricted compilation of the root back until
+    /// a dive has been made
+    struct Probe {
+        dd: All<KnapsackState>,
+        inflation: isize,
+        dives: Option<Arc<AtomicUsize>>,
+    }
+    impl DecisionDiagram for Probe {
+        type State = KnapsackState;
+
+        fn compile<P, R, O>(&mut self, input: &CompilationInput<P, R, O>)
+        where
+            P: Problem<State = KnapsackState>,
+            R: Relaxation<State = KnapsackState>,
+            O: StateRanking<State = KnapsackState>,
+        {
+            let Some(dives) = self.dives.as_ref() else {
+                return self.dd.compile(input);
+            };
+            // the dives are the only compilations ranked by a shuffled ranking
+            let dive = std::any::type_name::<O>().contains("Shuffled");
+            if !dive && input.residual.depth() == 0 && input.comp_type == CompilationType::Restricted {
+                let start = Instant::now();
+                while dives.load(Ordering::SeqCst) == 0 && start.elapsed() < Duration::from_secs(10) {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+            self.dd.compile(input);
+            if dive {
+                dives.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        fn is_exact(&self) -> bool {
+            self.dd.is_exact()
+        }
+        fn best_value(&self) -> Option<isize> {
+            self.dd.best_value().map(|value| value + self.inflation)
+        }
+        fn best_solution(&self) -> Option<Vec<Decision>> {
+            self.dd.best_solution()
+        }
+        fn best_exact_value(&self) -> Option<isize> {
+            self.dd.best_exact_value().map(|value| value + self.inflation)
+        }
+        fn best_exact_solution(&self) -> Option<Vec<Decision>> {
+            self.dd.best_exact_solution()
+        }
+        fn best_solutions(&self, limit: usize) -> Vec<Vec<Decision>> {
+            self.dd.best_solutions(limit)
+        }
+        fn drain_cutset<F>(&mut self, func: F)
+        where
+            F: FnMut(SubProblem<KnapsackState>),
+        {
+            self.dd.drain_cutset(func)
+        }
+        fn nb_nodes(&self) -> usize {
+            self.dd.nb_nodes()
+        }
+        fn nb_active_nodes(&self) -> usize {
+            self.dd.nb_active_nodes()
+        }
+        fn nb_edges(&self) -> usize {
+            self.dd.nb_edges()
+        }
+        fn nb_avoided_allocations(&self) -> usize {
+            self.dd.nb_avoided_allocations()
+        }
+        fn nb_reservations(&self) -> usize {
+            self.dd.nb_reservations()
+        }
+        fn nb_reallocations(&self) -> usize {
+            self.dd.nb_reallocations()
+        }
+        fn ran_dry(&self) -> bool {
+            self.dd.ran_dry()
+        }
+        fn stats(&self) -> &CompilationStats {
+            self.dd.stats()
+        }
+        fn model_time(&self) -> Duration {
+            self.dd.model_time()
+        }
+        fn framework_time(&self) -> Duration {
+            self.dd.framework_time()
+        }
+        fn drain_violations<F>(&mut self, func: F)
+        where
+            F: FnMut(Violation<KnapsackState>),
+        {
+            self.dd.drain_violations(func)
+        }
+    }
+
+    struct ProbeProcessor {
+        inflation: isize,
+        dives: Option<Arc<AtomicUsize>>,
+    }
+    impl NodeProcessor<KnapsackState> for ProbeProcessor {
+        type Dd = Probe;
+        type Bookkeeping = ();
+
+        fn new_dd(&self, cutset_type: CutsetType) -> Probe {
+            Probe { dd: All::new(cutset_type), inflation: self.inflation, dives: self.dives.clone() }
+        }
+        fn explored(dd: &Probe) -> usize {
+            dd.dd.get_explored()
+        }
+        fn footprint(dd: &Probe) -> usize {
+            dd.dd.footprint()
+        }
+    }
+
+    #[test]
+    fn an_inflated_best_value_fails_the_verification() {
+        let problem = Knapsack::toy();
+        for verify in [true, false] {
+            let mut engine = Engine::new(
+                &problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::Frontier,
+                NoDupFrontier::new(&KnapsackRanking), 1, ProbeProcessor { inflation: 1, dives: None }, (),
+            );
+            engine.configure(|config| config.verification = verify);
+            let status = engine.maximize_with_cutoff(InterruptCutoff(|| false));
+            let reported = engine.best_value().unwrap();
+            if verify {
+                assert_eq!(ResolutionStatus::VerificationFailed, status);
+                assert_eq!(Some(VerificationError::ValueMismatch { reported, replayed: reported - 1 }), engine.verification_error());
+            } else {
+                assert_eq!(ResolutionStatus::Proved, status);
+                assert_eq!(None, engine.verification_error());
+            }
+        }
+    }
+
+    #[test]
+    fn a_dive_shares_its_solution_with_the_branch_and_bound() {
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            // the root is held back until a dive has found a first solution
+            let dives = Arc::new(AtomicUsize::new(0));
+            let mut engine = Engine::new(
+                &problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), cutset_type,
+                NoDupFrontier::new(&KnapsackRanking), 2, ProbeProcessor { inflation: 0, dives: Some(dives.clone()) }, (),
+            );
+            engine.configure(|config| config.restarts = Some(Restarts::new(RestartTrigger::Stall(0))));
+            assert_eq!(ResolutionStatus::Proved, engine.maximize_with_cutoff(InterruptCutoff(|| false)));
+            assert!(dives.load(Ordering::SeqCst) > 0);
+            assert!(engine.get_dives() >= dives.load(Ordering::SeqCst));
+            assert!(engine.get_improving_dives() > 0);
+            assert_eq!(Some(13), engine.best_value());
+            assert_eq!(13, engine.best_upper_bound());
+            assert_eq!(Ok(13), check_path(&problem, &engine.best_solution().unwrap()));
+        }
+    }
+
+    #[test]
+    fn an_infeasible_root_is_reported_without_spawning_the_workers() {
+        let problem = Tweaked::infeasible(Knapsack::toy());
+        let mut engine = Engine::new(
+            &problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer,
+            NoDupFrontier::new(&KnapsackRanking), 2, ProbeProcessor { inflation: 0, dives: None }, (),
+        );
+        for _ in 0..2 {
+            assert_eq!(ResolutionStatus::Infeasible, engine.maximize_with_cutoff(InterruptCutoff(|| false)));
+            assert_eq!((None, 0, 0), (engine.best_value(), engine.get_explored(), engine.get_explored_dd()));
+            assert_eq!((isize::MIN, isize::MIN), (engine.best_lower_bound(), engine.best_upper_bound()));
+        }
+    }
+
+    fn node(capacity: usize, ub: isize) -> SubProblem<KnapsackState> {
+        SubProblem { state: Arc::new(KnapsackState { depth: 1, capacity }), value: 0, path: vec![], ub, id: capacity, parent: None, prefix: None }
+    }
+
+    #[test]
+    fn a_big_jump_of_the_best_value_sheds_the_fringe() {
+        let problem = Knapsack::toy();
+        for (margin, best_lb, expected) in [(0, 8, 8), (5, 8, 8), (10, 8, 0), (10, 11, 10)] {
+            let mut engine = Engine::new(
+                &problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier,
+                NoDupFrontier::new(&KnapsackRanking), 1, ProbeProcessor { inflation: 0, dives: None }, (),
+            );
+            // the fraction never triggers the shed by itself
+            engine.configure(|config| config.shedding = Some(Shedding::new(margin, 1.0)));
+            let mut critical = engine.shared.critical.lock();
+            critical.shed_lb = 0;
+            for ub in 1..=10 {
+                critical.fringe.push(node(ub as usize, ub));
+            }
+            critical.best_lb = best_lb;
+            Engine::maybe_shed(&engine.shared, &mut critical);
+            assert_eq!(expected, critical.shed);
+            assert_eq!(10 - expected, critical.fringe.len());
+            drop(critical);
+            assert_eq!(expected, engine.get_shed_nodes());
+        }
+    }
+
+    #[test]
+    fn the_dominated_fraction_sheds_the_fringe() {
+        let problem = Knapsack::toy();
+        let mut engine = Engine::new(
+            &problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier,
+            NoDupFrontier::new(&KnapsackRanking), 1, ProbeProcessor { inflation: 0, dives: None }, (),
+        );
+        engine.configure(|config| config.shedding = Some(Shedding::new(isize::MAX, 0.5).with_samples(10)));
+        let mut critical = engine.shared.critical.lock();
+        critical.shed_lb = 0;
+        for ub in 1..=10 {
+            critical.fringe.push(node(ub as usize, ub));
+        }
+        critical.best_lb = 5;
+        Engine::maybe_shed(&engine.shared, &mut critical);
+        assert_eq!((0, 10), (critical.shed, critical.fringe.len()));
+        critical.best_lb = 6;
+        Engine::maybe_shed(&engine.shared, &mut critical);
+        assert_eq!((6, 4), (critical.shed, critical.fringe.len()));
+    }
+}
@@ -0,0 +1,228 @@
+//! This module provides a solver which compiles the exact dd of the whole
+//! problem, instead of running the branch-and-bound. This is only viable for
+//! tiny instances, but it enumerates all the optimal solutions rather than
+//! the single one a resolution reports.
+
+use std::{cmp::Ordering, hash::Hash, marker::PhantomData};
+
+use crate::{
+    objective::negate, All, CompilationInputBuilder, CompilationStatistics, CutsetType, Decision, DecisionDiagram, Negated,
+    Objective, Problem, Relaxation, ResolutionStatus, StateRanking,
+};
+
+/// Compiles the exact dd of the root, and enumerates the solutions which are
+/// worth its best value (see `DecisionDiagram::best_solutions`). The value
+/// and the bounds are reported in terms of the objective of the problem (see
+/// `Problem::objective`).
+pub struct ExactSolver<'a, P, R, O> {
+    problem: &'a P,
+    relaxation: &'a R,
+    ranking: &'a O,
+    /// The largest number of optimal solutions which are enumerated
+    solution_limit: usize,
+    objective: Objective,
+
+    best_value: Option<isize>,
+    best_sols: Vec<Vec<Decision>>,
+    explored_dd: usize,
+    compilations: CompilationStatistics,
+}
+
+impl<'a, P, R, O> ExactSolver<'a, P, R, O>
+where
+    P: Problem,
+    P::State: Eq + Hash + Clone,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+{
+    /// The number of optimal solutions which are enumerated by default
+    pub const DEFAULT_SOLUTION_LIMIT: usize = 1000;
+
+    pub fn new(problem: &'a P, relaxation: &'a R, ranking: &'a O) -> Self {
+        Self {
+            problem,
+            relaxation,
+            ranking,
+            solution_limit: Self::DEFAULT_SOLUTION_LIMIT,
+            objective: problem.objective(),
+            best_value: None,
+            best_sols: vec![],
+            explored_dd: 0,
+            compilations: CompilationStatistics::default(),
+        }
+    }
+    /// Sets the largest number of optimal solutions which are enumerated
+    pub fn with_solution_limit(mut self, limit: usize) -> Self {
+        self.solution_limit = limit;
+        self
+    }
+
+    /// Compiles the exact dd of the problem and enumerates its optimal
+    /// solutions. The dd being exact, this always returns `Proved`.
+    pub fn compute(&mut self) -> ResolutionStatus {
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        match self.objective {
+            Objective::Maximize => Self::compile(&mut mdd, self.problem, self.relaxation, self.ranking),
+            Objective::Minimize => Self::compile(&mut mdd, &Negated(self.problem), &Negated(self.relaxation), self.ranking),
+        }
+        self.explored_dd += mdd.get_explored();
+        self.compilations.record(&mdd);
+        self.best_value = mdd.best_value().map(|value| match self.objective {
+            Objective::Maximize => value,
+            Objective::Minimize => negate(value),
+        });
+        self.best_sols = mdd.best_solutions(self.solution_limit);
+        ResolutionStatus::Proved
+    }
+    /// Compiles the exact dd of the root of the given (possibly negated)
+    /// problem
+    fn compile<PP, RR>(mdd: &mut All<P::State>, problem: &PP, relaxation: &RR, ranking: &O)
+    where
+        PP: Problem<State = P::State>,
+        RR: Relaxation<State = P::State>,
+    {
+        let compilation = CompilationInputBuilder::exact(problem, relaxation, ranking)
+            .root()
+            .max_width(usize::MAX)
+            .build()
+            .expect("the input of a compilation is complete");
+        mdd.compile(&compilation);
+    }
+
+    /// Returns the optimal value (none until the dd is compiled, or when the
+    /// problem has no solution)
+    pub fn best_value(&self) -> Option<isize> {
+        self.best_value
+    }
+    /// Returns the first of the optimal solutions
+    pub fn best_solution(&self) -> Option<Vec<Decision>> {
+        self.best_sols.first().cloned()
+    }
+    /// Returns (at most `solution_limit` of) the optimal solutions, starting
+    /// with `best_solution`
+    pub fn best_solutions(&self) -> &[Vec<Decision>] {
+        &self.best_sols
+    }
+    /// Returns the optimal value, or the worst value of the objective when
+    /// there is none (the lower and upper bounds are the same)
+    pub fn best_bound(&self) -> isize {
+        self.best_value.unwrap_or(match self.objective {
+            Objective::Maximize => isize::MIN,
+            Objective::Minimize => isize::MAX,
+        })
+    }
+
+    pub fn get_explored_dd(&self) -> usize {
+        self.explored_dd
+    }
+
+    pub fn get_compilation_statistics(&self) -> CompilationStatistics {
+        self.compilations
+    }
+}
+
+/// A placeholder for the relaxation and the ranking of the problems whose
+/// dds are only ever exact (see `exact_solve`): it ranks all the states
+/// alike, and it panics when it is asked to merge or relax anything.
+pub struct NoRelax<T>(PhantomData<T>);
+impl<T> Default for NoRelax<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+impl<T> Relaxation for NoRelax<T> {
+    type State = T;
+
+    fn merge(&self, _: &mut dyn Iterator<Item = &T>) -> T {
+        panic!("an exact dd never merges its nodes")
+    }
+    fn relax(&self, _: &T, _: &T, _: &T, _: Decision, _: isize) -> isize {
+        panic!("an exact dd never relaxes its arcs")
+    }
+}
+impl<T> StateRanking for NoRelax<T> {
+    type State = T;
+
+    fn compare(&self, _: &T, _: &T) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+/// Compiles the exact dd of the whole problem, and returns its optimal value
+/// (in terms of the objective of the problem) along with one of its optimal
+/// solutions. Unlike `ExactSolver`, this needs neither a relaxation nor a
+/// ranking, but it is just as limited to tiny instances.
+///
+/// # Panics
+/// When the problem has no variable, or no solution.
+pub fn exact_solve<P>(problem: &P) -> (isize, Vec<Decision>)
+where
+    P: Problem,
+    P::State: Eq + Hash + Clone,
+{
+    assert!(problem.nb_variables() > 0, "the problem has no variable");
+    let placeholder = NoRelax::default();
+    let mut solver = ExactSolver::new(problem, &placeholder, &placeholder).with_solution_limit(1);
+    solver.compute();
+    match (solver.best_value(), solver.best_solution()) {
+        (Some(value), Some(solution)) => (value, solution),
+        _ => panic!("the problem has no solution"),
+    }
+}
+
+#[cfg(test)]
+mod test_exact {
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, Tweaked};
+    use crate::{Problem, ResolutionStatus};
+
+    use super::{exact_solve, ExactSolver};
+
+    #[test]
+    fn every_optimal_solution_is_enumerated() {
+        // any two of the four items fit
+        let problem = Knapsack { capacity: 2, profit: vec![1, 1, 1, 1], weight: vec![1, 1, 1, 1] };
+        let mut solver = ExactSolver::new(&problem, &KnapsackRelax, &KnapsackRanking);
+        assert_eq!((None, None), (solver.best_value(), solver.best_solution()));
+        assert_eq!(ResolutionStatus::Proved, solver.compute());
+        assert_eq!((Some(2), 2), (solver.best_value(), solver.best_bound()));
+        assert_eq!(6, solver.best_solutions().len());
+        assert_eq!(solver.best_solutions().first(), solver.best_solution().as_ref());
+
+        let mut solver = ExactSolver::new(&problem, &KnapsackRelax, &KnapsackRanking).with_solution_limit(4);
+        solver.compute();
+        assert_eq!(4, solver.best_solutions().len());
+    }
+
+    #[test]
+    fn exact_solve_needs_no_relaxation() {
+        let problem = Knapsack::toy();
+        let (value, solution) = exact_solve(&problem);
+        assert_eq!(13, value);
+        let mut taken = solution.iter().filter(|d| d.value == 1).map(|d| d.var.id()).collect::<Vec<_>>();
+        taken.sort_unstable();
+        assert_eq!(vec![0, 3, 4], taken);
+        assert_eq!(problem.nb_variables(), solution.len());
+    }
+
+    #[test]
+    fn exact_solve_reports_the_optimum_of_a_minimization() {
+        let problem = Tweaked::spare_capacity(Knapsack::toy(), 3);
+        assert_eq!(12, exact_solve(&problem).0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the problem has no variable")]
+    fn exact_solve_rejects_a_problem_without_variables() {
+        exact_solve(&Knapsack { capacity: 1, profit: vec![], weight: vec![] });
+    }
+
+    #[test]
+    fn the_optimum_of_a_minimization_is_reported_as_such() {
+        let problem = Tweaked::spare_capacity(Knapsack::toy(), 3);
+        let mut solver = ExactSolver::new(&problem, &KnapsackRelax, &KnapsackRanking);
+        assert_eq!(isize::MAX, solver.best_bound());
+        solver.compute();
+        assert_eq!(Some(12), solver.best_value());
+        assert!(!solver.best_solutions().is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+//! This module implements the filter through which each worker skips the
+//! cutset nodes that are dominated by a node it has recently enqueued with the
+//! same state. These duplicates would be merged by a `NoDupFrontier` anyway,
+//! but only after hashing their state (and possibly moving them in the heap)
+//! while holding the critical lock.
+//!
+//! The filter is a small hash table which forgets: each state is mapped to one
+//! slot, and a state evicts the one which was in its slot. It never suppresses
+//! a node wrongly though, because the slots store the states themselves and
+//! compare them for equality (a mere collision does not make a duplicate).
+
+use std::{hash::{Hash, Hasher}, sync::Arc};
+
+use rustc_hash::FxHasher;
+
+/// What the filter remembers about the nodes enqueued with a given state
+struct Entry<T> {
+    state: Arc<T>,
+    /// The best value of the nodes enqueued with that state
+    value: isize,
+    /// The best upper bound of the nodes enqueued with that state
+    ub: isize,
+}
+
+/// The recently enqueued cutset nodes of one worker
+pub(crate) struct PushFilter<T> {
+    slots: Vec<Option<Entry<T>>>,
+    bits: u32,
+}
+impl<T: Hash + Eq> PushFilter<T> {
+    /// Creates a filter with (at least) the given number of slots. A filter
+    /// without any slot lets every node through.
+    pub fn new(capacity: usize) -> Self {
+        let len = if capacity == 0 { 0 } else { capacity.next_power_of_two() };
+        Self { slots: (0..len).map(|_| None).collect(), bits: len.trailing_zeros() }
+    }
+
+    /// Returns true when a node with the given state, value and upper bound
+    /// must be pushed onto the fringe, and records it. It returns false when
+    /// a node with the same state was enqueued with a value and an upper bound
+    /// which are at least as good: that node dominates this one.
+    ///
+    /// # Note
+    /// The recorded value and upper bound are the best ones of all the nodes
+    /// enqueued with the given state, which is sound because each of them
+    /// comes from a node which was enqueued: the nodes whose value does not
+    /// exceed it are dominated by that node.
+    pub fn admit(&mut self, state: &Arc<T>, value: isize, ub: isize) -> bool {
+        if self.slots.is_empty() {
+            return true;
+        }
+        let mut hasher = FxHasher::default();
+        state.hash(&mut hasher);
+        // the high bits of a fx hash are the most evenly distributed ones
+        let slot = hasher.finish().rotate_left(self.bits) as usize & (self.slots.len() - 1);
+
+        match self.slots[slot].as_mut() {
+            Some(entry) if entry.state == *state => {
+                if value <= entry.value && ub <= entry.ub {
+                    false
+                } else {
+                    entry.value = entry.value.max(value);
+                    entry.ub = entry.ub.max(ub);
+                    true
+                }
+            }
+            _ => {
+                self.slots[slot] = Some(Entry { state: state.clone(), value, ub });
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_filter {
+    use std::{hash::{Hash, Hasher}, sync::Arc};
+
+    use rustc_hash::FxHashMap;
+
+    use super::PushFilter;
+
+    /// A state whose hash is always the same: all the states collide
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Colliding(usize);
+    impl Hash for Colliding {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            0.hash(state)
+        }
+    }
+
+    /// A tiny xorshift generator, so that the test is reproducible
+    fn rng(seed: &mut u64, bound: usize) -> usize {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        (*seed % bound as u64) as usize
+    }
+
+    #[test]
+    fn a_dominated_duplicate_is_suppressed() {
+        let mut filter = PushFilter::new(8);
+        let state = Arc::new(42_usize);
+        assert!(filter.admit(&state, 10, 20));
+        assert!(!filter.admit(&Arc::new(42), 10, 20));
+        assert!(!filter.admit(&state, 5, 15));
+        // a better value or a better bound goes through
+        assert!(filter.admit(&state, 11, 15));
+        assert!(filter.admit(&state, 5, 21));
+        assert!(!filter.admit(&state, 11, 21));
+        // so does any other state
+        assert!(filter.admit(&Arc::new(43), 0, 0));
+
+        let mut disabled = PushFilter::new(0);
+        assert!(disabled.admit(&state, 10, 20));
+        assert!(disabled.admit(&state, 10, 20));
+    }
+
+    #[test]
+    fn no_node_is_suppressed_unless_an_enqueued_one_dominates_it() {
+        for capacity in [1, 2, 3, 16] {
+            let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+            let mut colliding = PushFilter::new(capacity);
+            let mut hashed = PushFilter::new(capacity);
+            // the best value and ub of the nodes enqueued with each state
+            let mut enqueued_c: FxHashMap<usize, (isize, isize)> = FxHashMap::default();
+            let mut enqueued_h: FxHashMap<usize, (isize, isize)> = FxHashMap::default();
+            let mut suppressed = 0;
+            for _ in 0..20_000 {
+                let state = rng(&mut seed, 8);
+                let value = rng(&mut seed, 10) as isize;
+                let ub = value + rng(&mut seed, 10) as isize;
+
+                for (pushed, enqueued) in [
+                    (colliding.admit(&Arc::new(Colliding(state)), value, ub), &mut enqueued_c),
+                    (hashed.admit(&Arc::new(state), value, ub), &mut enqueued_h),
+                ] {
+                    let best = enqueued.entry(state).or_insert((isize::MIN, isize::MIN));
+                    if pushed {
+                        *best = (best.0.max(value), best.1.max(ub));
+                    } else {
+                        suppressed += 1;
+                        assert!(value <= best.0 && ub <= best.1, "{} suppressed with ({}, {}) after {:?}", state, value, ub, best);
+                    }
+                }
+            }
+            assert!(suppressed > 0);
+        }
+    }
+}
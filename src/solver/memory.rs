@@ -0,0 +1,154 @@
+//! This module provides the (optional) sampling of the memory held by the
+//! main components of a solver, which tells which one of them is responsible
+//! for the peak memory usage of a resolution.
+
+use std::{fmt::Display, time::{Duration, Instant}};
+
+/// The estimated number of bytes held by the main components of a solver at
+/// some point of a resolution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryAttribution {
+    /// The time elapsed between the beginning of the resolution and the sample
+    pub elapsed: Duration,
+    /// The bytes held by the dds of all the workers
+    pub dds: usize,
+    /// The bytes held by the nodes of the fringe
+    pub fringe: usize,
+    /// The bytes held by the barrier (always 0 for the solvers without barrier)
+    pub barrier: usize,
+}
+impl MemoryAttribution {
+    /// Returns the bytes held by the three components altogether
+    pub fn total(&self) -> usize {
+        self.dds + self.fringe + self.barrier
+    }
+}
+impl Display for MemoryAttribution {
+    /// Prints the breakdown in the unit of the total, e.g.
+    /// "peak 14.2GB: DDs 9.1, fringe 4.6, barrier 0.5"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+        let mut unit = 0;
+        let mut scale = 1.0;
+        while unit + 1 < UNITS.len() && self.total() as f64 >= scale * 1024.0 {
+            unit += 1;
+            scale *= 1024.0;
+        }
+        write!(
+            f,
+            "peak {:.1}{}: DDs {:.1}, fringe {:.1}, barrier {:.1}",
+            self.total() as f64 / scale,
+            UNITS[unit],
+            self.dds as f64 / scale,
+            self.fringe as f64 / scale,
+            self.barrier as f64 / scale,
+        )
+    }
+}
+
+/// Takes a sample every `period` nodes popped from the fringe and keeps the
+/// one at which the components held the most memory altogether.
+#[derive(Debug, Clone)]
+pub(crate) struct MemorySampler {
+    /// The number of pops between two samples (0 when nothing is sampled)
+    period: usize,
+    /// The number of pops since the last sample
+    pops: usize,
+    start: Instant,
+    /// The sample with the largest total
+    peak: Option<MemoryAttribution>,
+    /// The largest value sampled for each component (maybe at different times)
+    component_peaks: MemoryAttribution,
+}
+impl MemorySampler {
+    pub fn new(period: usize) -> Self {
+        Self { period, pops: 0, start: Instant::now(), peak: None, component_peaks: MemoryAttribution::default() }
+    }
+    pub fn period(&self) -> usize {
+        self.period
+    }
+    /// Forgets all the samples: the elapsed times are measured from now on
+    pub fn restart(&mut self) {
+        *self = Self::new(self.period);
+    }
+    /// Accounts for a pop and tells whether a sample must be taken
+    pub fn is_due(&mut self) -> bool {
+        if self.period == 0 {
+            return false;
+        }
+        self.pops += 1;
+        if self.pops < self.period {
+            false
+        } else {
+            self.pops = 0;
+            true
+        }
+    }
+    /// Records the given estimates, taken right now
+    pub fn record(&mut self, dds: usize, fringe: usize, barrier: usize) {
+        let sample = MemoryAttribution { elapsed: self.start.elapsed(), dds, fringe, barrier };
+        if self.peak.is_none_or(|peak| sample.total() > peak.total()) {
+            self.peak = Some(sample);
+        }
+        let peaks = &mut self.component_peaks;
+        peaks.elapsed = sample.elapsed;
+        peaks.dds = peaks.dds.max(dds);
+        peaks.fringe = peaks.fringe.max(fringe);
+        peaks.barrier = peaks.barrier.max(barrier);
+    }
+    pub fn peak(&self) -> Option<MemoryAttribution> {
+        self.peak
+    }
+    /// Returns the largest value sampled for each component (the elapsed time
+    /// is that of the last sample)
+    #[cfg(test)]
+    pub fn component_peaks(&self) -> Option<MemoryAttribution> {
+        self.peak.map(|_| self.component_peaks)
+    }
+}
+
+#[cfg(test)]
+mod test_memory {
+    use std::time::Duration;
+
+    use super::{MemoryAttribution, MemorySampler};
+
+    #[test]
+    fn a_sample_is_taken_every_period() {
+        let mut sampler = MemorySampler::new(3);
+        let due = (0..7).map(|_| sampler.is_due()).collect::<Vec<_>>();
+        assert_eq!(vec![false, false, true, false, false, true, false], due);
+
+        let mut disabled = MemorySampler::new(0);
+        assert!((0..10).all(|_| !disabled.is_due()));
+    }
+
+    #[test]
+    fn the_peak_is_the_sample_with_the_largest_total() {
+        let mut sampler = MemorySampler::new(1);
+        assert_eq!(None, sampler.peak());
+        sampler.record(10, 50, 0);
+        sampler.record(40, 10, 5);
+        sampler.record(20, 25, 20);
+
+        let peak = sampler.peak().unwrap();
+        assert_eq!((20, 25, 20), (peak.dds, peak.fringe, peak.barrier));
+        let peaks = sampler.component_peaks().unwrap();
+        assert_eq!((40, 50, 20), (peaks.dds, peaks.fringe, peaks.barrier));
+    }
+
+    #[test]
+    fn the_breakdown_is_printed_in_the_unit_of_the_total() {
+        let gb = 1024.0 * 1024.0 * 1024.0;
+        let peak = MemoryAttribution {
+            elapsed: Duration::ZERO,
+            dds: (9.1 * gb) as usize,
+            fringe: (4.6 * gb) as usize,
+            barrier: (0.5 * gb) as usize,
+        };
+        assert_eq!("peak 14.2GB: DDs 9.1, fringe 4.6, barrier 0.5", peak.to_string());
+
+        let small = MemoryAttribution { elapsed: Duration::ZERO, dds: 600, fringe: 100, barrier: 0 };
+        assert_eq!("peak 700.0B: DDs 600.0, fringe 100.0, barrier 0.0", small.to_string());
+    }
+}
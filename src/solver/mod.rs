@@ -1,5 +1,33 @@
+mod cancellation;
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
+mod config;
+mod engine;
+mod exact;
+mod filter;
+mod observer;
 mod parallel;
+mod pool;
+mod relaxed_bound;
 mod barrier;
+mod memory;
+mod owned;
+mod restart;
+mod sequential;
+mod tree;
+mod warm_start;
 
+pub use cancellation::*;
+pub use config::*;
+pub use exact::*;
+pub use observer::*;
 pub use parallel::*;
+pub use pool::*;
+pub use relaxed_bound::*;
 pub use barrier::*;
+pub use memory::MemoryAttribution;
+pub use owned::*;
+pub use restart::*;
+pub use sequential::*;
+pub use tree::*;
+pub use warm_start::*;
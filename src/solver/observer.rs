@@ -0,0 +1,110 @@
+//! This module implements the hook through which the parallel solvers report
+//! the progress of a resolution as it goes: each improvement of the best
+//! solution and of the best bound is handed to a `SolverObserver`, along
+//! with the time elapsed since the resolution started. This is what one needs
+//! to plot the convergence of the primal and dual bounds over time, which the
+//! `TraceCollector` records.
+//!
+//! # Example
+//! ```ignore
+//! let trace = TraceCollector::new();
+//! let mut solver = ParallelSolver::new(&problem, &relaxation, &ranking, &width, CutsetType::LastExactLayer)
+//!     .with_observer(&trace);
+//! solver.maximize();
+//! trace.write_csv(File::create("trace.csv")?)?;
+//! ```
+
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+
+/// The callbacks a solver invokes when its bounds improve. The values are
+/// expressed in terms of the objective of the resolution: when it minimizes,
+/// the incumbents decrease and the bounds increase.
+///
+/// # Note
+/// The callbacks are invoked from within the critical section of the solver
+/// (so that the values are reported in the order in which they were found):
+/// they must be cheap, and they must not call the solver back.
+pub trait SolverObserver: Send + Sync {
+    /// Called when a better solution, worth `value`, was found `elapsed`
+    /// after the resolution started
+    fn on_new_incumbent(&self, _value: isize, _elapsed: Duration) {}
+    /// Called when the dual bound of the resolution was improved to `bound`,
+    /// `elapsed` after it started. The first one comes from the relaxed dd of
+    /// the root; the last one is worth the optimum when the resolution is
+    /// complete.
+    fn on_new_bound(&self, _bound: isize, _elapsed: Duration) {}
+}
+
+/// An observer which records all the improvements of the bounds, in order to
+/// dump them (see `write_csv`) once the resolution is over.
+#[derive(Debug, Default)]
+pub struct TraceCollector {
+    incumbents: Mutex<Vec<(Duration, isize)>>,
+    bounds: Mutex<Vec<(Duration, isize)>>,
+}
+impl TraceCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns the values of the successive incumbents, along with the time
+    /// at which they were found
+    pub fn incumbents(&self) -> Vec<(Duration, isize)> {
+        self.incumbents.lock().clone()
+    }
+    /// Returns the successive dual bounds, along with the time at which they
+    /// were proved
+    pub fn bounds(&self) -> Vec<(Duration, isize)> {
+        self.bounds.lock().clone()
+    }
+    /// Writes the trace as a csv whose rows are `elapsed,kind,value`: the
+    /// elapsed time is in seconds, and the kind is either `incumbent` or
+    /// `bound`. The rows are sorted by time.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let mut rows = self.incumbents().into_iter().map(|(t, v)| (t, "incumbent", v)).collect::<Vec<_>>();
+        rows.extend(self.bounds().into_iter().map(|(t, v)| (t, "bound", v)));
+        rows.sort_by_key(|(t, _, _)| *t);
+
+        writeln!(out, "elapsed,kind,value")?;
+        for (elapsed, kind, value) in rows {
+            writeln!(out, "{:.6},{},{}", elapsed.as_secs_f64(), kind, value)?;
+        }
+        out.flush()
+    }
+}
+impl SolverObserver for TraceCollector {
+    fn on_new_incumbent(&self, value: isize, elapsed: Duration) {
+        self.incumbents.lock().push((elapsed, value));
+    }
+    fn on_new_bound(&self, bound: isize, elapsed: Duration) {
+        self.bounds.lock().push((elapsed, bound));
+    }
+}
+
+#[cfg(test)]
+mod test_observer {
+    use std::time::Duration;
+
+    use crate::{SolverObserver, TraceCollector};
+
+    #[test]
+    fn the_csv_interleaves_the_incumbents_and_bounds_by_time() {
+        let trace = TraceCollector::new();
+        trace.on_new_bound(10, Duration::from_millis(5));
+        trace.on_new_incumbent(3, Duration::from_millis(1));
+        trace.on_new_incumbent(7, Duration::from_millis(8));
+        trace.on_new_bound(7, Duration::from_millis(9));
+
+        let mut csv = vec![];
+        trace.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            "elapsed,kind,value\n0.001000,incumbent,3\n0.005000,bound,10\n0.008000,incumbent,7\n0.009000,bound,7\n",
+            String::from_utf8(csv).unwrap()
+        );
+        assert_eq!(vec![(Duration::from_millis(1), 3), (Duration::from_millis(8), 7)], trace.incumbents());
+    }
+}
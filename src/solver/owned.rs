@@ -0,0 +1,1171 @@
+//! This module provides solvers which own their components (problem,
+//! relaxation, ranking and width heuristic) through `Arc`s. Unlike
+//! `ParallelSolver` and `BarrierParallelSolver` they do not borrow anything,
+//! which makes them easy to store in a long lived struct or to move across
+//! threads. Each resolution is delegated to the corresponding borrowed solver,
+//! whose results are kept once it completes.
+//!
+//! # Example
+//! ```
+//! # use std::{cmp::Ordering, sync::Arc, thread};
+//! # use engineering::*;
+//! #
+//! # /// Pick at most `k` of the given items so as to maximize their profit
+//! # struct Pick { profit: Vec<isize>, k: usize }
+//! # #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+//! # struct PickState { depth: usize, left: usize }
+//! # impl Problem for Pick {
+//! #     type State = PickState;
+//! #     fn nb_variables(&self) -> usize { self.profit.len() }
+//! #     fn initial_state(&self) -> PickState { PickState { depth: 0, left: self.k } }
+//! #     fn initial_value(&self) -> isize { 0 }
+//! #     fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &PickState>) -> Option<Variable> {
+//! #         next_layer.next().map(|s| s.depth).filter(|d| *d < self.profit.len()).map(Variable)
+//! #     }
+//! #     fn for_each_in_domain<F: FnMut(Decision)>(&self, var: Variable, state: &PickState, mut f: F) {
+//! #         f(Decision { var, value: 0 });
+//! #         if state.left > 0 { f(Decision { var, value: 1 }); }
+//! #     }
+//! #     fn transition(&self, state: &PickState, d: Decision) -> PickState {
+//! #         PickState { depth: state.depth + 1, left: state.left - d.value as usize }
+//! #     }
+//! #     fn transition_cost(&self, _: &PickState, d: Decision) -> isize { d.value * self.profit[d.var.id()] }
+//! # }
+//! # struct PickRelax;
+//! # impl Relaxation for PickRelax {
+//! #     type State = PickState;
+//! #     fn merge(&self, states: &mut dyn Iterator<Item = &PickState>) -> PickState {
+//! #         states.fold(PickState { depth: 0, left: 0 }, |a, s| PickState { depth: a.depth.max(s.depth), left: a.left.max(s.left) })
+//! #     }
+//! #     fn relax(&self, _: &PickState, _: &PickState, _: &PickState, _: Decision, cost: isize) -> isize { cost }
+//! # }
+//! # struct PickRanking;
+//! # impl StateRanking for PickRanking {
+//! #     type State = PickState;
+//! #     fn compare(&self, a: &PickState, b: &PickState) -> Ordering { a.left.cmp(&b.left) }
+//! # }
+//! #
+//! /// A service which keeps its solver around
+//! struct Service {
+//!     solver: OwnedBarrierSolver<Pick, PickRelax, PickRanking, Fixed>,
+//! }
+//!
+//! let problem = Pick { profit: vec![4, 1, 3, 5, 2], k: 2 };
+//! let mut service = Service {
+//!     solver: OwnedBarrierSolver::custom(
+//!         Arc::new(problem),
+//!         Arc::new(PickRelax),
+//!         Arc::new(PickRanking),
+//!         Arc::new(Fixed(2)),
+//!         CutsetType::Frontier,
+//!         2,
+//!     ),
+//! };
+//!
+//! let service = thread::spawn(move || {
+//!     service.solver.maximize();
+//!     service
+//! })
+//! .join()
+//! .unwrap();
+//!
+//! assert_eq!(Some(9), service.solver.best_value());
+//! ```
+
+use std::{hash::Hash, ops::Range, sync::Arc};
+
+use crate::{
+    BarrierConfig, BarrierEvent, BarrierParallelSolver, CancellationToken, CompilationStatistics, Cutoff, CutsetType, Decision,
+    DominanceChecker, DuplicatePolicy, FringePolicy, InterruptibleSolver, MemoryAttribution, MergePolicy, NoDupFrontier, Objective,
+    OnImprovement, OnViolation, ParallelSolver, Problem, Relaxation, ResolutionStatus, Restarts, SearchTree, SecondaryObjective,
+    Shedding, SolutionPool, Solver, SolverConfig, SolverObserver, StateRanking, SubProblem, VerificationError, Violation,
+    WidthHeuristic,
+};
+
+/// What is kept from the last resolution of an owned solver
+#[derive(Debug, Clone)]
+struct Outcome {
+    best_sol: Option<Vec<Decision>>,
+    best_value: Option<isize>,
+    best_lb: isize,
+    best_ub: isize,
+    explored: usize,
+    explored_dd: usize,
+    compilations: CompilationStatistics,
+    suppressed_pushes: usize,
+    cutset_generated: usize,
+    cutset_enqueued: usize,
+    shed: usize,
+    dives: usize,
+    improving_dives: usize,
+    verification_error: Option<VerificationError>,
+    tree: Option<SearchTree>,
+    peak_attribution: Option<MemoryAttribution>,
+    /// The pool of solutions (barrier solver only)
+    best_solutions: Vec<(isize, Vec<Decision>)>,
+    /// The log of the watched state (barrier solver only)
+    watched_state_log: Vec<BarrierEvent>,
+}
+impl Default for Outcome {
+    fn default() -> Self {
+        Self {
+            best_sol: None,
+            best_value: None,
+            best_lb: isize::MIN,
+            best_ub: isize::MAX,
+            explored: 0,
+            explored_dd: 0,
+            compilations: CompilationStatistics::default(),
+            suppressed_pushes: 0,
+            cutset_generated: 0,
+            cutset_enqueued: 0,
+            shed: 0,
+            dives: 0,
+            improving_dives: 0,
+            verification_error: None,
+            tree: None,
+            peak_attribution: None,
+            best_solutions: vec![],
+            watched_state_log: vec![],
+        }
+    }
+}
+
+/// The owned counterpart of `ParallelSolver`. It always uses a
+/// `NoDupFrontier` as fringe.
+pub struct OwnedParallelSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    problem: Arc<P>,
+    relaxation: Arc<R>,
+    ranking: Arc<O>,
+    width_heu: Arc<W>,
+    cutset_type: CutsetType,
+    /// The options handed to the borrowed solver of each resolution
+    config: SolverConfig,
+    secondary: Option<Arc<dyn SecondaryObjective + Send + Sync>>,
+    observer: Option<Arc<dyn SolverObserver>>,
+    /// The solution which seeds the best solution of each resolution
+    initial_lb: Option<(isize, Vec<Decision>)>,
+    /// The nodes which the next resolution starts from (none when it starts
+    /// from the root)
+    initial_fringe: Vec<SubProblem<P::State>>,
+    outcome: Outcome,
+    /// The violations found by the self check during the last resolution
+    violations: Vec<Violation<P::State>>,
+}
+
+impl<P, R, O, W> OwnedParallelSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    pub fn new(problem: Arc<P>, relaxation: Arc<R>, ranking: Arc<O>, width: Arc<W>, cutset_type: CutsetType) -> Self {
+        Self::custom(problem, relaxation, ranking, width, cutset_type, num_cpus::get())
+    }
+
+    pub fn custom(
+        problem: Arc<P>,
+        relaxation: Arc<R>,
+        ranking: Arc<O>,
+        width_heu: Arc<W>,
+        cutset_type: CutsetType,
+        nb_threads: usize,
+    ) -> Self {
+        Self {
+            problem,
+            relaxation,
+            ranking,
+            width_heu,
+            cutset_type,
+            config: SolverConfig { nb_threads, ..SolverConfig::default() },
+            secondary: None,
+            observer: None,
+            initial_lb: None,
+            initial_fringe: vec![],
+            outcome: Outcome::default(),
+            violations: vec![],
+        }
+    }
+    /// Replaces all the options of the solver at once (see `SolverConfig`)
+    pub fn with_config(mut self, config: SolverConfig) -> Self {
+        config.check(self.cutset_type);
+        self.config = config;
+        self
+    }
+    /// Changes the options handed to the borrowed solver of each resolution
+    fn configure(mut self, f: impl FnOnce(&mut SolverConfig)) -> Self {
+        f(&mut self.config);
+        self.config.check(self.cutset_type);
+        self
+    }
+    /// Sets `SolverConfig::nb_threads`
+    pub fn with_nb_threads(self, nb_threads: usize) -> Self {
+        self.configure(|config| config.nb_threads = nb_threads)
+    }
+    /// Sets `SolverConfig::min_relax_depth_offset` (panics when it is too small)
+    pub fn with_min_relax_depth_offset(self, offset: usize) -> Self {
+        self.configure(|config| config.min_relax_depth_offset = offset)
+    }
+    /// Sets `SolverConfig::restrict_first_layers`
+    pub fn with_first_layers_restriction(self, restrict: bool) -> Self {
+        self.configure(|config| config.restrict_first_layers = restrict)
+    }
+    /// Sets `SolverConfig::max_forced_layers`
+    pub fn with_forced_layers(self, max: usize) -> Self {
+        self.configure(|config| config.max_forced_layers = max)
+    }
+    /// Sets `SolverConfig::reuse_prefix`
+    pub fn with_prefix_reuse(self, reuse: bool) -> Self {
+        self.configure(|config| config.reuse_prefix = reuse)
+    }
+    /// Sets `SolverConfig::merge_policy`
+    pub fn with_merge_policy(self, policy: MergePolicy) -> Self {
+        self.configure(|config| config.merge_policy = policy)
+    }
+    /// Sets `SolverConfig::check_merge`
+    pub fn with_merge_check(self, check: bool) -> Self {
+        self.configure(|config| config.check_merge = check)
+    }
+    /// Sets `SolverConfig::model_timing`
+    pub fn with_model_timing(self, timed: bool) -> Self {
+        self.configure(|config| config.model_timing = timed)
+    }
+    /// Sets `SolverConfig::self_check`
+    pub fn with_self_check(self, check: bool) -> Self {
+        self.configure(|config| config.self_check = check)
+    }
+    /// Sets `SolverConfig::on_violation`
+    pub fn with_violation_policy(self, policy: OnViolation) -> Self {
+        self.configure(|config| config.on_violation = policy)
+    }
+    /// Sets `SolverConfig::push_filter`
+    pub fn with_push_filter(self, capacity: usize) -> Self {
+        self.configure(|config| config.push_filter = capacity)
+    }
+    /// Breaks the ties among the solutions of equal value with the given secondary objective
+    pub fn with_secondary_objective(mut self, secondary: Arc<dyn SecondaryObjective + Send + Sync>) -> Self {
+        self.secondary = Some(secondary);
+        self
+    }
+    /// Sets `SolverConfig::tie_limit`
+    pub fn with_tie_limit(self, limit: usize) -> Self {
+        self.configure(|config| config.tie_limit = limit)
+    }
+    /// Sets `SolverConfig::verification`
+    pub fn with_verification(self, verify: bool) -> Self {
+        self.configure(|config| config.verification = verify)
+    }
+    /// Sets `SolverConfig::shedding`
+    pub fn with_shedding(self, shedding: Shedding) -> Self {
+        self.configure(|config| config.shedding = Some(shedding))
+    }
+    /// Sets `SolverConfig::restarts`
+    pub fn with_restarts(self, restarts: Restarts) -> Self {
+        self.configure(|config| config.restarts = Some(restarts))
+    }
+    /// Sets `SolverConfig::cancellation`
+    pub fn with_cancellation_token(self, token: CancellationToken) -> Self {
+        self.configure(|config| config.cancellation = token)
+    }
+    /// Tells the given observer about each improvement of the bounds (see `SolverObserver`)
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+    /// Sets `SolverConfig::tree_recording`
+    pub fn with_tree_recording(self, record: bool) -> Self {
+        self.configure(|config| config.tree_recording = record)
+    }
+    /// Sets `SolverConfig::memory_sampling`
+    pub fn with_memory_sampling(self, period: usize) -> Self {
+        self.configure(|config| config.memory_sampling = period)
+    }
+    /// Seeds the best solution of each resolution with a solution worth `value`, known beforehand
+    pub fn with_initial_lb(mut self, value: isize, solution: Vec<Decision>) -> Self {
+        self.initial_lb = Some((value, solution));
+        self
+    }
+    /// Sets `SolverConfig::suspension`
+    pub fn with_suspension(self, suspend: bool) -> Self {
+        self.configure(|config| config.suspension = suspend)
+    }
+    /// Makes the next resolution start from the given nodes rather than from the root
+    pub fn with_initial_fringe(mut self, nodes: Vec<SubProblem<P::State>>) -> Self {
+        self.initial_fringe = nodes;
+        self
+    }
+
+    /// Returns a handle through which another thread can cancel the
+    /// resolutions of this solver, even while it is borrowed by one of them
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.config.cancellation.clone()
+    }
+
+    pub fn get_explored(&self) -> usize {
+        self.outcome.explored
+    }
+
+    pub fn get_explored_dd(&self) -> usize {
+        self.outcome.explored_dd
+    }
+
+    /// Returns the max and total size of the dds compiled during the resolution
+    pub fn get_compilation_statistics(&self) -> CompilationStatistics {
+        self.outcome.compilations
+    }
+
+    /// Returns the number of cutset nodes drained from the relaxed dds
+    pub fn get_cutset_generated(&self) -> usize {
+        self.outcome.cutset_generated
+    }
+
+    /// Returns the number of cutset nodes which were pushed onto the fringe
+    pub fn get_cutset_enqueued(&self) -> usize {
+        self.outcome.cutset_enqueued
+    }
+
+    /// Returns the number of cutset nodes which the push filter skipped
+    pub fn get_suppressed_pushes(&self) -> usize {
+        self.outcome.suppressed_pushes
+    }
+
+    /// Returns the number of nodes which were shed from the fringe
+    pub fn get_shed_nodes(&self) -> usize {
+        self.outcome.shed
+    }
+
+    /// Returns the number of dives from the root made by the random restarts
+    pub fn get_dives(&self) -> usize {
+        self.outcome.dives
+    }
+
+    /// Returns the number of dives which improved the best solution
+    pub fn get_improving_dives(&self) -> usize {
+        self.outcome.improving_dives
+    }
+
+    /// Returns the violations found by the self check of the model
+    pub fn violations(&self) -> &[Violation<P::State>] {
+        &self.violations
+    }
+
+    /// Returns the reason why the best solution failed its verification
+    /// during the last resolution (if it did)
+    pub fn verification_error(&self) -> Option<&VerificationError> {
+        self.outcome.verification_error.as_ref()
+    }
+
+    /// Returns the branch-and-bound tree of the last resolution (if it was
+    /// recorded, see `with_tree_recording`)
+    pub fn search_tree(&self) -> Option<&SearchTree> {
+        self.outcome.tree.as_ref()
+    }
+
+    /// Returns the memory sample at which the last resolution held the most
+    /// bytes altogether (if the memory was sampled)
+    pub fn peak_attribution(&self) -> Option<MemoryAttribution> {
+        self.outcome.peak_attribution
+    }
+
+    /// Runs a resolution with a borrowed solver and keeps its outcome
+    fn solve<C>(&mut self, objective: Objective, cutoff: C, on_improvement: Option<OnImprovement>) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        let mut fringe = NoDupFrontier::new(self.ranking.as_ref());
+        let mut solver = ParallelSolver::custom(
+            self.problem.as_ref(),
+            self.relaxation.as_ref(),
+            self.ranking.as_ref(),
+            self.width_heu.as_ref(),
+            self.cutset_type,
+            &mut fringe,
+            self.config.nb_threads,
+        )
+        .with_config(self.config.clone())
+        .with_initial_fringe(std::mem::take(&mut self.initial_fringe));
+        if let Some((value, solution)) = self.initial_lb.clone() {
+            solver = solver.with_initial_lb(value, solution);
+        }
+        if let Some(secondary) = self.secondary.as_deref() {
+            solver = solver.with_secondary_objective(secondary);
+        }
+        if let Some(observer) = self.observer.as_deref() {
+            solver = solver.with_observer(observer);
+        }
+
+        let status = match (objective, on_improvement) {
+            (Objective::Maximize, Some(on_improvement)) => solver.maximize_with_cutoff_and_progress(cutoff, on_improvement),
+            (Objective::Maximize, None) => solver.maximize_with_cutoff(cutoff),
+            (Objective::Minimize, Some(on_improvement)) => solver.minimize_with_cutoff_and_progress(cutoff, on_improvement),
+            (Objective::Minimize, None) => solver.minimize_with_cutoff(cutoff),
+        };
+        self.outcome = Outcome {
+            best_sol: solver.best_solution(),
+            best_value: solver.best_value(),
+            best_lb: solver.best_lower_bound(),
+            best_ub: solver.best_upper_bound(),
+            explored: solver.get_explored(),
+            explored_dd: solver.get_explored_dd(),
+            compilations: solver.get_compilation_statistics(),
+            suppressed_pushes: solver.get_suppressed_pushes(),
+            cutset_generated: solver.get_cutset_generated(),
+            cutset_enqueued: solver.get_cutset_enqueued(),
+            shed: solver.get_shed_nodes(),
+            dives: solver.get_dives(),
+            improving_dives: solver.get_improving_dives(),
+            verification_error: solver.verification_error(),
+            tree: solver.search_tree(),
+            peak_attribution: solver.peak_attribution(),
+            ..Outcome::default()
+        };
+        self.violations = solver.violations();
+        self.suspend(solver.into_open_subproblems());
+        status
+    }
+
+    /// Keeps the nodes which the last resolution did not explore, along with
+    /// its best solution, for the next resolution to resume from them
+    fn suspend(&mut self, open: Vec<SubProblem<P::State>>) {
+        if open.is_empty() {
+            return;
+        }
+        self.initial_fringe = open;
+        if let Some(solution) = self.outcome.best_sol.clone() {
+            self.initial_lb = self.outcome.best_value.map(|value| (value, solution));
+        }
+    }
+}
+
+impl<P, R, O, W> Solver for OwnedParallelSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    fn maximize(&mut self) {
+        self.maximize_with_interrupt(|| false);
+    }
+
+    fn minimize(&mut self) {
+        self.minimize_with_interrupt(|| false);
+    }
+
+    fn best_value(&self) -> Option<isize> {
+        self.outcome.best_value
+    }
+
+    fn best_solution(&self) -> Option<Vec<Decision>> {
+        self.outcome.best_sol.clone()
+    }
+}
+
+impl<P, R, O, W> InterruptibleSolver for OwnedParallelSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    fn maximize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        self.solve(Objective::Maximize, cutoff, None)
+    }
+
+    fn minimize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        self.solve(Objective::Minimize, cutoff, None)
+    }
+
+    fn maximize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.solve(Objective::Maximize, cutoff, Some(Box::new(on_improvement)))
+    }
+
+    fn minimize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.solve(Objective::Minimize, cutoff, Some(Box::new(on_improvement)))
+    }
+
+    fn best_value_so_far(&self) -> Option<isize> {
+        self.best_value()
+    }
+
+    fn best_solution_so_far(&self) -> Option<Vec<Decision>> {
+        self.best_solution()
+    }
+
+    fn best_lower_bound(&self) -> isize {
+        self.outcome.best_lb
+    }
+
+    fn best_upper_bound(&self) -> isize {
+        self.outcome.best_ub
+    }
+}
+
+
+/// The owned counterpart of `BarrierParallelSolver`.
+pub struct OwnedBarrierSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    problem: Arc<P>,
+    relaxation: Arc<R>,
+    ranking: Arc<O>,
+    width_heu: Arc<W>,
+    cutset_type: CutsetType,
+    /// The options handed to the borrowed solver of each resolution
+    config: SolverConfig,
+    /// The options handed to the borrowed solver which only the barrier
+    /// solvers have
+    barrier: BarrierConfig<P::State>,
+    dominance: Option<Arc<dyn DominanceChecker<P::State> + Send + Sync>>,
+    /// The pool which each resolution starts with, if any
+    pool: Option<SolutionPool>,
+    secondary: Option<Arc<dyn SecondaryObjective + Send + Sync>>,
+    observer: Option<Arc<dyn SolverObserver>>,
+    /// The solution which seeds the best solution of each resolution
+    initial_lb: Option<(isize, Vec<Decision>)>,
+    /// The nodes which the next resolution starts from (none when it starts
+    /// from the root)
+    initial_fringe: Vec<SubProblem<P::State>>,
+    outcome: Outcome,
+    /// The violations found by the self check during the last resolution
+    violations: Vec<Violation<P::State>>,
+}
+
+impl<P, R, O, W> OwnedBarrierSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    pub fn new(problem: Arc<P>, relaxation: Arc<R>, ranking: Arc<O>, width: Arc<W>, cutset_type: CutsetType) -> Self {
+        Self::custom(problem, relaxation, ranking, width, cutset_type, num_cpus::get())
+    }
+
+    pub fn custom(
+        problem: Arc<P>,
+        relaxation: Arc<R>,
+        ranking: Arc<O>,
+        width_heu: Arc<W>,
+        cutset_type: CutsetType,
+        nb_threads: usize,
+    ) -> Self {
+        Self {
+            problem,
+            relaxation,
+            ranking,
+            width_heu,
+            cutset_type,
+            config: SolverConfig { nb_threads, ..SolverConfig::default() },
+            barrier: BarrierConfig::default(),
+            dominance: None,
+            pool: None,
+            secondary: None,
+            observer: None,
+            initial_lb: None,
+            initial_fringe: vec![],
+            outcome: Outcome::default(),
+            violations: vec![],
+        }
+    }
+    /// Replaces all the options of the solver at once (see `SolverConfig`)
+    pub fn with_config(mut self, config: SolverConfig) -> Self {
+        config.check(self.cutset_type);
+        self.config = config;
+        self
+    }
+    /// Changes the options handed to the borrowed solver of each resolution
+    fn configure(mut self, f: impl FnOnce(&mut SolverConfig)) -> Self {
+        f(&mut self.config);
+        self.config.check(self.cutset_type);
+        self
+    }
+    /// Sets `SolverConfig::nb_threads`
+    pub fn with_nb_threads(self, nb_threads: usize) -> Self {
+        self.configure(|config| config.nb_threads = nb_threads)
+    }
+    /// Sets `SolverConfig::min_relax_depth_offset` (panics when it is too small)
+    pub fn with_min_relax_depth_offset(self, offset: usize) -> Self {
+        self.configure(|config| config.min_relax_depth_offset = offset)
+    }
+    /// Sets `SolverConfig::restrict_first_layers`
+    pub fn with_first_layers_restriction(self, restrict: bool) -> Self {
+        self.configure(|config| config.restrict_first_layers = restrict)
+    }
+    /// Sets `SolverConfig::max_forced_layers`
+    pub fn with_forced_layers(self, max: usize) -> Self {
+        self.configure(|config| config.max_forced_layers = max)
+    }
+    /// Sets `SolverConfig::reuse_prefix`
+    pub fn with_prefix_reuse(self, reuse: bool) -> Self {
+        self.configure(|config| config.reuse_prefix = reuse)
+    }
+    /// Sets `SolverConfig::merge_policy`
+    pub fn with_merge_policy(self, policy: MergePolicy) -> Self {
+        self.configure(|config| config.merge_policy = policy)
+    }
+    /// Sets `SolverConfig::check_merge`
+    pub fn with_merge_check(self, check: bool) -> Self {
+        self.configure(|config| config.check_merge = check)
+    }
+    /// Sets `SolverConfig::model_timing`
+    pub fn with_model_timing(self, timed: bool) -> Self {
+        self.configure(|config| config.model_timing = timed)
+    }
+    /// Sets `SolverConfig::self_check`
+    pub fn with_self_check(self, check: bool) -> Self {
+        self.configure(|config| config.self_check = check)
+    }
+    /// Sets `SolverConfig::on_violation`
+    pub fn with_violation_policy(self, policy: OnViolation) -> Self {
+        self.configure(|config| config.on_violation = policy)
+    }
+    /// Sets `SolverConfig::push_filter`
+    pub fn with_push_filter(self, capacity: usize) -> Self {
+        self.configure(|config| config.push_filter = capacity)
+    }
+    /// Breaks the ties among the solutions of equal value with the given secondary objective
+    pub fn with_secondary_objective(mut self, secondary: Arc<dyn SecondaryObjective + Send + Sync>) -> Self {
+        self.secondary = Some(secondary);
+        self
+    }
+    /// Sets `SolverConfig::tie_limit`
+    pub fn with_tie_limit(self, limit: usize) -> Self {
+        self.configure(|config| config.tie_limit = limit)
+    }
+    /// Sets `SolverConfig::verification`
+    pub fn with_verification(self, verify: bool) -> Self {
+        self.configure(|config| config.verification = verify)
+    }
+    /// Sets `SolverConfig::shedding`
+    pub fn with_shedding(self, shedding: Shedding) -> Self {
+        self.configure(|config| config.shedding = Some(shedding))
+    }
+    /// Sets `SolverConfig::restarts`
+    pub fn with_restarts(self, restarts: Restarts) -> Self {
+        self.configure(|config| config.restarts = Some(restarts))
+    }
+    /// Sets `SolverConfig::cancellation`
+    pub fn with_cancellation_token(self, token: CancellationToken) -> Self {
+        self.configure(|config| config.cancellation = token)
+    }
+    /// Tells the given observer about each improvement of the bounds (see `SolverObserver`)
+    pub fn with_observer(mut self, observer: Arc<dyn SolverObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+    /// Sets `SolverConfig::tree_recording`
+    pub fn with_tree_recording(self, record: bool) -> Self {
+        self.configure(|config| config.tree_recording = record)
+    }
+    /// Sets `SolverConfig::memory_sampling`
+    pub fn with_memory_sampling(self, period: usize) -> Self {
+        self.configure(|config| config.memory_sampling = period)
+    }
+    /// Seeds the best solution of each resolution with a solution worth `value`, known beforehand
+    pub fn with_initial_lb(mut self, value: isize, solution: Vec<Decision>) -> Self {
+        self.initial_lb = Some((value, solution));
+        self
+    }
+    /// Sets `SolverConfig::suspension`
+    pub fn with_suspension(self, suspend: bool) -> Self {
+        self.configure(|config| config.suspension = suspend)
+    }
+    /// Makes the next resolution start from the given nodes rather than from the root
+    pub fn with_initial_fringe(mut self, nodes: Vec<SubProblem<P::State>>) -> Self {
+        self.initial_fringe = nodes;
+        self
+    }
+    /// Replaces all the options which only the barrier solvers have at once (see `BarrierConfig`)
+    pub fn with_barrier_config(mut self, config: BarrierConfig<P::State>) -> Self {
+        self.barrier = config;
+        self
+    }
+    /// Sets the dominance rule the dds apply to the exact nodes of each layer (see `Dominance`)
+    pub fn with_dominance(mut self, dominance: Arc<dyn DominanceChecker<P::State> + Send + Sync>) -> Self {
+        self.dominance = Some(dominance);
+        self
+    }
+    /// Sets `BarrierConfig::duplicate_policy`
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.barrier.duplicate_policy = policy;
+        self
+    }
+    /// Sets `BarrierConfig::dd_capacity`
+    pub fn with_dd_capacity(mut self, nodes: usize, edges: usize) -> Self {
+        self.barrier.dd_capacity = (nodes, edges);
+        self
+    }
+    /// Sets `BarrierConfig::barrier_depths` (panics when they are out of range)
+    pub fn with_barrier_depths(mut self, depths: Range<usize>) -> Self {
+        self.barrier.barrier_depths = Some(depths);
+        self
+    }
+    /// Sets `BarrierConfig::watched_state`
+    pub fn with_watched_state(mut self, state: P::State) -> Self {
+        self.barrier.watched_state = Some(state);
+        self
+    }
+    /// Keeps the best distinct solutions found during the resolution in the given pool
+    pub fn with_solution_pool(mut self, pool: SolutionPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+    /// Sets `BarrierConfig::fringe_policy`
+    pub fn with_fringe_policy(mut self, policy: FringePolicy) -> Self {
+        self.barrier.fringe_policy = policy;
+        self
+    }
+    /// Sets `BarrierConfig::barrier_retention`
+    pub fn with_barrier_retention(mut self, retain: bool) -> Self {
+        self.barrier.barrier_retention = retain;
+        self
+    }
+
+    /// Returns a handle through which another thread can cancel the
+    /// resolutions of this solver, even while it is borrowed by one of them
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.config.cancellation.clone()
+    }
+
+    pub fn get_explored(&self) -> usize {
+        self.outcome.explored
+    }
+
+    pub fn get_explored_dd(&self) -> usize {
+        self.outcome.explored_dd
+    }
+
+    /// Returns the max and total size of the dds compiled during the resolution
+    pub fn get_compilation_statistics(&self) -> CompilationStatistics {
+        self.outcome.compilations
+    }
+
+    /// Returns the number of cutset nodes drained from the relaxed dds
+    pub fn get_cutset_generated(&self) -> usize {
+        self.outcome.cutset_generated
+    }
+
+    /// Returns the number of cutset nodes which were pushed onto the fringe
+    pub fn get_cutset_enqueued(&self) -> usize {
+        self.outcome.cutset_enqueued
+    }
+
+    /// Returns the number of cutset nodes which the push filter skipped
+    pub fn get_suppressed_pushes(&self) -> usize {
+        self.outcome.suppressed_pushes
+    }
+
+    /// Returns the number of nodes which were shed from the fringe
+    pub fn get_shed_nodes(&self) -> usize {
+        self.outcome.shed
+    }
+
+    /// Returns the number of dives from the root made by the random restarts
+    pub fn get_dives(&self) -> usize {
+        self.outcome.dives
+    }
+
+    /// Returns the number of dives which improved the best solution
+    pub fn get_improving_dives(&self) -> usize {
+        self.outcome.improving_dives
+    }
+
+    /// Returns the violations found by the self check of the model
+    pub fn violations(&self) -> &[Violation<P::State>] {
+        &self.violations
+    }
+
+    /// Returns the reason why the best solution failed its verification
+    /// during the last resolution (if it did)
+    pub fn verification_error(&self) -> Option<&VerificationError> {
+        self.outcome.verification_error.as_ref()
+    }
+
+    /// Returns the branch-and-bound tree of the last resolution (if it was
+    /// recorded, see `with_tree_recording`)
+    pub fn search_tree(&self) -> Option<&SearchTree> {
+        self.outcome.tree.as_ref()
+    }
+
+    /// Returns the memory sample at which the last resolution held the most
+    /// bytes altogether (if the memory was sampled)
+    pub fn peak_attribution(&self) -> Option<MemoryAttribution> {
+        self.outcome.peak_attribution
+    }
+
+    /// Returns the solutions of the pool of the last resolution, the best ones
+    /// first, along with their values (see `with_solution_pool`)
+    pub fn best_solutions(&self) -> &[(isize, Vec<Decision>)] {
+        &self.outcome.best_solutions
+    }
+
+    /// Returns the latest interactions with the threshold of the watched
+    /// state during the last resolution (see `with_watched_state`)
+    pub fn watched_state_log(&self) -> &[BarrierEvent] {
+        &self.outcome.watched_state_log
+    }
+
+    /// Runs a resolution with a borrowed solver and keeps its outcome
+    fn solve<C>(&mut self, objective: Objective, cutoff: C, on_improvement: Option<OnImprovement>) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        let mut solver = BarrierParallelSolver::custom(
+            self.problem.as_ref(),
+            self.relaxation.as_ref(),
+            self.ranking.as_ref(),
+            self.width_heu.as_ref(),
+            self.cutset_type,
+            self.config.nb_threads,
+        )
+        .with_config(self.config.clone())
+        .with_barrier_config(self.barrier.clone())
+        .with_initial_fringe(std::mem::take(&mut self.initial_fringe));
+        if let Some((value, solution)) = self.initial_lb.clone() {
+            solver = solver.with_initial_lb(value, solution);
+        }
+        if let Some(secondary) = self.secondary.as_deref() {
+            solver = solver.with_secondary_objective(secondary);
+        }
+        if let Some(observer) = self.observer.as_deref() {
+            solver = solver.with_observer(observer);
+        }
+        if let Some(dominance) = self.dominance.as_deref() {
+            solver = solver.with_dominance(dominance);
+        }
+        if let Some(pool) = self.pool.clone() {
+            solver = solver.with_solution_pool(pool);
+        }
+
+        let status = match (objective, on_improvement) {
+            (Objective::Maximize, Some(on_improvement)) => solver.maximize_with_cutoff_and_progress(cutoff, on_improvement),
+            (Objective::Maximize, None) => solver.maximize_with_cutoff(cutoff),
+            (Objective::Minimize, Some(on_improvement)) => solver.minimize_with_cutoff_and_progress(cutoff, on_improvement),
+            (Objective::Minimize, None) => solver.minimize_with_cutoff(cutoff),
+        };
+        self.outcome = Outcome {
+            best_sol: solver.best_solution(),
+            best_value: solver.best_value(),
+            best_lb: solver.best_lower_bound(),
+            best_ub: solver.best_upper_bound(),
+            explored: solver.get_explored(),
+            explored_dd: solver.get_explored_dd(),
+            compilations: solver.get_compilation_statistics(),
+            suppressed_pushes: solver.get_suppressed_pushes(),
+            cutset_generated: solver.get_cutset_generated(),
+            cutset_enqueued: solver.get_cutset_enqueued(),
+            shed: solver.get_shed_nodes(),
+            dives: solver.get_dives(),
+            improving_dives: solver.get_improving_dives(),
+            verification_error: solver.verification_error(),
+            tree: solver.search_tree(),
+            peak_attribution: solver.peak_attribution(),
+            best_solutions: solver.best_solutions(),
+            watched_state_log: solver.watched_state_log(),
+        };
+        self.violations = solver.violations();
+        self.suspend(solver.into_open_subproblems());
+        status
+    }
+
+    /// Keeps the nodes which the last resolution did not explore, along with
+    /// its best solution, for the next resolution to resume from them
+    fn suspend(&mut self, open: Vec<SubProblem<P::State>>) {
+        if open.is_empty() {
+            return;
+        }
+        self.initial_fringe = open;
+        if let Some(solution) = self.outcome.best_sol.clone() {
+            self.initial_lb = self.outcome.best_value.map(|value| (value, solution));
+        }
+    }
+}
+
+impl<P, R, O, W> Solver for OwnedBarrierSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    fn maximize(&mut self) {
+        self.maximize_with_interrupt(|| false);
+    }
+
+    fn minimize(&mut self) {
+        self.minimize_with_interrupt(|| false);
+    }
+
+    fn best_value(&self) -> Option<isize> {
+        self.outcome.best_value
+    }
+
+    fn best_solution(&self) -> Option<Vec<Decision>> {
+        self.outcome.best_sol.clone()
+    }
+}
+
+impl<P, R, O, W> InterruptibleSolver for OwnedBarrierSolver<P, R, O, W>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    fn maximize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        self.solve(Objective::Maximize, cutoff, None)
+    }
+
+    fn minimize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        self.solve(Objective::Minimize, cutoff, None)
+    }
+
+    fn maximize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.solve(Objective::Maximize, cutoff, Some(Box::new(on_improvement)))
+    }
+
+    fn minimize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.solve(Objective::Minimize, cutoff, Some(Box::new(on_improvement)))
+    }
+
+    fn best_value_so_far(&self) -> Option<isize> {
+        self.best_value()
+    }
+
+    fn best_solution_so_far(&self) -> Option<Vec<Decision>> {
+        self.best_solution()
+    }
+
+    fn best_lower_bound(&self) -> isize {
+        self.outcome.best_lb
+    }
+
+    fn best_upper_bound(&self) -> isize {
+        self.outcome.best_ub
+    }
+}
+
+
+#[cfg(test)]
+mod test_owned {
+    use std::sync::{Arc, Mutex};
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState, KnapsackStingyRelax, Tweaked};
+    use crate::{
+        BarrierConfig, BarrierEvent, BarrierParallelSolver, CutsetType, Decision, Fixed, FringePolicy, InterruptibleSolver, NoDupFrontier, NodeCutoff,
+        OnViolation, ParallelSolver, Problem, ResolutionStatus, SolutionPool, Solver, SolverConfig,
+    };
+
+    use super::{OwnedBarrierSolver, OwnedParallelSolver};
+
+    #[test]
+    fn owned_and_borrowed_parallel_solvers_agree() {
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+            let mut borrowed = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, &mut fringe, 1);
+            borrowed.maximize();
+
+            let mut owned = OwnedParallelSolver::custom(
+                Arc::new(problem.clone()), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), cutset_type, 1);
+            owned.maximize();
+
+            assert_eq!(Some(13), owned.best_value());
+            assert_eq!(borrowed.best_value(), owned.best_value());
+            assert_eq!(borrowed.best_solution(), owned.best_solution());
+            assert_eq!(borrowed.best_lower_bound(), owned.best_lower_bound());
+            assert_eq!(borrowed.best_upper_bound(), owned.best_upper_bound());
+            assert_eq!(borrowed.get_explored(), owned.get_explored());
+            assert_eq!(borrowed.get_explored_dd(), owned.get_explored_dd());
+            assert_eq!(borrowed.get_compilation_statistics(), owned.get_compilation_statistics());
+        }
+    }
+
+    #[test]
+    fn owned_and_borrowed_barrier_solvers_agree() {
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            let mut borrowed = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 1);
+            borrowed.maximize();
+
+            let mut owned = OwnedBarrierSolver::custom(
+                Arc::new(problem.clone()), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), cutset_type, 1);
+            owned.maximize();
+
+            assert_eq!(Some(13), owned.best_value());
+            assert_eq!(borrowed.best_value(), owned.best_value());
+            assert_eq!(borrowed.best_solution(), owned.best_solution());
+            assert_eq!(borrowed.best_lower_bound(), owned.best_lower_bound());
+            assert_eq!(borrowed.best_upper_bound(), owned.best_upper_bound());
+            assert_eq!(borrowed.get_explored(), owned.get_explored());
+            assert_eq!(borrowed.get_explored_dd(), owned.get_explored_dd());
+            assert_eq!(borrowed.get_compilation_statistics(), owned.get_compilation_statistics());
+        }
+    }
+
+    #[test]
+    fn nothing_is_known_before_the_resolution() {
+        let owned = OwnedBarrierSolver::custom(
+            Arc::new(Knapsack::toy()), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), CutsetType::Frontier, 1);
+        assert_eq!(None, owned.best_value());
+        assert_eq!(None, owned.best_solution());
+        assert_eq!(isize::MIN, owned.best_lower_bound());
+        assert_eq!(isize::MAX, owned.best_upper_bound());
+    }
+
+    #[test]
+    fn the_violations_of_the_last_resolution_are_kept() {
+        let mut owned = OwnedParallelSolver::custom(
+            Arc::new(Knapsack::toy()), Arc::new(KnapsackStingyRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), CutsetType::LastExactLayer, 1)
+            .with_self_check(true)
+            .with_violation_policy(OnViolation::Warn);
+        assert!(owned.violations().is_empty());
+        owned.maximize();
+        assert!(!owned.violations().is_empty());
+    }
+
+    #[test]
+    fn a_minimization_reports_its_progress() {
+        type Events = Arc<Mutex<Vec<(isize, Vec<Decision>)>>>;
+        let problem = Tweaked::lost_profit(Knapsack::toy(), true);
+        let check = |events: Events| {
+            let events = events.lock().unwrap();
+            assert!(events.windows(2).all(|pair| pair[0].0 > pair[1].0));
+            assert_eq!(Some(8), events.last().map(|(cost, _)| *cost));
+            for (cost, solution) in events.iter() {
+                assert_eq!(Ok(*cost), problem.validate_solution(solution));
+            }
+        };
+
+        let events = Events::default();
+        let collected = events.clone();
+        let mut owned = OwnedParallelSolver::custom(
+            Arc::new(problem.clone()), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), CutsetType::LastExactLayer, 1);
+        owned.minimize_with_progress(|| false, move |cost, solution: &[Decision]| {
+            collected.lock().unwrap().push((cost, solution.to_vec()));
+        });
+        check(events);
+
+        let events = Events::default();
+        let collected = events.clone();
+        let mut owned = OwnedBarrierSolver::custom(
+            Arc::new(problem.clone()), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), CutsetType::LastExactLayer, 1);
+        owned.minimize_with_progress(|| false, move |cost, solution: &[Decision]| {
+            collected.lock().unwrap().push((cost, solution.to_vec()));
+        });
+        check(events);
+    }
+
+    fn larger_knapsack() -> Knapsack {
+        Knapsack {
+            capacity: 40,
+            profit: (0..20).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..20).map(|i| 1 + (i * 3) % 7).collect(),
+        }
+    }
+
+    #[test]
+    fn the_owned_barrier_solver_hands_all_its_options_to_the_borrowed_one() {
+        let problem = larger_knapsack();
+        let config = SolverConfig { nb_threads: 1, tree_recording: true, memory_sampling: 1, ..SolverConfig::default() };
+        let barrier = BarrierConfig {
+            fringe_policy: FringePolicy::DeepestLayer,
+            watched_state: Some(KnapsackState { depth: 4, capacity: 32 }),
+            ..BarrierConfig::default()
+        };
+        let mut borrowed = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1)
+            .with_config(config.clone())
+            .with_barrier_config(barrier.clone())
+            .with_solution_pool(SolutionPool::new(3));
+        borrowed.maximize();
+
+        let mut owned = OwnedBarrierSolver::custom(
+            Arc::new(problem.clone()), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), CutsetType::LastExactLayer, 1)
+            .with_config(config)
+            .with_barrier_config(barrier)
+            .with_solution_pool(SolutionPool::new(3));
+        owned.maximize();
+
+        assert_eq!(borrowed.get_explored(), owned.get_explored());
+        assert!(owned.search_tree().is_some());
+        assert_eq!(borrowed.search_tree().as_ref(), owned.search_tree());
+        assert_eq!(3, owned.best_solutions().len());
+        assert_eq!(borrowed.best_solutions(), owned.best_solutions());
+        assert!(!owned.watched_state_log().is_empty());
+        let actions = |log: &[BarrierEvent]| log.iter().map(|event| event.action).collect::<Vec<_>>();
+        assert_eq!(actions(&borrowed.watched_state_log()), actions(owned.watched_state_log()));
+        assert!(owned.peak_attribution().is_some());
+    }
+
+    #[test]
+    fn an_owned_solver_resumes_its_suspended_resolution() {
+        let problem = Arc::new(larger_knapsack());
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut expected = ParallelSolver::custom(problem.as_ref(), &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, &mut fringe, 1);
+        expected.maximize();
+
+        let mut owned = OwnedParallelSolver::custom(
+            problem.clone(), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), CutsetType::LastExactLayer, 1)
+            .with_suspension(true);
+        assert_eq!(ResolutionStatus::Interrupted, owned.maximize_with_cutoff(NodeCutoff(5)));
+        assert!(!owned.initial_fringe.is_empty());
+        assert_eq!(ResolutionStatus::Proved, owned.maximize_with_cutoff(NodeCutoff(usize::MAX)));
+        assert!(owned.initial_fringe.is_empty());
+        assert_eq!(expected.best_value(), owned.best_value());
+
+        let mut owned = OwnedBarrierSolver::custom(
+            problem.clone(), Arc::new(KnapsackRelax), Arc::new(KnapsackRanking), Arc::new(Fixed(2)), CutsetType::LastExactLayer, 1)
+            .with_suspension(true);
+        assert_eq!(ResolutionStatus::Interrupted, owned.maximize_with_cutoff(NodeCutoff(5)));
+        assert!(!owned.initial_fringe.is_empty());
+        assert_eq!(ResolutionStatus::Proved, owned.maximize_with_cutoff(NodeCutoff(usize::MAX)));
+        assert!(owned.initial_fringe.is_empty());
+        assert_eq!(expected.best_value(), owned.best_value());
+    }
+}
@@ -1,113 +1,51 @@
-use std::{sync::Arc, hash::Hash};
-
-use parking_lot::{Condvar, Mutex};
+use std::hash::Hash;
 
 use crate::{
-    CompilationInput, CompilationType, Decision, DecisionDiagram, Frontier, InterruptibleSolver,
-    Problem, Relaxation, ResolutionStatus, Solver, StateRanking, SubProblem, WidthHeuristic, All, CutsetType,
+    CancellationToken, CompilationStatistics, Cutoff, InterruptCutoff, Decision, SearchTree, Frontier, InterruptibleSolver, Problem, Relaxation,
+    ResolutionStatus, Restarts, SecondaryObjective, Shedding, Solver, StateRanking, WidthHeuristic, All, CutsetType, OnViolation, Violation, VerificationError, WarmStartBudget,
+    MergePolicy, SolverObserver, SubProblem, MemoryAttribution, SolverConfig,
 };
 
-/// The shared data that may only be manipulated within critical sections
-struct Critical<'a, F: Frontier> {
-    /// This is the fringe: the set of nodes that must still be explored before
-    /// the problem can be considered 'solved'.
-    ///
-    /// # Note:
-    /// This fringe orders the nodes by upper bound (so the highest ub is going
-    /// to pop first). So, it is guaranteed that the upper bound of the first
-    /// node being popped is an upper bound on the value reachable by exploring
-    /// any of the nodes remaining on the fringe. As a consequence, the
-    /// exploration can be stopped as soon as a node with an ub <= current best
-    /// lower bound is popped.
-    fringe: &'a mut F,
-    /// This is the number of nodes that are currently being explored.
-    ///
-    /// # Note
-    /// This information may seem innocuous/superfluous, whereas in fact it is
-    /// very important. Indeed, this is the piece of information that lets us
-    /// distinguish between a node-starvation and the completion of the problem
-    /// resolution. The bottom line is, this counter needs to be carefully
-    /// managed to guarantee the termination of all threads.
-    ongoing: usize,
-    /// This is a counter that tracks the number of nodes that have effectively
-    /// been explored. That is, the number of nodes that have been popped from
-    /// the fringe, and for which a restricted and relaxed mdd have been developed.
-    explored: usize,
-    explored_dd: usize,
-    /// This is the value of the best known lower bound.
-    best_lb: isize,
-    /// This is the value of the best known lower bound.
-    /// *WARNING* This one only gets set when the interrupt condition is satisfied
-    best_ub: isize,
-    /// If set, this keeps the info about the best solution so far.
-    best_sol: Option<Vec<Decision>>,
-    /// This vector is used to store the upper bound on the node which is
-    /// currently processed by each thread.
-    ///
-    /// # Note
-    /// When a thread is idle (or more generally when it is done with processing
-    /// it node), it should place the value i32::min_value() in its corresponding
-    /// cell.
-    upper_bounds: Vec<isize>,
-    interrupted: bool,
-}
-/// The state which is shared among the many running threads: it provides an
-/// access to the critical data (protected by a mutex) as well as a monitor
-/// (condvar) to park threads in case of node-starvation.
-struct Shared<'a, P, R, O, W, F: Frontier>
+use super::engine::{Engine, NodeProcessor};
+
+/// The workers of the `ParallelSolver` compile plain dds without any
+/// knowledge of what has been explored by the other workers.
+struct AllProcessor;
+
+impl<T> NodeProcessor<T> for AllProcessor
 where
-    P: Problem + Send + Sync + 'a,
-    R: Relaxation<State = P::State> + Send + Sync + 'a,
-    O: StateRanking<State = P::State> + Send + Sync + 'a,
-    W: WidthHeuristic<P::State> + Send + Sync + 'a,
-    F: Frontier<State = P::State> + Send + Sync + 'a,
+    T: Eq + Hash + Clone,
 {
-    problem: &'a P,
-    relaxation: &'a R,
-    ranking: &'a O,
-    width_heu: &'a W,
-    cutset_type: CutsetType,
-
-    /// This is the shared state data which can only be accessed within critical
-    /// sections. Therefore, it is protected by a mutex which prevents concurrent
-    /// reads/writes.
-    critical: Mutex<Critical<'a, F>>,
-    /// This is the monitor on which nodes must wait when facing an empty fringe.
-    /// The corollary, it that whenever a node has completed the processing of
-    /// a subproblem, it must wakeup all parked threads waiting on this monitor.
-    monitor: Condvar,
-}
-/// The workload a thread can get from the shared state
-enum WorkLoad<T> {
-    /// There is no work left to be done: you can safely terminate
-    Complete,
-    /// The work must stop because of an external cutoff
-    Interruption,
-    /// There is nothing you can do right now. Check again when you wake up
-    Starvation,
-    /// The item to process
-    WorkItem { node: SubProblem<T> },
+    type Dd = All<T>;
+    type Bookkeeping = ();
+
+    fn new_dd(&self, cutset_type: CutsetType) -> All<T> {
+        All::new(cutset_type)
+    }
+    fn explored(dd: &All<T>) -> usize {
+        dd.get_explored()
+    }
+    fn footprint(dd: &All<T>) -> usize {
+        dd.footprint()
+    }
 }
 
 pub struct ParallelSolver<'a, P, R, O, W, F>
 where
     P: Problem + Send + Sync + 'a,
+    P::State: Eq + Hash + Clone,
     R: Relaxation<State = P::State> + Send + Sync + 'a,
     O: StateRanking<State = P::State> + Send + Sync + 'a,
     W: WidthHeuristic<P::State> + Send + Sync + 'a,
     F: Frontier<State = P::State> + Send + Sync + 'a,
 {
-    /// This is the shared state. Each thread is going to take a reference to it.
-    shared: Shared<'a, P, R, O, W, F>,
-    /// This is a configuration parameter that tunes the number of threads that
-    /// will be spawned to solve the problem. By default, this number amounts
-    /// to the number of hardware threads available on the machine.
-    nb_threads: usize,
+    /// This is the branch-and-bound which does the actual work.
+    engine: Engine<'a, P, R, O, W, &'a mut F, AllProcessor>,
 }
 
 // private interface.
-impl <'a, P, R, O, W, F> ParallelSolver<'a, P, R, O, W, F> 
-where 
+impl <'a, P, R, O, W, F> ParallelSolver<'a, P, R, O, W, F>
+where
     P: Problem + Send + Sync + 'a,
     R: Relaxation<State = P::State> + Send + Sync + 'a,
     O: StateRanking<State = P::State> + Send + Sync + 'a,
@@ -147,225 +85,242 @@ where
         nb_threads: usize,
     ) -> Self {
         ParallelSolver {
-            shared: Shared {
-                problem,
-                relaxation,
-                ranking,
-                width_heu,
-                cutset_type: cutset_type,
-                //
-                monitor: Condvar::new(),
-                critical: Mutex::new(Critical {
-                    best_sol: None,
-                    best_lb: isize::MIN,
-                    best_ub: isize::MAX,
-                    upper_bounds: vec![isize::MAX; nb_threads],
-                    fringe,
-                    ongoing: 0,
-                    explored: 0,
-                    explored_dd: 0,
-                    interrupted: false,
-                }),
-            },
-            nb_threads,
+            engine: Engine::new(problem, relaxation, ranking, width_heu, cutset_type, fringe, nb_threads, AllProcessor, ()),
         }
     }
-    /// Sets the number of threads used by the solver
+    /// Replaces all the options of the solver at once (see `SolverConfig`)
+    pub fn with_config(mut self, config: SolverConfig) -> Self {
+        self.engine.set_config(config);
+        self
+    }
+    /// Sets `SolverConfig::nb_threads`
     pub fn with_nb_threads(mut self, nb_threads: usize) -> Self {
-        self.nb_threads = nb_threads;
+        self.engine.configure(|config| config.nb_threads = nb_threads);
         self
     }
-
-    /// This method initializes the problem resolution. Put more simply, this
-    /// method posts the root node of the mdd onto the fringe so that a thread
-    /// can pick it up and the processing can be bootstrapped.
-    fn initialize(&self) {
-        let root = self.root_node();
-        self.shared.critical.lock().fringe.push(root);
+    /// Sets `SolverConfig::min_relax_depth_offset` (panics when it is too small)
+    pub fn with_min_relax_depth_offset(mut self, offset: usize) -> Self {
+        self.engine.configure(|config| config.min_relax_depth_offset = offset);
+        self
     }
-
-    fn root_node(&self) -> SubProblem<P::State> {
-        let shared = &self.shared;
-        SubProblem {
-            state: Arc::new(shared.problem.initial_state()),
-            value: shared.problem.initial_value(),
-            path: vec![],
-            ub: isize::MAX,
-        }
+    /// Sets `SolverConfig::restrict_first_layers`
+    pub fn with_first_layers_restriction(mut self, restrict: bool) -> Self {
+        self.engine.configure(|config| config.restrict_first_layers = restrict);
+        self
+    }
+    /// Sets `SolverConfig::max_forced_layers`
+    pub fn with_forced_layers(mut self, max: usize) -> Self {
+        self.engine.configure(|config| config.max_forced_layers = max);
+        self
+    }
+    /// Sets `SolverConfig::reuse_prefix`
+    pub fn with_prefix_reuse(mut self, reuse: bool) -> Self {
+        self.engine.configure(|config| config.reuse_prefix = reuse);
+        self
+    }
+    /// Sets `SolverConfig::merge_policy`
+    pub fn with_merge_policy(mut self, policy: MergePolicy) -> Self {
+        self.engine.configure(|config| config.merge_policy = policy);
+        self
+    }
+    /// Sets `SolverConfig::check_merge`
+    pub fn with_merge_check(mut self, check: bool) -> Self {
+        self.engine.configure(|config| config.check_merge = check);
+        self
+    }
+    /// Sets `SolverConfig::model_timing`
+    pub fn with_model_timing(mut self, timed: bool) -> Self {
+        self.engine.configure(|config| config.model_timing = timed);
+        self
+    }
+    /// Sets `SolverConfig::self_check`
+    pub fn with_self_check(mut self, check: bool) -> Self {
+        self.engine.configure(|config| config.self_check = check);
+        self
+    }
+    /// Sets `SolverConfig::on_violation`
+    pub fn with_violation_policy(mut self, policy: OnViolation) -> Self {
+        self.engine.configure(|config| config.on_violation = policy);
+        self
+    }
+    /// Sets `SolverConfig::push_filter`
+    pub fn with_push_filter(mut self, capacity: usize) -> Self {
+        self.engine.configure(|config| config.push_filter = capacity);
+        self
+    }
+    /// Breaks the ties among the solutions of equal value with the given secondary objective
+    pub fn with_secondary_objective(mut self, secondary: &'a (dyn SecondaryObjective + Sync)) -> Self {
+        self.engine.set_secondary_objective(secondary);
+        self
+    }
+    /// Sets `SolverConfig::tie_limit`
+    pub fn with_tie_limit(mut self, limit: usize) -> Self {
+        self.engine.configure(|config| config.tie_limit = limit);
+        self
+    }
+    /// Sets `SolverConfig::verification`
+    pub fn with_verification(mut self, verify: bool) -> Self {
+        self.engine.configure(|config| config.verification = verify);
+        self
+    }
+    /// Sets `SolverConfig::shedding`
+    pub fn with_shedding(mut self, shedding: Shedding) -> Self {
+        self.engine.configure(|config| config.shedding = Some(shedding));
+        self
+    }
+    /// Sets `SolverConfig::cancellation`
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.engine.configure(|config| config.cancellation = token);
+        self
+    }
+    /// Tells the given observer about each improvement of the bounds (see `SolverObserver`)
+    pub fn with_observer(mut self, observer: &'a dyn SolverObserver) -> Self {
+        self.engine.set_observer(observer);
+        self
+    }
+    /// Sets `SolverConfig::restarts`
+    pub fn with_restarts(mut self, restarts: Restarts) -> Self {
+        self.engine.configure(|config| config.restarts = Some(restarts));
+        self
+    }
+    /// Sets `SolverConfig::tree_recording`
+    pub fn with_tree_recording(mut self, record: bool) -> Self {
+        self.engine.configure(|config| config.tree_recording = record);
+        self
+    }
+    /// Sets `SolverConfig::memory_sampling`
+    pub fn with_memory_sampling(mut self, period: usize) -> Self {
+        self.engine.configure(|config| config.memory_sampling = period);
+        self
+    }
+    /// Seeds the best solution of the solver with a solution worth `value`, known beforehand
+    pub fn with_initial_lb(mut self, value: isize, solution: Vec<Decision>) -> Self {
+        self.engine.set_initial_solution(value, solution);
+        self
+    }
+    /// Sets `SolverConfig::suspension`
+    pub fn with_suspension(mut self, suspend: bool) -> Self {
+        self.engine.configure(|config| config.suspension = suspend);
+        self
     }
 
-    /// This method processes the given `node`. To do so, it reads the current
-    /// best lower bound from the critical data. Then it expands a restricted
-    /// and possibly a relaxed mdd rooted in `node`. If that is necessary,
-    /// it stores cutset nodes onto the fringe for further parallel processing.
-    fn process_one_node(
-        mdd: &mut All<P::State>,
-        shared: &Shared<P, R, O, W, F>,
-        node: SubProblem<P::State>,
-    ) -> usize
-    {
-        let mut explored_dd = 0;
-
-        // 1. RESTRICTION
-        let node_ub = node.ub;
-        let best_lb = Self::best_lb(shared);
+    pub fn get_explored(&self) -> usize {
+        self.engine.get_explored()
+    }
 
-        if node_ub <= best_lb {
-            return explored_dd;
-        }
+    pub fn get_explored_dd(&self) -> usize {
+        self.engine.get_explored_dd()
+    }
 
-        let width = shared.width_heu.max_width(&node.state);
-        let mut compilation = CompilationInput {
-            comp_type: CompilationType::Restricted,
-            max_width: width,
-            problem: shared.problem,
-            relaxation: shared.relaxation,
-            ranking: shared.ranking,
-            residual: node,
-            //
-            best_lb,
-        };
-
-        mdd.compile(&compilation);
-        explored_dd += mdd.get_explored();
-        Self::maybe_update_best(mdd, shared);
-        if mdd.is_exact() {
-            return explored_dd;
-        }
+    /// Returns the max and total size of the dds compiled during the resolution
+    pub fn get_compilation_statistics(&self) -> CompilationStatistics {
+        self.engine.get_compilation_statistics()
+    }
 
-        // 2. RELAXATION
-        let best_lb = Self::best_lb(shared);
-        compilation.comp_type = CompilationType::Relaxed;
-        compilation.best_lb = best_lb;
-        mdd.compile(&compilation);
-        explored_dd += mdd.get_explored();
-        if mdd.is_exact() {
-            Self::maybe_update_best(mdd, shared);
-        } else {
-            Self::enqueue_cutset(mdd, shared, node_ub);
-        }
+    /// Returns the number of cutset nodes drained from the relaxed dds
+    pub fn get_cutset_generated(&self) -> usize {
+        self.engine.get_cutset_generated()
+    }
 
-        return explored_dd;
+    /// Returns the number of cutset nodes which were pushed onto the fringe:
+    /// the others were pruned by the best lower bound (or by the push filter)
+    pub fn get_cutset_enqueued(&self) -> usize {
+        self.engine.get_cutset_enqueued()
     }
 
-    fn best_lb(shared: &Shared<P, R, O, W, F>) -> isize {
-        shared.critical.lock().best_lb
+    /// Returns the number of cutset nodes which the push filter skipped
+    pub fn get_suppressed_pushes(&self) -> usize {
+        self.engine.get_suppressed_pushes()
     }
 
-    /// This private method updates the shared best known node and lower bound in
-    /// case the best value of the current `mdd` expansion improves the current
-    /// bounds.
-    fn maybe_update_best(mdd: &All<P::State>, shared: &Shared<P, R, O, W, F>) {
-        let mut shared = shared.critical.lock();
-        let dd_best_value = mdd.best_value().unwrap_or(isize::MIN);
-        if dd_best_value > shared.best_lb {
-            shared.best_lb = dd_best_value;
-            shared.best_sol = mdd.best_solution();
-        }
+    /// Returns the number of nodes which were shed from the fringe
+    pub fn get_shed_nodes(&self) -> usize {
+        self.engine.get_shed_nodes()
+    }
+    /// Returns the largest number of nodes that the fringe held at once
+    pub fn get_peak_fringe(&self) -> usize {
+        self.engine.get_peak_fringe()
+    }
+    /// Returns the number of nodes which were popped from the fringe and
+    /// discarded because their upper bound could not improve the best lower
+    /// bound (the shed nodes are not counted, see `get_shed_nodes`)
+    pub fn get_discarded_by_bound(&self) -> usize {
+        self.engine.get_discarded_by_bound()
     }
-    /// If necessary, thightens the bound of nodes in the cutset of `mdd` and
-    /// then add the relevant nodes to the shared fringe.
-    fn enqueue_cutset(mdd: &mut All<P::State>, shared: &Shared<P, R, O, W, F>, ub: isize) {
-        let mut critical = shared.critical.lock();
-        let best_lb = critical.best_lb;
-        let fringe = &mut critical.fringe;
-        mdd.drain_cutset(|mut cutset_node| {
-            cutset_node.ub = ub.min(cutset_node.ub);
-            if cutset_node.ub > best_lb {
-                fringe.push(cutset_node);
-            }
-        });
-    }
-    /// Acknowledges that a thread finished processing its node.
-    fn notify_node_finished(shared: &Shared<P, R, O, W, F>, thread_id: usize, explored_dd: usize) {
-        let mut critical = shared.critical.lock();
-        critical.ongoing -= 1;
-        critical.upper_bounds[thread_id] = isize::MAX;
-        critical.explored_dd += explored_dd;
-        shared.monitor.notify_all();
-    }
-
-    /// Consults the shared state to fetch a workload. Depending on the current
-    /// state, the workload can either be:
-    ///
-    ///   + Complete, when the problem is solved and all threads should stop
-    ///   + Starvation, when there is no subproblem available for processing
-    ///     at the time being (but some subproblem are still being processed
-    ///     and thus the problem cannot be considered solved).
-    ///   + WorkItem, when the thread successfully obtained a subproblem to
-    ///     process.
-    fn get_workload<I>(
-        shared: &Shared<P, R, O, W, F>,
-        thread_id: usize,
-        interrupt: I,
-    ) -> WorkLoad<P::State>
-    where
-        I: Fn() -> bool,
-    {
-        let mut critical = shared.critical.lock();
 
-        // Are we done ?
-        if critical.ongoing == 0 && critical.fringe.is_empty() {
-            critical.best_ub = critical.best_lb;
-            return WorkLoad::Complete;
-        }
+    /// Returns the number of dives from the root made by the random restarts
+    pub fn get_dives(&self) -> usize {
+        self.engine.get_dives()
+    }
 
-        // Do we need to stop
-        if critical.interrupted {
-            return WorkLoad::Interruption;
-        } else if interrupt() {
-            critical.interrupted = true;
-
-            critical.best_ub = if critical.ongoing > 0 {
-                critical
-                    .upper_bounds
-                    .iter()
-                    .copied()
-                    .filter(|x| *x != isize::MAX)
-                    .max()
-                    .unwrap_or(isize::MAX)
-            } else {
-                let nn = critical.fringe.pop().unwrap();
-                nn.ub
-            };
-
-            critical.fringe.clear();
-            return WorkLoad::Interruption;
-        }
+    /// Returns the number of dives which improved the best solution
+    pub fn get_improving_dives(&self) -> usize {
+        self.engine.get_improving_dives()
+    }
+    /// Returns the token of `SolverConfig::cancellation`
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.engine.cancellation_token()
+    }
 
-        // Nothing to do yet ? => Wait for someone to post jobs
-        if critical.fringe.is_empty() {
-            shared.monitor.wait(&mut critical);
-            return WorkLoad::Starvation;
-        }
-        // Nothing relevant ? =>  Wait for someone to post jobs
-        let nn = critical.fringe.pop().unwrap();
-        if nn.ub <= critical.best_lb {
-            critical.fringe.clear();
-            return WorkLoad::Starvation;
-        }
+    /// Returns the violations found by the self check of the model
+    pub fn violations(&self) -> Vec<Violation<P::State>> {
+        self.engine.violations()
+    }
 
-        // Consume the current node and process it
-        critical.ongoing += 1;
-        critical.explored += 1;
-        critical.upper_bounds[thread_id] = nn.ub;
+    /// Returns the reason why the best solution failed its verification
+    /// during the last resolution (if it did)
+    pub fn verification_error(&self) -> Option<VerificationError> {
+        self.engine.verification_error()
+    }
 
-        WorkLoad::WorkItem { node: nn }
+    /// Returns the branch-and-bound tree explored during the resolution (if
+    /// it was recorded)
+    pub fn search_tree(&self) -> Option<SearchTree> {
+        self.engine.search_tree()
     }
 
-    pub fn get_explored(&self) -> usize {
-        return self.shared.critical.lock().explored;
+    /// Returns the memory sample at which the dds and the fringe held the most
+    /// bytes altogether (if the memory was sampled)
+    pub fn peak_attribution(&self) -> Option<MemoryAttribution> {
+        self.engine.peak_attribution()
     }
+}
 
-    pub fn get_explored_dd(&self) -> usize {
-        return self.shared.critical.lock().explored_dd;
+impl<'a, P, R, O, W, F> ParallelSolver<'a, P, R, O, W, F>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone + Send,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    F: Frontier<State = P::State> + Send + Sync + 'a,
+{
+    /// Seeds the best solution of the solver with restricted dds of the whole
+    /// problem, whose width doubles until the budget is spent (see
+    /// `WarmStartBudget`). This is meant to be called right before the
+    /// resolution: the first nodes of the branch-and-bound are then pruned
+    /// with the best lower bound of the warm start, which is also kept when
+    /// the resolution is interrupted right away.
+    pub fn warm_start(&mut self, budget: WarmStartBudget) {
+        self.engine.warm_start(budget)
+    }
+    /// Makes the first resolution start from the given nodes rather than from the root
+    pub fn with_initial_fringe(mut self, nodes: Vec<SubProblem<P::State>>) -> Self {
+        self.engine.set_initial_fringe(nodes);
+        self
+    }
+    /// Returns the nodes which the last resolution did not explore, for
+    /// another solver to resume it (see `with_initial_fringe`). There are
+    /// none unless that resolution was suspended (see `with_suspension`).
+    pub fn into_open_subproblems(self) -> Vec<SubProblem<P::State>> {
+        self.engine.into_open_subproblems()
     }
 }
 
 impl<'a, P, R, O, W, F> Solver for ParallelSolver<'a, P, R, O, W, F>
 where
     P: Problem + Send + Sync + 'a,
-    P::State: Eq + PartialEq + Hash + Clone,
+    P::State: Eq + PartialEq + Hash + Clone + Send,
     R: Relaxation<State = P::State> + Send + Sync + 'a,
     O: StateRanking<State = P::State> + Send + Sync + 'a,
     W: WidthHeuristic<P::State> + Send + Sync + 'a,
@@ -376,82 +331,62 @@ where
     /// (long running threads); each of which will continually get a workload
     /// and process it until the problem is solved.
     fn maximize(&mut self) {
-        self.initialize();
-
-        std::thread::scope(|s| {
-            for i in 0..self.nb_threads {
-                let shared = &self.shared;
-                s.spawn(move || {
-                    let mut mdd = All::<P::State>::new(shared.cutset_type);
-                    loop {
-                        match Self::get_workload(shared, i, || false) {
-                            WorkLoad::Complete => break,
-                            WorkLoad::Interruption => break, // this one cannot occur
-                            WorkLoad::Starvation => continue,
-                            WorkLoad::WorkItem { node } => {
-                                let explored_dd = Self::process_one_node(&mut mdd, shared, node);
-                                Self::notify_node_finished(shared, i, explored_dd);
-                            }
-                        }
-                    }
-                });
-            }
-        });
+        self.engine.maximize_with_cutoff(InterruptCutoff(|| false));
+    }
+
+    /// Solves the problem like `maximize`, but minimizes its objective.
+    fn minimize(&mut self) {
+        self.engine.minimize_with_cutoff(InterruptCutoff(|| false));
     }
 
     /// Returns the best solution that has been identified for this problem.
     fn best_solution(&self) -> Option<Vec<Decision>> {
-        self.shared.critical.lock().best_sol.clone()
+        self.engine.best_solution()
     }
     /// Returns the value of the best solution that has been identified for
     /// this problem.
     fn best_value(&self) -> Option<isize> {
-        let critical = self.shared.critical.lock();
-        critical.best_sol.as_ref().map(|_sol| critical.best_lb)
+        self.engine.best_value()
     }
 }
 
 impl<'a, P, R, O, W, F> InterruptibleSolver for ParallelSolver<'a, P, R, O, W, F>
 where
     P: Problem + Send + Sync + 'a,
-    P::State: Eq + PartialEq + Hash + Clone,
+    P::State: Eq + PartialEq + Hash + Clone + Send,
     R: Relaxation<State = P::State> + Send + Sync + 'a,
     O: StateRanking<State = P::State> + Send + Sync + 'a,
     W: WidthHeuristic<P::State> + Send + Sync + 'a,
     F: Frontier<State = P::State> + Send + Sync + 'a,
 {
-    fn maximize_with_interrupt<I>(&mut self, interrupt: I) -> crate::ResolutionStatus
+    fn maximize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
     where
-        I: Fn() -> bool + Send + Sync + 'static,
+        C: Cutoff + Send + Sync + 'static,
     {
-        self.initialize();
-        let callback = &interrupt;
-        std::thread::scope(|s| {
-            for i in 0..self.nb_threads {
-                let shared = &self.shared;
-                s.spawn(move || {
-                    let mut mdd = All::<P::State>::new(shared.cutset_type);
-                    loop {
-                        match Self::get_workload(shared, i, callback) {
-                            WorkLoad::Complete => break,
-                            WorkLoad::Interruption => break, // this one cannot occur
-                            WorkLoad::Starvation => continue,
-                            WorkLoad::WorkItem { node } => {
-                                let explored_dd = Self::process_one_node(&mut mdd, shared, node);
-                                Self::notify_node_finished(shared, i, explored_dd);
-                            }
-                        }
-                    }
-                });
-            }
-        });
-
-        let lock = self.shared.critical.lock();
-        if !lock.interrupted {
-            ResolutionStatus::Proved
-        } else {
-            ResolutionStatus::Interrupted
-        }
+        self.engine.maximize_with_cutoff(cutoff)
+    }
+
+    fn minimize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        self.engine.minimize_with_cutoff(cutoff)
+    }
+
+    fn maximize_with_cutoff_and_progress<C, G>(&mut self, cutoff: C, on_improvement: G) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        G: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.engine.maximize_with_progress(cutoff, on_improvement)
+    }
+
+    fn minimize_with_cutoff_and_progress<C, G>(&mut self, cutoff: C, on_improvement: G) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        G: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.engine.minimize_with_progress(cutoff, on_improvement)
     }
 
     fn best_value_so_far(&self) -> Option<isize> {
@@ -463,10 +398,10 @@ where
     }
 
     fn best_lower_bound(&self) -> isize {
-        self.shared.critical.lock().best_lb
+        self.engine.best_lower_bound()
     }
 
     fn best_upper_bound(&self) -> isize {
-        self.shared.critical.lock().best_ub
+        self.engine.best_upper_bound()
     }
 }
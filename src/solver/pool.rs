@@ -0,0 +1,207 @@
+//! This module implements the pool of solutions a solver may keep on top of
+//! its best one: the `capacity` best distinct solutions that its dds came
+//! across during the resolution. Since the solvers explore the nodes which can
+//! only tie with the best solution when they keep a pool, the pool of a
+//! complete resolution holds several optimal solutions whenever there are.
+//!
+//! # Note
+//! The pool is no enumeration of the optimal solutions (see `ExactSolver` for
+//! that): the solutions which go through a state that was already explored
+//! with the same value are pruned by the barrier, just like any other.
+//!
+//! # Example
+//! ```ignore
+//! let mut solver = PooledBarrierParallelSolver::new(&problem, &relaxation, &ranking, &width, CutsetType::Frontier, 5)
+//!     .with_diversity(|a, b| a.iter().zip(b).filter(|(x, y)| x != y).count() >= 2);
+//! solver.maximize();
+//! for (value, solution) in solver.best_solutions() { ... }
+//! ```
+
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    hash::Hash,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{BarrierParallelSolver, CutsetType, Decision, Problem, Relaxation, StateRanking, WidthHeuristic};
+
+/// Tells whether two solutions (whose decisions are sorted by variable) are
+/// different enough to be kept in the same pool
+pub type Diversity = fn(&[Decision], &[Decision]) -> bool;
+
+/// The best distinct solutions found so far, along with their values (those
+/// of the maximization, like all the values the engine deals with)
+#[derive(Debug, Clone, Default)]
+pub struct SolutionPool {
+    capacity: usize,
+    /// The worst solution is on top, so that it is the first to go
+    solutions: BinaryHeap<Reverse<(isize, Vec<Decision>)>>,
+    is_diverse: Option<Diversity>,
+}
+impl SolutionPool {
+    /// Creates an empty pool which keeps at most `capacity` solutions
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, solutions: BinaryHeap::with_capacity(capacity + 1), is_diverse: None }
+    }
+    /// Only keeps solutions which are diverse with each other: a solution
+    /// which is not diverse with a better (or as good) one of the pool is
+    /// turned down, and the worse ones it is not diverse with are dropped
+    pub fn with_diversity(mut self, is_diverse: Diversity) -> Self {
+        self.is_diverse = Some(is_diverse);
+        self
+    }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    pub fn len(&self) -> usize {
+        self.solutions.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.solutions.is_empty()
+    }
+    /// Returns the value a solution must exceed to get into the pool (none
+    /// while the pool is not full)
+    pub fn threshold(&self) -> Option<isize> {
+        (self.solutions.len() >= self.capacity).then(|| self.solutions.peek().map_or(isize::MAX, |Reverse((v, _))| *v))
+    }
+    /// Offers a solution worth `value` to the pool. It returns true iff the
+    /// solution was kept.
+    pub fn offer(&mut self, value: isize, mut solution: Vec<Decision>) -> bool {
+        if self.threshold().is_some_and(|threshold| value <= threshold) {
+            return false;
+        }
+        solution.sort_unstable_by_key(|d| d.var.id());
+        if self.solutions.iter().any(|Reverse((_, s))| *s == solution) {
+            return false;
+        }
+        if let Some(is_diverse) = self.is_diverse {
+            let too_close = |s: &[Decision]| !is_diverse(s, &solution);
+            if self.solutions.iter().any(|Reverse((v, s))| *v >= value && too_close(s)) {
+                return false;
+            }
+            self.solutions.retain(|Reverse((_, s))| !too_close(s));
+        }
+
+        self.solutions.push(Reverse((value, solution)));
+        if self.solutions.len() > self.capacity {
+            self.solutions.pop();
+        }
+        true
+    }
+    /// Returns the solutions of the pool, the best ones first
+    pub fn solutions(&self) -> Vec<(isize, Vec<Decision>)> {
+        let mut solutions = self.solutions.iter().map(|Reverse(s)| s.clone()).collect::<Vec<_>>();
+        solutions.sort_by_key(|(v, _)| Reverse(*v));
+        solutions
+    }
+}
+
+/// A barrier solver which keeps a pool of the best distinct solutions it
+/// finds (see `BarrierParallelSolver::with_solution_pool`). It derefs to the
+/// solver it wraps, which does all the work.
+pub struct PooledBarrierParallelSolver<'a, P, R, O, W>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+{
+    solver: BarrierParallelSolver<'a, P, R, O, W>,
+}
+impl<'a, P, R, O, W> PooledBarrierParallelSolver<'a, P, R, O, W>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+{
+    /// Creates a solver which keeps at most `capacity` solutions
+    pub fn new(problem: &'a P, relaxation: &'a R, ranking: &'a O, width_heu: &'a W, cutset_type: CutsetType, capacity: usize) -> Self {
+        let solver = BarrierParallelSolver::new(problem, relaxation, ranking, width_heu, cutset_type)
+            .with_solution_pool(SolutionPool::new(capacity));
+        Self { solver }
+    }
+    /// Creates a solver which keeps at most `capacity` solutions, and runs
+    /// the given number of threads
+    pub fn custom(problem: &'a P, relaxation: &'a R, ranking: &'a O, width_heu: &'a W, cutset_type: CutsetType, nb_threads: usize, capacity: usize) -> Self {
+        let solver = BarrierParallelSolver::custom(problem, relaxation, ranking, width_heu, cutset_type, nb_threads)
+            .with_solution_pool(SolutionPool::new(capacity));
+        Self { solver }
+    }
+    /// Only keeps solutions which are diverse with each other (see
+    /// `SolutionPool::with_diversity`)
+    pub fn with_diversity(mut self, is_diverse: Diversity) -> Self {
+        let pool = self.solver.solution_pool().with_diversity(is_diverse);
+        self.solver = self.solver.with_solution_pool(pool);
+        self
+    }
+    /// Returns the wrapped solver
+    pub fn into_inner(self) -> BarrierParallelSolver<'a, P, R, O, W> {
+        self.solver
+    }
+}
+impl<'a, P, R, O, W> Deref for PooledBarrierParallelSolver<'a, P, R, O, W>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+{
+    type Target = BarrierParallelSolver<'a, P, R, O, W>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.solver
+    }
+}
+impl<'a, P, R, O, W> DerefMut for PooledBarrierParallelSolver<'a, P, R, O, W>
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.solver
+    }
+}
+
+#[cfg(test)]
+mod test_pool {
+    use crate::{Decision, Variable};
+
+    use super::SolutionPool;
+
+    fn solution(values: &[isize]) -> Vec<Decision> {
+        values.iter().enumerate().map(|(i, value)| Decision { var: Variable(i), value: *value }).collect()
+    }
+
+    #[test]
+    fn the_pool_keeps_the_best_distinct_solutions() {
+        let mut pool = SolutionPool::new(2);
+        assert!(pool.offer(3, solution(&[0, 1])));
+        // the same solution, whatever the order of its decisions
+        assert!(!pool.offer(3, solution(&[0, 1]).into_iter().rev().collect()));
+        assert!(pool.offer(5, solution(&[1, 1])));
+        assert_eq!(Some(3), pool.threshold());
+        assert!(!pool.offer(3, solution(&[1, 0])));
+        assert!(pool.offer(4, solution(&[1, 0])));
+        assert_eq!(vec![(5, solution(&[1, 1])), (4, solution(&[1, 0]))], pool.solutions());
+    }
+
+    #[test]
+    fn the_pool_only_keeps_the_best_of_the_close_solutions() {
+        let differ = |a: &[Decision], b: &[Decision]| a.iter().zip(b).filter(|(x, y)| x != y).count() >= 2;
+        let mut pool = SolutionPool::new(3).with_diversity(differ);
+        assert!(pool.offer(3, solution(&[0, 0, 0])));
+        assert!(!pool.offer(2, solution(&[0, 0, 1])));
+        // a better close solution takes the place of the worse one
+        assert!(pool.offer(4, solution(&[1, 0, 0])));
+        assert!(pool.offer(1, solution(&[0, 1, 1])));
+        assert_eq!(vec![(4, solution(&[1, 0, 0])), (1, solution(&[0, 1, 1]))], pool.solutions());
+    }
+}
@@ -0,0 +1,210 @@
+//! This module provides a solver which only computes a dual bound of the
+//! problem: it compiles a single relaxed dd of the root, instead of running
+//! the branch-and-bound. This is much cheaper than a resolution, and the
+//! bound is the one a resolution would start from.
+
+use std::hash::Hash;
+
+use crate::{
+    objective::negate, All, CompilationInputBuilder, CompilationStatistics, CutsetType, Decision, DecisionDiagram, MergePolicy,
+    Negated, Objective, Problem, Relaxation, ResolutionStatus, StateRanking, WidthHeuristic,
+};
+
+/// Compiles a relaxed dd of the root with the width the heuristic gives to
+/// the initial state, and reports the bound it gives on the objective of the
+/// problem (see `Problem::objective`): an upper bound on a maximization, a
+/// lower bound on a minimization. When the dd turns out to be exact, its best
+/// solution is an optimal one.
+pub struct RelaxedBoundSolver<'a, P, R, O, W> {
+    problem: &'a P,
+    relaxation: &'a R,
+    ranking: &'a O,
+    width_heu: &'a W,
+    /// The number of layers below the root which are never merged
+    min_relax_depth_offset: usize,
+    merge_policy: MergePolicy,
+    objective: Objective,
+
+    /// The bound in terms of the objective of the problem
+    bound: isize,
+    best_sol: Option<Vec<Decision>>,
+    explored_dd: usize,
+    compilations: CompilationStatistics,
+}
+
+impl<'a, P, R, O, W> RelaxedBoundSolver<'a, P, R, O, W>
+where
+    P: Problem,
+    P::State: Eq + Hash + Clone,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+    W: WidthHeuristic<P::State>,
+{
+    pub fn new(problem: &'a P, relaxation: &'a R, ranking: &'a O, width_heu: &'a W) -> Self {
+        Self {
+            problem,
+            relaxation,
+            ranking,
+            width_heu,
+            min_relax_depth_offset: 1,
+            merge_policy: MergePolicy::SingleNode,
+            objective: problem.objective(),
+            bound: match problem.objective() {
+                Objective::Maximize => isize::MAX,
+                Objective::Minimize => isize::MIN,
+            },
+            best_sol: None,
+            explored_dd: 0,
+            compilations: CompilationStatistics::default(),
+        }
+    }
+    /// Sets the number of layers below the root that are kept exact (see
+    /// `with_min_relax_depth_offset` on the `ParallelSolver`)
+    pub fn with_min_relax_depth_offset(mut self, offset: usize) -> Self {
+        self.min_relax_depth_offset = offset;
+        self
+    }
+    /// Sets how the relaxed dd merges its overflowing layers (see
+    /// `with_merge_policy` on the `ParallelSolver`)
+    pub fn with_merge_policy(mut self, policy: MergePolicy) -> Self {
+        self.merge_policy = policy;
+        self
+    }
+
+    /// Compiles the relaxed dd of the root. This returns `Proved` when the dd
+    /// is exact (its bound is then the optimum), and `Bounded` otherwise.
+    pub fn compute(&mut self) -> ResolutionStatus {
+        let width = self.width_heu.max_width(&self.problem.initial_state()).max(1);
+        let mut mdd = All::new(CutsetType::LastExactLayer);
+        match self.objective {
+            Objective::Maximize => Self::compile(&mut mdd, self.problem, self.relaxation, self.ranking, width, self.min_relax_depth_offset, self.merge_policy),
+            Objective::Minimize => {
+                let (problem, relaxation) = (&Negated(self.problem), &Negated(self.relaxation));
+                Self::compile(&mut mdd, problem, relaxation, self.ranking, width, self.min_relax_depth_offset, self.merge_policy)
+            }
+        }
+        self.explored_dd += mdd.get_explored();
+        self.compilations.record(&mdd);
+        // a relaxed dd without any solution proves that there is none
+        let bound = mdd.best_value().unwrap_or(isize::MIN);
+        self.bound = match self.objective {
+            Objective::Maximize => bound,
+            Objective::Minimize => negate(bound),
+        };
+        self.best_sol = mdd.best_exact_solution();
+
+        if mdd.is_exact() {
+            ResolutionStatus::Proved
+        } else {
+            ResolutionStatus::Bounded
+        }
+    }
+    /// Compiles the relaxed dd of the root of the given (possibly negated)
+    /// problem
+    fn compile<PP, RR>(mdd: &mut All<P::State>, problem: &PP, relaxation: &RR, ranking: &O, width: usize, offset: usize, policy: MergePolicy)
+    where
+        PP: Problem<State = P::State>,
+        RR: Relaxation<State = P::State>,
+    {
+        let compilation = CompilationInputBuilder::relaxed(problem, relaxation, ranking)
+            .root()
+            .max_width(width)
+            .min_relax_depth_offset(offset)
+            .merge_policy(policy)
+            .build()
+            .expect("the input of a compilation is complete");
+        mdd.compile(&compilation);
+    }
+
+    /// Returns the bound given by the relaxed dd (`isize::MAX` until it is
+    /// computed, or `isize::MIN` for a minimization)
+    pub fn bound(&self) -> isize {
+        self.bound
+    }
+    /// Returns the best solution of the relaxed dd, when it is exact
+    pub fn best_solution(&self) -> Option<Vec<Decision>> {
+        self.best_sol.clone()
+    }
+    /// Returns the value of the best solution, when the relaxed dd is exact
+    pub fn best_value(&self) -> Option<isize> {
+        self.best_sol.as_ref().map(|_| self.bound)
+    }
+    /// Returns the best lower bound in terms of the objective of the problem:
+    /// for a maximization, this is the value of the best solution when the
+    /// relaxed dd is exact, and `isize::MIN` otherwise
+    pub fn best_lower_bound(&self) -> isize {
+        match self.objective {
+            Objective::Maximize => self.best_value().unwrap_or(isize::MIN),
+            Objective::Minimize => self.bound,
+        }
+    }
+    /// Returns the best upper bound in terms of the objective of the problem:
+    /// for a minimization, this is the value of the best solution when the
+    /// relaxed dd is exact, and `isize::MAX` otherwise
+    pub fn best_upper_bound(&self) -> isize {
+        match self.objective {
+            Objective::Maximize => self.bound,
+            Objective::Minimize => self.best_value().unwrap_or(isize::MAX),
+        }
+    }
+
+    pub fn get_explored_dd(&self) -> usize {
+        self.explored_dd
+    }
+
+    pub fn get_compilation_statistics(&self) -> CompilationStatistics {
+        self.compilations
+    }
+}
+
+#[cfg(test)]
+mod test_relaxed_bound {
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, Tweaked};
+    use crate::{BarrierParallelSolver, CutsetType, Decision, Fixed, InterruptibleSolver, NodeCutoff, ResolutionStatus, Solver};
+
+    use super::RelaxedBoundSolver;
+
+    #[test]
+    fn the_bound_of_the_root_is_that_of_the_resolution() {
+        let problem = Knapsack::toy();
+        let mut solver = RelaxedBoundSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1));
+        assert_eq!(isize::MAX, solver.bound());
+        assert_eq!(ResolutionStatus::Bounded, solver.compute());
+        assert!(solver.bound() >= 13);
+        assert_eq!((None, None, isize::MIN), (solver.best_value(), solver.best_solution(), solver.best_lower_bound()));
+        assert!(solver.get_explored_dd() > 0);
+        assert_eq!(1, solver.get_compilation_statistics().nb_compilations);
+
+        // the branch-and-bound starts from (at most) the same bound
+        let mut bnb = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, 1);
+        bnb.maximize_with_cutoff(NodeCutoff(1));
+        assert!(13 <= bnb.best_upper_bound() && bnb.best_upper_bound() <= solver.bound());
+    }
+
+    #[test]
+    fn an_exact_relaxed_dd_proves_the_optimum() {
+        let problem = Knapsack::toy();
+        let mut solver = RelaxedBoundSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1000));
+        assert_eq!(ResolutionStatus::Proved, solver.compute());
+        assert_eq!((13, Some(13), 13), (solver.bound(), solver.best_value(), solver.best_lower_bound()));
+
+        let mut bnb = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer, 1);
+        bnb.maximize();
+        let value = |sol: Vec<Decision>| sol.iter().filter(|d| d.value == 1).map(|d| problem.profit[d.var.id()]).sum::<usize>();
+        assert_eq!(value(bnb.best_solution().unwrap()), value(solver.best_solution().unwrap()));
+    }
+
+    #[test]
+    fn the_bound_of_a_minimization_is_a_lower_bound() {
+        let problem = Tweaked::spare_capacity(Knapsack::toy(), 3);
+        let mut solver = RelaxedBoundSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1));
+        assert_eq!(isize::MIN, solver.bound());
+        assert_eq!(ResolutionStatus::Bounded, solver.compute());
+        assert!(solver.bound() <= 12);
+        assert_eq!((solver.bound(), isize::MAX), (solver.best_lower_bound(), solver.best_upper_bound()));
+
+        let mut solver = RelaxedBoundSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1000));
+        assert_eq!(ResolutionStatus::Proved, solver.compute());
+        assert_eq!((Some(12), 12, 12), (solver.best_value(), solver.best_lower_bound(), solver.best_upper_bound()));
+    }
+}
@@ -0,0 +1,257 @@
+//! This module implements the random restarts of the hybrid mode of the
+//! solvers. When the branch-and-bound seems to make no progress (its fringe
+//! grows too large, or it explores many nodes without improving the best
+//! solution), some of the workers stop exploring the fringe: they dive from
+//! the root instead, that is, they compile restricted dds of the whole problem
+//! which each keep a different random selection of nodes. The dives share
+//! the best solution with the other workers, so that a good solution found by
+//! a dive lets the branch-and-bound prune its fringe. The divers go back to
+//! the fringe as soon as the best solution improves or the fringe shrinks.
+//!
+//! The dives never push anything onto the fringe, nor do they count as nodes
+//! being explored: the optimality proof is made by the branch-and-bound alone.
+
+use std::{cmp::Ordering, hash::{Hash, Hasher}};
+
+use rustc_hash::FxHasher;
+
+use crate::StateRanking;
+
+/// What makes some of the workers dive from the root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartTrigger {
+    /// The fringe holds more than the given number of nodes (and more than
+    /// when the best solution last improved or the dives last stopped)
+    FringeSize(usize),
+    /// The given number of nodes were explored since the best solution last
+    /// improved (or the dives last stopped)
+    Stall(usize),
+}
+
+/// The configuration of the random restarts: once the trigger fires, the
+/// given number of workers dive from the root until the best solution
+/// improves or the fringe gets twice smaller than it was. At least one
+/// worker always keeps exploring the fringe, hence a solver with a single
+/// thread never dives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Restarts {
+    pub trigger: RestartTrigger,
+    /// The number of workers which dive once the trigger fired (1 by default)
+    pub divers: usize,
+}
+impl Restarts {
+    pub fn new(trigger: RestartTrigger) -> Self {
+        Self { trigger, divers: 1 }
+    }
+    /// Sets the number of workers which dive once the trigger fired
+    pub fn with_divers(mut self, divers: usize) -> Self {
+        self.divers = divers;
+        self
+    }
+}
+
+/// What a worker does when it asks for a workload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Role {
+    /// It explores the nodes of the fringe
+    #[default]
+    BranchAndBound,
+    /// It compiles restricted dds from the root
+    Diver,
+}
+
+/// The state of the random restarts, which lives along with the critical data
+/// of the engine
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RestartState {
+    /// Whether the divers currently dive
+    diving: bool,
+    /// The best lower bound when the mode last changed (or improved)
+    best_lb: isize,
+    /// The number of explored nodes at that time
+    explored: usize,
+    /// The size of the fringe at that time
+    fringe: usize,
+    /// The role of each worker
+    roles: Vec<Role>,
+    /// The number of dives which were started
+    dives: usize,
+    /// The number of dives which improved the best solution
+    improving_dives: usize,
+}
+impl RestartState {
+    pub fn new(nb_threads: usize) -> Self {
+        Self { best_lb: isize::MIN, roles: vec![Role::BranchAndBound; nb_threads], ..Default::default() }
+    }
+
+    /// Updates the mode given the current state of the resolution, and returns
+    /// the role the given worker must play. The divers are the workers having
+    /// the highest identifiers.
+    pub fn role(&mut self, restarts: &Restarts, thread_id: usize, best_lb: isize, explored: usize, fringe: usize) -> Role {
+        let improved = best_lb > self.best_lb;
+        let diving = if self.diving {
+            !improved && fringe >= self.fringe / 2
+        } else {
+            !improved && match restarts.trigger {
+                RestartTrigger::FringeSize(size) => fringe > size.max(self.fringe),
+                RestartTrigger::Stall(nodes) => explored - self.explored >= nodes,
+            }
+        };
+        if improved || diving != self.diving {
+            self.diving = diving;
+            self.best_lb = best_lb;
+            self.explored = explored;
+            self.fringe = fringe;
+
+            let nb_threads = self.roles.len();
+            let divers = if diving { restarts.divers.min(nb_threads.saturating_sub(1)) } else { 0 };
+            for (i, role) in self.roles.iter_mut().enumerate() {
+                *role = if i + divers >= nb_threads { Role::Diver } else { Role::BranchAndBound };
+            }
+        }
+        self.roles[thread_id]
+    }
+    /// Counts a new dive and returns its seed
+    pub fn start_dive(&mut self) -> u64 {
+        self.dives += 1;
+        self.dives as u64
+    }
+    /// Records that a dive has improved the best solution
+    pub fn on_improving_dive(&mut self) {
+        self.improving_dives += 1;
+    }
+    pub fn dives(&self) -> usize {
+        self.dives
+    }
+    pub fn improving_dives(&self) -> usize {
+        self.improving_dives
+    }
+}
+
+/// The splitmix64 finalizer: it scatters the bits of consecutive seeds
+pub(crate) fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// The width of a dive: it is drawn between half and all of the given width
+pub(crate) fn dive_width(width: usize, seed: u64) -> usize {
+    let slack = width / 2;
+    width - (mix(seed) % (slack as u64 + 1)) as usize
+}
+
+/// The ranking of the dives: it orders the states randomly (but consistently
+/// for a given seed), and only relies on the ranking of the solver to break
+/// the ties. As the restricted dds keep the nodes having the best value
+/// first, this shuffles the nodes which are worth the same.
+pub(crate) struct Shuffled<'a, O> {
+    ranking: &'a O,
+    seed: u64,
+}
+impl<'a, O> Shuffled<'a, O> {
+    pub fn new(ranking: &'a O, seed: u64) -> Self {
+        Self { ranking, seed: mix(seed) }
+    }
+}
+impl<O> Shuffled<'_, O>
+where
+    O: StateRanking,
+    O::State: Hash,
+{
+    fn key(&self, state: &O::State) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.seed.hash(&mut hasher);
+        state.hash(&mut hasher);
+        mix(hasher.finish())
+    }
+}
+impl<O> StateRanking for Shuffled<'_, O>
+where
+    O: StateRanking,
+    O::State: Hash,
+{
+    type State = O::State;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        self.key(a).cmp(&self.key(b)).then_with(|| self.ranking.compare(a, b))
+    }
+}
+
+#[cfg(test)]
+mod test_restart {
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax};
+    use crate::{BarrierParallelSolver, CutsetType, Fixed, InterruptibleSolver, NoDupFrontier, ParallelSolver, Solver};
+
+    use super::{dive_width, RestartState, RestartTrigger, Restarts, Role};
+
+    #[test]
+    fn the_divers_dive_until_the_best_solution_improves_or_the_fringe_shrinks() {
+        let restarts = Restarts::new(RestartTrigger::FringeSize(10)).with_divers(2);
+        let mut state = RestartState::new(3);
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 2, 0, 5, 10));
+        // the trigger fires, but one worker keeps exploring the fringe
+        assert_eq!(Role::Diver, state.role(&restarts, 2, 0, 6, 20));
+        assert_eq!(Role::Diver, state.role(&restarts, 1, 0, 7, 12));
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 0, 0, 7, 12));
+        // the fringe is only half as large as when the trigger fired
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 2, 0, 8, 9));
+        assert_eq!(Role::Diver, state.role(&restarts, 2, 0, 9, 11));
+        // the best solution improves: the fringe must now grow beyond its size
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 2, 1, 10, 30));
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 2, 1, 11, 30));
+        assert_eq!(Role::Diver, state.role(&restarts, 2, 1, 12, 31));
+
+        let mut alone = RestartState::new(1);
+        assert_eq!(Role::BranchAndBound, alone.role(&restarts, 0, 0, 0, 100));
+        assert_eq!(Role::BranchAndBound, alone.role(&restarts, 0, 0, 1, 200));
+    }
+
+    #[test]
+    fn a_stall_is_counted_from_the_last_improvement() {
+        let restarts = Restarts::new(RestartTrigger::Stall(3));
+        let mut state = RestartState::new(2);
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 1, 5, 2, 1));
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 1, 5, 4, 1));
+        assert_eq!(Role::Diver, state.role(&restarts, 1, 5, 5, 1));
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 1, 6, 6, 1));
+        assert_eq!(Role::BranchAndBound, state.role(&restarts, 1, 6, 8, 1));
+        assert_eq!(Role::Diver, state.role(&restarts, 1, 6, 9, 1));
+    }
+
+    #[test]
+    fn a_dive_keeps_at_least_half_of_the_width() {
+        for width in 1..50 {
+            for seed in 0..20 {
+                let w = dive_width(width, seed);
+                assert!(w <= width && 2 * w >= width, "{} out of {}", w, width);
+            }
+        }
+    }
+
+    #[test]
+    fn the_optimum_is_proved_with_restarts() {
+        let problem = Knapsack::toy();
+        for trigger in [RestartTrigger::FringeSize(0), RestartTrigger::Stall(0), RestartTrigger::Stall(2)] {
+            for nb_threads in [1, 2, 4] {
+                let restarts = Restarts::new(trigger).with_divers(nb_threads);
+                let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+                let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, nb_threads)
+                    .with_restarts(restarts);
+                solver.maximize();
+                assert_eq!(Some(13), solver.best_value());
+                assert_eq!(13, solver.best_upper_bound());
+                if nb_threads == 1 {
+                    assert_eq!(0, solver.get_dives());
+                }
+
+                let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, nb_threads)
+                    .with_restarts(restarts);
+                solver.maximize();
+                assert_eq!(Some(13), solver.best_value());
+                assert_eq!(13, solver.best_upper_bound());
+            }
+        }
+    }
+}
@@ -0,0 +1,711 @@
+//! This module provides the reference implementation of the branch-and-bound:
+//! a solver which runs on the calling thread. It follows the same steps as
+//! the `BarrierParallelSolver` (a restricted, then a relaxed barrier dd is
+//! compiled for each node popped from the fringe), but without any worker nor
+//! critical section. Hence, it neither needs the problem nor its states to be
+//! `Send` or `Sync`.
+
+use std::{hash::Hash, sync::Arc, time::Instant};
+
+
+use crate::{
+    objective::{check_objective, negate}, check_path, Barrier, BarrierStore, Barriers, CancellationToken, CompilationInputBuilder, CompilationType, Cutoff,
+    CutoffStats, CutsetType, Decision, DecisionDiagram, Frontier, InterruptCutoff, InterruptibleSolver, MergePolicy, Negated, NoDupFrontier, OnImprovement,
+    Objective, Problem, PushOutcome, Relaxation, ResolutionStatus, Solver, StateRanking, SubProblem, VerificationError,
+    WidthHeuristic,
+};
+
+pub struct SequentialSolver<'a, P, R, O, W>
+where
+    P: Problem,
+    P::State: Eq + Hash + Clone,
+    O: StateRanking<State = P::State>,
+{
+    problem: &'a P,
+    relaxation: &'a R,
+    ranking: &'a O,
+    width_heu: &'a W,
+    cutset_type: CutsetType,
+    /// Whether the current (or last) resolution maximizes or minimizes the
+    /// objective. All the values known to the solver are those of the
+    /// maximization: they are only negated when they are reported.
+    objective: Objective,
+    /// The number of layers below the root of a subproblem which are never
+    /// merged when compiling a relaxed dd.
+    min_relax_depth_offset: usize,
+    /// Whether the relaxed dds restrict the layers they cannot merge (see
+    /// `CompilationInput::restrict_first_layers`)
+    restrict_first_layers: bool,
+    /// The number of forced layers the dds may collapse below each layer (see
+    /// `CompilationInput::max_forced_layers`)
+    max_forced_layers: usize,
+    /// How the relaxed dds merge their overflowing layers
+    merge_policy: MergePolicy,
+    /// Whether the dds should validate each merge (see `Relaxation::check_merge`)
+    check_merge: bool,
+    /// Whether the best solution is replayed before the optimum is reported
+    /// as proved
+    verification: bool,
+    /// The reason why the best solution of the last resolution failed its
+    /// verification (if it did)
+    verification_error: Option<VerificationError>,
+    /// Lets another thread cancel the resolution
+    cancellation: CancellationToken,
+    /// If set, this is given each better solution of the current resolution
+    /// (see `InterruptibleSolver::maximize_with_cutoff_and_progress`)
+    on_improvement: Option<OnImprovement>,
+
+    /// The nodes which must still be explored, by decreasing upper bound
+    fringe: NoDupFrontier<'a, O>,
+    /// The information about the states that have already been explored at
+    /// each layer of the problem
+    barriers: Barriers<P::State>,
+    /// The number of nodes of the fringe, for each layer of the problem
+    open_by_layer: Vec<usize>,
+    /// The index of the lowest layer above which there are no nodes in the fringe
+    lowest_active_layer: usize,
+    /// The identifier to give to the next subproblem pushed onto the fringe
+    next_id: usize,
+
+    /// The number of nodes which were popped from the fringe and explored
+    explored: usize,
+    explored_dd: usize,
+    best_lb: isize,
+    /// *WARNING* This one only gets set when the resolution is cut off
+    best_ub: isize,
+    /// The upper bound given by the relaxed dd of the root
+    global_ub: isize,
+    best_sol: Option<Vec<Decision>>,
+}
+
+impl<'a, P, R, O, W> SequentialSolver<'a, P, R, O, W>
+where
+    P: Problem,
+    P::State: Eq + Hash + Clone,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+    W: WidthHeuristic<P::State>,
+{
+    pub fn new(problem: &'a P, relaxation: &'a R, ranking: &'a O, width_heu: &'a W, cutset_type: CutsetType) -> Self {
+        let nb_variables = problem.nb_variables();
+        SequentialSolver {
+            problem,
+            relaxation,
+            ranking,
+            width_heu,
+            cutset_type,
+            objective: problem.objective(),
+            min_relax_depth_offset: 1,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: cfg!(debug_assertions),
+            verification: true,
+            verification_error: None,
+            cancellation: CancellationToken::new(),
+            on_improvement: None,
+            fringe: NoDupFrontier::new(ranking),
+            barriers: Arc::new(BarrierStore::new(nb_variables + 1)),
+            open_by_layer: vec![0; nb_variables + 1],
+            lowest_active_layer: 0,
+            next_id: 0,
+            explored: 0,
+            explored_dd: 0,
+            best_lb: isize::MIN,
+            best_ub: isize::MAX,
+            global_ub: isize::MAX,
+            best_sol: None,
+        }
+    }
+    /// Sets the equivalent of `SolverConfig::min_relax_depth_offset` (panics when it is too small)
+    pub fn with_min_relax_depth_offset(mut self, offset: usize) -> Self {
+        let min = self.cutset_type.min_relax_depth_offset();
+        assert!(
+            offset >= min,
+            "min_relax_depth_offset must be at least {} with the {} cutset",
+            min,
+            self.cutset_type
+        );
+        self.min_relax_depth_offset = offset;
+        self
+    }
+    /// Sets the equivalent of `SolverConfig::restrict_first_layers`
+    pub fn with_first_layers_restriction(mut self, restrict: bool) -> Self {
+        self.restrict_first_layers = restrict;
+        self
+    }
+    /// Sets the equivalent of `SolverConfig::max_forced_layers`
+    pub fn with_forced_layers(mut self, max: usize) -> Self {
+        self.max_forced_layers = max;
+        self
+    }
+    /// Sets the equivalent of `SolverConfig::merge_policy`
+    pub fn with_merge_policy(mut self, policy: MergePolicy) -> Self {
+        self.merge_policy = policy;
+        self
+    }
+    /// Sets the equivalent of `SolverConfig::check_merge`
+    pub fn with_merge_check(mut self, check: bool) -> Self {
+        self.check_merge = check;
+        self
+    }
+    /// Sets the equivalent of `SolverConfig::verification`
+    pub fn with_verification(mut self, verify: bool) -> Self {
+        self.verification = verify;
+        self
+    }
+    /// Sets the equivalent of `SolverConfig::cancellation`
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+    /// Returns a handle through which another thread can cancel the
+    /// resolutions of this solver (see `CancellationToken`)
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    pub fn get_explored(&self) -> usize {
+        self.explored
+    }
+
+    pub fn get_explored_dd(&self) -> usize {
+        self.explored_dd
+    }
+
+    /// Returns the reason why the best solution failed its verification
+    /// during the last resolution (if it did)
+    pub fn verification_error(&self) -> Option<VerificationError> {
+        self.verification_error.clone()
+    }
+
+    fn solve<C: Cutoff>(&mut self, objective: Objective, cutoff: C) -> ResolutionStatus {
+        check_objective(self.problem, objective);
+        self.objective = objective;
+        self.verification_error = None;
+        let (problem, relaxation) = (self.problem, self.relaxation);
+        match objective {
+            Objective::Maximize => self.solve_with(problem, relaxation, &cutoff),
+            Objective::Minimize => self.solve_with(&Negated(problem), &Negated(relaxation), &cutoff),
+        }
+    }
+
+    /// Runs the branch-and-bound on the given `problem` and `relaxation`,
+    /// which are either those of the solver or their `Negated` views. The
+    /// `cutoff` is polled before each node is popped from the fringe.
+    fn solve_with<PP, RR, C>(&mut self, problem: &PP, relaxation: &RR, cutoff: &C) -> ResolutionStatus
+    where
+        PP: Problem<State = P::State>,
+        RR: Relaxation<State = P::State>,
+        C: Cutoff,
+    {
+        let mut mdd = Barrier::new(self.barriers.clone(), self.cutset_type);
+        let state = problem.initial_state();
+        if problem.estimate(&state) == isize::MIN {
+            // the estimate of the root proves the problem infeasible
+            self.best_ub = isize::MIN;
+            return ResolutionStatus::Infeasible;
+        }
+        let root = SubProblem {
+            state: Arc::new(state),
+            value: problem.initial_value(),
+            path: vec![],
+            ub: isize::MAX,
+            id: 0,
+            parent: None,
+            prefix: None,
+        };
+        self.push(root, None);
+
+        let start = Instant::now();
+        loop {
+            self.clean_barriers();
+            if self.cancellation.is_cancelled()
+                || cutoff.must_stop(&CutoffStats { elapsed: start.elapsed(), explored: self.explored, explored_dd: self.explored_dd })
+            {
+                // the first node of the fringe bounds all the others
+                self.best_ub = self.fringe.pop().map_or(isize::MIN, |nn| nn.ub);
+                self.discard_fringe();
+                return ResolutionStatus::Interrupted;
+            }
+            let Some(node) = self.fringe.pop() else {
+                break;
+            };
+            if node.ub <= self.best_lb {
+                self.discard_fringe();
+                break;
+            }
+
+            let depth = node.depth();
+            self.open_by_layer[depth] -= 1;
+            // the threshold is only raised (or marked as explored) when the node must be explored
+            if !self.barriers.update_threshold(depth, node.state.clone(), node.value, true) {
+                continue;
+            }
+
+            self.explored += 1;
+            let ub = node.ub;
+            if !self.process_one_node(&mut mdd, problem, relaxation, node) {
+                // the node was cancelled: it bounds all those of the fringe
+                self.best_ub = ub;
+                self.discard_fringe();
+                return ResolutionStatus::Interrupted;
+            }
+        }
+
+        self.best_ub = self.best_lb;
+        match self.verify(problem) {
+            Some(error) => {
+                self.verification_error = Some(error);
+                ResolutionStatus::VerificationFailed
+            }
+            None => ResolutionStatus::Proved,
+        }
+    }
+
+    /// Expands a restricted and possibly a relaxed dd rooted in `node`, and
+    /// pushes the relevant nodes of its cutset onto the fringe (see the
+    /// `process_one_node` of the parallel solvers). It returns false when
+    /// the solver was cancelled during the compilations.
+    fn process_one_node<PP, RR>(&mut self, mdd: &mut Barrier<P::State>, problem: &PP, relaxation: &RR, node: SubProblem<P::State>) -> bool
+    where
+        PP: Problem<State = P::State>,
+        RR: Relaxation<State = P::State>,
+    {
+        // 1. RESTRICTION
+        let node_id = node.id;
+        let node_ub = node.ub;
+        let is_root = node.depth() == 0;
+        let width = self.width_heu.max_width_of(&node);
+        let cancellation = self.cancellation.clone();
+        let mut compilation = CompilationInputBuilder::restricted(problem, relaxation, self.ranking)
+            .max_width(width.max(1))
+            .residual(node)
+            .best_lb(self.best_lb)
+            .min_relax_depth_offset(self.min_relax_depth_offset)
+            .restrict_first_layers(self.restrict_first_layers)
+            .max_forced_layers(self.max_forced_layers)
+            .merge_policy(self.merge_policy)
+            .check_merge(self.check_merge)
+            .cancellation(&cancellation)
+            .build()
+            .expect("the input of a compilation is complete");
+
+        mdd.compile(&compilation);
+        self.explored_dd += mdd.get_explored();
+        if cancellation.is_cancelled() {
+            return false;
+        }
+        self.width_heu.on_restriction(mdd.ran_dry());
+        self.maybe_update_best(mdd);
+        if mdd.is_exact() {
+            if is_root {
+                self.global_ub = self.global_ub.min(mdd.best_value().unwrap_or(self.best_lb));
+            }
+            return true;
+        }
+
+        // 2. RELAXATION
+        compilation.comp_type = CompilationType::Relaxed;
+        compilation.best_lb = self.best_lb;
+        mdd.compile(&compilation);
+        self.explored_dd += mdd.get_explored();
+        if cancellation.is_cancelled() {
+            return false;
+        }
+        if is_root {
+            // the relaxed dd only discards the nodes which cannot beat best_lb
+            self.global_ub = self.global_ub.min(mdd.best_value().unwrap_or(self.best_lb).max(self.best_lb));
+        }
+        if mdd.is_exact() {
+            self.maybe_update_best(mdd);
+        } else {
+            let ub = node_ub.min(self.global_ub);
+            let mut cutset = vec![];
+            mdd.drain_cutset(|mut cutset_node| {
+                cutset_node.ub = ub.min(cutset_node.ub);
+                cutset.push(cutset_node);
+            });
+            for cutset_node in cutset {
+                if cutset_node.ub > self.best_lb {
+                    self.push(cutset_node, Some(node_id));
+                }
+            }
+        }
+        true
+    }
+
+    fn maybe_update_best(&mut self, mdd: &Barrier<P::State>) {
+        let dd_best_value = mdd.best_exact_value().unwrap_or(isize::MIN);
+        if dd_best_value > self.best_lb {
+            self.best_lb = dd_best_value;
+            self.best_sol = mdd.best_exact_solution();
+            if let (Some(on_improvement), Some(solution)) = (self.on_improvement.as_ref(), self.best_sol.as_deref()) {
+                on_improvement(self.reported(dd_best_value), solution);
+            }
+        }
+    }
+
+    /// Pushes the given node onto the fringe, after giving it a fresh identifier
+    fn push(&mut self, mut node: SubProblem<P::State>, parent: Option<usize>) {
+        node.id = self.next_id;
+        node.parent = parent;
+        self.next_id += 1;
+        self.open_by_layer[node.depth()] += 1;
+        if let PushOutcome::Merged(dropped) | PushOutcome::Evicted(dropped) = self.fringe.push(node) {
+            // only one of the two nodes remains on the fringe
+            self.open_by_layer[dropped.depth()] -= 1;
+        }
+    }
+    /// Empties the fringe
+    fn discard_fringe(&mut self) {
+        self.fringe.clear();
+        self.open_by_layer.iter_mut().for_each(|o| *o = 0);
+    }
+    /// Forgets about the layers which no node of the fringe can reach anymore
+    fn clean_barriers(&mut self) {
+        let nb_variables = self.barriers.nb_layers() - 1;
+        while self.lowest_active_layer < nb_variables && self.open_by_layer[self.lowest_active_layer] == 0 {
+            self.barriers.clear_layer(self.lowest_active_layer);
+            self.lowest_active_layer += 1;
+        }
+    }
+
+    /// Replays the best solution (when the verification is enabled) and
+    /// returns the reason why it is not worth the best lower bound, if any
+    fn verify<PP: Problem<State = P::State>>(&self, problem: &PP) -> Option<VerificationError> {
+        let path = self.best_sol.as_deref().filter(|_| self.verification)?;
+        match check_path(problem, path) {
+            Ok(value) if value == self.best_lb => None,
+            Ok(value) => Some(VerificationError::ValueMismatch { reported: self.reported(self.best_lb), replayed: self.reported(value) }),
+            Err(error) => Some(error),
+        }
+    }
+    /// Turns a value of the maximization into a value of the objective
+    fn reported(&self, value: isize) -> isize {
+        match self.objective {
+            Objective::Maximize => value,
+            Objective::Minimize => negate(value),
+        }
+    }
+    fn max_upper_bound(&self) -> isize {
+        self.best_ub.min(self.global_ub)
+    }
+}
+
+impl<P, R, O, W> Solver for SequentialSolver<'_, P, R, O, W>
+where
+    P: Problem,
+    P::State: Eq + Hash + Clone,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+    W: WidthHeuristic<P::State>,
+{
+    /// Applies the branch and bound algorithm proposed by Bergman et al. to
+    /// solve the problem to optimality, on the calling thread.
+    fn maximize(&mut self) {
+        self.solve(Objective::Maximize, InterruptCutoff(|| false));
+    }
+
+    /// Solves the problem like `maximize`, but minimizes its objective.
+    fn minimize(&mut self) {
+        self.solve(Objective::Minimize, InterruptCutoff(|| false));
+    }
+
+    /// Returns the best solution that has been identified for this problem.
+    fn best_solution(&self) -> Option<Vec<Decision>> {
+        self.best_sol.clone()
+    }
+    /// Returns the value of the best solution that has been identified for
+    /// this problem.
+    fn best_value(&self) -> Option<isize> {
+        self.best_sol.as_ref().map(|_sol| self.reported(self.best_lb))
+    }
+}
+
+impl<P, R, O, W> InterruptibleSolver for SequentialSolver<'_, P, R, O, W>
+where
+    P: Problem,
+    P::State: Eq + Hash + Clone,
+    R: Relaxation<State = P::State>,
+    O: StateRanking<State = P::State>,
+    W: WidthHeuristic<P::State>,
+{
+    fn maximize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        self.solve(Objective::Maximize, cutoff)
+    }
+
+    fn minimize_with_cutoff<C>(&mut self, cutoff: C) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+    {
+        self.solve(Objective::Minimize, cutoff)
+    }
+
+    fn maximize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.on_improvement = Some(Box::new(on_improvement));
+        let status = self.solve(Objective::Maximize, cutoff);
+        self.on_improvement = None;
+        status
+    }
+
+    fn minimize_with_cutoff_and_progress<C, F>(&mut self, cutoff: C, on_improvement: F) -> ResolutionStatus
+    where
+        C: Cutoff + Send + Sync + 'static,
+        F: Fn(isize, &[Decision]) + Send + Sync + 'static,
+    {
+        self.on_improvement = Some(Box::new(on_improvement));
+        let status = self.solve(Objective::Minimize, cutoff);
+        self.on_improvement = None;
+        status
+    }
+
+    fn best_value_so_far(&self) -> Option<isize> {
+        self.best_value()
+    }
+
+    fn best_solution_so_far(&self) -> Option<Vec<Decision>> {
+        self.best_solution()
+    }
+
+    fn best_lower_bound(&self) -> isize {
+        match self.objective {
+            Objective::Maximize => self.best_lb,
+            Objective::Minimize => negate(self.max_upper_bound()),
+        }
+    }
+
+    fn best_upper_bound(&self) -> isize {
+        match self.objective {
+            Objective::Maximize => self.max_upper_bound(),
+            Objective::Minimize => negate(self.best_lb),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_sequential {
+    use std::{cmp::Ordering, rc::Rc, sync::{Arc, Mutex}, time::Duration};
+
+    use crate::test_utils::{DepthProbe, Knapsack, KnapsackRanking, KnapsackRelax, KnapsackState, Tweaked};
+    use crate::{
+        BarrierParallelSolver, CancellationToken, CutsetType, Decision, Fixed, InterruptibleSolver, NodeCutoff, Problem, Relaxation,
+        ResolutionStatus, Solver, StateRanking, Variable,
+    };
+
+    use super::SequentialSolver;
+
+    /// The knapsack, with states that cannot be sent to another thread
+    struct RcKnapsack(Knapsack);
+    impl Problem for RcKnapsack {
+        type State = Rc<KnapsackState>;
+
+        fn nb_variables(&self) -> usize {
+            self.0.nb_variables()
+        }
+        fn initial_state(&self) -> Self::State {
+            Rc::new(self.0.initial_state())
+        }
+        fn initial_value(&self) -> isize {
+            self.0.initial_value()
+        }
+        fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+            self.0.next_variable(&mut next_layer.map(|s| s.as_ref()))
+        }
+        fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, f: F)
+        where
+            F: FnMut(Decision),
+        {
+            self.0.for_each_in_domain(var, state, f)
+        }
+        fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+            Rc::new(self.0.transition(state, decision))
+        }
+        fn transition_cost(&self, state: &Self::State, decision: Decision) -> isize {
+            self.0.transition_cost(state, decision)
+        }
+        fn estimate(&self, state: &Self::State) -> isize {
+            self.0.estimate(state)
+        }
+    }
+    struct RcRelax;
+    impl Relaxation for RcRelax {
+        type State = Rc<KnapsackState>;
+
+        fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+            Rc::new(KnapsackRelax.merge(&mut states.map(|s| s.as_ref())))
+        }
+        fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+            cost
+        }
+    }
+    struct RcRanking;
+    impl StateRanking for RcRanking {
+        type State = Rc<KnapsackState>;
+
+        fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+            KnapsackRanking.compare(a, b)
+        }
+    }
+
+    #[test]
+    fn the_optimum_is_found_with_states_which_are_not_send() {
+        let problem = RcKnapsack(Knapsack::toy());
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier, CutsetType::Adaptive] {
+            for width in 1..=3 {
+                let width = Fixed(width);
+                let mut solver = SequentialSolver::new(&problem, &RcRelax, &RcRanking, &width, cutset_type);
+                assert_eq!(ResolutionStatus::Proved, solver.maximize_with_interrupt(|| false));
+                assert_eq!(Some(13), solver.best_value());
+                assert_eq!(13, solver.best_lower_bound());
+                assert_eq!(13, solver.best_upper_bound());
+            }
+        }
+    }
+
+    #[test]
+    fn the_sequential_solver_agrees_with_the_barrier_solver() {
+        let problem = Knapsack::toy();
+        for cutset_type in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+            for minimize in [false, true] {
+                let mut expected = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type, 1);
+                let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), cutset_type);
+                if minimize {
+                    expected.minimize();
+                    solver.minimize();
+                } else {
+                    expected.maximize();
+                    solver.maximize();
+                }
+                assert_eq!(expected.best_value(), solver.best_value());
+                assert_eq!(expected.best_lower_bound(), solver.best_lower_bound());
+                assert_eq!(expected.best_upper_bound(), solver.best_upper_bound());
+                assert_eq!(expected.get_explored(), solver.get_explored());
+            }
+        }
+    }
+
+    #[test]
+    fn the_cutoff_is_polled_between_the_nodes() {
+        let problem = Knapsack::toy();
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer);
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_cutoff(NodeCutoff(1)));
+        assert_eq!(1, solver.get_explored());
+        assert!(solver.best_lower_bound() <= 13 && 13 <= solver.best_upper_bound());
+
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer);
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| true));
+        assert_eq!(0, solver.get_explored());
+        assert_eq!(None, solver.best_value());
+    }
+
+    #[test]
+    fn the_progress_reports_each_better_solution() {
+        let problem = Knapsack::toy();
+        let events = Arc::new(Mutex::new(vec![]));
+        let collected = events.clone();
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer);
+        let status = solver.maximize_with_progress(|| false, move |value, solution: &[Decision]| {
+            collected.lock().unwrap().push((value, solution.to_vec()));
+        });
+        assert_eq!(ResolutionStatus::Proved, status);
+        // the callback is dropped once the resolution is over
+        assert_eq!(1, Arc::strong_count(&events));
+
+        let events = events.lock().unwrap();
+        assert!(events.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(Some(13), events.last().map(|(value, _)| *value));
+        for (value, solution) in events.iter() {
+            assert_eq!(Ok(*value), problem.validate_solution(solution));
+        }
+    }
+
+    #[test]
+    fn the_progress_of_a_minimization_reports_decreasing_costs() {
+        let problem = Tweaked::spare_capacity(Knapsack::toy(), 3);
+        let events = Arc::new(Mutex::new(vec![]));
+        let collected = events.clone();
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer);
+        let status = solver.minimize_with_progress(|| false, move |cost, solution: &[Decision]| {
+            collected.lock().unwrap().push((cost, solution.to_vec()));
+        });
+        assert_eq!(ResolutionStatus::Proved, status);
+
+        let events = events.lock().unwrap();
+        assert!(events.windows(2).all(|pair| pair[0].0 > pair[1].0));
+        assert_eq!(Some(12), events.last().map(|(cost, _)| *cost));
+        for (cost, solution) in events.iter() {
+            assert_eq!(Ok(*cost), problem.validate_solution(solution));
+        }
+    }
+
+    #[test]
+    fn an_infeasible_root_stops_the_resolution_at_once() {
+        let problem = Tweaked::infeasible(Knapsack::toy());
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer);
+        assert_eq!(ResolutionStatus::Infeasible, solver.maximize_with_interrupt(|| false));
+        assert_eq!((None, 0), (solver.best_value(), solver.explored));
+        assert_eq!((isize::MIN, isize::MIN), (solver.best_lower_bound(), solver.best_upper_bound()));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be maximized")]
+    fn a_declared_minimization_cannot_be_maximized() {
+        let problem = Tweaked::spare_capacity(Knapsack::toy(), 3);
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(2), CutsetType::LastExactLayer);
+        solver.minimize();
+        assert_eq!(Some(12), solver.best_value());
+        solver.maximize();
+    }
+
+    #[test]
+    fn a_cancelled_compilation_keeps_the_bound_of_its_node() {
+        let knapsack = Knapsack {
+            capacity: 40,
+            profit: (0..30).map(|i| 1 + (i * 13) % 17).collect(),
+            weight: (0..30).map(|i| 1 + (i * 3) % 7).collect(),
+        };
+        let mut expected = SequentialSolver::new(&knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), CutsetType::LastExactLayer);
+        expected.maximize();
+        let optimum = expected.best_value().unwrap();
+
+        // the root alone takes about a tenth of a second to compile
+        let (problem, _) = Tweaked::slow_estimate(knapsack, Duration::from_millis(1));
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(4), CutsetType::LastExactLayer);
+        let token = solver.cancellation_token();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            token.cancel();
+        });
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| false));
+        assert!(solver.best_lower_bound() <= optimum && optimum <= solver.best_upper_bound());
+        canceller.join().unwrap();
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut solver = SequentialSolver::new(&problem.knapsack, &KnapsackRelax, &KnapsackRanking, &Fixed(4), CutsetType::LastExactLayer)
+            .with_cancellation_token(token);
+        assert_eq!(ResolutionStatus::Interrupted, solver.maximize_with_interrupt(|| false));
+        assert_eq!(0, solver.get_explored());
+    }
+
+    #[test]
+    fn the_width_heuristic_is_given_the_depth_of_each_subproblem() {
+        let problem = Knapsack::toy();
+        let width = DepthProbe::default();
+        let mut solver = SequentialSolver::new(&problem, &KnapsackRelax, &KnapsackRanking, &width, CutsetType::Frontier);
+        solver.maximize();
+        assert_eq!(Some(13), solver.best_value());
+
+        let depths = width.depths.lock().unwrap();
+        assert!(depths.iter().all(|(depth, state_depth)| depth == state_depth));
+        assert!(depths.iter().any(|(depth, _)| *depth > 0));
+    }
+}
@@ -0,0 +1,196 @@
+//! This module defines the record of the branch-and-bound tree explored by
+//! the solvers, which can be exported to Graphviz for post-hoc visualization.
+
+use std::fmt::{Display, Write};
+
+/// What eventually happened to some node of the branch-and-bound tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Disposition {
+    /// The node was closed: one of its dds was exact
+    Proved,
+    /// The node was discarded without being explored because its upper bound
+    /// could not improve the best known solution (or because some other node
+    /// with the same state dominated it)
+    Pruned,
+    /// The relaxed dd of the node was not exact: its cutset spawned children
+    Expanded,
+    /// The node was still waiting on the fringe when the resolution stopped
+    /// (or its dds were being compiled when the resolution was cancelled)
+    Interrupted,
+}
+impl Display for Disposition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Disposition::Proved => write!(f, "proved"),
+            Disposition::Pruned => write!(f, "pruned"),
+            Disposition::Expanded => write!(f, "expanded"),
+            Disposition::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+/// One node of the branch-and-bound tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeNode {
+    /// The identifier of the subproblem (the root is 0)
+    pub id: usize,
+    /// The identifier of the subproblem whose cutset contained this one
+    pub parent: Option<usize>,
+    /// The number of decisions leading to the subproblem
+    pub depth: usize,
+    /// The value of the longest path leading to the subproblem
+    pub value: isize,
+    /// The upper bound of the subproblem when it was pushed onto the fringe
+    pub ub: isize,
+    pub disposition: Disposition,
+}
+
+/// The branch-and-bound tree recorded during a resolution. Its nodes are
+/// ordered by identifier: the parent of a node always comes before it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTree {
+    pub nodes: Vec<TreeNode>,
+}
+impl SearchTree {
+    /// Returns the number of nodes having the given disposition
+    pub fn count(&self, disposition: Disposition) -> usize {
+        self.nodes.iter().filter(|n| n.disposition == disposition).count()
+    }
+
+    /// Writes the tree in the Graphviz dot format, with nodes colored by
+    /// disposition.
+    pub fn write_dot<W: Write>(&self, out: &mut W) -> std::fmt::Result {
+        writeln!(out, "digraph bnb {{")?;
+        writeln!(out, "  node [shape=box, style=filled];")?;
+        for node in self.nodes.iter() {
+            writeln!(out, "  n{} [label=\"#{} ({})\\nvalue: {}\\nub: {}\", fillcolor={}];",
+                node.id, node.id, node.disposition, node.value, bound(node.ub), color(node.disposition))?;
+        }
+        for node in self.nodes.iter() {
+            if let Some(parent) = node.parent {
+                writeln!(out, "  n{} -> n{};", parent, node.id)?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+    /// Returns the tree in the Graphviz dot format
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        self.write_dot(&mut out).unwrap();
+        out
+    }
+}
+
+fn bound(ub: isize) -> String {
+    if ub == isize::MAX {
+        "+inf".to_string()
+    } else {
+        ub.to_string()
+    }
+}
+fn color(disposition: Disposition) -> &'static str {
+    match disposition {
+        Disposition::Proved => "palegreen",
+        Disposition::Pruned => "lightgray",
+        Disposition::Expanded => "lightblue",
+        Disposition::Interrupted => "orange",
+    }
+}
+
+/// The tree being recorded by a solver: the disposition of a node is only
+/// known once it has been dealt with.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TreeRecorder {
+    nodes: Vec<(TreeNode, Option<Disposition>)>,
+}
+impl TreeRecorder {
+    pub fn push(&mut self, id: usize, parent: Option<usize>, depth: usize, value: isize, ub: isize) {
+        debug_assert_eq!(id, self.nodes.len());
+        let node = TreeNode { id, parent, depth, value, ub, disposition: Disposition::Pruned };
+        self.nodes.push((node, None));
+    }
+    pub fn dispose(&mut self, id: usize, disposition: Disposition) {
+        self.nodes[id].1 = Some(disposition);
+    }
+    /// Returns the recorded tree. The nodes which were silently dropped by
+    /// the fringe (because of a duplicate state) are reported as pruned.
+    pub fn tree(&self) -> SearchTree {
+        SearchTree {
+            nodes: self.nodes.iter()
+                .map(|(node, disposition)| TreeNode { disposition: disposition.unwrap_or(Disposition::Pruned), ..*node })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_tree {
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax};
+    use crate::{BarrierParallelSolver, CutsetType, Disposition, Fixed, ParallelSolver, SearchTree, SimpleFrontier, Solver};
+
+    fn check_shape(tree: &SearchTree, explored: usize) {
+        assert_eq!(explored + tree.count(Disposition::Pruned), tree.nodes.len());
+        assert_eq!(explored, tree.count(Disposition::Proved) + tree.count(Disposition::Expanded));
+        assert_eq!(0, tree.count(Disposition::Interrupted));
+        // ids are the positions in the list and parents always come first
+        assert_eq!(None, tree.nodes[0].parent);
+        for (i, node) in tree.nodes.iter().enumerate() {
+            assert_eq!(i, node.id);
+            if let Some(parent) = node.parent {
+                assert!(parent < node.id);
+                assert_eq!(Disposition::Expanded, tree.nodes[parent].disposition);
+                assert!(tree.nodes[parent].depth < node.depth);
+            }
+        }
+    }
+
+    #[test]
+    fn the_parallel_solver_records_the_whole_tree() {
+        let problem = Knapsack::toy();
+        let mut fringe = SimpleFrontier::new(&KnapsackRanking);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 1)
+            .with_tree_recording(true);
+        solver.maximize();
+        assert_eq!(Some(13), solver.best_value());
+
+        let tree = solver.search_tree().unwrap();
+        assert!(tree.nodes.len() > 1);
+        check_shape(&tree, solver.get_explored());
+    }
+
+    #[test]
+    fn the_barrier_solver_records_the_whole_tree() {
+        let problem = Knapsack::toy();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 1)
+            .with_tree_recording(true);
+        solver.maximize();
+        assert_eq!(Some(13), solver.best_value());
+
+        let tree = solver.search_tree().unwrap();
+        assert!(tree.nodes.len() > 1);
+        check_shape(&tree, solver.get_explored());
+    }
+
+    #[test]
+    fn nothing_is_recorded_by_default() {
+        let problem = Knapsack::toy();
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::Frontier, 1);
+        solver.maximize();
+        assert_eq!(None, solver.search_tree());
+    }
+
+    #[test]
+    fn the_dot_export_has_one_edge_per_child() {
+        let problem = Knapsack::toy();
+        let mut fringe = SimpleFrontier::new(&KnapsackRanking);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut fringe, 1)
+            .with_tree_recording(true);
+        solver.maximize();
+
+        let tree = solver.search_tree().unwrap();
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph bnb {"));
+        assert!(dot.contains("n0 [label=\"#0 (expanded)\\nvalue: 0\\nub: +inf\", fillcolor=lightblue];"));
+        assert_eq!(tree.nodes.len() - 1, dot.matches(" -> ").count());
+    }
+}
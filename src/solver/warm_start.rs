@@ -0,0 +1,48 @@
+//! This module implements the budget of the warm start of the solvers. Before
+//! the branch-and-bound starts, the warm start compiles restricted dds of the
+//! whole problem whose width doubles from one compilation to the next, so that
+//! the first nodes of the branch-and-bound are pruned with a good lower bound
+//! rather than with the one of the (narrow) restricted dd of the root.
+
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+/// How much effort the warm start can spend. The budget is checked before
+/// each compilation: the last one may hence exceed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmStartBudget {
+    /// The total number of dd nodes which may be expanded
+    Nodes(usize),
+    /// The time which may be spent
+    Time(Duration),
+}
+impl WarmStartBudget {
+    /// Returns true iff the warm start must stop, given the number of dd nodes
+    /// it expanded and the time it spent so far
+    pub(crate) fn is_spent(&self, explored_dd: usize, elapsed: Duration) -> bool {
+        match *self {
+            Self::Nodes(nodes) => explored_dd >= nodes,
+            Self::Time(time) => elapsed >= time,
+        }
+    }
+}
+impl FromStr for WarmStartBudget {
+    type Err = &'static str;
+
+    /// Parses a number of dd nodes (e.g. `100000`) or a number of seconds
+    /// (e.g. `5s`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = "A warm start budget is either a number of dd nodes (e.g. '100000') or of seconds (e.g. '5s')";
+        match s.strip_suffix('s') {
+            Some(secs) => secs.parse().map(|secs| Self::Time(Duration::from_secs(secs))).map_err(|_| invalid),
+            None => s.parse().map(Self::Nodes).map_err(|_| invalid),
+        }
+    }
+}
+impl Display for WarmStartBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Nodes(nodes) => write!(f, "{}", nodes),
+            Self::Time(time) => write!(f, "{}s", time.as_secs()),
+        }
+    }
+}
@@ -0,0 +1,764 @@
+//! This module defines a tiny 0/1 knapsack model which is only meant to be
+//! used by the unit tests of the crate.
+
+use std::{cmp::Ordering, sync::{atomic::{AtomicIsize, AtomicUsize, Ordering as AtomicOrdering}, Arc, Mutex}, thread, time::Duration};
+
+use crate::{AssignedVars, CancellationToken, CompilationInput, CompilationInputBuilder, CompilationType, DecisionDiagram, Decision, DepthDecreasing, MergePolicy, Objective, Problem, Relaxation, StateRanking, SubProblem, Variable, WidthHeuristic};
+
+/// The state of the knapsack: the number of items which have already been
+/// considered and the remaining capacity.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KnapsackState {
+    pub depth: usize,
+    pub capacity: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Knapsack {
+    pub capacity: usize,
+    pub profit: Vec<usize>,
+    pub weight: Vec<usize>,
+}
+impl Knapsack {
+    /// A small instance whose optimal value is 13 (items 0, 3 and 4)
+    pub fn toy() -> Self {
+        Knapsack {
+            capacity: 10,
+            profit: vec![5, 3, 4, 6, 2, 1],
+            weight: vec![4, 3, 5, 4, 2, 3],
+        }
+    }
+    /// An instance whose optimum is 8. When the nodes which cannot beat 7
+    /// are pruned, its frontier cutset is smaller than its last exact layer.
+    pub fn funnel() -> Self {
+        Knapsack { capacity: 6, profit: vec![4, 3, 3, 5, 1], weight: vec![4, 2, 5, 4, 2] }
+    }
+}
+impl Problem for Knapsack {
+    type State = KnapsackState;
+
+    fn nb_variables(&self) -> usize {
+        self.profit.len()
+    }
+
+    fn initial_state(&self) -> Self::State {
+        KnapsackState { depth: 0, capacity: self.capacity }
+    }
+
+    fn initial_value(&self) -> isize {
+        0
+    }
+
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        next_layer
+            .next()
+            .map(|s| s.depth)
+            .filter(|depth| *depth < self.nb_variables())
+            .map(Variable)
+    }
+
+    fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        f(Decision { var, value: 0 });
+        if self.weight[var.id()] <= state.capacity {
+            f(Decision { var, value: 1 });
+        }
+    }
+
+    fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        let taken = decision.value as usize;
+        KnapsackState {
+            depth: state.depth + 1,
+            capacity: state.capacity - taken * self.weight[decision.var.id()],
+        }
+    }
+
+    fn transition_cost(&self, _state: &Self::State, decision: Decision) -> isize {
+        decision.value * self.profit[decision.var.id()] as isize
+    }
+
+    fn estimate(&self, state: &Self::State) -> isize {
+        self.profit[state.depth..].iter().sum::<usize>() as isize
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KnapsackRelax;
+impl Relaxation for KnapsackRelax {
+    type State = KnapsackState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        let mut depth = 0;
+        let mut capacity = 0;
+        for state in states {
+            depth = depth.max(state.depth);
+            capacity = capacity.max(state.capacity);
+        }
+        KnapsackState { depth, capacity }
+    }
+
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        cost
+    }
+
+    fn check_merge(&self, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        check_knapsack_merge(merged, originals)
+    }
+}
+
+/// A buggy relaxation: merging keeps the smallest capacity, hence it may
+/// prune the optimal solution away
+#[derive(Debug, Clone, Copy)]
+pub struct KnapsackBrokenRelax;
+impl Relaxation for KnapsackBrokenRelax {
+    type State = KnapsackState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        let mut depth = 0;
+        let mut capacity = usize::MAX;
+        for state in states {
+            depth = depth.max(state.depth);
+            capacity = capacity.min(state.capacity);
+        }
+        KnapsackState { depth, capacity }
+    }
+
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        cost
+    }
+
+    fn check_merge(&self, merged: &Self::State, originals: &[&Self::State]) -> Result<(), String> {
+        check_knapsack_merge(merged, originals)
+    }
+}
+
+/// A buggy relaxation: the arcs it redirects towards a merged node forget
+/// their profit, hence the relaxed bound may be smaller than the optimum
+#[derive(Debug, Clone, Copy)]
+pub struct KnapsackStingyRelax;
+impl Relaxation for KnapsackStingyRelax {
+    type State = KnapsackState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        KnapsackRelax.merge(states)
+    }
+
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, _: isize) -> isize {
+        0
+    }
+}
+
+/// A relaxation which merges the states into the one of the given capacity.
+/// When that state is kept in the layer, the merged node recycles its node.
+/// Such a merge is not a valid relaxation in general: it may only be used to
+/// check how the dds build the merged nodes.
+#[derive(Debug, Clone, Copy)]
+pub struct KnapsackMergeInto(pub usize);
+impl Relaxation for KnapsackMergeInto {
+    type State = KnapsackState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        let depth = states.map(|state| state.depth).max().unwrap_or(0);
+        KnapsackState { depth, capacity: self.0 }
+    }
+
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        cost
+    }
+}
+
+/// The depth, capacity, value and upper bound of a subproblem
+pub type Drained = (usize, usize, isize, isize);
+
+/// Compiles the relaxed dd of the knapsack with the given width and best
+/// value, and returns the subproblems of its cutset (sorted)
+pub fn drained<D>(mdd: &mut D, problem: &Knapsack, max_width: usize, best_lb: isize) -> Vec<Drained>
+where
+    D: DecisionDiagram<State = KnapsackState>,
+{
+    let input = CompilationInputBuilder::relaxed(problem, &KnapsackRelax, &KnapsackRanking)
+        .root()
+        .max_width(max_width)
+        .best_lb(best_lb)
+        .check_merge(true)
+        .build()
+        .unwrap();
+    mdd.compile(&input);
+    let mut cutset = vec![];
+    mdd.drain_cutset(|n| cutset.push((n.state.depth, n.state.capacity, n.value, n.ub)));
+    cutset.sort_unstable();
+    cutset
+}
+
+/// A relaxation whose relaxed costs take (at least) the given delay, and
+/// which counts them
+#[derive(Debug, Clone, Default)]
+pub struct KnapsackSlowRelax {
+    pub delay: Duration,
+    pub calls: Arc<AtomicUsize>,
+}
+impl Relaxation for KnapsackSlowRelax {
+    type State = KnapsackState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        KnapsackRelax.merge(states)
+    }
+
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        self.calls.fetch_add(1, AtomicOrdering::Relaxed);
+        thread::sleep(self.delay);
+        cost
+    }
+}
+
+/// The merged capacity may not be smaller than that of any merged state
+fn check_knapsack_merge(merged: &KnapsackState, originals: &[&KnapsackState]) -> Result<(), String> {
+    match originals.iter().find(|s| s.capacity > merged.capacity) {
+        Some(s) => Err(format!("merged capacity of {:?} is smaller than that of {:?}", merged, s)),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KnapsackRanking;
+impl StateRanking for KnapsackRanking {
+    type State = KnapsackState;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        a.capacity.cmp(&b.capacity)
+    }
+}
+
+type NextVariable = Arc<dyn Fn(&Knapsack, &AssignedVars, &mut dyn Iterator<Item = &KnapsackState>) -> Option<Variable> + Send + Sync>;
+type Domain = Arc<dyn Fn(&Knapsack, Variable, &KnapsackState, &mut dyn FnMut(Decision)) + Send + Sync>;
+type DomainSizeHint = Arc<dyn Fn(&Knapsack, Variable, &KnapsackState) -> Option<usize> + Send + Sync>;
+type Transition = Arc<dyn Fn(&Knapsack, &KnapsackState, Decision) -> KnapsackState + Send + Sync>;
+type TransitionCost = Arc<dyn Fn(&Knapsack, &KnapsackState, Decision) -> isize + Send + Sync>;
+type StateValue = Arc<dyn Fn(&Knapsack, &KnapsackState) -> isize + Send + Sync>;
+type PathEstimate = Arc<dyn Fn(&Knapsack, &KnapsackState, &[Decision]) -> isize + Send + Sync>;
+/// The states which a `Tweaked::path_probe` estimated, with their best paths
+pub type EstimatedPaths = Arc<Mutex<Vec<(KnapsackState, Vec<Decision>)>>>;
+
+/// A knapsack whose methods can be overridden one by one: each override which
+/// is set replaces the method of the knapsack (it is given the knapsack along
+/// with the arguments of the method), which is forwarded otherwise. The
+/// constructors below build the variants which the tests share; those which
+/// record what the dds ask return the record along with the problem.
+#[derive(Clone)]
+pub struct Tweaked {
+    pub knapsack: Knapsack,
+    objective: Objective,
+    next_variable: Option<NextVariable>,
+    domain: Option<Domain>,
+    domain_size_hint: Option<DomainSizeHint>,
+    transition: Option<Transition>,
+    transition_cost: Option<TransitionCost>,
+    terminal_value: Option<StateValue>,
+    estimate: Option<StateValue>,
+    estimate_from_path: Option<PathEstimate>,
+}
+impl std::fmt::Debug for Tweaked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tweaked").field("knapsack", &self.knapsack).field("objective", &self.objective).finish_non_exhaustive()
+    }
+}
+impl Tweaked {
+    /// A knapsack which forwards every method
+    pub fn new(knapsack: Knapsack) -> Self {
+        Self {
+            knapsack,
+            objective: Objective::Maximize,
+            next_variable: None,
+            domain: None,
+            domain_size_hint: None,
+            transition: None,
+            transition_cost: None,
+            terminal_value: None,
+            estimate: None,
+            estimate_from_path: None,
+        }
+    }
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objective = objective;
+        self
+    }
+    /// Overrides `next_variable_with_context` (and thus `next_variable`)
+    pub fn with_next_variable<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, &AssignedVars, &mut dyn Iterator<Item = &KnapsackState>) -> Option<Variable> + Send + Sync + 'static,
+    {
+        self.next_variable = Some(Arc::new(f));
+        self
+    }
+    pub fn with_domain<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, Variable, &KnapsackState, &mut dyn FnMut(Decision)) + Send + Sync + 'static,
+    {
+        self.domain = Some(Arc::new(f));
+        self
+    }
+    pub fn with_domain_size_hint<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, Variable, &KnapsackState) -> Option<usize> + Send + Sync + 'static,
+    {
+        self.domain_size_hint = Some(Arc::new(f));
+        self
+    }
+    pub fn with_transition<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, &KnapsackState, Decision) -> KnapsackState + Send + Sync + 'static,
+    {
+        self.transition = Some(Arc::new(f));
+        self
+    }
+    pub fn with_transition_cost<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, &KnapsackState, Decision) -> isize + Send + Sync + 'static,
+    {
+        self.transition_cost = Some(Arc::new(f));
+        self
+    }
+    pub fn with_terminal_value<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, &KnapsackState) -> isize + Send + Sync + 'static,
+    {
+        self.terminal_value = Some(Arc::new(f));
+        self
+    }
+    pub fn with_estimate<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, &KnapsackState) -> isize + Send + Sync + 'static,
+    {
+        self.estimate = Some(Arc::new(f));
+        self
+    }
+    /// Overrides `estimate_from_path`, which the problem then asks for (see
+    /// `Problem::estimates_from_path`)
+    pub fn with_estimate_from_path<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Knapsack, &KnapsackState, &[Decision]) -> isize + Send + Sync + 'static,
+    {
+        self.estimate_from_path = Some(Arc::new(f));
+        self
+    }
+
+    /// A knapsack whose estimate deems every state infeasible, the root included
+    pub fn infeasible(knapsack: Knapsack) -> Self {
+        Self::new(knapsack).with_estimate(|_, _| isize::MIN)
+    }
+    /// A knapsack with a buggy estimate: it only counts the most profitable of
+    /// the remaining items
+    pub fn short_estimate(knapsack: Knapsack) -> Self {
+        Self::new(knapsack).with_estimate(|knapsack, state| knapsack.profit[state.depth..].iter().copied().max().unwrap_or(0) as isize)
+    }
+    /// A knapsack which tells the dds how many decisions each of its domains holds
+    pub fn hinted(knapsack: Knapsack) -> Self {
+        Self::new(knapsack).with_domain_size_hint(|knapsack, var, state| Some(1 + usize::from(knapsack.weight[var.id()] <= state.capacity)))
+    }
+    /// A knapsack whose solutions all earn a `bonus` once the last item was
+    /// considered (see `Problem::terminal_value`): the optimum of the toy
+    /// instance is 13 plus that bonus.
+    pub fn bonus(knapsack: Knapsack, bonus: isize) -> Self {
+        Self::new(knapsack)
+            .with_terminal_value(move |_, _| bonus)
+            .with_estimate(move |knapsack, state| knapsack.estimate(state) + bonus)
+    }
+    /// A knapsack which cancels the given token as soon as a dd makes a
+    /// transition: the first compilation of a resolution which uses that token
+    /// is cancelled
+    pub fn cancelling(knapsack: Knapsack, token: CancellationToken) -> Self {
+        Self::new(knapsack).with_transition(move |knapsack, state, decision| {
+            token.cancel();
+            knapsack.transition(state, decision)
+        })
+    }
+    /// The minimization counterpart of the knapsack: each item which is left out
+    /// costs its profit. The optimum of the toy instance is 8 (the 21 units of
+    /// profit of all the items minus the 13 which fit in the knapsack). When it
+    /// is `estimated`, the cost of the remaining items is bounded by 0; otherwise
+    /// the problem has no estimate.
+    pub fn lost_profit(knapsack: Knapsack, estimated: bool) -> Self {
+        Self::new(knapsack)
+            .with_transition_cost(|knapsack, _, decision| (1 - decision.value) * knapsack.profit[decision.var.id()] as isize)
+            .with_estimate(move |_, _| if estimated { 0 } else { isize::MAX })
+    }
+    /// A minimization in which every decision costs something: each item costs 1
+    /// to handle, plus its profit when it is left out. The optimum of the toy
+    /// instance is 14, and the remaining items cost at least 1 each. When it is
+    /// `negated`, the problem maximizes the negation of its costs (as it should);
+    /// otherwise it states its costs as they are but is maximized all the same.
+    pub fn handling(knapsack: Knapsack, negated: bool) -> Self {
+        let sign = if negated { -1 } else { 1 };
+        Self::new(knapsack)
+            .with_transition_cost(move |knapsack, _, decision| sign * (1 + (1 - decision.value) * knapsack.profit[decision.var.id()] as isize))
+            .with_estimate(move |knapsack, state| sign * (knapsack.nb_variables() - state.depth) as isize)
+    }
+    /// The lost profit of a knapsack which must keep some spare capacity, stated
+    /// as a minimization (see `Problem::objective`). The items which no longer
+    /// fit are lost for sure, and a state which does not keep the spare capacity
+    /// is infeasible: its estimate is `isize::MAX`. The optimum of the toy
+    /// instance with 3 units of spare capacity is 12.
+    pub fn spare_capacity(knapsack: Knapsack, spare: usize) -> Self {
+        Self::lost_profit(knapsack, true)
+            .with_objective(Objective::Minimize)
+            .with_domain(move |knapsack, var, state, f| {
+                if state.capacity >= knapsack.weight[var.id()] + spare {
+                    f(Decision { var, value: 1 });
+                }
+                f(Decision { var, value: 0 });
+            })
+            .with_estimate(move |knapsack, state| {
+                if state.capacity < spare {
+                    return isize::MAX;
+                }
+                (state.depth..knapsack.nb_variables())
+                    .filter(|i| knapsack.weight[*i] + spare > state.capacity)
+                    .map(|i| knapsack.profit[i] as isize)
+                    .sum()
+            })
+    }
+    /// A knapsack which records the assigned variables it is given each time a
+    /// dd asks for the next variable
+    pub fn context_probe(knapsack: Knapsack) -> (Self, Arc<Mutex<Vec<Vec<usize>>>>) {
+        let observed = Arc::new(Mutex::new(vec![]));
+        let record = observed.clone();
+        let problem = Self::new(knapsack).with_next_variable(move |knapsack, assigned, next_layer| {
+            record.lock().unwrap().push(assigned.iter().map(|v| v.id()).collect());
+            knapsack.next_variable(next_layer)
+        });
+        (problem, observed)
+    }
+    /// A knapsack whose estimate takes (at least) the given delay, along with
+    /// the number of times it has been computed
+    pub fn slow_estimate(knapsack: Knapsack, delay: Duration) -> (Self, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let count = calls.clone();
+        let problem = Self::new(knapsack).with_estimate(move |knapsack, state| {
+            count.fetch_add(1, AtomicOrdering::Relaxed);
+            thread::sleep(delay);
+            knapsack.estimate(state)
+        });
+        (problem, calls)
+    }
+    /// A knapsack which records the paths along which its nodes are estimated,
+    /// when it asks to estimate them from their paths at all
+    pub fn path_probe(knapsack: Knapsack, from_path: bool) -> (Self, EstimatedPaths) {
+        let observed = Arc::new(Mutex::new(vec![]));
+        let record = observed.clone();
+        let mut problem = Self::new(knapsack);
+        if from_path {
+            problem = problem.with_estimate_from_path(move |knapsack, state, path| {
+                record.lock().unwrap().push((state.clone(), path.to_vec()));
+                knapsack.estimate(state)
+            });
+        }
+        (problem, observed)
+    }
+    /// A knapsack whose incumbent is improved by another thread as soon as a dd
+    /// reaches the given depth: the mirror of that incumbent (see
+    /// `CompilationInput::incumbent`) then holds `value`
+    pub fn improving(knapsack: Knapsack, depth: usize, value: isize) -> (Self, Arc<AtomicIsize>) {
+        let incumbent = Arc::new(AtomicIsize::new(isize::MIN));
+        let improved = incumbent.clone();
+        let problem = Self::new(knapsack).with_next_variable(move |knapsack, _, next_layer| {
+            let mut next_layer = next_layer.peekable();
+            if next_layer.peek().is_some_and(|state| state.depth == depth) {
+                thread::scope(|s| {
+                    s.spawn(|| improved.fetch_max(value, AtomicOrdering::Relaxed));
+                });
+            }
+            knapsack.next_variable(&mut next_layer)
+        });
+        (problem, incumbent)
+    }
+
+    /// Returns the input needed to compile the subproblem reached by taking
+    /// the given decisions from the root
+    pub fn input(&self, comp_type: CompilationType, path: Vec<Decision>) -> CompilationInput<'_, Tweaked, KnapsackRelax, KnapsackRanking> {
+        let mut state = self.initial_state();
+        let mut value = self.initial_value();
+        for d in path.iter() {
+            value += self.transition_cost(&state, *d);
+            state = self.transition(&state, *d);
+        }
+        CompilationInput {
+            comp_type,
+            max_width: 2,
+            problem: self,
+            relaxation: &KnapsackRelax,
+            ranking: &KnapsackRanking,
+            assigned: AssignedVars::from_path(self.nb_variables(), &path),
+            residual: SubProblem { state: Arc::new(state), value, path, ub: isize::MAX, id: 0, parent: None, prefix: None },
+            best_lb: isize::MIN,
+            min_relax_depth_offset: 1,
+            restrict_first_layers: false,
+            max_forced_layers: 0,
+            dominance: None,
+            reuse_prefix: false,
+            merge_policy: MergePolicy::SingleNode,
+            check_merge: true,
+            timed: false,
+            self_check: false,
+            cancellation: None,
+            incumbent: None,
+        }
+    }
+
+    /// Returns the state reached by taking the given decisions from the root
+    pub fn replay(&self, path: &[Decision]) -> KnapsackState {
+        path.iter().fold(self.initial_state(), |state, d| self.transition(&state, *d))
+    }
+}
+impl Problem for Tweaked {
+    type State = KnapsackState;
+
+    fn nb_variables(&self) -> usize {
+        self.knapsack.nb_variables()
+    }
+    fn initial_state(&self) -> Self::State {
+        self.knapsack.initial_state()
+    }
+    fn initial_value(&self) -> isize {
+        self.knapsack.initial_value()
+    }
+    fn objective(&self) -> Objective {
+        self.objective
+    }
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        self.knapsack.next_variable(next_layer)
+    }
+    fn next_variable_with_context(&self, assigned: &AssignedVars, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        match self.next_variable.as_ref() {
+            Some(next_variable) => next_variable(&self.knapsack, assigned, next_layer),
+            None => self.knapsack.next_variable(next_layer),
+        }
+    }
+    fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        match self.domain.as_ref() {
+            Some(domain) => domain(&self.knapsack, var, state, &mut f),
+            None => self.knapsack.for_each_in_domain(var, state, f),
+        }
+    }
+    fn domain_size_hint(&self, var: Variable, state: &Self::State) -> Option<usize> {
+        match self.domain_size_hint.as_ref() {
+            Some(hint) => hint(&self.knapsack, var, state),
+            None => self.knapsack.domain_size_hint(var, state),
+        }
+    }
+    fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        match self.transition.as_ref() {
+            Some(transition) => transition(&self.knapsack, state, decision),
+            None => self.knapsack.transition(state, decision),
+        }
+    }
+    fn transition_cost(&self, state: &Self::State, decision: Decision) -> isize {
+        match self.transition_cost.as_ref() {
+            Some(cost) => cost(&self.knapsack, state, decision),
+            None => self.knapsack.transition_cost(state, decision),
+        }
+    }
+    fn terminal_value(&self, state: &Self::State) -> isize {
+        match self.terminal_value.as_ref() {
+            Some(value) => value(&self.knapsack, state),
+            None => self.knapsack.terminal_value(state),
+        }
+    }
+    fn estimate(&self, state: &Self::State) -> isize {
+        match self.estimate.as_ref() {
+            Some(estimate) => estimate(&self.knapsack, state),
+            None => self.knapsack.estimate(state),
+        }
+    }
+    fn estimate_from_path(&self, state: &Self::State, path: &[Decision]) -> isize {
+        match self.estimate_from_path.as_ref() {
+            Some(estimate) => estimate(&self.knapsack, state, path),
+            None => self.estimate(state),
+        }
+    }
+    fn estimates_from_path(&self) -> bool {
+        self.estimate_from_path.is_some()
+    }
+}
+
+/// A knapsack whose states are only made of the remaining capacity: the item
+/// to consider next is the first one which is not assigned yet. Leaving out
+/// an item keeps the state unchanged, hence the same state can be reached at
+/// several depths (like in a diamond-shaped DP).
+#[derive(Debug, Clone)]
+pub struct Diamond(pub Knapsack);
+impl Problem for Diamond {
+    type State = usize;
+
+    fn nb_variables(&self) -> usize {
+        self.0.nb_variables()
+    }
+    fn initial_state(&self) -> Self::State {
+        self.0.capacity
+    }
+    fn initial_value(&self) -> isize {
+        0
+    }
+    fn next_variable(&self, _next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        unreachable!("the next item depends on the assigned variables")
+    }
+    fn next_variable_with_context(&self, assigned: &AssignedVars, _next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        (0..self.nb_variables()).map(Variable).find(|var| !assigned.contains(*var))
+    }
+    fn for_each_in_domain<F>(&self, var: Variable, capacity: &Self::State, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        f(Decision { var, value: 0 });
+        if self.0.weight[var.id()] <= *capacity {
+            f(Decision { var, value: 1 });
+        }
+    }
+    fn transition(&self, capacity: &Self::State, decision: Decision) -> Self::State {
+        capacity - decision.value as usize * self.0.weight[decision.var.id()]
+    }
+    fn transition_cost(&self, _capacity: &Self::State, decision: Decision) -> isize {
+        decision.value * self.0.profit[decision.var.id()] as isize
+    }
+}
+
+/// The relaxation and the ranking of the `Diamond` states
+#[derive(Debug, Clone, Copy)]
+pub struct DiamondRelax;
+impl Relaxation for DiamondRelax {
+    type State = usize;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        states.copied().max().unwrap_or(0)
+    }
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        cost
+    }
+}
+impl StateRanking for DiamondRelax {
+    type State = usize;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+/// The state of a `Chained` knapsack: the number of items which have already
+/// been considered, the remaining capacity, and whether the items of the
+/// current chain are taken (none when a merge mixed both).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChainState {
+    pub depth: usize,
+    pub capacity: usize,
+    pub taken: Option<bool>,
+}
+
+/// A knapsack whose items come in chains of `length` items: only the first
+/// item of a chain is a choice, the others are taken iff it was. Every layer
+/// but the first of each chain is thus made of forced decisions.
+#[derive(Debug, Clone)]
+pub struct Chained {
+    pub knapsack: Knapsack,
+    pub length: usize,
+}
+impl Chained {
+    /// The toy knapsack in chains of two items, whose optimal value is 10
+    /// (items 2 and 3)
+    pub fn toy() -> Self {
+        Chained { knapsack: Knapsack::toy(), length: 2 }
+    }
+}
+impl Problem for Chained {
+    type State = ChainState;
+
+    fn nb_variables(&self) -> usize {
+        self.knapsack.nb_variables()
+    }
+    fn initial_state(&self) -> Self::State {
+        ChainState { depth: 0, capacity: self.knapsack.capacity, taken: None }
+    }
+    fn initial_value(&self) -> isize {
+        0
+    }
+    fn next_variable(&self, next_layer: &mut dyn Iterator<Item = &Self::State>) -> Option<Variable> {
+        next_layer.next().map(|s| s.depth).filter(|d| *d < self.nb_variables()).map(Variable)
+    }
+    fn for_each_in_domain<F>(&self, var: Variable, state: &Self::State, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        let fits = self.knapsack.weight[var.id()] <= state.capacity;
+        match state.taken {
+            Some(taken) if !var.id().is_multiple_of(self.length) => {
+                if !taken || fits {
+                    f(Decision { var, value: taken as isize });
+                }
+            }
+            _ => {
+                f(Decision { var, value: 0 });
+                if fits {
+                    f(Decision { var, value: 1 });
+                }
+            }
+        }
+    }
+    fn transition(&self, state: &Self::State, decision: Decision) -> Self::State {
+        ChainState {
+            depth: state.depth + 1,
+            capacity: state.capacity - decision.value as usize * self.knapsack.weight[decision.var.id()],
+            taken: Some(decision.value == 1),
+        }
+    }
+    fn transition_cost(&self, _state: &Self::State, decision: Decision) -> isize {
+        decision.value * self.knapsack.profit[decision.var.id()] as isize
+    }
+}
+
+/// The relaxation and the ranking of the `Chained` states: a merged state
+/// keeps the greatest capacity, and lets its chain be taken or not
+#[derive(Debug, Clone, Copy)]
+pub struct ChainedRelax;
+impl Relaxation for ChainedRelax {
+    type State = ChainState;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &Self::State>) -> Self::State {
+        let first = states.next().expect("a merged group is not empty").clone();
+        states.fold(first, |merged, s| ChainState {
+            depth: merged.depth,
+            capacity: merged.capacity.max(s.capacity),
+            taken: merged.taken.filter(|t| s.taken == Some(*t)),
+        })
+    }
+    fn relax(&self, _: &Self::State, _: &Self::State, _: &Self::State, _: Decision, cost: isize) -> isize {
+        cost
+    }
+}
+impl StateRanking for ChainedRelax {
+    type State = ChainState;
+
+    fn compare(&self, a: &Self::State, b: &Self::State) -> Ordering {
+        a.capacity.cmp(&b.capacity)
+    }
+}
+
+/// A width heuristic which records the depth of each subproblem it is asked a
+/// width for, along with the depth of its knapsack state, and which gives
+/// them the width of a `DepthDecreasing` heuristic
+#[derive(Debug, Default)]
+pub struct DepthProbe {
+    pub depths: Mutex<Vec<(usize, usize)>>,
+}
+impl WidthHeuristic<KnapsackState> for DepthProbe {
+    fn max_width(&self, state: &KnapsackState) -> usize {
+        DepthDecreasing { base: 2, factor: 1 }.max_width(state)
+    }
+    fn max_width_of(&self, sub: &SubProblem<KnapsackState>) -> usize {
+        self.depths.lock().unwrap().push((sub.depth(), sub.state.depth));
+        DepthDecreasing { base: 2, factor: 1 }.max_width_of(sub)
+    }
+}
@@ -38,7 +38,7 @@ pub struct BitSetIter<'a> {
 impl BitSetIter<'_> {
     /// This method creates an iterator for the given bitset from an immutable
     /// reference to that bitset.
-    pub fn new(bs: &BitSet) -> BitSetIter {
+    pub fn new(bs: &BitSet) -> BitSetIter<'_> {
         let mut iter = bs.buffer().iter().cloned();
         let word = iter.next();
         BitSetIter {iter, word, base: 0, offset: 0}
@@ -166,7 +166,7 @@ impl PartialEq for LexBitSet<'_> {
 ///
 /// # Example
 /// ```
-/// # use ddo::Matrix;
+/// # use engineering::Matrix;
 ///
 /// let mut adjacency = Matrix::new_default(5, 5, None);
 ///
@@ -216,4 +216,64 @@ impl <T> IndexMut<(usize, usize)> for Matrix<T> {
         let position = self.pos(idx);
         &mut self.data[position]
     }
-}
\ No newline at end of file
+}
+
+/// Returns the relative gap between the bounds `lb` and `ub` of a resolution,
+/// that is `(ub - lb) / max(|lb|, |ub|, 1)`: the usual gap of the MIP solvers,
+/// which is meaningful whatever the signs of the bounds. It is 0 when the
+/// bounds meet (or cross), and 1 when one of them is missing (`isize::MIN`
+/// for `lb`, `isize::MAX` for `ub`).
+pub fn optimality_gap(lb: isize, ub: isize) -> f64 {
+    if lb == isize::MIN || ub == isize::MAX {
+        return 1.0;
+    }
+    if lb >= ub {
+        return 0.0;
+    }
+    let scale = lb.unsigned_abs().max(ub.unsigned_abs()).max(1);
+    (ub as i128 - lb as i128) as f64 / scale as f64
+}
+
+/// Runs the given action when it is dropped while the current thread is
+/// panicking. The solvers use it to wake up the other workers when one of them
+/// panics: these would otherwise wait forever for it to finish its subproblem.
+pub(crate) struct OnPanic<F: FnMut()>(pub F);
+impl<F: FnMut()> Drop for OnPanic<F> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            (self.0)()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_gap {
+    use super::optimality_gap;
+
+    #[test]
+    fn the_gap_is_relative_to_the_largest_bound() {
+        assert_eq!(0.5, optimality_gap(5, 10));
+        assert_eq!(0.5, optimality_gap(-10, -5));
+        assert_eq!(1.0, optimality_gap(0, 3));
+        // the bounds have opposite signs
+        assert_eq!(1.5, optimality_gap(-10, 5));
+        assert_eq!(1.5, optimality_gap(-5, 10));
+    }
+
+    #[test]
+    fn the_gap_is_zero_once_the_bounds_meet() {
+        assert_eq!(0.0, optimality_gap(7, 7));
+        assert_eq!(0.0, optimality_gap(-7, -7));
+        assert_eq!(0.0, optimality_gap(0, 0));
+        assert_eq!(0.0, optimality_gap(-5, -10));
+    }
+
+    #[test]
+    fn a_missing_bound_gives_a_full_gap() {
+        assert_eq!(1.0, optimality_gap(isize::MIN, 10));
+        assert_eq!(1.0, optimality_gap(-10, isize::MAX));
+        assert_eq!(1.0, optimality_gap(isize::MIN, isize::MAX));
+        // the extreme bounds which are not sentinels do not overflow
+        assert_eq!(2.0, optimality_gap(isize::MIN + 1, isize::MAX - 1));
+    }
+}
@@ -0,0 +1,340 @@
+//! This module implements the self check of the models: while they compile
+//! their first relaxed dds, the solvers can verify that the relaxation and
+//! the estimate of the problem yield valid upper bounds. Both errors are hard
+//! to spot otherwise, as they silently make the solver prune the optimum.
+//! Before the resolution starts, the self check also looks for a minimization
+//! whose costs were stated as they are, and which is maximized as a result.
+//!
+//! It also implements the verification of the solutions: before a solver
+//! reports that it has proved the optimum, it replays the best path through
+//! the transitions of the problem and checks that it is worth the reported
+//! value.
+
+use std::fmt::{Debug, Display};
+
+use crate::{AssignedVars, Decision, Problem, Variable};
+
+/// The number of relaxed compilations that are checked when the self check
+/// of a solver is enabled
+pub const SELF_CHECKED_COMPILATIONS: usize = 10;
+
+/// The number of decisions of the root whose costs are sampled to check the
+/// sign convention of the model
+const SAMPLED_DECISIONS: usize = 8;
+
+/// A way in which a model fails to over-approximate the value of the solutions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation<T> {
+    /// The arcs redirected towards a merged node lost some value: the merged
+    /// node is worth less than one of the nodes it replaces. This is what
+    /// happens when `Relaxation::relax` returns a cost smaller than that of
+    /// the original arc.
+    LostValue {
+        merged: T,
+        merged_value: isize,
+        original: T,
+        original_value: isize,
+    },
+    /// The estimate of some exact state is smaller than the value of one of
+    /// its completions (given by `decisions`)
+    Underestimate {
+        state: T,
+        estimate: isize,
+        decisions: Vec<Decision>,
+        completion: isize,
+    },
+    /// The sampled decisions of the root all have a positive cost, and a
+    /// completion of the root (given by `decisions`) beats its estimate: the
+    /// model most likely states the costs of a minimization but is maximized.
+    /// This is only a suspicion, it never aborts the resolution.
+    SignConvention {
+        estimate: isize,
+        decisions: Vec<Decision>,
+        completion: isize,
+    },
+}
+impl<T: Debug> Display for Violation<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LostValue { merged, merged_value, original, original_value } => write!(
+                f,
+                "the relaxation lost value: {:?} is worth {} once merged into {:?} which is only worth {}",
+                original, original_value, merged, merged_value
+            ),
+            Self::Underestimate { state, estimate, completion, .. } => write!(
+                f,
+                "the estimate of {:?} is {} but it has a completion worth {}",
+                state, estimate, completion
+            ),
+            Self::SignConvention { estimate, completion, .. } => write!(
+                f,
+                "all the decisions of the root have a positive cost and one of its completions is worth {} while its estimate is {}: \
+                 a minimization should declare `Objective::Minimize` (see `Problem::objective`) rather than be maximized",
+                completion, estimate
+            ),
+        }
+    }
+}
+
+/// What a solver does when its self check finds a violation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnViolation {
+    /// The resolution is interrupted at the end of the compilation in which
+    /// the violation was found
+    #[default]
+    Abort,
+    /// The resolution goes on (but its outcome cannot be trusted)
+    Warn,
+}
+
+/// Completes the given state by greedily taking the decision with the largest
+/// cost for each variable, starting with `var`. The `assigned` variables must
+/// include `var`. It returns the decisions of the completion and its value
+/// (terminal value included), or none when the dive reaches a dead end.
+pub(crate) fn dive<P: Problem>(problem: &P, state: &P::State, var: Variable, assigned: &AssignedVars) -> Option<(Vec<Decision>, isize)> {
+    let mut assigned = assigned.clone();
+    let mut decisions = vec![];
+    let mut value = 0_isize;
+    let mut current = None;
+    let mut var = var;
+    loop {
+        let state = current.as_ref().unwrap_or(state);
+        let mut best: Option<(Decision, isize)> = None;
+        problem.for_each_in_domain(var, state, |decision| {
+            let cost = problem.transition_cost(state, decision);
+            if best.is_none_or(|(_, c)| cost > c) {
+                best = Some((decision, cost));
+            }
+        });
+        let (decision, cost) = best?;
+        let next = problem.transition(state, decision);
+        decisions.push(decision);
+        value = value.saturating_add(cost);
+
+        match problem.next_variable_with_context(&assigned, &mut std::iter::once(&next)) {
+            Some(next_var) => {
+                assigned.insert(next_var);
+                var = next_var;
+                current = Some(next);
+            }
+            None => return Some((decisions, value.saturating_add(problem.terminal_value(&next)))),
+        }
+    }
+}
+
+/// Returns the violation found by a dive from the given exact state, if its
+/// completion is worth more than its estimate
+pub(crate) fn check_estimate<P>(problem: &P, state: &P::State, var: Variable, assigned: &AssignedVars, estimate: isize)
+    -> Option<Violation<P::State>>
+where
+    P: Problem,
+    P::State: Clone,
+{
+    let (decisions, completion) = dive(problem, state, var, assigned)?;
+    (completion > estimate).then(|| Violation::Underestimate { state: state.clone(), estimate, decisions, completion })
+}
+
+/// Returns a violation when the problem looks like a minimization which is
+/// maximized: the costs of the (first few) decisions of the root are all
+/// positive, and the greedy completion of the root is worth more than its
+/// estimate. A maximization may well have positive costs only; its estimate
+/// is then valid and this stays silent.
+pub(crate) fn check_sign_convention<P: Problem>(problem: &P) -> Option<Violation<P::State>> {
+    let root = problem.initial_state();
+    let mut assigned = AssignedVars::new(problem.nb_variables());
+    let var = problem.next_variable_with_context(&assigned, &mut std::iter::once(&root))?;
+    assigned.insert(var);
+
+    let mut costs = vec![];
+    problem.for_each_in_domain(var, &root, |decision| {
+        if costs.len() < SAMPLED_DECISIONS {
+            costs.push(problem.transition_cost(&root, decision));
+        }
+    });
+    if costs.is_empty() || costs.iter().any(|cost| *cost <= 0) {
+        return None;
+    }
+    let estimate = problem.estimate(&root);
+    let (decisions, completion) = dive(problem, &root, var, &assigned)?;
+    (completion > estimate).then_some(Violation::SignConvention { estimate, decisions, completion })
+}
+
+/// A reason why the best solution of a resolution failed its verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The path has no decision for a variable the problem branches on
+    Unassigned(Variable),
+    /// The path has several decisions for the same variable
+    Duplicate(Variable),
+    /// The path has a decision for a variable the problem never branches on
+    Unexpected(Decision),
+    /// The value of a decision is not in the domain of its variable
+    OutOfDomain(Decision),
+    /// The path is not worth the value reported by the solver
+    ValueMismatch { reported: isize, replayed: isize },
+}
+impl Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unassigned(var) => write!(f, "the best solution does not assign {:?}", var),
+            Self::Duplicate(var) => write!(f, "the best solution assigns {:?} more than once", var),
+            Self::Unexpected(decision) => write!(f, "the best solution assigns {:?} which is never branched on", decision.var),
+            Self::OutOfDomain(decision) => write!(f, "{:?} is not in the domain of its variable", decision),
+            Self::ValueMismatch { reported, replayed } => write!(
+                f,
+                "the best solution was reported to be worth {} but its replay is worth {}",
+                reported, replayed
+            ),
+        }
+    }
+}
+
+/// Why a solution fails its validation, and where its replay stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError<S> {
+    /// The number of decisions which had been replayed. It is 0 when the
+    /// solution assigns some variable more than once, as this is checked
+    /// before the replay starts.
+    pub step: usize,
+    /// The offending decision (none when a variable is left unassigned)
+    pub decision: Option<Decision>,
+    /// The state reached by the replay
+    pub state: S,
+    pub reason: VerificationError,
+}
+impl<S: Debug> Display for ValidationError<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (after {} decisions, in {:?})", self.reason, self.step, self.state)
+    }
+}
+
+/// Replays the given (complete) solution from the initial state of the
+/// problem: this is what `Problem::validate_solution` does by default. The
+/// decisions are taken in the order in which the problem branches on their
+/// variables, and each variable the problem branches on must be assigned
+/// exactly once, with a value of its domain. The value of the solution
+/// includes the terminal value of the state it ends in.
+///
+/// # Note
+/// The variables are picked by `next_variable_with_context` given the only
+/// state of the replay (just like the dive of the self check does).
+pub fn replay_solution<P: Problem + ?Sized>(problem: &P, solution: &[Decision]) -> Result<isize, ValidationError<P::State>> {
+    let fail = |step, decision, state, reason| Err(ValidationError { step, decision, state, reason });
+
+    let mut decisions: Vec<Option<Decision>> = vec![None; problem.nb_variables()];
+    for decision in solution.iter().copied() {
+        match decisions.get_mut(decision.var.id()) {
+            None => return fail(0, Some(decision), problem.initial_state(), VerificationError::Unexpected(decision)),
+            Some(Some(_)) => return fail(0, Some(decision), problem.initial_state(), VerificationError::Duplicate(decision.var)),
+            Some(slot) => *slot = Some(decision),
+        }
+    }
+
+    let mut assigned = AssignedVars::new(problem.nb_variables());
+    let mut state = problem.initial_state();
+    let mut value = problem.initial_value();
+    let mut step = 0;
+    while let Some(var) = problem.next_variable_with_context(&assigned, &mut std::iter::once(&state)) {
+        let Some(decision) = decisions.get_mut(var.id()).and_then(Option::take) else {
+            return fail(step, None, state, VerificationError::Unassigned(var));
+        };
+        let mut in_domain = false;
+        problem.for_each_in_domain(var, &state, |d| in_domain |= d == decision);
+        if !in_domain {
+            return fail(step, Some(decision), state, VerificationError::OutOfDomain(decision));
+        }
+        assigned.insert(var);
+        value = value.saturating_add(problem.transition_cost(&state, decision));
+        state = problem.transition(&state, decision);
+        step += 1;
+    }
+
+    match decisions.into_iter().flatten().next() {
+        Some(decision) => fail(step, Some(decision), state, VerificationError::Unexpected(decision)),
+        None => Ok(value.saturating_add(problem.terminal_value(&state))),
+    }
+}
+
+/// Replays the given (complete) solution through `Problem::validate_solution`
+/// and returns its value, or the reason why it is not a solution
+pub fn check_path<P: Problem>(problem: &P, path: &[Decision]) -> Result<isize, VerificationError> {
+    problem.validate_solution(path).map_err(|error| error.reason)
+}
+
+#[cfg(test)]
+mod test_validate {
+    use crate::test_utils::{Knapsack, KnapsackState, Tweaked};
+    use crate::{AssignedVars, Decision, Problem, Variable, Violation};
+
+    use super::{check_estimate, check_path, check_sign_convention, dive, VerificationError};
+
+    #[test]
+    fn the_dive_greedily_takes_the_items_which_fit() {
+        let problem = Knapsack::toy();
+        let mut assigned = AssignedVars::new(problem.nb_variables());
+        assigned.insert(Variable(0));
+        let (decisions, value) = dive(&problem, &problem.initial_state(), Variable(0), &assigned).unwrap();
+
+        // items 0 and 1 are taken, then only item 4 still fits
+        let taken = decisions.iter().map(|d| d.value).collect::<Vec<_>>();
+        assert_eq!(vec![1, 1, 0, 0, 1, 0], taken);
+        assert_eq!(10, value);
+        assert!(check_estimate(&problem, &problem.initial_state(), Variable(0), &assigned, 10).is_none());
+        assert!(check_estimate(&problem, &problem.initial_state(), Variable(0), &assigned, 9).is_some());
+    }
+
+    #[test]
+    fn a_maximized_minimization_breaks_the_sign_convention() {
+        let problem = Tweaked::handling(Knapsack::toy(), false);
+        // leaving all the items out costs 6 to handle them, plus their 21 units of profit
+        match check_sign_convention(&problem) {
+            Some(Violation::SignConvention { estimate, decisions, completion }) => {
+                assert_eq!((6, 27), (estimate, completion));
+                assert!(decisions.iter().all(|d| d.value == 0));
+            }
+            violation => panic!("unexpected outcome: {:?}", violation),
+        }
+
+        assert!(check_sign_convention(&Tweaked::handling(Knapsack::toy(), true)).is_none());
+        // a maximization whose costs may be 0 is not suspicious
+        assert!(check_sign_convention(&Knapsack::toy()).is_none());
+    }
+
+    #[test]
+    fn the_replay_checks_each_variable_is_assigned_once() {
+        let problem = Knapsack::toy();
+        let path = |taken: &[isize]| {
+            taken.iter().enumerate().map(|(i, v)| Decision { var: Variable(i), value: *v }).rev().collect::<Vec<_>>()
+        };
+        assert_eq!(Ok(10), check_path(&problem, &path(&[1, 1, 0, 0, 1, 0])));
+        assert_eq!(Err(VerificationError::Unassigned(Variable(5))), check_path(&problem, &path(&[1, 1, 0, 0, 1])));
+
+        let mut duplicate = path(&[1, 1, 0, 0, 1, 0]);
+        duplicate.push(Decision { var: Variable(2), value: 1 });
+        assert_eq!(Err(VerificationError::Duplicate(Variable(2))), check_path(&problem, &duplicate));
+
+        let mut unexpected = path(&[1, 1, 0, 0, 1, 0]);
+        unexpected.push(Decision { var: Variable(6), value: 0 });
+        assert_eq!(Err(VerificationError::Unexpected(unexpected[6])), check_path(&problem, &unexpected));
+        // all the items cannot fit at once
+        assert!(matches!(check_path(&problem, &path(&[1; 6])), Err(VerificationError::OutOfDomain(_))));
+    }
+
+    #[test]
+    fn the_validation_tells_where_the_replay_failed() {
+        let problem = Knapsack::toy();
+        let path = |taken: &[isize]| taken.iter().enumerate().map(|(i, v)| Decision { var: Variable(i), value: *v }).collect::<Vec<_>>();
+        assert_eq!(Ok(13), problem.validate_solution(&path(&[1, 0, 0, 1, 1, 0])));
+
+        // items 0 and 1 leave room for 3 units, hence item 2 does not fit
+        let error = problem.validate_solution(&path(&[1; 6])).unwrap_err();
+        assert_eq!(2, error.step);
+        assert_eq!(Some(Decision { var: Variable(2), value: 1 }), error.decision);
+        assert_eq!(KnapsackState { depth: 2, capacity: 3 }, error.state);
+        assert_eq!(VerificationError::OutOfDomain(Decision { var: Variable(2), value: 1 }), error.reason);
+
+        let error = problem.validate_solution(&path(&[1, 0, 0, 1, 1])).unwrap_err();
+        assert_eq!((5, None, KnapsackState { depth: 5, capacity: 0 }), (error.step, error.decision, error.state));
+        assert_eq!(VerificationError::Unassigned(Variable(5)), error.reason);
+    }
+}
@@ -1,7 +1,9 @@
 use peak_alloc::PeakAlloc;
 use std::{
-    fmt::Display,
+    fmt::{Debug, Display},
+    fs::File,
     hash::Hash,
+    io::{self, BufWriter, Write},
     process::exit,
     str::FromStr,
     sync::{Arc, Mutex},
@@ -9,16 +11,20 @@ use std::{
     time::{Duration, Instant},
 };
 use structopt::StructOpt;
+#[cfg(feature = "checkpoint")]
+use std::io::BufReader;
 
 use crate::{
-    InterruptibleSolver,
+    optimality_gap, CompilationStatistics, Decision, InterruptibleSolver, MemoryAttribution,
     ParallelSolver, Problem, Relaxation, Solver, StateRanking,
-    WidthHeuristic, BarrierParallelSolver, NoDupFrontier, CutsetType,
+    WidthHeuristic, BarrierParallelSolver, ExactSolver, estimate_dd_size, RelaxedBoundSolver, StratifiedFrontier, FringePolicy, CutsetType, FrontierOrder, MaxUB, MaxValue, MaxDepth, Objective, EitherCutoff, NodeCutoff, TimeCutoff, ResolutionStatus, SecondaryObjective, DominanceChecker, SolverObserver, TraceCollector, VerificationError, WarmStartBudget, BarrierLayerStats, LayerStats, MergePolicy, Violation,
 };
 
 #[global_allocator]
 static PEAK_ALLOC: PeakAlloc = PeakAlloc;
 
+// the arguments are only parsed once
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, StructOpt)]
 pub enum Args {
     Solve {
@@ -34,6 +40,59 @@ pub enum Args {
         solver: SolverType,
         #[structopt(short, long, default_value = "lel")]
         cutset: CutsetType,
+        /// Writes the best solution to that file, in the format of the problem
+        #[structopt(long)]
+        solution_out: Option<String>,
+        /// Writes the status, the objective and the decisions of the best solution to that file
+        #[structopt(short, long)]
+        output: Option<String>,
+        /// Also prints how the compilation time splits between the model and the solver
+        #[structopt(short, long)]
+        verbose: bool,
+        /// Merges the layers shallower than that depth with a coarser (but cheaper) relaxation, for the problems which have one
+        #[structopt(long)]
+        coarse_depth: Option<usize>,
+        /// Among the optimal solutions, prefers the one which visits that node the earliest, for the problems which support it
+        #[structopt(long)]
+        earliest_visit: Option<usize>,
+        /// Drops the nodes which are dominated by another node of their layer, for the problems which have a dominance rule (barrier solver only)
+        #[structopt(long)]
+        dominance: bool,
+        /// The order in which the fringe pops its nodes: 'maxub', 'maxvalue' or 'maxdepth'
+        #[structopt(long, default_value = "maxub")]
+        order: OrderType,
+        /// How the fringe picks the next node to explore: 'bestbound' (in the given order) or 'deepest' (the deepest layer first, which keeps the fringe smaller)
+        #[structopt(long, default_value = "bestbound")]
+        fringe_policy: FringePolicy,
+        /// Also stops the resolution once it has explored that many nodes of the branch-and-bound
+        #[structopt(long)]
+        max_nodes: Option<usize>,
+        /// Compiles restricted dds of growing width before the branch-and-bound, within that budget: a number of dd nodes (e.g. '100000') or of seconds (e.g. '5s')
+        #[structopt(long)]
+        warm_start: Option<WarmStartBudget>,
+        /// How the relaxed dds merge the nodes of their overflowing layers: 'single' (one merged node) or 'chunked:<k>' (k merged nodes, by clusters of the ranking)
+        #[structopt(long, default_value = "single")]
+        merge: MergePolicy,
+        /// Reads the thresholds of the barrier from that file before the resolution, for the problems which support it (barrier solver only)
+        #[structopt(long)]
+        barrier_in: Option<String>,
+        /// Writes the thresholds of the barrier to that file once the resolution is complete, for the problems which support it (barrier solver only)
+        #[structopt(long)]
+        barrier_out: Option<String>,
+        /// Writes the time at which each better solution and each better bound was found to that csv file (parallel and barrier solvers only)
+        #[structopt(long)]
+        trace: Option<String>,
+        /// Checks the model during the first compilations and prints the violations it finds (parallel and barrier solvers only)
+        #[structopt(long)]
+        self_check: bool,
+    },
+    /// Runs all the configurations described in the experiment file on all
+    /// of its instances, and writes the results in the output csv
+    Bench {
+        #[structopt(short, long)]
+        experiment: String,
+        #[structopt(short, long, default_value = "results.csv")]
+        output: String,
     },
     PrintHeader,
 }
@@ -42,6 +101,15 @@ pub enum Args {
 pub enum SolverType {
     Parallel,
     Barrier,
+    /// Only compiles the relaxed dd of the root (see `RelaxedBoundSolver`)
+    RootRelaxation,
+    /// Compiles the exact dd of the whole problem and enumerates its optimal
+    /// solutions (see `ExactSolver`), which is only viable on tiny instances
+    Exact,
+    /// Runs the exact solver when the exact dd is estimated to have at most
+    /// `AUTO_EXACT_BUDGET` nodes (see `estimate_dd_size`), and the barrier
+    /// solver otherwise
+    Auto,
 }
 impl FromStr for SolverType {
     type Err = &'static str;
@@ -50,7 +118,10 @@ impl FromStr for SolverType {
         match s {
             "parallel" => Ok(Self::Parallel),
             "barrier" => Ok(Self::Barrier),
-            _ => Err("The only supported frontier types are 'parallel' and 'barrier'"),
+            "root-relaxation" => Ok(Self::RootRelaxation),
+            "exact" => Ok(Self::Exact),
+            "auto" => Ok(Self::Auto),
+            _ => Err("The only supported solver types are 'parallel', 'barrier', 'root-relaxation', 'exact' and 'auto'"),
         }
     }
 }
@@ -59,8 +130,197 @@ impl Display for SolverType {
         match self {
             Self::Parallel => write!(f, "parallel"),
             Self::Barrier => write!(f, "barrier"),
+            Self::RootRelaxation => write!(f, "root-relaxation"),
+            Self::Exact => write!(f, "exact"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// The order in which the fringe of a solver pops its nodes (see `FrontierOrder`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OrderType {
+    #[default]
+    MaxUb,
+    MaxValue,
+    MaxDepth,
+}
+impl OrderType {
+    fn order<'a, O: StateRanking + Send + Sync>(self, ranking: &'a O) -> Box<dyn FrontierOrder<O::State> + Send + Sync + 'a> {
+        match self {
+            Self::MaxUb => Box::new(MaxUB(ranking)),
+            Self::MaxValue => Box::new(MaxValue(ranking)),
+            Self::MaxDepth => Box::new(MaxDepth(ranking)),
+        }
+    }
+}
+impl FromStr for OrderType {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "maxub" => Ok(Self::MaxUb),
+            "maxvalue" => Ok(Self::MaxValue),
+            "maxdepth" => Ok(Self::MaxDepth),
+            _ => Err("The only supported frontier orders are 'maxub', 'maxvalue' and 'maxdepth'"),
+        }
+    }
+}
+impl Display for OrderType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MaxUb => write!(f, "maxub"),
+            Self::MaxValue => write!(f, "maxvalue"),
+            Self::MaxDepth => write!(f, "maxdepth"),
+        }
+    }
+}
+
+/// The outcome of one resolution, as printed in the results table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveReport {
+    pub name: String,
+    pub solver: SolverType,
+    pub cutset: CutsetType,
+    pub status: ResolutionStatus,
+    pub duration: Duration,
+    /// Peak memory usage of the whole process (in MB)
+    pub peak_memory: f32,
+    pub best_value: Option<isize>,
+    pub lb: isize,
+    pub ub: isize,
+    pub gap: f32,
+    pub explored: usize,
+    pub explored_dd: usize,
+    /// The number of cutset nodes drained from the relaxed dds
+    pub cutset_generated: usize,
+    /// The number of these cutset nodes which were pushed onto the fringe
+    pub cutset_enqueued: usize,
+    /// The number of fringe nodes which were skipped because the barrier
+    /// dominates them (see `BarrierParallelSolver::get_skipped_by_barrier`)
+    pub skipped_by_barrier: usize,
+    /// The decisions of the best solution (sorted by variable), if any
+    pub best_solution: Option<Vec<Decision>>,
+    pub compilations: CompilationStatistics,
+    /// The estimated memory held by the dds, the fringe and the barrier when
+    /// they held the most altogether
+    pub peak_attribution: Option<MemoryAttribution>,
+    /// Why the best solution failed its verification, if it did (for a
+    /// mismatch, this gives both the reported and the replayed value)
+    pub verification_error: Option<VerificationError>,
+    /// What the barrier did at each depth (empty unless the barrier solver was used)
+    pub barrier_layers: Vec<BarrierLayerStats>,
+    /// The number of optimal solutions (none unless the exact solver was used,
+    /// which enumerates at most `ExactSolver::DEFAULT_SOLUTION_LIMIT` of them)
+    pub optimal_solutions: Option<usize>,
+    /// The violations found by the self check of the model, if it was enabled
+    /// (see `SolverConfig::self_check`)
+    pub violations: Vec<String>,
+}
+impl SolveReport {
+    pub const CSV_HEADER: &'static str =
+        "name,solver,cutset,status,duration,ram_mb,best_value,lb,ub,gap,nodes_bnb,nodes_dd";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{:.2},{:.2},{},{},{},{:.4},{},{}",
+            self.name,
+            self.solver,
+            self.cutset,
+            self.status,
+            self.duration.as_secs_f32(),
+            self.peak_memory,
+            self.best_value.map(|v| v.to_string()).unwrap_or_default(),
+            self.format_bound(self.lb),
+            self.format_bound(self.ub),
+            self.gap,
+            self.explored,
+            self.explored_dd,
+        )
+    }
+
+    /// Formats one bound of the resolution: an infeasible problem has none
+    fn format_bound(&self, bound: isize) -> String {
+        match self.status {
+            ResolutionStatus::Infeasible => "-".to_owned(),
+            _ => bound.to_string(),
         }
     }
+
+    /// Tells which share of the compilation time was spent in the model and
+    /// which share was spent in the solver (when the compilations were timed)
+    pub fn time_split(&self) -> Option<String> {
+        self.compilations.model_time_share().map(|share| {
+            format!(
+                "model: {:.2}% ({:.2}s) | framework: {:.2}% ({:.2}s)",
+                100.0 * share,
+                self.compilations.model_time.as_secs_f64(),
+                100.0 * (1.0 - share),
+                self.compilations.framework_time.as_secs_f64(),
+            )
+        })
+    }
+
+    /// Summarizes what the barrier did (when it did something): a sparkline
+    /// of its hits by depth, followed by its totals
+    pub fn barrier_summary(&self) -> Option<String> {
+        const TICKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let total = self.barrier_layers.iter().fold(BarrierLayerStats::default(), |acc, l| BarrierLayerStats {
+            hits: acc.hits + l.hits,
+            misses: acc.misses + l.misses,
+            inserts: acc.inserts + l.inserts,
+            overwrites: acc.overwrites + l.overwrites,
+        });
+        if total == BarrierLayerStats::default() {
+            return None;
+        }
+        let max = self.barrier_layers.iter().map(|l| l.hits).max().unwrap_or(0).max(1);
+        let sparkline = self
+            .barrier_layers
+            .iter()
+            .map(|l| if l.hits == 0 { ' ' } else { TICKS[l.hits * (TICKS.len() - 1) / max] })
+            .collect::<String>();
+        let lookups = total.hits + total.misses;
+        Some(format!(
+            "barrier hits by depth: [{}] | hits: {} of {} lookups ({:.2}%) | inserts: {} | overwrites: {}",
+            sparkline,
+            total.hits,
+            lookups,
+            100.0 * total.hits as f64 / lookups.max(1) as f64,
+            total.inserts,
+            total.overwrites,
+        ))
+    }
+}
+impl Display for SolveReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let best_value = match (self.best_value, self.status) {
+            (Some(value), _) => value.to_string(),
+            (None, ResolutionStatus::Infeasible) => "infeasible".to_owned(),
+            (None, _) => "not found".to_owned(),
+        };
+
+        write!(
+            f,
+            "{:>30} | {:>10} | {:>15} | {:>8.2} | {:>8.2} | {:>15} | {:>15} | {:>15} | {:>5.4} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15}",
+            self.name,
+            self.solver,
+            self.status,
+            self.duration.as_secs_f32(),
+            self.peak_memory,
+            best_value,
+            self.format_bound(self.lb),
+            self.format_bound(self.ub),
+            self.gap,
+            self.explored,
+            self.explored_dd,
+            self.cutset_generated,
+            self.cutset_enqueued,
+            self.compilations.counters.barrier_prunes,
+            self.compilations.counters.rub_prunes,
+            self.skipped_by_barrier,
+        )
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -78,17 +338,135 @@ pub fn solve_timeout<P, R, O, W>(
 -> isize
 where
     P: Problem + Send + Sync,
-    P::State: Eq + PartialEq + Hash + Clone + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Debug + Send + Sync,
     R: Relaxation<State = P::State> + Send + Sync,
     O: StateRanking<State = P::State> + Send + Sync,
     W: WidthHeuristic<P::State> + Send + Sync,
 {
-    let mut fringe = NoDupFrontier::new(ranking);
+    let report = solve(name, to, width, model, relax, ranking, threads, solver_type, cutset_type, false);
+    println!("{}", report);
+    // the dual bound of the resolution
+    match model.objective() {
+        Objective::Maximize => report.ub,
+        Objective::Minimize => report.lb,
+    }
+}
 
-    match solver_type {
+/// The number of nodes popped from the fringe between two samples of the
+/// memory (see `SolveReport::peak_attribution`)
+const MEMORY_SAMPLING_PERIOD: usize = 16;
+
+#[allow(clippy::too_many_arguments)]
+pub fn solve<P, R, O, W>(
+    name: &str,
+    to: Duration,
+    width: &W,
+    model: &P,
+    relax: &R,
+    ranking: &O,
+    threads: Option<usize>,
+    solver_type: SolverType,
+    cutset_type: CutsetType,
+    model_timing: bool,
+)
+-> SolveReport
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Debug + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    solve_with_secondary(name, to, width, model, relax, ranking, threads, solver_type, cutset_type, model_timing, None)
+}
+
+/// Solves the problem like `solve`, but breaks the ties among the optimal
+/// solutions with the given secondary objective (if any)
+#[allow(clippy::too_many_arguments)]
+pub fn solve_with_secondary<P, R, O, W>(
+    name: &str,
+    to: Duration,
+    width: &W,
+    model: &P,
+    relax: &R,
+    ranking: &O,
+    threads: Option<usize>,
+    solver_type: SolverType,
+    cutset_type: CutsetType,
+    model_timing: bool,
+    secondary: Option<&(dyn SecondaryObjective + Sync)>,
+)
+-> SolveReport
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Debug + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    solve_custom(name, to, width, model, relax, ranking, threads, solver_type, cutset_type, model_timing, false, secondary, None, OrderType::default(), FringePolicy::default(), None, None, MergePolicy::SingleNode, None)
+}
+
+/// Solves the problem like `solve_with_secondary`, with a fringe which pops
+/// its nodes in the given order (within the layers of the given fringe
+/// policy, see `FringePolicy`). When `max_nodes` is set, the resolution also
+/// stops once it has explored that many nodes of the branch-and-bound. When
+/// `warm_start` is set, the solver is warm started within that budget before
+/// the resolution (see `ParallelSolver::warm_start`); the time it takes counts
+/// in the duration of the resolution, but not in its timeout. The problem is
+/// maximized or minimized as its `Problem::objective` says: all the values of
+/// the report are in that sense. The relaxed dds merge their overflowing
+/// layers along the given `merge_policy`. The dds of the barrier solver drop
+/// the nodes that the `dominance` rule (if any) finds dominated. When `trace`
+/// is set, the improvements of the bounds of the parallel and barrier solvers
+/// are written to that csv file (see `TraceCollector::write_csv`). When
+/// `self_check` is set, these solvers check the model during their first
+/// compilations and the report gives the violations they found.
+#[allow(clippy::too_many_arguments)]
+pub fn solve_custom<P, R, O, W>(
+    name: &str,
+    to: Duration,
+    width: &W,
+    model: &P,
+    relax: &R,
+    ranking: &O,
+    threads: Option<usize>,
+    solver_type: SolverType,
+    cutset_type: CutsetType,
+    model_timing: bool,
+    self_check: bool,
+    secondary: Option<&(dyn SecondaryObjective + Sync)>,
+    dominance: Option<&(dyn DominanceChecker<P::State> + Sync)>,
+    order: OrderType,
+    fringe_policy: FringePolicy,
+    max_nodes: Option<usize>,
+    warm_start: Option<WarmStartBudget>,
+    merge_policy: MergePolicy,
+    trace: Option<&str>,
+)
+-> SolveReport
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Debug + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    let mut fringe = StratifiedFrontier::<O, _>::with_order(order.order(ranking), fringe_policy);
+    let collector = TraceCollector::new();
+    let observer = trace.map(|_| &collector as &dyn SolverObserver);
+    let start = Instant::now();
+    let cutoff = EitherCutoff(TimeCutoff(to), NodeCutoff(max_nodes.unwrap_or(usize::MAX)));
+
+    let peak_attribution;
+    let solver_type = match solver_type {
+        SolverType::Auto => auto_solver(model),
+        solver_type => solver_type,
+    };
+    let mut optimal_solutions = None;
+    let outcome = match solver_type {
         SolverType::Parallel => {
-            let start = Instant::now();
-            let mut solver = ParallelSolver::<P, R, O, W, NoDupFrontier<O>>::custom(
+            let mut solver = ParallelSolver::custom(
                 model,
                 relax,
                 ranking,
@@ -96,92 +474,371 @@ where
                 cutset_type,
                 &mut fringe,
                 threads.unwrap_or_else(num_cpus::get),
-            );
-            let status = solver.maximize_with_interrupt(move || start.elapsed().gt(&to));
-
-            let duration = start.elapsed();
-            let best_value = solver
-                .best_value()
-                .map(|v| v.to_string())
-                .unwrap_or_else(|| "not found".to_owned());
-
-            let lb = solver.best_lower_bound();
-            let ub = solver.best_upper_bound();
-            let gap = gap(lb, ub);
-
-            println!(
-                "{:>30} | {:>10} | {:>15} | {:>8.2} | {:>8.2} | {:>15} | {:>15} | {:>15} | {:>5.4} | {:>15} | {:>15}",
-                name,
-                solver_type,
-                status,
-                duration.as_secs_f32(),
-                PEAK_ALLOC.peak_usage_as_mb(),
-                best_value,
-                lb,
-                ub,
-                gap,
-                solver.get_explored(),
-                solver.get_explored_dd(),
-            );
-
-            ub
+            )
+            .with_model_timing(model_timing)
+            .with_self_check(self_check)
+            .with_memory_sampling(MEMORY_SAMPLING_PERIOD)
+            .with_merge_policy(merge_policy);
+            if let Some(secondary) = secondary {
+                solver = solver.with_secondary_objective(secondary);
+            }
+            if let Some(observer) = observer {
+                solver = solver.with_observer(observer);
+            }
+            if let Some(budget) = warm_start {
+                solver.warm_start(budget);
+            }
+            let status = match model.objective() {
+                Objective::Maximize => solver.maximize_with_cutoff(cutoff),
+                Objective::Minimize => solver.minimize_with_cutoff(cutoff),
+            };
+            peak_attribution = solver.peak_attribution();
+            let cutset = (solver.get_cutset_generated(), solver.get_cutset_enqueued());
+            (status, solver.best_value(), solver.best_lower_bound(), solver.best_upper_bound(), solver.get_explored(), solver.get_explored_dd(), cutset, 0, solver.best_solution(), solver.get_compilation_statistics(), solver.verification_error(), vec![], describe(&solver.violations()))
         },
         SolverType::Barrier => {
-            let start = Instant::now();
-            let mut solver = BarrierParallelSolver::<P, R, O, W>::custom(
-                model,
-                relax,
-                ranking,
-                width,
-                cutset_type,
-                threads.unwrap_or_else(num_cpus::get),
-            );
-            let status = solver.maximize_with_interrupt(move || start.elapsed().gt(&to));
+            let mut solver = barrier_solver(width, model, relax, ranking, threads, cutset_type, model_timing, self_check, secondary, dominance, order, fringe_policy, merge_policy, observer);
+            let outcome = solve_barrier(&mut solver, model, cutoff, warm_start);
+            peak_attribution = solver.peak_attribution();
+            outcome
+        }
+        SolverType::RootRelaxation => {
+            let mut solver = RelaxedBoundSolver::new(model, relax, ranking, width).with_merge_policy(merge_policy);
+            let status = solver.compute();
+            peak_attribution = None;
+            (status, solver.best_value(), solver.best_lower_bound(), solver.best_upper_bound(), 0, solver.get_explored_dd(), (0, 0), 0, solver.best_solution(), solver.get_compilation_statistics(), None, vec![], vec![])
+        }
+        SolverType::Exact => {
+            let mut solver = ExactSolver::new(model, relax, ranking);
+            let status = solver.compute();
+            optimal_solutions = Some(solver.best_solutions().len());
+            peak_attribution = None;
+            (status, solver.best_value(), solver.best_bound(), solver.best_bound(), 0, solver.get_explored_dd(), (0, 0), 0, solver.best_solution(), solver.get_compilation_statistics(), None, vec![], vec![])
+        }
+        SolverType::Auto => unreachable!("the auto solver is either the exact or the barrier one"),
+    };
+    if let Some(path) = trace {
+        write_trace(&collector, path);
+    }
+    report(name, solver_type, cutset_type, start, outcome, optimal_solutions, peak_attribution)
+}
+
+/// Solves the problem like `solve_custom` with the barrier solver, whose
+/// thresholds are read from the `barrier_in` file before the resolution (see
+/// `BarrierParallelSolver::load_barrier`). Once the resolution is complete,
+/// the thresholds it proved are written to the `barrier_out` file (see
+/// `BarrierParallelSolver::dump_barrier`); nothing is written when it was
+/// interrupted. The trace of the resolution is written as in `solve_custom`.
+#[cfg(feature = "checkpoint")]
+#[allow(clippy::too_many_arguments)]
+pub fn solve_with_barrier_files<P, R, O, W>(
+    name: &str,
+    to: Duration,
+    width: &W,
+    model: &P,
+    relax: &R,
+    ranking: &O,
+    threads: Option<usize>,
+    cutset_type: CutsetType,
+    model_timing: bool,
+    self_check: bool,
+    secondary: Option<&(dyn SecondaryObjective + Sync)>,
+    dominance: Option<&(dyn DominanceChecker<P::State> + Sync)>,
+    order: OrderType,
+    fringe_policy: FringePolicy,
+    max_nodes: Option<usize>,
+    warm_start: Option<WarmStartBudget>,
+    merge_policy: MergePolicy,
+    barrier_in: Option<&str>,
+    barrier_out: Option<&str>,
+    trace: Option<&str>,
+)
+-> io::Result<SolveReport>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Debug + Send + Sync + serde::Serialize + serde::de::DeserializeOwned,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    let collector = TraceCollector::new();
+    let observer = trace.map(|_| &collector as &dyn SolverObserver);
+    let start = Instant::now();
+    let cutoff = EitherCutoff(TimeCutoff(to), NodeCutoff(max_nodes.unwrap_or(usize::MAX)));
+
+    let mut solver = barrier_solver(width, model, relax, ranking, threads, cutset_type, model_timing, self_check, secondary, dominance, order, fringe_policy, merge_policy, observer)
+        .with_barrier_retention(barrier_out.is_some());
+    if let Some(path) = barrier_in {
+        solver.load_barrier(BufReader::new(File::open(path)?))?;
+    }
+    let outcome = solve_barrier(&mut solver, model, cutoff, warm_start);
+    if let Some(path) = barrier_out {
+        if outcome.0 == ResolutionStatus::Proved {
+            solver.dump_barrier(BufWriter::new(File::create(path)?))?;
+        } else {
+            eprintln!("the resolution is not complete: {} was not written", path);
+        }
+    }
+    if let Some(path) = trace {
+        write_trace(&collector, path);
+    }
+    Ok(report(name, SolverType::Barrier, cutset_type, start, outcome, None, solver.peak_attribution()))
+}
 
-            let duration = start.elapsed();
-            let best_value = solver
-                .best_value()
-                .map(|v| v.to_string())
-                .unwrap_or_else(|| "not found".to_owned());
+/// What a resolution yields: its status, best value, bounds, explored nodes,
+/// cutset counts, fringe nodes skipped by the barrier, best solution,
+/// compilation statistics, verification error, barrier stats and violations
+/// (see `SolveReport`)
+type Outcome = (
+    ResolutionStatus,
+    Option<isize>,
+    isize,
+    isize,
+    usize,
+    usize,
+    (usize, usize),
+    usize,
+    Option<Vec<Decision>>,
+    CompilationStatistics,
+    Option<VerificationError>,
+    Vec<BarrierLayerStats>,
+    Vec<String>,
+);
 
-            let lb = solver.best_lower_bound();
-            let ub = solver.best_upper_bound();
-            let gap = gap(lb, ub);
+/// Describes the violations found by the self check of a solver, as printed
+fn describe<T: Debug>(violations: &[Violation<T>]) -> Vec<String> {
+    violations.iter().map(ToString::to_string).collect()
+}
 
-            println!(
-                "{:>30} | {:>10} | {:>15} | {:>8.2} | {:>8.2} | {:>15} | {:>15} | {:>15} | {:>5.4} | {:>15} | {:>15}",
-                name,
-                solver_type,
-                status,
-                duration.as_secs_f32(),
-                PEAK_ALLOC.peak_usage_as_mb(),
-                best_value,
-                lb,
-                ub,
-                gap,
-                solver.get_explored(),
-                solver.get_explored_dd(),
-            );
+/// The barrier solver of `solve_custom`, whose fringe pops its nodes in the
+/// given order and along the given policy (and which applies the given dominance rule and tells the given
+/// observer about its bounds, if any)
+#[allow(clippy::too_many_arguments)]
+fn barrier_solver<'a, P, R, O, W>(
+    width: &'a W,
+    model: &'a P,
+    relax: &'a R,
+    ranking: &'a O,
+    threads: Option<usize>,
+    cutset_type: CutsetType,
+    model_timing: bool,
+    self_check: bool,
+    secondary: Option<&'a (dyn SecondaryObjective + Sync)>,
+    dominance: Option<&'a (dyn DominanceChecker<P::State> + Sync)>,
+    order: OrderType,
+    fringe_policy: FringePolicy,
+    merge_policy: MergePolicy,
+    observer: Option<&'a dyn SolverObserver>,
+)
+-> BarrierParallelSolver<'a, P, R, O, W, Box<dyn FrontierOrder<P::State> + Send + Sync + 'a>>
+where
+    P: Problem + Send + Sync,
+    P::State: Eq + PartialEq + Hash + Clone + Debug + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync,
+    O: StateRanking<State = P::State> + Send + Sync,
+    W: WidthHeuristic<P::State> + Send + Sync,
+{
+    let mut solver = BarrierParallelSolver::ordered(
+        model,
+        relax,
+        ranking,
+        width,
+        cutset_type,
+        threads.unwrap_or_else(num_cpus::get),
+        order.order(ranking),
+    )
+    .with_fringe_policy(fringe_policy)
+    .with_model_timing(model_timing)
+    .with_self_check(self_check)
+    .with_memory_sampling(MEMORY_SAMPLING_PERIOD)
+    .with_merge_policy(merge_policy);
+    if let Some(secondary) = secondary {
+        solver = solver.with_secondary_objective(secondary);
+    }
+    if let Some(dominance) = dominance {
+        solver = solver.with_dominance(dominance);
+    }
+    if let Some(observer) = observer {
+        solver = solver.with_observer(observer);
+    }
+    solver
+}
 
-            ub
+/// Writes the trace gathered by the `collector` to the csv file at `path`
+fn write_trace(collector: &TraceCollector, path: &str) {
+    let written = File::create(path).and_then(|file| collector.write_csv(BufWriter::new(file)));
+    if let Err(error) = written {
+        eprintln!("the trace could not be written to {}: {}", path, error);
+    }
+}
+
+/// Runs the resolution of the given barrier solver (after its warm start, if
+/// any) in the sense of the objective of the problem
+fn solve_barrier<'a, P, R, O, W, C>(
+    solver: &mut BarrierParallelSolver<'a, P, R, O, W, C>,
+    model: &P,
+    cutoff: EitherCutoff<TimeCutoff, NodeCutoff>,
+    warm_start: Option<WarmStartBudget>,
+)
+-> Outcome
+where
+    P: Problem + Send + Sync + 'a,
+    P::State: Eq + PartialEq + Hash + Clone + Debug + Send + Sync,
+    R: Relaxation<State = P::State> + Send + Sync + 'a,
+    O: StateRanking<State = P::State> + Send + Sync + 'a,
+    W: WidthHeuristic<P::State> + Send + Sync + 'a,
+    C: FrontierOrder<P::State> + Send + Sync,
+{
+    if let Some(budget) = warm_start {
+        solver.warm_start(budget);
+    }
+    let status = match model.objective() {
+        Objective::Maximize => solver.maximize_with_cutoff(cutoff),
+        Objective::Minimize => solver.minimize_with_cutoff(cutoff),
+    };
+    let cutset = (solver.get_cutset_generated(), solver.get_cutset_enqueued());
+    (status, solver.best_value(), solver.best_lower_bound(), solver.best_upper_bound(), solver.get_explored(), solver.get_explored_dd(), cutset, solver.get_skipped_by_barrier(), solver.best_solution(), solver.get_compilation_statistics(), solver.verification_error(), solver.barrier_layer_stats(), describe(&solver.violations()))
+}
+
+/// The largest number of nodes of an exact dd which the auto solver compiles
+/// rather than running the branch-and-bound
+pub const AUTO_EXACT_BUDGET: usize = 1_000_000;
+/// The number of random dives from which the auto solver estimates the size
+/// of the exact dd
+const AUTO_SAMPLES: usize = 1000;
+
+/// Returns the solver which the auto solver runs on the given problem: the
+/// exact one when the (pessimistic) estimate of its exact dd fits within the
+/// `AUTO_EXACT_BUDGET`, and the barrier one otherwise
+fn auto_solver<P>(model: &P) -> SolverType
+where
+    P: Problem,
+    P::State: Hash,
+{
+    if estimate_dd_size(model, AUTO_SAMPLES, 0).fits(AUTO_EXACT_BUDGET) {
+        SolverType::Exact
+    } else {
+        SolverType::Barrier
+    }
+}
+
+/// Turns the outcome of a resolution which started at `start` into its report
+fn report(name: &str, solver_type: SolverType, cutset_type: CutsetType, start: Instant, outcome: Outcome, optimal_solutions: Option<usize>, peak_attribution: Option<MemoryAttribution>) -> SolveReport {
+    let (status, best_value, lb, ub, explored, explored_dd, cutset, skipped_by_barrier, best_solution, compilations, verification_error, barrier_layers, violations) = outcome;
+    SolveReport {
+        name: name.to_owned(),
+        solver: solver_type,
+        cutset: cutset_type,
+        status,
+        duration: start.elapsed(),
+        peak_memory: PEAK_ALLOC.peak_usage_as_mb(),
+        best_value,
+        lb,
+        ub,
+        // the infeasibility of the problem is proved: nothing is left open
+        gap: if status == ResolutionStatus::Infeasible { 0.0 } else { optimality_gap(lb, ub) as f32 },
+        explored,
+        explored_dd,
+        cutset_generated: cutset.0,
+        cutset_enqueued: cutset.1,
+        skipped_by_barrier,
+        best_solution: best_solution.map(|mut sol| {
+            sol.sort_unstable_by_key(|d| d.var.id());
+            sol
+        }),
+        compilations,
+        peak_attribution,
+        verification_error,
+        barrier_layers,
+        optimal_solutions,
+        violations,
+    }
+}
+
+/// This trait is implemented by the writers of the solutions of each problem,
+/// in the format expected by the tools which use them.
+pub trait SolutionFormatter {
+    /// Writes the solution made of the given decisions (sorted by variable)
+    fn write_solution(&self, solution: &[Decision], out: &mut dyn Write) -> io::Result<()>;
+
+    /// Returns the solution as written by `write_solution`
+    fn format_solution(&self, solution: &[Decision]) -> String {
+        let mut out = vec![];
+        self.write_solution(solution, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+}
+
+/// Writes the best solution of the report (if any) in the given file. Nothing
+/// is written when no solution was found.
+pub fn write_solution_file<S: SolutionFormatter>(formatter: &S, report: &SolveReport, path: &str) -> io::Result<()> {
+    match report.best_solution.as_ref() {
+        Some(solution) => {
+            let mut out = BufWriter::new(File::create(path)?);
+            formatter.write_solution(solution, &mut out)?;
+            out.flush()
+        }
+        None => {
+            eprintln!("no solution was found: {} was not written", path);
+            Ok(())
         }
     }
 }
 
-fn gap(lb: isize, ub: isize) -> f32 {
-    let aub = ub.abs();
-    let alb = lb.abs();
-    let u = aub.max(alb);
-    let l = aub.min(alb);
+/// Writes the status of the resolution and the best solution of the report
+/// (if any): a header line `status <status> objective <value>`, followed by
+/// one `var value` line per decision (sorted by variable). When no solution
+/// was found, only `status <status>` is written.
+pub fn write_decisions<W: Write>(report: &SolveReport, out: &mut W) -> io::Result<()> {
+    match (report.best_value, report.best_solution.as_ref()) {
+        (Some(value), Some(solution)) => {
+            writeln!(out, "status {} objective {}", report.status, value)?;
+            for decision in solution {
+                writeln!(out, "{} {}", decision.var.id(), decision.value)?;
+            }
+            Ok(())
+        }
+        _ => writeln!(out, "status {}", report.status),
+    }
+}
 
-    (u - l) as f32 / u as f32
+/// Writes the decisions of the report in the given file (see `write_decisions`).
+/// The file is written even when no solution was found.
+pub fn write_decisions_file(report: &SolveReport, path: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_decisions(report, &mut out)?;
+    out.flush()
 }
 
 pub fn resolution_header() {
     println!(
-        "{:>30} | {:>10} | {:>15} | {:>8} | {:>8} | {:>15} | {:>15} | {:>15} | {:>5.4} | {:>15} | {:>15}",
-        "NAME", "SOLVER", "STATUS", "DURATION", "RAM_(MB)", "BEST-VAL", "LB", "UB", "GAP", "NODES B&B", "NODES DD"
+        "{:>30} | {:>10} | {:>15} | {:>8} | {:>8} | {:>15} | {:>15} | {:>15} | {:>5.4} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15}",
+        "NAME", "SOLVER", "STATUS", "DURATION", "RAM_(MB)", "BEST-VAL", "LB", "UB", "GAP", "NODES B&B", "NODES DD",
+        "CUTSET GEN", "CUTSET ENQ", "PRUNED BARRIER", "PRUNED RUB", "SKIPPED BARRIER"
+    );
+}
+
+/// Formats the statistics of each layer of a barrier solver (see
+/// `BarrierParallelSolver::layer_statistics`) as a table with one row per
+/// depth. The depths at which no node was ever seen are left out.
+pub fn layer_stats_table(stats: &[LayerStats]) -> String {
+    let mut table = format!(
+        "{:>8} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15}\n",
+        "DEPTH", "OPEN", "ONGOING", "COMPLETED", "BY BOUND", "BY BARRIER"
     );
+    for (depth, layer) in stats.iter().enumerate().filter(|(_, layer)| **layer != LayerStats::default()) {
+        table += &format!(
+            "{:>8} | {:>15} | {:>15} | {:>15} | {:>15} | {:>15}\n",
+            depth, layer.open, layer.ongoing, layer.completed, layer.discarded_by_bound, layer.discarded_by_barrier
+        );
+    }
+    table
+}
+
+/// Prints the statistics of each layer of a barrier solver, typically once
+/// its resolution is over (see `layer_stats_table`)
+pub fn print_layer_stats(stats: &[LayerStats]) {
+    print!("{}", layer_stats_table(stats));
 }
 
 pub fn timeout<A, E>(duration: Duration, mut action: A, at_exit: E)
@@ -207,3 +864,117 @@ where
     let _lock = switch.lock().unwrap();
     exit(0);
 }
+
+#[cfg(test)]
+mod test_xputils {
+    use std::{fs, time::{Duration, Instant}};
+
+    use crate::test_utils::{Knapsack, KnapsackRanking, KnapsackRelax, Tweaked};
+    use crate::{CompilationStatistics, CutsetType, Decision, Fixed, FringePolicy, LayerStats, MergePolicy, ResolutionStatus, Variable};
+
+    use super::{layer_stats_table, report, solve, solve_custom, write_decisions_file, OrderType, SolveReport, SolverType};
+
+    /// Parses a file written by `write_decisions_file`
+    fn read_decisions(path: &str) -> (String, Option<isize>, Vec<Decision>) {
+        let text = fs::read_to_string(path).unwrap();
+        let mut lines = text.lines();
+        let header = lines.next().unwrap().split_whitespace().collect::<Vec<_>>();
+        let objective = header.get(3).map(|value| value.parse().unwrap());
+        let decisions = lines
+            .map(|line| {
+                let (var, value) = line.split_once(' ').unwrap();
+                Decision { var: Variable(var.parse().unwrap()), value: value.parse().unwrap() }
+            })
+            .collect();
+        (header[1].to_string(), objective, decisions)
+    }
+
+    #[test]
+    fn the_decisions_file_holds_the_best_solution() {
+        let problem = Knapsack::toy();
+        let path = std::env::temp_dir().join(format!("decisions-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let solve_toy = |solver| {
+            solve("toy", Duration::from_secs(10), &Fixed(2), &problem, &KnapsackRelax, &KnapsackRanking, Some(1), solver, CutsetType::LastExactLayer, false)
+        };
+        for solver in [SolverType::Parallel, SolverType::Barrier] {
+            let report = solve_toy(solver);
+            write_decisions_file(&report, path).unwrap();
+            let (status, objective, decisions) = read_decisions(path);
+            assert_eq!(("Proved", Some(13)), (status.as_str(), objective));
+            assert_eq!(report.best_solution, Some(decisions));
+        }
+
+        // without a solution, the file only tells the status
+        let report = SolveReport { best_value: None, best_solution: None, status: ResolutionStatus::Interrupted, ..solve_toy(SolverType::Barrier) };
+        write_decisions_file(&report, path).unwrap();
+        assert_eq!("status Timeout\n", fs::read_to_string(path).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn the_report_gives_the_violations_of_the_self_check() {
+        let problem = Tweaked::handling(Knapsack::toy(), false);
+        for solver in [SolverType::Parallel, SolverType::Barrier] {
+            let solve_toy = |self_check| {
+                solve_custom("toy", Duration::from_secs(10), &Fixed(2), &problem, &KnapsackRelax, &KnapsackRanking, Some(2), solver, CutsetType::LastExactLayer,
+                    false, self_check, None, None, OrderType::default(), FringePolicy::default(), None, None, MergePolicy::SingleNode, None)
+            };
+            let report = solve_toy(true);
+            assert!(report.violations.iter().any(|violation| violation.contains("`Objective::Minimize`")));
+            assert!(solve_toy(false).violations.is_empty());
+        }
+    }
+
+    #[test]
+    fn an_infeasible_problem_is_reported_without_bounds() {
+        let outcome = (ResolutionStatus::Infeasible, None, isize::MIN, isize::MIN, 0, 0, (0, 0), 0, None, CompilationStatistics::default(), None, vec![], vec![]);
+        let report = report("toy", SolverType::Barrier, CutsetType::LastExactLayer, Instant::now(), outcome, None, None);
+        assert_eq!(0.0, report.gap);
+        let line = report.to_string();
+        assert!(line.contains("Infeasible") && line.contains("infeasible"));
+        assert!(!line.contains(&isize::MIN.to_string()));
+        assert!(report.to_csv().contains(",Infeasible,") && report.to_csv().contains(",,-,-,0.0000,"));
+    }
+
+    #[test]
+    fn the_root_relaxation_reports_a_bound_unless_it_is_exact() {
+        let problem = Knapsack::toy();
+        let solve_toy = |width| {
+            solve("toy", Duration::from_secs(10), &Fixed(width), &problem, &KnapsackRelax, &KnapsackRanking, None, SolverType::RootRelaxation, CutsetType::LastExactLayer, false)
+        };
+        let report = solve_toy(1);
+        assert_eq!(("Bound", None, 0), (report.status.to_string().as_str(), report.best_value, report.explored));
+        assert!(report.ub >= 13 && report.explored_dd > 0);
+        assert_eq!(1.0, report.gap);
+
+        let report = solve_toy(1000);
+        assert_eq!((ResolutionStatus::Proved, Some(13), 13, 13), (report.status, report.best_value, report.lb, report.ub));
+        assert_eq!("root-relaxation", report.solver.to_string());
+        assert_eq!(Ok(SolverType::RootRelaxation), "root-relaxation".parse());
+    }
+
+    #[test]
+    fn the_auto_solver_compiles_the_exact_dd_of_a_tiny_problem() {
+        let problem = Knapsack::toy();
+        let report = solve("toy", Duration::from_secs(10), &Fixed(2), &problem, &KnapsackRelax, &KnapsackRanking, Some(1), SolverType::Auto, CutsetType::LastExactLayer, false);
+        assert_eq!((SolverType::Exact, ResolutionStatus::Proved, Some(13)), (report.solver, report.status, report.best_value));
+        assert!(report.optimal_solutions.is_some());
+        assert_eq!(Ok(SolverType::Auto), "auto".parse());
+    }
+
+    #[test]
+    fn the_layer_stats_table_has_a_row_per_visited_depth() {
+        let stats = [
+            LayerStats { completed: 1, ..LayerStats::default() },
+            LayerStats::default(),
+            LayerStats { open: 4, ongoing: 1, completed: 3, discarded_by_bound: 2, discarded_by_barrier: 5 },
+        ];
+        let table = layer_stats_table(&stats);
+        let rows = table.lines().map(|line| line.split('|').map(str::trim).collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(vec!["DEPTH", "OPEN", "ONGOING", "COMPLETED", "BY BOUND", "BY BARRIER"], rows[0]);
+        assert_eq!(vec!["0", "0", "0", "1", "0", "0"], rows[1]);
+        assert_eq!(vec!["2", "4", "1", "3", "2", "5"], rows[2]);
+        assert_eq!(3, rows.len());
+    }
+}
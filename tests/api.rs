@@ -0,0 +1,105 @@
+//! Pins the stable api of the crate: this test only uses what `api` exports,
+//! and it must keep compiling whatever the internal refactorings.
+
+use std::cmp::Ordering;
+
+use engineering::api::*;
+
+/// A knapsack whose state is the remaining capacity
+struct Knapsack {
+    capacity: usize,
+    profit: Vec<isize>,
+    weight: Vec<usize>,
+}
+
+impl Problem for Knapsack {
+    type State = usize;
+
+    fn nb_variables(&self) -> usize {
+        self.profit.len()
+    }
+    fn initial_state(&self) -> usize {
+        self.capacity
+    }
+    fn initial_value(&self) -> isize {
+        0
+    }
+    fn next_variable(&self, _next_layer: &mut dyn Iterator<Item = &usize>) -> Option<Variable> {
+        None
+    }
+    fn next_variable_with_context(&self, assigned: &AssignedVars, _next_layer: &mut dyn Iterator<Item = &usize>) -> Option<Variable> {
+        (0..self.nb_variables()).map(Variable).find(|var| !assigned.contains(*var))
+    }
+    fn for_each_in_domain<F>(&self, var: Variable, state: &usize, mut f: F)
+    where
+        F: FnMut(Decision),
+    {
+        if *state >= self.weight[var.id()] {
+            f(Decision { var, value: 1 });
+        }
+        f(Decision { var, value: 0 });
+    }
+    fn transition(&self, state: &usize, decision: Decision) -> usize {
+        state - decision.value as usize * self.weight[decision.var.id()]
+    }
+    fn transition_cost(&self, _state: &usize, decision: Decision) -> isize {
+        decision.value * self.profit[decision.var.id()]
+    }
+}
+
+struct KnapsackRelax;
+impl Relaxation for KnapsackRelax {
+    type State = usize;
+
+    fn merge(&self, states: &mut dyn Iterator<Item = &usize>) -> usize {
+        states.copied().max().unwrap_or(0)
+    }
+    fn relax(&self, _source: &usize, _dest: &usize, _merged: &usize, _decision: Decision, cost: isize) -> isize {
+        cost
+    }
+}
+
+struct KnapsackRanking;
+impl StateRanking for KnapsackRanking {
+    type State = usize;
+
+    fn compare(&self, a: &usize, b: &usize) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+fn knapsack() -> Knapsack {
+    Knapsack { capacity: 15, profit: vec![6, 5, 4, 3, 2], weight: vec![10, 7, 5, 3, 2] }
+}
+
+#[test]
+fn the_facade_solves_a_problem_with_both_solvers() {
+    let problem = knapsack();
+    let width = MinWidth(Fixed(2), Fixed(3));
+    for cutset in [CutsetType::LastExactLayer, CutsetType::Frontier] {
+        let mut fringe = NoDupFrontier::new(&KnapsackRanking);
+        let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &width, cutset, &mut fringe, 2);
+        assert_eq!(ResolutionStatus::Proved, solver.maximize_with_cutoff(NodeCutoff(usize::MAX)));
+        assert_eq!(Some(12), solver.best_value());
+
+        let mut solver = BarrierParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &width, cutset, 2);
+        solver.maximize();
+        assert_eq!(Some(12), solver.best_value());
+        let solution = solver.best_solution().unwrap();
+        assert_eq!(Ok(12), problem.validate_solution(&solution).map_err(|_| ()));
+    }
+}
+
+#[test]
+fn the_facade_exposes_the_frontiers() {
+    let problem = knapsack();
+    let mut bounded = BoundedFrontier::new(&KnapsackRanking, 100);
+    let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut bounded, 1);
+    solver.maximize();
+    assert_eq!(Some(12), solver.best_value());
+
+    let mut stratified = StratifiedFrontier::new(&KnapsackRanking, FringePolicy::DeepestLayer);
+    let mut solver = ParallelSolver::custom(&problem, &KnapsackRelax, &KnapsackRanking, &Fixed(1), CutsetType::LastExactLayer, &mut stratified, 1);
+    solver.maximize();
+    assert_eq!(Some(12), solver.best_value());
+}